@@ -0,0 +1,141 @@
+use std::any::Any;
+
+use web_audio_api::context::{
+    AudioContext, AudioContextLatencyCategory, AudioContextOptions, AudioContextRegistration,
+    BaseAudioContext,
+};
+use web_audio_api::node::{AudioNode, AudioScheduledSourceNode, ChannelConfig};
+use web_audio_api::render::{
+    Complex, SpectralFrame, SpectralOptions, SpectralProcessor, StftProcessor,
+};
+
+// Showcase how to create your own frequency-domain audio node
+//
+// `cargo run --release --example spectral_worklet`
+//
+// If you are on Linux and use ALSA as audio backend backend, you might want to run
+// the example with the `WEB_AUDIO_LATENCY=playback ` env variable which will
+// increase the buffer size to 1024
+//
+// `WEB_AUDIO_LATENCY=playback cargo run --release --example spectral_worklet`
+
+/// Audio node turning its input into a robotic voice
+struct RobotizeNode {
+    /// handle to the audio context, required for all audio nodes
+    registration: AudioContextRegistration,
+    /// channel configuration (for up/down-mixing of inputs), required for all audio nodes
+    channel_config: ChannelConfig,
+}
+
+// implement required methods for AudioNode trait
+impl AudioNode for RobotizeNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl RobotizeNode {
+    /// Construct a new RobotizeNode
+    fn new<C: BaseAudioContext>(context: &C) -> Self {
+        context.register(move |registration| {
+            // the hop size sets the pitch of the robot, one frame per hop
+            let options = SpectralOptions {
+                fft_size: 1024,
+                hop_size: 256,
+                ..SpectralOptions::default()
+            };
+
+            // the engine computes the frames and overlap-adds the processed output
+            let render = StftProcessor::new(RobotizeProcessor { enabled: true }, options);
+
+            let node = RobotizeNode {
+                registration,
+                channel_config: ChannelConfig::default(),
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    fn set_enabled(&self, enabled: bool) {
+        self.registration.post_message(enabled);
+    }
+}
+
+struct RobotizeProcessor {
+    enabled: bool,
+}
+
+impl SpectralProcessor for RobotizeProcessor {
+    fn process_frame(&mut self, _inputs: &[SpectralFrame], output: &mut SpectralFrame) {
+        // the output is initialized with the spectrum of the input
+        if !self.enabled {
+            return;
+        }
+
+        // keep the magnitudes and drop the phases of all bins
+        output.channels_mut().for_each(|channel| {
+            channel
+                .iter_mut()
+                .for_each(|bin| *bin = Complex::new(bin.norm(), 0.))
+        });
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if let Some(enabled) = msg.downcast_ref::<bool>() {
+            self.enabled = *enabled;
+            return;
+        }
+
+        log::warn!("RobotizeProcessor: Dropping incoming message {msg:?}");
+    }
+}
+
+fn main() {
+    env_logger::init();
+
+    let latency_hint = match std::env::var("WEB_AUDIO_LATENCY").as_deref() {
+        Ok("playback") => AudioContextLatencyCategory::Playback,
+        _ => AudioContextLatencyCategory::default(),
+    };
+
+    let context = AudioContext::new(AudioContextOptions {
+        latency_hint,
+        ..AudioContextOptions::default()
+    });
+
+    // a sawtooth gliding up and down, rich in harmonics
+    let mut osc = context.create_oscillator();
+    osc.set_type(web_audio_api::node::OscillatorType::Sawtooth);
+    osc.frequency().set_value_at_time(110., 0.);
+    osc.frequency().linear_ramp_to_value_at_time(440., 4.);
+    osc.frequency().linear_ramp_to_value_at_time(110., 8.);
+
+    let gain = context.create_gain();
+    gain.gain().set_value(0.2);
+
+    let robotize = RobotizeNode::new(&context);
+    osc.connect(&gain);
+    gain.connect(&robotize);
+    robotize.connect(&context.destination());
+    osc.start();
+
+    println!("Robot voice");
+    std::thread::sleep(std::time::Duration::from_secs(4));
+
+    println!("Original voice");
+    robotize.set_enabled(false);
+    std::thread::sleep(std::time::Duration::from_secs(4));
+}
@@ -0,0 +1,142 @@
+//! Detection of memory allocations in the render thread
+//!
+//! Allocating or freeing memory in [`AudioProcessor::process`](crate::render::AudioProcessor)
+//! can block the render thread for an unbounded time, which results in audio glitches. This
+//! module provides a global allocator wrapper that detects such allocations. It is meant for
+//! debug builds and tests of nodes and worklets, and requires the `alloc-guard` feature.
+//!
+//! The detection only works when [`AllocGuard`] is installed as the global allocator of the
+//! binary. Each render quantum in which a node allocates is counted in [`allocation_count`], and
+//! the first one of each node is logged as a warning. With [`set_panic_on_allocation`] the render
+//! thread panics instead, e.g. to fail a test rendering an `OfflineAudioContext`.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use std::alloc::System;
+//! use web_audio_api::alloc_guard::{self, AllocGuard};
+//!
+//! #[global_allocator]
+//! static ALLOCATOR: AllocGuard<System> = AllocGuard::new(System);
+//!
+//! alloc_guard::set_panic_on_allocation(true);
+//! ```
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use crate::context::AudioNodeId;
+
+thread_local! {
+    /// Number of allocations since the start of the current `process` call, `None` outside of
+    /// `process`
+    static ALLOCATIONS: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+static PANIC_ON_ALLOCATION: AtomicBool = AtomicBool::new(false);
+static ALLOCATION_COUNT: AtomicU64 = AtomicU64::new(0);
+
+/// Global allocator wrapper that detects the allocations of the audio processors
+///
+/// The allocations are forwarded to the wrapped allocator, see the [module docs](self).
+pub struct AllocGuard<A = System> {
+    inner: A,
+}
+
+impl<A> AllocGuard<A> {
+    pub const fn new(inner: A) -> Self {
+        Self { inner }
+    }
+}
+
+impl<A: GlobalAlloc> AllocGuard<A> {
+    fn record(&self) {
+        // `try_with` fails when the thread is being torn down, nothing to report then
+        let _ = ALLOCATIONS.try_with(|allocations| {
+            if let Some(count) = allocations.get() {
+                allocations.set(Some(count + 1));
+            }
+        });
+    }
+}
+
+// SAFETY: all calls are forwarded to the inner allocator
+unsafe impl<A: GlobalAlloc> GlobalAlloc for AllocGuard<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.record();
+        self.inner.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        self.record();
+        self.inner.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        self.record();
+        self.inner.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.record();
+        self.inner.dealloc(ptr, layout)
+    }
+}
+
+/// Panic in the render thread when a node allocates, instead of logging a warning
+///
+/// Defaults to `false`.
+pub fn set_panic_on_allocation(enabled: bool) {
+    PANIC_ON_ALLOCATION.store(enabled, Ordering::Relaxed);
+}
+
+/// Number of render quanta in which a node allocated, of all contexts
+#[must_use]
+pub fn allocation_count() -> u64 {
+    ALLOCATION_COUNT.load(Ordering::Relaxed)
+}
+
+/// Detection of the allocations during a single `process` call
+pub(crate) struct Guard(());
+
+impl Guard {
+    pub fn start() -> Self {
+        ALLOCATIONS.with(|allocations| allocations.set(Some(0)));
+        Self(())
+    }
+
+    /// Stop the detection and report the allocations of the node, returns whether it allocated
+    ///
+    /// # Panics
+    ///
+    /// Panics when the node allocated and [`set_panic_on_allocation`] is enabled
+    pub fn finish(self, id: AudioNodeId, reported: bool) -> bool {
+        let allocations = ALLOCATIONS.with(Cell::get).unwrap_or(0);
+        drop(self);
+        if allocations == 0 {
+            return false;
+        }
+
+        ALLOCATION_COUNT.fetch_add(1, Ordering::Relaxed);
+        if PANIC_ON_ALLOCATION.load(Ordering::Relaxed) {
+            panic!(
+                "node {} performed {allocations} allocation(s) in the render thread",
+                id.0
+            );
+        } else if !reported {
+            log::warn!(
+                "node {} performed {allocations} allocation(s) in the render thread",
+                id.0
+            );
+        }
+
+        true
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        ALLOCATIONS.with(|allocations| allocations.set(None));
+    }
+}
@@ -4,14 +4,15 @@
 
 use std::f32::consts::PI;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
-use realfft::{num_complex::Complex, RealFftPlanner};
+use realfft::num_complex::Complex;
 
+use crate::dsp::plan_fft_forward;
 use crate::{AtomicF32, RENDER_QUANTUM_SIZE};
 
 /// Blackman window values iterator with alpha = 0.16
-fn generate_blackman(size: usize) -> impl Iterator<Item = f32> {
+pub(crate) fn generate_blackman(size: usize) -> impl Iterator<Item = f32> {
     let alpha = 0.16;
     let a0 = (1. - alpha) / 2.;
     let a1 = 1. / 2.;
@@ -158,7 +159,6 @@ pub(crate) struct Analyser {
     smoothing_time_constant: f64,
     min_decibels: f64,
     max_decibels: f64,
-    fft_planner: Mutex<RealFftPlanner<f32>>, // RealFftPlanner is not `Sync` on all platforms
     fft_input: Vec<f32>,
     fft_scratch: Vec<Complex<f32>>,
     fft_output: Vec<Complex<f32>>,
@@ -171,8 +171,7 @@ impl Analyser {
     pub fn new() -> Self {
         let ring_buffer = AnalyserRingBuffer::new();
         // FFT utils
-        let mut fft_planner = RealFftPlanner::<f32>::new();
-        let max_fft = fft_planner.plan_fft_forward(MAX_FFT_SIZE);
+        let max_fft = plan_fft_forward(MAX_FFT_SIZE);
 
         let fft_input = max_fft.make_input_vec();
         let fft_scratch = max_fft.make_scratch_vec();
@@ -190,7 +189,6 @@ impl Analyser {
             smoothing_time_constant: DEFAULT_SMOOTHING_TIME_CONSTANT,
             min_decibels: DEFAULT_MIN_DECIBELS,
             max_decibels: DEFAULT_MAX_DECIBELS,
-            fft_planner: Mutex::new(fft_planner),
             fft_input,
             fft_scratch,
             fft_output,
@@ -281,7 +279,7 @@ impl Analyser {
         let fft_size = self.fft_size();
         let smoothing_time_constant = self.smoothing_time_constant() as f32;
         // setup FFT planner and properly sized buffers
-        let r2c = self.fft_planner.lock().unwrap().plan_fft_forward(fft_size);
+        let r2c = plan_fft_forward(fft_size);
         let input = &mut self.fft_input[..fft_size];
         let output = &mut self.fft_output[..fft_size / 2 + 1];
         let scratch = &mut self.fft_scratch[..r2c.get_scratch_len()];
@@ -0,0 +1,371 @@
+//! Automation lanes recorded from the live changes of params
+//!
+//! While a recording is running, see [`BaseAudioContext::start_automation_recording`], every
+//! value set on an armed param with [`AudioParam::set_value`] (or through an RTPC) is captured
+//! with the current time of the context into the lane of that param. The recorded
+//! [`AutomationTimeline`] can be replayed on params, possibly of another context, and exported
+//! to a text format that can be loaded back:
+//!
+//! ```text
+//! # times in seconds since the start of the recording
+//! lane cutoff
+//! 0 800
+//! 0.5 1200
+//! lane volume
+//! 0 1
+//! ```
+//!
+//! [`BaseAudioContext::start_automation_recording`]: crate::context::BaseAudioContext::start_automation_recording
+
+use std::error::Error;
+use std::fmt;
+
+use crate::param::{AudioParam, WeakAudioParam};
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Lane names are single words, so they can be exported
+fn is_valid_lane_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(|c: char| c.is_whitespace() || c == '#')
+}
+
+/// Named sequence of `(time, value)` points of a param, with non-decreasing times
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AutomationLane {
+    name: String,
+    points: Vec<(f64, f32)>,
+}
+
+impl AutomationLane {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Recorded `(time, value)` points, in seconds since the start of the recording
+    pub fn points(&self) -> &[(f64, f32)] {
+        &self.points
+    }
+
+    /// Value held by the param at the given time, `None` before the first point
+    pub fn value_at(&self, time: f64) -> Option<f32> {
+        let index = self.points.partition_point(|(t, _)| *t <= time);
+        index.checked_sub(1).map(|index| self.points[index].1)
+    }
+}
+
+/// Automation lanes of several params, recorded together
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::automation::AutomationTimeline;
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+///
+/// let context = AudioContext::default();
+/// let filter = context.create_biquad_filter();
+///
+/// // write automation while the user turns a knob
+/// context.start_automation_recording(&[("cutoff", filter.frequency())]);
+/// filter.frequency().set_value(1200.);
+/// let timeline = context.stop_automation_recording();
+///
+/// // read it back later, or save it as text
+/// timeline.replay(&[("cutoff", filter.frequency())], context.current_time());
+/// let text = timeline.to_string();
+/// assert_eq!(AutomationTimeline::parse(&text).unwrap(), timeline);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AutomationTimeline {
+    lanes: Vec<AutomationLane>,
+}
+
+impl AutomationTimeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a timeline exported with its `Display` implementation
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text is not a valid timeline, e.g. a point outside of a lane or
+    /// with a decreasing time
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut timeline = Self::default();
+
+        for (index, line) in source.lines().enumerate() {
+            let line = line.split_once('#').map_or(line, |(line, _)| line).trim();
+            let error = |message: String| format!("SyntaxError - line {}: {}", index + 1, message);
+
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix("lane ") {
+                let name = name.trim();
+                if !is_valid_lane_name(name) {
+                    return Err(error(format!("invalid lane name {name:?}")).into());
+                }
+                if timeline.lane(name).is_some() {
+                    return Err(error(format!("lane {name:?} is declared twice")).into());
+                }
+                timeline.lanes.push(AutomationLane {
+                    name: name.to_owned(),
+                    points: vec![],
+                });
+                continue;
+            }
+
+            let Some(lane) = timeline.lanes.last_mut() else {
+                return Err(error(format!("expected a lane, got {line:?}")).into());
+            };
+            let point = line
+                .split_once(char::is_whitespace)
+                .and_then(|(time, value)| Some((time.parse().ok()?, value.trim().parse().ok()?)))
+                .filter(|(time, value): &(f64, f32)| {
+                    *time >= 0. && time.is_finite() && value.is_finite()
+                })
+                .ok_or_else(|| error(format!("expected a time and a value, got {line:?}")))?;
+            if lane.points.last().is_some_and(|(time, _)| *time > point.0) {
+                return Err(error(format!("time {} is before the previous point", point.0)).into());
+            }
+            lane.points.push(point);
+        }
+
+        Ok(timeline)
+    }
+
+    pub fn lane(&self, name: &str) -> Option<&AutomationLane> {
+        self.lanes.iter().find(|lane| lane.name == name)
+    }
+
+    pub fn lanes(&self) -> impl Iterator<Item = &AutomationLane> {
+        self.lanes.iter()
+    }
+
+    /// Time of the last recorded point, in seconds
+    pub fn duration(&self) -> f64 {
+        self.lanes
+            .iter()
+            .filter_map(|lane| lane.points.last())
+            .map(|(time, _)| *time)
+            .fold(0., f64::max)
+    }
+
+    /// Schedule the recorded values of the named lanes on the given params, starting at `when`
+    ///
+    /// # Panics
+    ///
+    /// Panics if a lane does not exist, or if `when` is negative
+    pub fn replay(&self, params: &[(&str, &AudioParam)], when: f64) {
+        params.iter().for_each(|(name, param)| {
+            let lane = self
+                .lane(name)
+                .unwrap_or_else(|| panic!("InvalidStateError - unknown automation lane {name}"));
+            lane.points.iter().for_each(|(time, value)| {
+                param.set_value_at_time(*value, when + time);
+            });
+        });
+    }
+}
+
+impl fmt::Display for AutomationTimeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for lane in &self.lanes {
+            writeln!(f, "lane {}", lane.name)?;
+            for (time, value) in &lane.points {
+                writeln!(f, "{time} {value}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct ArmedParam {
+    param: WeakAudioParam,
+    lane: usize,
+}
+
+/// Running automation recording of a context
+#[derive(Default)]
+pub(crate) struct AutomationRecorder {
+    /// Time of the context when the recording started, `None` when not recording
+    start: Option<f64>,
+    armed: Vec<ArmedParam>,
+    timeline: AutomationTimeline,
+}
+
+impl AutomationRecorder {
+    /// Start recording the given params, each lane starts with the current value of its param
+    pub fn start(&mut self, params: &[(&str, &AudioParam)], now: f64) {
+        assert!(
+            self.start.is_none(),
+            "InvalidStateError - automation is already being recorded"
+        );
+        params.iter().enumerate().for_each(|(index, (name, _))| {
+            assert!(
+                is_valid_lane_name(name),
+                "SyntaxError - invalid automation lane name {name:?}"
+            );
+            assert!(
+                params[..index].iter().all(|(other, _)| other != name),
+                "InvalidStateError - automation lane {name} is declared twice"
+            );
+        });
+
+        self.start = Some(now);
+        self.armed = params
+            .iter()
+            .enumerate()
+            .map(|(lane, (_, param))| ArmedParam {
+                param: WeakAudioParam::new(param),
+                lane,
+            })
+            .collect();
+        self.timeline.lanes = params
+            .iter()
+            .map(|(name, param)| AutomationLane {
+                name: (*name).to_owned(),
+                points: vec![(0., param.value())],
+            })
+            .collect();
+    }
+
+    /// Capture a value set on a param, if it is armed
+    pub fn record(&mut self, param: &AudioParam, value: f32, now: f64) {
+        let Some(start) = self.start else {
+            return;
+        };
+        if let Some(armed) = self.armed.iter().find(|armed| armed.param.is(param)) {
+            let time = (now - start).max(0.);
+            self.timeline.lanes[armed.lane].points.push((time, value));
+        }
+    }
+
+    /// Stop the recording and return the recorded lanes
+    pub fn stop(&mut self) -> AutomationTimeline {
+        assert!(
+            self.start.take().is_some(),
+            "InvalidStateError - automation is not being recorded"
+        );
+        self.armed.clear();
+        std::mem::take(&mut self.timeline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::{AudioNode, AudioScheduledSourceNode};
+    use crate::rtpc::RtpcCurve;
+    use crate::RENDER_QUANTUM_SIZE;
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_record_automation() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44100.);
+        let gain = context.create_gain();
+        let filter = context.create_biquad_filter();
+        let other = context.create_gain();
+
+        context.bind_rtpc("health", filter.frequency(), RtpcCurve::identity());
+        context
+            .start_automation_recording(&[("volume", gain.gain()), ("cutoff", filter.frequency())]);
+        gain.gain().set_value(0.5);
+        other.gain().set_value(0.25);
+        context.set_rtpc("health", 1000.);
+        let timeline = context.stop_automation_recording();
+
+        // the values set after the recording are not captured
+        gain.gain().set_value(3.);
+
+        let volume = timeline.lane("volume").unwrap();
+        assert_eq!(volume.points(), &[(0., 1.), (0., 0.5)]);
+        let cutoff = timeline.lane("cutoff").unwrap();
+        assert_eq!(cutoff.points(), &[(0., 350.), (0., 1000.)]);
+        assert_eq!(timeline.lanes().count(), 2);
+    }
+
+    #[test]
+    fn test_recorded_times() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44100.);
+        let gain = context.create_gain();
+
+        // the times are relative to the start of the recording
+        let mut recorder = AutomationRecorder::default();
+        recorder.start(&[("volume", gain.gain())], 2.);
+        recorder.record(gain.gain(), 0.5, 2.5);
+        recorder.record(gain.gain(), 0.25, 3.);
+        let timeline = recorder.stop();
+
+        let volume = timeline.lane("volume").unwrap();
+        assert_eq!(volume.points(), &[(0., 1.), (0.5, 0.5), (1., 0.25)]);
+        assert_float_eq!(timeline.duration(), 1., abs <= 0.);
+        assert_eq!(volume.value_at(-1.), None);
+        assert_eq!(volume.value_at(0.75), Some(0.5));
+    }
+
+    #[test]
+    fn test_replay_automation() {
+        let sample_rate = 44100.;
+        let mut timeline = AutomationTimeline::parse("lane offset\n0 1\n0.01 2\n").unwrap();
+        timeline.lanes[0].points.push((0.02, 3.));
+
+        let context = OfflineAudioContext::new(1, 4096, sample_rate);
+        let mut src = context.create_constant_source();
+        src.connect(&context.destination());
+        src.start();
+        timeline.replay(&[("offset", src.offset())], 0.03);
+
+        let output = context.start_rendering_sync();
+        let output = output.get_channel_data(0);
+        let frame = |time: f64| (time * f64::from(sample_rate)).round() as usize;
+        assert_float_eq!(output[frame(0.04) - 2], 1., abs <= 0.);
+        assert_float_eq!(output[frame(0.04) + 2], 2., abs <= 0.);
+        assert_float_eq!(output[frame(0.05) - 2], 2., abs <= 0.);
+        assert_float_eq!(output[frame(0.05) + 2], 3., abs <= 0.);
+    }
+
+    #[test]
+    fn test_export_automation() {
+        let timeline = AutomationTimeline::parse(
+            "# recorded\nlane cutoff\n0 800\n0.5 1200.5 # knob\n\nlane volume\n0 1\n",
+        )
+        .unwrap();
+        assert_eq!(
+            timeline.lane("cutoff").unwrap().points(),
+            &[(0., 800.), (0.5, 1200.5)]
+        );
+        let text = timeline.to_string();
+        assert_eq!(text, "lane cutoff\n0 800\n0.5 1200.5\nlane volume\n0 1\n");
+        assert_eq!(AutomationTimeline::parse(&text).unwrap(), timeline);
+    }
+
+    #[test]
+    fn test_invalid_automation_text() {
+        assert!(AutomationTimeline::parse("0 1\n").is_err());
+        assert!(AutomationTimeline::parse("lane a\n0\n").is_err());
+        assert!(AutomationTimeline::parse("lane a\n1 1\n0 1\n").is_err());
+        assert!(AutomationTimeline::parse("lane a\n-1 1\n").is_err());
+        assert!(AutomationTimeline::parse("lane a\nlane a\n").is_err());
+        assert!(AutomationTimeline::parse("lane a b\n").is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_stop_without_recording() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44100.);
+        context.stop_automation_recording();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_replay_unknown_lane() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44100.);
+        let gain = context.create_gain();
+        AutomationTimeline::new().replay(&[("volume", gain.gain())], 0.);
+    }
+}
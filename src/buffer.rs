@@ -1,8 +1,14 @@
 //! General purpose audio signal data structures
+use std::ops::{Bound, RangeBounds};
 use std::sync::Arc;
 
+use crate::dsp::{build_peaks, integrated_loudness, PeakPyramid};
+#[cfg(feature = "mmap")]
+use crate::mapped::{MappedChannel, MappedFile};
+use crate::memory::Allocation;
 use crate::{
     assert_valid_channel_number, assert_valid_number_of_channels, assert_valid_sample_rate,
+    db_to_gain,
 };
 
 /// Options for constructing an [`AudioBuffer`]
@@ -21,10 +27,39 @@ pub struct AudioBufferOptions {
     pub sample_rate: f32,
 }
 
+/// Gain curve of the fades applied by [`AudioBuffer::fade_in`] and [`AudioBuffer::fade_out`]
+///
+/// This is an extension to the spec.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum FadeCurve {
+    /// Gain proportional to the time
+    #[default]
+    Linear,
+    /// Quarter sine gain, so an overlapping fade in and fade out keep a constant power
+    EqualPower,
+    /// Gain rising by 60 dB at a constant rate in decibels, offset to start from silence
+    Exponential,
+}
+
+impl FadeCurve {
+    /// Gain of a fade in at the given position in the fade, from 0 to 1
+    fn gain(self, position: f32) -> f32 {
+        match self {
+            Self::Linear => position,
+            Self::EqualPower => (position * std::f32::consts::FRAC_PI_2).sin(),
+            Self::Exponential => (1000_f32.powf(position) - 1.) / 999.,
+        }
+    }
+}
+
 /// Memory-resident audio asset, basically a matrix of channels * samples
 ///
 /// An AudioBuffer has copy-on-write semantics, so it is cheap to clone.
 ///
+/// The methods to memory-map the samples (`map_pcm_file`, `write_pcm_file` and `is_memory_mapped`),
+/// to measure and normalize the loudness, to build the peaks and to edit the buffer (trimming,
+/// fades, slicing and concatenation) are an extension to the spec.
+///
 /// - MDN documentation: <https://developer.mozilla.org/en-US/docs/Web/API/AudioBuffer>
 /// - specification: <https://webaudio.github.io/web-audio-api/#AudioBuffer>
 /// - see also: [`BaseAudioContext::create_buffer`](crate::context::BaseAudioContext::create_buffer)
@@ -77,8 +112,7 @@ impl AudioBuffer {
     ///
     /// This function will panic if:
     /// - the given sample rate is zero
-    /// - the given number of channels is outside the [1, 32] range,
-    /// 32 being defined by the MAX_CHANNELS constant.
+    /// - the given number of channels is outside the [1, MAX_CHANNELS] range.
     pub fn new(options: AudioBufferOptions) -> Self {
         assert_valid_sample_rate(options.sample_rate);
         assert_valid_number_of_channels(options.number_of_channels);
@@ -100,7 +134,7 @@ impl AudioBuffer {
     /// This function will panic if:
     /// - the given sample rate is zero
     /// - the given number of channels defined by `samples.len()`is outside the
-    ///   [1, 32] range, 32 being defined by the MAX_CHANNELS constant.
+    ///   [1, MAX_CHANNELS] range.
     /// - any of its items have different lengths
     pub fn from(samples: Vec<Vec<f32>>, sample_rate: f32) -> Self {
         assert_valid_sample_rate(sample_rate);
@@ -117,6 +151,91 @@ impl AudioBuffer {
         }
     }
 
+    /// Create an AudioBuffer backed by a memory-mapped PCM file, without loading its samples
+    /// in memory
+    ///
+    /// The file holds the raw 32-bit float samples in native byte order, one channel after the
+    /// other, as written by [`write_pcm_file`](Self::write_pcm_file). Only the pages that are
+    /// played are read from disk, so multi-gigabyte sample libraries can be used without resident
+    /// memory. They do not count towards the buffer memory quota of a context.
+    ///
+    /// To avoid page faults in the render thread, a background thread keeps the frames following
+    /// the playhead of the `AudioBufferSourceNode`s playing the buffer resident. Writing to the
+    /// buffer (e.g. with [`copy_to_channel`](Self::copy_to_channel)) copies the channel in
+    /// memory.
+    ///
+    /// - This requires the `mmap` feature
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the file cannot be opened or mapped, or if its size is
+    /// not a multiple of the size of a frame.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while the buffer, or any of its clones, is
+    /// alive. This is undefined behavior, as for any memory-mapped file.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if:
+    /// - the given sample rate is zero
+    /// - the given number of channels is outside the [1, MAX_CHANNELS] range.
+    #[cfg(feature = "mmap")]
+    pub unsafe fn map_pcm_file<P: AsRef<std::path::Path>>(
+        path: P,
+        number_of_channels: usize,
+        sample_rate: f32,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        assert_valid_sample_rate(sample_rate);
+        assert_valid_number_of_channels(number_of_channels);
+
+        let file = MappedFile::open(path.as_ref(), number_of_channels)?;
+        let channels = (0..file.number_of_channels())
+            .map(|index| ChannelData::mapped(MappedChannel::new(Arc::clone(&file), index)))
+            .collect();
+
+        Ok(Self {
+            channels,
+            sample_rate,
+        })
+    }
+
+    /// Write the samples to a PCM file that can be memory-mapped with
+    /// [`map_pcm_file`](Self::map_pcm_file)
+    ///
+    /// The sample rate is not stored in the file.
+    ///
+    /// - This requires the `mmap` feature
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the file cannot be written.
+    #[cfg(feature = "mmap")]
+    pub fn write_pcm_file<P: AsRef<std::path::Path>>(&self, path: P) -> std::io::Result<()> {
+        use std::io::Write;
+
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        for channel in &self.channels {
+            for sample in channel.as_slice() {
+                writer.write_all(&sample.to_ne_bytes())?;
+            }
+        }
+        writer.flush()
+    }
+
+    /// Whether the samples of the first channel are memory-mapped, see
+    /// [`map_pcm_file`](Self::map_pcm_file)
+    ///
+    /// - This requires the `mmap` feature
+    #[cfg(feature = "mmap")]
+    pub fn is_memory_mapped(&self) -> bool {
+        matches!(
+            self.channels.first().map(|c| &c.data),
+            Some(Samples::Mapped(_))
+        )
+    }
+
     /// Number of channels in this `AudioBuffer`
     pub fn number_of_channels(&self) -> usize {
         self.channels.len()
@@ -235,6 +354,260 @@ impl AudioBuffer {
         self.channel_data_mut(channel_number).as_mut_slice()
     }
 
+    /// Integrated loudness of the buffer in LUFS, following ITU-R BS.1770-4 (EBU R 128)
+    ///
+    /// The channels are weighted according to their speaker layout: the LFE channel of 5.1
+    /// buffers is ignored, and the surround channels of quad and 5.1 buffers weigh +1.5 dB.
+    /// Buffers shorter than 400 ms are measured as a single gating block.
+    ///
+    /// Returns negative infinity if the buffer is silent.
+    pub fn measure_loudness(&self) -> f32 {
+        integrated_loudness(self)
+    }
+
+    /// Apply a gain to the buffer so its integrated loudness reaches the given target in LUFS,
+    /// e.g. -14 LUFS for streaming or -23 LUFS for broadcast
+    ///
+    /// Returns the applied linear gain, 1 if the buffer is silent. The gain is not limited, the
+    /// normalized buffer may clip.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the target loudness is not finite
+    pub fn normalize_loudness(&mut self, target: f32) -> f32 {
+        assert!(
+            target.is_finite(),
+            "RangeError - target loudness ({:?}) should be finite",
+            target
+        );
+
+        let loudness = self.measure_loudness();
+        if loudness == f32::NEG_INFINITY {
+            return 1.;
+        }
+
+        let gain = db_to_gain(target - loudness);
+        self.channels_mut().iter_mut().for_each(|channel| {
+            channel.as_mut_slice().iter_mut().for_each(|s| *s *= gain);
+        });
+        gain
+    }
+
+    /// Build the multi-resolution waveform peaks of the buffer, for drawing it
+    ///
+    /// Each level summarizes the buffer with the minimum, maximum and RMS of the samples of
+    /// consecutive pixels of `samples_per_pixel` sample frames, for each of the given
+    /// resolutions. Levels whose resolution is a multiple of a finer one are built from it
+    /// instead of the samples, so pyramids of doubling resolutions only read the samples once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no resolution is given or if a resolution is zero
+    ///
+    /// # Usage
+    ///
+    /// ```no_run
+    /// use web_audio_api::AudioBuffer;
+    ///
+    /// let buffer = AudioBuffer::from(vec![vec![0.; 44_100 * 60]], 44_100.);
+    /// let peaks = buffer.build_peaks(&[256, 512, 1024, 2048, 4096]);
+    ///
+    /// // zoomed out to 3000 sample frames per pixel, draw the level of 2048
+    /// let level = peaks.level_for(3000);
+    /// for (x, peak) in level.channel(0).iter().enumerate() {
+    ///     println!("{x}: [{}, {}]", peak.min, peak.max);
+    /// }
+    /// ```
+    pub fn build_peaks(&self, samples_per_pixel_levels: &[usize]) -> PeakPyramid {
+        build_peaks(self, samples_per_pixel_levels)
+    }
+
+    /// Copy of the buffer without its leading and trailing silence
+    ///
+    /// The sample frames at the start and at the end whose samples are all within
+    /// `[-threshold, threshold]` are removed. The copy is empty if the whole buffer is silent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the threshold is negative or NaN
+    pub fn trim_silence(&self, threshold: f32) -> AudioBuffer {
+        assert!(
+            threshold >= 0.,
+            "RangeError - threshold ({:?}) should be positive",
+            threshold
+        );
+
+        let is_audible = |i: &usize| {
+            self.channels
+                .iter()
+                .any(|channel| channel.as_slice()[*i].abs() > threshold)
+        };
+        let start = (0..self.length()).find(is_audible);
+        let end = (0..self.length()).rev().find(is_audible);
+        let range = match (start, end) {
+            (Some(start), Some(end)) => start..end + 1,
+            _ => 0..0,
+        };
+
+        let channels = self
+            .channels
+            .iter()
+            .map(|channel| ChannelData::from(channel.as_slice()[range.clone()].to_vec()))
+            .collect();
+        AudioBuffer::from_channels(channels, self.sample_rate)
+    }
+
+    /// Fade in the start of the buffer, from silence to the original level
+    ///
+    /// The fade is shortened to the duration of the buffer if needed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the duration is negative or not finite
+    pub fn fade_in(&mut self, duration: f64, curve: FadeCurve) {
+        let length = self.fade_length(duration);
+        self.channels.iter_mut().for_each(|channel| {
+            channel.as_mut_slice()[..length]
+                .iter_mut()
+                .enumerate()
+                .for_each(|(i, s)| *s *= curve.gain(i as f32 / length as f32));
+        });
+    }
+
+    /// Fade out the end of the buffer, from the original level to silence
+    ///
+    /// The fade is shortened to the duration of the buffer if needed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the duration is negative or not finite
+    pub fn fade_out(&mut self, duration: f64, curve: FadeCurve) {
+        let length = self.fade_length(duration);
+        let start = self.length() - length;
+        self.channels.iter_mut().for_each(|channel| {
+            channel.as_mut_slice()[start..]
+                .iter_mut()
+                .enumerate()
+                .for_each(|(i, s)| *s *= curve.gain(1. - (i + 1) as f32 / length as f32));
+        });
+    }
+
+    /// Copy of the given range of sample frames of the buffer
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of the bounds of the buffer
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> AudioBuffer {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.length(),
+        };
+        assert!(
+            start <= end && end <= self.length(),
+            "IndexSizeError - range {:?}..{:?} is out of the bounds of the buffer of length {:?}",
+            start,
+            end,
+            self.length()
+        );
+
+        let channels = self
+            .channels
+            .iter()
+            .map(|channel| ChannelData::from(channel.as_slice()[start..end].to_vec()))
+            .collect();
+        AudioBuffer::from_channels(channels, self.sample_rate)
+    }
+
+    /// Concatenate buffers one after the other
+    ///
+    /// # Panics
+    ///
+    /// Panics if the list is empty, or if the buffers do not all have the same number of
+    /// channels and sample rate
+    pub fn concat(buffers: &[AudioBuffer]) -> AudioBuffer {
+        Self::concat_with_crossfade(buffers, 0., FadeCurve::Linear)
+    }
+
+    /// Concatenate buffers, crossfading each buffer into the next one
+    ///
+    /// Consecutive buffers overlap during the crossfade, which is shortened to the duration of
+    /// the shortest of the two buffers if needed, so the result is shorter than the sum of the
+    /// buffers. Use [`FadeCurve::EqualPower`] to keep a constant power across joins of
+    /// uncorrelated signals.
+    ///
+    /// # Panics
+    ///
+    /// Panics if:
+    /// - the list is empty
+    /// - the buffers do not all have the same number of channels and sample rate
+    /// - the crossfade duration is negative or not finite
+    pub fn concat_with_crossfade(
+        buffers: &[AudioBuffer],
+        duration: f64,
+        curve: FadeCurve,
+    ) -> AudioBuffer {
+        let first = buffers
+            .first()
+            .expect("NotSupportedError - cannot concatenate an empty list of buffers");
+        let number_of_channels = first.number_of_channels();
+        let sample_rate = first.sample_rate();
+        buffers.iter().for_each(|buffer| {
+            assert!(
+                buffer.number_of_channels() == number_of_channels,
+                "NotSupportedError - cannot concatenate buffers with {:?} and {:?} channels",
+                number_of_channels,
+                buffer.number_of_channels()
+            );
+            assert!(
+                buffer.sample_rate() == sample_rate,
+                "NotSupportedError - cannot concatenate buffers with sample rates {:?} and {:?}",
+                sample_rate,
+                buffer.sample_rate()
+            );
+        });
+
+        let length = buffers.iter().map(AudioBuffer::length).sum();
+        let mut channels = vec![Vec::with_capacity(length); number_of_channels];
+        buffers.iter().for_each(|buffer| {
+            let overlap = buffer.fade_length(duration).min(channels[0].len());
+            channels
+                .iter_mut()
+                .zip(&buffer.channels)
+                .for_each(|(channel, data)| {
+                    let data = data.as_slice();
+                    let start = channel.len() - overlap;
+                    channel[start..]
+                        .iter_mut()
+                        .zip(&data[..overlap])
+                        .enumerate()
+                        .for_each(|(i, (s, v))| {
+                            let position = (i as f32 + 0.5) / overlap as f32;
+                            *s = *s * curve.gain(1. - position) + v * curve.gain(position);
+                        });
+                    channel.extend_from_slice(&data[overlap..]);
+                });
+        });
+
+        let channels = channels.into_iter().map(ChannelData::from).collect();
+        AudioBuffer::from_channels(channels, sample_rate)
+    }
+
+    /// Number of sample frames of a fade of the given duration
+    fn fade_length(&self, duration: f64) -> usize {
+        assert!(
+            duration >= 0. && duration.is_finite(),
+            "RangeError - fade duration ({:?}) should be positive and finite",
+            duration
+        );
+        ((duration * self.sample_rate as f64).round() as usize).min(self.length())
+    }
+
     /// Create a multi-channel audiobuffer directly from `ChannelData`s.
     // @todo - remove in favor of `AudioBuffer::from`
     pub(crate) fn from_channels(channels: Vec<ChannelData>, sample_rate: f32) -> Self {
@@ -269,6 +642,35 @@ impl AudioBuffer {
         &mut self.channels[index]
     }
 
+    /// Memory held by the channels, which may be shared with clones of this buffer
+    ///
+    /// Memory-mapped channels are not resident, they are not accounted for.
+    pub(crate) fn allocations(&self) -> Vec<Allocation> {
+        self.channels
+            .iter()
+            .map(|channel| match &channel.data {
+                Samples::Owned(data) => {
+                    let bytes = channel.len() * std::mem::size_of::<f32>();
+                    Allocation::shared(data, bytes)
+                }
+                #[cfg(feature = "mmap")]
+                Samples::Mapped(_) => Allocation::owned(0),
+            })
+            .collect()
+    }
+
+    /// Report the playhead position to the prefetching of memory-mapped channels
+    ///
+    /// This does nothing for memory-resident buffers. It does not block nor allocate, so it can
+    /// be called from the render thread.
+    #[cfg_attr(not(feature = "mmap"), allow(unused_variables))]
+    pub(crate) fn prefetch(&self, frame: usize) {
+        #[cfg(feature = "mmap")]
+        if let Some(Samples::Mapped(channel)) = self.channels.first().map(|c| &c.data) {
+            channel.file().set_playhead(frame);
+        }
+    }
+
     /// Extends an AudioBuffer with the contents of another.
     ///
     /// This function will panic if the sample_rate and channel_count are not equal
@@ -280,8 +682,7 @@ impl AudioBuffer {
         data.iter_mut()
             .zip(other.channels.iter())
             .for_each(|(channel, other_channel)| {
-                let cur_channel_data = Arc::make_mut(&mut channel.data);
-                cur_channel_data.extend(other_channel.as_slice());
+                channel.to_mut().extend(other_channel.as_slice());
             })
     }
 
@@ -290,7 +691,7 @@ impl AudioBuffer {
         let channels: Vec<_> = self
             .channels_mut()
             .iter_mut()
-            .map(|channel_data| Arc::make_mut(&mut channel_data.data).split_off(index))
+            .map(|channel_data| channel_data.to_mut().split_off(index))
             .map(ChannelData::from)
             .collect();
 
@@ -342,8 +743,8 @@ impl AudioBuffer {
             let k_inv = 1. - k;
 
             for (channel, resampled_data) in resampled.iter_mut().enumerate() {
-                let prev_sample = self.channels[channel].data[prev_index];
-                let next_sample = self.channels[channel].data[next_index];
+                let prev_sample = self.channels[channel].as_slice()[prev_index];
+                let next_sample = self.channels[channel].as_slice()[next_index];
 
                 let value = k_inv * prev_sample + k * next_sample;
                 resampled_data.push(value);
@@ -354,7 +755,7 @@ impl AudioBuffer {
             .iter_mut()
             .zip(resampled)
             .for_each(|(channel_data, resampled_data)| {
-                channel_data.data = Arc::new(resampled_data);
+                *channel_data = ChannelData::from(resampled_data);
             });
 
         self.sample_rate = sample_rate;
@@ -364,41 +765,79 @@ impl AudioBuffer {
 /// Single channel audio samples, basically wraps a `Arc<Vec<f32>>`
 ///
 /// ChannelData has copy-on-write semantics, so it is cheap to clone.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub(crate) struct ChannelData {
-    data: Arc<Vec<f32>>,
+    data: Samples,
+}
+
+/// Storage of the samples of a channel
+#[derive(Clone, Debug)]
+enum Samples {
+    Owned(Arc<Vec<f32>>),
+    /// Read-only samples of a memory-mapped file, copied on write
+    #[cfg(feature = "mmap")]
+    Mapped(MappedChannel),
 }
 
 impl ChannelData {
     pub fn new(length: usize) -> Self {
         let buffer = vec![0.; length];
-        let data = Arc::new(buffer);
-
-        Self { data }
+        Self::from(buffer)
     }
 
     pub fn from(data: Vec<f32>) -> Self {
         Self {
-            data: Arc::new(data),
+            data: Samples::Owned(Arc::new(data)),
+        }
+    }
+
+    #[cfg(feature = "mmap")]
+    fn mapped(channel: MappedChannel) -> Self {
+        Self {
+            data: Samples::Mapped(channel),
         }
     }
 
     pub fn len(&self) -> usize {
-        self.data.len()
+        self.as_slice().len()
     }
 
     // clippy wants to keep it, so keep it :)
     #[allow(dead_code)]
     pub fn is_empty(&self) -> bool {
-        self.data.is_empty()
+        self.as_slice().is_empty()
     }
 
     pub fn as_slice(&self) -> &[f32] {
-        &self.data[..]
+        match &self.data {
+            Samples::Owned(data) => &data[..],
+            #[cfg(feature = "mmap")]
+            Samples::Mapped(channel) => channel.as_slice(),
+        }
     }
 
     pub fn as_mut_slice(&mut self) -> &mut [f32] {
-        &mut Arc::make_mut(&mut self.data)[..]
+        &mut self.to_mut()[..]
+    }
+
+    /// Owned samples, cloned if they are shared or memory-mapped
+    fn to_mut(&mut self) -> &mut Vec<f32> {
+        #[cfg(feature = "mmap")]
+        if let Samples::Mapped(channel) = &self.data {
+            self.data = Samples::Owned(Arc::new(channel.as_slice().to_vec()));
+        }
+
+        match &mut self.data {
+            Samples::Owned(data) => Arc::make_mut(data),
+            #[cfg(feature = "mmap")]
+            Samples::Mapped(_) => unreachable!(),
+        }
+    }
+}
+
+impl PartialEq for ChannelData {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
     }
 }
 
@@ -796,4 +1235,230 @@ mod tests {
             assert_float_eq!(buffer.sample_rate, target_sr as f32, abs_all <= 0.);
         });
     }
+
+    fn sine(amplitude: f32, length: usize, sample_rate: f32) -> Vec<f32> {
+        (0..length)
+            .map(|i| amplitude * (2. * PI * 1000. * i as f32 / sample_rate).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_measure_loudness() {
+        // EBU Tech 3341: a stereo 1 kHz sine at -23 dBFS measures -23 LUFS
+        let sample_rate = 48000.;
+        let amplitude = db_to_gain(-23.);
+        for sample_rate in [sample_rate, 44100.] {
+            let channel = sine(amplitude, 20 * sample_rate as usize, sample_rate);
+            let buffer = AudioBuffer::from(vec![channel.clone(), channel], sample_rate);
+            assert_float_eq!(buffer.measure_loudness(), -23., abs <= 0.1);
+        }
+
+        // silence is gated out
+        let mut channel = sine(amplitude, 10 * 48000, sample_rate);
+        channel.resize(20 * 48000, 0.);
+        let buffer = AudioBuffer::from(vec![channel.clone(), channel], sample_rate);
+        assert_float_eq!(buffer.measure_loudness(), -23., abs <= 0.1);
+
+        let buffer = AudioBuffer::from(vec![vec![0.; 48000]], sample_rate);
+        assert_eq!(buffer.measure_loudness(), f32::NEG_INFINITY);
+
+        // shorter than a gating block
+        let buffer = AudioBuffer::from(vec![sine(1., 4800, sample_rate)], sample_rate);
+        assert_float_eq!(buffer.measure_loudness(), -3., abs <= 0.1);
+    }
+
+    #[test]
+    fn test_normalize_loudness() {
+        let sample_rate = 48000.;
+        let channel = sine(0.5, 48000, sample_rate);
+        let mut buffer = AudioBuffer::from(vec![channel.clone(), channel], sample_rate);
+        let loudness = buffer.measure_loudness();
+
+        let gain = buffer.normalize_loudness(-14.);
+        assert_float_eq!(gain, db_to_gain(-14. - loudness), ulps <= 0);
+        assert_float_eq!(buffer.measure_loudness(), -14., abs <= 0.01);
+        assert_float_eq!(
+            buffer.get_channel_data(0)[12],
+            gain * sine(0.5, 13, sample_rate)[12],
+            abs <= 1e-6
+        );
+
+        let mut silence = AudioBuffer::from(vec![vec![0.; 48000]], sample_rate);
+        assert_float_eq!(silence.normalize_loudness(-14.), 1., abs <= 0.);
+    }
+
+    #[test]
+    fn test_trim_silence() {
+        let buffer = AudioBuffer::from(
+            vec![
+                vec![0., 0.01, 0., 0.5, 0., -0.2, 0.01, 0.],
+                vec![0., 0., 0.3, 0., 0., 0., 0., 0.],
+            ],
+            48000.,
+        );
+
+        let trimmed = buffer.trim_silence(0.05);
+        assert_eq!(trimmed.number_of_channels(), 2);
+        assert_eq!(trimmed.sample_rate(), 48000.);
+        assert_float_eq!(
+            trimmed.get_channel_data(0),
+            &[0., 0.5, 0., -0.2][..],
+            abs_all <= 0.
+        );
+        assert_float_eq!(
+            trimmed.get_channel_data(1),
+            &[0.3, 0., 0., 0.][..],
+            abs_all <= 0.
+        );
+
+        // the source is left untouched
+        assert_eq!(buffer.length(), 8);
+        assert_eq!(buffer.trim_silence(0.).length(), 6);
+        assert_eq!(buffer.trim_silence(1.).length(), 0);
+    }
+
+    #[test]
+    fn test_fades() {
+        let mut buffer = AudioBuffer::from(vec![vec![1.; 8]], 4000.);
+        buffer.fade_in(0.001, FadeCurve::Linear);
+        assert_float_eq!(
+            buffer.get_channel_data(0),
+            &[0., 0.25, 0.5, 0.75, 1., 1., 1., 1.][..],
+            abs_all <= 0.
+        );
+        buffer.fade_out(0.001, FadeCurve::Linear);
+        assert_float_eq!(
+            buffer.get_channel_data(0),
+            &[0., 0.25, 0.5, 0.75, 0.75, 0.5, 0.25, 0.][..],
+            abs_all <= 0.
+        );
+
+        // equal power fades keep a constant power when crossfading
+        for i in 0..=10 {
+            let position = i as f32 / 10.;
+            let fade_in = FadeCurve::EqualPower.gain(position);
+            let fade_out = FadeCurve::EqualPower.gain(1. - position);
+            assert_float_eq!(fade_in.powi(2) + fade_out.powi(2), 1., abs <= 1e-6);
+        }
+
+        // the fade is shortened to the duration of the buffer
+        let mut buffer = AudioBuffer::from(vec![vec![1.; 4]], 4000.);
+        buffer.fade_out(10., FadeCurve::Exponential);
+        let data = buffer.get_channel_data(0);
+        assert_float_eq!(data[0], 1000_f32.powf(0.75) / 999. - 1. / 999., abs <= 1e-6);
+        assert_float_eq!(data[3], 0., abs <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_fade_duration() {
+        let mut buffer = AudioBuffer::from(vec![vec![1.; 4]], 4000.);
+        buffer.fade_in(-1., FadeCurve::Linear);
+    }
+
+    #[test]
+    fn test_slice() {
+        let buffer = AudioBuffer::from(vec![vec![0., 1., 2., 3.], vec![4., 5., 6., 7.]], 48000.);
+
+        let slice = buffer.slice(1..3);
+        assert_eq!(slice.number_of_channels(), 2);
+        assert_float_eq!(slice.get_channel_data(0), &[1., 2.][..], abs_all <= 0.);
+        assert_float_eq!(slice.get_channel_data(1), &[5., 6.][..], abs_all <= 0.);
+
+        assert_eq!(buffer.slice(..).length(), 4);
+        assert_eq!(buffer.slice(2..).length(), 2);
+        assert_eq!(buffer.slice(..=2).length(), 3);
+        assert_eq!(buffer.slice(4..).length(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_slice_out_of_bounds() {
+        let buffer = AudioBuffer::from(vec![vec![0.; 4]], 48000.);
+        let _ = buffer.slice(2..5);
+    }
+
+    #[test]
+    fn test_concat_buffers() {
+        let a = AudioBuffer::from(vec![vec![1., 1.], vec![2., 2.]], 48000.);
+        let b = AudioBuffer::from(vec![vec![3.], vec![4.]], 48000.);
+
+        let buffer = AudioBuffer::concat(&[a.clone(), b.clone(), a.clone()]);
+        assert_eq!(buffer.sample_rate(), 48000.);
+        assert_float_eq!(
+            buffer.get_channel_data(0),
+            &[1., 1., 3., 1., 1.][..],
+            abs_all <= 0.
+        );
+        assert_float_eq!(
+            buffer.get_channel_data(1),
+            &[2., 2., 4., 2., 2.][..],
+            abs_all <= 0.
+        );
+
+        // crossfades overlap the buffers
+        let ones = AudioBuffer::from(vec![vec![1.; 8]], 4000.);
+        let zeros = AudioBuffer::from(vec![vec![0.; 8]], 4000.);
+        let buffer = AudioBuffer::concat_with_crossfade(&[ones, zeros], 0.001, FadeCurve::Linear);
+        assert_float_eq!(
+            buffer.get_channel_data(0),
+            &[1., 1., 1., 1., 0.875, 0.625, 0.375, 0.125, 0., 0., 0., 0.][..],
+            abs_all <= 1e-6
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_concat_sample_rate_mismatch() {
+        let a = AudioBuffer::from(vec![vec![0.; 4]], 48000.);
+        let b = AudioBuffer::from(vec![vec![0.; 4]], 44100.);
+        let _ = AudioBuffer::concat(&[a, b]);
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn test_memory_mapped() {
+        use crate::context::{BaseAudioContext, OfflineAudioContext};
+        use crate::node::{AudioNode, AudioScheduledSourceNode};
+
+        let path = std::env::temp_dir().join("web_audio_api_test_memory_mapped.pcm");
+        let samples: Vec<f32> = (0..1000).map(|i| i as f32 / 1000.).collect();
+        let negated = samples.iter().map(|s| -s).collect();
+        let original = AudioBuffer::from(vec![samples, negated], 48000.);
+        original.write_pcm_file(&path).unwrap();
+
+        let mut buffer = unsafe { AudioBuffer::map_pcm_file(&path, 2, 48000.) }.unwrap();
+        assert!(buffer.is_memory_mapped());
+        assert_eq!(buffer.number_of_channels(), 2);
+        assert_eq!(buffer.length(), 1000);
+        assert_float_eq!(
+            buffer.get_channel_data(1),
+            original.get_channel_data(1),
+            abs_all <= 0.
+        );
+
+        // mapped channels are not resident memory
+        let context = OfflineAudioContext::new(2, 128, 48000.);
+        let mut src = context.create_buffer_source();
+        src.set_buffer(buffer.clone());
+        assert_eq!(context.memory_report().audio_buffers, 0);
+
+        src.connect(&context.destination());
+        src.start();
+        let output = context.start_rendering_sync();
+        assert_float_eq!(
+            output.get_channel_data(0),
+            &original.get_channel_data(0)[..128],
+            abs_all <= 0.
+        );
+
+        // writing copies the channel, the file is left untouched
+        buffer.get_channel_data_mut(0)[0] = 1.;
+        assert!(!buffer.is_memory_mapped());
+        let mapped = unsafe { AudioBuffer::map_pcm_file(&path, 2, 48000.) }.unwrap();
+        assert_eq!(mapped.get_channel_data(0)[0], 0.);
+
+        // the file does not hold whole frames of 3 channels
+        assert!(unsafe { AudioBuffer::map_pcm_file(&path, 3, 48000.) }.is_err());
+    }
 }
@@ -1,9 +1,15 @@
+use arrayvec::ArrayVec;
 use crossbeam_channel::{Receiver, Sender};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
-use crate::context::{BaseAudioContext, ConcreteBaseAudioContext};
+use crate::context::{AudioNodeId, BaseAudioContext, ConcreteBaseAudioContext};
 use crate::events::{EventDispatch, EventHandler, EventPayload, EventType};
-use crate::Event;
+use crate::message::ControlMessage;
+use crate::{AtomicF64, Event};
+
+/// Maximum number of nodes listed in an [`AudioUnderrunEvent`]
+pub(crate) const MAX_REPORTED_NODES: usize = 8;
 
 #[derive(Copy, Clone)]
 pub(crate) struct AudioRenderCapacityLoad {
@@ -56,6 +62,252 @@ impl AudioRenderCapacityEvent {
     }
 }
 
+/// Render time of a single node during a render callback
+#[derive(Clone, Copy, Debug)]
+pub struct NodeRenderTime {
+    /// Identifier of the node, see
+    /// [`AudioContextRegistration::node_id`](crate::context::AudioContextRegistration::node_id)
+    pub node_id: u64,
+    /// Time spent rendering the node, in seconds
+    pub render_time: f64,
+}
+
+/// Diagnostics of an audio glitch, i.e. a render callback that missed its deadline or an under/
+/// overrun reported by the audio backend
+#[derive(Clone, Debug)]
+pub struct AudioUnderrunEvent {
+    /// Time of the start of the render callback, in terms of the associated AudioContext's
+    /// currentTime
+    pub timestamp: f64,
+    /// Index of the first render quantum of the render callback
+    pub render_quantum: u64,
+    /// Measured duration of the render callback, in seconds
+    ///
+    /// Zero when the underrun was reported by the audio backend.
+    pub render_time: f64,
+    /// Duration of the audio rendered by the callback, i.e. the time the callback should have
+    /// taken at most
+    ///
+    /// Zero when the underrun was reported by the audio backend.
+    pub deadline: f64,
+    /// Slowest nodes of the render callback, by decreasing render time
+    ///
+    /// The render time of the nodes is only measured when an underrun handler is set with
+    /// [`AudioRenderCapacity::set_onunderrun`], the list is empty otherwise.
+    pub slowest_nodes: Vec<NodeRenderTime>,
+    /// Error message of the audio backend, if the underrun was reported by the backend
+    pub backend_message: Option<String>,
+    /// Inherits from this base Event
+    pub event: Event,
+}
+
+/// Underrun diagnostics collected in the render thread, without allocations
+pub(crate) struct UnderrunReport {
+    pub timestamp: f64,
+    pub render_quantum: u64,
+    pub render_time: f64,
+    pub deadline: f64,
+    pub slowest_nodes: ArrayVec<(AudioNodeId, f64), MAX_REPORTED_NODES>,
+    pub backend_message: Option<String>,
+}
+
+impl UnderrunReport {
+    pub(crate) fn to_event(&self) -> AudioUnderrunEvent {
+        AudioUnderrunEvent {
+            timestamp: self.timestamp,
+            render_quantum: self.render_quantum,
+            render_time: self.render_time,
+            deadline: self.deadline,
+            slowest_nodes: self
+                .slowest_nodes
+                .iter()
+                .map(|&(id, render_time)| NodeRenderTime {
+                    node_id: id.0,
+                    render_time,
+                })
+                .collect(),
+            backend_message: self.backend_message.clone(),
+            event: Event {
+                type_: "AudioUnderrunEvent",
+            },
+        }
+    }
+}
+
+/// Upper bounds of the buckets of a [`TimingHistogram`], in seconds
+///
+/// The histogram has an extra bucket for the durations above the last bound.
+pub const TIMING_HISTOGRAM_BOUNDS: [f64; 10] =
+    [0.001, 0.002, 0.005, 0.01, 0.02, 0.05, 0.1, 0.2, 0.5, 1.];
+
+const TIMING_HISTOGRAM_BUCKETS: usize = TIMING_HISTOGRAM_BOUNDS.len() + 1;
+
+/// Histogram of durations, see [`TIMING_HISTOGRAM_BOUNDS`] for the buckets
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TimingHistogram {
+    /// Number of durations in each bucket, `counts[i]` counts the durations up to
+    /// `TIMING_HISTOGRAM_BOUNDS[i]` (and above the previous bound), the last bucket counts the
+    /// durations above one second
+    pub counts: [u64; TIMING_HISTOGRAM_BUCKETS],
+}
+
+impl TimingHistogram {
+    /// Total number of durations
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Upper bound of the bucket holding the given quantile (between 0 and 1) of the durations,
+    /// in seconds
+    ///
+    /// Returns `None` when the histogram is empty, and `f64::INFINITY` when the quantile falls in
+    /// the last bucket.
+    #[must_use]
+    pub fn quantile(&self, quantile: f64) -> Option<f64> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+
+        let rank = (quantile.clamp(0., 1.) * total as f64).ceil().max(1.) as u64;
+        let mut count = 0;
+        let bucket = self.counts.iter().take_while(|&&c| {
+            count += c;
+            count < rank
+        });
+
+        Some(
+            TIMING_HISTOGRAM_BOUNDS
+                .get(bucket.count())
+                .copied()
+                .unwrap_or(f64::INFINITY),
+        )
+    }
+}
+
+/// Timing of the scheduled control messages, relative to the render thread
+///
+/// The automation events of the [`AudioParam`](crate::AudioParam)s (except `set_value`) and the
+/// start and stop of the [`AudioScheduledSourceNode`](crate::node::AudioScheduledSourceNode)s
+/// carry the time at which they take effect. The render thread compares this time with its clock
+/// when the message arrives: a message arriving in time is applied sample accurately, a message
+/// arriving late is applied at the start of the current render quantum.
+///
+/// The lookahead of a sequencer, i.e. how far in advance it schedules its events, should exceed
+/// the lookahead of the slowest messages: when late messages are reported, the lookahead is too
+/// short for the pipeline (control thread, message queue and render thread buffering).
+///
+/// The times of the nodes added to a [`Transport`](crate::transport::Transport) are compared with the clock
+/// of the context, not the clock of the transport. The start and end times of the linear and
+/// exponential ramps are not measured.
+#[derive(Clone, Debug, Default)]
+pub struct SchedulingStats {
+    /// Number of scheduled messages received by the render thread
+    pub messages: u64,
+    /// Smallest lookahead, in seconds, negative if a message arrived late
+    ///
+    /// `None` when no message was received.
+    pub min_lookahead: Option<f64>,
+    /// Time between the arrival of the messages and their scheduled time, for the messages
+    /// arriving in time
+    pub lookahead: TimingHistogram,
+    /// Delay between the scheduled time and the actual time of the late messages
+    pub jitter: TimingHistogram,
+}
+
+impl SchedulingStats {
+    /// Number of messages that arrived after their scheduled time
+    #[must_use]
+    pub fn late(&self) -> u64 {
+        self.jitter.total()
+    }
+}
+
+/// Lock-free counters of the [`SchedulingStats`], written by the render thread, and of the
+/// scheduled graph mutations
+pub(crate) struct SchedulingCounters {
+    messages: AtomicU64,
+    min_lookahead: AtomicF64,
+    lookahead: [AtomicU64; TIMING_HISTOGRAM_BUCKETS],
+    jitter: [AtomicU64; TIMING_HISTOGRAM_BUCKETS],
+    /// graph mutations sent by the control thread and not applied yet
+    pending_mutations: AtomicUsize,
+}
+
+impl Default for SchedulingCounters {
+    fn default() -> Self {
+        Self {
+            messages: AtomicU64::new(0),
+            min_lookahead: AtomicF64::new(f64::INFINITY),
+            lookahead: Default::default(),
+            jitter: Default::default(),
+            pending_mutations: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl SchedulingCounters {
+    /// Record the lookahead of a message, in seconds, negative if the message is late
+    ///
+    /// Only called from the render thread, the counters have a single writer.
+    pub fn record(&self, lookahead: f64) {
+        self.messages.fetch_add(1, Ordering::Relaxed);
+        if lookahead < self.min_lookahead.load(Ordering::Relaxed) {
+            self.min_lookahead.store(lookahead, Ordering::Relaxed);
+        }
+
+        let (histogram, duration) = if lookahead >= 0. {
+            (&self.lookahead, lookahead)
+        } else {
+            (&self.jitter, -lookahead)
+        };
+        let bucket = TIMING_HISTOGRAM_BOUNDS.partition_point(|&bound| bound < duration);
+        histogram[bucket].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> SchedulingStats {
+        let histogram = |counters: &[AtomicU64; TIMING_HISTOGRAM_BUCKETS]| TimingHistogram {
+            counts: std::array::from_fn(|i| counters[i].load(Ordering::Relaxed)),
+        };
+        let min_lookahead = self.min_lookahead.load(Ordering::Relaxed);
+
+        SchedulingStats {
+            messages: self.messages.load(Ordering::Relaxed),
+            min_lookahead: min_lookahead.is_finite().then_some(min_lookahead),
+            lookahead: histogram(&self.lookahead),
+            jitter: histogram(&self.jitter),
+        }
+    }
+
+    /// Reserve room for `count` graph mutations in the queue of the render thread, returns false
+    /// if more than `max` mutations would be pending
+    pub fn reserve_mutations(&self, count: usize, max: usize) -> bool {
+        self.pending_mutations
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |pending| {
+                pending.checked_add(count).filter(|&total| total <= max)
+            })
+            .is_ok()
+    }
+
+    /// Release the room of a graph mutation, once applied by the render thread
+    pub fn release_mutation(&self) {
+        self.pending_mutations.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Clear the counters, a message recorded meanwhile may be partially cleared
+    ///
+    /// The count of the pending graph mutations is not a statistic and is kept.
+    pub fn reset(&self) {
+        self.messages.store(0, Ordering::Relaxed);
+        self.min_lookahead.store(f64::INFINITY, Ordering::Relaxed);
+        self.lookahead
+            .iter()
+            .chain(&self.jitter)
+            .for_each(|c| c.store(0, Ordering::Relaxed));
+    }
+}
+
 /// Provider for rendering performance metrics
 ///
 /// A load value is computed for each system-level audio callback, by dividing its execution
@@ -181,4 +433,129 @@ impl AudioRenderCapacity {
     pub fn clear_onupdate(&self) {
         self.context.clear_event_handler(EventType::RenderCapacity);
     }
+
+    /// The EventHandler for [`AudioUnderrunEvent`], dispatched when an audio glitch occurs
+    ///
+    /// Setting a handler enables the measurement of the render time of each node, so the event
+    /// can list the slowest nodes. This adds a small overhead to the render thread.
+    ///
+    /// Only a single event handler is active at any time. Calling this method multiple times will
+    /// override the previous event handler.
+    pub fn set_onunderrun<F: FnMut(AudioUnderrunEvent) + Send + 'static>(&self, mut callback: F) {
+        let callback = move |v| match v {
+            EventPayload::Underrun(v) => callback(v.to_event()),
+            _ => unreachable!(),
+        };
+
+        self.context.set_event_handler(
+            EventType::Underrun,
+            EventHandler::Multiple(Box::new(callback)),
+        );
+        let _ = self
+            .context
+            .send_control_msg(ControlMessage::SetNodeProfiling { enabled: true });
+    }
+
+    /// Timing of the scheduled control messages since the creation of the context, or the last
+    /// call to [`reset_scheduling_stats`](Self::reset_scheduling_stats)
+    ///
+    /// Use these statistics to tune the lookahead of a sequencer, see [`SchedulingStats`].
+    #[must_use]
+    pub fn scheduling_stats(&self) -> SchedulingStats {
+        self.context.scheduling_stats()
+    }
+
+    /// Clear the [`SchedulingStats`]
+    pub fn reset_scheduling_stats(&self) {
+        self.context.reset_scheduling_stats();
+    }
+
+    /// Unset the EventHandler for [`AudioUnderrunEvent`], and stop measuring the render time of
+    /// each node
+    pub fn clear_onunderrun(&self) {
+        self.context.clear_event_handler(EventType::Underrun);
+        let _ = self
+            .context
+            .send_control_msg(ControlMessage::SetNodeProfiling { enabled: false });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::OfflineAudioContext;
+    use crate::node::{AudioNode, AudioScheduledSourceNode};
+
+    #[test]
+    fn test_timing_histogram() {
+        let mut histogram = TimingHistogram::default();
+        assert_eq!(histogram.quantile(0.5), None);
+
+        histogram.counts[0] = 8;
+        histogram.counts[3] = 1;
+        histogram.counts[10] = 1;
+        assert_eq!(histogram.total(), 10);
+        assert_eq!(histogram.quantile(0.), Some(0.001));
+        assert_eq!(histogram.quantile(0.8), Some(0.001));
+        assert_eq!(histogram.quantile(0.9), Some(0.01));
+        assert_eq!(histogram.quantile(1.), Some(f64::INFINITY));
+    }
+
+    #[test]
+    fn test_scheduling_counters() {
+        let counters = SchedulingCounters::default();
+        let stats = counters.snapshot();
+        assert_eq!(stats.messages, 0);
+        assert_eq!(stats.min_lookahead, None);
+
+        counters.record(0.0005);
+        counters.record(0.03);
+        counters.record(0.03);
+        counters.record(-0.004);
+        counters.record(5.);
+
+        let stats = counters.snapshot();
+        assert_eq!(stats.messages, 5);
+        assert_eq!(stats.late(), 1);
+        assert_eq!(stats.min_lookahead, Some(-0.004));
+        assert_eq!(stats.lookahead.counts, [1, 0, 0, 0, 0, 2, 0, 0, 0, 0, 1]);
+        assert_eq!(stats.jitter.counts, [0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+        counters.reset();
+        let stats = counters.snapshot();
+        assert_eq!(stats.messages, 0);
+        assert_eq!(stats.min_lookahead, None);
+        assert_eq!(stats.lookahead.total(), 0);
+        assert_eq!(stats.jitter.total(), 0);
+    }
+
+    #[test]
+    fn test_scheduling_stats() {
+        let context = OfflineAudioContext::new(1, 48_000, 48_000.);
+        let base = context.base().clone();
+
+        let mut src = context.create_constant_source();
+        src.connect(&context.destination());
+        src.offset().set_value(0.5); // not scheduled
+        src.offset().set_value_at_time(1., 0.3);
+        src.offset().linear_ramp_to_value_at_time(0., 0.9); // not measured
+        src.start_at(0.25);
+
+        let output = context.start_rendering_sync();
+
+        // the scheduled messages still reach the processors
+        let output = output.get_channel_data(0);
+        assert_eq!(output[11_999], 0.);
+        assert_eq!(output[12_100], 0.5);
+        assert_eq!(output[14_400], 1.);
+
+        let stats = base.scheduling_stats();
+        assert_eq!(stats.messages, 2);
+        assert_eq!(stats.late(), 0);
+        assert_eq!(stats.min_lookahead, Some(0.25));
+        assert_eq!(stats.lookahead.counts, [0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0]);
+
+        base.reset_scheduling_stats();
+        assert_eq!(base.scheduling_stats().messages, 0);
+    }
 }
@@ -0,0 +1,999 @@
+//! Containers playing variations of a sound, as found in game audio middleware
+//!
+//! A [`RandomContainer`] plays one of its buffers each time it is triggered, e.g. so the
+//! footsteps of a character do not sound the same at every step. A [`SequenceContainer`] plays its
+//! buffers one after the other, e.g. the layers of an explosion or the bars of a musical phrase.
+//! A [`BlendContainer`] loops layers together and mixes them along a control value, e.g. the
+//! engine of a car at increasing speeds.
+
+use std::collections::VecDeque;
+use std::hash::{BuildHasher, Hasher};
+
+use crate::buffer::AudioBuffer;
+use crate::context::{BaseAudioContext, ConcreteBaseAudioContext};
+use crate::db_to_gain;
+use crate::node::{
+    AudioBufferSourceNode, AudioBufferSourceOptions, AudioNode, AudioScheduledSourceNode, GainNode,
+};
+use crate::param::set_values_synchronized;
+use crate::rtpc::RtpcCurve;
+
+/// Small pseudo random number generator (xorshift64*) for the picks of the containers
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+    /// Seeded from the random keys of the standard library
+    fn from_entropy() -> Self {
+        let seed = std::collections::hash_map::RandomState::new()
+            .build_hasher()
+            .finish();
+        Self::new(seed)
+    }
+
+    fn new(seed: u64) -> Self {
+        // the state must not be zero
+        Self(seed.max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniformly distributed in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniformly distributed in [min, max]
+    fn range(&mut self, (min, max): (f32, f32)) -> f32 {
+        min + (max - min) * self.next_f64() as f32
+    }
+}
+
+/// Variation of a [`RandomContainer`]
+struct RandomChild {
+    buffer: AudioBuffer,
+    weight: f32,
+}
+
+/// Plays one of its buffers, picked at random, each time it is triggered
+///
+/// The probability of a buffer to be picked is proportional to its weight. The most recently
+/// played buffers can be excluded from the next picks with
+/// [`set_avoid_repeat`](Self::set_avoid_repeat), so the same variation is never heard twice in
+/// a row. Each pick is played with a random detune and gain, drawn uniformly in the ranges of
+/// the container.
+///
+/// All picks are played into the output bus, which is connected to the destination of the
+/// context. The picks overlap when triggered faster than their duration.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use std::fs::File;
+/// use web_audio_api::container::RandomContainer;
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+///
+/// let context = AudioContext::default();
+/// let mut footsteps = RandomContainer::new(&context);
+/// for path in ["step-1.wav", "step-2.wav", "step-3.wav"] {
+///     let buffer = context.decode_audio_data_sync(File::open(path).unwrap()).unwrap();
+///     footsteps.add(buffer, 1.);
+/// }
+/// footsteps.set_avoid_repeat(1);
+/// footsteps.set_detune_range(-100., 100.);
+/// footsteps.set_gain_range(-3., 0.);
+///
+/// // on each step of the character
+/// footsteps.play();
+/// ```
+pub struct RandomContainer {
+    context: ConcreteBaseAudioContext,
+    output: GainNode,
+    children: Vec<RandomChild>,
+    /// in cents
+    detune_range: (f32, f32),
+    /// in dB
+    gain_range: (f32, f32),
+    avoid_repeat: usize,
+    /// most recent picks first
+    history: VecDeque<usize>,
+    rng: Rng,
+}
+
+impl std::fmt::Debug for RandomContainer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RandomContainer")
+            .field("len", &self.children.len())
+            .field("detune_range", &self.detune_range)
+            .field("gain_range", &self.gain_range)
+            .field("avoid_repeat", &self.avoid_repeat)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RandomContainer {
+    /// Create a new container, with its output bus connected to the destination of the context
+    pub fn new<C: BaseAudioContext>(context: &C) -> Self {
+        let output = context.create_gain();
+        output.connect(&context.destination());
+
+        Self {
+            context: context.base().clone(),
+            output,
+            children: vec![],
+            detune_range: (0., 0.),
+            gain_range: (0., 0.),
+            avoid_repeat: 0,
+            history: VecDeque::new(),
+            rng: Rng::from_entropy(),
+        }
+    }
+
+    /// The output bus, all picks are played into this node
+    pub fn output(&self) -> &GainNode {
+        &self.output
+    }
+
+    /// Add a variation and return its index
+    ///
+    /// # Panics
+    ///
+    /// Panics if the weight is negative or not finite
+    pub fn add(&mut self, buffer: AudioBuffer, weight: f32) -> usize {
+        assert_valid_weight(weight);
+        self.children.push(RandomChild { buffer, weight });
+        self.children.len() - 1
+    }
+
+    /// Number of variations
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Check if the container has no variations
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Weight of a variation
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds
+    pub fn weight(&self, index: usize) -> f32 {
+        self.child(index).weight
+    }
+
+    /// Change the weight of a variation, a weight of zero disables it
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds, or if the weight is negative or not finite
+    pub fn set_weight(&mut self, index: usize, weight: f32) {
+        assert_valid_weight(weight);
+        self.child(index);
+        self.children[index].weight = weight;
+    }
+
+    fn child(&self, index: usize) -> &RandomChild {
+        match self.children.get(index) {
+            Some(child) => child,
+            None => panic!(
+                "IndexSizeError - Invalid variation index {} for container of length {}",
+                index,
+                self.children.len()
+            ),
+        }
+    }
+
+    /// Range of the random detune of the picks, in cents
+    pub fn detune_range(&self) -> (f32, f32) {
+        self.detune_range
+    }
+
+    /// Set the range of the random detune of the picks, in cents
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` is greater than `max`, or if a bound is not finite
+    pub fn set_detune_range(&mut self, min: f32, max: f32) {
+        assert_valid_range(min, max);
+        self.detune_range = (min, max);
+    }
+
+    /// Range of the random gain of the picks, in dB
+    pub fn gain_range(&self) -> (f32, f32) {
+        self.gain_range
+    }
+
+    /// Set the range of the random gain of the picks, in dB
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` is greater than `max`, or if a bound is not finite
+    pub fn set_gain_range(&mut self, min: f32, max: f32) {
+        assert_valid_range(min, max);
+        self.gain_range = (min, max);
+    }
+
+    /// Number of most recent picks excluded from the next pick
+    pub fn avoid_repeat(&self) -> usize {
+        self.avoid_repeat
+    }
+
+    /// Exclude the `count` most recent picks from the next pick
+    ///
+    /// At least one variation always remains available: with `n` enabled variations, at most
+    /// the `n - 1` most recent picks are excluded.
+    pub fn set_avoid_repeat(&mut self, count: usize) {
+        self.avoid_repeat = count;
+        self.history.truncate(count);
+    }
+
+    /// Seed the random picks, to get a reproducible sequence
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    /// Pick a variation, without playing it
+    ///
+    /// Returns `None` when no variation has a positive weight. The pick counts as played for
+    /// the repetition avoidance.
+    pub fn pick(&mut self) -> Option<usize> {
+        let enabled = self.children.iter().filter(|c| c.weight > 0.).count();
+        if enabled == 0 {
+            return None;
+        }
+
+        let excluded = self.avoid_repeat.min(enabled - 1);
+        let is_allowed = |index: &usize| {
+            self.children[*index].weight > 0.
+                && !self.history.iter().take(excluded).any(|i| i == index)
+        };
+        let total: f64 = (0..self.children.len())
+            .filter(is_allowed)
+            .map(|i| self.children[i].weight as f64)
+            .sum();
+
+        let mut target = self.rng.next_f64() * total;
+        let mut pick = None;
+        for index in (0..self.children.len()).filter(is_allowed) {
+            pick = Some(index);
+            target -= self.children[index].weight as f64;
+            if target < 0. {
+                break;
+            }
+        }
+
+        if let Some(index) = pick {
+            self.history.push_front(index);
+            self.history.truncate(self.avoid_repeat);
+        }
+        pick
+    }
+
+    /// Play a variation now, and return its index
+    ///
+    /// Returns `None` and plays nothing when no variation has a positive weight.
+    pub fn play(&mut self) -> Option<usize> {
+        self.play_at(self.context.current_time())
+    }
+
+    /// Play a variation at the given time of the context, and return its index
+    ///
+    /// Returns `None` and plays nothing when no variation has a positive weight.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `when` is negative
+    pub fn play_at(&mut self, when: f64) -> Option<usize> {
+        assert!(
+            when >= 0.,
+            "RangeError - Unable to play container at negative time {:?}",
+            when
+        );
+
+        let index = self.pick()?;
+        let detune = self.rng.range(self.detune_range);
+        let gain = db_to_gain(self.rng.range(self.gain_range));
+
+        let options = AudioBufferSourceOptions {
+            buffer: Some(self.children[index].buffer.clone()),
+            detune,
+            ..AudioBufferSourceOptions::default()
+        };
+        self.context.batch_node_messages(|| {
+            let volume = self.context.create_gain();
+            volume.gain().set_value(gain);
+            volume.connect(&self.output);
+            let mut source = AudioBufferSourceNode::new(&self.context, options);
+            source.connect(&volume);
+            source.start_at(when);
+        });
+
+        Some(index)
+    }
+}
+
+/// Start time of a step of a [`SequenceContainer`], relative to the previous step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StepTiming {
+    /// Start this many seconds after the end of the previous step, a negative delay makes the
+    /// steps overlap
+    AfterEnd(f64),
+    /// Start this many beats after the start of the previous step, at the tempo of the
+    /// container
+    Beats(f64),
+}
+
+impl Default for StepTiming {
+    fn default() -> Self {
+        Self::AfterEnd(0.)
+    }
+}
+
+/// Step of a [`SequenceContainer`]
+struct SequenceStep {
+    buffer: AudioBuffer,
+    timing: StepTiming,
+}
+
+/// Plays its buffers in order, with a delay between the steps
+///
+/// The timing of each step is given relative to the previous step, either in seconds after its
+/// end or in beats after its start (see [`StepTiming`]). The timing of the first step is
+/// ignored when the sequence starts, and applies between the loops: the whole sequence is
+/// played [`loop_count`](Self::loop_count) times.
+///
+/// All steps are scheduled at once when the sequence is played, so they are sample accurate.
+/// They are played into the output bus, which is connected to the destination of the context.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use std::fs::File;
+/// use web_audio_api::container::{SequenceContainer, StepTiming};
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+///
+/// let context = AudioContext::default();
+/// let decode = |path| context.decode_audio_data_sync(File::open(path).unwrap()).unwrap();
+///
+/// let mut phrase = SequenceContainer::new(&context);
+/// phrase.set_tempo(96.);
+/// phrase.push(decode("bar-1.wav"), StepTiming::Beats(4.));
+/// phrase.push(decode("bar-2.wav"), StepTiming::Beats(4.));
+/// phrase.push(decode("fill.wav"), StepTiming::Beats(4.));
+/// phrase.set_loop_count(2);
+/// phrase.play();
+/// ```
+pub struct SequenceContainer {
+    context: ConcreteBaseAudioContext,
+    output: GainNode,
+    steps: Vec<SequenceStep>,
+    /// in beats per minute
+    tempo: f64,
+    loop_count: usize,
+    /// scheduled sources, with their end times
+    sources: Vec<(AudioBufferSourceNode, f64)>,
+}
+
+impl std::fmt::Debug for SequenceContainer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SequenceContainer")
+            .field("len", &self.steps.len())
+            .field("tempo", &self.tempo)
+            .field("loop_count", &self.loop_count)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SequenceContainer {
+    /// Create a new container, with its output bus connected to the destination of the context
+    pub fn new<C: BaseAudioContext>(context: &C) -> Self {
+        let output = context.create_gain();
+        output.connect(&context.destination());
+
+        Self {
+            context: context.base().clone(),
+            output,
+            steps: vec![],
+            tempo: 120.,
+            loop_count: 1,
+            sources: vec![],
+        }
+    }
+
+    /// The output bus, all steps are played into this node
+    pub fn output(&self) -> &GainNode {
+        &self.output
+    }
+
+    /// Add a step at the end of the sequence and return its index
+    pub fn push(&mut self, buffer: AudioBuffer, timing: StepTiming) -> usize {
+        self.steps.push(SequenceStep { buffer, timing });
+        self.steps.len() - 1
+    }
+
+    /// Number of steps of the sequence
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Check if the sequence has no steps
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Timing of a step
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds
+    pub fn timing(&self, index: usize) -> StepTiming {
+        self.step(index).timing
+    }
+
+    /// Change the timing of a step, for the next playbacks
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds
+    pub fn set_timing(&mut self, index: usize, timing: StepTiming) {
+        self.step(index);
+        self.steps[index].timing = timing;
+    }
+
+    fn step(&self, index: usize) -> &SequenceStep {
+        match self.steps.get(index) {
+            Some(step) => step,
+            None => panic!(
+                "IndexSizeError - Invalid step index {} for sequence of length {}",
+                index,
+                self.steps.len()
+            ),
+        }
+    }
+
+    /// Tempo of the [`StepTiming::Beats`] timings, in beats per minute
+    pub fn tempo(&self) -> f64 {
+        self.tempo
+    }
+
+    /// Set the tempo of the [`StepTiming::Beats`] timings, in beats per minute
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tempo is not strictly positive and finite
+    pub fn set_tempo(&mut self, bpm: f64) {
+        assert!(
+            bpm.is_finite() && bpm > 0.,
+            "RangeError - Invalid tempo {:?}, should be strictly positive",
+            bpm
+        );
+        self.tempo = bpm;
+    }
+
+    /// Number of times the sequence is played, defaults to 1
+    pub fn loop_count(&self) -> usize {
+        self.loop_count
+    }
+
+    /// Set the number of times the sequence is played
+    ///
+    /// # Panics
+    ///
+    /// Panics if the count is zero
+    pub fn set_loop_count(&mut self, count: usize) {
+        assert!(count > 0, "RangeError - Loop count should be at least 1");
+        self.loop_count = count;
+    }
+
+    /// Play the sequence now, and return the time of the context at which it ends
+    pub fn play(&mut self) -> f64 {
+        self.play_at(self.context.current_time())
+    }
+
+    /// Play the sequence at the given time of the context, and return the time at which it ends
+    ///
+    /// An empty sequence ends at `when`. The previous playbacks are not stopped, the sequences
+    /// overlap.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `when` is negative
+    pub fn play_at(&mut self, when: f64) -> f64 {
+        assert!(
+            when >= 0.,
+            "RangeError - Unable to play container at negative time {:?}",
+            when
+        );
+
+        let current_time = self.context.current_time();
+        self.sources.retain(|(_, end)| *end > current_time);
+
+        let seconds_per_beat = 60. / self.tempo;
+        let mut previous: Option<(f64, f64)> = None;
+        let mut end_time = when;
+
+        let context = self.context.clone();
+        context.batch_node_messages(|| {
+            let steps = self
+                .steps
+                .iter()
+                .cycle()
+                .take(self.steps.len() * self.loop_count);
+            for step in steps {
+                let start = match (previous, step.timing) {
+                    (None, _) => when,
+                    (Some((_, end)), StepTiming::AfterEnd(delay)) => end + delay,
+                    (Some((start, _)), StepTiming::Beats(beats)) => {
+                        start + beats * seconds_per_beat
+                    }
+                };
+                // the steps never start before the sequence
+                let start = start.max(when);
+                let end = start + step.buffer.duration();
+                previous = Some((start, end));
+                end_time = end_time.max(end);
+
+                let options = AudioBufferSourceOptions {
+                    buffer: Some(step.buffer.clone()),
+                    ..AudioBufferSourceOptions::default()
+                };
+                let mut source = AudioBufferSourceNode::new(&self.context, options);
+                source.connect(&self.output);
+                source.start_at(start);
+                self.sources.push((source, end));
+            }
+        });
+
+        end_time
+    }
+
+    /// Stop all the playbacks of the sequence
+    pub fn stop(&mut self) {
+        let now = self.context.current_time();
+        let context = self.context.clone();
+        context.batch_node_messages(|| {
+            self.sources
+                .drain(..)
+                .for_each(|(mut source, _)| source.stop_at(now));
+        });
+    }
+}
+
+/// Layer of a [`BlendContainer`]
+struct BlendLayer {
+    buffer: AudioBuffer,
+    curve: RtpcCurve,
+    /// applies the curve
+    level: GainNode,
+    source: Option<AudioBufferSourceNode>,
+}
+
+/// Loops multiple layers in sync and crossfades them along a control value
+///
+/// The gain of each layer is given by its curve at the value of the container, e.g. with
+/// overlapping triangles made with [`RtpcCurve::lookup_table`]. The value is set directly with
+/// [`set_value`](Self::set_value), or follows an RTPC of the context after
+/// [`bind_rtpc`](Self::bind_rtpc). The gains of all layers are updated in the same render
+/// quantum.
+///
+/// The layers are started in the same sample frame and loop over their whole buffer, they are
+/// played into the output bus, which is connected to the destination of the context.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use std::fs::File;
+/// use web_audio_api::container::BlendContainer;
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::rtpc::RtpcCurve;
+///
+/// let context = AudioContext::default();
+/// let decode = |path| context.decode_audio_data_sync(File::open(path).unwrap()).unwrap();
+///
+/// let mut engine = BlendContainer::new(&context);
+/// engine.add_layer(decode("idle.wav"), RtpcCurve::lookup_table(&[(0., 1.), (0.5, 0.)]));
+/// let cruise = RtpcCurve::lookup_table(&[(0., 0.), (0.5, 1.), (1., 0.)]);
+/// engine.add_layer(decode("cruise.wav"), cruise);
+/// engine.add_layer(decode("high.wav"), RtpcCurve::lookup_table(&[(0.5, 0.), (1., 1.)]));
+///
+/// engine.bind_rtpc("speed");
+/// engine.play();
+///
+/// // from the game loop
+/// context.set_rtpc("speed", 0.7);
+/// ```
+pub struct BlendContainer {
+    context: ConcreteBaseAudioContext,
+    output: GainNode,
+    layers: Vec<BlendLayer>,
+    value: f32,
+    rtpc: Option<String>,
+}
+
+impl std::fmt::Debug for BlendContainer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlendContainer")
+            .field("len", &self.layers.len())
+            .field("value", &self.value())
+            .field("rtpc", &self.rtpc)
+            .field("playing", &self.is_playing())
+            .finish_non_exhaustive()
+    }
+}
+
+impl BlendContainer {
+    /// Create a new container, with its output bus connected to the destination of the context
+    pub fn new<C: BaseAudioContext>(context: &C) -> Self {
+        let output = context.create_gain();
+        output.connect(&context.destination());
+
+        Self {
+            context: context.base().clone(),
+            output,
+            layers: vec![],
+            value: 0.,
+            rtpc: None,
+        }
+    }
+
+    /// The output bus, all layers are mixed into this node
+    pub fn output(&self) -> &GainNode {
+        &self.output
+    }
+
+    /// Add a layer, with the curve mapping the value of the container to its gain, and return
+    /// its index
+    ///
+    /// When the container is playing, all layers are restarted in sync.
+    pub fn add_layer(&mut self, buffer: AudioBuffer, curve: RtpcCurve) -> usize {
+        let level = self.context.create_gain();
+        level.gain().set_value(curve.value_at(self.value()));
+        level.connect(&self.output);
+        if let Some(name) = &self.rtpc {
+            self.context.bind_rtpc(name, level.gain(), curve.clone());
+        }
+
+        self.layers.push(BlendLayer {
+            buffer,
+            curve,
+            level,
+            source: None,
+        });
+
+        if self.is_playing() {
+            self.play();
+        }
+        self.layers.len() - 1
+    }
+
+    /// Number of layers
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Check if the container has no layers
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Current control value, the value of the bound RTPC if it has been set
+    pub fn value(&self) -> f32 {
+        self.rtpc
+            .as_deref()
+            .and_then(|name| self.context.rtpc(name))
+            .unwrap_or(self.value)
+    }
+
+    /// Set the control value, and the gains of the layers accordingly
+    ///
+    /// The container is unbound from its RTPC, if any.
+    pub fn set_value(&mut self, value: f32) {
+        self.unbind_rtpc();
+        self.value = value;
+        let gains: Vec<_> = self
+            .layers
+            .iter()
+            .map(|layer| (layer.level.gain(), layer.curve.value_at(value)))
+            .collect();
+        set_values_synchronized(&gains);
+    }
+
+    /// Let the control value follow an RTPC of the context
+    ///
+    /// The layers take the current value of the RTPC, if it has been set.
+    pub fn bind_rtpc(&mut self, name: &str) {
+        self.unbind_rtpc();
+        self.layers.iter().for_each(|layer| {
+            self.context
+                .bind_rtpc(name, layer.level.gain(), layer.curve.clone());
+        });
+        self.rtpc = Some(name.to_string());
+    }
+
+    /// Stop following the RTPC of the context, the layers keep their current gains
+    pub fn unbind_rtpc(&mut self) {
+        if let Some(name) = self.rtpc.take() {
+            self.value = self.context.rtpc(&name).unwrap_or(self.value);
+            self.layers
+                .iter()
+                .for_each(|layer| self.context.unbind_rtpc(&name, layer.level.gain()));
+        }
+    }
+
+    /// Name of the RTPC the control value follows, if any
+    pub fn rtpc(&self) -> Option<&str> {
+        self.rtpc.as_deref()
+    }
+
+    /// Check if the layers are playing
+    pub fn is_playing(&self) -> bool {
+        self.layers.iter().any(|layer| layer.source.is_some())
+    }
+
+    /// Start the layers now, from the start of their buffers
+    pub fn play(&mut self) {
+        self.play_at(self.context.current_time());
+    }
+
+    /// Start the layers at the given time of the context, from the start of their buffers
+    ///
+    /// The current playback, if any, is stopped at that time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `when` is negative
+    pub fn play_at(&mut self, when: f64) {
+        assert!(
+            when >= 0.,
+            "RangeError - Unable to play container at negative time {:?}",
+            when
+        );
+
+        let context = self.context.clone();
+        context.batch_node_messages(|| {
+            for layer in &mut self.layers {
+                if let Some(mut source) = layer.source.take() {
+                    source.stop_at(when);
+                }
+                let options = AudioBufferSourceOptions {
+                    buffer: Some(layer.buffer.clone()),
+                    loop_: true,
+                    ..AudioBufferSourceOptions::default()
+                };
+                let mut source = AudioBufferSourceNode::new(&context, options);
+                source.connect(&layer.level);
+                source.start_at(when);
+                layer.source = Some(source);
+            }
+        });
+    }
+
+    /// Stop the layers now
+    pub fn stop(&mut self) {
+        self.stop_at(self.context.current_time());
+    }
+
+    /// Stop the layers at the given time of the context
+    ///
+    /// # Panics
+    ///
+    /// Panics if `when` is negative
+    pub fn stop_at(&mut self, when: f64) {
+        assert!(
+            when >= 0.,
+            "RangeError - Unable to stop container at negative time {:?}",
+            when
+        );
+
+        let context = self.context.clone();
+        context.batch_node_messages(|| {
+            self.layers
+                .iter_mut()
+                .filter_map(|layer| layer.source.take())
+                .for_each(|mut source| source.stop_at(when));
+        });
+    }
+}
+
+fn assert_valid_weight(weight: f32) {
+    assert!(
+        weight.is_finite() && weight >= 0.,
+        "RangeError - Invalid variation weight {:?}, should be positive or zero",
+        weight
+    );
+}
+
+fn assert_valid_range(min: f32, max: f32) {
+    assert!(
+        min.is_finite() && max.is_finite() && min <= max,
+        "RangeError - Invalid range [{:?}, {:?}]",
+        min,
+        max
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::OfflineAudioContext;
+    use crate::RENDER_QUANTUM_SIZE;
+
+    use float_eq::assert_float_eq;
+
+    fn constant(value: f32) -> AudioBuffer {
+        AudioBuffer::from(vec![vec![value; RENDER_QUANTUM_SIZE]], 44_100.)
+    }
+
+    #[test]
+    fn test_weights() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let mut container = RandomContainer::new(&context);
+        assert_eq!(container.pick(), None);
+
+        container.set_seed(1);
+        container.add(constant(1.), 1.);
+        container.add(constant(2.), 0.);
+        container.add(constant(3.), 3.);
+
+        let mut counts = [0; 3];
+        (0..4000).for_each(|_| counts[container.pick().unwrap()] += 1);
+        assert_eq!(counts[1], 0);
+        assert!((2800..3200).contains(&counts[2]), "{:?}", counts);
+
+        container.set_weight(0, 0.);
+        container.set_weight(2, 0.);
+        assert_eq!(container.pick(), None);
+    }
+
+    #[test]
+    fn test_avoid_repeat() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let mut container = RandomContainer::new(&context);
+        container.set_seed(2);
+        (0..3).for_each(|_| {
+            container.add(constant(1.), 1.);
+        });
+
+        container.set_avoid_repeat(2);
+        let picks: Vec<_> = (0..30).map(|_| container.pick().unwrap()).collect();
+        picks.windows(3).for_each(|w| {
+            assert!(w[0] != w[1] && w[1] != w[2] && w[0] != w[2], "{:?}", picks);
+        });
+
+        // a single variation is always available
+        container.set_weight(1, 0.);
+        container.set_weight(2, 0.);
+        assert_eq!(container.pick(), Some(0));
+        assert_eq!(container.pick(), Some(0));
+    }
+
+    #[test]
+    fn test_play() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let mut container = RandomContainer::new(&context);
+        container.add(constant(2.), 1.);
+        container.set_gain_range(-6., -6.);
+        container.set_detune_range(0., 0.);
+        assert_eq!(container.play_at(64. / 44_100.), Some(0));
+
+        let output = context.start_rendering_sync();
+        let mut expected = vec![0.; 64];
+        expected.resize(RENDER_QUANTUM_SIZE, 2. * db_to_gain(-6.));
+        assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 1e-6);
+    }
+
+    #[test]
+    fn test_sequence() {
+        // power of two sample rate so the step times are exact
+        let sample_rate = 32_768.;
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, sample_rate);
+        let step = |value, length| AudioBuffer::from(vec![vec![value; length]], sample_rate);
+
+        let mut sequence = SequenceContainer::new(&context);
+        // one beat lasts 32 frames
+        sequence.set_tempo(60. * sample_rate as f64 / 32.);
+        sequence.push(step(1., 10), StepTiming::Beats(1.));
+        sequence.push(step(2., 10), StepTiming::AfterEnd(6. / sample_rate as f64));
+        sequence.set_loop_count(2);
+        let end = sequence.play_at(8. / sample_rate as f64);
+        assert_float_eq!(end, 82. / sample_rate as f64, abs <= 0.);
+
+        let output = context.start_rendering_sync();
+        let mut expected = vec![0.; RENDER_QUANTUM_SIZE];
+        expected[8..18].fill(1.);
+        expected[24..34].fill(2.);
+        // the first step follows the start of the last one in the next loop
+        expected[56..66].fill(1.);
+        expected[72..82].fill(2.);
+        assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_sequence_stop() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let mut sequence = SequenceContainer::new(&context);
+        sequence.push(constant(1.), StepTiming::default());
+        sequence.play();
+        sequence.stop();
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(
+            output.get_channel_data(0),
+            &[0.; RENDER_QUANTUM_SIZE][..],
+            abs_all <= 0.
+        );
+    }
+
+    fn crossfade() -> [RtpcCurve; 2] {
+        [
+            RtpcCurve::lookup_table(&[(0., 1.), (1., 0.)]),
+            RtpcCurve::lookup_table(&[(0., 0.), (1., 1.)]),
+        ]
+    }
+
+    #[test]
+    fn test_blend() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let [low, high] = crossfade();
+        let mut blend = BlendContainer::new(&context);
+        let ramp = |offset| {
+            let data = (0..32).map(|i| offset + i as f32).collect();
+            AudioBuffer::from(vec![data], 44_100.)
+        };
+        blend.add_layer(ramp(0.), low);
+        blend.add_layer(ramp(100.), high);
+        blend.set_value(0.25);
+        blend.play();
+
+        // both layers loop in sync
+        let output = context.start_rendering_sync();
+        let expected: Vec<f32> = (0..RENDER_QUANTUM_SIZE)
+            .map(|i| 0.75 * (i % 32) as f32 + 0.25 * (100. + (i % 32) as f32))
+            .collect();
+        assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 1e-4);
+    }
+
+    #[test]
+    fn test_blend_rtpc() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let [low, high] = crossfade();
+        let mut blend = BlendContainer::new(&context);
+        blend.bind_rtpc("intensity");
+        blend.add_layer(constant(1.), low);
+        blend.add_layer(constant(2.), high);
+        blend.play();
+        assert!(blend.is_playing());
+
+        context.set_rtpc("intensity", 1.);
+        assert_float_eq!(blend.value(), 1., abs <= 0.);
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(
+            output.get_channel_data(0),
+            &[2.; RENDER_QUANTUM_SIZE][..],
+            abs_all <= 0.
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_range() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let mut container = RandomContainer::new(&context);
+        container.set_gain_range(0., -6.);
+    }
+}
@@ -1,22 +1,35 @@
 //! The `BaseAudioContext` interface
 
+use crate::automation::AutomationTimeline;
 use crate::buffer::{AudioBuffer, AudioBufferOptions};
 use crate::context::{
     AudioContextRegistration, AudioContextState, AudioParamId, ConcreteBaseAudioContext,
-    DESTINATION_NODE_ID,
+    GraphMutations, DESTINATION_NODE_ID,
 };
 use crate::decoding::MediaDecoder;
+use crate::events::{Event, EventHandler, EventPayload, EventStream, EventType};
+use crate::memory::Allocation;
+use crate::message::ControlMessage;
+use crate::metadata::ContainerMetadata;
 use crate::node::{AudioNode, ChannelConfigOptions};
 use crate::param::AudioParamDescriptor;
 use crate::periodic_wave::{PeriodicWave, PeriodicWaveOptions};
 use crate::render::AudioProcessor;
-use crate::{node, AudioListener};
+use crate::rtpc::RtpcCurve;
+use crate::watchdog::{WatchdogConfig, WatchdogEvent, WatchdogOptions};
+use crate::{node, AudioListener, DecodedAudio, MemoryReport, QuotaExceededError, ResourceQuotas};
 
 /// The interface representing an audio-processing graph built from audio modules linked together,
 /// each represented by an `AudioNode`.
 ///
 /// An audio context controls both the creation of the nodes it contains and the execution of the
 /// audio processing, or decoding.
+///
+/// The methods to [`schedule`](Self::schedule) graph mutations, to fade the connections, to bind
+/// RTPCs, to record the automation and to manage the memory and the
+/// [resource quotas](Self::set_resource_quotas), as well as
+/// [`decode_audio_data_with_metadata_sync`](Self::decode_audio_data_with_metadata_sync), are an
+/// extension to the spec.
 #[allow(clippy::module_name_repetitions)]
 pub trait BaseAudioContext {
     /// Returns the [`BaseAudioContext`] concrete type associated with this `AudioContext`
@@ -53,7 +66,8 @@ pub trait BaseAudioContext {
     ///
     /// # Errors
     ///
-    /// This method returns an Error in various cases (IO, mime sniffing, decoding).
+    /// This method returns an Error in various cases (IO, mime sniffing, decoding), or a
+    /// [`QuotaExceededError`] when the decoded buffer exceeds the buffer memory quota.
     ///
     /// # Usage
     ///
@@ -82,27 +96,77 @@ pub trait BaseAudioContext {
         input: R,
     ) -> Result<AudioBuffer, Box<dyn std::error::Error + Send + Sync>> {
         // Set up a media decoder, consume the stream in full and construct a single buffer out of it
-        let mut buffer = MediaDecoder::try_new(input)?
-            .collect::<Result<Vec<_>, _>>()?
-            .into_iter()
-            .reduce(|mut accum, item| {
-                accum.extend(&item);
-                accum
-            })
-            // if there are no samples decoded, return an empty buffer
-            .unwrap_or_else(|| AudioBuffer::from(vec![vec![]], self.sample_rate()));
+        let mut buffer = MediaDecoder::decode_to_buffer(input, self.sample_rate())?;
 
         // resample to desired rate (no-op if already matching)
         buffer.resample(self.sample_rate());
 
+        self.base()
+            .check_quota(|memory| memory.check_buffer_memory(None, &buffer.allocations()))?;
+
         Ok(buffer)
     }
 
+    /// Decode an audio file along with the markers and metadata of its container
+    ///
+    /// This is [`decode_audio_data_sync`](Self::decode_audio_data_sync), which also reads the
+    /// cue points, loops and broadcast extension of WAV and AIFF files, see [`DecodedAudio`].
+    /// The positions of the markers are in sample frames of the decoded buffer. Other formats
+    /// have no markers.
+    ///
+    /// The whole encoded file is held in memory during decoding, as the markers may follow the
+    /// audio data.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an Error in various cases (IO, mime sniffing, decoding), or a
+    /// [`QuotaExceededError`] when the decoded buffer exceeds the buffer memory quota.
+    ///
+    /// # Usage
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use web_audio_api::context::{BaseAudioContext, OfflineAudioContext};
+    ///
+    /// let context = OfflineAudioContext::new(2, 44_100, 44_100.);
+    /// let file = File::open("samples/sample.wav").unwrap();
+    /// let decoded = context.decode_audio_data_with_metadata_sync(file).unwrap();
+    ///
+    /// for marker in &decoded.markers {
+    ///     println!("{:?} at {}", marker.label, marker.position);
+    /// }
+    /// ```
+    fn decode_audio_data_with_metadata_sync<R: std::io::Read + Send + Sync + 'static>(
+        &self,
+        mut input: R,
+    ) -> Result<DecodedAudio, Box<dyn std::error::Error + Send + Sync>> {
+        let mut bytes = vec![];
+        input.read_to_end(&mut bytes)?;
+        let metadata = ContainerMetadata::parse(&bytes);
+
+        let mut buffer =
+            MediaDecoder::decode_to_buffer(std::io::Cursor::new(bytes), self.sample_rate())?;
+        let file_sample_rate = buffer.sample_rate();
+
+        // resample to desired rate (no-op if already matching)
+        buffer.resample(self.sample_rate());
+
+        self.base()
+            .check_quota(|memory| memory.check_buffer_memory(None, &buffer.allocations()))?;
+
+        Ok(metadata.into_decoded_audio(buffer, file_sample_rate))
+    }
+
     /// Create an new "in-memory" `AudioBuffer` with the given number of channels,
     /// length (i.e. number of samples per channel) and sample rate.
     ///
     /// Note: In most cases you will want the sample rate to match the current
     /// audio context sample rate.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the buffer exceeds the buffer memory quota of the context, see
+    /// [`set_resource_quotas`](Self::set_resource_quotas)
     #[must_use]
     fn create_buffer(
         &self,
@@ -116,6 +180,10 @@ pub trait BaseAudioContext {
             sample_rate,
         };
 
+        let bytes = number_of_channels * length * std::mem::size_of::<f32>();
+        self.base()
+            .enforce_quota(|memory| memory.check_buffer_memory(None, &[Allocation::owned(bytes)]));
+
         AudioBuffer::new(options)
     }
 
@@ -272,6 +340,215 @@ pub trait BaseAudioContext {
         self.base().state()
     }
 
+    /// Register callback to run when the state of the context has changed
+    ///
+    /// Only a single event handler is active at any time. Calling this method multiple times will
+    /// override the previous event handler.
+    fn set_onstatechange<F: FnMut(Event) + Send + 'static>(&self, mut callback: F) {
+        let callback = move |_| {
+            callback(Event {
+                type_: "statechange",
+            })
+        };
+
+        self.base().set_event_handler(
+            EventType::StateChange,
+            EventHandler::Multiple(Box::new(callback)),
+        );
+    }
+
+    /// Unset the callback to run when the state of the context has changed
+    fn clear_onstatechange(&self) {
+        self.base().clear_event_handler(EventType::StateChange);
+    }
+
+    /// Stream of the events dispatched by the context, as an alternative to the callbacks
+    ///
+    /// The events are only dispatched by an [`AudioContext`](crate::context::AudioContext), the
+    /// stream of an `OfflineAudioContext` ends right away.
+    fn events(&self) -> EventStream {
+        self.base().subscribe_events()
+    }
+
+    /// Enable the render thread watchdog, which mutes the faulty signal paths
+    ///
+    /// The output of the watch points (the destination by default) is inspected every render
+    /// quantum. When it contains NaN or infinite sample values, or when it has been clipping for
+    /// longer than the configured duration, the faulty signal is traced back to the most upstream
+    /// node producing it. This node is muted and a [`WatchdogEvent`] naming it is dispatched, see
+    /// [`BaseAudioContext::set_onwatchdog`].
+    ///
+    /// Calling this method again replaces the previous options and unmutes all nodes.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the clipping threshold is not strictly positive or if the clipping duration
+    /// is negative
+    fn enable_watchdog(&self, options: WatchdogOptions) {
+        let config = WatchdogConfig::new(options, self.sample_rate());
+        let message = ControlMessage::SetWatchdog {
+            config: Some(config),
+        };
+        let _ = self.base().send_control_msg(message);
+    }
+
+    /// Disable the render thread watchdog and unmute all nodes muted by it
+    fn disable_watchdog(&self) {
+        let message = ControlMessage::SetWatchdog { config: None };
+        let _ = self.base().send_control_msg(message);
+    }
+
+    /// Schedule changes of the connections of the audio graph at the given context time
+    ///
+    /// The changes recorded by `f` are applied together by the render thread at the start of
+    /// the render quantum containing `when`, or at the next render quantum if `when` is in the
+    /// past. This allows to quantize effect switches musically instead of applying them whenever
+    /// the control message arrives.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `when` is negative or not finite, if `f` panics, or if more than 1024 mutations
+    /// would be waiting for their scheduled time
+    ///
+    /// # Usage
+    ///
+    /// ```no_run
+    /// use web_audio_api::context::{AudioContext, BaseAudioContext};
+    /// use web_audio_api::node::AudioNode;
+    ///
+    /// let context = AudioContext::default();
+    /// let dry = context.create_gain();
+    /// let reverb = context.create_convolver();
+    /// dry.connect(&context.destination());
+    ///
+    /// // switch to the reverb on the next bar
+    /// context.schedule(2., |graph| {
+    ///     graph.disconnect(&dry);
+    ///     graph.connect(&dry, &reverb);
+    ///     graph.connect(&reverb, &context.destination());
+    /// });
+    /// ```
+    fn schedule<F: FnOnce(&mut GraphMutations<'_>)>(&self, when: f64, f: F) {
+        assert!(
+            when >= 0. && when.is_finite(),
+            "RangeError - scheduled time ({:?}) should be positive and finite",
+            when
+        );
+
+        let mut mutations = GraphMutations::new(self.base());
+        f(&mut mutations);
+        if mutations.mutations.is_empty() {
+            return;
+        }
+
+        self.base().schedule_mutations(when, mutations.mutations);
+    }
+
+    /// Fade the connections in and out when the audio graph is changed, instead of an
+    /// instantaneous cut
+    ///
+    /// With a positive `duration` (in seconds), a new connection is faded in and a removed
+    /// connection is faded out over this duration, e.g. 0.005 for 5 ms. This applies to
+    /// [`AudioNode::connect`](crate::node::AudioNode::connect),
+    /// [`AudioNode::disconnect`](crate::node::AudioNode::disconnect) and the changes applied by
+    /// [`BaseAudioContext::schedule`]. The connections to an `AudioParam` are not faded.
+    ///
+    /// The default duration is 0, the changes are then applied instantaneously as mandated by
+    /// the specification. The fades are disabled in [strict conformance
+    /// mode](Self::set_strict_conformance).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `duration` is negative or not finite
+    fn set_connection_fade(&self, duration: f64) {
+        assert!(
+            duration >= 0. && duration.is_finite(),
+            "RangeError - connection fade duration ({:?}) should be positive and finite",
+            duration
+        );
+
+        let frames = if self.strict_conformance() {
+            0
+        } else {
+            (duration * self.sample_rate() as f64).round() as usize
+        };
+        let message = ControlMessage::SetConnectionFade { frames };
+        let _ = self.base().send_control_msg(message);
+    }
+
+    /// Register callback to run when the watchdog has muted a node
+    ///
+    /// Only a single event handler is active at any time. Calling this method multiple times will
+    /// override the previous event handler.
+    fn set_onwatchdog<F: FnMut(WatchdogEvent) + Send + 'static>(&self, mut callback: F) {
+        let callback = move |v| match v {
+            EventPayload::Watchdog(v) => callback(v),
+            _ => unreachable!(),
+        };
+
+        self.base().set_event_handler(
+            EventType::Watchdog,
+            EventHandler::Multiple(Box::new(callback)),
+        );
+    }
+
+    /// Unset the callback to run when the watchdog has muted a node
+    fn clear_onwatchdog(&self) {
+        self.base().clear_event_handler(EventType::Watchdog);
+    }
+
+    /// Bind an [`AudioParam`](crate::AudioParam) to the real-time parameter control (RTPC) of the
+    /// given name
+    ///
+    /// The param follows the value of the RTPC mapped through `curve`, and is updated right
+    /// away if the RTPC has already been set. Binding the param again to the same RTPC replaces
+    /// its curve. The binding does not keep the param alive.
+    fn bind_rtpc(&self, name: &str, param: &crate::AudioParam, curve: RtpcCurve) {
+        self.base().rtpcs().bind(name, param, curve);
+    }
+
+    /// Unbind an [`AudioParam`](crate::AudioParam) from the RTPC of the given name
+    fn unbind_rtpc(&self, name: &str, param: &crate::AudioParam) {
+        self.base().rtpcs().unbind(name, param);
+    }
+
+    /// Set the value of the RTPC of the given name, updating all the params bound to it within
+    /// the same render quantum
+    fn set_rtpc(&self, name: &str, value: f32) {
+        self.base().rtpcs().set(name, value);
+    }
+
+    /// Current value of the RTPC of the given name, `None` if it has never been set
+    #[must_use]
+    fn rtpc(&self, name: &str) -> Option<f32> {
+        self.base().rtpcs().value(name)
+    }
+
+    /// Start recording the values set on the given params into automation lanes of the given
+    /// names
+    ///
+    /// Each lane starts with the current value of its param, then every value set with
+    /// [`AudioParam::set_value`](crate::AudioParam::set_value) or through an RTPC is recorded
+    /// with the current time of the context. See [`automation`](crate::automation).
+    ///
+    /// # Panics
+    ///
+    /// Panics if a recording is already running, or if a lane name is empty, contains
+    /// whitespace, or is given twice
+    fn start_automation_recording(&self, params: &[(&str, &crate::AudioParam)]) {
+        let now = self.current_time();
+        self.base().automation().start(params, now);
+    }
+
+    /// Stop the running automation recording, and return the recorded lanes
+    ///
+    /// # Panics
+    ///
+    /// Panics if no recording is running
+    fn stop_automation_recording(&self) -> AutomationTimeline {
+        self.base().automation().stop()
+    }
+
     /// This is the time in seconds of the sample frame immediately following the last sample-frame
     /// in the block of audio most recently processed by the context’s rendering graph.
     #[must_use]
@@ -279,6 +556,91 @@ pub trait BaseAudioContext {
         self.base().current_time()
     }
 
+    /// Returns true if the context runs in strict spec-conformance mode
+    #[must_use]
+    fn strict_conformance(&self) -> bool {
+        self.base().strict_conformance()
+    }
+
+    /// Toggle strict spec-conformance mode (disabled by default)
+    ///
+    /// In strict mode, the extensions of this library that change the rendering of the nodes,
+    /// params and connections of the spec fall back to the behavior of the spec, for the nodes
+    /// created and the options set afterwards:
+    ///
+    /// - the [`PannerNode`](crate::node::PannerNode) ignores its equal-power elevation, near-field
+    ///   compensation, distance curve, Doppler effect, air absorption, occlusion and obstruction
+    /// - the `mix` param of the effect nodes is fixed to 1, i.e. fully wet
+    /// - the [loop
+    ///   crossfade](crate::node::AudioBufferSourceNode::set_loop_crossfade_duration) of the
+    ///   `AudioBufferSourceNode` is disabled
+    /// - the [connection fades](Self::set_connection_fade) are disabled
+    ///
+    /// [`AudioParam::bind_expression`](crate::AudioParam::bind_expression) and
+    /// [`OfflineAudioContext::capture_param`](crate::context::OfflineAudioContext::capture_param)
+    /// panic with a `NotSupportedError`. The extension nodes, and the extension methods that add
+    /// features without changing the rendering of the spec, e.g. scheduling graph mutations or
+    /// editing buffers, are not affected.
+    fn set_strict_conformance(&self, value: bool) {
+        self.base().set_strict_conformance(value);
+    }
+
+    /// Summary of the memory held by the audio graph
+    ///
+    /// The report accounts for the larger allocations of the nodes (audio buffers, convolver
+    /// partitions, HRIR data, delay lines) and counts the nodes of the graph, so long-running
+    /// applications can watch for leaks. Nodes dropped by the control thread are included until
+    /// the render thread has freed them.
+    #[must_use]
+    fn memory_report(&self) -> MemoryReport {
+        self.base().memory_report()
+    }
+
+    /// Current resource quotas of the context (all disabled by default)
+    #[must_use]
+    fn resource_quotas(&self) -> ResourceQuotas {
+        self.base().resource_quotas()
+    }
+
+    /// Set the resource quotas of the context
+    ///
+    /// The quotas are enforced when creating nodes, creating or decoding buffers, assigning
+    /// buffers to `AudioBufferSourceNode`s and enabling HRTF panning. Resources already held are
+    /// not released when lowering a quota.
+    fn set_resource_quotas(&self, quotas: ResourceQuotas) {
+        self.base().set_resource_quotas(quotas);
+    }
+
+    /// Run `f` to create nodes or buffers, returning an error instead of panicking when a
+    /// resource quota is exceeded
+    ///
+    /// The nodes created by `f` before the quota was exceeded are dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`QuotaExceededError`] of the first quota exceeded by `f`
+    ///
+    /// # Usage
+    ///
+    /// ```no_run
+    /// use web_audio_api::context::{BaseAudioContext, OfflineAudioContext};
+    /// use web_audio_api::ResourceQuotas;
+    ///
+    /// let context = OfflineAudioContext::new(2, 44_100, 44_100.);
+    /// context.set_resource_quotas(ResourceQuotas {
+    ///     max_nodes: Some(64),
+    ///     ..ResourceQuotas::default()
+    /// });
+    ///
+    /// match context.try_create(|context| context.create_gain()) {
+    ///     Ok(_gain) => (),
+    ///     Err(e) => eprintln!("refusing the graph description: {e}"),
+    /// }
+    /// ```
+    fn try_create<T, F: FnOnce(&Self) -> T>(&self, f: F) -> Result<T, QuotaExceededError> {
+        crate::memory::catch_quota_exceeded(|| f(self))
+    }
+
     /// Create an `AudioParam`.
     ///
     /// Call this inside the `register` closure when setting up your `AudioNode`
@@ -288,7 +650,7 @@ pub trait BaseAudioContext {
         opts: AudioParamDescriptor,
         dest: &AudioContextRegistration,
     ) -> (crate::param::AudioParam, AudioParamId) {
-        let param = self.register(move |registration| {
+        let param = self.base().register_audio_param(move |registration| {
             let (node, proc) = crate::param::audio_param_pair(opts, registration);
 
             (node, Box::new(proc))
@@ -1,20 +1,34 @@
 //! The `ConcreteBaseAudioContext` type
 
+use crate::automation::AutomationRecorder;
+use crate::capacity::{SchedulingCounters, SchedulingStats};
 use crate::context::{
     AudioContextRegistration, AudioContextState, AudioNodeId, BaseAudioContext,
     DESTINATION_NODE_ID, LISTENER_NODE_ID, LISTENER_PARAM_IDS,
 };
-use crate::events::{EventDispatch, EventHandler, EventLoop, EventType};
-use crate::message::ControlMessage;
+use crate::events::{EventDispatch, EventHandler, EventLoop, EventStream, EventType};
+use crate::lookback::LookbackBuffer;
+use crate::memory::{
+    raise_quota_exceeded, Allocation, MemoryKind, MemoryLedger, MemoryReport, QuotaExceededError,
+    ResourceQuotas,
+};
+use crate::message::{ControlMessage, NodeMessagePayload};
 use crate::node::{AudioDestinationNode, AudioNode, ChannelConfig, ChannelConfigOptions};
 use crate::param::AudioParam;
+use crate::render::graph::{GraphMutation, MAX_SCHEDULED_MUTATIONS};
 use crate::render::AudioProcessor;
+use crate::rtpc::RtpcRegistry;
 use crate::spatial::AudioListenerParams;
+use crate::sync::atomic::AtomicU64;
+use crate::sync::channel;
 
 use crate::AudioListener;
 
 use crossbeam_channel::{Receiver, SendError, Sender};
-use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use smallvec::SmallVec;
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, RwLock, RwLockWriteGuard};
 
 /// This struct assigns new [`AudioNodeId`]s for [`AudioNode`]s
@@ -26,6 +40,8 @@ struct AudioNodeIdProvider {
     id_inc: AtomicU64,
     /// receiver for decommissioned AudioNodeIds, which can be reused
     id_consumer: Mutex<llq::Consumer<AudioNodeId>>,
+    /// decommissioned AudioNodeIds already taken from the receiver, see `reclaim`
+    reclaimed: Mutex<Vec<AudioNodeId>>,
 }
 
 impl AudioNodeIdProvider {
@@ -33,16 +49,30 @@ impl AudioNodeIdProvider {
         Self {
             id_inc: AtomicU64::new(0),
             id_consumer: Mutex::new(id_consumer),
+            reclaimed: Mutex::new(Vec::new()),
         }
     }
 
     fn get(&self) -> AudioNodeId {
-        if let Some(available_id) = self.id_consumer.lock().unwrap().pop() {
+        if let Some(id) = self.reclaimed.lock().unwrap().pop() {
+            id
+        } else if let Some(available_id) = self.id_consumer.lock().unwrap().pop() {
             llq::Node::into_inner(available_id)
         } else {
             AudioNodeId(self.id_inc.fetch_add(1, Ordering::Relaxed))
         }
     }
+
+    /// Take all the ids decommissioned by the render thread so far, calling `f` for each of them
+    fn reclaim(&self, mut f: impl FnMut(AudioNodeId)) {
+        let mut id_consumer = self.id_consumer.lock().unwrap();
+        let mut reclaimed = self.reclaimed.lock().unwrap();
+        while let Some(available_id) = id_consumer.pop() {
+            let id = llq::Node::into_inner(available_id);
+            f(id);
+            reclaimed.push(id);
+        }
+    }
 }
 
 /// The struct that corresponds to the Javascript `BaseAudioContext` object.
@@ -80,7 +110,7 @@ struct ConcreteBaseAudioContextInner {
     /// destination node's current channel count
     destination_channel_config: ChannelConfig,
     /// message channel from control to render thread
-    render_channel: RwLock<Sender<ControlMessage>>,
+    render_channel: RwLock<channel::Sender<ControlMessage>>,
     /// control messages that cannot be sent immediately
     queued_messages: Mutex<Vec<ControlMessage>>,
     /// number of frames played
@@ -91,12 +121,28 @@ struct ConcreteBaseAudioContextInner {
     listener_params: Option<AudioListenerParams>,
     /// Denotes if this AudioContext is offline or not
     offline: bool,
+    /// Denotes if the non-standard extensions of this library are disabled
+    strict_conformance: AtomicBool,
     /// Describes the current state of the `ConcreteBaseAudioContext`
     state: AtomicU8,
     /// Stores the event handlers
     event_loop: EventLoop,
     /// Sender for events that will be handled by the EventLoop
     event_send: Option<Sender<EventDispatch>>,
+    /// Recordings of the output of the nodes with an enabled lookback
+    lookbacks: Mutex<HashMap<AudioNodeId, LookbackBuffer>>,
+    /// Node messages held back to be sent together, see `batch_node_messages`
+    node_message_batch: Mutex<Option<SmallVec<[NodeMessagePayload; 6]>>>,
+    /// Memory held by the nodes of the graph
+    memory: Mutex<MemoryLedger>,
+    /// Number of transports created, see `Transport`
+    transport_count: AtomicUsize,
+    /// Real-time parameter controls, with the params bound to them
+    rtpcs: Mutex<RtpcRegistry>,
+    /// Running recording of the automation of params
+    automation: Mutex<AutomationRecorder>,
+    /// Lookahead of the scheduled node messages, measured by the render thread
+    scheduling_counters: Arc<SchedulingCounters>,
 }
 
 impl BaseAudioContext for ConcreteBaseAudioContext {
@@ -110,9 +156,25 @@ impl BaseAudioContext for ConcreteBaseAudioContext {
     >(
         &self,
         f: F,
+    ) -> T {
+        self.enforce_quota(MemoryLedger::check_nodes);
+        self.register_node(f, false)
+    }
+}
+
+impl ConcreteBaseAudioContext {
+    /// Register the node (or `AudioParam`) and send its renderer to the audio graph
+    fn register_node<
+        T: AudioNode,
+        F: FnOnce(AudioContextRegistration) -> (T, Box<dyn AudioProcessor>),
+    >(
+        &self,
+        f: F,
+        is_param: bool,
     ) -> T {
         // create a unique id for this node
         let id = self.inner.audio_node_id_provider.get();
+        self.inner.memory.lock().unwrap().register(id, is_param);
         let registration = AudioContextRegistration {
             id,
             context: self.clone(),
@@ -143,6 +205,16 @@ impl BaseAudioContext for ConcreteBaseAudioContext {
 
         node
     }
+
+    /// Register an `AudioParam`, which does not count as a node for the quotas
+    pub(crate) fn register_audio_param<
+        F: FnOnce(AudioContextRegistration) -> (AudioParam, Box<dyn AudioProcessor>),
+    >(
+        &self,
+        f: F,
+    ) -> AudioParam {
+        self.register_node(f, true)
+    }
 }
 
 impl ConcreteBaseAudioContext {
@@ -151,7 +223,7 @@ impl ConcreteBaseAudioContext {
         sample_rate: f32,
         max_channel_count: usize,
         frames_played: Arc<AtomicU64>,
-        render_channel: Sender<ControlMessage>,
+        render_channel: channel::Sender<ControlMessage>,
         event_channel: Option<(Sender<EventDispatch>, Receiver<EventDispatch>)>,
         offline: bool,
         node_id_consumer: llq::Consumer<AudioNodeId>,
@@ -175,9 +247,17 @@ impl ConcreteBaseAudioContext {
             queued_audio_listener_msgs: Mutex::new(Vec::new()),
             listener_params: None,
             offline,
+            strict_conformance: AtomicBool::new(false),
             state: AtomicU8::new(AudioContextState::Suspended as u8),
             event_loop: event_loop.clone(),
             event_send,
+            lookbacks: Mutex::new(HashMap::new()),
+            node_message_batch: Mutex::new(None),
+            memory: Mutex::new(MemoryLedger::default()),
+            transport_count: AtomicUsize::new(0),
+            rtpcs: Mutex::new(RtpcRegistry::default()),
+            automation: Mutex::new(AutomationRecorder::default()),
+            scheduling_counters: Arc::new(SchedulingCounters::default()),
         };
         let base = Self {
             inner: Arc::new(base_inner),
@@ -240,10 +320,10 @@ impl ConcreteBaseAudioContext {
             LISTENER_PARAM_IDS.end,
         );
 
-        // For an online AudioContext, pre-create the HRTF-database for panner nodes
-        if !offline {
-            crate::node::load_hrtf_processor(sample_rate as u32);
-        }
+        let message = ControlMessage::SetSchedulingCounters {
+            counters: Arc::clone(&base.inner.scheduling_counters),
+        };
+        let _ = base.send_control_msg(message);
 
         // Boot the event loop thread that handles the events spawned by the render thread
         // (we don't do this for offline rendering because it makes little sense, the graph cannot
@@ -262,6 +342,69 @@ impl ConcreteBaseAudioContext {
         self.inner.render_channel.read().unwrap().send(msg)
     }
 
+    /// Real-time parameter controls of this context
+    pub(crate) fn rtpcs(&self) -> std::sync::MutexGuard<'_, RtpcRegistry> {
+        self.inner.rtpcs.lock().unwrap()
+    }
+
+    /// Running recording of the automation of params
+    pub(crate) fn automation(&self) -> std::sync::MutexGuard<'_, AutomationRecorder> {
+        self.inner.automation.lock().unwrap()
+    }
+
+    /// Capture a value set on a param, if the param is being recorded
+    pub(crate) fn record_automation(&self, param: &AudioParam, value: f32) {
+        let now = self.current_time();
+        self.automation().record(param, value, now);
+    }
+
+    /// Unique identifier of a new transport of the render graph
+    pub(crate) fn next_transport_id(&self) -> usize {
+        self.inner.transport_count.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Send a message to the audio processor of a node, or hold it back when batching
+    pub(crate) fn post_node_message(&self, id: AudioNodeId, msg: llq::Node<Box<dyn Any + Send>>) {
+        if let Some(batch) = self.inner.node_message_batch.lock().unwrap().as_mut() {
+            batch.push((id, msg));
+            return;
+        }
+
+        let message = ControlMessage::NodeMessage { id, msg };
+        let _ = self.send_control_msg(message);
+    }
+
+    /// Run `f` and send all node messages posted meanwhile in a single control message, so they
+    /// are handled by the render thread within the same render quantum
+    ///
+    /// Nested calls are part of the outer batch.
+    pub(crate) fn batch_node_messages<R>(&self, f: impl FnOnce() -> R) -> R {
+        // flush the batch when `f` panics
+        struct Flush<'a>(&'a ConcreteBaseAudioContext);
+
+        impl Drop for Flush<'_> {
+            fn drop(&mut self) {
+                let batch = self.0.inner.node_message_batch.lock().unwrap().take();
+                if let Some(messages) = batch.filter(|m| !m.is_empty()) {
+                    let message = ControlMessage::NodeMessages { messages };
+                    let _ = self.0.send_control_msg(message);
+                }
+            }
+        }
+
+        let mut batch = self.inner.node_message_batch.lock().unwrap();
+        if batch.is_some() {
+            drop(batch);
+            return f();
+        }
+        *batch = Some(SmallVec::new());
+        drop(batch);
+
+        let _flush = Flush(self);
+        f()
+    }
+
+    #[allow(clippy::result_large_err)] // underrun reports are sent without allocating
     pub(crate) fn send_event(&self, msg: EventDispatch) -> Result<(), SendError<EventDispatch>> {
         match self.inner.event_send.as_ref() {
             Some(s) => s.send(msg),
@@ -269,7 +412,9 @@ impl ConcreteBaseAudioContext {
         }
     }
 
-    pub(crate) fn lock_control_msg_sender(&self) -> RwLockWriteGuard<'_, Sender<ControlMessage>> {
+    pub(crate) fn lock_control_msg_sender(
+        &self,
+    ) -> RwLockWriteGuard<'_, channel::Sender<ControlMessage>> {
         self.inner.render_channel.write().unwrap()
     }
 
@@ -281,6 +426,7 @@ impl ConcreteBaseAudioContext {
             || LISTENER_PARAM_IDS.contains(&id.0);
 
         if !magic {
+            self.inner.lookbacks.lock().unwrap().remove(&id);
             let message = ControlMessage::FreeWhenFinished { id };
 
             // Sending the message will fail when the render thread has already shut down.
@@ -289,6 +435,105 @@ impl ConcreteBaseAudioContext {
         }
     }
 
+    /// Record the output of a node into the lookback buffer, or stop recording when `None`
+    pub(crate) fn set_lookback(&self, id: AudioNodeId, buffer: Option<LookbackBuffer>) {
+        let mut lookbacks = self.inner.lookbacks.lock().unwrap();
+        match buffer.as_ref() {
+            Some(buffer) => lookbacks.insert(id, buffer.clone()),
+            None => lookbacks.remove(&id),
+        };
+
+        let message = ControlMessage::SetLookback { id, buffer };
+        let _ = self.send_control_msg(message);
+    }
+
+    /// The lookback buffer of a node, `None` when not recording
+    pub(crate) fn lookback(&self, id: AudioNodeId) -> Option<LookbackBuffer> {
+        self.inner.lookbacks.lock().unwrap().get(&id).cloned()
+    }
+
+    /// Mark a registered node as an `AudioParam`, which is not counted in the memory report
+    /// Check a resource quota with the ledger of the nodes which have not been freed yet
+    pub(crate) fn check_quota(
+        &self,
+        check: impl FnOnce(&MemoryLedger) -> Result<(), QuotaExceededError>,
+    ) -> Result<(), QuotaExceededError> {
+        let mut memory = self.inner.memory.lock().unwrap();
+        self.inner
+            .audio_node_id_provider
+            .reclaim(|id| memory.remove(id));
+        check(&memory)
+    }
+
+    /// Enforce a resource quota, see `check_quota`
+    ///
+    /// # Panics
+    ///
+    /// Panics with the `QuotaExceededError` when the quota is exceeded
+    pub(crate) fn enforce_quota(
+        &self,
+        check: impl FnOnce(&MemoryLedger) -> Result<(), QuotaExceededError>,
+    ) {
+        // the ledger lock is released before raising the error
+        if let Err(error) = self.check_quota(check) {
+            raise_quota_exceeded(error);
+        }
+    }
+
+    pub(super) fn resource_quotas(&self) -> ResourceQuotas {
+        self.inner.memory.lock().unwrap().quotas()
+    }
+
+    pub(super) fn set_resource_quotas(&self, quotas: ResourceQuotas) {
+        self.inner.memory.lock().unwrap().set_quotas(quotas);
+    }
+
+    /// Replace the memory allocations of the given kind held by a node
+    pub(crate) fn set_node_memory(
+        &self,
+        id: AudioNodeId,
+        kind: MemoryKind,
+        allocations: Vec<Allocation>,
+    ) {
+        self.inner.memory.lock().unwrap().set(id, kind, allocations);
+    }
+
+    /// Timing of the scheduled node messages, see [`SchedulingStats`]
+    pub(crate) fn scheduling_stats(&self) -> SchedulingStats {
+        self.inner.scheduling_counters.snapshot()
+    }
+
+    pub(crate) fn reset_scheduling_stats(&self) {
+        self.inner.scheduling_counters.reset();
+    }
+
+    /// Send graph mutations to be applied at the given time
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than `MAX_SCHEDULED_MUTATIONS` mutations would be waiting for their time
+    pub(crate) fn schedule_mutations(&self, when: f64, mutations: Vec<GraphMutation>) {
+        let counters = &self.inner.scheduling_counters;
+        if !counters.reserve_mutations(mutations.len(), MAX_SCHEDULED_MUTATIONS) {
+            panic!(
+                "InvalidStateError - too many graph mutations are scheduled, at most {} can be pending",
+                MAX_SCHEDULED_MUTATIONS
+            );
+        }
+
+        let message = ControlMessage::ScheduleMutations { when, mutations };
+        let _ = self.send_control_msg(message);
+    }
+
+    /// Summary of the memory held by the nodes which have not been freed by the render thread yet
+    pub(crate) fn memory_report(&self) -> MemoryReport {
+        let mut memory = self.inner.memory.lock().unwrap();
+        self.inner
+            .audio_node_id_provider
+            .reclaim(|id| memory.remove(id));
+        memory.report()
+    }
+
     /// Inform render thread that this node can act as a cycle breaker
     #[doc(hidden)]
     pub fn mark_cycle_breaker(&self, reg: &AudioContextRegistration) {
@@ -337,7 +582,34 @@ impl ConcreteBaseAudioContext {
 
     /// Updates state of current context
     pub(super) fn set_state(&self, state: AudioContextState) {
-        self.inner.state.store(state as u8, Ordering::SeqCst);
+        let previous = self.inner.state.swap(state as u8, Ordering::SeqCst);
+        if previous != state as u8 {
+            let _ = self.send_event(EventDispatch::state_change(state));
+        }
+    }
+
+    /// Returns true if the context runs in strict conformance mode
+    #[must_use]
+    pub(super) fn strict_conformance(&self) -> bool {
+        self.inner.strict_conformance.load(Ordering::SeqCst)
+    }
+
+    /// Enable or disable strict spec-conformance mode
+    pub(super) fn set_strict_conformance(&self, value: bool) {
+        self.inner.strict_conformance.store(value, Ordering::SeqCst);
+    }
+
+    /// Guard for the library extensions that are disabled in strict conformance mode
+    ///
+    /// # Panics
+    ///
+    /// Panics if the context runs in strict conformance mode
+    pub(crate) fn assert_extension_allowed(&self, name: &str) {
+        assert!(
+            !self.strict_conformance(),
+            "NotSupportedError - {} is not available in strict conformance mode",
+            name
+        );
     }
 
     /// The sample rate (in sample-frames per second) at which the `AudioContext` handles audio.
@@ -453,6 +725,10 @@ impl ConcreteBaseAudioContext {
     pub(crate) fn clear_event_handler(&self, event: EventType) {
         self.inner.event_loop.clear_handler(event);
     }
+
+    pub(crate) fn subscribe_events(&self) -> EventStream {
+        self.inner.event_loop.subscribe()
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,138 @@
+//! The `DeviceMixer`, sharing an output stream between contexts
+use std::sync::{Arc, Mutex, Weak};
+
+use crate::context::{AudioContext, AudioContextOptions, BaseAudioContext};
+use crate::io::device_mixer::MIXER_SINK_PREFIX;
+use crate::node::AudioNode;
+use crate::param::AudioParam;
+
+/// Contexts of the active device mixers, by name
+static DEVICE_MIXERS: Mutex<Vec<(String, Weak<AudioContext>)>> = Mutex::new(Vec::new());
+
+/// Context of the device mixer with the given name
+pub(crate) fn find_device_mixer(name: &str) -> Option<Arc<AudioContext>> {
+    DEVICE_MIXERS
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(n, _)| n == name)
+        .and_then(|(_, context)| context.upgrade())
+}
+
+/// Single audio output stream shared by multiple [`AudioContext`]s
+///
+/// The mixer opens the audio output device once. The contexts created with the
+/// [`sink_id`](Self::sink_id) of the mixer, or moved to it with
+/// [`AudioContext::set_sink_id_sync`], do not open a stream of their own: their graphs are
+/// rendered in the callback of the shared stream and their outputs are summed, each with its own
+/// [`gain`](Self::gain). This allows to isolate independent graphs, e.g. one per document or
+/// plugin, while playing through a single device.
+///
+/// The contexts render at the sample rate of the mixer, and can be suspended, resumed and closed
+/// independently. Dropping or closing the mixer stops the rendering of all its contexts.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, AudioContextOptions, DeviceMixer};
+///
+/// let mixer = DeviceMixer::new("documents", AudioContextOptions::default());
+///
+/// let options = AudioContextOptions {
+///     sink_id: mixer.sink_id(),
+///     ..AudioContextOptions::default()
+/// };
+/// let first = AudioContext::new(options.clone());
+/// let second = AudioContext::new(options);
+///
+/// // play the second document at half volume
+/// mixer.gain(&second).set_value(0.5);
+/// ```
+pub struct DeviceMixer {
+    name: String,
+    context: Arc<AudioContext>,
+}
+
+impl std::fmt::Debug for DeviceMixer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeviceMixer")
+            .field("name", &self.name)
+            .field("sink_id", &self.context.sink_id())
+            .finish_non_exhaustive()
+    }
+}
+
+impl DeviceMixer {
+    /// Open the output stream of the mixer, configured by the options like an [`AudioContext`]
+    ///
+    /// # Panics
+    ///
+    /// Will panic if a device mixer with the same name is active
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn new(name: &str, options: AudioContextOptions) -> Self {
+        let context = Arc::new(AudioContext::new(options));
+
+        // the channels of the contexts are passed through to the device
+        let destination = context.destination();
+        destination.set_channel_count(destination.max_channel_count());
+
+        let mut mixers = DEVICE_MIXERS.lock().unwrap();
+        mixers.retain(|(_, context)| context.strong_count() > 0);
+        if mixers.iter().any(|(n, _)| n == name) {
+            panic!("InvalidStateError - a device mixer named {name:?} is already active");
+        }
+        mixers.push((name.to_owned(), Arc::downgrade(&context)));
+
+        Self {
+            name: name.to_owned(),
+            context,
+        }
+    }
+
+    /// Name of the mixer
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Sink id of the mixer, to route the output of an [`AudioContext`] to the mixer
+    pub fn sink_id(&self) -> String {
+        format!("{MIXER_SINK_PREFIX}{}", self.name)
+    }
+
+    /// Sample rate of the mixer, and of all its contexts
+    pub fn sample_rate(&self) -> f32 {
+        self.context.sample_rate()
+    }
+
+    /// Gain applied to the output of the context in the mix, 1 by default
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the context does not output to this mixer
+    pub fn gain(&self, context: &AudioContext) -> AudioParam {
+        match context.mixer_gain() {
+            Some(gain) if context.sink_id() == self.sink_id() => gain,
+            _ => panic!(
+                "InvalidStateError - the context does not output to the device mixer {:?}",
+                self.name
+            ),
+        }
+    }
+
+    /// Close the output stream, the contexts of the mixer stop rendering
+    pub fn close_sync(&self) {
+        self.context.close_sync();
+    }
+}
+
+impl Drop for DeviceMixer {
+    fn drop(&mut self) {
+        let context = Arc::downgrade(&self.context);
+        DEVICE_MIXERS
+            .lock()
+            .unwrap()
+            .retain(|(_, c)| !c.ptr_eq(&context) && c.strong_count() > 0);
+    }
+}
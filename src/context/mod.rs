@@ -1,18 +1,27 @@
 //! The `BaseAudioContext` interface and the `AudioContext` and `OfflineAudioContext` types
 use std::{any::Any, ops::Range};
 
+use crate::memory::{Allocation, MemoryKind};
+use crate::message::ScheduledMessage;
+
 mod base;
 pub use base::*;
 
 mod concrete_base;
 pub use concrete_base::*;
 
+mod device_mixer;
+pub use device_mixer::*;
+
 mod offline;
 pub use offline::*;
 
 mod online;
 pub use online::*;
 
+mod schedule;
+pub use schedule::*;
+
 // magic node values
 /// Destination node id is always at index 0
 const DESTINATION_NODE_ID: AudioNodeId = AudioNodeId(0);
@@ -52,6 +61,13 @@ impl From<&AudioParamId> for AudioNodeId {
     }
 }
 
+impl AudioParamId {
+    /// Identifier of the processor of the given param, to read its values from another node
+    pub(crate) fn of(param: &crate::param::AudioParam) -> Self {
+        Self(crate::node::AudioNode::registration(param).id().0)
+    }
+}
+
 /// Describes the current state of the `AudioContext`
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum AudioContextState {
@@ -95,22 +111,45 @@ impl AudioContextRegistration {
         self.id
     }
 
+    /// Identifier of the node, unique within its context
+    ///
+    /// Use this to match the node of a [`ContextEvent`](crate::ContextEvent).
+    #[must_use]
+    pub fn node_id(&self) -> u64 {
+        self.id.0
+    }
+
     /// Get the [`BaseAudioContext`] concrete type associated with this `AudioContext`
     #[must_use]
     pub(crate) fn context(&self) -> &ConcreteBaseAudioContext {
         &self.context
     }
 
+    /// Replace the memory allocations of the given kind held by this node, for the memory report
+    pub(crate) fn set_memory(&self, kind: MemoryKind, allocations: Vec<Allocation>) {
+        self.context.set_node_memory(self.id, kind, allocations);
+    }
+
     /// Send a message to the corresponding audio processor of this node
     ///
     /// The message will be handled by
     /// [`AudioProcessor::onmessage`](crate::render::AudioProcessor::onmessage).
     pub fn post_message<M: Any + Send + 'static>(&self, msg: M) {
-        let wrapped = crate::message::ControlMessage::NodeMessage {
-            id: self.id,
-            msg: llq::Node::new(Box::new(msg)),
+        self.context
+            .post_node_message(self.id, llq::Node::new(Box::new(msg)));
+    }
+
+    /// Send a message to the corresponding audio processor of this node, that takes effect at
+    /// the given time
+    ///
+    /// The processor receives the bare message, the time is only used for the
+    /// [`SchedulingStats`](crate::SchedulingStats).
+    pub(crate) fn post_scheduled_message<M: Any + Send + 'static>(&self, msg: M, when: f64) {
+        let msg = ScheduledMessage {
+            when,
+            msg: Box::new(msg),
         };
-        let _ = self.context.send_control_msg(wrapped);
+        self.post_message(msg);
     }
 }
 
@@ -1,11 +1,13 @@
 //! The `OfflineAudioContext` type
-use std::sync::atomic::AtomicU64;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use crate::assert_valid_sample_rate;
 use crate::buffer::AudioBuffer;
-use crate::context::{BaseAudioContext, ConcreteBaseAudioContext};
+use crate::context::{AudioNodeId, BaseAudioContext, ConcreteBaseAudioContext};
+use crate::node::AudioNode;
+use crate::param::AudioParam;
 use crate::render::RenderThread;
+use crate::sync::atomic::AtomicU64;
 
 /// The `OfflineAudioContext` doesn't render the audio to the device hardware; instead, it generates
 /// it, as fast as it can, and outputs the result to an `AudioBuffer`.
@@ -18,6 +20,25 @@ pub struct OfflineAudioContext {
     length: usize,
     /// the rendering 'thread', fully controlled by the offline context
     renderer: RenderThread,
+    /// AudioParams whose rendered values are recorded during rendering
+    param_captures: Vec<(AudioNodeId, Arc<Mutex<Vec<f32>>>)>,
+}
+
+/// Rendered values of an [`AudioParam`], recorded by [`OfflineAudioContext::capture_param`]
+#[derive(Clone, Debug)]
+pub struct AudioParamCapture {
+    values: Arc<Mutex<Vec<f32>>>,
+}
+
+impl AudioParamCapture {
+    /// The rendered values of the `AudioParam`, one value per sample-frame
+    ///
+    /// The result is empty until [`OfflineAudioContext::start_rendering_sync`] has returned.
+    #[must_use]
+    #[allow(clippy::missing_panics_doc)]
+    pub fn values(&self) -> Vec<f32> {
+        self.values.lock().unwrap().clone()
+    }
 }
 
 impl BaseAudioContext for OfflineAudioContext {
@@ -41,7 +62,7 @@ impl OfflineAudioContext {
 
         // communication channel to the render thread,
         // unbounded is fine because it does not need to be realtime safe
-        let (sender, receiver) = crossbeam_channel::unbounded();
+        let (sender, receiver) = crate::sync::channel::unbounded();
 
         let (node_id_producer, node_id_consumer) = llq::Queue::new().split();
         let graph = crate::render::graph::Graph::new(node_id_producer);
@@ -75,15 +96,41 @@ impl OfflineAudioContext {
             base,
             length,
             renderer,
+            param_captures: vec![],
         }
     }
 
+    /// Record the rendered values of the given `AudioParam` during rendering
+    ///
+    /// The values include the automation events, the connected inputs and the nominal range
+    /// clamping, i.e. the exact values the owning node observes while rendering. They are
+    /// available from the returned [`AudioParamCapture`] after rendering has finished.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `AudioParam` does not belong to this context, or when the context runs in
+    /// strict conformance mode
+    pub fn capture_param(&mut self, param: &AudioParam) -> AudioParamCapture {
+        self.base.assert_extension_allowed("capture_param");
+        assert!(
+            param.context() == self.base(),
+            "InvalidAccessError - AudioParam belongs to another context"
+        );
+
+        let values = Arc::new(Mutex::new(vec![]));
+        self.param_captures
+            .push((param.registration().id(), Arc::clone(&values)));
+
+        AudioParamCapture { values }
+    }
+
     /// Given the current connections and scheduled changes, starts rendering audio.
     ///
     /// This function will block the current thread and returns the rendered `AudioBuffer`
     /// synchronously. An async version is currently not implemented.
     pub fn start_rendering_sync(self) -> AudioBuffer {
-        self.renderer.render_audiobuffer_sync(self.length)
+        self.renderer
+            .render_audiobuffer_sync(self.length, self.param_captures)
     }
 
     /// get the length of rendering audio buffer
@@ -110,4 +157,44 @@ mod tests {
         assert_float_eq!(buffer.get_channel_data(0), &[0.; 555][..], abs_all <= 0.);
         assert_float_eq!(buffer.get_channel_data(1), &[0.; 555][..], abs_all <= 0.);
     }
+
+    #[test]
+    fn test_capture_param() {
+        let mut context = OfflineAudioContext::new(1, 300, 44_100.);
+        let gain = context.create_gain();
+        gain.gain().set_value(0.);
+        gain.gain().linear_ramp_to_value_at_time(1., 299. / 44_100.);
+
+        let capture = context.capture_param(gain.gain());
+        assert!(capture.values().is_empty());
+
+        let _ = context.start_rendering_sync();
+
+        let values = capture.values();
+        assert_eq!(values.len(), 300);
+        assert_float_eq!(values[0], 0., abs <= 1e-6);
+        assert_float_eq!(values[150], 150. / 299., abs <= 1e-6);
+        assert_float_eq!(values[299], 1., abs <= 1e-6);
+    }
+
+    #[test]
+    fn test_capture_param_constant() {
+        let mut context = OfflineAudioContext::new(1, 200, 44_100.);
+        let src = context.create_constant_source();
+        src.offset().set_value(0.5);
+
+        let capture = context.capture_param(src.offset());
+        let _ = context.start_rendering_sync();
+
+        assert_float_eq!(capture.values()[..], [0.5; 200][..], abs_all <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_capture_param_strict_conformance() {
+        let mut context = OfflineAudioContext::new(1, 128, 44_100.);
+        context.set_strict_conformance(true);
+        let gain = context.create_gain();
+        let _ = context.capture_param(gain.gain());
+    }
 }
@@ -1,5 +1,6 @@
 //! The `AudioContext` type and constructor options
 use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
 
 use crate::context::{AudioContextState, BaseAudioContext, ConcreteBaseAudioContext};
@@ -9,16 +10,22 @@ use crate::media_devices::{enumerate_devices_sync, MediaDeviceInfoKind};
 use crate::media_streams::{MediaStream, MediaStreamTrack};
 use crate::message::ControlMessage;
 use crate::node::{self, ChannelConfigOptions};
+use crate::param::AudioParam;
 use crate::render::graph::Graph;
 use crate::MediaElement;
 use crate::{AudioRenderCapacity, Event};
 
 /// Check if the provided sink_id is available for playback
 ///
-/// It should be "", "none" or a valid output `sinkId` returned from [`enumerate_devices_sync`]
+/// It should be "", "none", a file sink, a device mixer or a valid output `sinkId` returned from
+/// [`enumerate_devices_sync`]
 fn is_valid_sink_id(sink_id: &str) -> bool {
     if sink_id.is_empty() || sink_id == "none" {
         true
+    } else if let Some(name) = sink_id.strip_prefix(io::device_mixer::MIXER_SINK_PREFIX) {
+        super::find_device_mixer(name).is_some()
+    } else if sink_id.starts_with(io::file::FILE_SINK_PREFIX) {
+        io::file::FileSinkConfig::is_valid(sink_id)
     } else {
         enumerate_devices_sync()
             .into_iter()
@@ -67,6 +74,32 @@ impl Default for AudioContextRenderSizeCategory {
     }
 }
 
+/// Notification of the audio session of the operating system, see
+/// [`AudioContext::handle_session_event`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AudioSessionEvent {
+    /// Another application or the system has taken over the audio output, e.g. an incoming phone
+    /// call or the app moving to the background
+    InterruptionBegan,
+    /// The interruption has ended, `should_resume` is the hint of the system to resume playback
+    InterruptionEnded { should_resume: bool },
+    /// The audio output route has changed, e.g. headphones were plugged in or unplugged
+    RouteChanged,
+}
+
+/// Metadata of the audio output stream, shown by desktop sound mixers
+///
+/// The metadata is currently only honored by the `cubeb` backend, which names the PulseAudio or
+/// PipeWire client and stream accordingly.
+#[derive(Clone, Debug, Default)]
+pub struct AudioStreamMetadata {
+    /// Name of the application, defaults to the name chosen by the audio backend
+    pub application_name: Option<String>,
+    /// Name of the stream, e.g. the title of the media being played
+    pub media_name: Option<String>,
+}
+
 /// Specify the playback configuration for the [`AudioContext`] constructor.
 ///
 /// All fields are optional and will default to the value best suited for interactive playback on
@@ -89,16 +122,32 @@ pub struct AudioContextOptions {
     pub latency_hint: AudioContextLatencyCategory,
 
     /// Sample rate of the audio context and audio output hardware. Use `None` for a default value.
+    ///
+    /// If the audio output hardware does not support the requested sample rate, the rendered
+    /// audio is converted to the device sample rate, see [`AudioContext::device_sample_rate`].
     pub sample_rate: Option<f32>,
 
     /// The audio output device
     /// - use `""` for the default audio output device
     /// - use `"none"` to process the audio graph without playing through an audio output device.
+    ///   The graph is still rendered in real time, clocked by a timer, which is useful for tests
+    ///   and servers without a sound card.
+    /// - use `"file:<path>"` to render to a 32-bit float WAV file instead of an audio output
+    ///   device. The query parameters `speed=<factor>` (or `speed=max` to render as fast as
+    ///   possible), `channels=<count>` (2 by default) and `rate=<hertz>` (the sample rate of the
+    ///   file, the context sample rate by default) are supported, e.g.
+    ///   `"file:out.wav?speed=4&channels=1"`. The file is finalized when the context is closed.
+    /// - use the [`sink_id`](super::DeviceMixer::sink_id) of a
+    ///   [`DeviceMixer`](super::DeviceMixer) to share its output stream with other contexts. The
+    ///   context then renders at the sample rate of the mixer.
     /// - use `"sinkId"` to use the specified audio sink id, obtained with [`enumerate_devices_sync`]
     pub sink_id: String,
 
     /// Option to request a default, optimized or specific render quantum size. It is a hint that might not be honored.
     pub render_size_hint: AudioContextRenderSizeCategory,
+
+    /// Metadata of the output stream, used by desktop sound mixers
+    pub stream_metadata: AudioStreamMetadata,
 }
 
 /// This interface represents an audio graph whose `AudioDestinationNode` is routed to a real-time
@@ -114,6 +163,10 @@ pub struct AudioContext {
     render_capacity: AudioRenderCapacity,
     /// Initializer for the render thread (when restart is required)
     render_thread_init: RenderThreadInit,
+    /// Metadata of the output stream, reused when the sink changes
+    stream_metadata: AudioStreamMetadata,
+    /// Indicates if the context was suspended by an audio session interruption
+    interrupted: AtomicBool,
 }
 
 impl BaseAudioContext for AudioContext {
@@ -162,6 +215,7 @@ impl AudioContext {
             options.sink_id = String::from("");
         }
 
+        let stream_metadata = options.stream_metadata.clone();
         let (control_thread_init, render_thread_init) = io::thread_init();
         let backend = io::build_output(options, render_thread_init.clone());
 
@@ -198,6 +252,8 @@ impl AudioContext {
             backend_manager: Mutex::new(backend),
             render_capacity,
             render_thread_init,
+            stream_metadata,
+            interrupted: AtomicBool::new(false),
         }
     }
 
@@ -222,6 +278,34 @@ impl AudioContext {
         self.backend_manager.lock().unwrap().output_latency()
     }
 
+    /// Estimation in seconds of the round-trip latency of a live input, i.e. the interval between
+    /// the capture of a sample by the input device of the `media_stream` and its playback by the
+    /// audio output device
+    ///
+    /// The input buffering can be minimized with the
+    /// [`MediaTrackConstraints::monitoring`](crate::media_devices::MediaTrackConstraints::monitoring)
+    /// constraint.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `media_stream` does not contain any audio tracks.
+    #[must_use]
+    pub fn round_trip_latency(&self, media_stream: &MediaStream) -> f64 {
+        let input_latency = media_stream.get_tracks()[0].latency();
+        input_latency + self.base_latency() + self.output_latency()
+    }
+
+    /// Sample rate of the current audio output device
+    ///
+    /// This equals [`BaseAudioContext::sample_rate`] unless the device does not support the sample
+    /// rate of the context, in which case the rendered audio is resampled to this rate before
+    /// being shipped to the device.
+    #[must_use]
+    #[allow(clippy::missing_panics_doc)]
+    pub fn device_sample_rate(&self) -> f32 {
+        self.backend_manager.lock().unwrap().device_sample_rate()
+    }
+
     /// Identifier or the information of the current audio output device.
     ///
     /// The initial value is `""`, which means the default audio output device.
@@ -249,10 +333,53 @@ impl AudioContext {
             Err(format!("NotFoundError: invalid sinkId {sink_id}"))?;
         };
 
+        self.switch_sink(sink_id);
+
+        Ok(())
+    }
+
+    /// Map a notification of the audio session of the operating system to the context state
+    ///
+    /// This is the integration point for the lifecycle of mobile platforms, where the
+    /// application forwards the AVAudioSession interruption and route change notifications on
+    /// iOS, or the audio focus changes on Android:
+    ///
+    /// - an interruption suspends a running context, which is resumed when the interruption ends
+    ///   and the system hints to resume playback. A context suspended by the user is not resumed.
+    /// - a route change moves the output to the new default audio output device, if the context
+    ///   plays through the default device.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the audio device is not available when resuming or changing route
+    pub fn handle_session_event(&self, event: AudioSessionEvent) {
+        match event {
+            AudioSessionEvent::InterruptionBegan => {
+                if self.state() == AudioContextState::Running {
+                    self.suspend_sync();
+                    self.interrupted.store(true, Ordering::SeqCst);
+                }
+            }
+            AudioSessionEvent::InterruptionEnded { should_resume } => {
+                let interrupted = self.interrupted.swap(false, Ordering::SeqCst);
+                if interrupted && should_resume && self.state() == AudioContextState::Suspended {
+                    self.resume_sync();
+                }
+            }
+            AudioSessionEvent::RouteChanged => {
+                if self.sink_id().is_empty() {
+                    self.switch_sink(String::new());
+                }
+            }
+        }
+    }
+
+    /// Move the audio graph to a new backend for the given sink
+    fn switch_sink(&self, sink_id: String) {
         let mut backend_manager_guard = self.backend_manager.lock().unwrap();
         let original_state = self.state();
         if original_state == AudioContextState::Closed {
-            return Ok(());
+            return;
         }
 
         // Temporarily set the state to Suspended, resume after the new backend is up
@@ -292,6 +419,7 @@ impl AudioContext {
             latency_hint: AudioContextLatencyCategory::default(), // todo reuse existing setting
             sink_id,
             render_size_hint: AudioContextRenderSizeCategory::default(), // todo reuse existing setting
+            stream_metadata: self.stream_metadata.clone(),
         };
         *backend_manager_guard = io::build_output(options, self.render_thread_init.clone());
 
@@ -308,18 +436,22 @@ impl AudioContext {
             self.base().set_state(AudioContextState::Running);
         }
 
-        // flush the cached msgs
+        // flush the cached msgs, through the locked sender to prevent a deadlock
         pending_msgs
             .into_iter()
-            .for_each(|m| self.base().send_control_msg(m).unwrap());
+            .for_each(|m| ctrl_msg_send.send(m).unwrap());
 
         // explicitly release the lock to prevent concurrent render threads
         drop(backend_manager_guard);
 
         // trigger event when all the work is done
         let _ = self.base.send_event(EventDispatch::sink_change());
+    }
 
-        Ok(())
+    /// Gain of the context in the graph of its device mixer, if the context outputs to a
+    /// [`DeviceMixer`](super::DeviceMixer)
+    pub(crate) fn mixer_gain(&self) -> Option<AudioParam> {
+        self.backend_manager.lock().unwrap().mixer_gain()
     }
 
     /// Register callback to run when the audio sink has changed
@@ -360,6 +492,7 @@ impl AudioContext {
     /// * For a `BackendSpecificError`
     #[allow(clippy::missing_const_for_fn, clippy::unused_self)]
     pub fn suspend_sync(&self) {
+        self.interrupted.store(false, Ordering::SeqCst);
         if self.backend_manager.lock().unwrap().suspend() {
             self.base().set_state(AudioContextState::Suspended);
         }
@@ -379,6 +512,7 @@ impl AudioContext {
     /// * For a `BackendSpecificError`
     #[allow(clippy::missing_const_for_fn, clippy::unused_self)]
     pub fn resume_sync(&self) {
+        self.interrupted.store(false, Ordering::SeqCst);
         if self.backend_manager.lock().unwrap().resume() {
             self.base().set_state(AudioContextState::Running);
         }
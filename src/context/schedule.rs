@@ -0,0 +1,114 @@
+//! Time-scheduled mutations of the audio graph
+
+use crate::node::AudioNode;
+use crate::render::graph::GraphMutation;
+
+use super::ConcreteBaseAudioContext;
+
+/// Changes of the connections of the audio graph, applied at a scheduled time
+///
+/// The changes are recorded in the closure given to
+/// [`BaseAudioContext::schedule`](crate::context::BaseAudioContext::schedule), then applied all
+/// together by the render thread at the start of the render quantum containing the scheduled
+/// time. Nodes created in the closure are only connected to the graph at that time, so adding a
+/// node is scheduled by scheduling its connections.
+pub struct GraphMutations<'a> {
+    context: &'a ConcreteBaseAudioContext,
+    pub(super) mutations: Vec<GraphMutation>,
+}
+
+impl std::fmt::Debug for GraphMutations<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GraphMutations")
+            .field("mutations", &self.mutations.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> GraphMutations<'a> {
+    pub(super) fn new(context: &'a ConcreteBaseAudioContext) -> Self {
+        Self {
+            context,
+            mutations: vec![],
+        }
+    }
+
+    #[track_caller]
+    fn assert_same_context(&self, node: &dyn AudioNode) {
+        if self.context != node.context() {
+            panic!("InvalidAccessError: Attempting to schedule a change of a different context");
+        }
+    }
+
+    /// Connect the output of `from` to the input of `to`
+    ///
+    /// # Panics
+    ///
+    /// This function will panic when the nodes do not belong to the scheduling context
+    pub fn connect(&mut self, from: &dyn AudioNode, to: &dyn AudioNode) -> &mut Self {
+        self.connect_at(from, to, 0, 0)
+    }
+
+    /// Connect a specific output of `from` to a specific input of `to`
+    ///
+    /// # Panics
+    ///
+    /// This function will panic when
+    /// - the nodes do not belong to the scheduling context
+    /// - if the input port is out of bounds for the destination node
+    /// - if the output port is out of bounds for the source node
+    pub fn connect_at(
+        &mut self,
+        from: &dyn AudioNode,
+        to: &dyn AudioNode,
+        output: usize,
+        input: usize,
+    ) -> &mut Self {
+        self.assert_same_context(from);
+        self.assert_same_context(to);
+        if from.number_of_outputs() <= output {
+            panic!("IndexSizeError: output port {} is out of bounds", output);
+        }
+        if to.number_of_inputs() <= input {
+            panic!("IndexSizeError: input port {} is out of bounds", input);
+        }
+
+        self.mutations.push(GraphMutation::Connect {
+            from: from.output_registration().id(),
+            to: to.registration().id(),
+            output,
+            input,
+        });
+        self
+    }
+
+    /// Disconnect all outputs of `from` that go to `to`
+    ///
+    /// # Panics
+    ///
+    /// This function will panic when the nodes do not belong to the scheduling context
+    pub fn disconnect_from(&mut self, from: &dyn AudioNode, to: &dyn AudioNode) -> &mut Self {
+        self.assert_same_context(from);
+        self.assert_same_context(to);
+
+        self.mutations.push(GraphMutation::Disconnect {
+            from: from.output_registration().id(),
+            to: to.registration().id(),
+        });
+        self
+    }
+
+    /// Disconnect all outgoing connections of `from`
+    ///
+    /// # Panics
+    ///
+    /// This function will panic when the node does not belong to the scheduling context
+    pub fn disconnect(&mut self, from: &dyn AudioNode) -> &mut Self {
+        self.assert_same_context(from);
+
+        self.mutations.push(GraphMutation::DisconnectAll {
+            from: from.output_registration().id(),
+        });
+        self
+    }
+}
@@ -0,0 +1,56 @@
+//! Conversions between linear gain values and levels in decibels
+
+/// Convert a level in decibels to a linear gain value
+///
+/// `f32::NEG_INFINITY` converts to a gain of zero.
+///
+/// ```
+/// use web_audio_api::db_to_gain;
+///
+/// assert_eq!(db_to_gain(0.), 1.);
+/// assert!((db_to_gain(-20.) - 0.1).abs() < 1e-6);
+/// assert_eq!(db_to_gain(f32::NEG_INFINITY), 0.);
+/// ```
+#[must_use]
+pub fn db_to_gain(db: f32) -> f32 {
+    10_f32.powf(db / 20.)
+}
+
+/// Convert a linear gain value, or a sample value, to a level in decibels (dBFS for samples)
+///
+/// The sign of the value is ignored, zero converts to `f32::NEG_INFINITY`.
+///
+/// ```
+/// use web_audio_api::gain_to_db;
+///
+/// assert_eq!(gain_to_db(1.), 0.);
+/// assert!((gain_to_db(-0.1) + 20.).abs() < 1e-4);
+/// assert_eq!(gain_to_db(0.), f32::NEG_INFINITY);
+/// ```
+#[must_use]
+pub fn gain_to_db(gain: f32) -> f32 {
+    20. * gain.abs().log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        for db in [-96., -60., -6., 0., 6., 24.] {
+            assert_float_eq!(gain_to_db(db_to_gain(db)), db, abs <= 1e-4);
+        }
+        assert_float_eq!(db_to_gain(6.0206), 2., abs <= 1e-4);
+    }
+
+    #[test]
+    fn test_infinity() {
+        assert_eq!(db_to_gain(f32::NEG_INFINITY), 0.);
+        assert_eq!(gain_to_db(0.), f32::NEG_INFINITY);
+        assert_eq!(gain_to_db(-0.), f32::NEG_INFINITY);
+        assert!(gain_to_db(f32::NAN).is_nan());
+    }
+}
@@ -2,6 +2,7 @@ use std::error::Error;
 use std::io::{Read, Seek, SeekFrom};
 
 use crate::buffer::{AudioBuffer, ChannelData};
+use crate::MAX_CHANNELS;
 
 use symphonia::core::audio::AudioBufferRef;
 use symphonia::core::audio::Signal;
@@ -55,6 +56,8 @@ pub(crate) struct MediaDecoder {
     decoder: Box<dyn Decoder>,
     track_index: usize,
     packet_count: usize,
+    /// encoder delay and padding in frames, to be removed from the decoded stream
+    gapless_trim: Option<(usize, usize)>,
 }
 
 impl MediaDecoder {
@@ -65,6 +68,19 @@ impl MediaDecoder {
     /// This method returns an Error in various cases (IO, mime sniffing, decoding).
     pub fn try_new<R: std::io::Read + Send + Sync + 'static>(
         input: R,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Self::try_new_with_gapless(input, false)
+    }
+
+    /// Try to construct a new instance from a `Read` implementer, optionally removing the
+    /// encoder delay and padding of the stream
+    ///
+    /// # Errors
+    ///
+    /// This method returns an Error in various cases (IO, mime sniffing, decoding).
+    fn try_new_with_gapless<R: std::io::Read + Send + Sync + 'static>(
+        input: R,
+        gapless: bool,
     ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         // Symphonia lib needs a Box<dyn MediaSource> - use our own MediaInput
         let input = Box::new(MediaInput::new(input));
@@ -77,7 +93,10 @@ impl MediaDecoder {
         let hint = Hint::new();
 
         // TODO: Allow to customize some options.
-        let format_opts: FormatOptions = Default::default();
+        let format_opts = FormatOptions {
+            enable_gapless: gapless,
+            ..FormatOptions::default()
+        };
         let metadata_opts: MetadataOptions = Default::default();
         let decoder_opts = DecoderOptions {
             // Opt-in to verify the decoded data against the checksums in the container.
@@ -85,16 +104,28 @@ impl MediaDecoder {
         };
 
         // Probe the media source stream for a format.
-        let probed =
+        let mut probed =
             symphonia::default::get_probe().format(&hint, stream, &format_opts, &metadata_opts)?;
 
         // Get the format reader yielded by the probe operation.
-        let format = probed.format;
+        let mut format = probed.format;
 
         // Get the default track.
         let track = format.default_track().ok_or(SymphoniaError::Unsupported(
             "no default media track available",
         ))?;
+        // refuse tracks that cannot be rendered rather than truncating them
+        if let Some(channels) = track.codec_params.channels {
+            if channels.count() > MAX_CHANNELS {
+                return Err(format!(
+                    "NotSupportedError - media track has {} channels, more than MAX_CHANNELS ({})",
+                    channels.count(),
+                    MAX_CHANNELS
+                )
+                .into());
+            }
+        }
+
         let track_index = format
             .tracks()
             .iter()
@@ -103,15 +134,108 @@ impl MediaDecoder {
         // Create a (stateful) decoder for the track.
         let decoder = symphonia::default::get_codecs().make(&track.codec_params, &decoder_opts)?;
 
+        // The format reader trims the delay and padding it knows about (e.g. from the LAME header
+        // of MP3 files), otherwise look for the iTunes gapless tag (e.g. in M4A files)
+        let gapless_trim = if gapless && track.codec_params.delay.is_none() {
+            let probed_tags = probed
+                .metadata
+                .get()
+                .and_then(|metadata| metadata.current().map(|rev| rev.tags().to_vec()));
+            let format_tags = format.metadata().current().map(|rev| rev.tags().to_vec());
+            probed_tags
+                .into_iter()
+                .chain(format_tags)
+                .flatten()
+                .filter(|tag| tag.key.ends_with("iTunSMPB"))
+                .find_map(|tag| parse_itunes_gapless(&tag.value.to_string()))
+        } else {
+            None
+        };
+
         Ok(Self {
             format,
             decoder,
             track_index,
             packet_count: 0,
+            gapless_trim,
         })
     }
 }
 
+impl MediaDecoder {
+    /// Decode a stream in full into a single buffer, at the sample rate of the stream
+    ///
+    /// The buffer is empty, at the given sample rate, if the stream has no samples.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an Error in various cases (IO, mime sniffing, decoding).
+    pub fn decode_to_buffer<R: std::io::Read + Send + Sync + 'static>(
+        input: R,
+        empty_sample_rate: f32,
+    ) -> Result<AudioBuffer, Box<dyn std::error::Error + Send + Sync>> {
+        let buffer = Self::try_new(input)?
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .reduce(|mut accum, item| {
+                accum.extend(&item);
+                accum
+            })
+            // if there are no samples decoded, return an empty buffer
+            .unwrap_or_else(|| AudioBuffer::from(vec![vec![]], empty_sample_rate));
+
+        Ok(buffer)
+    }
+
+    /// Decode a stream in full into a single buffer without the encoder delay and padding, at
+    /// the sample rate of the stream
+    ///
+    /// The buffer is empty, at the given sample rate, if the stream has no samples.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an Error in various cases (IO, mime sniffing, decoding).
+    pub fn decode_gapless_to_buffer<R: std::io::Read + Send + Sync + 'static>(
+        input: R,
+        empty_sample_rate: f32,
+    ) -> Result<AudioBuffer, Box<dyn std::error::Error + Send + Sync>> {
+        let mut decoder = Self::try_new_with_gapless(input, true)?;
+        let gapless_trim = decoder.gapless_trim;
+        let buffer = decoder
+            .by_ref()
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .reduce(|mut accum, item| {
+                accum.extend(&item);
+                accum
+            })
+            .unwrap_or_else(|| AudioBuffer::from(vec![vec![]], empty_sample_rate));
+
+        let buffer = match gapless_trim {
+            Some((delay, padding)) => {
+                let start = delay.min(buffer.length());
+                let end = buffer.length().saturating_sub(padding).max(start);
+                buffer.slice(start..end)
+            }
+            None => buffer,
+        };
+
+        Ok(buffer)
+    }
+}
+
+/// Parse the delay and padding, in frames, of an `iTunSMPB` tag value
+///
+/// The value is a list of hexadecimal fields, the second and third being the delay and padding.
+fn parse_itunes_gapless(value: &str) -> Option<(usize, usize)> {
+    let mut fields = value
+        .split_whitespace()
+        .map(|field| usize::from_str_radix(field, 16).ok());
+    let delay = fields.nth(1)??;
+    let padding = fields.next()??;
+    Some((delay, padding))
+}
+
 impl Iterator for MediaDecoder {
     type Item = Result<AudioBuffer, Box<dyn Error + Send + Sync>>;
 
@@ -121,6 +245,7 @@ impl Iterator for MediaDecoder {
             decoder,
             track_index,
             packet_count,
+            ..
         } = self;
 
         // Get the track.
@@ -243,4 +368,13 @@ mod tests {
 
         assert!(media.is_err()); // the input was not a valid MIME type
     }
+
+    #[test]
+    fn test_parse_itunes_gapless() {
+        let value = " 00000000 00000840 000001CA 00000000003F31F6 00000000 00000000";
+        assert_eq!(parse_itunes_gapless(value), Some((2112, 458)));
+
+        assert_eq!(parse_itunes_gapless(" 00000000"), None);
+        assert_eq!(parse_itunes_gapless("invalid tag value"), None);
+    }
 }
@@ -0,0 +1,919 @@
+//! Offline signal processing utilities over [`AudioBuffer`]s
+//!
+//! The utilities follow the conventions of the [`AnalyserNode`](crate::node::AnalyserNode), and
+//! share the FFT plans of the crate.
+//!
+//! This is an extension to the spec.
+
+use std::f32::consts::PI;
+use std::ops::Index;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+
+use crate::analysis::generate_blackman;
+use crate::AudioBuffer;
+
+/// Planner shared by all the FFTs computed off the render thread, so plans of the same size are
+/// only computed once
+fn fft_planner() -> &'static Mutex<RealFftPlanner<f32>> {
+    static INSTANCE: OnceLock<Mutex<RealFftPlanner<f32>>> = OnceLock::new();
+    INSTANCE.get_or_init(|| Mutex::new(RealFftPlanner::new()))
+}
+
+/// Plan a forward FFT of the given length, with the planner shared by the crate
+pub(crate) fn plan_fft_forward(length: usize) -> Arc<dyn RealToComplex<f32>> {
+    fft_planner().lock().unwrap().plan_fft_forward(length)
+}
+
+/// Plan an inverse FFT of the given length, with the planner shared by the crate
+pub(crate) fn plan_fft_inverse(length: usize) -> Arc<dyn ComplexToReal<f32>> {
+    fft_planner().lock().unwrap().plan_fft_inverse(length)
+}
+
+/// Window function applied to each frame before the FFT
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Window {
+    /// No window
+    Rectangular,
+    Hann,
+    Hamming,
+    /// Blackman window with alpha = 0.16, as used by the `AnalyserNode`
+    #[default]
+    Blackman,
+}
+
+impl Window {
+    /// Values of the window for the given frame size
+    pub(crate) fn values(self, size: usize) -> Vec<f32> {
+        let cos = |i: usize| (2. * PI * i as f32 / size as f32).cos();
+        match self {
+            Self::Rectangular => vec![1.; size],
+            Self::Hann => (0..size).map(|i| 0.5 - 0.5 * cos(i)).collect(),
+            Self::Hamming => (0..size).map(|i| 0.54 - 0.46 * cos(i)).collect(),
+            Self::Blackman => generate_blackman(size).collect(),
+        }
+    }
+}
+
+/// Magnitudes of the short-time Fourier transform of a signal, see [`spectrogram`]
+///
+/// The magnitudes are stored frame by frame, and can be indexed with `[(frame, bin)]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spectrogram {
+    data: Vec<f32>,
+    number_of_bins: usize,
+    fft_size: usize,
+    hop: usize,
+    sample_rate: f32,
+}
+
+impl Spectrogram {
+    /// Number of frames and number of frequency bins
+    pub fn shape(&self) -> (usize, usize) {
+        (self.number_of_frames(), self.number_of_bins)
+    }
+
+    /// Number of frames, i.e. of spectra over time
+    pub fn number_of_frames(&self) -> usize {
+        self.data.len() / self.number_of_bins
+    }
+
+    /// Number of frequency bins of each frame, i.e. half the FFT size
+    pub fn number_of_bins(&self) -> usize {
+        self.number_of_bins
+    }
+
+    /// Magnitudes of the frequency bins of a frame
+    ///
+    /// # Panics
+    ///
+    /// Panics if the frame is out of bounds
+    pub fn frame(&self, frame: usize) -> &[f32] {
+        let start = frame * self.number_of_bins;
+        &self.data[start..start + self.number_of_bins]
+    }
+
+    /// Iterator over the frames
+    pub fn frames(&self) -> impl Iterator<Item = &[f32]> {
+        self.data.chunks_exact(self.number_of_bins)
+    }
+
+    /// Time in seconds of the start of a frame, relative to the start of the buffer
+    pub fn frame_time(&self, frame: usize) -> f64 {
+        (frame * self.hop) as f64 / self.sample_rate as f64
+    }
+
+    /// Center frequency in Hertz of a frequency bin
+    pub fn bin_frequency(&self, bin: usize) -> f32 {
+        bin as f32 * self.sample_rate / self.fft_size as f32
+    }
+
+    /// All the magnitudes, frame by frame
+    pub fn as_slice(&self) -> &[f32] {
+        &self.data
+    }
+
+    /// Take all the magnitudes, frame by frame
+    pub fn into_vec(self) -> Vec<f32> {
+        self.data
+    }
+}
+
+impl Index<(usize, usize)> for Spectrogram {
+    type Output = f32;
+
+    fn index(&self, (frame, bin): (usize, usize)) -> &f32 {
+        assert!(
+            bin < self.number_of_bins,
+            "IndexSizeError - bin out of bounds"
+        );
+        &self.data[frame * self.number_of_bins + bin]
+    }
+}
+
+/// Compute the spectrogram of a buffer
+///
+/// The channels of the buffer are averaged to a mono signal. Frame `i` covers the samples
+/// `[i * hop, i * hop + fft_size)` of the signal, the frames are computed until the end of the
+/// signal is covered and zero padded past it.
+///
+/// The magnitudes follow the conventions of the `AnalyserNode` (with a `smoothing_time_constant`
+/// of zero): they are normalized by the FFT size, and the `fft_size / 2` bins exclude the
+/// Nyquist frequency. Use [`gain_to_db`](crate::gain_to_db) to get the decibel values of
+/// [`AnalyserNode::get_float_frequency_data`](crate::node::AnalyserNode::get_float_frequency_data).
+///
+/// # Panics
+///
+/// Panics if `fft_size` is not an even number of at least two, or if `hop` is zero
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::dsp::{spectrogram, Window};
+/// use web_audio_api::AudioBuffer;
+///
+/// let buffer = AudioBuffer::from(vec![vec![0.; 44_100]], 44_100.);
+/// let spectrogram = spectrogram(&buffer, 2048, 512, Window::Hann);
+///
+/// let (frames, bins) = spectrogram.shape();
+/// for frame in 0..frames {
+///     let peak = (0..bins).max_by(|&a, &b| {
+///         spectrogram[(frame, a)].total_cmp(&spectrogram[(frame, b)])
+///     });
+///     println!("{:.2}s: {:?}", spectrogram.frame_time(frame), peak);
+/// }
+/// ```
+pub fn spectrogram(
+    buffer: &AudioBuffer,
+    fft_size: usize,
+    hop: usize,
+    window: Window,
+) -> Spectrogram {
+    assert!(
+        fft_size >= 2 && fft_size % 2 == 0,
+        "IndexSizeError - Invalid fft size: {fft_size:?} is not an even number of at least two"
+    );
+    assert!(hop > 0, "IndexSizeError - Invalid hop: cannot be zero");
+
+    // mono mixdown
+    let number_of_channels = buffer.number_of_channels();
+    let mut signal = vec![0.; buffer.length()];
+    (0..number_of_channels).for_each(|channel| {
+        signal
+            .iter_mut()
+            .zip(buffer.get_channel_data(channel))
+            .for_each(|(s, v)| *s += v / number_of_channels as f32);
+    });
+
+    let r2c = plan_fft_forward(fft_size);
+    let mut input = r2c.make_input_vec();
+    let mut output = r2c.make_output_vec();
+    let mut scratch = r2c.make_scratch_vec();
+    let window = window.values(fft_size);
+
+    let number_of_bins = fft_size / 2;
+    let number_of_frames = ((signal.len() + hop - 1) / hop).max(1);
+    let normalize_factor = 1. / fft_size as f32;
+    let mut data = Vec::with_capacity(number_of_frames * number_of_bins);
+
+    (0..number_of_frames).for_each(|frame| {
+        let start = (frame * hop).min(signal.len());
+        let end = (start + fft_size).min(signal.len());
+        input.fill(0.);
+        input[..end - start].copy_from_slice(&signal[start..end]);
+        input.iter_mut().zip(&window).for_each(|(i, w)| *i *= w);
+
+        r2c.process_with_scratch(&mut input, &mut output, &mut scratch)
+            .unwrap();
+
+        data.extend(
+            output[..number_of_bins]
+                .iter()
+                .map(|c| c.norm() * normalize_factor),
+        );
+    });
+
+    Spectrogram {
+        data,
+        number_of_bins,
+        fft_size,
+        hop,
+        sample_rate: buffer.sample_rate(),
+    }
+}
+
+/// Lowest frequency accounted for in the chroma vectors, in Hertz
+const MIN_CHROMA_FREQUENCY: f32 = 55.;
+
+/// Highest frequency accounted for in the chroma vectors, in Hertz
+const MAX_CHROMA_FREQUENCY: f32 = 5000.;
+
+/// Major key profile of Krumhansl and Kessler, starting from the tonic
+const MAJOR_PROFILE: [f32; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+
+/// Minor key profile of Krumhansl and Kessler, starting from the tonic
+const MINOR_PROFILE: [f32; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+
+/// Compute the chroma vectors of a buffer
+///
+/// Each frame of the [`spectrogram`] of the buffer (with a Hann window) is folded into the 12
+/// pitch classes of the equal tempered scale tuned to A4 = 440 Hz, starting from C: the energy
+/// of each frequency bin between 55 Hz and 5 kHz is added to the pitch class of its nearest
+/// semitone. The vectors are normalized so their highest pitch class is 1, silent frames are
+/// all zeros.
+///
+/// The FFT size should be large enough to resolve the semitones of the lowest notes, e.g. 8192
+/// at 44.1 kHz.
+///
+/// # Panics
+///
+/// Panics if `fft_size` is not an even number of at least two, or if `hop` is zero
+pub fn chromagram(buffer: &AudioBuffer, fft_size: usize, hop: usize) -> Vec<[f32; 12]> {
+    let spectrogram = spectrogram(buffer, fft_size, hop, Window::Hann);
+
+    // pitch class of each frequency bin in range
+    let pitch_classes: Vec<_> = (0..spectrogram.number_of_bins())
+        .map(|bin| {
+            let frequency = spectrogram.bin_frequency(bin);
+            (MIN_CHROMA_FREQUENCY..=MAX_CHROMA_FREQUENCY)
+                .contains(&frequency)
+                .then(|| {
+                    let midi = 69. + 12. * (frequency / 440.).log2();
+                    midi.round() as usize % 12
+                })
+        })
+        .collect();
+
+    spectrogram
+        .frames()
+        .map(|frame| {
+            let mut chroma = [0.; 12];
+            frame
+                .iter()
+                .zip(&pitch_classes)
+                .filter_map(|(m, pitch_class)| pitch_class.map(|p| (m, p)))
+                .for_each(|(m, pitch_class)| chroma[pitch_class] += m * m);
+
+            let max = chroma.iter().copied().fold(0., f32::max);
+            if max > 0. {
+                chroma.iter_mut().for_each(|c| *c /= max);
+            }
+            chroma
+        })
+        .collect()
+}
+
+/// Pitch class of the equal tempered scale
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PitchClass {
+    C,
+    CSharp,
+    D,
+    DSharp,
+    E,
+    F,
+    FSharp,
+    G,
+    GSharp,
+    A,
+    ASharp,
+    B,
+}
+
+impl PitchClass {
+    const ALL: [Self; 12] = [
+        Self::C,
+        Self::CSharp,
+        Self::D,
+        Self::DSharp,
+        Self::E,
+        Self::F,
+        Self::FSharp,
+        Self::G,
+        Self::GSharp,
+        Self::A,
+        Self::ASharp,
+        Self::B,
+    ];
+
+    /// Pitch class of the given index in the chroma vectors, 0 being C
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is not smaller than 12
+    pub fn from_index(index: usize) -> Self {
+        assert!(
+            index < 12,
+            "IndexSizeError - pitch class index ({index:?}) should be smaller than 12"
+        );
+        Self::ALL[index]
+    }
+
+    /// Index of the pitch class in the chroma vectors, 0 being C
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl std::fmt::Display for PitchClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = [
+            "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+        ][self.index()];
+        f.write_str(name)
+    }
+}
+
+/// Mode of a musical key
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Major,
+    Minor,
+}
+
+/// Musical key, as estimated by [`estimate_key`]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Key {
+    pub tonic: PitchClass,
+    pub mode: Mode,
+    /// Correlation between the chroma profile and the key profile, from -1 to 1
+    pub correlation: f32,
+}
+
+impl Key {
+    /// Key best matching a chroma profile, i.e. the energy of each pitch class starting from C
+    ///
+    /// The profile is correlated with the major and minor key profiles of Krumhansl and Kessler
+    /// in the 12 tonalities. Returns `None` if all the pitch classes have the same energy, e.g.
+    /// for silence.
+    pub fn from_chroma(chroma: &[f32; 12]) -> Option<Self> {
+        let candidates = [(Mode::Major, &MAJOR_PROFILE), (Mode::Minor, &MINOR_PROFILE)];
+        (0..12)
+            .flat_map(|tonic| candidates.map(|(mode, profile)| (tonic, mode, profile)))
+            .filter_map(|(tonic, mode, profile)| {
+                let rotated: [f32; 12] = std::array::from_fn(|i| profile[(i + 12 - tonic) % 12]);
+                correlation(chroma, &rotated).map(|correlation| Self {
+                    tonic: PitchClass::from_index(tonic),
+                    mode,
+                    correlation,
+                })
+            })
+            .max_by(|a, b| a.correlation.total_cmp(&b.correlation))
+    }
+}
+
+impl std::fmt::Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mode = match self.mode {
+            Mode::Major => "major",
+            Mode::Minor => "minor",
+        };
+        write!(f, "{} {}", self.tonic, mode)
+    }
+}
+
+/// Pearson correlation coefficient, `None` if a series is constant
+fn correlation(a: &[f32; 12], b: &[f32; 12]) -> Option<f32> {
+    let mean_a = a.iter().sum::<f32>() / 12.;
+    let mean_b = b.iter().sum::<f32>() / 12.;
+    let (mut covariance, mut variance_a, mut variance_b) = (0., 0., 0.);
+    a.iter().zip(b).for_each(|(a, b)| {
+        covariance += (a - mean_a) * (b - mean_b);
+        variance_a += (a - mean_a).powi(2);
+        variance_b += (b - mean_b).powi(2);
+    });
+    let norm = (variance_a * variance_b).sqrt();
+    (norm > 0.).then(|| covariance / norm)
+}
+
+/// Estimate the musical key of a buffer
+///
+/// The chroma vectors of the buffer (see [`chromagram`]) are averaged, and the key is the best
+/// match of the average profile (see [`Key::from_chroma`]). Returns `None` for silence.
+///
+/// # Usage
+///
+/// ```no_run
+/// use std::fs::File;
+/// use web_audio_api::context::{BaseAudioContext, OfflineAudioContext};
+/// use web_audio_api::dsp::estimate_key;
+///
+/// let context = OfflineAudioContext::new(2, 1, 44_100.);
+/// let file = File::open("samples/sample.wav").unwrap();
+/// let buffer = context.decode_audio_data_sync(file).unwrap();
+///
+/// if let Some(key) = estimate_key(&buffer) {
+///     println!("{key}"); // e.g. "F# minor"
+/// }
+/// ```
+pub fn estimate_key(buffer: &AudioBuffer) -> Option<Key> {
+    // frequency resolution of 5 Hz or finer, with frames overlapping by half
+    let fft_size = (buffer.sample_rate() / 5.).max(2.) as usize;
+    let fft_size = fft_size.next_power_of_two();
+    let chroma = chromagram(buffer, fft_size, fft_size / 2);
+
+    let mut profile = [0.; 12];
+    chroma.iter().for_each(|frame| {
+        profile.iter_mut().zip(frame).for_each(|(p, c)| *p += c);
+    });
+
+    Key::from_chroma(&profile)
+}
+
+/// Duration of the gating blocks of the loudness measurement, in seconds
+const LOUDNESS_BLOCK: f64 = 0.4;
+
+/// Absolute gate of the loudness measurement, in LUFS
+const LOUDNESS_ABSOLUTE_GATE: f64 = -70.;
+
+/// Relative gate of the loudness measurement, in LU below the loudness of the blocks above the
+/// absolute gate
+const LOUDNESS_RELATIVE_GATE: f64 = -10.;
+
+/// Biquad filter in direct form I, with normalized coefficients `[b0, b1, b2, a1, a2]`
+fn biquad(signal: &mut [f64], [b0, b1, b2, a1, a2]: [f64; 5]) {
+    let (mut x1, mut x2, mut y1, mut y2) = (0., 0., 0., 0.);
+    signal.iter_mut().for_each(|s| {
+        let x = *s;
+        let y = b0 * x + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+        (x2, x1, y2, y1) = (x1, x, y1, y);
+        *s = y;
+    });
+}
+
+/// Coefficients of the two stages of the K-weighting filter of ITU-R BS.1770 at the given
+/// sample rate: a high shelf modelling the head, and a high pass
+fn k_weighting(sample_rate: f64) -> [[f64; 5]; 2] {
+    let (frequency, gain, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (std::f64::consts::PI * frequency / sample_rate).tan();
+    let vh = 10_f64.powf(gain / 20.);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1. + k / q + k * k;
+    let shelf = [
+        (vh + vb * k / q + k * k) / a0,
+        2. * (k * k - vh) / a0,
+        (vh - vb * k / q + k * k) / a0,
+        2. * (k * k - 1.) / a0,
+        (1. - k / q + k * k) / a0,
+    ];
+
+    let (frequency, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (std::f64::consts::PI * frequency / sample_rate).tan();
+    let a0 = 1. + k / q + k * k;
+    let high_pass = [
+        1.,
+        -2.,
+        1.,
+        2. * (k * k - 1.) / a0,
+        (1. - k / q + k * k) / a0,
+    ];
+
+    [shelf, high_pass]
+}
+
+/// Integrated loudness of a buffer in LUFS, see [`AudioBuffer::measure_loudness`]
+pub(crate) fn integrated_loudness(buffer: &AudioBuffer) -> f32 {
+    let sample_rate = buffer.sample_rate() as f64;
+    let length = buffer.length();
+    let number_of_channels = buffer.number_of_channels();
+    let weights: Vec<f64> = match number_of_channels {
+        4 => vec![1., 1., 1.41, 1.41],
+        6 => vec![1., 1., 1., 0., 1.41, 1.41],
+        n => vec![1.; n],
+    };
+
+    let block = ((LOUDNESS_BLOCK * sample_rate).round() as usize).clamp(1, length.max(1));
+    let hop = (block / 4).max(1);
+    let number_of_blocks = if length < block {
+        1
+    } else {
+        (length - block) / hop + 1
+    };
+
+    // weighted sum of the mean squares of the channels, for each block
+    let mut powers = vec![0.; number_of_blocks];
+    let filters = k_weighting(sample_rate);
+    let mut signal = vec![0.; length];
+    (0..number_of_channels)
+        .filter(|&channel| weights[channel] > 0.)
+        .for_each(|channel| {
+            signal
+                .iter_mut()
+                .zip(buffer.get_channel_data(channel))
+                .for_each(|(s, &v)| *s = v as f64);
+            filters
+                .into_iter()
+                .for_each(|coefficients| biquad(&mut signal, coefficients));
+
+            powers.iter_mut().enumerate().for_each(|(i, power)| {
+                let start = i * hop;
+                let end = (start + block).min(length);
+                let sum = signal[start..end].iter().map(|s| s * s).sum::<f64>();
+                *power += weights[channel] * sum / block as f64;
+            });
+        });
+
+    let loudness = |power: f64| -0.691 + 10. * power.log10();
+    let gated_loudness = |gate: f64| {
+        let gated = powers.iter().filter(|&&power| loudness(power) > gate);
+        let (sum, count) = gated.fold((0., 0), |(sum, count), power| (sum + power, count + 1));
+        (count > 0).then(|| loudness(sum / count as f64))
+    };
+
+    gated_loudness(LOUDNESS_ABSOLUTE_GATE)
+        .and_then(|absolute| gated_loudness(absolute + LOUDNESS_RELATIVE_GATE))
+        .map_or(f32::NEG_INFINITY, |loudness| loudness as f32)
+}
+
+/// Minimum, maximum and RMS of the samples of a pixel of a [`PeakLevel`]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Peak {
+    pub min: f32,
+    pub max: f32,
+    pub rms: f32,
+}
+
+/// Peaks of a buffer at a given resolution, see [`AudioBuffer::build_peaks`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeakLevel {
+    samples_per_pixel: usize,
+    channels: Vec<Vec<Peak>>,
+}
+
+impl PeakLevel {
+    /// Number of sample frames summarized by each pixel
+    pub fn samples_per_pixel(&self) -> usize {
+        self.samples_per_pixel
+    }
+
+    /// Number of pixels of each channel, the last pixel may summarize fewer sample frames
+    pub fn number_of_pixels(&self) -> usize {
+        self.channels[0].len()
+    }
+
+    /// Number of channels
+    pub fn number_of_channels(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// Peaks of the pixels of a channel
+    ///
+    /// # Panics
+    ///
+    /// Panics if the channel number is out of bounds
+    pub fn channel(&self, channel_number: usize) -> &[Peak] {
+        &self.channels[channel_number]
+    }
+}
+
+/// Peaks of a buffer at multiple resolutions, see [`AudioBuffer::build_peaks`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeakPyramid {
+    levels: Vec<PeakLevel>,
+}
+
+impl PeakPyramid {
+    /// The levels, from the finest to the coarsest resolution
+    pub fn levels(&self) -> &[PeakLevel] {
+        &self.levels
+    }
+
+    /// Coarsest level at least as fine as the given resolution, or the finest level
+    ///
+    /// The level is the one to draw when zoomed at `samples_per_pixel`.
+    pub fn level_for(&self, samples_per_pixel: usize) -> &PeakLevel {
+        self.levels
+            .iter()
+            .rev()
+            .find(|level| level.samples_per_pixel <= samples_per_pixel)
+            .unwrap_or(&self.levels[0])
+    }
+}
+
+/// Peaks of a level being built, with the sums of squares instead of the RMS
+struct PartialLevel {
+    samples_per_pixel: usize,
+    /// min, max and sum of squares of the pixels of each channel
+    channels: Vec<Vec<(f32, f32, f32)>>,
+}
+
+/// Minimum, maximum and sum of squares of samples
+///
+/// The samples are processed in lanes, so the loop is vectorized by the compiler.
+fn summarize(samples: &[f32]) -> (f32, f32, f32) {
+    const LANES: usize = 8;
+    let mut min = [f32::INFINITY; LANES];
+    let mut max = [f32::NEG_INFINITY; LANES];
+    let mut sum_squares = [0.; LANES];
+
+    let chunks = samples.chunks_exact(LANES);
+    let remainder = chunks.remainder();
+    chunks.for_each(|chunk| {
+        for lane in 0..LANES {
+            min[lane] = min[lane].min(chunk[lane]);
+            max[lane] = max[lane].max(chunk[lane]);
+            sum_squares[lane] += chunk[lane] * chunk[lane];
+        }
+    });
+    remainder.iter().enumerate().for_each(|(lane, &s)| {
+        min[lane] = min[lane].min(s);
+        max[lane] = max[lane].max(s);
+        sum_squares[lane] += s * s;
+    });
+
+    (
+        min.into_iter().fold(f32::INFINITY, f32::min),
+        max.into_iter().fold(f32::NEG_INFINITY, f32::max),
+        sum_squares.into_iter().sum(),
+    )
+}
+
+/// Build the peak pyramid of a buffer, see [`AudioBuffer::build_peaks`]
+pub(crate) fn build_peaks(buffer: &AudioBuffer, samples_per_pixel_levels: &[usize]) -> PeakPyramid {
+    assert!(
+        !samples_per_pixel_levels.is_empty(),
+        "IndexSizeError - at least one level is required"
+    );
+    assert!(
+        samples_per_pixel_levels.iter().all(|&s| s > 0),
+        "IndexSizeError - samples per pixel cannot be zero"
+    );
+
+    let mut resolutions = samples_per_pixel_levels.to_vec();
+    resolutions.sort_unstable();
+    resolutions.dedup();
+
+    let length = buffer.length();
+    let number_of_pixels =
+        |samples_per_pixel: usize| (length + samples_per_pixel - 1) / samples_per_pixel;
+
+    let mut partials: Vec<PartialLevel> = Vec::with_capacity(resolutions.len());
+    resolutions.into_iter().for_each(|samples_per_pixel| {
+        // coarser levels are merged from the finest level they are a multiple of
+        let finer = partials
+            .iter()
+            .rev()
+            .find(|level| samples_per_pixel % level.samples_per_pixel == 0);
+
+        let channels = match finer {
+            Some(finer) => {
+                let ratio = samples_per_pixel / finer.samples_per_pixel;
+                finer
+                    .channels
+                    .iter()
+                    .map(|pixels| {
+                        pixels
+                            .chunks(ratio)
+                            .map(|chunk| {
+                                chunk.iter().fold(
+                                    (f32::INFINITY, f32::NEG_INFINITY, 0.),
+                                    |(min, max, sum), &(a, b, c)| (min.min(a), max.max(b), sum + c),
+                                )
+                            })
+                            .collect()
+                    })
+                    .collect()
+            }
+            None => (0..buffer.number_of_channels())
+                .map(|channel| {
+                    buffer
+                        .get_channel_data(channel)
+                        .chunks(samples_per_pixel)
+                        .map(summarize)
+                        .collect()
+                })
+                .collect(),
+        };
+
+        partials.push(PartialLevel {
+            samples_per_pixel,
+            channels,
+        });
+    });
+
+    let levels = partials
+        .into_iter()
+        .map(|partial| {
+            let samples_per_pixel = partial.samples_per_pixel;
+            let pixels = number_of_pixels(samples_per_pixel);
+            let channels = partial
+                .channels
+                .into_iter()
+                .map(|channel| {
+                    channel
+                        .into_iter()
+                        .enumerate()
+                        .map(|(pixel, (min, max, sum_squares))| {
+                            let count = if pixel + 1 == pixels {
+                                length - pixel * samples_per_pixel
+                            } else {
+                                samples_per_pixel
+                            };
+                            Peak {
+                                min,
+                                max,
+                                rms: (sum_squares / count as f32).sqrt(),
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+
+            PeakLevel {
+                samples_per_pixel,
+                channels,
+            }
+        })
+        .collect();
+
+    PeakPyramid { levels }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_shape() {
+        let buffer = AudioBuffer::from(vec![vec![0.; 1000]], 8000.);
+        let spec = spectrogram(&buffer, 256, 100, Window::default());
+        assert_eq!(spec.shape(), (10, 128));
+        assert_eq!(spec.frames().count(), 10);
+        assert_float_eq!(spec.frame_time(2), 0.025, abs <= 1e-9);
+        assert_float_eq!(spec.bin_frequency(4), 125., abs <= 0.);
+
+        // an empty buffer has a single silent frame
+        let buffer = AudioBuffer::from(vec![vec![]], 8000.);
+        let spec = spectrogram(&buffer, 256, 100, Window::default());
+        assert_eq!(spec.shape(), (1, 128));
+        assert_float_eq!(spec.frame(0), &[0.; 128][..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_sine_peak() {
+        let sample_rate = 8000.;
+        let fft_size = 512;
+        // centered on bin 32
+        let frequency = 32. * sample_rate / fft_size as f32;
+        let sine: Vec<f32> = (0..4096)
+            .map(|i| (2. * PI * frequency * i as f32 / sample_rate).sin())
+            .collect();
+        // the channels are averaged
+        let buffer = AudioBuffer::from(vec![sine.clone(), sine], sample_rate);
+
+        for window in [
+            Window::Rectangular,
+            Window::Hann,
+            Window::Hamming,
+            Window::Blackman,
+        ] {
+            let spec = spectrogram(&buffer, fft_size, fft_size, window);
+            spec.frames().for_each(|frame| {
+                let peak = (0..frame.len())
+                    .max_by(|&a, &b| frame[a].total_cmp(&frame[b]))
+                    .unwrap();
+                assert_eq!(peak, 32);
+            });
+        }
+
+        // a full scale sine has a magnitude of 1/2 without a window
+        let spec = spectrogram(&buffer, fft_size, fft_size, Window::Rectangular);
+        assert_float_eq!(spec[(0, 32)], 0.5, abs <= 1e-4);
+        assert_float_eq!(spec[(0, 10)], 0., abs <= 1e-4);
+    }
+
+    /// Mix of sines at the given MIDI notes
+    fn chord(notes: &[u8], sample_rate: f32) -> AudioBuffer {
+        let signal = (0..sample_rate as usize)
+            .map(|i| {
+                notes
+                    .iter()
+                    .map(|&note| {
+                        let frequency = 440. * 2_f32.powf((note as f32 - 69.) / 12.);
+                        (2. * PI * frequency * i as f32 / sample_rate).sin() / notes.len() as f32
+                    })
+                    .sum()
+            })
+            .collect();
+        AudioBuffer::from(vec![signal], sample_rate)
+    }
+
+    #[test]
+    fn test_chromagram() {
+        // A4
+        let buffer = chord(&[69], 22050.);
+        let chroma = chromagram(&buffer, 4096, 2048);
+        assert_eq!(chroma.len(), 11);
+        chroma[..chroma.len() - 1].iter().for_each(|frame| {
+            assert_float_eq!(frame[9], 1., abs <= 0.);
+            assert!(frame.iter().enumerate().all(|(i, &c)| i == 9 || c < 0.1));
+        });
+
+        let buffer = AudioBuffer::from(vec![vec![0.; 10_000]], 22050.);
+        chromagram(&buffer, 4096, 2048)
+            .iter()
+            .for_each(|frame| assert_float_eq!(frame, &[0.; 12], abs_all <= 0.));
+    }
+
+    #[test]
+    fn test_estimate_key() {
+        // C4 E4 G4 C5
+        let key = estimate_key(&chord(&[60, 64, 67, 72], 22050.)).unwrap();
+        assert_eq!((key.tonic, key.mode), (PitchClass::C, Mode::Major));
+        assert!(key.correlation > 0.5);
+        assert_eq!(key.to_string(), "C major");
+
+        // A3 C4 E4
+        let key = estimate_key(&chord(&[57, 60, 64], 22050.)).unwrap();
+        assert_eq!(key.to_string(), "A minor");
+
+        // F#3 A#3 C#4
+        let key = estimate_key(&chord(&[54, 58, 61], 22050.)).unwrap();
+        assert_eq!(key.to_string(), "F# major");
+
+        let silence = AudioBuffer::from(vec![vec![0.; 10_000]], 22050.);
+        assert!(estimate_key(&silence).is_none());
+    }
+
+    #[test]
+    fn test_build_peaks() {
+        let left: Vec<f32> = (0..1001)
+            .map(|i| ((i * 37) % 101) as f32 / 50. - 1.)
+            .collect();
+        let right: Vec<f32> = left.iter().map(|v| -v / 2.).collect();
+        let buffer = AudioBuffer::from(vec![left, right], 8000.);
+
+        let peaks = buffer.build_peaks(&[64, 16, 3, 16]);
+        let levels = peaks.levels();
+        assert_eq!(levels.len(), 3);
+
+        // levels built from the samples (3, 16) and merged from a finer level (64)
+        [3, 16, 64]
+            .iter()
+            .zip(levels)
+            .for_each(|(&samples_per_pixel, level)| {
+                assert_eq!(level.samples_per_pixel(), samples_per_pixel);
+                assert_eq!(level.number_of_channels(), 2);
+                assert_eq!(
+                    level.number_of_pixels(),
+                    (1001 + samples_per_pixel - 1) / samples_per_pixel
+                );
+
+                (0..2).for_each(|channel| {
+                    let data = buffer.get_channel_data(channel).chunks(samples_per_pixel);
+                    level
+                        .channel(channel)
+                        .iter()
+                        .zip(data)
+                        .for_each(|(peak, chunk)| {
+                            let min = chunk.iter().copied().fold(f32::INFINITY, f32::min);
+                            let max = chunk.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+                            let rms = (chunk.iter().map(|v| v * v).sum::<f32>()
+                                / chunk.len() as f32)
+                                .sqrt();
+                            assert_float_eq!(peak.min, min, abs <= 0.);
+                            assert_float_eq!(peak.max, max, abs <= 0.);
+                            assert_float_eq!(peak.rms, rms, abs <= 1e-5);
+                        });
+                });
+            });
+
+        assert_eq!(peaks.level_for(1).samples_per_pixel(), 3);
+        assert_eq!(peaks.level_for(20).samples_per_pixel(), 16);
+        assert_eq!(peaks.level_for(1000).samples_per_pixel(), 64);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_hop() {
+        let buffer = AudioBuffer::from(vec![vec![0.; 100]], 8000.);
+        let _ = spectrogram(&buffer, 256, 0, Window::default());
+    }
+}
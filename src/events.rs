@@ -1,10 +1,15 @@
-use crate::context::AudioNodeId;
-use crate::AudioRenderCapacityEvent;
+use crate::capacity::UnderrunReport;
+use crate::context::{AudioContextState, AudioNodeId};
+use crate::node::OnsetEvent;
+use crate::{AudioRenderCapacityEvent, AudioUnderrunEvent, WatchdogEvent};
 
 use std::any::Any;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
 use std::hash::Hash;
-use std::sync::{Arc, Mutex};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll, Waker};
 
 use crossbeam_channel::Receiver;
 
@@ -19,8 +24,12 @@ pub struct Event {
 pub(crate) enum EventType {
     Ended(AudioNodeId),
     SinkChange,
+    StateChange,
     RenderCapacity,
+    Underrun,
+    Watchdog,
     ProcessorError(AudioNodeId),
+    Onset(AudioNodeId),
 }
 
 /// The Error Event interface
@@ -37,8 +46,12 @@ pub struct ErrorEvent {
 
 pub(crate) enum EventPayload {
     None,
+    StateChange(AudioContextState),
     RenderCapacity(AudioRenderCapacityEvent),
+    Underrun(UnderrunReport),
+    Watchdog(WatchdogEvent),
     ProcessorError(ErrorEvent),
+    Onset(OnsetEvent),
 }
 
 pub(crate) struct EventDispatch {
@@ -61,6 +74,13 @@ impl EventDispatch {
         }
     }
 
+    pub fn state_change(state: AudioContextState) -> Self {
+        EventDispatch {
+            type_: EventType::StateChange,
+            payload: EventPayload::StateChange(state),
+        }
+    }
+
     pub fn render_capacity(value: AudioRenderCapacityEvent) -> Self {
         EventDispatch {
             type_: EventType::RenderCapacity,
@@ -68,12 +88,180 @@ impl EventDispatch {
         }
     }
 
+    pub fn underrun(value: UnderrunReport) -> Self {
+        EventDispatch {
+            type_: EventType::Underrun,
+            payload: EventPayload::Underrun(value),
+        }
+    }
+
+    pub fn watchdog(value: WatchdogEvent) -> Self {
+        EventDispatch {
+            type_: EventType::Watchdog,
+            payload: EventPayload::Watchdog(value),
+        }
+    }
+
     pub fn processor_error(id: AudioNodeId, value: ErrorEvent) -> Self {
         EventDispatch {
             type_: EventType::ProcessorError(id),
             payload: EventPayload::ProcessorError(value),
         }
     }
+
+    pub fn onset(id: AudioNodeId, value: OnsetEvent) -> Self {
+        EventDispatch {
+            type_: EventType::Onset(id),
+            payload: EventPayload::Onset(value),
+        }
+    }
+
+    /// Copy of the event for the [`EventStream`]s
+    fn to_context_event(&self) -> ContextEvent {
+        match (&self.type_, &self.payload) {
+            (EventType::Ended(id), _) => ContextEvent::Ended { node_id: id.0 },
+            (EventType::SinkChange, _) => ContextEvent::SinkChange,
+            (_, EventPayload::StateChange(state)) => ContextEvent::StateChange(*state),
+            (_, EventPayload::RenderCapacity(event)) => ContextEvent::RenderCapacity(event.clone()),
+            (_, EventPayload::Underrun(report)) => ContextEvent::Underrun(report.to_event()),
+            (_, EventPayload::Watchdog(event)) => ContextEvent::Watchdog(event.clone()),
+            (EventType::ProcessorError(id), EventPayload::ProcessorError(event)) => {
+                ContextEvent::ProcessorError {
+                    node_id: id.0,
+                    message: event.message.clone(),
+                }
+            }
+            (_, EventPayload::Onset(event)) => ContextEvent::Onset(event.clone()),
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Event dispatched by an `AudioContext`, as yielded by an [`EventStream`]
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum ContextEvent {
+    /// A source node has stopped playing
+    ///
+    /// The node can be identified with
+    /// [`AudioContextRegistration::node_id`](crate::context::AudioContextRegistration::node_id).
+    Ended { node_id: u64 },
+    /// The state of the context has changed
+    StateChange(AudioContextState),
+    /// The audio sink has changed
+    SinkChange,
+    /// Performance metrics of the render thread, when enabled with
+    /// [`AudioRenderCapacity::start`](crate::AudioRenderCapacity::start)
+    RenderCapacity(AudioRenderCapacityEvent),
+    /// An audio glitch occurred
+    Underrun(AudioUnderrunEvent),
+    /// The watchdog has muted a node, when enabled with
+    /// [`BaseAudioContext::enable_watchdog`](crate::context::BaseAudioContext::enable_watchdog)
+    Watchdog(WatchdogEvent),
+    /// An audio processor has panicked and was removed from the graph
+    ProcessorError { node_id: u64, message: String },
+    /// A [`BeatDetectorNode`](crate::node::BeatDetectorNode) has detected an onset
+    Onset(OnsetEvent),
+}
+
+/// Maximum number of events buffered by an [`EventStream`], older events are dropped first
+const MAX_QUEUED_EVENTS: usize = 1024;
+
+#[derive(Default)]
+struct EventQueue {
+    events: VecDeque<ContextEvent>,
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+/// Asynchronous stream of the events dispatched by an `AudioContext`
+///
+/// The stream does not depend on a specific async runtime. [`EventStream::poll_next`] has the
+/// signature of `futures::Stream::poll_next` so the stream is easily adapted to the `Stream`
+/// trait when needed.
+///
+/// The callbacks (e.g. `set_onended`) are still called when a stream is active. The stream ends
+/// when the context is closed.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::ContextEvent;
+/// # async fn run() {
+/// let context = AudioContext::default();
+/// let mut events = context.events();
+///
+/// while let Some(event) = events.next().await {
+///     match event {
+///         ContextEvent::StateChange(state) => println!("state: {state:?}"),
+///         event => println!("{event:?}"),
+///     }
+/// }
+/// # }
+/// ```
+pub struct EventStream {
+    queue: Arc<Mutex<EventQueue>>,
+}
+
+impl std::fmt::Debug for EventStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let queue = self.queue.lock().unwrap();
+        f.debug_struct("EventStream")
+            .field("queued", &queue.events.len())
+            .field("closed", &queue.closed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl EventStream {
+    /// Wait for the next event, resolves to `None` when the context is closed
+    #[allow(clippy::should_implement_trait)] // async counterpart of `Iterator::next`
+    pub fn next(&mut self) -> NextEvent<'_> {
+        NextEvent { stream: self }
+    }
+
+    /// Attempt to pull out the next event, registering the current task for wakeup if none is
+    /// available yet
+    ///
+    /// # Panics
+    ///
+    /// Panics if the event loop thread has panicked
+    pub fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ContextEvent>> {
+        let mut queue = self.queue.lock().unwrap();
+        match queue.events.pop_front() {
+            Some(event) => Poll::Ready(Some(event)),
+            None if queue.closed => Poll::Ready(None),
+            None => {
+                queue.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Pull out the next event if one is available, without waiting
+    ///
+    /// # Panics
+    ///
+    /// Panics if the event loop thread has panicked
+    pub fn try_next(&mut self) -> Option<ContextEvent> {
+        self.queue.lock().unwrap().events.pop_front()
+    }
+}
+
+/// Future returned by [`EventStream::next`]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct NextEvent<'a> {
+    stream: &'a mut EventStream,
+}
+
+impl Future for NextEvent<'_> {
+    type Output = Option<ContextEvent>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut *self.stream).poll_next(cx)
+    }
 }
 
 pub(crate) enum EventHandler {
@@ -81,9 +269,13 @@ pub(crate) enum EventHandler {
     Multiple(Box<dyn FnMut(EventPayload) + Send + 'static>),
 }
 
+type StreamQueue = Weak<Mutex<EventQueue>>;
+
 #[derive(Clone, Default)]
 pub(crate) struct EventLoop {
     event_handlers: Arc<Mutex<HashMap<EventType, EventHandler>>>,
+    /// queues of the active streams, `None` when the event loop is not running
+    streams: Arc<Mutex<Option<Vec<StreamQueue>>>>,
 }
 
 impl EventLoop {
@@ -93,10 +285,19 @@ impl EventLoop {
 
     pub fn run(&self, event_channel: Receiver<EventDispatch>) {
         let self_clone = self.clone();
+        *self.streams.lock().unwrap() = Some(vec![]);
 
-        std::thread::spawn(move || loop {
+        std::thread::spawn(move || {
             // this thread is dedicated to event handling so we can block
             for event in event_channel.iter() {
+                self_clone.publish(&event);
+                if matches!(
+                    event.payload,
+                    EventPayload::StateChange(AudioContextState::Closed)
+                ) {
+                    self_clone.close();
+                }
+
                 let mut event_handler_lock = self_clone.event_handlers.lock().unwrap();
                 let callback_option = event_handler_lock.remove(&event.type_);
                 drop(event_handler_lock); // release Mutex while running callback
@@ -115,9 +316,56 @@ impl EventLoop {
                     };
                 }
             }
+
+            // the context is dropped
+            self_clone.close();
+        });
+    }
+
+    /// Forward the event to the active streams
+    fn publish(&self, event: &EventDispatch) {
+        let mut streams = self.streams.lock().unwrap();
+        let streams = match streams.as_mut() {
+            Some(streams) if !streams.is_empty() => streams,
+            _ => return,
+        };
+        streams.retain(|stream| stream.strong_count() > 0);
+
+        let event = event.to_context_event();
+        streams.iter().filter_map(Weak::upgrade).for_each(|queue| {
+            let mut queue = queue.lock().unwrap();
+            if queue.events.len() == MAX_QUEUED_EVENTS {
+                queue.events.pop_front();
+            }
+            queue.events.push_back(event.clone());
+            if let Some(waker) = queue.waker.take() {
+                waker.wake();
+            }
+        });
+    }
+
+    fn close(&self) {
+        let streams = self.streams.lock().unwrap().take().unwrap_or_default();
+        streams.iter().filter_map(Weak::upgrade).for_each(|queue| {
+            let mut queue = queue.lock().unwrap();
+            queue.closed = true;
+            if let Some(waker) = queue.waker.take() {
+                waker.wake();
+            }
         });
     }
 
+    /// Create a new stream of the events, which is closed right away if the event loop is not
+    /// running
+    pub fn subscribe(&self) -> EventStream {
+        let queue = Arc::new(Mutex::new(EventQueue::default()));
+        match self.streams.lock().unwrap().as_mut() {
+            Some(streams) => streams.push(Arc::downgrade(&queue)),
+            None => queue.lock().unwrap().closed = true,
+        }
+        EventStream { queue }
+    }
+
     pub fn set_handler(&self, event: EventType, callback: EventHandler) {
         self.event_handlers.lock().unwrap().insert(event, callback);
     }
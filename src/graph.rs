@@ -0,0 +1,1636 @@
+//! Declarative construction of audio graphs
+//!
+//! A [`GraphBuilder`] describes the nodes and connections of a graph without touching the
+//! context. The whole description is validated before any node is created, so a complex patch
+//! either fully applies or fails with a precise [`GraphError`]:
+//!
+//! ```no_run
+//! use web_audio_api::context::{AudioContext, BaseAudioContext};
+//! use web_audio_api::graph::{GraphBuilder, DESTINATION};
+//! use web_audio_api::node::{BiquadFilterOptions, GainOptions, OscillatorOptions};
+//!
+//! let context = AudioContext::default();
+//!
+//! let mut builder = GraphBuilder::new();
+//! builder
+//!     .add_node("osc", OscillatorOptions::default())
+//!     .add_node("filter", BiquadFilterOptions::default())
+//!     .add_node("amp", GainOptions { gain: 0.2, ..GainOptions::default() })
+//!     .connect("osc", "filter")
+//!     .connect("filter", "amp")
+//!     .connect("amp", DESTINATION);
+//!
+//! let mut graph = builder.build(&context).unwrap();
+//! graph.start_at(context.current_time());
+//! ```
+//!
+//! The [`Graph`] can then be edited with [transactions](Graph::transaction), which apply a group
+//! of operations atomically and record the inverse operations, for editors implementing undo.
+//!
+//! The feedback loops of a description can be checked for stability before they run live with
+//! [`GraphBuilder::analyze_feedback`]. Unstable loops are logged as warnings when the graph is
+//! built or edited.
+//!
+//! This is an extension to the spec.
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+
+use crate::context::{BaseAudioContext, ConcreteBaseAudioContext};
+use crate::node::*;
+use crate::param::AudioParam;
+use crate::{QuotaExceededError, MAX_CHANNELS};
+
+/// Name of the destination of the context in a graph description
+pub const DESTINATION: &str = "destination";
+
+/// Options of a node of a graph description
+///
+/// All variants can be created from the options of the node with `into()`
+#[derive(Clone, Debug)]
+pub enum NodeDescription {
+    Oscillator(OscillatorOptions),
+    ConstantSource(ConstantSourceOptions),
+    Gain(GainOptions),
+    BiquadFilter(BiquadFilterOptions),
+    Delay(DelayOptions),
+    StereoPanner(StereoPannerOptions),
+    ChannelSplitter(ChannelSplitterOptions),
+    ChannelMerger(ChannelMergerOptions),
+}
+
+impl From<OscillatorOptions> for NodeDescription {
+    fn from(options: OscillatorOptions) -> Self {
+        Self::Oscillator(options)
+    }
+}
+
+impl From<ConstantSourceOptions> for NodeDescription {
+    fn from(options: ConstantSourceOptions) -> Self {
+        Self::ConstantSource(options)
+    }
+}
+
+impl From<GainOptions> for NodeDescription {
+    fn from(options: GainOptions) -> Self {
+        Self::Gain(options)
+    }
+}
+
+impl From<BiquadFilterOptions> for NodeDescription {
+    fn from(options: BiquadFilterOptions) -> Self {
+        Self::BiquadFilter(options)
+    }
+}
+
+impl From<DelayOptions> for NodeDescription {
+    fn from(options: DelayOptions) -> Self {
+        Self::Delay(options)
+    }
+}
+
+impl From<StereoPannerOptions> for NodeDescription {
+    fn from(options: StereoPannerOptions) -> Self {
+        Self::StereoPanner(options)
+    }
+}
+
+impl From<ChannelSplitterOptions> for NodeDescription {
+    fn from(options: ChannelSplitterOptions) -> Self {
+        Self::ChannelSplitter(options)
+    }
+}
+
+impl From<ChannelMergerOptions> for NodeDescription {
+    fn from(options: ChannelMergerOptions) -> Self {
+        Self::ChannelMerger(options)
+    }
+}
+
+impl NodeDescription {
+    fn number_of_inputs(&self) -> usize {
+        match self {
+            Self::Oscillator(_) | Self::ConstantSource(_) => 0,
+            Self::ChannelMerger(options) => options.number_of_inputs,
+            _ => 1,
+        }
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        match self {
+            Self::ChannelSplitter(options) => options.number_of_outputs,
+            _ => 1,
+        }
+    }
+
+    /// Names of the audio params of the node, named after their accessors
+    fn params(&self) -> &'static [&'static str] {
+        match self {
+            Self::Oscillator(_) => &["frequency", "detune"],
+            Self::ConstantSource(_) => &["offset"],
+            Self::Gain(_) => &["gain"],
+            Self::BiquadFilter(_) => &["frequency", "detune", "q", "gain"],
+            Self::Delay(_) => &["delay_time"],
+            Self::StereoPanner(_) => &["pan"],
+            Self::ChannelSplitter(_) | Self::ChannelMerger(_) => &[],
+        }
+    }
+
+    /// Magnitude of the linearized response of the node from its inputs to its outputs, at the
+    /// given normalized frequency (0 is DC, 1 is the Nyquist frequency)
+    ///
+    /// Delays, panners and channel routing are considered unity gain, sources do not pass any
+    /// signal.
+    fn magnitude_response(&self, sample_rate: f32, frequency: f64) -> f64 {
+        match self {
+            Self::Oscillator(_) | Self::ConstantSource(_) => 0.,
+            Self::Gain(options) => f64::from(options.gain.abs()),
+            Self::BiquadFilter(options) => options.magnitude_response(sample_rate, frequency),
+            Self::Delay(_)
+            | Self::StereoPanner(_)
+            | Self::ChannelSplitter(_)
+            | Self::ChannelMerger(_) => 1.,
+        }
+    }
+
+    /// Channel config of the node, the constant source has none
+    fn channel_config(&self) -> Option<&ChannelConfigOptions> {
+        let channel_config = match self {
+            Self::ConstantSource(_) => return None,
+            Self::Oscillator(options) => &options.channel_config,
+            Self::Gain(options) => &options.channel_config,
+            Self::BiquadFilter(options) => &options.channel_config,
+            Self::Delay(options) => &options.channel_config,
+            Self::StereoPanner(options) => &options.channel_config,
+            Self::ChannelSplitter(options) => &options.channel_config,
+            Self::ChannelMerger(options) => &options.channel_config,
+        };
+        Some(channel_config)
+    }
+
+    /// Check the rules the node constructors (and the render thread) rely on
+    fn validate(&self, node: &str) -> Result<(), GraphError> {
+        let channel_error = |reason: &str| GraphError::InvalidChannelConfig {
+            node: node.to_owned(),
+            reason: reason.to_owned(),
+        };
+        let options_error = |reason: &str| GraphError::InvalidOptions {
+            node: node.to_owned(),
+            reason: reason.to_owned(),
+        };
+
+        let channel_config = match self.channel_config() {
+            Some(channel_config) => channel_config,
+            None => return Ok(()),
+        };
+        match self {
+            // the channel count of the splitter and merger is derived from their ports
+            Self::ChannelSplitter(_) | Self::ChannelMerger(_) => {
+                if channel_config.count_mode != ChannelCountMode::Explicit {
+                    return Err(channel_error("channel count mode must be explicit"));
+                }
+            }
+            _ => {
+                if channel_config.count == 0 || channel_config.count > MAX_CHANNELS {
+                    return Err(channel_error(
+                        "channel count is outside the [1, MAX_CHANNELS] range",
+                    ));
+                }
+            }
+        }
+
+        match self {
+            Self::Oscillator(options)
+                if options.type_ == OscillatorType::Custom && options.periodic_wave.is_none() =>
+            {
+                Err(options_error("custom type requires a periodic wave"))
+            }
+            Self::Delay(options)
+                if options.max_delay_time <= 0. || options.max_delay_time >= 180. =>
+            {
+                Err(options_error(
+                    "max delay time must be greater than zero and less than three minutes",
+                ))
+            }
+            Self::StereoPanner(_) if channel_config.count > 2 => {
+                Err(channel_error("channel count cannot be greater than two"))
+            }
+            Self::StereoPanner(_) if channel_config.count_mode == ChannelCountMode::Max => {
+                Err(channel_error("channel count mode cannot be max"))
+            }
+            Self::ChannelSplitter(options)
+                if !(1..=MAX_CHANNELS).contains(&options.number_of_outputs) =>
+            {
+                Err(options_error(
+                    "number of outputs is outside the [1, MAX_CHANNELS] range",
+                ))
+            }
+            Self::ChannelMerger(options)
+                if !(1..=MAX_CHANNELS).contains(&options.number_of_inputs) =>
+            {
+                Err(options_error(
+                    "number of inputs is outside the [1, MAX_CHANNELS] range",
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Connection between two nodes of a graph, or between a node and a param
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Connection {
+    /// Name of the source node
+    pub from: String,
+    /// Output port of the source node
+    pub output: usize,
+    /// Name of the destination node (or [`DESTINATION`])
+    pub to: String,
+    /// Input port or param of the destination node
+    pub target: ConnectionTarget,
+}
+
+/// Input port or param at the end of a [`Connection`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConnectionTarget {
+    Input(usize),
+    /// param named after its accessor, e.g. `"frequency"`
+    Param(String),
+}
+
+impl Connection {
+    /// Connection between an output of a node and an input of another node
+    pub fn ports(
+        from: impl Into<String>,
+        output: usize,
+        to: impl Into<String>,
+        input: usize,
+    ) -> Self {
+        Self {
+            from: from.into(),
+            output,
+            to: to.into(),
+            target: ConnectionTarget::Input(input),
+        }
+    }
+
+    /// Connection between the first output of a node and a param of another node
+    pub fn param(from: impl Into<String>, to: impl Into<String>, param: impl Into<String>) -> Self {
+        Self {
+            from: from.into(),
+            output: 0,
+            to: to.into(),
+            target: ConnectionTarget::Param(param.into()),
+        }
+    }
+
+    fn involves(&self, node: &str) -> bool {
+        self.from == node || self.to == node
+    }
+
+    /// Check if both connections are between the same audio nodes (or param)
+    fn has_same_endpoints(&self, other: &Self) -> bool {
+        let same_target = match (&self.target, &other.target) {
+            (ConnectionTarget::Input(_), ConnectionTarget::Input(_)) => true,
+            (ConnectionTarget::Param(a), ConnectionTarget::Param(b)) => a == b,
+            _ => false,
+        };
+        self.from == other.from && self.to == other.to && same_target
+    }
+}
+
+impl fmt::Display for Connection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.target {
+            ConnectionTarget::Input(input) => {
+                write!(f, "{}:{} -> {}:{}", self.from, self.output, self.to, input)
+            }
+            ConnectionTarget::Param(param) => {
+                write!(f, "{}:{} -> {}.{}", self.from, self.output, self.to, param)
+            }
+        }
+    }
+}
+
+/// Error of an invalid graph description, or of a graph that cannot be created
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GraphError {
+    /// Two nodes are declared with the same name (or a node is named [`DESTINATION`])
+    DuplicateNode { node: String },
+    /// A connection refers to an undeclared node
+    UnknownNode { node: String },
+    /// A connection starts from an output port the node does not have
+    InvalidOutput { node: String, output: usize },
+    /// A connection ends at an input port the node does not have
+    InvalidInput { node: String, input: usize },
+    /// A connection targets a param the node does not have
+    UnknownParam { node: String, param: String },
+    /// The channel configuration of a node is not supported
+    InvalidChannelConfig { node: String, reason: String },
+    /// The options of a node are invalid
+    InvalidOptions { node: String, reason: String },
+    /// A connection to remove does not exist
+    UnknownConnection { connection: Connection },
+    /// The nodes form a cycle without a `DelayNode`, which would be muted while rendering
+    Cycle { nodes: Vec<String> },
+    /// Creating the nodes exceeds a resource quota of the context
+    QuotaExceeded(QuotaExceededError),
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateNode { node } => write!(f, "node {node:?} is declared twice"),
+            Self::UnknownNode { node } => write!(f, "unknown node {node:?}"),
+            Self::InvalidOutput { node, output } => {
+                write!(
+                    f,
+                    "IndexSizeError - output port {output} of {node:?} is out of bounds"
+                )
+            }
+            Self::InvalidInput { node, input } => {
+                write!(
+                    f,
+                    "IndexSizeError - input port {input} of {node:?} is out of bounds"
+                )
+            }
+            Self::UnknownParam { node, param } => write!(f, "unknown param {param:?} of {node:?}"),
+            Self::InvalidChannelConfig { node, reason } => {
+                write!(f, "NotSupportedError - {node:?}: {reason}")
+            }
+            Self::InvalidOptions { node, reason } => {
+                write!(f, "NotSupportedError - {node:?}: {reason}")
+            }
+            Self::UnknownConnection { connection } => {
+                write!(f, "unknown connection {connection}")
+            }
+            Self::Cycle { nodes } => {
+                write!(f, "cycle without a delay node: {}", nodes.join(" -> "))
+            }
+            Self::QuotaExceeded(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for GraphError {}
+
+impl From<QuotaExceededError> for GraphError {
+    fn from(error: QuotaExceededError) -> Self {
+        Self::QuotaExceeded(error)
+    }
+}
+
+/// Feedback loop of a graph description, see [`GraphBuilder::analyze_feedback`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeedbackLoop {
+    /// Names of the nodes along the loop, the first node is repeated at the end
+    pub nodes: Vec<String>,
+    /// Magnitude of the gain around the loop at DC
+    pub gain_at_dc: f64,
+    /// Magnitude of the gain around the loop at the Nyquist frequency
+    pub gain_at_nyquist: f64,
+}
+
+impl FeedbackLoop {
+    /// Check if the loop gain stays below unity at DC and at the Nyquist frequency, i.e. if the
+    /// signal circulating in the loop decays at these frequencies
+    pub fn is_stable(&self) -> bool {
+        self.gain_at_dc < 1. && self.gain_at_nyquist < 1.
+    }
+}
+
+impl fmt::Display for FeedbackLoop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (loop gain {:.3} at DC, {:.3} at Nyquist)",
+            self.nodes.join(" -> "),
+            self.gain_at_dc,
+            self.gain_at_nyquist
+        )
+    }
+}
+
+/// Declarative description of an audio graph, see the [module docs](self)
+#[derive(Clone, Debug, Default)]
+pub struct GraphBuilder {
+    nodes: Vec<(String, NodeDescription)>,
+    connections: Vec<Connection>,
+}
+
+impl GraphBuilder {
+    /// Create an empty description
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare a node
+    pub fn add_node(
+        &mut self,
+        name: impl Into<String>,
+        description: impl Into<NodeDescription>,
+    ) -> &mut Self {
+        self.nodes.push((name.into(), description.into()));
+        self
+    }
+
+    /// Connect the first output of a node to the first input of another node (or the
+    /// [`DESTINATION`])
+    pub fn connect(&mut self, from: impl Into<String>, to: impl Into<String>) -> &mut Self {
+        self.connect_ports(from, 0, to, 0)
+    }
+
+    /// Connect an output of a node to an input of another node (or the [`DESTINATION`])
+    pub fn connect_ports(
+        &mut self,
+        from: impl Into<String>,
+        output: usize,
+        to: impl Into<String>,
+        input: usize,
+    ) -> &mut Self {
+        self.connections
+            .push(Connection::ports(from, output, to, input));
+        self
+    }
+
+    /// Connect the first output of a node to a param of another node
+    ///
+    /// Params are named after their accessors, e.g. `"frequency"` or `"delay_time"`.
+    pub fn connect_param(
+        &mut self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+        param: impl Into<String>,
+    ) -> &mut Self {
+        self.connections.push(Connection::param(from, to, param));
+        self
+    }
+
+    /// Validate the whole description, without creating any node
+    ///
+    /// # Errors
+    ///
+    /// Returns the first problem found, checking the nodes, then the connections, then the cycles
+    pub fn validate(&self) -> Result<(), GraphError> {
+        let mut nodes = HashMap::new();
+        for (name, description) in &self.nodes {
+            if name == DESTINATION || nodes.insert(name.as_str(), description).is_some() {
+                return Err(GraphError::DuplicateNode { node: name.clone() });
+            }
+            description.validate(name)?;
+        }
+
+        for connection in &self.connections {
+            let unknown = |node: &String| GraphError::UnknownNode { node: node.clone() };
+
+            // the destination has no output
+            let outputs = match nodes.get(connection.from.as_str()) {
+                Some(from) => from.number_of_outputs(),
+                None if connection.from == DESTINATION => 0,
+                None => return Err(unknown(&connection.from)),
+            };
+            if connection.output >= outputs {
+                return Err(GraphError::InvalidOutput {
+                    node: connection.from.clone(),
+                    output: connection.output,
+                });
+            }
+
+            let to = nodes.get(connection.to.as_str());
+            if to.is_none() && connection.to != DESTINATION {
+                return Err(unknown(&connection.to));
+            }
+            match &connection.target {
+                ConnectionTarget::Input(input) => {
+                    let inputs = to.map_or(1, |to| to.number_of_inputs());
+                    if *input >= inputs {
+                        return Err(GraphError::InvalidInput {
+                            node: connection.to.clone(),
+                            input: *input,
+                        });
+                    }
+                }
+                ConnectionTarget::Param(param) => {
+                    if !to.is_some_and(|to| to.params().contains(&param.as_str())) {
+                        return Err(GraphError::UnknownParam {
+                            node: connection.to.clone(),
+                            param: param.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        match self.find_cycle() {
+            Some(nodes) => Err(GraphError::Cycle { nodes }),
+            None => Ok(()),
+        }
+    }
+
+    /// Find a cycle that is not broken by a delay node, as the names of its nodes
+    fn find_cycle(&self) -> Option<Vec<String>> {
+        let index: HashMap<&str, usize> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| (name.as_str(), i))
+            .collect();
+
+        // the input of a delay node breaks cycles, but not its delay_time param
+        let mut adjacency = vec![vec![]; self.nodes.len()];
+        self.connections.iter().for_each(|connection| {
+            if let (Some(&from), Some(&to)) = (
+                index.get(connection.from.as_str()),
+                index.get(connection.to.as_str()),
+            ) {
+                let is_delay = matches!(self.nodes[to].1, NodeDescription::Delay(_));
+                if !(is_delay && matches!(connection.target, ConnectionTarget::Input(_))) {
+                    adjacency[from].push(to);
+                }
+            }
+        });
+
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark {
+            Unvisited,
+            InProgress,
+            Done,
+        }
+
+        fn visit(
+            node: usize,
+            adjacency: &[Vec<usize>],
+            marks: &mut [Mark],
+            path: &mut Vec<usize>,
+        ) -> Option<Vec<usize>> {
+            marks[node] = Mark::InProgress;
+            path.push(node);
+            for &next in &adjacency[node] {
+                match marks[next] {
+                    Mark::InProgress => {
+                        let start = path.iter().position(|&n| n == next).unwrap();
+                        let mut cycle = path[start..].to_vec();
+                        cycle.push(next);
+                        return Some(cycle);
+                    }
+                    Mark::Unvisited => {
+                        if let Some(cycle) = visit(next, adjacency, marks, path) {
+                            return Some(cycle);
+                        }
+                    }
+                    Mark::Done => (),
+                }
+            }
+            path.pop();
+            marks[node] = Mark::Done;
+            None
+        }
+
+        let mut marks = vec![Mark::Unvisited; self.nodes.len()];
+        (0..self.nodes.len()).find_map(|node| {
+            if marks[node] != Mark::Unvisited {
+                return None;
+            }
+            visit(node, &adjacency, &mut marks, &mut vec![])
+                .map(|cycle| cycle.into_iter().map(|i| self.nodes[i].0.clone()).collect())
+        })
+    }
+
+    /// Find the feedback loops of the description and estimate their stability
+    ///
+    /// Every elementary cycle of audio connections is reported (connections to params are
+    /// modulations and are not followed). The loop gains are the products of the magnitude
+    /// responses of the nodes along the loop, at DC and at the Nyquist frequency, linearized
+    /// around the initial values of the options of the nodes. A loop gain of 1 or more means the
+    /// loop rings forever or blows up at that frequency.
+    ///
+    /// This is an estimate: the gains between DC and the Nyquist frequency (e.g. a resonant
+    /// filter), the interaction of loops sharing nodes and the automations of the params are not
+    /// taken into account. Unknown node names are ignored.
+    pub fn analyze_feedback(&self, sample_rate: f32) -> Vec<FeedbackLoop> {
+        let index: HashMap<&str, usize> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| (name.as_str(), i))
+            .collect();
+
+        let mut adjacency = vec![vec![]; self.nodes.len()];
+        self.connections.iter().for_each(|connection| {
+            if let (Some(&from), Some(&to), ConnectionTarget::Input(_)) = (
+                index.get(connection.from.as_str()),
+                index.get(connection.to.as_str()),
+                &connection.target,
+            ) {
+                if !adjacency[from].contains(&to) {
+                    adjacency[from].push(to);
+                }
+            }
+        });
+
+        // each cycle is found once, from its node with the lowest index
+        fn visit(
+            start: usize,
+            node: usize,
+            adjacency: &[Vec<usize>],
+            path: &mut Vec<usize>,
+            cycles: &mut Vec<Vec<usize>>,
+        ) {
+            path.push(node);
+            for &next in &adjacency[node] {
+                if next == start {
+                    let mut cycle = path.clone();
+                    cycle.push(start);
+                    cycles.push(cycle);
+                } else if next > start && !path.contains(&next) {
+                    visit(start, next, adjacency, path, cycles);
+                }
+            }
+            path.pop();
+        }
+
+        let mut cycles = vec![];
+        (0..self.nodes.len()).for_each(|start| {
+            visit(start, start, &adjacency, &mut vec![], &mut cycles);
+        });
+
+        cycles
+            .into_iter()
+            .map(|cycle| {
+                let descriptions = || cycle[1..].iter().map(|&i| &self.nodes[i].1);
+                FeedbackLoop {
+                    gain_at_dc: descriptions()
+                        .map(|d| d.magnitude_response(sample_rate, 0.))
+                        .product(),
+                    gain_at_nyquist: descriptions()
+                        .map(|d| d.magnitude_response(sample_rate, 1.))
+                        .product(),
+                    nodes: cycle.into_iter().map(|i| self.nodes[i].0.clone()).collect(),
+                }
+            })
+            .collect()
+    }
+
+    /// Apply an operation to the description, recording it (and its inverse) in `edit`
+    fn simulate(
+        &mut self,
+        operation: GraphOperation,
+        edit: &mut GraphEdit,
+    ) -> Result<(), GraphError> {
+        match operation {
+            GraphOperation::AddNode { name, description } => {
+                if name == DESTINATION || self.nodes.iter().any(|(n, _)| *n == name) {
+                    return Err(GraphError::DuplicateNode { node: name });
+                }
+                self.nodes.push((name.clone(), description.clone()));
+                edit.inverse
+                    .push(GraphOperation::RemoveNode { name: name.clone() });
+                edit.operations
+                    .push(GraphOperation::AddNode { name, description });
+            }
+            GraphOperation::RemoveNode { name } => {
+                let index = self
+                    .nodes
+                    .iter()
+                    .position(|(n, _)| *n == name)
+                    .ok_or_else(|| GraphError::UnknownNode { node: name.clone() })?;
+
+                let connections: Vec<_> = self
+                    .connections
+                    .iter()
+                    .filter(|c| c.involves(&name))
+                    .cloned()
+                    .collect();
+                for connection in connections {
+                    self.simulate(GraphOperation::Disconnect(connection), edit)?;
+                }
+
+                let (_, description) = self.nodes.remove(index);
+                edit.inverse.push(GraphOperation::AddNode {
+                    name: name.clone(),
+                    description,
+                });
+                edit.operations.push(GraphOperation::RemoveNode { name });
+            }
+            GraphOperation::Connect(connection) => {
+                if !self.connections.contains(&connection) {
+                    self.connections.push(connection.clone());
+                    edit.inverse
+                        .push(GraphOperation::Disconnect(connection.clone()));
+                    edit.operations.push(GraphOperation::Connect(connection));
+                }
+            }
+            GraphOperation::Disconnect(connection) => {
+                let index = self
+                    .connections
+                    .iter()
+                    .position(|c| *c == connection)
+                    .ok_or_else(|| GraphError::UnknownConnection {
+                        connection: connection.clone(),
+                    })?;
+                self.connections.remove(index);
+                edit.inverse
+                    .push(GraphOperation::Connect(connection.clone()));
+                edit.operations.push(GraphOperation::Disconnect(connection));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate the description, then create and connect all the nodes
+    ///
+    /// Nothing is created when the description is invalid. When a resource quota of the context
+    /// is exceeded, the nodes created so far are dropped before they are connected.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the description is invalid (see [`validate`](Self::validate)) or if
+    /// the nodes exceed a resource quota of the context
+    pub fn build<C: BaseAudioContext>(&self, context: &C) -> Result<Graph, GraphError> {
+        let mut graph = Graph {
+            context: context.base().clone(),
+            description: Self::default(),
+            nodes: BTreeMap::new(),
+            started: false,
+        };
+
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|(name, description)| GraphOperation::AddNode {
+                name: name.clone(),
+                description: description.clone(),
+            });
+        let connections = self
+            .connections
+            .iter()
+            .cloned()
+            .map(GraphOperation::Connect);
+        graph.apply(nodes.chain(connections))?;
+
+        Ok(graph)
+    }
+}
+
+/// Node of a [`Graph`]
+pub enum GraphNode {
+    Oscillator(OscillatorNode),
+    ConstantSource(ConstantSourceNode),
+    Gain(GainNode),
+    BiquadFilter(BiquadFilterNode),
+    Delay(DelayNode),
+    StereoPanner(StereoPannerNode),
+    ChannelSplitter(ChannelSplitterNode),
+    ChannelMerger(ChannelMergerNode),
+}
+
+impl fmt::Debug for GraphNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Oscillator(_) => "Oscillator",
+            Self::ConstantSource(_) => "ConstantSource",
+            Self::Gain(_) => "Gain",
+            Self::BiquadFilter(_) => "BiquadFilter",
+            Self::Delay(_) => "Delay",
+            Self::StereoPanner(_) => "StereoPanner",
+            Self::ChannelSplitter(_) => "ChannelSplitter",
+            Self::ChannelMerger(_) => "ChannelMerger",
+        };
+        write!(f, "{name}(..)")
+    }
+}
+
+impl GraphNode {
+    fn new<C: BaseAudioContext>(context: &C, description: &NodeDescription) -> Self {
+        match description.clone() {
+            NodeDescription::Oscillator(options) => {
+                Self::Oscillator(OscillatorNode::new(context, options))
+            }
+            NodeDescription::ConstantSource(options) => {
+                Self::ConstantSource(ConstantSourceNode::new(context, options))
+            }
+            NodeDescription::Gain(options) => Self::Gain(GainNode::new(context, options)),
+            NodeDescription::BiquadFilter(options) => {
+                Self::BiquadFilter(BiquadFilterNode::new(context, options))
+            }
+            NodeDescription::Delay(options) => Self::Delay(DelayNode::new(context, options)),
+            NodeDescription::StereoPanner(options) => {
+                Self::StereoPanner(StereoPannerNode::new(context, options))
+            }
+            NodeDescription::ChannelSplitter(options) => {
+                Self::ChannelSplitter(ChannelSplitterNode::new(context, options))
+            }
+            NodeDescription::ChannelMerger(options) => {
+                Self::ChannelMerger(ChannelMergerNode::new(context, options))
+            }
+        }
+    }
+
+    fn start_at(&mut self, when: f64) {
+        match self {
+            Self::Oscillator(node) => node.start_at(when),
+            Self::ConstantSource(node) => node.start_at(when),
+            _ => (),
+        }
+    }
+
+    fn stop(&mut self) {
+        match self {
+            Self::Oscillator(node) => node.stop(),
+            Self::ConstantSource(node) => node.stop(),
+            _ => (),
+        }
+    }
+
+    /// The node as a generic `AudioNode`
+    pub fn node(&self) -> &dyn AudioNode {
+        match self {
+            Self::Oscillator(node) => node,
+            Self::ConstantSource(node) => node,
+            Self::Gain(node) => node,
+            Self::BiquadFilter(node) => node,
+            Self::Delay(node) => node,
+            Self::StereoPanner(node) => node,
+            Self::ChannelSplitter(node) => node,
+            Self::ChannelMerger(node) => node,
+        }
+    }
+
+    /// Get a param of the node, named after its accessor
+    pub fn param(&self, name: &str) -> Option<&AudioParam> {
+        let param = match (self, name) {
+            (Self::Oscillator(node), "frequency") => node.frequency(),
+            (Self::Oscillator(node), "detune") => node.detune(),
+            (Self::ConstantSource(node), "offset") => node.offset(),
+            (Self::Gain(node), "gain") => node.gain(),
+            (Self::BiquadFilter(node), "frequency") => node.frequency(),
+            (Self::BiquadFilter(node), "detune") => node.detune(),
+            (Self::BiquadFilter(node), "q") => node.q(),
+            (Self::BiquadFilter(node), "gain") => node.gain(),
+            (Self::Delay(node), "delay_time") => node.delay_time(),
+            (Self::StereoPanner(node), "pan") => node.pan(),
+            _ => return None,
+        };
+        Some(param)
+    }
+}
+
+/// Edit of a [`Graph`], see [`Transaction`]
+#[derive(Clone, Debug)]
+pub enum GraphOperation {
+    /// Create a node
+    AddNode {
+        name: String,
+        description: NodeDescription,
+    },
+    /// Remove a node, after removing all its connections
+    RemoveNode { name: String },
+    /// Add a connection, nothing happens if the connection already exists
+    Connect(Connection),
+    /// Remove a connection
+    Disconnect(Connection),
+}
+
+/// Record of the operations applied to a [`Graph`], and the operations reverting them
+#[derive(Clone, Debug, Default)]
+pub struct GraphEdit {
+    operations: Vec<GraphOperation>,
+    inverse: Vec<GraphOperation>,
+}
+
+impl GraphEdit {
+    /// The operations that were applied
+    ///
+    /// Removing a node is recorded after the removal of its connections, and connections that
+    /// already existed are left out.
+    pub fn operations(&self) -> &[GraphOperation] {
+        &self.operations
+    }
+
+    /// The operations to apply to revert this edit, e.g. to implement undo
+    ///
+    /// Applying them returns the edit to redo this one. Nodes removed by this edit are recreated
+    /// from their description, so they do not keep the changes made to them since creation.
+    pub fn inverse(&self) -> &[GraphOperation] {
+        &self.inverse
+    }
+
+    /// Check if the edit left the graph unchanged
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+}
+
+/// Group of graph operations applied atomically, see [`Graph::transaction`]
+///
+/// The operations are only staged until [`commit`](Self::commit). Dropping the transaction (or
+/// calling [`rollback`](Self::rollback)) leaves the graph untouched.
+pub struct Transaction<'a> {
+    graph: &'a mut Graph,
+    operations: Vec<GraphOperation>,
+}
+
+impl fmt::Debug for Transaction<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transaction")
+            .field("operations", &self.operations)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Transaction<'_> {
+    /// Stage the creation of a node
+    pub fn add_node(
+        &mut self,
+        name: impl Into<String>,
+        description: impl Into<NodeDescription>,
+    ) -> &mut Self {
+        self.operations.push(GraphOperation::AddNode {
+            name: name.into(),
+            description: description.into(),
+        });
+        self
+    }
+
+    /// Stage the removal of a node and all its connections
+    pub fn remove_node(&mut self, name: impl Into<String>) -> &mut Self {
+        let name = name.into();
+        self.operations.push(GraphOperation::RemoveNode { name });
+        self
+    }
+
+    /// Stage a connection from the first output of a node to the first input of another node
+    pub fn connect(&mut self, from: impl Into<String>, to: impl Into<String>) -> &mut Self {
+        self.connect_ports(from, 0, to, 0)
+    }
+
+    /// Stage a connection from an output of a node to an input of another node
+    pub fn connect_ports(
+        &mut self,
+        from: impl Into<String>,
+        output: usize,
+        to: impl Into<String>,
+        input: usize,
+    ) -> &mut Self {
+        let connection = Connection::ports(from, output, to, input);
+        self.operations.push(GraphOperation::Connect(connection));
+        self
+    }
+
+    /// Stage a connection from the first output of a node to a param of another node
+    pub fn connect_param(
+        &mut self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+        param: impl Into<String>,
+    ) -> &mut Self {
+        let connection = Connection::param(from, to, param);
+        self.operations.push(GraphOperation::Connect(connection));
+        self
+    }
+
+    /// Stage the removal of the connection from the first output of a node to the first input of
+    /// another node
+    pub fn disconnect(&mut self, from: impl Into<String>, to: impl Into<String>) -> &mut Self {
+        self.disconnect_ports(from, 0, to, 0)
+    }
+
+    /// Stage the removal of the connection from an output of a node to an input of another node
+    pub fn disconnect_ports(
+        &mut self,
+        from: impl Into<String>,
+        output: usize,
+        to: impl Into<String>,
+        input: usize,
+    ) -> &mut Self {
+        let connection = Connection::ports(from, output, to, input);
+        self.operations.push(GraphOperation::Disconnect(connection));
+        self
+    }
+
+    /// Stage the removal of the connection from the first output of a node to a param of
+    /// another node
+    pub fn disconnect_param(
+        &mut self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+        param: impl Into<String>,
+    ) -> &mut Self {
+        let connection = Connection::param(from, to, param);
+        self.operations.push(GraphOperation::Disconnect(connection));
+        self
+    }
+
+    /// The operations staged so far
+    pub fn operations(&self) -> &[GraphOperation] {
+        &self.operations
+    }
+
+    /// Apply all the staged operations, or none of them
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an operation is invalid, if the resulting graph is invalid (see
+    /// [`GraphBuilder::validate`]) or if the new nodes exceed a resource quota of the context. The
+    /// graph is left untouched in that case.
+    pub fn commit(self) -> Result<GraphEdit, GraphError> {
+        self.graph.apply(self.operations)
+    }
+
+    /// Discard all the staged operations
+    pub fn rollback(self) {}
+}
+
+/// Audio graph created by a [`GraphBuilder`]
+///
+/// The graph can be edited with [transactions](Self::transaction). The nodes are dropped with
+/// the graph, unless they are taken out with [`into_nodes`](Self::into_nodes).
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::AudioContext;
+/// use web_audio_api::graph::{GraphBuilder, DESTINATION};
+/// use web_audio_api::node::{BiquadFilterOptions, OscillatorOptions};
+///
+/// let context = AudioContext::default();
+/// let mut builder = GraphBuilder::new();
+/// builder
+///     .add_node("osc", OscillatorOptions::default())
+///     .connect("osc", DESTINATION);
+/// let mut graph = builder.build(&context).unwrap();
+/// graph.start_at(0.);
+///
+/// // insert a filter
+/// let mut transaction = graph.transaction();
+/// transaction
+///     .disconnect("osc", DESTINATION)
+///     .add_node("filter", BiquadFilterOptions::default())
+///     .connect("osc", "filter")
+///     .connect("filter", DESTINATION);
+/// let edit = transaction.commit().unwrap();
+///
+/// // undo, then redo
+/// let undo = graph.apply(edit.inverse().to_vec()).unwrap();
+/// graph.apply(undo.inverse().to_vec()).unwrap();
+/// ```
+pub struct Graph {
+    context: ConcreteBaseAudioContext,
+    /// the nodes and connections of the graph
+    description: GraphBuilder,
+    nodes: BTreeMap<String, GraphNode>,
+    /// if the sources were started, in which case new sources are started when created
+    started: bool,
+}
+
+impl fmt::Debug for Graph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Graph")
+            .field("nodes", &self.nodes)
+            .field("connections", &self.description.connections)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Graph {
+    /// Get a node of the graph by name
+    pub fn node(&self, name: &str) -> Option<&GraphNode> {
+        self.nodes.get(name)
+    }
+
+    /// Get a node of the graph by name, e.g. to change its type
+    pub fn node_mut(&mut self, name: &str) -> Option<&mut GraphNode> {
+        self.nodes.get_mut(name)
+    }
+
+    /// Get a param of a node of the graph, e.g. to automate it
+    pub fn param(&self, node: &str, param: &str) -> Option<&AudioParam> {
+        self.nodes.get(node).and_then(|node| node.param(param))
+    }
+
+    /// Names of the nodes of the graph
+    pub fn node_names(&self) -> impl Iterator<Item = &str> {
+        self.nodes.keys().map(String::as_str)
+    }
+
+    /// Connections of the graph
+    pub fn connections(&self) -> &[Connection] {
+        &self.description.connections
+    }
+
+    /// Schedule all the source nodes of the graph to start at the given time
+    ///
+    /// The source nodes created afterwards are started immediately.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sources were already started
+    pub fn start_at(&mut self, when: f64) {
+        assert!(
+            !self.started,
+            "InvalidStateError - graph sources were already started"
+        );
+        self.started = true;
+        self.nodes.values_mut().for_each(|node| node.start_at(when));
+    }
+
+    /// Start a transaction to edit the graph
+    pub fn transaction(&mut self) -> Transaction<'_> {
+        Transaction {
+            graph: self,
+            operations: vec![],
+        }
+    }
+
+    /// Apply the operations atomically, see [`Transaction::commit`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an operation or the resulting graph is invalid, or if the new nodes
+    /// exceed a resource quota of the context. The graph is left untouched in that case.
+    #[allow(clippy::missing_panics_doc)] // the operations are validated first
+    pub fn apply(
+        &mut self,
+        operations: impl IntoIterator<Item = GraphOperation>,
+    ) -> Result<GraphEdit, GraphError> {
+        // check the operations on a copy of the description first
+        let mut description = self.description.clone();
+        let mut edit = GraphEdit::default();
+        for operation in operations {
+            description.simulate(operation, &mut edit)?;
+        }
+        description.validate()?;
+        edit.inverse.reverse();
+
+        let sample_rate = self.context.sample_rate();
+        description
+            .analyze_feedback(sample_rate)
+            .iter()
+            .filter(|feedback| !feedback.is_stable())
+            .for_each(|feedback| log::warn!("Unstable feedback loop in graph: {}", feedback));
+
+        let mut created = self.context.try_create(|context| {
+            edit.operations
+                .iter()
+                .filter_map(|operation| match operation {
+                    GraphOperation::AddNode { name, description } => {
+                        Some((name.clone(), GraphNode::new(context, description)))
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .into_iter()
+        })?;
+
+        // the operations cannot fail from here on
+        let mut connections = std::mem::take(&mut self.description.connections);
+        for operation in &edit.operations {
+            match operation {
+                GraphOperation::AddNode { .. } => {
+                    let (name, mut node) = created.next().unwrap();
+                    if self.started {
+                        node.start_at(0.);
+                    }
+                    self.nodes.insert(name, node);
+                }
+                GraphOperation::RemoveNode { name } => {
+                    let mut node = self.nodes.remove(name).unwrap();
+                    if self.started {
+                        node.stop();
+                    }
+                }
+                GraphOperation::Connect(connection) => {
+                    self.connect(connection);
+                    connections.push(connection.clone());
+                }
+                GraphOperation::Disconnect(connection) => {
+                    connections.retain(|c| c != connection);
+                    self.disconnect(connection, &connections);
+                }
+            }
+        }
+        self.description = description;
+
+        Ok(edit)
+    }
+
+    /// The audio nodes (or param) at both ends of a connection
+    fn endpoints(&self, connection: &Connection) -> (&dyn AudioNode, AudioNodeRef<'_>) {
+        let from = self.nodes[&connection.from].node();
+        let to = match &connection.target {
+            ConnectionTarget::Input(_) if connection.to == DESTINATION => {
+                AudioNodeRef::Destination(self.context.destination())
+            }
+            ConnectionTarget::Input(_) => AudioNodeRef::Node(self.nodes[&connection.to].node()),
+            ConnectionTarget::Param(param) => {
+                AudioNodeRef::Node(self.nodes[&connection.to].param(param).unwrap())
+            }
+        };
+        (from, to)
+    }
+
+    fn connect(&self, connection: &Connection) {
+        let (from, to) = self.endpoints(connection);
+        let input = match connection.target {
+            ConnectionTarget::Input(input) => input,
+            ConnectionTarget::Param(_) => 0,
+        };
+        from.connect_at(to.node(), connection.output, input);
+    }
+
+    /// Remove a connection, given the remaining connections of the graph
+    fn disconnect(&self, connection: &Connection, connections: &[Connection]) {
+        // only all the connections between two nodes can be removed, so restore the others
+        let (from, to) = self.endpoints(connection);
+        from.disconnect_from(to.node());
+        connections
+            .iter()
+            .filter(|c| c.has_same_endpoints(connection))
+            .for_each(|c| self.connect(c));
+    }
+
+    /// Take the nodes out of the graph, by name
+    pub fn into_nodes(self) -> BTreeMap<String, GraphNode> {
+        self.nodes
+    }
+}
+
+/// Audio node at the end of a connection
+enum AudioNodeRef<'a> {
+    Node(&'a dyn AudioNode),
+    Destination(AudioDestinationNode),
+}
+
+impl AudioNodeRef<'_> {
+    fn node(&self) -> &dyn AudioNode {
+        match self {
+            Self::Node(node) => *node,
+            Self::Destination(node) => node,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::OfflineAudioContext;
+    use crate::ResourceQuotas;
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_build() {
+        let context = OfflineAudioContext::new(2, 128, 48000.);
+
+        let mut builder = GraphBuilder::new();
+        builder
+            .add_node("src", ConstantSourceOptions { offset: 0.5 })
+            .add_node("amp", GainOptions::default())
+            .add_node("split", ChannelSplitterOptions::default())
+            .add_node(
+                "merge",
+                ChannelMergerOptions {
+                    number_of_inputs: 2,
+                    ..ChannelMergerOptions::default()
+                },
+            )
+            .add_node("mod", ConstantSourceOptions::default())
+            .connect("src", "amp")
+            .connect_param("mod", "amp", "gain")
+            .connect("amp", "split")
+            .connect_ports("split", 0, "merge", 1)
+            .connect("merge", DESTINATION);
+
+        let mut graph = builder.build(&context).unwrap();
+        assert_eq!(graph.node_names().count(), 5);
+        assert!(matches!(graph.node("amp"), Some(GraphNode::Gain(_))));
+        assert!(graph.param("amp", "gain").is_some());
+        graph.start_at(0.);
+
+        // gain is 1 + 1 from the modulation, in the right channel only
+        let output = context.start_rendering_sync();
+        assert_float_eq!(output.get_channel_data(0), &[0.; 128][..], abs_all <= 0.);
+        assert_float_eq!(output.get_channel_data(1), &[1.; 128][..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_invalid_connections() {
+        let mut builder = GraphBuilder::new();
+        builder
+            .add_node("osc", OscillatorOptions::default())
+            .add_node("amp", GainOptions::default());
+
+        let mut b = builder.clone();
+        b.connect("osc", "filter");
+        let error = GraphError::UnknownNode {
+            node: "filter".into(),
+        };
+        assert_eq!(b.validate(), Err(error));
+
+        let mut b = builder.clone();
+        b.connect_ports("osc", 1, "amp", 0);
+        let error = GraphError::InvalidOutput {
+            node: "osc".into(),
+            output: 1,
+        };
+        assert_eq!(b.validate(), Err(error));
+
+        let mut b = builder.clone();
+        b.connect("amp", "osc");
+        let error = GraphError::InvalidInput {
+            node: "osc".into(),
+            input: 0,
+        };
+        assert_eq!(b.validate(), Err(error));
+
+        let mut b = builder.clone();
+        b.connect_param("osc", "amp", "frequency");
+        let error = GraphError::UnknownParam {
+            node: "amp".into(),
+            param: "frequency".into(),
+        };
+        assert_eq!(b.validate(), Err(error));
+
+        let mut b = builder.clone();
+        b.add_node("amp", GainOptions::default());
+        let error = GraphError::DuplicateNode { node: "amp".into() };
+        assert_eq!(b.validate(), Err(error));
+
+        builder.connect("osc", "amp").connect("amp", DESTINATION);
+        assert_eq!(builder.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_nodes() {
+        let mut builder = GraphBuilder::new();
+        let mut options = StereoPannerOptions::default();
+        options.channel_config.count = 3;
+        builder.add_node("pan", options);
+        assert!(matches!(
+            builder.validate(),
+            Err(GraphError::InvalidChannelConfig { node, .. }) if node == "pan"
+        ));
+
+        let mut builder = GraphBuilder::new();
+        let options = DelayOptions {
+            max_delay_time: 0.,
+            ..DelayOptions::default()
+        };
+        builder.add_node("delay", options);
+        assert!(matches!(
+            builder.validate(),
+            Err(GraphError::InvalidOptions { node, .. }) if node == "delay"
+        ));
+    }
+
+    #[test]
+    fn test_cycles() {
+        let mut builder = GraphBuilder::new();
+        builder
+            .add_node("a", GainOptions::default())
+            .add_node("b", GainOptions::default())
+            .add_node("delay", DelayOptions::default())
+            .connect("a", "b")
+            .connect("b", "delay")
+            .connect("delay", "a");
+        // the delay node breaks the cycle
+        assert_eq!(builder.validate(), Ok(()));
+
+        // but not through its param
+        let mut b = builder.clone();
+        b.connect_param("a", "delay", "delay_time");
+        let error = GraphError::Cycle {
+            nodes: vec!["a".into(), "delay".into(), "a".into()],
+        };
+        assert_eq!(b.validate(), Err(error));
+
+        builder.connect("b", "a");
+        let error = GraphError::Cycle {
+            nodes: vec!["a".into(), "b".into(), "a".into()],
+        };
+        assert_eq!(builder.validate(), Err(error));
+    }
+
+    #[test]
+    fn test_analyze_feedback() {
+        let mut builder = GraphBuilder::new();
+        builder
+            .add_node("delay", DelayOptions::default())
+            .add_node(
+                "feedback",
+                GainOptions {
+                    gain: -0.5,
+                    ..GainOptions::default()
+                },
+            )
+            .add_node(
+                "damping",
+                BiquadFilterOptions {
+                    type_: BiquadFilterType::Lowshelf,
+                    frequency: 1000.,
+                    gain: 6.,
+                    ..BiquadFilterOptions::default()
+                },
+            )
+            .add_node("src", ConstantSourceOptions::default())
+            .connect("src", "delay")
+            .connect("delay", "feedback")
+            .connect("feedback", "delay")
+            .connect_param("feedback", "damping", "frequency")
+            .connect_ports("delay", 0, "damping", 0)
+            .connect("damping", "delay");
+
+        let loops = builder.analyze_feedback(48000.);
+        assert_eq!(loops.len(), 2);
+        assert_eq!(loops[0].nodes, ["delay", "feedback", "delay"]);
+        assert_float_eq!(loops[0].gain_at_dc, 0.5, abs <= 0.);
+        assert!(loops[0].is_stable());
+
+        // the low shelf filter boosts the low frequencies
+        assert_eq!(loops[1].nodes, ["delay", "damping", "delay"]);
+        assert_float_eq!(loops[1].gain_at_dc, 1.995, abs <= 1e-3);
+        assert_float_eq!(loops[1].gain_at_nyquist, 1., abs <= 1e-3);
+        assert!(!loops[1].is_stable());
+
+        let mut builder = GraphBuilder::new();
+        builder
+            .add_node("a", GainOptions::default())
+            .connect("a", DESTINATION);
+        assert!(builder.analyze_feedback(48000.).is_empty());
+    }
+
+    #[test]
+    fn test_build_is_atomic() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let nodes = context.memory_report().nodes;
+        context.set_resource_quotas(ResourceQuotas {
+            max_nodes: Some(nodes + 1),
+            ..ResourceQuotas::default()
+        });
+
+        let mut builder = GraphBuilder::new();
+        builder
+            .add_node("src", ConstantSourceOptions::default())
+            .add_node("amp", GainOptions::default())
+            .connect("src", "amp")
+            .connect("amp", DESTINATION);
+        let error = GraphError::QuotaExceeded(QuotaExceededError::Nodes { limit: nodes + 1 });
+        assert_eq!(builder.build(&context).err(), Some(error));
+
+        // nothing was connected to the destination
+        let output = context.start_rendering_sync();
+        assert_float_eq!(output.get_channel_data(0), &[0.; 128][..], abs_all <= 0.);
+    }
+
+    fn constant_to_destination(context: &OfflineAudioContext) -> Graph {
+        let mut builder = GraphBuilder::new();
+        builder
+            .add_node("src", ConstantSourceOptions::default())
+            .connect("src", DESTINATION);
+        let mut graph = builder.build(context).unwrap();
+        graph.start_at(0.);
+        graph
+    }
+
+    fn half_gain() -> GainOptions {
+        GainOptions {
+            gain: 0.5,
+            ..GainOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_transaction() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let mut graph = constant_to_destination(&context);
+
+        let mut transaction = graph.transaction();
+        transaction
+            .disconnect("src", DESTINATION)
+            .add_node("amp", half_gain())
+            .connect("src", "amp")
+            .connect("amp", DESTINATION)
+            // already connected
+            .connect("amp", DESTINATION);
+        assert_eq!(transaction.operations().len(), 5);
+
+        let edit = transaction.commit().unwrap();
+        assert_eq!(edit.operations().len(), 4);
+        assert!(matches!(
+            edit.inverse(),
+            [
+                GraphOperation::Disconnect(_),
+                GraphOperation::Disconnect(_),
+                GraphOperation::RemoveNode { .. },
+                GraphOperation::Connect(_),
+            ]
+        ));
+        assert_eq!(graph.connections().len(), 2);
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(output.get_channel_data(0), &[0.5; 128][..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_failed_transaction() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let mut graph = constant_to_destination(&context);
+
+        let mut transaction = graph.transaction();
+        transaction
+            .disconnect("src", DESTINATION)
+            .add_node("amp", half_gain())
+            .connect("src", "filter");
+        let error = GraphError::UnknownNode {
+            node: "filter".into(),
+        };
+        assert_eq!(transaction.commit().err(), Some(error));
+
+        let mut transaction = graph.transaction();
+        transaction.disconnect("src", "amp");
+        assert!(matches!(
+            transaction.commit(),
+            Err(GraphError::UnknownConnection { .. })
+        ));
+
+        let mut transaction = graph.transaction();
+        transaction.disconnect("src", DESTINATION);
+        transaction.rollback();
+
+        // the graph is untouched
+        assert_eq!(graph.node_names().collect::<Vec<_>>(), ["src"]);
+        assert_eq!(
+            graph.connections(),
+            [Connection::ports("src", 0, DESTINATION, 0)]
+        );
+        let output = context.start_rendering_sync();
+        assert_float_eq!(output.get_channel_data(0), &[1.; 128][..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let mut graph = constant_to_destination(&context);
+
+        let mut transaction = graph.transaction();
+        transaction
+            .add_node("amp", half_gain())
+            .connect("src", "amp")
+            .connect("amp", DESTINATION)
+            .disconnect("src", DESTINATION);
+        let edit = transaction.commit().unwrap();
+
+        let undo = graph.apply(edit.inverse().to_vec()).unwrap();
+        assert_eq!(graph.node_names().collect::<Vec<_>>(), ["src"]);
+        assert_eq!(
+            graph.connections(),
+            [Connection::ports("src", 0, DESTINATION, 0)]
+        );
+
+        // removing the node again removes its connections first
+        let redo = graph.apply(undo.inverse().to_vec()).unwrap();
+        assert_eq!(graph.node_names().collect::<Vec<_>>(), ["amp", "src"]);
+        assert_eq!(graph.connections().len(), 2);
+        let undo = graph
+            .apply([GraphOperation::RemoveNode { name: "amp".into() }])
+            .unwrap();
+        assert_eq!(undo.operations().len(), 3);
+        graph.apply(undo.inverse().to_vec()).unwrap();
+        assert_eq!(redo.operations().len(), 4);
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(output.get_channel_data(0), &[0.5; 128][..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_disconnect_ports() {
+        let context = OfflineAudioContext::new(2, 128, 48000.);
+
+        let mut builder = GraphBuilder::new();
+        builder
+            .add_node("src", ConstantSourceOptions::default())
+            .add_node(
+                "merge",
+                ChannelMergerOptions {
+                    number_of_inputs: 2,
+                    ..ChannelMergerOptions::default()
+                },
+            )
+            .connect_ports("src", 0, "merge", 0)
+            .connect_ports("src", 0, "merge", 1)
+            .connect("merge", DESTINATION);
+        let mut graph = builder.build(&context).unwrap();
+        graph.start_at(0.);
+
+        let mut transaction = graph.transaction();
+        transaction.disconnect_ports("src", 0, "merge", 1);
+        transaction.commit().unwrap();
+
+        // the other connection between the nodes is kept
+        let output = context.start_rendering_sync();
+        assert_float_eq!(output.get_channel_data(0), &[1.; 128][..], abs_all <= 0.);
+        assert_float_eq!(output.get_channel_data(1), &[0.; 128][..], abs_all <= 0.);
+    }
+}
@@ -5,7 +5,7 @@ use std::sync::Mutex;
 
 use cpal::{
     traits::{DeviceTrait, HostTrait, StreamTrait},
-    BuildStreamError, Device, OutputCallbackInfo, SampleFormat, Stream, StreamConfig,
+    BuildStreamError, Device, OutputCallbackInfo, SampleFormat, Stream, StreamConfig, StreamError,
     SupportedBufferSize,
 };
 use crossbeam_channel::Receiver;
@@ -15,7 +15,7 @@ use super::{AudioBackendManager, RenderThreadInit};
 use crate::buffer::AudioBuffer;
 use crate::context::AudioContextOptions;
 use crate::io::microphone::MicrophoneRender;
-use crate::media_devices::{MediaDeviceInfo, MediaDeviceInfoKind};
+use crate::media_devices::{InputChannelLayout, MediaDeviceInfo, MediaDeviceInfoKind};
 use crate::render::RenderThread;
 use crate::{AtomicF64, MAX_CHANNELS};
 
@@ -71,6 +71,20 @@ mod private {
 use private::ThreadSafeClosableStream;
 
 fn get_host() -> cpal::Host {
+    #[cfg(all(target_os = "windows", feature = "cpal-asio"))]
+    {
+        // the ASIO host is only usable when an ASIO driver is installed,
+        // fallback to the default (WASAPI) host otherwise
+        let asio_host = cpal::host_from_id(cpal::HostId::Asio)
+            .ok()
+            .filter(|host| host.default_output_device().is_some());
+
+        match asio_host {
+            Some(asio_host) => return asio_host,
+            None => log::warn!("No ASIO driver found, fallback to default host"),
+        }
+    }
+
     #[cfg(feature = "cpal-jack")]
     {
         // seems to be always Some when jack is installed,
@@ -109,6 +123,8 @@ pub(crate) struct CpalBackend {
     stream: ThreadSafeClosableStream,
     output_latency: Arc<AtomicF64>,
     sample_rate: f32,
+    /// sample rate of the device, differs from `sample_rate` when the output is resampled
+    device_sample_rate: f32,
     number_of_channels: usize,
     sink_id: String,
 }
@@ -151,7 +167,15 @@ impl AudioBackendManager for CpalBackend {
 
         // we grab the largest number of channels provided by the soundcard
         // clamped to MAX_CHANNELS, this value cannot be changed by the user
-        let number_of_channels = usize::from(default_device_config.channels()).min(MAX_CHANNELS);
+        let device_channels = usize::from(default_device_config.channels());
+        if device_channels > MAX_CHANNELS {
+            log::warn!(
+                "Output device has {} channels, only the first {} (MAX_CHANNELS) are used",
+                device_channels,
+                MAX_CHANNELS
+            );
+        }
+        let number_of_channels = device_channels.min(MAX_CHANNELS);
 
         // override default device configuration with the options provided by
         // the user when creating the `AudioContext`
@@ -179,9 +203,10 @@ impl AudioBackendManager for CpalBackend {
         preferred_config.buffer_size = cpal::BufferSize::Fixed(clamped_buffer_size);
 
         // report the picked sample rate to the render thread, i.e. if the requested
-        // sample rate is not supported by the hardware, it will fallback to the
-        // default device sample rate
-        let mut sample_rate = preferred_config.sample_rate.0 as f32;
+        // sample rate is not supported by the hardware, the rendered audio is resampled
+        // to the default device sample rate
+        let sample_rate = preferred_config.sample_rate.0 as f32;
+        let mut device_sample_rate = sample_rate;
 
         // shared atomic to report output latency to the control thread
         let output_latency = Arc::new(AtomicF64::new(0.));
@@ -219,8 +244,8 @@ impl AudioBackendManager for CpalBackend {
                 let mut supported_config: StreamConfig = default_device_config.clone().into();
                 // make sure number of channels is clamped to MAX_CHANNELS
                 supported_config.channels = number_of_channels as u16;
-                // fallback to device default sample rate
-                sample_rate = supported_config.sample_rate.0 as f32;
+                // fallback to device default sample rate, the context keeps its sample rate
+                device_sample_rate = supported_config.sample_rate.0 as f32;
 
                 log::debug!(
                     "Attempt output stream with fallback config: {:?}",
@@ -234,6 +259,7 @@ impl AudioBackendManager for CpalBackend {
                     frames_played,
                 );
                 renderer.set_event_channels(load_value_send, event_send);
+                renderer.set_device_sample_rate(device_sample_rate);
                 renderer.spawn_garbage_collector_thread();
 
                 let spawned = spawn_output_stream(
@@ -254,12 +280,16 @@ impl AudioBackendManager for CpalBackend {
             stream: ThreadSafeClosableStream::new(stream),
             output_latency,
             sample_rate,
+            device_sample_rate,
             number_of_channels,
             sink_id: options.sink_id,
         }
     }
 
-    fn build_input(options: AudioContextOptions) -> (Self, Receiver<AudioBuffer>)
+    fn build_input(
+        options: AudioContextOptions,
+        layout: InputChannelLayout,
+    ) -> (Self, Receiver<AudioBuffer>)
     where
         Self: Sized,
     {
@@ -290,6 +320,22 @@ impl AudioBackendManager for CpalBackend {
         // clone the config, we may need to fall back on it later
         let mut preferred: StreamConfig = supported.clone().into();
 
+        // open the channels required by the layout, the default config may not include all the
+        // channels of the device
+        let default_channels = usize::from(supported.channels());
+        let max_channels = device
+            .supported_input_configs()
+            .ok()
+            .and_then(|configs| {
+                configs
+                    .filter(|c| c.sample_format() == supported.sample_format())
+                    .map(|c| usize::from(c.channels()))
+                    .max()
+            })
+            .unwrap_or(default_channels)
+            .max(default_channels);
+        preferred.channels = layout.device_channels(default_channels, max_channels) as u16;
+
         // set specific sample rate if requested
         if let Some(sample_rate) = options.sample_rate {
             crate::assert_valid_sample_rate(sample_rate);
@@ -314,7 +360,8 @@ impl AudioBackendManager for CpalBackend {
 
         let smoothing = 3; // todo, use buffering to smooth frame drops
         let (sender, mut receiver) = crossbeam_channel::bounded(smoothing);
-        let renderer = MicrophoneRender::new(number_of_channels, sample_rate, sender);
+        let renderer =
+            MicrophoneRender::new(number_of_channels, sample_rate, layout.clone(), sender);
 
         let maybe_stream =
             spawn_input_stream(&device, supported.sample_format(), &preferred, renderer);
@@ -330,15 +377,18 @@ impl AudioBackendManager for CpalBackend {
                     preferred
                 );
 
-                let supported_config: StreamConfig = supported.clone().into();
-                number_of_channels = usize::from(supported_config.channels);
+                let mut supported_config: StreamConfig = supported.clone().into();
+                // the default config must provide the channels of the layout
+                number_of_channels = layout.device_channels(default_channels, default_channels);
+                supported_config.channels = number_of_channels as u16;
                 sample_rate = supported_config.sample_rate.0 as f32;
 
                 // setup a new comms channel
                 let (sender, receiver2) = crossbeam_channel::bounded(smoothing);
                 receiver = receiver2; // overwrite earlier
 
-                let renderer = MicrophoneRender::new(number_of_channels, sample_rate, sender);
+                let renderer =
+                    MicrophoneRender::new(number_of_channels, sample_rate, layout.clone(), sender);
 
                 let spawned = spawn_input_stream(
                     &device,
@@ -357,7 +407,8 @@ impl AudioBackendManager for CpalBackend {
             stream: ThreadSafeClosableStream::new(stream),
             output_latency: Arc::new(AtomicF64::new(0.)),
             sample_rate,
-            number_of_channels,
+            device_sample_rate: sample_rate,
+            number_of_channels: layout.number_of_channels(number_of_channels),
             sink_id: options.sink_id,
         };
 
@@ -380,6 +431,10 @@ impl AudioBackendManager for CpalBackend {
         self.sample_rate
     }
 
+    fn device_sample_rate(&self) -> f32 {
+        self.device_sample_rate
+    }
+
     fn number_of_channels(&self) -> usize {
         self.number_of_channels
     }
@@ -472,7 +527,14 @@ fn spawn_output_stream(
     mut render: RenderThread,
     output_latency: Arc<AtomicF64>,
 ) -> Result<Stream, BuildStreamError> {
-    let err_fn = |err| log::error!("an error occurred on the output audio stream: {}", err);
+    let report_underrun = render.backend_underrun_reporter();
+    let err_fn = move |err: StreamError| {
+        log::error!("an error occurred on the output audio stream: {}", err);
+        // under/overruns are reported as backend specific errors
+        if let StreamError::BackendSpecific { err } = err {
+            report_underrun(err.description);
+        }
+    };
 
     match sample_format {
         SampleFormat::F32 => device.build_output_stream(
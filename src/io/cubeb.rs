@@ -1,3 +1,4 @@
+use std::ffi::CString;
 use std::sync::Arc;
 
 use super::{AudioBackendManager, RenderThreadInit};
@@ -5,7 +6,7 @@ use super::{AudioBackendManager, RenderThreadInit};
 use crate::buffer::AudioBuffer;
 use crate::context::AudioContextOptions;
 use crate::io::microphone::MicrophoneRender;
-use crate::media_devices::{MediaDeviceInfo, MediaDeviceInfoKind};
+use crate::media_devices::{InputChannelLayout, MediaDeviceInfo, MediaDeviceInfoKind};
 use crate::render::RenderThread;
 use crate::{MAX_CHANNELS, RENDER_QUANTUM_SIZE};
 
@@ -100,6 +101,7 @@ use private::ThreadSafeClosableStream;
 
 fn init_output_backend<const N: usize>(
     ctx: &Context,
+    stream_name: &str,
     params: StreamParams,
     buffer_size: u32,
     device: Option<DeviceId>,
@@ -113,7 +115,7 @@ fn init_output_backend<const N: usize>(
     };
 
     builder
-        .name("Cubeb web_audio_api")
+        .name(stream_name)
         .latency(buffer_size)
         .data_callback(move |_input, output| {
             // `output` is `&mut [[f32; N]]`, a slice of slices.
@@ -157,8 +159,17 @@ impl AudioBackendManager for CubebBackend {
             event_send,
         } = render_thread_init;
 
-        // Set up cubeb context
-        let ctx = Context::init(None, None).unwrap();
+        // Set up cubeb context, named after the application
+        let metadata = &options.stream_metadata;
+        let context_name = metadata
+            .application_name
+            .as_deref()
+            .and_then(|name| CString::new(name).ok());
+        let ctx = Context::init(context_name.as_deref(), None).unwrap();
+        let stream_name = metadata
+            .media_name
+            .as_deref()
+            .unwrap_or("Cubeb web_audio_api");
         log::info!("Audio Output Host: cubeb {:?}", ctx.backend_id());
 
         // Use user requested sample rate, or else the device preferred one
@@ -173,12 +184,21 @@ impl AudioBackendManager for CubebBackend {
 
         // clamp the requested stream number of channels to MAX_CHANNELS even if
         // the soundcard can provide more channels
+        if number_of_channels > MAX_CHANNELS {
+            log::warn!(
+                "Output device has {} channels, only the first {} (MAX_CHANNELS) are used",
+                number_of_channels,
+                MAX_CHANNELS
+            );
+        }
         let number_of_channels = number_of_channels.min(MAX_CHANNELS);
 
         let layout = match number_of_channels {
             1 => cubeb::ChannelLayout::MONO,
             2 => cubeb::ChannelLayout::STEREO,
             4 => cubeb::ChannelLayout::QUAD,
+            6 => cubeb::ChannelLayout::_3F2_LFE, // 5.1, same channel order as the spec
+            8 => cubeb::ChannelLayout::_3F4_LFE, // 7.1
             _ => cubeb::ChannelLayout::UNDEFINED, // TODO, does this work?
         };
 
@@ -218,38 +238,84 @@ impl AudioBackendManager for CubebBackend {
 
         let stream = match number_of_channels {
             // so sorry, but I need to constify the non-const `number_of_channels`
-            1 => init_output_backend::<1>(&ctx, params, buffer_size, device, renderer),
-            2 => init_output_backend::<2>(&ctx, params, buffer_size, device, renderer),
-            3 => init_output_backend::<3>(&ctx, params, buffer_size, device, renderer),
-            4 => init_output_backend::<4>(&ctx, params, buffer_size, device, renderer),
-            5 => init_output_backend::<5>(&ctx, params, buffer_size, device, renderer),
-            6 => init_output_backend::<6>(&ctx, params, buffer_size, device, renderer),
-            7 => init_output_backend::<7>(&ctx, params, buffer_size, device, renderer),
-            8 => init_output_backend::<8>(&ctx, params, buffer_size, device, renderer),
-            9 => init_output_backend::<9>(&ctx, params, buffer_size, device, renderer),
-            10 => init_output_backend::<10>(&ctx, params, buffer_size, device, renderer),
-            11 => init_output_backend::<11>(&ctx, params, buffer_size, device, renderer),
-            12 => init_output_backend::<12>(&ctx, params, buffer_size, device, renderer),
-            13 => init_output_backend::<13>(&ctx, params, buffer_size, device, renderer),
-            14 => init_output_backend::<14>(&ctx, params, buffer_size, device, renderer),
-            15 => init_output_backend::<15>(&ctx, params, buffer_size, device, renderer),
-            16 => init_output_backend::<16>(&ctx, params, buffer_size, device, renderer),
-            17 => init_output_backend::<17>(&ctx, params, buffer_size, device, renderer),
-            18 => init_output_backend::<18>(&ctx, params, buffer_size, device, renderer),
-            19 => init_output_backend::<19>(&ctx, params, buffer_size, device, renderer),
-            20 => init_output_backend::<20>(&ctx, params, buffer_size, device, renderer),
-            21 => init_output_backend::<21>(&ctx, params, buffer_size, device, renderer),
-            22 => init_output_backend::<22>(&ctx, params, buffer_size, device, renderer),
-            23 => init_output_backend::<23>(&ctx, params, buffer_size, device, renderer),
-            24 => init_output_backend::<24>(&ctx, params, buffer_size, device, renderer),
-            25 => init_output_backend::<25>(&ctx, params, buffer_size, device, renderer),
-            26 => init_output_backend::<26>(&ctx, params, buffer_size, device, renderer),
-            27 => init_output_backend::<27>(&ctx, params, buffer_size, device, renderer),
-            28 => init_output_backend::<28>(&ctx, params, buffer_size, device, renderer),
-            29 => init_output_backend::<29>(&ctx, params, buffer_size, device, renderer),
-            30 => init_output_backend::<30>(&ctx, params, buffer_size, device, renderer),
-            31 => init_output_backend::<31>(&ctx, params, buffer_size, device, renderer),
-            32 => init_output_backend::<32>(&ctx, params, buffer_size, device, renderer),
+            1 => init_output_backend::<1>(&ctx, stream_name, params, buffer_size, device, renderer),
+            2 => init_output_backend::<2>(&ctx, stream_name, params, buffer_size, device, renderer),
+            3 => init_output_backend::<3>(&ctx, stream_name, params, buffer_size, device, renderer),
+            4 => init_output_backend::<4>(&ctx, stream_name, params, buffer_size, device, renderer),
+            5 => init_output_backend::<5>(&ctx, stream_name, params, buffer_size, device, renderer),
+            6 => init_output_backend::<6>(&ctx, stream_name, params, buffer_size, device, renderer),
+            7 => init_output_backend::<7>(&ctx, stream_name, params, buffer_size, device, renderer),
+            8 => init_output_backend::<8>(&ctx, stream_name, params, buffer_size, device, renderer),
+            9 => init_output_backend::<9>(&ctx, stream_name, params, buffer_size, device, renderer),
+            10 => {
+                init_output_backend::<10>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            11 => {
+                init_output_backend::<11>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            12 => {
+                init_output_backend::<12>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            13 => {
+                init_output_backend::<13>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            14 => {
+                init_output_backend::<14>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            15 => {
+                init_output_backend::<15>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            16 => {
+                init_output_backend::<16>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            17 => {
+                init_output_backend::<17>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            18 => {
+                init_output_backend::<18>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            19 => {
+                init_output_backend::<19>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            20 => {
+                init_output_backend::<20>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            21 => {
+                init_output_backend::<21>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            22 => {
+                init_output_backend::<22>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            23 => {
+                init_output_backend::<23>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            24 => {
+                init_output_backend::<24>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            25 => {
+                init_output_backend::<25>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            26 => {
+                init_output_backend::<26>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            27 => {
+                init_output_backend::<27>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            28 => {
+                init_output_backend::<28>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            29 => {
+                init_output_backend::<29>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            30 => {
+                init_output_backend::<30>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            31 => {
+                init_output_backend::<31>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
+            32 => {
+                init_output_backend::<32>(&ctx, stream_name, params, buffer_size, device, renderer)
+            }
             _ => unreachable!(),
         };
 
@@ -265,7 +331,10 @@ impl AudioBackendManager for CubebBackend {
         backend
     }
 
-    fn build_input(options: AudioContextOptions) -> (Self, Receiver<AudioBuffer>)
+    fn build_input(
+        options: AudioContextOptions,
+        layout: InputChannelLayout,
+    ) -> (Self, Receiver<AudioBuffer>)
     where
         Self: Sized,
     {
@@ -287,13 +356,16 @@ impl AudioBackendManager for CubebBackend {
         // TODO support all channel configs
         let _max_channel_count = ctx.max_channel_count().map(|v| v as usize).ok();
         const NUMBER_OF_INPUT_CHANNELS: usize = 2;
-        let layout = cubeb::ChannelLayout::STEREO;
+        // the channels of the layout are picked from the stereo input, this panics when a
+        // channel beyond the stereo pair is selected
+        layout.device_channels(NUMBER_OF_INPUT_CHANNELS, NUMBER_OF_INPUT_CHANNELS);
+        let number_of_channels = layout.number_of_channels(NUMBER_OF_INPUT_CHANNELS);
 
         let params = cubeb::StreamParamsBuilder::new()
             .format(cubeb::SampleFormat::Float32NE) // use float (native endian)
             .rate(sample_rate as u32)
             .channels(NUMBER_OF_INPUT_CHANNELS as u32)
-            .layout(layout)
+            .layout(cubeb::ChannelLayout::STEREO)
             .take();
 
         // Calculate ideal latency
@@ -316,7 +388,7 @@ impl AudioBackendManager for CubebBackend {
 
         let smoothing = 3; // todo, use buffering to smooth frame drops
         let (sender, receiver) = crossbeam_channel::bounded(smoothing);
-        let renderer = MicrophoneRender::new(NUMBER_OF_INPUT_CHANNELS, sample_rate, sender);
+        let renderer = MicrophoneRender::new(NUMBER_OF_INPUT_CHANNELS, sample_rate, layout, sender);
 
         // Microphone input is always assumed STEREO (TODO)
         let mut builder = cubeb::StreamBuilder::<StereoFrame<f32>>::new();
@@ -350,7 +422,7 @@ impl AudioBackendManager for CubebBackend {
 
         let backend = CubebBackend {
             stream: ThreadSafeClosableStream::new(stream),
-            number_of_channels: NUMBER_OF_INPUT_CHANNELS,
+            number_of_channels,
             sample_rate,
             sink_id: options.sink_id,
         };
@@ -0,0 +1,266 @@
+//! Output of an `AudioContext` through a [`DeviceMixer`](crate::context::DeviceMixer)
+
+use std::sync::Arc;
+
+use crossbeam_channel::{Receiver, Sender, TryRecvError};
+
+use super::{AudioBackendManager, RenderThreadInit};
+
+use crate::buffer::AudioBuffer;
+use crate::context::{AudioContextOptions, AudioContextRegistration, BaseAudioContext};
+use crate::media_devices::{InputChannelLayout, MediaDeviceInfo};
+use crate::node::{AudioNode, ChannelConfig, GainNode};
+use crate::param::AudioParam;
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope, RenderThread,
+};
+use crate::RENDER_QUANTUM_SIZE;
+
+/// Prefix of the sink ids rendering through a device mixer
+pub(crate) const MIXER_SINK_PREFIX: &str = "mixer:";
+
+enum MixerBackendMessage {
+    Resume,
+    Suspend,
+    Close,
+}
+
+/// Source node of the mixer graph, rendering the graph of a context
+struct MixerInputNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+}
+
+impl AudioNode for MixerInputNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        0
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+struct MixerInputRenderer {
+    receiver: Receiver<MixerBackendMessage>,
+    render_thread: RenderThread,
+    number_of_channels: usize,
+    /// interleaved render quantum of the context
+    buffer: Vec<f32>,
+    running: bool,
+    closed: bool,
+}
+
+impl AudioProcessor for MixerInputRenderer {
+    fn process(
+        &mut self,
+        _inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &RenderScope,
+    ) -> bool {
+        let output = &mut outputs[0];
+
+        while !self.closed {
+            match self.receiver.try_recv() {
+                Ok(MixerBackendMessage::Resume) => self.running = true,
+                Ok(MixerBackendMessage::Suspend) => self.running = false,
+                // stop when the backend has been dropped, e.g. after a sink change
+                Ok(MixerBackendMessage::Close) | Err(TryRecvError::Disconnected) => {
+                    self.closed = true
+                }
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+
+        if self.closed {
+            output.make_silent();
+            return false;
+        }
+
+        if !self.running {
+            output.make_silent();
+            return true;
+        }
+
+        self.render_thread.render(&mut self.buffer[..]);
+
+        let number_of_channels = self.number_of_channels;
+        output.set_number_of_channels(number_of_channels);
+        output
+            .channels_mut()
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, channel)| {
+                let samples = self.buffer.iter().skip(i).step_by(number_of_channels);
+                channel.iter_mut().zip(samples).for_each(|(o, s)| *o = *s);
+            });
+
+        true
+    }
+}
+
+/// Nodes of a context in the mixer graph, removed when the last backend clone is dropped
+struct MixerInput {
+    _node: MixerInputNode,
+    gain: GainNode,
+}
+
+#[derive(Clone)]
+pub(crate) struct MixerBackend {
+    sender: Sender<MixerBackendMessage>,
+    input: Arc<MixerInput>,
+    sample_rate: f32,
+    device_sample_rate: f32,
+    number_of_channels: usize,
+    output_latency: f64,
+    sink_id: String,
+}
+
+impl AudioBackendManager for MixerBackend {
+    /// Setup a new output stream (speakers)
+    fn build_output(options: AudioContextOptions, render_thread_init: RenderThreadInit) -> Self
+    where
+        Self: Sized,
+    {
+        let name = options
+            .sink_id
+            .strip_prefix(MIXER_SINK_PREFIX)
+            .unwrap_or_default();
+        let mixer = crate::context::find_device_mixer(name)
+            .unwrap_or_else(|| panic!("NotFoundError - no device mixer named {name:?}"));
+
+        // the context renders at the rate and channel count of the mixer
+        let sample_rate = mixer.sample_rate();
+        let number_of_channels = mixer.destination().max_channel_count();
+
+        let RenderThreadInit {
+            frames_played,
+            ctrl_msg_recv,
+            load_value_send,
+            event_send,
+        } = render_thread_init;
+
+        let mut render_thread = RenderThread::new(
+            sample_rate,
+            number_of_channels,
+            ctrl_msg_recv,
+            frames_played,
+        );
+        render_thread.set_event_channels(load_value_send, event_send);
+        render_thread.spawn_garbage_collector_thread();
+
+        // Use a bounded channel for real-time safety. A maximum of 32 control messages (resume,
+        // suspend, ..) will be handled per render quantum. The control thread will block when the
+        // capacity is reached.
+        let (sender, receiver) = crossbeam_channel::bounded(32);
+
+        let renderer = MixerInputRenderer {
+            receiver,
+            render_thread,
+            number_of_channels,
+            buffer: vec![0.; RENDER_QUANTUM_SIZE * number_of_channels],
+            running: true,
+            closed: false,
+        };
+
+        let node = mixer.register(move |registration| {
+            let node = MixerInputNode {
+                registration,
+                channel_config: ChannelConfig::default(),
+            };
+            (node, Box::new(renderer))
+        });
+        let gain = mixer.create_gain();
+        node.connect(&gain);
+        gain.connect(&mixer.destination());
+
+        Self {
+            sender,
+            input: Arc::new(MixerInput { _node: node, gain }),
+            sample_rate,
+            device_sample_rate: mixer.device_sample_rate(),
+            number_of_channels,
+            output_latency: mixer.output_latency(),
+            sink_id: options.sink_id,
+        }
+    }
+
+    /// Setup a new input stream (microphone capture)
+    fn build_input(
+        _options: AudioContextOptions,
+        _channels: InputChannelLayout,
+    ) -> (Self, Receiver<AudioBuffer>)
+    where
+        Self: Sized,
+    {
+        unimplemented!()
+    }
+
+    /// Resume or start the stream
+    fn resume(&self) -> bool {
+        self.sender.send(MixerBackendMessage::Resume).is_ok()
+    }
+
+    /// Suspend the stream
+    fn suspend(&self) -> bool {
+        self.sender.send(MixerBackendMessage::Suspend).is_ok()
+    }
+
+    /// Close the stream, freeing all resources. It cannot be started again after closing.
+    fn close(&self) {
+        // the mixer may have been dropped already
+        let _ = self.sender.send(MixerBackendMessage::Close);
+    }
+
+    /// Sample rate of the stream
+    fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    fn device_sample_rate(&self) -> f32 {
+        self.device_sample_rate
+    }
+
+    /// Number of channels of the stream
+    fn number_of_channels(&self) -> usize {
+        self.number_of_channels
+    }
+
+    /// Output latency of the stream in seconds
+    ///
+    /// This is the difference between the time the backend acquires the data in the callback and
+    /// the listener can hear the sound.
+    fn output_latency(&self) -> f64 {
+        self.output_latency
+    }
+
+    /// The audio output device
+    fn sink_id(&self) -> &str {
+        &self.sink_id
+    }
+
+    fn mixer_gain(&self) -> Option<AudioParam> {
+        Some(self.input.gain.gain().clone())
+    }
+
+    /// Clone the stream reference
+    fn boxed_clone(&self) -> Box<dyn AudioBackendManager> {
+        Box::new(self.clone())
+    }
+
+    fn enumerate_devices_sync() -> Vec<MediaDeviceInfo>
+    where
+        Self: Sized,
+    {
+        unimplemented!()
+    }
+}
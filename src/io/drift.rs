@@ -0,0 +1,272 @@
+//! Clock drift compensation between an input stream and the render thread
+
+use std::collections::VecDeque;
+
+use rubato::{
+    Resampler, SincFixedOut, SincInterpolationParameters, SincInterpolationType, WindowFunction,
+};
+
+use crate::buffer::AudioBuffer;
+use crate::RENDER_QUANTUM_SIZE;
+
+/// Maximum correction of the resample ratio, i.e. 2000 parts per million
+const MAX_RELATIVE_RATIO: f64 = 1.002;
+/// Smoothing of the measured fill level, per pulled chunk
+const LEVEL_SMOOTHING: f64 = 0.01;
+/// Correction of the resample ratio, relative to the deviation from the target level
+const CORRECTION_GAIN: f64 = 0.01;
+/// Smoothing of the drift estimate, per pulled chunk
+const DRIFT_SMOOTHING: f64 = 0.0005;
+/// Maximum fill level relative to the target level, older frames are dropped above it
+const MAX_LEVEL_FACTOR: usize = 8;
+
+/// Adaptive resampler keeping the queue between an input device and its consumer at a stable
+/// fill level
+///
+/// The input device and the render thread are driven by different clocks when the input device
+/// is not the output device. Tiny differences in their actual sample rates would otherwise make
+/// the queue run empty (dropouts) or grow without bounds (latency and dropped frames) in long
+/// sessions. The drift is measured through the smoothed fill level of the queue, and the input
+/// is resampled by a very small ratio to compensate for it.
+pub(crate) struct DriftCompensator {
+    processor: SincFixedOut<f32>,
+    sample_rate: f32,
+    /// input frames per channel, not yet consumed
+    queue: Vec<VecDeque<f32>>,
+    samples_in: Vec<Vec<f32>>,
+    samples_out: Vec<Vec<f32>>,
+    /// fill level of the queue to maintain, in frames
+    target_level: usize,
+    /// smoothed fill level of the queue, in frames
+    level: f64,
+    /// current correction of the resample ratio
+    ratio: f64,
+    /// smoothed relative drift of the input clock
+    drift: f64,
+    /// true when enough input frames are queued to start consuming them
+    primed: bool,
+    /// keep the minimal amount of queued frames, at the expense of more frequent dropouts
+    low_latency: bool,
+    /// number of frames of the last input device callback
+    callback_frames: usize,
+}
+
+impl DriftCompensator {
+    pub fn new(number_of_channels: usize, sample_rate: f32, low_latency: bool) -> Self {
+        let parameters = SincInterpolationParameters {
+            sinc_len: 64,
+            f_cutoff: 0.95,
+            oversampling_factor: 128,
+            interpolation: SincInterpolationType::Linear,
+            window: WindowFunction::BlackmanHarris2,
+        };
+        let processor = SincFixedOut::new(
+            1.,
+            MAX_RELATIVE_RATIO,
+            parameters,
+            RENDER_QUANTUM_SIZE,
+            number_of_channels,
+        )
+        .unwrap();
+
+        let samples_in = processor.input_buffer_allocate(true);
+        let samples_out = processor.output_buffer_allocate(true);
+
+        Self {
+            processor,
+            sample_rate,
+            queue: vec![VecDeque::new(); number_of_channels],
+            samples_in,
+            samples_out,
+            target_level: if low_latency {
+                RENDER_QUANTUM_SIZE
+            } else {
+                2 * RENDER_QUANTUM_SIZE
+            },
+            level: 0.,
+            ratio: 1.,
+            drift: 0.,
+            primed: false,
+            low_latency,
+            callback_frames: 0,
+        }
+    }
+
+    /// Number of queued input frames
+    fn queued_frames(&self) -> usize {
+        self.queue[0].len()
+    }
+
+    /// Estimated relative clock drift of the input device, in parts per million
+    ///
+    /// A positive value means the input device runs faster than its consumer.
+    pub fn drift_ppm(&self) -> f64 {
+        self.drift * 1e6
+    }
+
+    /// Estimated latency in seconds between the capture of a frame by the input device and its
+    /// consumption
+    pub fn latency(&self) -> f64 {
+        let frames = self.callback_frames + self.queued_frames() + self.processor.output_delay();
+        frames as f64 / self.sample_rate as f64
+    }
+
+    /// Queue the frames of an input device callback
+    pub fn push(&mut self, buffer: &AudioBuffer) {
+        // keep room for two callbacks of the input device and a render quantum, or a single
+        // callback in low latency mode
+        let length = buffer.length();
+        let target_level = if self.low_latency {
+            length
+        } else {
+            2 * length + RENDER_QUANTUM_SIZE
+        };
+        self.callback_frames = length;
+        self.target_level = self
+            .target_level
+            .max(target_level)
+            .min(self.sample_rate as usize);
+
+        let max_level = self.target_level * MAX_LEVEL_FACTOR;
+        let overflow = (self.queued_frames() + length).saturating_sub(max_level);
+        if overflow > 0 {
+            log::debug!("input queue overrun: {} frames dropped", overflow);
+            self.queue.iter_mut().for_each(|channel| {
+                channel.drain(..overflow.min(channel.len()));
+            });
+        }
+
+        let number_of_channels = buffer.number_of_channels();
+        self.queue.iter_mut().enumerate().for_each(|(i, channel)| {
+            // upmix mono input devices by copying the single channel
+            let data = buffer.get_channel_data(i.min(number_of_channels - 1));
+            channel.extend(data.iter().skip(length.saturating_sub(max_level)));
+        });
+    }
+
+    /// Take a chunk of `RENDER_QUANTUM_SIZE` frames, `None` when not enough frames are queued
+    pub fn pull(&mut self) -> Option<AudioBuffer> {
+        let queued = self.queued_frames();
+
+        if !self.primed {
+            if queued < self.target_level {
+                return None;
+            }
+            self.primed = true;
+            self.level = queued as f64;
+        }
+
+        let needed = self.processor.input_frames_next();
+        if queued < needed {
+            log::debug!("input queue underrun");
+            self.primed = false;
+            return None;
+        }
+
+        self.queue
+            .iter_mut()
+            .zip(self.samples_in.iter_mut())
+            .for_each(|(channel, samples)| {
+                samples
+                    .iter_mut()
+                    .zip(channel.drain(..needed))
+                    .for_each(|(o, i)| *o = i);
+            });
+
+        let (_, frames) = self
+            .processor
+            .process_into_buffer(&self.samples_in, &mut self.samples_out, None)
+            .unwrap();
+        debug_assert_eq!(frames, RENDER_QUANTUM_SIZE);
+
+        // consume faster when the queue grows, slower when it shrinks
+        self.level += LEVEL_SMOOTHING * (self.queued_frames() as f64 - self.level);
+        let deviation = (self.level - self.target_level as f64) / self.target_level as f64;
+        self.ratio =
+            (1. - CORRECTION_GAIN * deviation).clamp(1. / MAX_RELATIVE_RATIO, MAX_RELATIVE_RATIO);
+        self.processor
+            .set_resample_ratio_relative(self.ratio, true)
+            .unwrap();
+        self.drift += DRIFT_SMOOTHING * (1. / self.ratio - 1. - self.drift);
+
+        Some(AudioBuffer::from(
+            self.samples_out.clone(),
+            self.sample_rate,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run an input device at `drift_ppm` relative to its consumer for `duration` seconds, with
+    /// callbacks of 480 frames
+    fn simulate(
+        drift_ppm: f64,
+        duration: f64,
+        low_latency: bool,
+    ) -> (DriftCompensator, usize, usize) {
+        let sample_rate = 48000.;
+        let mut compensator = DriftCompensator::new(1, sample_rate, low_latency);
+        let input_rate = 1. + drift_ppm / 1e6;
+
+        let mut produced = 0;
+        let mut underruns = 0;
+        let mut max_queued = 0;
+        let pulls = (duration * sample_rate as f64) as usize / RENDER_QUANTUM_SIZE;
+
+        for i in 0..pulls {
+            let consumed = (i * RENDER_QUANTUM_SIZE) as f64;
+            while (produced as f64) < consumed * input_rate {
+                let chunk = AudioBuffer::from(vec![vec![0.5; 480]], sample_rate);
+                compensator.push(&chunk);
+                produced += 480;
+            }
+
+            match compensator.pull() {
+                Some(buffer) => assert_eq!(buffer.length(), RENDER_QUANTUM_SIZE),
+                None if i * RENDER_QUANTUM_SIZE > 48000 => underruns += 1,
+                None => (),
+            }
+            max_queued = max_queued.max(compensator.queued_frames());
+        }
+
+        (compensator, underruns, max_queued)
+    }
+
+    #[test]
+    fn test_fast_input_device() {
+        let (compensator, underruns, max_queued) = simulate(500., 30., false);
+        assert_eq!(underruns, 0);
+        assert!(max_queued < 2 * compensator.target_level);
+        assert!((compensator.drift_ppm() - 500.).abs() < 50.);
+    }
+
+    #[test]
+    fn test_slow_input_device() {
+        let (compensator, underruns, max_queued) = simulate(-500., 30., false);
+        assert_eq!(underruns, 0);
+        assert!(max_queued < 2 * compensator.target_level);
+        assert!((compensator.drift_ppm() + 500.).abs() < 50.);
+    }
+
+    #[test]
+    fn test_same_clock() {
+        let (compensator, underruns, _) = simulate(0., 10., false);
+        assert_eq!(underruns, 0);
+        assert!(compensator.drift_ppm().abs() < 50.);
+    }
+
+    #[test]
+    fn test_low_latency() {
+        let (regular, _, _) = simulate(0., 2., false);
+        let (compensator, underruns, max_queued) = simulate(0., 10., true);
+        assert_eq!(underruns, 0);
+        assert!(max_queued < 2 * compensator.target_level);
+
+        // one callback of 480 frames less queued
+        assert!(compensator.latency() > 480. / 48000.);
+        assert!(compensator.latency() < regular.latency() - 400. / 48000.);
+    }
+}
@@ -0,0 +1,373 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use super::{AudioBackendManager, RenderThreadInit};
+
+use crate::buffer::AudioBuffer;
+use crate::context::AudioContextOptions;
+use crate::media_devices::{InputChannelLayout, MediaDeviceInfo};
+use crate::render::RenderThread;
+use crate::MAX_CHANNELS;
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+
+/// Prefix of the sink ids rendering to a file
+pub(crate) const FILE_SINK_PREFIX: &str = "file:";
+
+/// Settings of a file sink, parsed from a sink id like `"file:out.wav?speed=4&channels=1"`
+#[derive(Debug, PartialEq)]
+pub(crate) struct FileSinkConfig {
+    path: PathBuf,
+    /// playback rate relative to real time, `None` to render as fast as possible
+    speed: Option<f64>,
+    number_of_channels: usize,
+    /// sample rate of the file, `None` to use the context sample rate
+    sample_rate: Option<f32>,
+}
+
+impl FileSinkConfig {
+    pub fn parse(sink_id: &str) -> Result<Self, String> {
+        let spec = sink_id
+            .strip_prefix(FILE_SINK_PREFIX)
+            .ok_or_else(|| format!("NotFoundError: invalid sinkId {sink_id}"))?;
+
+        let (path, query) = match spec.rsplit_once('?') {
+            Some((path, query)) => (path, query),
+            None => (spec, ""),
+        };
+        if path.is_empty() {
+            return Err(format!(
+                "NotFoundError: missing file path in sinkId {sink_id}"
+            ));
+        }
+
+        let mut config = Self {
+            path: PathBuf::from(path),
+            speed: Some(1.),
+            number_of_channels: 2,
+            sample_rate: None,
+        };
+
+        for param in query.split('&').filter(|p| !p.is_empty()) {
+            let invalid =
+                || format!("NotFoundError: invalid parameter {param:?} in sinkId {sink_id}");
+
+            match param.split_once('=').ok_or_else(invalid)? {
+                ("speed", "max") => config.speed = None,
+                ("speed", value) => {
+                    let speed = value.parse::<f64>().map_err(|_| invalid())?;
+                    if !(speed > 0. && speed.is_finite()) {
+                        return Err(invalid());
+                    }
+                    config.speed = Some(speed);
+                }
+                ("channels", value) => {
+                    let channels = value.parse::<usize>().map_err(|_| invalid())?;
+                    if channels == 0 || channels > MAX_CHANNELS {
+                        return Err(invalid());
+                    }
+                    config.number_of_channels = channels;
+                }
+                ("rate", value) => {
+                    let sample_rate = value.parse::<f32>().map_err(|_| invalid())?;
+                    // same lower bound as `assert_valid_sample_rate`
+                    if !(sample_rate > 1000. && sample_rate.is_finite()) {
+                        return Err(invalid());
+                    }
+                    config.sample_rate = Some(sample_rate);
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Check if the file can be created
+    pub fn is_valid(sink_id: &str) -> bool {
+        Self::parse(sink_id).is_ok_and(|config| match config.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.is_dir(),
+            _ => true, // relative to the working directory
+        })
+    }
+}
+
+enum FileBackendMessage {
+    Resume,
+    Suspend,
+    Close,
+}
+
+type Writer = hound::WavWriter<BufWriter<File>>;
+
+#[derive(Clone)]
+pub(crate) struct FileBackend {
+    sender: Sender<FileBackendMessage>,
+    sample_rate: f32,
+    device_sample_rate: f32,
+    number_of_channels: usize,
+    sink_id: String,
+    thread: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+struct Callback {
+    receiver: Receiver<FileBackendMessage>,
+    render_thread: RenderThread,
+    writer: Option<Writer>,
+    sample_rate: f32,
+    buffer_size: usize,
+    number_of_channels: usize,
+    speed: Option<f64>,
+    running: bool,
+}
+
+impl Callback {
+    /// Deadline of the next callback, `None` when rendering as fast as possible
+    fn deadline(&self, clock_start: Instant, callbacks: u64) -> Option<Instant> {
+        let speed = self.speed?;
+        let frames = callbacks * self.buffer_size as u64;
+        let elapsed = frames as f64 / self.sample_rate as f64 / speed;
+        Some(clock_start + Duration::from_secs_f64(elapsed))
+    }
+
+    fn write(&mut self, buffer: &[f32]) {
+        if let Some(writer) = self.writer.as_mut() {
+            if let Err(e) = buffer.iter().try_for_each(|&s| writer.write_sample(s)) {
+                log::error!("an error occurred writing the audio file: {}", e);
+                self.writer = None;
+            }
+        }
+    }
+
+    fn run(mut self) {
+        let mut buffer = vec![0.; self.buffer_size * self.number_of_channels];
+
+        // Same clock as the "none" backend, but possibly scaled to an accelerated rate
+        let mut clock_start = Instant::now();
+        let mut callbacks = 1;
+
+        loop {
+            let deadline = self.deadline(clock_start, callbacks);
+
+            // poll the receiver as long as the deadline is in the future, block when suspended
+            loop {
+                let msg = match (self.running, deadline) {
+                    (false, _) => self
+                        .receiver
+                        .recv()
+                        .map_err(|_| RecvTimeoutError::Disconnected),
+                    (true, Some(deadline)) => self.receiver.recv_deadline(deadline),
+                    (true, None) => self
+                        .receiver
+                        .try_recv()
+                        .map_err(|_| RecvTimeoutError::Timeout),
+                };
+
+                match msg {
+                    Ok(FileBackendMessage::Close) | Err(RecvTimeoutError::Disconnected) => {
+                        if let Some(Err(e)) = self.writer.take().map(Writer::finalize) {
+                            log::error!("an error occurred finalizing the audio file: {}", e);
+                        }
+                        return;
+                    }
+                    Ok(FileBackendMessage::Resume) => {
+                        self.running = true;
+                        clock_start = Instant::now();
+                        callbacks = 0;
+                        break; // start processing right away
+                    }
+                    Ok(FileBackendMessage::Suspend) => self.running = false,
+                    Err(RecvTimeoutError::Timeout) => break,
+                }
+            }
+
+            if self.running {
+                self.render_thread.render(&mut buffer[..]);
+                self.write(&buffer);
+            }
+
+            callbacks += 1;
+        }
+    }
+}
+
+impl AudioBackendManager for FileBackend {
+    /// Setup a new output stream (a WAV file)
+    fn build_output(options: AudioContextOptions, render_thread_init: RenderThreadInit) -> Self
+    where
+        Self: Sized,
+    {
+        let config = FileSinkConfig::parse(&options.sink_id).unwrap();
+        let sample_rate = options.sample_rate.unwrap_or(48000.);
+        let device_sample_rate = config.sample_rate.unwrap_or(sample_rate);
+
+        let spec = hound::WavSpec {
+            channels: config.number_of_channels as u16,
+            sample_rate: device_sample_rate.round() as u32,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let writer = hound::WavWriter::create(&config.path, spec).unwrap_or_else(|e| {
+            panic!(
+                "NotFoundError: unable to create the audio file {:?}: {}",
+                config.path, e
+            )
+        });
+
+        let RenderThreadInit {
+            frames_played,
+            ctrl_msg_recv,
+            load_value_send,
+            event_send,
+        } = render_thread_init;
+
+        let mut render_thread = RenderThread::new(
+            sample_rate,
+            config.number_of_channels,
+            ctrl_msg_recv,
+            frames_played,
+        );
+        render_thread.set_event_channels(load_value_send, event_send);
+        render_thread.set_device_sample_rate(device_sample_rate);
+        render_thread.spawn_garbage_collector_thread();
+
+        let (sender, receiver) = crossbeam_channel::bounded(32);
+
+        let buffer_size =
+            super::buffer_size_for_latency_category(options.latency_hint, device_sample_rate);
+        let callback = Callback {
+            render_thread,
+            receiver,
+            writer: Some(writer),
+            sample_rate: device_sample_rate,
+            buffer_size,
+            number_of_channels: config.number_of_channels,
+            speed: config.speed,
+            running: true,
+        };
+
+        let thread = thread::spawn(move || callback.run());
+
+        Self {
+            sender,
+            sample_rate,
+            device_sample_rate,
+            number_of_channels: config.number_of_channels,
+            sink_id: options.sink_id,
+            thread: Arc::new(Mutex::new(Some(thread))),
+        }
+    }
+
+    /// Setup a new input stream (microphone capture)
+    fn build_input(
+        _options: AudioContextOptions,
+        _channels: InputChannelLayout,
+    ) -> (Self, Receiver<AudioBuffer>)
+    where
+        Self: Sized,
+    {
+        unimplemented!()
+    }
+
+    /// Resume or start the stream
+    fn resume(&self) -> bool {
+        self.sender.send(FileBackendMessage::Resume).unwrap();
+        true
+    }
+
+    /// Suspend the stream
+    fn suspend(&self) -> bool {
+        self.sender.send(FileBackendMessage::Suspend).unwrap();
+        true
+    }
+
+    /// Close the stream, freeing all resources. It cannot be started again after closing.
+    ///
+    /// This blocks until the audio file is finalized.
+    fn close(&self) {
+        self.sender.send(FileBackendMessage::Close).unwrap();
+        if let Some(thread) = self.thread.lock().unwrap().take() {
+            let _ = thread.join();
+        }
+    }
+
+    /// Sample rate of the stream
+    fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Sample rate of the file
+    fn device_sample_rate(&self) -> f32 {
+        self.device_sample_rate
+    }
+
+    /// Number of channels of the stream
+    fn number_of_channels(&self) -> usize {
+        self.number_of_channels
+    }
+
+    /// Output latency of the stream in seconds
+    fn output_latency(&self) -> f64 {
+        0.
+    }
+
+    /// The audio output device
+    fn sink_id(&self) -> &str {
+        &self.sink_id
+    }
+
+    /// Clone the stream reference
+    fn boxed_clone(&self) -> Box<dyn AudioBackendManager> {
+        Box::new(self.clone())
+    }
+
+    fn enumerate_devices_sync() -> Vec<MediaDeviceInfo>
+    where
+        Self: Sized,
+    {
+        unimplemented!()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sink_id() {
+        let config = FileSinkConfig::parse("file:out.wav").unwrap();
+        assert_eq!(config.path, PathBuf::from("out.wav"));
+        assert_eq!(config.speed, Some(1.));
+        assert_eq!(config.number_of_channels, 2);
+
+        let config = FileSinkConfig::parse("file:/tmp/out.wav?speed=max&channels=1").unwrap();
+        assert_eq!(config.path, PathBuf::from("/tmp/out.wav"));
+        assert_eq!(config.speed, None);
+        assert_eq!(config.number_of_channels, 1);
+
+        let config = FileSinkConfig::parse("file:out.wav?speed=2.5").unwrap();
+        assert_eq!(config.speed, Some(2.5));
+        assert_eq!(config.sample_rate, None);
+
+        let config = FileSinkConfig::parse("file:out.wav?rate=44100").unwrap();
+        assert_eq!(config.sample_rate, Some(44100.));
+    }
+
+    #[test]
+    fn test_parse_invalid_sink_id() {
+        assert!(FileSinkConfig::parse("out.wav").is_err());
+        assert!(FileSinkConfig::parse("file:").is_err());
+        assert!(FileSinkConfig::parse("file:out.wav?speed=0").is_err());
+        assert!(FileSinkConfig::parse("file:out.wav?speed=fast").is_err());
+        assert!(FileSinkConfig::parse("file:out.wav?channels=0").is_err());
+        assert!(FileSinkConfig::parse("file:out.wav?gain=2").is_err());
+        assert!(FileSinkConfig::parse("file:out.wav?rate=0").is_err());
+
+        assert!(!FileSinkConfig::is_valid("file:/non/existing/dir/out.wav"));
+        assert!(FileSinkConfig::is_valid("file:out.wav"));
+    }
+}
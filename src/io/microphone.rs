@@ -1,8 +1,12 @@
 use std::error::Error;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 
 use crate::buffer::{AudioBuffer, AudioBufferOptions};
+use crate::io::drift::DriftCompensator;
 use crate::io::AudioBackendManager;
-use crate::RENDER_QUANTUM_SIZE;
+use crate::media_devices::InputChannelLayout;
+use crate::{AtomicF64, RENDER_QUANTUM_SIZE};
 
 use crossbeam_channel::{Receiver, Sender, TryRecvError};
 
@@ -10,6 +14,10 @@ pub(crate) struct MicrophoneStream {
     receiver: Receiver<AudioBuffer>,
     number_of_channels: usize,
     sample_rate: f32,
+    /// compensates for the clock drift between the input device and the render thread
+    compensator: DriftCompensator,
+    /// latency of the input, reported to the media stream track
+    latency: Arc<AtomicF64>,
     stream: Box<dyn AudioBackendManager>,
 }
 
@@ -17,19 +25,33 @@ impl MicrophoneStream {
     pub(crate) fn new(
         receiver: Receiver<AudioBuffer>,
         backend: Box<dyn AudioBackendManager>,
+        low_latency: bool,
     ) -> Self {
+        let number_of_channels = backend.number_of_channels();
+        let sample_rate = backend.sample_rate();
+
         Self {
             receiver,
-            number_of_channels: backend.number_of_channels(),
-            sample_rate: backend.sample_rate(),
+            number_of_channels,
+            sample_rate,
+            compensator: DriftCompensator::new(number_of_channels, sample_rate, low_latency),
+            latency: Arc::new(AtomicF64::new(0.)),
             stream: backend,
         }
     }
+
+    /// Shared value of the input latency, in seconds
+    pub(crate) fn latency(&self) -> Arc<AtomicF64> {
+        Arc::clone(&self.latency)
+    }
 }
 
 impl Drop for MicrophoneStream {
     fn drop(&mut self) {
-        log::debug!("Microphone stream has been dropped");
+        log::debug!(
+            "Microphone stream has been dropped, estimated clock drift: {:.1} ppm",
+            self.compensator.drift_ppm()
+        );
         self.stream.close()
     }
 }
@@ -38,61 +60,64 @@ impl Iterator for MicrophoneStream {
     type Item = Result<AudioBuffer, Box<dyn Error + Send + Sync>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let next = match self.receiver.try_recv() {
-            Ok(buffer) => {
-                // new frame was ready
-                buffer
+        // queue all the frames received from the input device
+        loop {
+            match self.receiver.try_recv() {
+                Ok(buffer) => self.compensator.push(&buffer),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    // MicrophoneRender has stopped, close stream
+                    return None;
+                }
             }
-            Err(TryRecvError::Empty) => {
-                // frame not received in time, emit silence
-                log::debug!("empty channel: input frame delayed");
+        }
 
-                let options = AudioBufferOptions {
-                    number_of_channels: self.number_of_channels,
-                    length: RENDER_QUANTUM_SIZE,
-                    sample_rate: self.sample_rate,
-                };
+        self.latency
+            .store(self.compensator.latency(), Ordering::Relaxed);
 
-                AudioBuffer::new(options)
-            }
-            Err(TryRecvError::Disconnected) => {
-                // MicrophoneRender has stopped, close stream
-                return None;
-            }
-        };
+        let next = self.compensator.pull().unwrap_or_else(|| {
+            // frame not received in time, emit silence
+            log::debug!("empty queue: input frame delayed");
+
+            let options = AudioBufferOptions {
+                number_of_channels: self.number_of_channels,
+                length: RENDER_QUANTUM_SIZE,
+                sample_rate: self.sample_rate,
+            };
+
+            AudioBuffer::new(options)
+        });
 
         Some(Ok(next))
     }
 }
 
 pub(crate) struct MicrophoneRender {
+    /// number of channels of the input device
     number_of_channels: usize,
     sample_rate: f32,
+    /// channels of the input device forwarded to the media stream
+    layout: InputChannelLayout,
     sender: Sender<AudioBuffer>,
 }
 
 impl MicrophoneRender {
-    pub fn new(number_of_channels: usize, sample_rate: f32, sender: Sender<AudioBuffer>) -> Self {
+    pub fn new(
+        number_of_channels: usize,
+        sample_rate: f32,
+        layout: InputChannelLayout,
+        sender: Sender<AudioBuffer>,
+    ) -> Self {
         Self {
             number_of_channels,
             sample_rate,
+            layout,
             sender,
         }
     }
 
     pub fn render<S: dasp_sample::ToSample<f32> + Copy>(&self, data: &[S]) {
-        let mut channels = Vec::with_capacity(self.number_of_channels);
-
-        // copy rendered audio into output slice
-        for i in 0..self.number_of_channels {
-            channels.push(
-                data.iter()
-                    .skip(i)
-                    .step_by(self.number_of_channels)
-                    .map(|v| v.to_sample_())
-                    .collect(),
-            );
-        }
+        let channels = self.layout.deinterleave(data, self.number_of_channels);
 
         let buffer = AudioBuffer::from(channels, self.sample_rate);
         let result = self.sender.try_send(buffer); // can fail (frame dropped)
@@ -1,6 +1,5 @@
 //! Audio input/output interfaces
 
-use std::sync::atomic::AtomicU64;
 use std::sync::Arc;
 
 use crossbeam_channel::{Receiver, Sender};
@@ -8,11 +7,16 @@ use crossbeam_channel::{Receiver, Sender};
 use crate::buffer::AudioBuffer;
 use crate::context::{AudioContextLatencyCategory, AudioContextOptions};
 use crate::events::EventDispatch;
-use crate::media_devices::MediaDeviceInfo;
+use crate::media_devices::{InputChannelLayout, MediaDeviceInfo};
 use crate::media_streams::{MediaStream, MediaStreamTrack};
 use crate::message::ControlMessage;
+use crate::param::AudioParam;
+use crate::sync::atomic::AtomicU64;
+use crate::sync::channel;
 use crate::{AudioRenderCapacityLoad, RENDER_QUANTUM_SIZE};
 
+pub(crate) mod device_mixer;
+pub(crate) mod file;
 mod none;
 
 #[cfg(feature = "cpal")]
@@ -24,10 +28,13 @@ mod cubeb;
 #[cfg(any(feature = "cubeb", feature = "cpal"))]
 mod microphone;
 
+#[cfg(any(feature = "cubeb", feature = "cpal", test))]
+mod drift;
+
 #[derive(Debug)]
 pub(crate) struct ControlThreadInit {
     pub frames_played: Arc<AtomicU64>,
-    pub ctrl_msg_send: Sender<ControlMessage>,
+    pub ctrl_msg_send: channel::Sender<ControlMessage>,
     pub load_value_recv: Receiver<AudioRenderCapacityLoad>,
     pub event_send: Sender<EventDispatch>,
     pub event_recv: Receiver<EventDispatch>,
@@ -36,7 +43,7 @@ pub(crate) struct ControlThreadInit {
 #[derive(Clone, Debug)]
 pub(crate) struct RenderThreadInit {
     pub frames_played: Arc<AtomicU64>,
-    pub ctrl_msg_recv: Receiver<ControlMessage>,
+    pub ctrl_msg_recv: channel::Receiver<ControlMessage>,
     pub load_value_send: Sender<AudioRenderCapacityLoad>,
     pub event_send: Sender<EventDispatch>,
 }
@@ -49,7 +56,7 @@ pub(crate) fn thread_init() -> (ControlThreadInit, RenderThreadInit) {
     // Use a bounded channel for real-time safety. A maximum of 256 control messages (add/remove
     // node, settings, ..) will be handled per render quantum. The control thread will block when
     // the capacity is reached.
-    let (ctrl_msg_send, ctrl_msg_recv) = crossbeam_channel::bounded(256);
+    let (ctrl_msg_send, ctrl_msg_recv) = channel::bounded(256);
 
     // Communication channel for render load values.
     // A dedicated thread is consuming these messages so there is no need for buffering.
@@ -78,7 +85,8 @@ pub(crate) fn thread_init() -> (ControlThreadInit, RenderThreadInit) {
     (control_thread_init, render_thread_init)
 }
 
-/// Set up an output stream (speakers) bases on the selected features (cubeb/cpal/none)
+/// Set up an output stream (speakers) bases on the selected features (cubeb/cpal/none), or the
+/// file sink or a device mixer
 pub(crate) fn build_output(
     options: AudioContextOptions,
     render_thread_init: RenderThreadInit,
@@ -88,6 +96,16 @@ pub(crate) fn build_output(
         return Box::new(backend);
     }
 
+    if options.sink_id.starts_with(device_mixer::MIXER_SINK_PREFIX) {
+        let backend = device_mixer::MixerBackend::build_output(options, render_thread_init);
+        return Box::new(backend);
+    }
+
+    if options.sink_id.starts_with(file::FILE_SINK_PREFIX) {
+        let backend = file::FileBackend::build_output(options, render_thread_init);
+        return Box::new(backend);
+    }
+
     #[cfg(feature = "cubeb")]
     {
         let backend = cubeb::CubebBackend::build_output(options, render_thread_init);
@@ -104,8 +122,22 @@ pub(crate) fn build_output(
     }
 }
 
+/// Settings of an input stream, besides the [`AudioContextOptions`]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(not(any(feature = "cubeb", feature = "cpal")), allow(dead_code))]
+pub(crate) struct InputOptions {
+    /// minimize the buffering of the input, for live monitoring
+    pub low_latency: bool,
+    /// channels of the input device to capture
+    pub channels: InputChannelLayout,
+}
+
 /// Set up an input stream (microphone) bases on the selected features (cubeb/cpal/none)
-pub(crate) fn build_input(options: AudioContextOptions) -> MediaStream {
+#[allow(unused_variables)] // when no audio backend is enabled
+pub(crate) fn build_input(
+    options: AudioContextOptions,
+    input_options: InputOptions,
+) -> MediaStream {
     #[cfg(all(not(feature = "cubeb"), not(feature = "cpal")))]
     {
         panic!("No audio backend available, enable the 'cpal' or 'cubeb' feature")
@@ -116,17 +148,22 @@ pub(crate) fn build_input(options: AudioContextOptions) -> MediaStream {
         let (backend, receiver) = {
             #[cfg(feature = "cubeb")]
             {
-                cubeb::CubebBackend::build_input(options)
+                cubeb::CubebBackend::build_input(options, input_options.channels)
             }
 
             #[cfg(all(not(feature = "cubeb"), feature = "cpal"))]
             {
-                cpal::CpalBackend::build_input(options)
+                cpal::CpalBackend::build_input(options, input_options.channels)
             }
         };
 
-        let media_iter = microphone::MicrophoneStream::new(receiver, Box::new(backend));
-        let track = MediaStreamTrack::from_iter(media_iter);
+        let media_iter = microphone::MicrophoneStream::new(
+            receiver,
+            Box::new(backend),
+            input_options.low_latency,
+        );
+        let latency = media_iter.latency();
+        let track = MediaStreamTrack::from_iter_with_latency(media_iter, Some(latency));
         MediaStream::from_tracks(vec![track])
     }
 }
@@ -139,7 +176,12 @@ pub(crate) trait AudioBackendManager: Send + Sync + 'static {
         Self: Sized;
 
     /// Setup a new input stream (microphone capture)
-    fn build_input(options: AudioContextOptions) -> (Self, Receiver<AudioBuffer>)
+    ///
+    /// The number of channels of the stream is the number of channels of the `channels` layout.
+    fn build_input(
+        options: AudioContextOptions,
+        channels: InputChannelLayout,
+    ) -> (Self, Receiver<AudioBuffer>)
     where
         Self: Sized;
 
@@ -155,6 +197,14 @@ pub(crate) trait AudioBackendManager: Send + Sync + 'static {
     /// Sample rate of the stream
     fn sample_rate(&self) -> f32;
 
+    /// Sample rate of the audio device
+    ///
+    /// This differs from [`Self::sample_rate`] when the rendered audio is resampled because the
+    /// device does not support the sample rate of the context.
+    fn device_sample_rate(&self) -> f32 {
+        self.sample_rate()
+    }
+
     /// Number of channels of the stream
     fn number_of_channels(&self) -> usize;
 
@@ -167,6 +217,11 @@ pub(crate) trait AudioBackendManager: Send + Sync + 'static {
     /// The audio output device - `""` means the default device
     fn sink_id(&self) -> &str;
 
+    /// Gain of the context in the mixer graph, if the stream is shared through a device mixer
+    fn mixer_gain(&self) -> Option<AudioParam> {
+        None
+    }
+
     /// Clone the stream reference
     fn boxed_clone(&self) -> Box<dyn AudioBackendManager>;
 
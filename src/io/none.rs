@@ -5,11 +5,11 @@ use super::{AudioBackendManager, RenderThreadInit};
 
 use crate::buffer::AudioBuffer;
 use crate::context::AudioContextOptions;
-use crate::media_devices::MediaDeviceInfo;
+use crate::media_devices::{InputChannelLayout, MediaDeviceInfo};
 use crate::render::RenderThread;
-use crate::{MAX_CHANNELS, RENDER_QUANTUM_SIZE};
+use crate::MAX_CHANNELS;
 
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
 
 enum NoneBackendMessage {
     Resume,
@@ -27,29 +27,42 @@ struct Callback {
     receiver: Receiver<NoneBackendMessage>,
     render_thread: RenderThread,
     sample_rate: f32,
+    buffer_size: usize,
     running: bool,
 }
 
 impl Callback {
+    /// Time elapsed since the start of the clock, after the given number of callbacks
+    fn clock_time(&self, callbacks: u64) -> Duration {
+        let frames = callbacks * self.buffer_size as u64;
+        Duration::from_secs_f64(frames as f64 / self.sample_rate as f64)
+    }
+
     fn run(mut self) {
-        let buffer_size = RENDER_QUANTUM_SIZE; // TODO Latency Category
-        let mut buffer = vec![0.; buffer_size * MAX_CHANNELS];
-        let interval = Duration::from_secs_f32(buffer_size as f32 / self.sample_rate);
+        let mut buffer = vec![0.; self.buffer_size * MAX_CHANNELS];
 
-        // For an isochronous callback we must calculate the deadline every render quantum
-        let mut deadline = Instant::now().checked_add(interval).unwrap();
+        // For an isochronous callback we must calculate the deadline every callback. The deadlines
+        // are derived from the number of callbacks since the start of the clock, rather than being
+        // accumulated, so the rendered frames do not drift from the wall clock.
+        let mut clock_start = Instant::now();
+        let mut callbacks = 1;
 
         loop {
+            let deadline = clock_start + self.clock_time(callbacks);
+
             // poll the receiver as long as the deadline is in the future
-            while let Ok(msg) = self.receiver.recv_deadline(deadline) {
-                match msg {
-                    NoneBackendMessage::Close => return,
-                    NoneBackendMessage::Resume => {
+            loop {
+                match self.receiver.recv_deadline(deadline) {
+                    // stop when the backend has been dropped, e.g. after a sink change
+                    Ok(NoneBackendMessage::Close) | Err(RecvTimeoutError::Disconnected) => return,
+                    Ok(NoneBackendMessage::Resume) => {
                         self.running = true;
-                        deadline = Instant::now().checked_add(interval).unwrap();
+                        clock_start = Instant::now();
+                        callbacks = 0;
                         break; // start processing right away
                     }
-                    NoneBackendMessage::Suspend => self.running = false,
+                    Ok(NoneBackendMessage::Suspend) => self.running = false,
+                    Err(RecvTimeoutError::Timeout) => break,
                 }
             }
 
@@ -57,7 +70,7 @@ impl Callback {
                 self.render_thread.render(&mut buffer[..]);
             }
 
-            deadline = deadline.checked_add(interval).unwrap();
+            callbacks += 1;
         }
     }
 }
@@ -87,11 +100,13 @@ impl AudioBackendManager for NoneBackend {
         // capacity is reached.
         let (sender, receiver) = crossbeam_channel::bounded(32);
 
-        // todo: pass buffer size and sample rate
+        let buffer_size =
+            super::buffer_size_for_latency_category(options.latency_hint, sample_rate);
         let callback = Callback {
             render_thread,
             receiver,
             sample_rate,
+            buffer_size,
             running: true,
         };
 
@@ -104,7 +119,10 @@ impl AudioBackendManager for NoneBackend {
     }
 
     /// Setup a new input stream (microphone capture)
-    fn build_input(_options: AudioContextOptions) -> (Self, Receiver<AudioBuffer>)
+    fn build_input(
+        _options: AudioContextOptions,
+        _channels: InputChannelLayout,
+    ) -> (Self, Receiver<AudioBuffer>)
     where
         Self: Sized,
     {
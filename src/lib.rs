@@ -41,14 +41,24 @@
 #![deny(trivial_numeric_casts)]
 
 use std::error::Error;
-use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::atomic::Ordering;
+
+use crate::sync::atomic::{AtomicU32, AtomicU64};
 
 /// Render quantum size, the audio graph is rendered in blocks of RENDER_QUANTUM_SIZE samples
 /// see. <https://webaudio.github.io/web-audio-api/#render-quantum>
 pub(crate) const RENDER_QUANTUM_SIZE: usize = 128;
 
 /// Maximum number of channels for audio processing
-pub const MAX_CHANNELS: usize = 32;
+///
+/// Defaults to 64 which allows for up to 7th order ambisonics, enable the
+/// `high-channel-count` feature to raise it to 256.
+#[cfg(not(feature = "high-channel-count"))]
+pub const MAX_CHANNELS: usize = 64;
+
+/// Maximum number of channels for audio processing
+#[cfg(feature = "high-channel-count")]
+pub const MAX_CHANNELS: usize = 256;
 
 mod buffer;
 pub use buffer::*;
@@ -56,16 +66,44 @@ pub use buffer::*;
 mod capacity;
 pub use capacity::*;
 
+mod decibels;
+#[cfg(feature = "mmap")]
+mod mapped;
+pub use decibels::{db_to_gain, gain_to_db};
+
+mod memory;
+pub use memory::{MemoryReport, QuotaExceededError, ResourceQuotas};
+
+mod metadata;
+pub use metadata::{BroadcastExtension, DecodedAudio, LoopMode, LoopPoint, Marker};
+
+#[cfg(feature = "alloc-guard")]
+pub mod alloc_guard;
+pub mod automation;
+pub mod container;
 pub mod context;
 
+pub mod dsp;
+pub mod graph;
 pub mod media_devices;
 pub mod media_recorder;
 pub mod media_streams;
+pub mod mixer;
+pub mod music;
+#[cfg(feature = "osc")]
+pub mod osc;
+pub mod patch;
+pub mod playback_queue;
+pub mod stems;
+pub mod transport;
 
 pub mod node;
 
+mod watchdog;
+pub use watchdog::*;
+
 mod events;
-pub use events::{ErrorEvent, Event};
+pub use events::{ContextEvent, ErrorEvent, Event, EventStream, NextEvent};
 
 mod param;
 pub use param::*;
@@ -74,14 +112,17 @@ mod periodic_wave;
 pub use periodic_wave::*;
 
 pub mod render;
+pub mod rtpc;
 
 mod spatial;
-pub use spatial::AudioListener;
+pub use spatial::{AudioListener, CoordinateSystem, Handedness, UpAxis};
 
 mod io;
 
 mod analysis;
+mod lookback;
 mod message;
+mod sync;
 
 mod decoding;
 
@@ -90,6 +131,9 @@ pub use media_element::MediaElement;
 
 mod resampling;
 
+mod sofa;
+pub use sofa::SofaHrtf;
+
 #[derive(Debug)]
 #[repr(transparent)]
 pub(crate) struct AtomicF32 {
@@ -170,8 +214,7 @@ pub(crate) fn assert_valid_sample_rate(sample_rate: f32) {
 /// # Panics
 ///
 /// This function will panic if:
-/// - the given number of channels is outside the [1, 32] range,
-/// 32 being defined by the MAX_CHANNELS constant.
+/// - the given number of channels is outside the [1, MAX_CHANNELS] range.
 ///
 #[track_caller]
 #[inline(always)]
@@ -256,12 +299,14 @@ mod tests {
     #[test]
     #[should_panic]
     fn test_invalid_number_of_channels_max() {
-        assert_valid_number_of_channels(33);
+        assert_valid_number_of_channels(MAX_CHANNELS + 1);
     }
 
     #[test]
     fn test_valid_number_of_channels() {
         assert_valid_number_of_channels(1);
         assert_valid_number_of_channels(32);
+        assert_valid_number_of_channels(64);
+        assert_valid_number_of_channels(MAX_CHANNELS);
     }
 }
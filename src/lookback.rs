@@ -0,0 +1,199 @@
+//! Recording of the most recent output of a node
+//!
+//! See [`AudioNode::enable_lookback`](crate::node::AudioNode::enable_lookback)
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::buffer::AudioBuffer;
+use crate::render::AudioRenderQuantum;
+use crate::{AtomicF32, RENDER_QUANTUM_SIZE};
+
+#[track_caller]
+fn assert_valid_duration(duration: f64) {
+    if !(duration > 0. && duration.is_finite()) {
+        panic!(
+            "RangeError - lookback duration ({:?}) should be strictly positive and finite",
+            duration
+        );
+    }
+}
+
+struct LookbackRing {
+    channels: Box<[Box<[AtomicF32]>]>,
+    /// total number of frames written since the buffer was created
+    written: AtomicUsize,
+    /// number of frames returned by a dump
+    length: usize,
+    sample_rate: f32,
+}
+
+/// Single producer / multiple consumer ring buffer of the last `length` frames of a node output
+///
+/// The render thread writes a render quantum at a time, the control thread copies the recorded
+/// frames into an `AudioBuffer` on demand.
+#[derive(Clone)]
+pub(crate) struct LookbackBuffer {
+    inner: Arc<LookbackRing>,
+}
+
+impl LookbackBuffer {
+    /// # Panics
+    ///
+    /// Will panic if the duration is not strictly positive and finite
+    #[track_caller]
+    pub fn new(duration: f64, number_of_channels: usize, sample_rate: f32) -> Self {
+        assert_valid_duration(duration);
+
+        let length = (duration * sample_rate as f64).ceil() as usize;
+        // as the ring is composed of AtomicF32, one render quantum of extra room protects the
+        // oldest frames of a dump from being overwritten while being copied
+        let capacity = length + RENDER_QUANTUM_SIZE;
+        let channels = (0..number_of_channels)
+            .map(|_| (0..capacity).map(|_| AtomicF32::new(0.)).collect())
+            .collect();
+
+        Self {
+            inner: Arc::new(LookbackRing {
+                channels,
+                written: AtomicUsize::new(0),
+                length,
+                sample_rate,
+            }),
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        self.inner.length + RENDER_QUANTUM_SIZE
+    }
+
+    /// Record a render quantum, called on the render thread
+    ///
+    /// A mono quantum is copied to all recorded channels, missing channels are recorded as
+    /// silence and extra channels are discarded.
+    pub fn write(&self, quantum: &AudioRenderQuantum) {
+        let capacity = self.capacity();
+        let written = self.inner.written.load(Ordering::Relaxed);
+        let start = written % capacity;
+        let number_of_channels = quantum.number_of_channels();
+
+        self.inner
+            .channels
+            .iter()
+            .enumerate()
+            .for_each(|(index, channel)| {
+                let index = if number_of_channels == 1 { 0 } else { index };
+                if index < number_of_channels {
+                    let data = quantum.channel_data(index);
+                    data.iter().enumerate().for_each(|(i, v)| {
+                        channel[(start + i) % capacity].store(*v, Ordering::Relaxed);
+                    });
+                } else {
+                    (0..RENDER_QUANTUM_SIZE).for_each(|i| {
+                        channel[(start + i) % capacity].store(0., Ordering::Relaxed);
+                    });
+                }
+            });
+
+        self.inner
+            .written
+            .store(written + RENDER_QUANTUM_SIZE, Ordering::Release);
+    }
+
+    /// Copy the recorded frames, oldest first
+    ///
+    /// The buffer is shorter than the lookback duration when less frames have been recorded.
+    pub fn dump(&self) -> AudioBuffer {
+        let capacity = self.capacity();
+        let written = self.inner.written.load(Ordering::Acquire);
+        let length = written.min(self.inner.length);
+        let start = written - length;
+
+        let samples = self
+            .inner
+            .channels
+            .iter()
+            .map(|channel| {
+                (start..written)
+                    .map(|i| channel[i % capacity].load(Ordering::Relaxed))
+                    .collect()
+            })
+            .collect();
+
+        AudioBuffer::from(samples, self.inner.sample_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::Alloc;
+
+    use float_eq::assert_float_eq;
+
+    fn quantum(alloc: &Alloc, values: &[f32]) -> AudioRenderQuantum {
+        let mut quantum = AudioRenderQuantum::from(alloc.silence());
+        quantum.set_number_of_channels(values.len());
+        quantum
+            .channels_mut()
+            .iter_mut()
+            .zip(values)
+            .for_each(|(channel, v)| channel.fill(*v));
+        quantum
+    }
+
+    #[test]
+    fn test_partial_dump() {
+        let alloc = Alloc::with_capacity(1);
+        let lookback = LookbackBuffer::new(1., 2, 1024.);
+
+        assert_eq!(lookback.dump().length(), 0);
+
+        lookback.write(&quantum(&alloc, &[1., 2.]));
+        let buffer = lookback.dump();
+        assert_eq!(buffer.number_of_channels(), 2);
+        assert_eq!(buffer.length(), RENDER_QUANTUM_SIZE);
+        assert_float_eq!(buffer.get_channel_data(0), &[1.; 128][..], abs_all <= 0.);
+        assert_float_eq!(buffer.get_channel_data(1), &[2.; 128][..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_wrap_around() {
+        let alloc = Alloc::with_capacity(1);
+        // 200 frames
+        let lookback = LookbackBuffer::new(200. / 1024., 1, 1024.);
+
+        (0..10).for_each(|i| lookback.write(&quantum(&alloc, &[i as f32])));
+
+        let buffer = lookback.dump();
+        assert_eq!(buffer.length(), 200);
+        let data = buffer.get_channel_data(0);
+        assert_float_eq!(&data[..72], &[8.; 72][..], abs_all <= 0.);
+        assert_float_eq!(&data[72..], &[9.; 128][..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_channel_mismatch() {
+        let alloc = Alloc::with_capacity(1);
+        let lookback = LookbackBuffer::new(1., 3, 1024.);
+
+        lookback.write(&quantum(&alloc, &[1.]));
+        lookback.write(&quantum(&alloc, &[2., 3.]));
+        lookback.write(&quantum(&alloc, &[4., 5., 6., 7.]));
+
+        let buffer = lookback.dump();
+        let expected = [[1., 1., 1.], [2., 3., 0.], [4., 5., 6.]];
+        expected.iter().enumerate().for_each(|(i, values)| {
+            values.iter().enumerate().for_each(|(c, v)| {
+                let data = &buffer.get_channel_data(c)[i * 128..(i + 1) * 128];
+                assert_float_eq!(data, &[*v; 128][..], abs_all <= 0.);
+            });
+        });
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_duration() {
+        let _ = LookbackBuffer::new(0., 1, 1024.);
+    }
+}
@@ -0,0 +1,167 @@
+//! Read-only `AudioBuffer` channels backed by memory-mapped PCM files
+//!
+//! See [`AudioBuffer::map_pcm_file`](crate::AudioBuffer::map_pcm_file)
+
+use std::error::Error;
+use std::fs::File;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Once, Weak};
+use std::time::Duration;
+
+use memmap2::Mmap;
+
+/// Number of frames ahead of the playhead that are kept resident (about 5 seconds at 48 kHz)
+const PREFETCH_FRAMES: usize = 1 << 18;
+/// Interval between the passes of the prefetch thread
+const PREFETCH_INTERVAL: Duration = Duration::from_millis(20);
+/// Conservative page size, touching more than one byte per page is harmless
+const PAGE_SIZE: usize = 4096;
+
+/// Files prefetched by the prefetch thread
+static MAPPED_FILES: Mutex<Vec<Weak<MappedFile>>> = Mutex::new(Vec::new());
+static PREFETCH_THREAD: Once = Once::new();
+
+/// Memory-mapped file of planar 32-bit float samples
+pub(crate) struct MappedFile {
+    map: Mmap,
+    number_of_channels: usize,
+    length: usize,
+    /// Frame position of the playhead, reported by the render thread
+    playhead: AtomicUsize,
+}
+
+impl MappedFile {
+    /// Map the file and prefetch its first frames
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is mapped.
+    pub unsafe fn open(
+        path: &Path,
+        number_of_channels: usize,
+    ) -> Result<Arc<Self>, Box<dyn Error + Send + Sync>> {
+        let file = File::open(path)?;
+        let bytes = file.metadata()?.len() as usize;
+        let frame_size = number_of_channels * std::mem::size_of::<f32>();
+        if bytes == 0 || bytes % frame_size != 0 {
+            return Err(format!(
+                "InvalidData - file of {bytes} bytes does not hold {number_of_channels} channel(s) of f32 samples"
+            )
+            .into());
+        }
+
+        let map = Mmap::map(&file)?;
+        let file = Arc::new(Self {
+            map,
+            number_of_channels,
+            length: bytes / frame_size,
+            playhead: AtomicUsize::new(0),
+        });
+
+        // the first frames are resident before the buffer is played
+        file.prefetch();
+        register(&file);
+
+        Ok(file)
+    }
+
+    pub fn number_of_channels(&self) -> usize {
+        self.number_of_channels
+    }
+
+    /// Samples of a single channel
+    pub fn channel(&self, index: usize) -> &[f32] {
+        assert!(index < self.number_of_channels);
+        // SAFETY: the map is page aligned and holds `number_of_channels * length` f32 samples,
+        // the offset of the channel is a multiple of the alignment of f32
+        unsafe {
+            let ptr = self.map.as_ptr().cast::<f32>().add(index * self.length);
+            std::slice::from_raw_parts(ptr, self.length)
+        }
+    }
+
+    /// Report the position of the playhead, the following frames are prefetched
+    ///
+    /// This is called by the render thread, it does not block nor allocate.
+    pub fn set_playhead(&self, frame: usize) {
+        self.playhead.store(frame, Ordering::Relaxed);
+    }
+
+    /// Touch the pages of the frames following the playhead, so they are resident when the
+    /// render thread reads them
+    fn prefetch(&self) {
+        let start = self.playhead.load(Ordering::Relaxed).min(self.length);
+        let end = (start + PREFETCH_FRAMES).min(self.length);
+        let ptr = self.map.as_ptr();
+
+        for channel in 0..self.number_of_channels {
+            let offset = channel * self.length * std::mem::size_of::<f32>();
+            let range = offset + start * std::mem::size_of::<f32>()
+                ..offset + end * std::mem::size_of::<f32>();
+            for byte in range.step_by(PAGE_SIZE) {
+                // SAFETY: the byte is within the map
+                unsafe { std::ptr::read_volatile(ptr.add(byte)) };
+            }
+        }
+    }
+}
+
+/// Add the file to the prefetch thread, which is started by the first mapped file
+fn register(file: &Arc<MappedFile>) {
+    MAPPED_FILES.lock().unwrap().push(Arc::downgrade(file));
+
+    PREFETCH_THREAD.call_once(|| {
+        std::thread::Builder::new()
+            .name("web-audio-api-prefetch".into())
+            .spawn(prefetch_loop)
+            .expect("failed to spawn the prefetch thread");
+    });
+}
+
+fn prefetch_loop() {
+    loop {
+        // upgrade the files, and forget the dropped ones
+        let files: Vec<_> = {
+            let mut weak_files = MAPPED_FILES.lock().unwrap();
+            weak_files.retain(|file| file.strong_count() > 0);
+            weak_files.iter().filter_map(Weak::upgrade).collect()
+        };
+
+        files.iter().for_each(|file| file.prefetch());
+        // the file may be unmapped here, outside of the render thread
+        drop(files);
+
+        std::thread::sleep(PREFETCH_INTERVAL);
+    }
+}
+
+/// Single channel of a [`MappedFile`]
+#[derive(Clone)]
+pub(crate) struct MappedChannel {
+    file: Arc<MappedFile>,
+    index: usize,
+}
+
+impl MappedChannel {
+    pub fn new(file: Arc<MappedFile>, index: usize) -> Self {
+        Self { file, index }
+    }
+
+    pub fn as_slice(&self) -> &[f32] {
+        self.file.channel(self.index)
+    }
+
+    pub fn file(&self) -> &Arc<MappedFile> {
+        &self.file
+    }
+}
+
+impl std::fmt::Debug for MappedChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MappedChannel")
+            .field("index", &self.index)
+            .field("length", &self.file.length)
+            .finish_non_exhaustive()
+    }
+}
@@ -8,7 +8,9 @@ use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
 use crate::context::{AudioContextLatencyCategory, AudioContextOptions};
+use crate::io::InputOptions;
 use crate::media_streams::MediaStream;
+use crate::MAX_CHANNELS;
 
 /// List the available media output devices, such as speakers, headsets, loopbacks, etc
 ///
@@ -151,6 +153,109 @@ pub struct MediaTrackConstraints {
     //ConstrainULong channelCount;
     pub device_id: Option<String>,
     // ConstrainDOMString groupId;
+    /// Channels of the input device to capture, the default layout of the device when not
+    /// specified
+    pub channels: InputChannelLayout,
+    /// Minimize the buffering of the input, for live monitoring through the speakers (e.g. guitar
+    /// amp simulation). This comes at the expense of more frequent dropouts when the input device
+    /// callbacks are irregular.
+    ///
+    /// The achieved latency is reported by [`AudioContext::round_trip_latency`](crate::context::AudioContext::round_trip_latency).
+    pub monitoring: bool,
+}
+
+/// Channels of the input device captured by the [`MediaStream`], see
+/// [`MediaTrackConstraints::channels`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InputChannelLayout {
+    /// The channels of the default configuration of the input device
+    #[default]
+    Default,
+    /// All the channels supported by the input device (clamped to [`MAX_CHANNELS`]), as
+    /// discrete channels
+    Discrete,
+    /// The channels of the default configuration of the input device, mixed down to a single
+    /// channel
+    Mono,
+    /// Specific channels of the input device, by zero based index and in the given order, e.g.
+    /// `Select(vec![2])` for the third input of an audio interface
+    Select(Vec<usize>),
+}
+
+#[cfg_attr(not(any(feature = "cubeb", feature = "cpal")), allow(dead_code))]
+impl InputChannelLayout {
+    /// # Panics
+    ///
+    /// Panics when no channel or more than [`MAX_CHANNELS`] channels are selected
+    fn assert_valid(&self) {
+        if let Self::Select(channels) = self {
+            assert!(
+                !channels.is_empty() && channels.len() <= MAX_CHANNELS,
+                "OverconstrainedError - Invalid number of selected input channels: {:?}",
+                channels.len()
+            );
+        }
+    }
+
+    /// Number of channels to open on the input device
+    ///
+    /// # Panics
+    ///
+    /// Panics when a selected channel is not supported by the device
+    pub(crate) fn device_channels(&self, default_channels: usize, max_channels: usize) -> usize {
+        match self {
+            Self::Default | Self::Mono => default_channels,
+            Self::Discrete => max_channels.min(MAX_CHANNELS),
+            Self::Select(channels) => {
+                let required = channels.iter().max().map_or(0, |c| c + 1);
+                assert!(
+                    required <= max_channels,
+                    "OverconstrainedError - Input channel {} is not supported by the device, which has {} channels",
+                    required - 1,
+                    max_channels
+                );
+                required.max(default_channels)
+            }
+        }
+    }
+
+    /// Number of channels of the media stream, for the given number of device channels
+    pub(crate) fn number_of_channels(&self, device_channels: usize) -> usize {
+        match self {
+            Self::Default | Self::Discrete => device_channels,
+            Self::Mono => 1,
+            Self::Select(channels) => channels.len(),
+        }
+    }
+
+    /// Convert the interleaved samples of the device into the channels of the media stream
+    pub(crate) fn deinterleave<S: dasp_sample::ToSample<f32> + Copy>(
+        &self,
+        data: &[S],
+        device_channels: usize,
+    ) -> Vec<Vec<f32>> {
+        let channel = |i: usize| -> Vec<f32> {
+            data.iter()
+                .skip(i)
+                .step_by(device_channels)
+                .map(|v| v.to_sample_())
+                .collect()
+        };
+
+        match self {
+            Self::Default | Self::Discrete => (0..device_channels).map(channel).collect(),
+            Self::Mono => {
+                let gain = 1. / device_channels as f32;
+                let mono = data
+                    .chunks_exact(device_channels)
+                    .map(|frame| frame.iter().map(|v| v.to_sample_()).sum::<f32>() * gain)
+                    .collect();
+                vec![mono]
+            }
+            Self::Select(channels) => channels.iter().copied().map(channel).collect(),
+        }
+    }
 }
 
 impl From<MediaTrackConstraints> for AudioContextOptions {
@@ -166,6 +271,7 @@ impl From<MediaTrackConstraints> for AudioContextOptions {
             sample_rate: value.sample_rate,
             sink_id,
             render_size_hint: Default::default(),
+            stream_metadata: Default::default(),
         }
     }
 }
@@ -196,6 +302,11 @@ fn is_valid_device_id(device_id: &str) -> bool {
 /// This function operates synchronously, which may be undesirable on the control thread. An async
 /// version is currently not implemented.
 ///
+/// # Panics
+///
+/// Panics when the [`InputChannelLayout`] selects no channels, more than [`MAX_CHANNELS`]
+/// channels, or a channel which is not supported by the input device.
+///
 /// # Example
 ///
 /// ```no_run
@@ -218,9 +329,16 @@ fn is_valid_device_id(device_id: &str) -> bool {
 /// std::thread::sleep(std::time::Duration::from_secs(4));
 /// ```
 pub fn get_user_media_sync(constraints: MediaStreamConstraints) -> MediaStream {
-    let mut options = match constraints {
-        MediaStreamConstraints::Audio => AudioContextOptions::default(),
-        MediaStreamConstraints::AudioWithConstraints(cs) => cs.into(),
+    let (mut options, input_options) = match constraints {
+        MediaStreamConstraints::Audio => (AudioContextOptions::default(), InputOptions::default()),
+        MediaStreamConstraints::AudioWithConstraints(cs) => {
+            cs.channels.assert_valid();
+            let input_options = InputOptions {
+                low_latency: cs.monitoring,
+                channels: cs.channels.clone(),
+            };
+            (cs.into(), input_options)
+        }
     };
 
     if !is_valid_device_id(&options.sink_id) {
@@ -228,5 +346,50 @@ pub fn get_user_media_sync(constraints: MediaStreamConstraints) -> MediaStream {
         options.sink_id = String::from("");
     }
 
-    crate::io::build_input(options)
+    crate::io::build_input(options, input_options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_channel_layout() {
+        // interleaved frames of 4 channels
+        let data = [0., 1., 2., 3., 0., 1., 2., 3.];
+
+        let layout = InputChannelLayout::Default;
+        assert_eq!(layout.device_channels(4, 8), 4);
+        assert_eq!(layout.number_of_channels(4), 4);
+        assert_eq!(layout.deinterleave(&data, 4)[3], vec![3., 3.]);
+
+        let layout = InputChannelLayout::Discrete;
+        assert_eq!(layout.device_channels(2, 8), 8);
+        assert_eq!(layout.device_channels(2, MAX_CHANNELS + 8), MAX_CHANNELS);
+
+        let layout = InputChannelLayout::Mono;
+        assert_eq!(layout.device_channels(4, 8), 4);
+        assert_eq!(layout.number_of_channels(4), 1);
+        assert_eq!(layout.deinterleave(&data, 4), vec![vec![1.5, 1.5]]);
+
+        let layout = InputChannelLayout::Select(vec![3, 1]);
+        assert_eq!(layout.device_channels(2, 8), 4);
+        assert_eq!(layout.number_of_channels(4), 2);
+        assert_eq!(
+            layout.deinterleave(&data, 4),
+            vec![vec![3., 3.], vec![1., 1.]]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_select_unsupported_input_channel() {
+        InputChannelLayout::Select(vec![2]).device_channels(2, 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_select_no_input_channels() {
+        InputChannelLayout::Select(vec![]).assert_valid();
+    }
 }
@@ -6,7 +6,7 @@ use std::sync::Arc;
 use creek::{ReadDiskStream, SeekMode, SymphoniaDecoder};
 use crossbeam_channel::{Receiver, Sender};
 
-use crate::{AtomicF64, AudioBuffer, RENDER_QUANTUM_SIZE};
+use crate::{AtomicF64, AudioBuffer, MAX_CHANNELS, RENDER_QUANTUM_SIZE};
 
 /// Real time safe audio stream
 pub(crate) struct RTSStream {
@@ -17,6 +17,33 @@ pub(crate) struct RTSStream {
     loop_: Arc<AtomicBool>,
     paused: Arc<AtomicBool>,
     playback_rate: Arc<AtomicF64>,
+    /// the next chunk is faded out (pausing) or in (playing), to avoid clicks
+    fade: Option<Fade>,
+}
+
+/// Direction of the fade applied to a chunk when pausing or playing
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Fade {
+    In,
+    Out,
+}
+
+/// Apply a linear fade over the whole chunk
+fn apply_fade(buffer: &mut AudioBuffer, fade: Fade) {
+    let length = buffer.length() as f32;
+    for channel in 0..buffer.number_of_channels() {
+        buffer
+            .get_channel_data_mut(channel)
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, s)| {
+                let position = (i + 1) as f32 / length;
+                *s *= match fade {
+                    Fade::In => position,
+                    Fade::Out => 1. - position,
+                };
+            });
+    }
 }
 
 /// Controller actions for a media element
@@ -56,6 +83,13 @@ impl MediaElement {
             Default::default(), // Use default read stream options.
         )?;
         let number_of_channels = read_disk_stream.info().num_channels as usize;
+        if number_of_channels == 0 || number_of_channels > MAX_CHANNELS {
+            return Err(format!(
+                "NotSupportedError - media has {} channels, outside range [1, {}]",
+                number_of_channels, MAX_CHANNELS
+            )
+            .into());
+        }
 
         // Cache the start of the file into cache with index `0`.
         let _ = read_disk_stream.cache(0, 0);
@@ -87,6 +121,7 @@ impl MediaElement {
             loop_: Arc::clone(&loop_),
             paused: Arc::clone(&paused),
             playback_rate: Arc::clone(&playback_rate),
+            fade: None,
         };
 
         Ok(Self {
@@ -119,10 +154,12 @@ impl MediaElement {
         let _ = self.sender.send(MediaElementAction::SetLoop(value));
     }
 
+    /// Start or continue the playback, with a short fade in
     pub fn play(&self) {
         let _ = self.sender.send(MediaElementAction::Play);
     }
 
+    /// Pause the playback after a short fade out
     pub fn pause(&self) {
         let _ = self.sender.send(MediaElementAction::Pause);
     }
@@ -157,13 +194,22 @@ impl Iterator for RTSStream {
                 SetLoop(value) => {
                     self.loop_.store(value, Ordering::SeqCst);
                 }
-                Play => self.paused.store(false, Ordering::SeqCst),
-                Pause => self.paused.store(true, Ordering::SeqCst),
+                Play => {
+                    if self.paused.swap(false, Ordering::SeqCst) {
+                        self.fade = Some(Fade::In);
+                    }
+                }
+                Pause => {
+                    if !self.paused.swap(true, Ordering::SeqCst) {
+                        self.fade = Some(Fade::Out);
+                    }
+                }
                 SetPlaybackRate(value) => self.playback_rate.store(value, Ordering::SeqCst),
             };
         }
 
-        if self.paused.load(Ordering::SeqCst) {
+        // the chunk fading out is played after pausing
+        if self.paused.load(Ordering::SeqCst) && self.fade != Some(Fade::Out) {
             let silence = AudioBuffer::from(
                 vec![vec![0.; RENDER_QUANTUM_SIZE]; self.number_of_channels],
                 sample_rate,
@@ -180,7 +226,10 @@ impl Iterator for RTSStream {
                 let channels: Vec<_> = (0..data.num_channels())
                     .map(|i| data.read_channel(i).to_vec())
                     .collect();
-                let buf = AudioBuffer::from(channels, sample_rate * playback_rate as f32);
+                let mut buf = AudioBuffer::from(channels, sample_rate * playback_rate as f32);
+                if let Some(fade) = self.fade.take() {
+                    apply_fade(&mut buf, fade);
+                }
 
                 if self.loop_.load(Ordering::SeqCst) && data.reached_end_of_file() {
                     self.stream.seek(0, SeekMode::default()).unwrap();
@@ -201,3 +250,29 @@ impl Iterator for RTSStream {
         Some(next)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_apply_fade() {
+        let mut buffer = AudioBuffer::from(vec![vec![1.; 4]; 2], 44_100.);
+        apply_fade(&mut buffer, Fade::Out);
+        assert_float_eq!(
+            buffer.get_channel_data(1),
+            &[0.75, 0.5, 0.25, 0.][..],
+            abs_all <= 0.
+        );
+
+        let mut buffer = AudioBuffer::from(vec![vec![1.; 4]], 44_100.);
+        apply_fade(&mut buffer, Fade::In);
+        assert_float_eq!(
+            buffer.get_channel_data(0),
+            &[0.25, 0.5, 0.75, 1.][..],
+            abs_all <= 0.
+        );
+    }
+}
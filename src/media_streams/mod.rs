@@ -9,7 +9,7 @@
 //!
 //! <https://developer.mozilla.org/en-US/docs/Web/API/Media_Capture_and_Streams_API>
 
-use crate::{AudioBuffer, FallibleBuffer};
+use crate::{AtomicF64, AudioBuffer, FallibleBuffer};
 use arc_swap::ArcSwap;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
@@ -37,11 +37,24 @@ struct MediaStreamTrackInner {
     position: AtomicU64,
     ended: AtomicBool,
     provider: Mutex<Box<dyn Iterator<Item = FallibleBuffer> + Send + Sync + 'static>>,
+    /// latency reported by the input device stream, if any
+    latency: Option<Arc<AtomicF64>>,
 }
 
 impl MediaStreamTrack {
     #[allow(clippy::should_implement_trait)]
     pub fn from_iter<T: IntoIterator<Item = FallibleBuffer>>(iter: T) -> Self
+    where
+        <T as IntoIterator>::IntoIter: Send + Sync + 'static,
+    {
+        Self::from_iter_with_latency(iter, None)
+    }
+
+    /// Track of an input device stream, which reports its latency
+    pub(crate) fn from_iter_with_latency<T: IntoIterator<Item = FallibleBuffer>>(
+        iter: T,
+        latency: Option<Arc<AtomicF64>>,
+    ) -> Self
     where
         <T as IntoIterator>::IntoIter: Send + Sync + 'static,
     {
@@ -51,12 +64,24 @@ impl MediaStreamTrack {
             position: AtomicU64::new(0),
             ended: AtomicBool::new(false),
             provider: Mutex::new(Box::new(iter.into_iter())),
+            latency,
         };
         MediaStreamTrack {
             inner: Arc::new(inner),
         }
     }
 
+    /// Estimated latency in seconds between the capture of the audio by the input device and its
+    /// delivery to the render thread
+    ///
+    /// This is zero for tracks that are not backed by an input device.
+    pub fn latency(&self) -> f64 {
+        self.inner
+            .latency
+            .as_ref()
+            .map_or(0., |latency| latency.load(Ordering::Relaxed))
+    }
+
     pub fn ready_state(&self) -> MediaStreamTrackState {
         if self.inner.ended.load(Ordering::Relaxed) {
             MediaStreamTrackState::Ended
@@ -240,4 +265,19 @@ mod tests {
         track.close();
         assert!(iter.next().is_none());
     }
+
+    #[test]
+    fn test_latency() {
+        let buffers = vec![Ok(AudioBuffer::from(vec![vec![1.]], 48000.))];
+        let track = MediaStreamTrack::from_iter(buffers);
+        assert_eq!(track.latency(), 0.);
+
+        let buffers = vec![Ok(AudioBuffer::from(vec![vec![1.]], 48000.))];
+        let latency = Arc::new(AtomicF64::new(0.01));
+        let track = MediaStreamTrack::from_iter_with_latency(buffers, Some(Arc::clone(&latency)));
+        assert_eq!(track.latency(), 0.01);
+
+        latency.store(0.02, Ordering::Relaxed);
+        assert_eq!(track.clone().latency(), 0.02);
+    }
 }
@@ -0,0 +1,491 @@
+//! Accounting of the memory held by the audio graph, and the resource quotas of a context
+//!
+//! See [`BaseAudioContext::memory_report`](crate::context::BaseAudioContext::memory_report) and
+//! [`BaseAudioContext::set_resource_quotas`](crate::context::BaseAudioContext::set_resource_quotas)
+
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+
+use crate::context::AudioNodeId;
+
+/// Kind of memory held by a node
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum MemoryKind {
+    AudioBuffer,
+    Convolver,
+    Hrir,
+    DelayLine,
+}
+
+/// Memory allocation held by a node
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Allocation {
+    bytes: usize,
+    /// address of the shared data, so it is only counted once
+    shared: Option<usize>,
+}
+
+impl Allocation {
+    /// Allocation owned by a single node
+    pub fn owned(bytes: usize) -> Self {
+        Self {
+            bytes,
+            shared: None,
+        }
+    }
+
+    /// Allocation which may be shared by multiple nodes
+    pub fn shared<T: ?Sized>(data: &Arc<T>, bytes: usize) -> Self {
+        Self {
+            bytes,
+            shared: Some(Arc::as_ptr(data) as *const () as usize),
+        }
+    }
+}
+
+#[derive(Default)]
+struct NodeMemory {
+    is_param: bool,
+    allocations: HashMap<MemoryKind, Vec<Allocation>>,
+}
+
+/// Allocations of the nodes of a context, by node
+///
+/// The entry of a node is created when it is registered and removed when the render thread has
+/// freed the node (i.e. when its id is reclaimed).
+#[derive(Default)]
+pub(crate) struct MemoryLedger {
+    nodes: HashMap<AudioNodeId, NodeMemory>,
+    quotas: ResourceQuotas,
+}
+
+impl MemoryLedger {
+    pub fn register(&mut self, id: AudioNodeId, is_param: bool) {
+        let node = NodeMemory {
+            is_param,
+            ..NodeMemory::default()
+        };
+        self.nodes.insert(id, node);
+    }
+
+    pub fn remove(&mut self, id: AudioNodeId) {
+        self.nodes.remove(&id);
+    }
+
+    /// Replace the allocations of the given kind of a node
+    pub fn set(&mut self, id: AudioNodeId, kind: MemoryKind, allocations: Vec<Allocation>) {
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.allocations.insert(kind, allocations);
+        }
+    }
+
+    pub fn quotas(&self) -> ResourceQuotas {
+        self.quotas
+    }
+
+    pub fn set_quotas(&mut self, quotas: ResourceQuotas) {
+        self.quotas = quotas;
+    }
+
+    /// Check that a new node can be added to the graph
+    pub fn check_nodes(&self) -> Result<(), QuotaExceededError> {
+        match self.quotas.max_nodes {
+            Some(limit) if self.nodes.values().filter(|n| !n.is_param).count() >= limit => {
+                Err(QuotaExceededError::Nodes { limit })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that the audio buffers held by the graph stay within quota when the given node (if
+    /// any) holds the given allocations instead of its current buffers
+    pub fn check_buffer_memory(
+        &self,
+        id: Option<AudioNodeId>,
+        allocations: &[Allocation],
+    ) -> Result<(), QuotaExceededError> {
+        let limit = match self.quotas.max_buffer_memory {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+
+        let mut counted = HashSet::new();
+        let held: usize = self
+            .nodes
+            .iter()
+            .filter(|(node_id, _)| Some(**node_id) != id)
+            .filter_map(|(_, node)| node.allocations.get(&MemoryKind::AudioBuffer))
+            .flatten()
+            .filter(|a| a.shared.map_or(true, |address| counted.insert(address)))
+            .map(|a| a.bytes)
+            .sum();
+        let requested: usize = allocations
+            .iter()
+            .filter(|a| a.shared.map_or(true, |address| counted.insert(address)))
+            .map(|a| a.bytes)
+            .sum();
+
+        if held + requested > limit {
+            Err(QuotaExceededError::BufferMemory { limit, requested })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Check that the given node (or a new node) can load HRIR data
+    pub fn check_hrtf(&self, id: Option<AudioNodeId>) -> Result<(), QuotaExceededError> {
+        let holds_hrir = |node: &NodeMemory| {
+            node.allocations
+                .get(&MemoryKind::Hrir)
+                .is_some_and(|allocations| allocations.iter().any(|a| a.bytes > 0))
+        };
+
+        let limit = match self.quotas.max_hrtf_panners {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        if id
+            .and_then(|id| self.nodes.get(&id))
+            .is_some_and(holds_hrir)
+        {
+            return Ok(());
+        }
+
+        if self.nodes.values().filter(|n| holds_hrir(n)).count() >= limit {
+            Err(QuotaExceededError::HrtfPanners { limit })
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn report(&self) -> MemoryReport {
+        let mut report = MemoryReport::default();
+        let mut counted = HashSet::new();
+
+        self.nodes.values().for_each(|node| {
+            if !node.is_param {
+                report.nodes += 1;
+            }
+
+            node.allocations.iter().for_each(|(kind, allocations)| {
+                let bytes: usize = allocations
+                    .iter()
+                    .filter(|a| a.shared.map_or(true, |address| counted.insert(address)))
+                    .map(|a| a.bytes)
+                    .sum();
+
+                match kind {
+                    MemoryKind::AudioBuffer => report.audio_buffers += bytes,
+                    MemoryKind::Convolver => report.convolvers += bytes,
+                    MemoryKind::Hrir => report.hrir += bytes,
+                    MemoryKind::DelayLine => report.delay_lines += bytes,
+                }
+            });
+        });
+
+        report
+    }
+}
+
+/// Summary of the memory held by the audio graph of a context
+///
+/// Only the larger allocations of the nodes are accounted for, as an estimate to watch for leaks
+/// and to budget memory. Data shared by multiple nodes (e.g. an `AudioBuffer` played by multiple
+/// sources) is counted once.
+///
+/// This is an extension to the spec.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    /// Bytes of the audio buffers played by the `AudioBufferSourceNode`s
+    pub audio_buffers: usize,
+    /// Bytes of the partitioned impulse responses and buffers of the `ConvolverNode`s
+    pub convolvers: usize,
+    /// Bytes of the head-related impulse responses of the HRTF panners and binaural decoders
+    pub hrir: usize,
+    /// Bytes of the delay lines of the `DelayNode`s, for mono signals (the delay lines grow with
+    /// the number of channels of their input)
+    pub delay_lines: usize,
+    /// Number of audio nodes in the render graph, including the nodes dropped by the control
+    /// thread but still rendering (a `DelayNode` is rendered by two nodes)
+    pub nodes: usize,
+}
+
+impl MemoryReport {
+    /// Total number of bytes of all categories
+    pub fn total_bytes(&self) -> usize {
+        self.audio_buffers + self.convolvers + self.hrir + self.delay_lines
+    }
+}
+
+/// Optional limits on the resources of a context, enforced when nodes and buffers are created
+///
+/// Servers constructing audio graphs from untrusted descriptions can use quotas to bound the
+/// resources a single context may take. All quotas are disabled (`None`) by default. Like in the
+/// [`MemoryReport`], the resources of dropped nodes count until the render thread has freed them.
+///
+/// When a quota is exceeded, the creating method panics with a [`QuotaExceededError`], unless it
+/// is run inside [`BaseAudioContext::try_create`](crate::context::BaseAudioContext::try_create)
+/// which returns the error instead.
+///
+/// This is an extension to the spec.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResourceQuotas {
+    /// Maximum number of nodes in the render graph, as counted by [`MemoryReport::nodes`]
+    /// (including the destination and listener nodes)
+    pub max_nodes: Option<usize>,
+    /// Maximum number of bytes of the audio buffers held by the graph, as counted by
+    /// [`MemoryReport::audio_buffers`]. Creating and decoding buffers is checked too.
+    pub max_buffer_memory: Option<usize>,
+    /// Maximum number of nodes holding HRIR data, i.e. `PannerNode`s using the HRTF panning model
+    /// and binaural decoders
+    pub max_hrtf_panners: Option<usize>,
+}
+
+/// Error raised when a creation would exceed one of the [`ResourceQuotas`] of a context
+///
+/// This is an extension to the spec.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QuotaExceededError {
+    /// The graph holds the maximum number of nodes
+    Nodes { limit: usize },
+    /// The audio buffers would take more than the maximum number of bytes
+    BufferMemory { limit: usize, requested: usize },
+    /// The maximum number of nodes holding HRIR data is reached
+    HrtfPanners { limit: usize },
+}
+
+impl fmt::Display for QuotaExceededError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nodes { limit } => {
+                write!(f, "QuotaExceededError - maximum number of nodes ({limit}) reached")
+            }
+            Self::BufferMemory { limit, requested } => write!(
+                f,
+                "QuotaExceededError - {requested} bytes of audio buffers exceed the maximum buffer memory ({limit} bytes)"
+            ),
+            Self::HrtfPanners { limit } => write!(
+                f,
+                "QuotaExceededError - maximum number of HRTF panners ({limit}) reached"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for QuotaExceededError {}
+
+thread_local! {
+    /// Number of nested `catch_quota_exceeded` calls on this thread
+    static CATCHING_QUOTAS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Abort the current creation because a quota is exceeded
+///
+/// Inside `catch_quota_exceeded` the error unwinds silently to be returned, otherwise this
+/// panics like the other errors of this library.
+pub(crate) fn raise_quota_exceeded(error: QuotaExceededError) -> ! {
+    if CATCHING_QUOTAS.with(Cell::get) > 0 {
+        panic::resume_unwind(Box::new(error))
+    } else {
+        panic!("{error}")
+    }
+}
+
+/// Run `f`, returning a quota error raised by the creations it contains
+pub(crate) fn catch_quota_exceeded<T>(f: impl FnOnce() -> T) -> Result<T, QuotaExceededError> {
+    CATCHING_QUOTAS.with(|c| c.set(c.get() + 1));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    CATCHING_QUOTAS.with(|c| c.set(c.get() - 1));
+
+    match result {
+        Ok(value) => Ok(value),
+        Err(payload) => match payload.downcast::<QuotaExceededError>() {
+            Ok(error) => Err(*error),
+            Err(payload) => panic::resume_unwind(payload),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::{AudioNode, AudioScheduledSourceNode, PanningModelType};
+    use crate::AudioBuffer;
+
+    #[test]
+    fn test_shared_allocations() {
+        let mut ledger = MemoryLedger::default();
+        let data = Arc::new([0_u8; 16]);
+
+        ledger.register(AudioNodeId(0), false);
+        ledger.register(AudioNodeId(1), false);
+        ledger.register(AudioNodeId(2), true);
+
+        let shared = Allocation::shared(&data, 16);
+        ledger.set(AudioNodeId(0), MemoryKind::AudioBuffer, vec![shared]);
+        ledger.set(AudioNodeId(1), MemoryKind::AudioBuffer, vec![shared]);
+        ledger.set(
+            AudioNodeId(1),
+            MemoryKind::DelayLine,
+            vec![Allocation::owned(4)],
+        );
+
+        let report = ledger.report();
+        assert_eq!(report.nodes, 2);
+        assert_eq!(report.audio_buffers, 16);
+        assert_eq!(report.delay_lines, 4);
+        assert_eq!(report.total_bytes(), 20);
+
+        ledger.set(AudioNodeId(1), MemoryKind::DelayLine, vec![]);
+        ledger.remove(AudioNodeId(0));
+        let report = ledger.report();
+        assert_eq!(report.nodes, 1);
+        assert_eq!(report.audio_buffers, 16);
+        assert_eq!(report.delay_lines, 0);
+    }
+
+    #[test]
+    fn test_memory_report() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let empty = context.memory_report();
+        assert_eq!(empty.total_bytes(), 0);
+
+        let buffer = AudioBuffer::from(vec![vec![0.; 1000], vec![1.; 1000]], 48000.);
+        let mut src1 = context.create_buffer_source();
+        src1.set_buffer(buffer.clone());
+        let mut src2 = context.create_buffer_source();
+        src2.set_buffer(buffer);
+
+        let report = context.memory_report();
+        assert_eq!(report.nodes, empty.nodes + 2);
+        // the buffer is shared by both sources
+        assert_eq!(report.audio_buffers, 2 * 1000 * 4);
+
+        let _delay = context.create_delay(1.);
+        let mut convolver = context.create_convolver();
+        convolver.set_buffer(context.create_buffer(1, 1000, 48000.));
+
+        let report = context.memory_report();
+        // the delay node is rendered by two nodes
+        assert_eq!(report.nodes, empty.nodes + 5);
+        assert!(report.delay_lines >= 48000 * 4);
+        assert!(report.convolvers > 0);
+        assert_eq!(report.hrir, 0);
+    }
+
+    #[test]
+    fn test_memory_report_freed_nodes() {
+        let context = OfflineAudioContext::new(1, 128 * 4, 48000.);
+        let base = context.base().clone();
+
+        let mut src = context.create_buffer_source();
+        src.set_buffer(context.create_buffer(1, 100, 48000.));
+        src.connect(&context.destination());
+        src.start();
+        drop(src);
+        assert_eq!(base.memory_report().audio_buffers, 100 * 4);
+
+        let _ = context.start_rendering_sync();
+        assert_eq!(base.memory_report().audio_buffers, 0);
+    }
+
+    #[test]
+    fn test_node_quota() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let nodes = context.memory_report().nodes;
+        context.set_resource_quotas(ResourceQuotas {
+            max_nodes: Some(nodes + 2),
+            ..ResourceQuotas::default()
+        });
+
+        // params do not count as nodes
+        let gain = context.try_create(|context| context.create_gain());
+        assert!(gain.is_ok());
+
+        let result = context.try_create(|context| {
+            let _ = context.create_gain();
+            context.create_gain()
+        });
+        assert_eq!(
+            result.err(),
+            Some(QuotaExceededError::Nodes { limit: nodes + 2 })
+        );
+
+        // the dropped nodes still count until they are freed by the render thread
+        let result = context.try_create(|context| context.create_gain());
+        assert_eq!(
+            result.err(),
+            Some(QuotaExceededError::Nodes { limit: nodes + 2 })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "QuotaExceededError")]
+    fn test_node_quota_panics() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        context.set_resource_quotas(ResourceQuotas {
+            max_nodes: Some(0),
+            ..ResourceQuotas::default()
+        });
+        let _ = context.create_gain();
+    }
+
+    #[test]
+    fn test_buffer_memory_quota() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        context.set_resource_quotas(ResourceQuotas {
+            max_buffer_memory: Some(1000 * 4),
+            ..ResourceQuotas::default()
+        });
+
+        let result = context.try_create(|context| context.create_buffer(2, 1000, 48000.));
+        assert_eq!(
+            result.err(),
+            Some(QuotaExceededError::BufferMemory {
+                limit: 4000,
+                requested: 8000
+            })
+        );
+
+        let buffer = AudioBuffer::from(vec![vec![1.; 1000]], 48000.);
+        let mut src1 = context.create_buffer_source();
+        src1.set_buffer(buffer.clone());
+        // the same buffer is only counted once
+        let mut src2 = context.create_buffer_source();
+        src2.set_buffer(buffer);
+
+        let other = AudioBuffer::from(vec![vec![1.; 10]], 48000.);
+        let result = context.try_create(|context| {
+            let mut src = context.create_buffer_source();
+            src.set_buffer(other);
+            src
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_hrtf_quota() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        context.set_resource_quotas(ResourceQuotas {
+            max_hrtf_panners: Some(1),
+            ..ResourceQuotas::default()
+        });
+
+        let mut panner1 = context.create_panner();
+        let mut panner2 = context.create_panner();
+        panner1.set_panning_model(PanningModelType::HRTF);
+        // switching again keeps the same HRIR slot
+        panner1.set_panning_model(PanningModelType::HRTF);
+
+        let result = context.try_create(|_| panner2.set_panning_model(PanningModelType::HRTF));
+        assert_eq!(result, Err(QuotaExceededError::HrtfPanners { limit: 1 }));
+
+        panner1.set_panning_model(PanningModelType::EqualPower);
+        panner2.set_panning_model(PanningModelType::HRTF);
+        assert_eq!(panner2.panning_model(), PanningModelType::HRTF);
+    }
+}
@@ -2,12 +2,31 @@
 
 use std::any::Any;
 
+use crate::capacity::SchedulingCounters;
 use crate::context::AudioNodeId;
+use crate::lookback::LookbackBuffer;
 use crate::node::ChannelConfig;
-use crate::render::graph::Graph;
+use crate::render::graph::{Graph, GraphMutation};
 use crate::render::AudioProcessor;
+use crate::watchdog::WatchdogConfig;
+use crate::AtomicF64;
 
 use crossbeam_channel::Sender;
+use smallvec::SmallVec;
+use std::sync::Arc;
+
+/// Generic message addressed to an AudioProcessor
+pub(crate) type NodeMessagePayload = (AudioNodeId, llq::Node<Box<dyn Any + Send>>);
+
+/// Node message that takes effect at a given time, e.g. an automation event or the start of a
+/// source
+///
+/// The render thread compares the time with its clock when the message arrives, for the
+/// [`SchedulingStats`](crate::SchedulingStats), and hands the inner message to the processor.
+pub(crate) struct ScheduledMessage {
+    pub when: f64,
+    pub msg: Box<dyn Any + Send>,
+}
 
 /// Commands from the control thread to the render thread
 pub(crate) enum ControlMessage {
@@ -41,6 +60,46 @@ pub(crate) enum ControlMessage {
     /// Mark node as a cycle breaker (DelayNode only)
     MarkCycleBreaker { id: AudioNodeId },
 
+    /// Toggle the measurement of the render time of each node
+    SetNodeProfiling { enabled: bool },
+
+    /// Collect the lookahead of the scheduled node messages in these counters
+    SetSchedulingCounters { counters: Arc<SchedulingCounters> },
+
+    /// Enable or disable the render thread watchdog
+    SetWatchdog { config: Option<WatchdogConfig> },
+
+    /// Duration in frames of the fade of the connections on connect and disconnect
+    SetConnectionFade { frames: usize },
+
+    /// Mutations of the topology of the graph, applied at the given time
+    ScheduleMutations {
+        when: f64,
+        mutations: Vec<GraphMutation>,
+    },
+
+    /// Start or stop recording the output of a node
+    SetLookback {
+        id: AudioNodeId,
+        buffer: Option<LookbackBuffer>,
+    },
+
+    /// Add a clock that can be paused for a group of nodes, starting at the current frame
+    AddTransport {
+        id: usize,
+        current_time: Arc<AtomicF64>,
+    },
+
+    /// Add a node (and its parameters) to a transport, optionally with all nodes upstream
+    AddToTransport {
+        id: usize,
+        node: AudioNodeId,
+        upstream: bool,
+    },
+
+    /// Pause or resume the nodes of a transport
+    SetTransportPaused { id: usize, paused: bool },
+
     /// Shut down and recycle the audio graph
     Shutdown { sender: Sender<Graph> },
 
@@ -52,4 +111,9 @@ pub(crate) enum ControlMessage {
         id: AudioNodeId,
         msg: llq::Node<Box<dyn Any + Send>>,
     },
+
+    /// Multiple generic messages, handled together within the same render quantum
+    NodeMessages {
+        messages: SmallVec<[NodeMessagePayload; 6]>,
+    },
 }
@@ -0,0 +1,497 @@
+//! Markers and metadata of the containers of decoded audio files
+use crate::AudioBuffer;
+
+/// Audio file decoded with the markers and metadata of its container, see
+/// [`BaseAudioContext::decode_audio_data_with_metadata_sync`](crate::context::BaseAudioContext::decode_audio_data_with_metadata_sync)
+///
+/// The markers are read from WAV (`cue `, `LIST`/`adtl` and `smpl` chunks) and AIFF (`MARK`
+/// and `INST` chunks) files, and the broadcast extension from BWF files (`bext` chunk). The
+/// positions are in sample frames of the decoded buffer, i.e. they are scaled when the file is
+/// resampled to the sample rate of the context.
+///
+/// This is an extension to the spec.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct DecodedAudio {
+    pub buffer: AudioBuffer,
+    /// Cue points, sorted by position
+    pub markers: Vec<Marker>,
+    /// Loops defined for samplers
+    pub loops: Vec<LoopPoint>,
+    /// Metadata of Broadcast Wave Format files
+    pub broadcast_extension: Option<BroadcastExtension>,
+}
+
+/// Cue point of a decoded file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Marker {
+    /// Identifier of the marker in the file
+    pub id: u32,
+    /// Position, in sample frames
+    pub position: usize,
+    pub label: Option<String>,
+}
+
+/// Playback direction of a [`LoopPoint`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LoopMode {
+    Forward,
+    /// Alternating forward and backward
+    PingPong,
+    Backward,
+}
+
+/// Loop of a decoded file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoopPoint {
+    /// First sample frame of the loop
+    pub start: usize,
+    /// Sample frame following the last one of the loop
+    pub end: usize,
+    pub mode: LoopMode,
+    /// Number of repetitions, `None` for an infinite loop
+    pub play_count: Option<u32>,
+}
+
+/// Metadata of a Broadcast Wave Format file (EBU Tech 3285)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BroadcastExtension {
+    pub description: String,
+    pub originator: String,
+    pub originator_reference: String,
+    /// Date of creation, formatted as `yyyy-mm-dd`
+    pub origination_date: String,
+    /// Time of creation, formatted as `hh:mm:ss`
+    pub origination_time: String,
+    /// Position of the start of the file since midnight, in sample frames
+    pub time_reference: u64,
+    pub coding_history: String,
+}
+
+/// Markers and metadata read from the chunks of a container, in the sample frames of the file
+#[derive(Debug, Default)]
+pub(crate) struct ContainerMetadata {
+    markers: Vec<Marker>,
+    loops: Vec<LoopPoint>,
+    broadcast_extension: Option<BroadcastExtension>,
+}
+
+impl ContainerMetadata {
+    /// Read the chunks of a WAV or AIFF file, other formats have no metadata
+    pub fn parse(bytes: &[u8]) -> Self {
+        match bytes.get(..12) {
+            Some([b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E']) => {
+                Self::parse_wav(&bytes[12..])
+            }
+            Some([b'F', b'O', b'R', b'M', _, _, _, _, b'A', b'I', b'F', b'F' | b'C']) => {
+                Self::parse_aiff(&bytes[12..])
+            }
+            _ => Self::default(),
+        }
+    }
+
+    fn parse_wav(bytes: &[u8]) -> Self {
+        let mut metadata = Self::default();
+        let mut labels = vec![];
+
+        chunks(bytes, u32::from_le_bytes).for_each(|(id, data)| match &id {
+            b"cue " => {
+                let count = read_u32_le(data, 0).unwrap_or(0) as usize;
+                // id, position, data chunk id, chunk start, block start, sample offset
+                metadata.markers.extend(
+                    data.get(4..)
+                        .unwrap_or_default()
+                        .chunks_exact(24)
+                        .take(count)
+                        .map(|cue| Marker {
+                            id: read_u32_le(cue, 0).unwrap(),
+                            position: read_u32_le(cue, 20).unwrap() as usize,
+                            label: None,
+                        }),
+                );
+            }
+            b"LIST" if data.starts_with(b"adtl") => {
+                chunks(&data[4..], u32::from_le_bytes)
+                    .filter(|(id, _)| id == b"labl")
+                    .for_each(|(_, label)| {
+                        if let Some(id) = read_u32_le(label, 0) {
+                            labels.push((id, read_string(&label[4..])));
+                        }
+                    });
+            }
+            b"smpl" => {
+                let count = read_u32_le(data, 28).unwrap_or(0) as usize;
+                // cue id, type, start, end (inclusive), fraction, play count
+                metadata.loops.extend(
+                    data.get(36..)
+                        .unwrap_or_default()
+                        .chunks_exact(24)
+                        .take(count)
+                        .map(|sample_loop| LoopPoint {
+                            start: read_u32_le(sample_loop, 8).unwrap() as usize,
+                            end: read_u32_le(sample_loop, 12).unwrap() as usize + 1,
+                            mode: match read_u32_le(sample_loop, 4).unwrap() {
+                                1 => LoopMode::PingPong,
+                                2 => LoopMode::Backward,
+                                _ => LoopMode::Forward,
+                            },
+                            play_count: Some(read_u32_le(sample_loop, 20).unwrap())
+                                .filter(|&count| count > 0),
+                        }),
+                );
+            }
+            b"bext" if data.len() >= 602 => {
+                let time_reference_low = read_u32_le(data, 338).unwrap() as u64;
+                let time_reference_high = read_u32_le(data, 342).unwrap() as u64;
+                metadata.broadcast_extension = Some(BroadcastExtension {
+                    description: read_string(&data[..256]),
+                    originator: read_string(&data[256..288]),
+                    originator_reference: read_string(&data[288..320]),
+                    origination_date: read_string(&data[320..330]),
+                    origination_time: read_string(&data[330..338]),
+                    time_reference: time_reference_high << 32 | time_reference_low,
+                    coding_history: read_string(&data[602..]),
+                });
+            }
+            _ => (),
+        });
+
+        metadata.markers.iter_mut().for_each(|marker| {
+            marker.label = labels
+                .iter()
+                .find(|(id, _)| *id == marker.id)
+                .map(|(_, label)| label.clone());
+        });
+        metadata.markers.sort_by_key(|marker| marker.position);
+
+        metadata
+    }
+
+    fn parse_aiff(bytes: &[u8]) -> Self {
+        let mut metadata = Self::default();
+        // play mode, begin and end marker ids of the sustain and release loops
+        let mut loops = vec![];
+
+        chunks(bytes, u32::from_be_bytes).for_each(|(id, data)| match &id {
+            b"MARK" => {
+                let count = read_u16_be(data, 0).unwrap_or(0);
+                let mut offset = 2;
+                for _ in 0..count {
+                    // id, position, pascal string padded to an even length
+                    let (Some(id), Some(position), Some(&length)) = (
+                        read_u16_be(data, offset),
+                        read_u32_be(data, offset + 2),
+                        data.get(offset + 6),
+                    ) else {
+                        break;
+                    };
+                    let name = data
+                        .get(offset + 7..offset + 7 + length as usize)
+                        .map(read_string)
+                        .filter(|name| !name.is_empty());
+                    metadata.markers.push(Marker {
+                        id: id as u32,
+                        position: position as usize,
+                        label: name,
+                    });
+                    offset += 7 + length as usize + (length as usize + 1) % 2;
+                }
+            }
+            b"INST" => {
+                [8, 14].into_iter().for_each(|offset| {
+                    if let (Some(mode), Some(begin), Some(end)) = (
+                        read_u16_be(data, offset),
+                        read_u16_be(data, offset + 2),
+                        read_u16_be(data, offset + 4),
+                    ) {
+                        loops.push((mode, begin, end));
+                    }
+                });
+            }
+            _ => (),
+        });
+
+        let position = |id: u16| {
+            metadata
+                .markers
+                .iter()
+                .find(|marker| marker.id == id as u32)
+                .map(|marker| marker.position)
+        };
+        metadata.loops = loops
+            .into_iter()
+            .filter_map(|(mode, begin, end)| {
+                let mode = match mode {
+                    1 => LoopMode::Forward,
+                    2 => LoopMode::PingPong,
+                    _ => return None,
+                };
+                Some(LoopPoint {
+                    start: position(begin)?,
+                    end: position(end)?,
+                    mode,
+                    play_count: None,
+                })
+            })
+            .collect();
+        metadata.markers.sort_by_key(|marker| marker.position);
+
+        metadata
+    }
+
+    /// Attach the metadata to the decoded buffer, the positions are scaled by the ratio of the
+    /// sample rate of the buffer to the one of the file
+    pub fn into_decoded_audio(self, buffer: AudioBuffer, file_sample_rate: f32) -> DecodedAudio {
+        let ratio = buffer.sample_rate() as f64 / file_sample_rate as f64;
+        let scale = |position: usize| (position as f64 * ratio).round() as usize;
+
+        let markers = self
+            .markers
+            .into_iter()
+            .map(|marker| Marker {
+                position: scale(marker.position),
+                ..marker
+            })
+            .collect();
+        let loops = self
+            .loops
+            .into_iter()
+            .map(|sample_loop| LoopPoint {
+                start: scale(sample_loop.start),
+                end: scale(sample_loop.end),
+                ..sample_loop
+            })
+            .collect();
+        let broadcast_extension = self
+            .broadcast_extension
+            .map(|extension| BroadcastExtension {
+                time_reference: (extension.time_reference as f64 * ratio).round() as u64,
+                ..extension
+            });
+
+        DecodedAudio {
+            buffer,
+            markers,
+            loops,
+            broadcast_extension,
+        }
+    }
+}
+
+/// Iterator over the chunks of a RIFF or IFF container, with the given endianness of the
+/// chunk sizes
+fn chunks(
+    mut bytes: &[u8],
+    size_from_bytes: fn([u8; 4]) -> u32,
+) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    std::iter::from_fn(move || {
+        let id: [u8; 4] = bytes.get(..4)?.try_into().unwrap();
+        let size = size_from_bytes(bytes.get(4..8)?.try_into().unwrap()) as usize;
+        let data = &bytes[8..];
+        let data = &data[..size.min(data.len())];
+        // chunks are padded to an even length
+        bytes = bytes.get(8 + size + size % 2..).unwrap_or_default();
+        Some((id, data))
+    })
+}
+
+fn read_u16_be(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        bytes.get(offset..offset + 2)?.try_into().unwrap(),
+    ))
+}
+
+fn read_u32_be(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        bytes.get(offset..offset + 4)?.try_into().unwrap(),
+    ))
+}
+
+fn read_u32_le(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(offset..offset + 4)?.try_into().unwrap(),
+    ))
+}
+
+/// Text of a fixed size or null terminated string
+fn read_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end])
+        .trim_end()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+
+    fn chunk(id: &[u8; 4], data: &[u8], size_to_bytes: fn(u32) -> [u8; 4]) -> Vec<u8> {
+        let mut chunk = id.to_vec();
+        chunk.extend(size_to_bytes(data.len() as u32));
+        chunk.extend(data);
+        if data.len() % 2 == 1 {
+            chunk.push(0);
+        }
+        chunk
+    }
+
+    fn wav_chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        chunk(id, data, u32::to_le_bytes)
+    }
+
+    fn aiff_chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        chunk(id, data, u32::to_be_bytes)
+    }
+
+    fn le(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    /// Mono 16 bits WAV of 1000 silent sample frames at 8 kHz, with the given trailing chunks
+    fn wav(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let mut fmt = vec![1, 0, 1, 0];
+        fmt.extend(le(&[8000, 16000]));
+        fmt.extend([2, 0, 16, 0]);
+
+        let mut body = b"WAVE".to_vec();
+        body.extend(wav_chunk(b"fmt ", &fmt));
+        body.extend(wav_chunk(b"data", &[0; 2000]));
+        chunks.iter().for_each(|chunk| body.extend(chunk));
+        wav_chunk(b"RIFF", &body)
+    }
+
+    #[test]
+    fn test_wav_markers() {
+        let mut cue = le(&[2]);
+        cue.extend(le(&[7, 0, u32::from_le_bytes(*b"data"), 0, 0, 500]));
+        cue.extend(le(&[3, 1, u32::from_le_bytes(*b"data"), 0, 0, 100]));
+
+        let mut adtl = b"adtl".to_vec();
+        let mut label = le(&[7]);
+        label.extend(b"chorus\0");
+        adtl.extend(wav_chunk(b"labl", &label));
+
+        let mut smpl = le(&[0, 0, 125_000, 60, 0, 0, 0, 1, 0]);
+        smpl.extend(le(&[3, 1, 100, 499, 0, 0]));
+
+        let mut bext = vec![0; 602];
+        bext[..5].copy_from_slice(b"take1");
+        bext[256..260].copy_from_slice(b"desk");
+        bext[320..330].copy_from_slice(b"2024-01-31");
+        bext[330..338].copy_from_slice(b"12:30:00");
+        bext[338..342].copy_from_slice(&8000_u32.to_le_bytes());
+        bext.extend(b"A=PCM,F=8000\r\n");
+
+        let file = wav(&[
+            wav_chunk(b"cue ", &cue),
+            wav_chunk(b"LIST", &adtl),
+            wav_chunk(b"smpl", &smpl),
+            wav_chunk(b"bext", &bext),
+        ]);
+
+        let context = OfflineAudioContext::new(1, 1, 8000.);
+        let decoded = context
+            .decode_audio_data_with_metadata_sync(std::io::Cursor::new(file.clone()))
+            .unwrap();
+        assert_eq!(decoded.buffer.length(), 1000);
+        assert_eq!(
+            decoded.markers,
+            vec![
+                Marker {
+                    id: 3,
+                    position: 100,
+                    label: None
+                },
+                Marker {
+                    id: 7,
+                    position: 500,
+                    label: Some("chorus".to_string())
+                },
+            ]
+        );
+        assert_eq!(
+            decoded.loops,
+            vec![LoopPoint {
+                start: 100,
+                end: 500,
+                mode: LoopMode::PingPong,
+                play_count: None,
+            }]
+        );
+        let extension = decoded.broadcast_extension.unwrap();
+        assert_eq!(extension.description, "take1");
+        assert_eq!(extension.originator, "desk");
+        assert_eq!(extension.origination_date, "2024-01-31");
+        assert_eq!(extension.origination_time, "12:30:00");
+        assert_eq!(extension.time_reference, 8000);
+        assert_eq!(extension.coding_history, "A=PCM,F=8000");
+
+        // the positions follow the resampling
+        let context = OfflineAudioContext::new(1, 1, 16000.);
+        let decoded = context
+            .decode_audio_data_with_metadata_sync(std::io::Cursor::new(file))
+            .unwrap();
+        assert_eq!(decoded.markers[1].position, 1000);
+        assert_eq!((decoded.loops[0].start, decoded.loops[0].end), (200, 1000));
+        assert_eq!(decoded.broadcast_extension.unwrap().time_reference, 16000);
+    }
+
+    #[test]
+    fn test_aiff_markers() {
+        let mut mark = 2_u16.to_be_bytes().to_vec();
+        mark.extend(1_u16.to_be_bytes());
+        mark.extend(250_u32.to_be_bytes());
+        mark.extend(b"\x05start");
+        mark.extend(2_u16.to_be_bytes());
+        mark.extend(750_u32.to_be_bytes());
+        mark.extend(b"\x00\x00");
+
+        let mut inst = vec![60, 0, 0, 127, 0, 127, 0, 0];
+        [1_u16, 1, 2, 0, 0, 0]
+            .iter()
+            .for_each(|v| inst.extend(v.to_be_bytes()));
+
+        let mut body = b"AIFF".to_vec();
+        body.extend(aiff_chunk(b"MARK", &mark));
+        body.extend(aiff_chunk(b"INST", &inst));
+        let file = aiff_chunk(b"FORM", &body);
+
+        let metadata = ContainerMetadata::parse(&file);
+        assert_eq!(
+            metadata.markers,
+            vec![
+                Marker {
+                    id: 1,
+                    position: 250,
+                    label: Some("start".to_string())
+                },
+                Marker {
+                    id: 2,
+                    position: 750,
+                    label: None
+                },
+            ]
+        );
+        assert_eq!(
+            metadata.loops,
+            vec![LoopPoint {
+                start: 250,
+                end: 750,
+                mode: LoopMode::Forward,
+                play_count: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_no_metadata() {
+        let metadata = ContainerMetadata::parse(&wav(&[]));
+        assert!(metadata.markers.is_empty());
+        assert!(metadata.loops.is_empty());
+        assert!(metadata.broadcast_extension.is_none());
+
+        let metadata = ContainerMetadata::parse(b"OggS");
+        assert!(metadata.markers.is_empty());
+    }
+}
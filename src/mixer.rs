@@ -0,0 +1,1328 @@
+//! Category buses and ducking, as commonly used for game audio mixing
+//!
+//! A [`Mixer`] routes sounds through named category buses (e.g. music, SFX, voice), each with its
+//! own volume, into a master bus. Ducking rules attenuate a category while another category is
+//! playing, e.g. to lower the music while a character is talking. Snapshots store the values of
+//! a set of params, to smoothly transition between mixer states (e.g. "underwater", "paused").
+//!
+//! Like on a mixing console, buses can be muted and soloed, and their pre-fader signal can be
+//! listened to on a separate monitor bus (PFL).
+//!
+//! For slow motion effects, the buses of the game world can be slowed down and pitched down
+//! together, while the other buses (e.g. UI, voice) are not affected.
+//!
+//! Each bus has a chain of insert effects, like the channel strip of a mixing console. The chain
+//! is rewired without clicks, and the latency of the effects is compensated on the other buses.
+
+use std::any::Any;
+
+use crate::context::{AudioContextRegistration, BaseAudioContext, ConcreteBaseAudioContext};
+use crate::node::{
+    AudioNode, ChannelConfig, ChannelConfigOptions, ChannelCountMode, ChannelInterpretation,
+    DelayNode, GainNode, GainOptions,
+};
+use crate::param::AudioParam;
+use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
+use crate::{db_to_gain, RENDER_QUANTUM_SIZE};
+
+/// Duration in seconds of the fades of the mute, solo and PFL switches
+const SWITCH_FADE: f64 = 0.01;
+
+/// Maximum total latency in seconds of the insert effects of a category
+const MAX_INSERT_LATENCY: f64 = 1.;
+
+/// Maximum delay in seconds accumulated by a time dilated category, e.g. 10 seconds of slow
+/// motion at half speed
+const MAX_TIME_DILATION_DELAY: f64 = 10.;
+/// Time constant in seconds of the transitions of the playback rate of the time dilated
+/// categories
+const TIME_DILATION_SMOOTHING: f64 = 0.05;
+/// Time constant in seconds of the catch up of the delay, once the time dilation is over
+const TIME_DILATION_CATCH_UP: f64 = 1.;
+/// Maximum playback rate of the catch up of the delay
+const MAX_CATCH_UP_RATE: f64 = 1.25;
+
+/// Rule to attenuate the `target` category while the `trigger` category is active
+///
+/// The trigger is considered active as long as the level of its bus (after its volume is applied)
+/// is above the threshold.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::mixer::DuckingRule;
+///
+/// // duck music by 6 dB while voice is active, 200 ms release
+/// let rule = DuckingRule {
+///     target: "music".into(),
+///     trigger: "voice".into(),
+///     amount_db: 6.,
+///     release: 0.2,
+///     ..DuckingRule::default()
+/// };
+/// ```
+#[derive(Clone, Debug)]
+pub struct DuckingRule {
+    /// Name of the attenuated category
+    pub target: String,
+    /// Name of the category that triggers the attenuation
+    pub trigger: String,
+    /// Attenuation of the target in decibels, when the trigger is active
+    pub amount_db: f32,
+    /// Level in dBFS above which the trigger is considered active
+    pub threshold_db: f32,
+    /// Time constant in seconds for the attenuation to kick in
+    pub attack: f64,
+    /// Time constant in seconds for the target to recover when the trigger is no longer active
+    pub release: f64,
+}
+
+impl Default for DuckingRule {
+    fn default() -> Self {
+        Self {
+            target: String::new(),
+            trigger: String::new(),
+            amount_db: 6.,
+            threshold_db: -40.,
+            attack: 0.02,
+            release: 0.2,
+        }
+    }
+}
+
+/// Bus of a sound category in a [`Mixer`]
+pub struct CategoryBus {
+    name: String,
+    input: GainNode,
+    /// insert effects, with their latency in seconds
+    effects: Vec<(Box<dyn AudioNode>, f64)>,
+    /// delay aligning the category with the category of the largest latency, inserted once
+    /// needed
+    compensation: Option<DelayNode>,
+    compensation_time: f64,
+    /// end of the insert chain, which fades out while the chain is rewired
+    insert_return: GainNode,
+    volume: GainNode,
+    /// one gain stage per ducking rule that targets this category
+    ducking: Vec<GainNode>,
+    detectors: Vec<DuckingDetectorNode>,
+    /// last stage, which applies the mute and solo states
+    switch: GainNode,
+    /// pre-fader tap to the monitor bus, after the insert effects
+    pfl_tap: GainNode,
+    muted: bool,
+    soloed: bool,
+    pfl: bool,
+    /// resampling stage after the input, inserted once the category is time dilated
+    dilation: Option<TimeDilationNode>,
+    time_dilated: bool,
+}
+
+impl CategoryBus {
+    /// Name of the category
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Node to connect the sounds of this category to
+    pub fn input(&self) -> &GainNode {
+        &self.input
+    }
+
+    /// Volume of the category, as linear gain
+    pub fn volume(&self) -> &AudioParam {
+        self.volume.gain()
+    }
+
+    /// Whether the category is muted, see [`Mixer::set_mute`]
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    /// Whether the category is soloed, see [`Mixer::set_solo`]
+    pub fn is_soloed(&self) -> bool {
+        self.soloed
+    }
+
+    /// Whether the category is sent to the monitor bus, see [`Mixer::set_pfl`]
+    pub fn is_pfl(&self) -> bool {
+        self.pfl
+    }
+
+    /// Whether the category follows the time dilation, see [`Mixer::set_time_dilated`]
+    pub fn is_time_dilated(&self) -> bool {
+        self.time_dilated
+    }
+
+    /// Insert effects of the category, in processing order, see [`Mixer::insert_effect`]
+    pub fn effects(&self) -> impl Iterator<Item = &dyn AudioNode> {
+        self.effects.iter().map(|(effect, _)| effect.as_ref())
+    }
+
+    /// Total latency in seconds of the insert effects of the category
+    pub fn latency(&self) -> f64 {
+        self.effects.iter().map(|(_, latency)| latency).sum()
+    }
+
+    /// Nodes of the insert chain, from the node feeding the effects to the insert return
+    fn chain(&self) -> Vec<&dyn AudioNode> {
+        let head: &dyn AudioNode = match &self.dilation {
+            Some(dilation) => dilation,
+            None => &self.input,
+        };
+        let mut chain = vec![head];
+        chain.extend(self.effects());
+        if let Some(compensation) = &self.compensation {
+            chain.push(compensation);
+        }
+        chain.push(&self.insert_return);
+        chain
+    }
+
+    /// Last node of the ducking chain of this category
+    fn output(&self) -> &GainNode {
+        self.ducking.last().unwrap_or(&self.volume)
+    }
+}
+
+/// Fade the gain of a switch stage to the given value
+fn fade_to(gain: &AudioParam, value: f32, now: f64) {
+    gain.cancel_and_hold_at_time(now)
+        .linear_ramp_to_value_at_time(value, now + SWITCH_FADE);
+}
+
+/// Routes sounds through category buses with per-category volume and ducking rules
+///
+/// All category buses end up in the master bus, which is connected to the destination of the
+/// context. The buses stay active as long as the `Mixer` is alive.
+///
+/// While a category is soloed, the categories that are not soloed are attenuated by the [solo
+/// dim](Self::set_solo_dim_db), and muted categories stay silent. The pre-fader signal of the
+/// categories with PFL enabled is mixed into the [monitor bus](Self::monitor), which is not
+/// connected by default: connect it to the monitoring output, e.g. to the headphones outputs of an
+/// audio interface with a channel merger and
+/// [`AudioDestinationNode::set_channel_map`](crate::node::AudioDestinationNode::set_channel_map).
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::mixer::{DuckingRule, Mixer};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+///
+/// let context = AudioContext::default();
+/// let mut mixer = Mixer::new(&context);
+/// mixer.add_category("music");
+/// mixer.add_category("voice");
+/// mixer.add_ducking_rule(DuckingRule {
+///     target: "music".into(),
+///     trigger: "voice".into(),
+///     ..DuckingRule::default()
+/// });
+///
+/// let music = mixer.category("music").unwrap();
+/// music.volume().set_value(0.8);
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(music.input());
+/// osc.start();
+/// ```
+pub struct Mixer {
+    context: ConcreteBaseAudioContext,
+    master: GainNode,
+    monitor: GainNode,
+    categories: Vec<CategoryBus>,
+    snapshots: Vec<(String, Vec<(AudioParam, f32)>)>,
+    solo_dim_db: f32,
+    time_dilation: f32,
+}
+
+impl std::fmt::Debug for Mixer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<_> = self.categories.iter().map(CategoryBus::name).collect();
+        let snapshots: Vec<_> = self.snapshots().collect();
+        f.debug_struct("Mixer")
+            .field("categories", &names)
+            .field("snapshots", &snapshots)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Mixer {
+    /// Create a new mixer, with its master bus connected to the destination of the context
+    pub fn new<C: BaseAudioContext>(context: &C) -> Self {
+        let master = context.create_gain();
+        master.connect(&context.destination());
+
+        Self {
+            context: context.base().clone(),
+            master,
+            monitor: context.create_gain(),
+            categories: vec![],
+            snapshots: vec![],
+            solo_dim_db: f32::INFINITY,
+            time_dilation: 1.,
+        }
+    }
+
+    /// The master bus, all categories are mixed into this node
+    pub fn master(&self) -> &GainNode {
+        &self.master
+    }
+
+    /// The monitor bus, the pre-fader signal of the categories with PFL enabled is mixed into
+    /// this node
+    ///
+    /// The monitor bus is not connected by default.
+    pub fn monitor(&self) -> &GainNode {
+        &self.monitor
+    }
+
+    /// Add a category bus
+    ///
+    /// # Panics
+    ///
+    /// Panics if a category with the same name already exists
+    pub fn add_category(&mut self, name: &str) -> &CategoryBus {
+        assert!(
+            self.category(name).is_none(),
+            "InvalidStateError - category {name} already exists"
+        );
+
+        let input = self.context.create_gain();
+        let insert_return = self.context.create_gain();
+        let volume = self.context.create_gain();
+        let switch = self.context.create_gain();
+        input.connect(&insert_return);
+        insert_return.connect(&volume);
+        volume.connect(&switch);
+        switch.connect(&self.master);
+
+        let pfl_tap = self.context.create_gain();
+        pfl_tap.gain().set_value(0.);
+        insert_return.connect(&pfl_tap);
+        pfl_tap.connect(&self.monitor);
+
+        self.categories.push(CategoryBus {
+            name: name.to_string(),
+            input,
+            effects: vec![],
+            compensation: None,
+            compensation_time: 0.,
+            insert_return,
+            volume,
+            ducking: vec![],
+            detectors: vec![],
+            switch,
+            pfl_tap,
+            muted: false,
+            soloed: false,
+            pfl: false,
+            dilation: None,
+            time_dilated: false,
+        });
+        // the new category is dimmed while other categories are soloed
+        self.update_switches();
+        // and aligned with the latency of the other categories
+        self.update_inserts(None, None);
+        self.categories.last().unwrap()
+    }
+
+    /// The category bus with the given name
+    pub fn category(&self, name: &str) -> Option<&CategoryBus> {
+        self.categories.iter().find(|c| c.name == name)
+    }
+
+    /// Names of all categories, in order of creation
+    pub fn categories(&self) -> impl Iterator<Item = &str> {
+        self.categories.iter().map(CategoryBus::name)
+    }
+
+    /// Add a ducking rule between two categories
+    ///
+    /// Multiple rules can target the same category, their attenuations add up.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the target or trigger category does not exist, or if they are the same
+    pub fn add_ducking_rule(&mut self, rule: DuckingRule) {
+        assert!(
+            rule.target != rule.trigger,
+            "InvalidStateError - a category cannot duck itself"
+        );
+        let trigger = self
+            .categories
+            .iter()
+            .position(|c| c.name == rule.trigger)
+            .unwrap_or_else(|| panic!("InvalidStateError - unknown category {}", rule.trigger));
+        let target = self
+            .categories
+            .iter()
+            .position(|c| c.name == rule.target)
+            .unwrap_or_else(|| panic!("InvalidStateError - unknown category {}", rule.target));
+
+        // the detector modulates the gain of an additional stage in the target chain
+        let ducking = GainNode::new(&self.context, GainOptions::default());
+        let detector = DuckingDetectorNode::new(&self.context, &rule);
+        self.categories[trigger].volume.connect(&detector);
+        detector.connect(ducking.gain());
+
+        let bus = &mut self.categories[target];
+        let last = bus.output();
+        last.disconnect_from(&bus.switch);
+        last.connect(&ducking);
+        ducking.connect(&bus.switch);
+
+        bus.ducking.push(ducking);
+        bus.detectors.push(detector);
+    }
+}
+
+/// Mute, solo and PFL
+impl Mixer {
+    /// Mute or unmute a category
+    ///
+    /// A muted category is silent, also while it is soloed. Its PFL signal is not affected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the category does not exist
+    pub fn set_mute(&mut self, name: &str, muted: bool) {
+        self.category_mut(name).muted = muted;
+        self.update_switches();
+    }
+
+    /// Solo or unsolo a category
+    ///
+    /// While at least one category is soloed, the other categories are attenuated by the [solo
+    /// dim](Self::set_solo_dim_db). Multiple categories can be soloed at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the category does not exist
+    pub fn set_solo(&mut self, name: &str, soloed: bool) {
+        self.category_mut(name).soloed = soloed;
+        self.update_switches();
+    }
+
+    /// Attenuation in decibels of the categories that are not soloed, while a category is
+    /// soloed
+    ///
+    /// Defaults to infinity, the categories are silent
+    pub fn solo_dim_db(&self) -> f32 {
+        self.solo_dim_db
+    }
+
+    /// Set the attenuation in decibels of the categories that are not soloed, while a category
+    /// is soloed
+    ///
+    /// # Panics
+    ///
+    /// Panics if the attenuation is NaN
+    pub fn set_solo_dim_db(&mut self, value: f32) {
+        assert!(
+            !value.is_nan(),
+            "RangeError - solo dim must be a number, received {value}"
+        );
+        self.solo_dim_db = value.abs();
+        self.update_switches();
+    }
+
+    /// Enable or disable pre-fader listening of a category
+    ///
+    /// The signal of the category, before its volume and ducking, is mixed into the [monitor
+    /// bus](Self::monitor). The main mix is not affected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the category does not exist
+    pub fn set_pfl(&mut self, name: &str, pfl: bool) {
+        let now = self.context.current_time();
+        let bus = self.category_mut(name);
+        bus.pfl = pfl;
+        fade_to(bus.pfl_tap.gain(), if pfl { 1. } else { 0. }, now);
+    }
+
+    fn category_mut(&mut self, name: &str) -> &mut CategoryBus {
+        self.categories
+            .iter_mut()
+            .find(|c| c.name == name)
+            .unwrap_or_else(|| panic!("InvalidStateError - unknown category {name}"))
+    }
+
+    /// Fade the switch stage of each category to its mute and solo state
+    fn update_switches(&self) {
+        let any_soloed = self.categories.iter().any(|c| c.soloed);
+        let dimmed = db_to_gain(-self.solo_dim_db);
+        let now = self.context.current_time();
+
+        self.categories.iter().for_each(|bus| {
+            let gain = if bus.muted {
+                0.
+            } else if any_soloed && !bus.soloed {
+                dimmed
+            } else {
+                1.
+            };
+            fade_to(bus.switch.gain(), gain, now);
+        });
+    }
+}
+
+/// Time dilation
+impl Mixer {
+    /// Current time dilation factor of the time dilated categories
+    ///
+    /// Defaults to 1, the categories play in real time
+    pub fn time_dilation(&self) -> f32 {
+        self.time_dilation
+    }
+
+    /// Slow down the time dilated categories, e.g. 0.5 for slow motion at half speed
+    ///
+    /// The signal of the categories is resampled: it is slowed down and pitched down together,
+    /// and the playback rate glides smoothly to the new factor. While slowed down, the
+    /// categories lag behind the rest of the mix. Once the factor is set back to 1, they play
+    /// slightly faster until this delay is caught up. The delay is limited to 10 seconds, the
+    /// categories play in real time again when it is reached.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the factor is not in the range `(0, 1]`
+    pub fn set_time_dilation(&mut self, factor: f32) {
+        assert!(
+            factor > 0. && factor <= 1.,
+            "RangeError - time dilation factor must be in the range (0, 1], received {factor}"
+        );
+        self.time_dilation = factor;
+        self.categories
+            .iter()
+            .filter(|bus| bus.time_dilated)
+            .for_each(|bus| bus.dilation.as_ref().unwrap().set_factor(factor));
+    }
+
+    /// Make a category follow the time dilation, or play it in real time
+    ///
+    /// Categories are not time dilated by default. A category that is no longer time dilated
+    /// catches up its delay, as if the factor was set back to 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the category does not exist
+    pub fn set_time_dilated(&mut self, name: &str, dilated: bool) {
+        let factor = self.time_dilation;
+        let context = self.context.clone();
+        let bus = self.category_mut(name);
+        bus.time_dilated = dilated;
+
+        // the resampling stage is only inserted when needed, it holds the delay line
+        if bus.dilation.is_none() {
+            if !dilated {
+                return;
+            }
+            let dilation = TimeDilationNode::new(&context);
+            bus.input.disconnect();
+            bus.input.connect(&dilation);
+            dilation.connect(bus.chain()[1]);
+            bus.dilation = Some(dilation);
+        }
+
+        let factor = if dilated { factor } else { 1. };
+        bus.dilation.as_ref().unwrap().set_factor(factor);
+    }
+}
+
+/// Insert effects
+impl Mixer {
+    /// Insert an effect in the chain of a category, at the given position
+    ///
+    /// The effect is connected between the input of the category and its volume, after the
+    /// effects before `index`. The `latency` of the effect, in seconds, is compensated on the
+    /// other categories so they stay aligned, e.g. the lookahead of a compressor. The signal of
+    /// the category fades out while the chain is rewired, and fades back in afterwards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the category does not exist, if `index` is larger than the number of effects,
+    /// if the effect belongs to another context, or if the latency is negative or the total
+    /// latency of the category exceeds 1 second
+    pub fn insert_effect<N: AudioNode + 'static>(
+        &mut self,
+        name: &str,
+        index: usize,
+        effect: N,
+        latency: f64,
+    ) {
+        assert!(
+            self.context == *effect.context(),
+            "InvalidAccessError - the effect belongs to another context"
+        );
+        assert!(
+            latency >= 0. && latency.is_finite(),
+            "RangeError - latency must be a positive number, received {latency}"
+        );
+        let bus = self.category_mut(name);
+        assert!(
+            index <= bus.effects.len(),
+            "IndexSizeError - effect index {index} is out of bounds"
+        );
+        assert!(
+            bus.latency() + latency <= MAX_INSERT_LATENCY,
+            "RangeError - total latency of category {name} must not exceed {MAX_INSERT_LATENCY} seconds"
+        );
+
+        bus.effects.insert(index, (Box::new(effect), latency));
+        let category = self.categories.iter().position(|c| c.name == name);
+        self.update_inserts(category, None);
+    }
+
+    /// Remove an effect from the chain of a category, and return it
+    ///
+    /// The returned effect is disconnected once the signal of the category has faded out.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the category does not exist, or if `index` is out of bounds
+    pub fn remove_effect(&mut self, name: &str, index: usize) -> Box<dyn AudioNode> {
+        let bus = self.category_mut(name);
+        assert!(
+            index < bus.effects.len(),
+            "IndexSizeError - effect index {index} is out of bounds"
+        );
+
+        let (effect, _) = bus.effects.remove(index);
+        let category = self.categories.iter().position(|c| c.name == name);
+        self.update_inserts(category, Some((index, effect.as_ref())));
+        effect
+    }
+
+    /// Rewire the insert chain of the changed category, and of the categories whose latency
+    /// compensation has changed
+    ///
+    /// The rewiring is scheduled while the insert return of the categories is faded out. The
+    /// `removed` effect of the `changed` category is given with its former index, if any.
+    fn update_inserts(&mut self, changed: Option<usize>, removed: Option<(usize, &dyn AudioNode)>) {
+        let max_latency = self
+            .categories
+            .iter()
+            .map(CategoryBus::latency)
+            .fold(0., f64::max);
+        let now = self.context.current_time();
+        let render_quantum = RENDER_QUANTUM_SIZE as f64 / f64::from(self.context.sample_rate());
+        let when = now + SWITCH_FADE + render_quantum;
+
+        let mut rewired = vec![false; self.categories.len()];
+        for (i, bus) in self.categories.iter_mut().enumerate() {
+            let compensation_time = max_latency - bus.latency();
+            let missing_delay = compensation_time > 0. && bus.compensation.is_none();
+            if missing_delay {
+                bus.compensation = Some(self.context.create_delay(MAX_INSERT_LATENCY));
+            }
+            let is_changed = changed == Some(i);
+            if !is_changed && !missing_delay && compensation_time == bus.compensation_time {
+                continue;
+            }
+
+            if let Some(compensation) = &bus.compensation {
+                compensation
+                    .delay_time()
+                    .set_value_at_time(compensation_time as f32, when);
+            }
+            bus.compensation_time = compensation_time;
+            bus.insert_return
+                .gain()
+                .cancel_and_hold_at_time(now)
+                .linear_ramp_to_value_at_time(0., now + SWITCH_FADE)
+                .set_value_at_time(0., when)
+                .linear_ramp_to_value_at_time(1., when + SWITCH_FADE);
+            rewired[i] = true;
+        }
+
+        if !rewired.contains(&true) {
+            return;
+        }
+        self.context.schedule(when, |graph| {
+            if let (Some(category), Some((index, removed))) = (changed, removed) {
+                // the removed effect was linked between the nodes now surrounding its index
+                let chain = self.categories[category].chain();
+                graph.disconnect_from(chain[index], removed);
+                graph.disconnect_from(removed, chain[index + 1]);
+            }
+            self.categories
+                .iter()
+                .zip(rewired)
+                .filter(|(_, rewired)| *rewired)
+                .for_each(|(bus, _)| {
+                    // the previous links all point forward in the new chain, the incoming
+                    // connections of the bus input are kept
+                    let chain = bus.chain();
+                    chain.iter().enumerate().for_each(|(i, &from)| {
+                        chain[i + 1..].iter().for_each(|&to| {
+                            graph.disconnect_from(from, to);
+                        });
+                    });
+                    chain.windows(2).for_each(|pair| {
+                        graph.connect(pair[0], pair[1]);
+                    });
+                });
+        });
+    }
+}
+
+/// Snapshots
+impl Mixer {
+    /// Store the current values of the given params as a named snapshot
+    ///
+    /// The params can be any params of the context, not only the volumes of the mixer. An
+    /// existing snapshot with the same name is replaced.
+    pub fn capture_snapshot(&mut self, name: &str, params: &[&AudioParam]) {
+        let values = params
+            .iter()
+            .map(|&param| (param.clone(), param.value()))
+            .collect();
+        self.remove_snapshot(name);
+        self.snapshots.push((name.to_string(), values));
+    }
+
+    /// Store the current volumes of the master bus and all categories as a named snapshot
+    pub fn capture_volumes_snapshot(&mut self, name: &str) {
+        let params: Vec<_> = std::iter::once(self.master.gain())
+            .chain(self.categories.iter().map(CategoryBus::volume))
+            .cloned()
+            .collect();
+        let params: Vec<_> = params.iter().collect();
+        self.capture_snapshot(name, &params);
+    }
+
+    /// Names of all snapshots, in order of creation
+    pub fn snapshots(&self) -> impl Iterator<Item = &str> {
+        self.snapshots.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Remove a snapshot, returns `false` if there was no snapshot with this name
+    pub fn remove_snapshot(&mut self, name: &str) -> bool {
+        let len = self.snapshots.len();
+        self.snapshots.retain(|(n, _)| n != name);
+        self.snapshots.len() != len
+    }
+
+    /// Interpolate all params of a snapshot from their current value to the stored value, over
+    /// the given duration in seconds
+    ///
+    /// Scheduled automation of the params is cancelled. The interpolation is linear, a duration
+    /// of zero applies the snapshot immediately.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no snapshot with the given name
+    pub fn apply_snapshot(&self, name: &str, duration: f64) {
+        let (_, values) = self
+            .snapshots
+            .iter()
+            .find(|(n, _)| n == name)
+            .unwrap_or_else(|| panic!("InvalidStateError - unknown snapshot {name}"));
+
+        let now = self.context.current_time();
+        values.iter().for_each(|(param, value)| {
+            if duration > 0. {
+                param
+                    .cancel_and_hold_at_time(now)
+                    .linear_ramp_to_value_at_time(*value, now + duration);
+            } else {
+                param
+                    .cancel_scheduled_values(now)
+                    .set_value_at_time(*value, now);
+            }
+        });
+    }
+}
+
+/// Converts the level of the trigger category into a gain offset for the target category
+struct DuckingDetectorNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+}
+
+impl AudioNode for DuckingDetectorNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl DuckingDetectorNode {
+    fn new<C: BaseAudioContext>(context: &C, rule: &DuckingRule) -> Self {
+        let threshold = db_to_gain(rule.threshold_db);
+        let ducked_gain = db_to_gain(-rule.amount_db.abs());
+        let attack = rule.attack.max(0.);
+        let release = rule.release.max(0.);
+
+        context.register(move |registration| {
+            let render = DuckingDetectorRenderer {
+                threshold,
+                ducked_gain,
+                attack,
+                release,
+                gain: 1.,
+            };
+            let node = Self {
+                registration,
+                channel_config: ChannelConfigOptions::default().into(),
+            };
+
+            (node, Box::new(render))
+        })
+    }
+}
+
+struct DuckingDetectorRenderer {
+    threshold: f32,
+    ducked_gain: f32,
+    attack: f64,
+    release: f64,
+    /// current gain of the target
+    gain: f32,
+}
+
+/// Coefficient of a one-pole smoothing filter with the given time constant
+fn smoothing_coefficient(time_constant: f64, sample_rate: f32) -> f32 {
+    if time_constant <= 0. {
+        0.
+    } else {
+        (-1. / (time_constant * f64::from(sample_rate))).exp() as f32
+    }
+}
+
+impl AudioProcessor for DuckingDetectorRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        scope: &RenderScope,
+    ) -> bool {
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        // fully recovered and nothing to detect
+        if input.is_silent() && self.gain == 1. {
+            output.make_silent();
+            return false;
+        }
+
+        let attack = smoothing_coefficient(self.attack, scope.sample_rate);
+        let release = smoothing_coefficient(self.release, scope.sample_rate);
+
+        let mut active = [false; crate::RENDER_QUANTUM_SIZE];
+        if !input.is_silent() {
+            input.channels().iter().for_each(|channel| {
+                active
+                    .iter_mut()
+                    .zip(channel.iter())
+                    .for_each(|(a, s)| *a |= s.abs() >= self.threshold);
+            });
+        }
+
+        output.set_number_of_channels(1);
+        let data = output.channel_data_mut(0);
+        data.iter_mut().zip(active).for_each(|(o, active)| {
+            let (target, coefficient) = if active {
+                (self.ducked_gain, attack)
+            } else {
+                (1., release)
+            };
+            self.gain = target + (self.gain - target) * coefficient;
+            if (self.gain - 1.).abs() < 1e-6 && !active {
+                self.gain = 1.;
+            }
+            // the output is added to the intrinsic gain of 1 of the ducking stage
+            *o = self.gain - 1.;
+        });
+
+        true
+    }
+}
+
+/// Resamples the signal of a time dilated category with a variable delay line
+struct TimeDilationNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+}
+
+impl AudioNode for TimeDilationNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl TimeDilationNode {
+    fn new<C: BaseAudioContext>(context: &C) -> Self {
+        // the delay line is allocated here, for a stereo signal
+        let length = (MAX_TIME_DILATION_DELAY * f64::from(context.sample_rate())).ceil() as usize;
+        let channel_config = ChannelConfigOptions {
+            count: 2,
+            count_mode: ChannelCountMode::Explicit,
+            interpretation: ChannelInterpretation::Speakers,
+        };
+
+        context.register(move |registration| {
+            let render = TimeDilationRenderer {
+                buffer: [vec![0.; length + 1], vec![0.; length + 1]],
+                write_index: 0,
+                delay: 0.,
+                rate: 1.,
+                factor: 1.,
+            };
+            let node = Self {
+                registration,
+                channel_config: channel_config.into(),
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    fn set_factor(&self, factor: f32) {
+        self.registration.post_message(f64::from(factor));
+    }
+}
+
+struct TimeDilationRenderer {
+    /// delay line of each channel
+    buffer: [Vec<f32>; 2],
+    /// index of the next frame written in the delay line
+    write_index: usize,
+    /// current delay in frames of the read position
+    delay: f64,
+    /// current playback rate
+    rate: f64,
+    /// target playback rate, 1 once the time dilation is over
+    factor: f64,
+}
+
+impl AudioProcessor for TimeDilationRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        scope: &RenderScope,
+    ) -> bool {
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        let sample_rate = f64::from(scope.sample_rate);
+        let length = self.buffer[0].len();
+        let max_delay = (length - 1) as f64;
+        let smoothing = smoothing_coefficient(TIME_DILATION_SMOOTHING, scope.sample_rate) as f64;
+
+        *output = input.clone();
+        output.set_number_of_channels(2);
+        let [left, right] = &mut self.buffer;
+        let [out_left, out_right] = output.stereo_mut();
+
+        for i in 0..RENDER_QUANTUM_SIZE {
+            left[self.write_index] = out_left[i];
+            right[self.write_index] = out_right[i];
+
+            // once the time dilation is over, catch up the delay by playing slightly faster
+            let target = if self.factor < 1. {
+                self.factor
+            } else {
+                (1. + self.delay / (TIME_DILATION_CATCH_UP * sample_rate)).min(MAX_CATCH_UP_RATE)
+            };
+            self.rate = target + (self.rate - target) * smoothing;
+            self.delay = (self.delay + 1. - self.rate).clamp(0., max_delay);
+            if self.delay < 1e-3 && self.factor == 1. {
+                self.delay = 0.;
+                self.rate = 1.;
+            }
+
+            // read the delay line with linear interpolation
+            let mut position = self.write_index as f64 - self.delay;
+            if position < 0. {
+                position += length as f64;
+            }
+            let index = position.floor() as usize % length;
+            let next = (index + 1) % length;
+            let frac = (position - position.floor()) as f32;
+            out_left[i] = left[index] + (left[next] - left[index]) * frac;
+            out_right[i] = right[index] + (right[next] - right[index]) * frac;
+
+            self.write_index = (self.write_index + 1) % length;
+        }
+
+        // the delay line still holds the signal to play
+        self.delay > 0.
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if let Some(&mut factor) = msg.downcast_mut::<f64>() {
+            self.factor = factor;
+            return;
+        }
+
+        log::warn!("TimeDilationRenderer: Dropping incoming message {msg:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    #[test]
+    fn test_category_volume() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44100.);
+        let mut mixer = Mixer::new(&context);
+        mixer.add_category("music");
+        mixer.add_category("sfx");
+        assert_eq!(mixer.categories().collect::<Vec<_>>(), ["music", "sfx"]);
+        assert!(mixer.category("voice").is_none());
+
+        let music = mixer.category("music").unwrap();
+        music.volume().set_value(0.5);
+        let mut src = context.create_constant_source();
+        src.connect(music.input());
+        src.start();
+
+        let sfx = mixer.category("sfx").unwrap();
+        sfx.volume().set_value(0.25);
+        let mut src = context.create_constant_source();
+        src.connect(sfx.input());
+        src.start();
+
+        mixer.master().gain().set_value(2.);
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(
+            output.get_channel_data(0),
+            &[1.5; RENDER_QUANTUM_SIZE][..],
+            abs_all <= 0.
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_duplicate_category() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44100.);
+        let mut mixer = Mixer::new(&context);
+        mixer.add_category("music");
+        mixer.add_category("music");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unknown_category_rule() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44100.);
+        let mut mixer = Mixer::new(&context);
+        mixer.add_category("music");
+        mixer.add_ducking_rule(DuckingRule {
+            target: "music".into(),
+            trigger: "voice".into(),
+            ..DuckingRule::default()
+        });
+    }
+
+    #[test]
+    fn test_ducking() {
+        let sample_rate = 48000.;
+        let length = 48000;
+        let context = OfflineAudioContext::new(1, length, sample_rate);
+
+        let mut mixer = Mixer::new(&context);
+        mixer.add_category("music");
+        mixer.add_category("voice");
+        mixer.add_ducking_rule(DuckingRule {
+            target: "music".into(),
+            trigger: "voice".into(),
+            amount_db: 6.,
+            attack: 0.01,
+            release: 0.05,
+            ..DuckingRule::default()
+        });
+
+        // music is constant, voice is active between 0.25 and 0.5 seconds
+        let mut music = context.create_constant_source();
+        music.connect(mixer.category("music").unwrap().input());
+        music.start();
+
+        let mut voice = context.create_constant_source();
+        voice.offset().set_value(0.1);
+        voice.connect(mixer.category("voice").unwrap().input());
+        voice.start_at(0.25);
+        voice.stop_at(0.5);
+        // cancel the voice in the mix, so the output is the music only
+        let mut cancel = context.create_constant_source();
+        cancel.offset().set_value(-0.1);
+        cancel.connect(mixer.master());
+        cancel.start_at(0.25);
+        cancel.stop_at(0.5);
+
+        let output = context.start_rendering_sync();
+        let output = output.get_channel_data(0);
+
+        let ducked = 10_f32.powf(-6. / 20.);
+        // not ducked before the voice
+        assert_float_eq!(output[10000], 1., abs <= 1e-6);
+        // ducked after the attack time
+        assert_float_eq!(output[22000], ducked, abs <= 1e-3);
+        // recovered after the release time
+        assert_float_eq!(output[47000], 1., abs <= 1e-3);
+        // smooth transitions
+        output
+            .windows(2)
+            .for_each(|w| assert!((w[1] - w[0]).abs() < 0.01));
+    }
+
+    #[test]
+    fn test_mute_solo() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE * 8, 44100.);
+        let mut mixer = Mixer::new(&context);
+        for (name, value) in [("music", 1.), ("sfx", 2.), ("voice", 4.)] {
+            mixer.add_category(name);
+            let mut src = context.create_constant_source();
+            src.offset().set_value(value);
+            src.connect(mixer.category(name).unwrap().input());
+            src.start();
+        }
+
+        mixer.set_solo_dim_db(-6.);
+        assert_float_eq!(mixer.solo_dim_db(), 6., abs <= 0.);
+        mixer.set_solo("music", true);
+        mixer.set_solo("voice", true);
+        mixer.set_mute("voice", true);
+        assert!(mixer.category("music").unwrap().is_soloed());
+        assert!(mixer.category("voice").unwrap().is_muted());
+        assert!(!mixer.category("sfx").unwrap().is_soloed());
+
+        let output = context.start_rendering_sync();
+        let output = output.get_channel_data(0);
+        // smooth fade from the full mix
+        assert_float_eq!(output[0], 7., abs <= 1e-2);
+        output
+            .windows(2)
+            .for_each(|w| assert!((w[1] - w[0]).abs() < 0.1));
+        // the muted voice is silent, the sfx are dimmed
+        let expected = 1. + 2. * db_to_gain(-6.);
+        assert_float_eq!(output[RENDER_QUANTUM_SIZE * 8 - 1], expected, abs <= 1e-5);
+    }
+
+    #[test]
+    fn test_pfl() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE * 8, 44100.);
+        let mut mixer = Mixer::new(&context);
+        mixer.add_category("music");
+        mixer.add_category("sfx");
+        for name in ["music", "sfx"] {
+            let mut src = context.create_constant_source();
+            src.connect(mixer.category(name).unwrap().input());
+            src.start();
+        }
+
+        // listen to the monitor bus only
+        mixer.master().gain().set_value(0.);
+        mixer.monitor().connect(&context.destination());
+
+        // the signal is tapped before the volume and the mute
+        let music = mixer.category("music").unwrap();
+        music.volume().set_value(0.5);
+        mixer.set_mute("music", true);
+        mixer.set_pfl("music", true);
+        assert!(mixer.category("music").unwrap().is_pfl());
+        assert!(!mixer.category("sfx").unwrap().is_pfl());
+
+        let output = context.start_rendering_sync();
+        let output = output.get_channel_data(0);
+        assert_float_eq!(output[RENDER_QUANTUM_SIZE * 8 - 1], 1., abs <= 0.);
+    }
+
+    #[test]
+    fn test_time_dilation() {
+        let sample_rate = 8000.;
+        let render = |dilated: bool| {
+            let context = OfflineAudioContext::new(1, 8000, sample_rate);
+            let mut mixer = Mixer::new(&context);
+            mixer.add_category("world");
+            mixer.set_time_dilated("world", dilated);
+            mixer.set_time_dilation(0.5);
+            assert_eq!(mixer.category("world").unwrap().is_time_dilated(), dilated);
+
+            // the value of the signal is the elapsed time
+            let ramp = (0..8000).map(|i| i as f32 / sample_rate).collect();
+            let mut src = context.create_buffer_source();
+            src.set_buffer(crate::AudioBuffer::from(vec![ramp], sample_rate));
+            src.connect(mixer.category("world").unwrap().input());
+            src.start();
+
+            context.start_rendering_sync()
+        };
+
+        // slope of the output at the end of the rendering
+        let slope = |output: &[f32]| (output[7999] - output[7899]) * sample_rate / 100.;
+
+        let output = render(true);
+        let output = output.get_channel_data(0);
+        assert_float_eq!(slope(output), 0.5, abs <= 1e-3);
+        // smooth transition to the dilated time
+        output
+            .windows(2)
+            .for_each(|w| assert!(w[1] - w[0] <= 1. / sample_rate + 1e-6));
+
+        // the other categories play in real time
+        let output = render(false);
+        assert_float_eq!(slope(output.get_channel_data(0)), 1., abs <= 1e-3);
+    }
+
+    #[test]
+    fn test_insert_effects() {
+        let length = RENDER_QUANTUM_SIZE * 16;
+        let context = OfflineAudioContext::new(1, length, 44100.);
+        let mut mixer = Mixer::new(&context);
+        mixer.add_category("music");
+        let mut src = context.create_constant_source();
+        src.connect(mixer.category("music").unwrap().input());
+        src.start();
+
+        let half = context.create_gain();
+        half.gain().set_value(0.5);
+        mixer.insert_effect("music", 0, half, 0.);
+        let quarter = context.create_gain();
+        quarter.gain().set_value(0.25);
+        mixer.insert_effect("music", 0, quarter, 0.);
+        let removed = mixer.remove_effect("music", 1);
+        assert_eq!(removed.number_of_inputs(), 1);
+        assert_eq!(mixer.category("music").unwrap().effects().count(), 1);
+
+        let output = context.start_rendering_sync();
+        let output = output.get_channel_data(0);
+        // the chain is rewired while the category is faded out
+        output
+            .windows(2)
+            .for_each(|w| assert!((w[1] - w[0]).abs() < 0.01));
+        assert_float_eq!(output[length - 1], 0.25, abs <= 0.);
+    }
+
+    #[test]
+    fn test_insert_latency_compensation() {
+        let sample_rate = 44100.;
+        let context = OfflineAudioContext::new(1, 4096, sample_rate);
+        let mut mixer = Mixer::new(&context);
+        mixer.add_category("music");
+        mixer.add_category("sfx");
+
+        // an effect with a latency of 441 frames
+        let delay = context.create_delay(1.);
+        delay.delay_time().set_value(0.01);
+        mixer.insert_effect("music", 0, delay, 0.01);
+        assert_float_eq!(mixer.category("music").unwrap().latency(), 0.01, abs <= 0.);
+
+        // an impulse played on both categories, once the chains are rewired
+        let mut impulse = vec![0.; 2000];
+        impulse[1999] = 1.;
+        let mut src = context.create_buffer_source();
+        src.set_buffer(crate::AudioBuffer::from(vec![impulse], sample_rate));
+        src.connect(mixer.category("music").unwrap().input());
+        src.connect(mixer.category("sfx").unwrap().input());
+        src.start();
+
+        let output = context.start_rendering_sync();
+        let output = output.get_channel_data(0);
+        let mut expected = vec![0.; 4096];
+        expected[1999 + 441] = 2.;
+        assert_float_eq!(output, &expected[..], abs_all <= 1e-4);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_remove_missing_effect() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44100.);
+        let mut mixer = Mixer::new(&context);
+        mixer.add_category("music");
+        mixer.remove_effect("music", 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_time_dilation() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44100.);
+        let mut mixer = Mixer::new(&context);
+        mixer.set_time_dilation(0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_solo_unknown_category() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44100.);
+        let mut mixer = Mixer::new(&context);
+        mixer.set_solo("music", true);
+    }
+
+    #[test]
+    fn test_snapshots() {
+        let sample_rate = 48000.;
+        let context = OfflineAudioContext::new(1, 48000, sample_rate);
+        let mut mixer = Mixer::new(&context);
+        let music = mixer.add_category("music").volume().clone();
+
+        music.set_value(0.2);
+        mixer.capture_volumes_snapshot("quiet");
+        music.set_value(1.);
+        mixer.capture_snapshot("loud", &[&music]);
+        mixer.capture_snapshot("replaced", &[]);
+        mixer.capture_snapshot("replaced", &[&music]);
+        assert_eq!(
+            mixer.snapshots().collect::<Vec<_>>(),
+            ["quiet", "loud", "replaced"]
+        );
+        assert!(mixer.remove_snapshot("replaced"));
+        assert!(!mixer.remove_snapshot("replaced"));
+
+        let mut src = context.create_constant_source();
+        src.connect(mixer.category("music").unwrap().input());
+        src.start();
+
+        mixer.apply_snapshot("quiet", 0.5);
+
+        let output = context.start_rendering_sync();
+        let output = output.get_channel_data(0);
+        assert_float_eq!(output[0], 1., abs <= 1e-4);
+        assert_float_eq!(output[12000], 0.6, abs <= 1e-4);
+        assert_float_eq!(output[24000], 0.2, abs <= 1e-4);
+        assert_float_eq!(output[47999], 0.2, abs <= 1e-4);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unknown_snapshot() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44100.);
+        let mixer = Mixer::new(&context);
+        mixer.apply_snapshot("underwater", 0.5);
+    }
+}
@@ -0,0 +1,607 @@
+//! Interactive music, switching between musical states as found in game audio middleware
+//!
+//! A [`MusicSwitch`] plays one [`MusicState`] at a time, e.g. the exploration, combat and victory
+//! themes of a game. Each state loops a set of stems in sync. Switching to another state is
+//! quantized to the next beat or bar of the musical grid, with optional crossfades and segments
+//! played around the transition: a pre-entry leading into the new state and an exit tail of the
+//! old state.
+
+use std::collections::HashMap;
+
+use crate::buffer::AudioBuffer;
+use crate::context::{BaseAudioContext, ConcreteBaseAudioContext};
+use crate::node::{
+    AudioBufferSourceNode, AudioBufferSourceOptions, AudioNode, AudioScheduledSourceNode, GainNode,
+};
+use crate::transport::Transport;
+
+/// Musical state of a [`MusicSwitch`]
+#[derive(Clone, Debug, Default)]
+pub struct MusicState {
+    /// Stems looped together while the state is active
+    pub stems: Vec<AudioBuffer>,
+    /// Segment leading into the state, it ends at the transition so the stems start right after
+    pub pre_entry: Option<AudioBuffer>,
+    /// Segment played from the transition when leaving the state, e.g. a cymbal tail
+    pub exit: Option<AudioBuffer>,
+}
+
+/// Musical boundary at which a transition of a [`MusicSwitch`] happens
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Quantization {
+    /// Switch as soon as possible
+    Immediate,
+    /// Switch at the next beat
+    Beat,
+    /// Switch at the next bar
+    #[default]
+    Bar,
+}
+
+/// Transition rule between two states of a [`MusicSwitch`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MusicTransition {
+    /// Boundary of the musical grid the transition is aligned to, defaults to the next bar
+    pub quantization: Quantization,
+    /// Duration in seconds of the fade out of the old state, from the transition
+    pub fade_out: f64,
+    /// Duration in seconds of the fade in of the new state, from the transition
+    pub fade_in: f64,
+}
+
+impl Default for MusicTransition {
+    fn default() -> Self {
+        Self {
+            quantization: Quantization::Bar,
+            fade_out: 0.,
+            fade_in: 0.,
+        }
+    }
+}
+
+impl MusicTransition {
+    fn assert_valid(&self) {
+        assert!(
+            self.fade_out.is_finite() && self.fade_out >= 0.,
+            "RangeError - Invalid fade out duration {:?}",
+            self.fade_out
+        );
+        assert!(
+            self.fade_in.is_finite() && self.fade_in >= 0.,
+            "RangeError - Invalid fade in duration {:?}",
+            self.fade_in
+        );
+    }
+}
+
+/// Nodes of the active state of a [`MusicSwitch`]
+struct ActiveState {
+    index: usize,
+    /// applies the fades
+    gain: GainNode,
+    sources: Vec<AudioBufferSourceNode>,
+}
+
+/// Plays one musical state at a time and switches between them on the musical grid
+///
+/// The grid is given by the tempo and the number of beats per bar, it starts when the first
+/// state starts. A transition picks the rule registered for its pair of states with
+/// [`set_transition`](Self::set_transition), or the default rule. The pre-entry segment of the
+/// new state must fit before the transition, which is postponed to the next boundary otherwise.
+///
+/// All nodes follow the clock of the [`Transport`] of the switch, so the music can be paused
+/// and resumed without losing its place on the grid. Times are expressed in that clock, see
+/// [`current_time`](Self::current_time). The music is played into the output bus, which is
+/// connected to the destination of the context.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use std::fs::File;
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::music::{MusicState, MusicSwitch, MusicTransition, Quantization};
+///
+/// let context = AudioContext::default();
+/// let decode = |path| context.decode_audio_data_sync(File::open(path).unwrap()).unwrap();
+///
+/// let mut music = MusicSwitch::new(&context, 120., 4);
+/// music.add_state("explore", MusicState {
+///     stems: vec![decode("explore-pads.wav"), decode("explore-drums.wav")],
+///     ..MusicState::default()
+/// });
+/// music.add_state("combat", MusicState {
+///     stems: vec![decode("combat.wav")],
+///     pre_entry: Some(decode("combat-fill.wav")),
+///     ..MusicState::default()
+/// });
+///
+/// let to_combat = MusicTransition {
+///     quantization: Quantization::Beat,
+///     fade_out: 0.5,
+///     ..MusicTransition::default()
+/// };
+/// music.set_transition("explore", "combat", to_combat);
+///
+/// music.set_state("explore");
+/// // when the enemies show up
+/// music.set_state("combat");
+/// ```
+pub struct MusicSwitch {
+    context: ConcreteBaseAudioContext,
+    output: GainNode,
+    transport: Transport,
+    /// in beats per minute
+    tempo: f64,
+    beats_per_bar: usize,
+    states: Vec<(String, MusicState)>,
+    default_transition: MusicTransition,
+    transitions: HashMap<(usize, usize), MusicTransition>,
+    active: Option<ActiveState>,
+    /// start of the musical grid, in the clock of the transport
+    origin: f64,
+    /// time of the last scheduled transition, in the clock of the transport
+    last_change: f64,
+}
+
+impl std::fmt::Debug for MusicSwitch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MusicSwitch")
+            .field("states", &self.states.len())
+            .field("state", &self.state())
+            .field("tempo", &self.tempo)
+            .field("beats_per_bar", &self.beats_per_bar)
+            .field("transport", &self.transport)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MusicSwitch {
+    /// Create a new switch with the given tempo in beats per minute, with its output bus
+    /// connected to the destination of the context
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tempo is not strictly positive and finite, or if there are no beats per bar
+    pub fn new<C: BaseAudioContext>(context: &C, tempo: f64, beats_per_bar: usize) -> Self {
+        assert_valid_tempo(tempo);
+        assert!(
+            beats_per_bar > 0,
+            "RangeError - A bar should have at least one beat"
+        );
+
+        let output = context.create_gain();
+        output.connect(&context.destination());
+        let transport = Transport::new(context);
+
+        Self {
+            context: context.base().clone(),
+            output,
+            transport,
+            tempo,
+            beats_per_bar,
+            states: vec![],
+            default_transition: MusicTransition::default(),
+            transitions: HashMap::new(),
+            active: None,
+            origin: 0.,
+            last_change: 0.,
+        }
+    }
+
+    /// The output bus, all states and segments are played into this node
+    pub fn output(&self) -> &GainNode {
+        &self.output
+    }
+
+    /// Register a state under the given name and return its index
+    ///
+    /// A state registered under an existing name replaces it for the next transitions.
+    pub fn add_state(&mut self, name: &str, state: MusicState) -> usize {
+        if let Some(index) = self.state_index(name) {
+            self.states[index].1 = state;
+            return index;
+        }
+        self.states.push((name.to_string(), state));
+        self.states.len() - 1
+    }
+
+    /// Index of the state registered under the given name
+    pub fn state_index(&self, name: &str) -> Option<usize> {
+        self.states.iter().position(|(n, _)| n == name)
+    }
+
+    #[track_caller]
+    fn index_of(&self, name: &str) -> usize {
+        match self.state_index(name) {
+            Some(index) => index,
+            None => panic!("NotFoundError - Unknown music state {:?}", name),
+        }
+    }
+
+    /// Name of the current state, or of the state being transitioned to
+    pub fn state(&self) -> Option<&str> {
+        self.active
+            .as_ref()
+            .map(|active| self.states[active.index].0.as_str())
+    }
+
+    /// Tempo of the musical grid, in beats per minute
+    pub fn tempo(&self) -> f64 {
+        self.tempo
+    }
+
+    /// Number of beats per bar of the musical grid
+    pub fn beats_per_bar(&self) -> usize {
+        self.beats_per_bar
+    }
+
+    /// Transition rule used between states without a specific rule
+    pub fn default_transition(&self) -> MusicTransition {
+        self.default_transition
+    }
+
+    /// Set the transition rule used between states without a specific rule
+    ///
+    /// # Panics
+    ///
+    /// Panics if a fade duration is negative or not finite
+    pub fn set_default_transition(&mut self, transition: MusicTransition) {
+        transition.assert_valid();
+        self.default_transition = transition;
+    }
+
+    /// Transition rule used from one state to another
+    ///
+    /// # Panics
+    ///
+    /// Panics if a state is unknown
+    pub fn transition(&self, from: &str, to: &str) -> MusicTransition {
+        let key = (self.index_of(from), self.index_of(to));
+        self.transitions
+            .get(&key)
+            .copied()
+            .unwrap_or(self.default_transition)
+    }
+
+    /// Set the transition rule used from one state to another
+    ///
+    /// # Panics
+    ///
+    /// Panics if a state is unknown, or if a fade duration is negative or not finite
+    pub fn set_transition(&mut self, from: &str, to: &str, transition: MusicTransition) {
+        transition.assert_valid();
+        let key = (self.index_of(from), self.index_of(to));
+        self.transitions.insert(key, transition);
+    }
+
+    /// Time in seconds of the clock of the music, see [`Transport::current_time`]
+    pub fn current_time(&self) -> f64 {
+        self.transport.current_time()
+    }
+
+    /// Pause the music, it resumes at the same place of the grid
+    pub fn pause(&mut self) {
+        self.transport.pause();
+    }
+
+    /// Resume the music after a pause
+    pub fn resume(&mut self) {
+        self.transport.resume();
+    }
+
+    /// Check if the music has been paused
+    pub fn is_paused(&self) -> bool {
+        self.transport.is_paused()
+    }
+
+    /// First boundary of the grid at or after the given time, in the clock of the transport
+    pub fn next_boundary(&self, time: f64, quantization: Quantization) -> f64 {
+        next_boundary(
+            time,
+            quantization,
+            self.tempo,
+            self.beats_per_bar,
+            self.origin,
+        )
+    }
+
+    /// Switch to the given state at the next boundary, and return the time of the transition
+    ///
+    /// Returns `None` when the state is already the current one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the state is unknown
+    pub fn set_state(&mut self, name: &str) -> Option<f64> {
+        self.set_state_at(name, self.current_time())
+    }
+
+    /// Switch to the given state at the first boundary after the given time, and return the
+    /// time of the transition
+    ///
+    /// The first state starts right at the given time, and sets the start of the grid. The
+    /// transitions never happen before a previous transition. Returns `None` when the state is
+    /// already the current one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the state is unknown, or if `when` is negative
+    pub fn set_state_at(&mut self, name: &str, when: f64) -> Option<f64> {
+        assert!(
+            when >= 0.,
+            "RangeError - Unable to switch music state at negative time {:?}",
+            when
+        );
+        let index = self.index_of(name);
+
+        let pre_entry = self.states[index]
+            .1
+            .pre_entry
+            .as_ref()
+            .map(AudioBuffer::duration)
+            .unwrap_or(0.);
+
+        let (transition, from) = match &self.active {
+            Some(active) if active.index == index => return None,
+            Some(active) => {
+                let transition = self
+                    .transitions
+                    .get(&(active.index, index))
+                    .copied()
+                    .unwrap_or(self.default_transition);
+                (transition, Some(active.index))
+            }
+            None => (MusicTransition::default(), None),
+        };
+
+        let time = if from.is_none() {
+            let time = when + pre_entry;
+            self.origin = time;
+            time
+        } else {
+            let earliest = when.max(self.last_change) + pre_entry;
+            self.next_boundary(earliest, transition.quantization)
+        };
+        self.last_change = time;
+
+        let context = self.context.clone();
+        context.batch_node_messages(|| {
+            if let Some(active) = self.active.take() {
+                self.release(active, time, transition.fade_out);
+            }
+            self.activate(index, time, transition.fade_in);
+        });
+
+        Some(time)
+    }
+
+    /// Stop the music at the next boundary after the given time, fading out the current state
+    /// with the default transition rule, and return the time of the stop
+    ///
+    /// The exit segment of the state is played. Returns `None` when no state is active.
+    pub fn stop_at(&mut self, when: f64) -> Option<f64> {
+        let active = self.active.take()?;
+        let transition = self.default_transition;
+        let time = self.next_boundary(when.max(self.last_change), transition.quantization);
+        self.last_change = time;
+
+        let context = self.context.clone();
+        context.batch_node_messages(|| self.release(active, time, transition.fade_out));
+        Some(time)
+    }
+
+    /// Stop the music at the next boundary, see [`stop_at`](Self::stop_at)
+    pub fn stop(&mut self) -> Option<f64> {
+        self.stop_at(self.current_time())
+    }
+
+    fn play_segment(&mut self, buffer: &AudioBuffer, when: f64) {
+        let options = AudioBufferSourceOptions {
+            buffer: Some(buffer.clone()),
+            ..AudioBufferSourceOptions::default()
+        };
+        let mut source = AudioBufferSourceNode::new(&self.context, options);
+        source.connect(&self.output);
+        self.transport.add(&source);
+        source.start_at(when.max(0.));
+    }
+
+    fn activate(&mut self, index: usize, time: f64, fade_in: f64) {
+        let gain = self.context.create_gain();
+        gain.connect(&self.output);
+        self.transport.add(&gain);
+        if fade_in > 0. {
+            gain.gain().set_value(0.);
+            gain.gain()
+                .set_value_at_time(0., time)
+                .linear_ramp_to_value_at_time(1., time + fade_in);
+        }
+
+        let state = self.states[index].1.clone();
+        if let Some(pre_entry) = &state.pre_entry {
+            self.play_segment(pre_entry, time - pre_entry.duration());
+        }
+
+        let sources = state
+            .stems
+            .iter()
+            .map(|buffer| {
+                let options = AudioBufferSourceOptions {
+                    buffer: Some(buffer.clone()),
+                    loop_: true,
+                    ..AudioBufferSourceOptions::default()
+                };
+                let mut source = AudioBufferSourceNode::new(&self.context, options);
+                source.connect(&gain);
+                self.transport.add(&source);
+                source.start_at(time);
+                source
+            })
+            .collect();
+
+        self.active = Some(ActiveState {
+            index,
+            gain,
+            sources,
+        });
+    }
+
+    fn release(&mut self, active: ActiveState, time: f64, fade_out: f64) {
+        let gain = active.gain.gain();
+        gain.cancel_and_hold_at_time(time);
+        if fade_out > 0. {
+            gain.set_value_at_time(gain.value(), time)
+                .linear_ramp_to_value_at_time(0., time + fade_out);
+        } else {
+            gain.set_value_at_time(0., time);
+        }
+        active
+            .sources
+            .into_iter()
+            .for_each(|mut source| source.stop_at(time + fade_out));
+
+        if let Some(exit) = self.states[active.index].1.exit.clone() {
+            self.play_segment(&exit, time);
+        }
+    }
+}
+
+/// First boundary at or after the given time of the grid starting at `origin`
+pub(crate) fn next_boundary(
+    time: f64,
+    quantization: Quantization,
+    tempo: f64,
+    beats_per_bar: usize,
+    origin: f64,
+) -> f64 {
+    let seconds_per_beat = 60. / tempo;
+    let unit = match quantization {
+        Quantization::Immediate => return time,
+        Quantization::Beat => seconds_per_beat,
+        Quantization::Bar => seconds_per_beat * beats_per_bar as f64,
+    };
+    if time <= origin {
+        return origin;
+    }
+    // tolerate rounding errors of times given on the grid
+    let count = ((time - origin) / unit - 1e-9).ceil();
+    origin + count * unit
+}
+
+pub(crate) fn assert_valid_tempo(bpm: f64) {
+    assert!(
+        bpm.is_finite() && bpm > 0.,
+        "RangeError - Invalid tempo {:?}, should be strictly positive",
+        bpm
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::OfflineAudioContext;
+    use crate::RENDER_QUANTUM_SIZE;
+
+    use float_eq::assert_float_eq;
+
+    // power of two sample rate so the boundaries are exact
+    const SAMPLE_RATE: f32 = 32_768.;
+
+    fn constant(value: f32, length: usize) -> AudioBuffer {
+        AudioBuffer::from(vec![vec![value; length]], SAMPLE_RATE)
+    }
+
+    fn frames(count: usize) -> f64 {
+        count as f64 / SAMPLE_RATE as f64
+    }
+
+    #[test]
+    fn test_quantized_transition() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, SAMPLE_RATE);
+        // one beat lasts 32 frames, one bar 64 frames
+        let mut music = MusicSwitch::new(&context, 60. * SAMPLE_RATE as f64 / 32., 2);
+        music.add_state(
+            "a",
+            MusicState {
+                stems: vec![constant(1., 64)],
+                exit: Some(constant(4., 8)),
+                ..MusicState::default()
+            },
+        );
+        music.add_state(
+            "b",
+            MusicState {
+                stems: vec![constant(2., 64)],
+                pre_entry: Some(constant(10., 16)),
+                ..MusicState::default()
+            },
+        );
+
+        assert_eq!(music.set_state("a"), Some(0.));
+        assert_eq!(music.set_state("a"), None);
+        assert_eq!(music.set_state_at("b", frames(1)), Some(frames(64)));
+        assert_eq!(music.state(), Some("b"));
+
+        let output = context.start_rendering_sync();
+        let mut expected = vec![1.; RENDER_QUANTUM_SIZE];
+        expected[48..64].fill(11.);
+        expected[64..].fill(2.);
+        expected[64..72].fill(6.);
+        assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_boundaries() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, SAMPLE_RATE);
+        let mut music = MusicSwitch::new(&context, 120., 4);
+        music.add_state("a", MusicState::default());
+        music.set_state_at("a", 1.);
+
+        assert_float_eq!(music.next_boundary(0.2, Quantization::Bar), 1., abs <= 0.);
+        assert_float_eq!(music.next_boundary(1.2, Quantization::Beat), 1.5, abs <= 0.);
+        assert_float_eq!(music.next_boundary(1.2, Quantization::Bar), 3., abs <= 0.);
+        assert_float_eq!(music.next_boundary(3., Quantization::Bar), 3., abs <= 0.);
+        assert_float_eq!(
+            music.next_boundary(1.2, Quantization::Immediate),
+            1.2,
+            abs <= 0.
+        );
+    }
+
+    #[test]
+    fn test_fade_out() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, SAMPLE_RATE);
+        let mut music = MusicSwitch::new(&context, 120., 4);
+        music.add_state(
+            "a",
+            MusicState {
+                stems: vec![constant(1., 256)],
+                ..MusicState::default()
+            },
+        );
+        music.set_default_transition(MusicTransition {
+            quantization: Quantization::Immediate,
+            fade_out: frames(64),
+            fade_in: 0.,
+        });
+        music.set_state("a");
+        assert_eq!(music.stop_at(frames(32)), Some(frames(32)));
+        assert_eq!(music.state(), None);
+
+        let output = context.start_rendering_sync();
+        let output = output.get_channel_data(0);
+        assert_float_eq!(output[31], 1., abs <= 0.);
+        assert_float_eq!(output[64], 0.5, abs <= 1e-6);
+        assert_float_eq!(output[100], 0., abs <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unknown_state() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, SAMPLE_RATE);
+        let mut music = MusicSwitch::new(&context, 120., 4);
+        music.set_state("a");
+    }
+}
@@ -0,0 +1,316 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
+use crate::{db_to_gain, AtomicF32, RENDER_QUANTUM_SIZE};
+
+use super::{AudioNode, ChannelConfig, ChannelConfigOptions};
+
+/// Time constant of the level detection, in seconds
+const LEVEL_TIME_CONSTANT: f64 = 0.1;
+
+/// Level in dBFS below which the input is considered noise, the gain is held meanwhile
+const GATE_LEVEL: f32 = -60.;
+
+/// Options for constructing an [`AgcNode`]
+#[derive(Clone, Debug)]
+pub struct AgcOptions {
+    /// level of the output, in dBFS RMS
+    pub target_level: f32,
+    /// maximum gain applied to the input, in dB
+    pub max_gain: f32,
+    /// maximum rate of change of the gain, in dB per second
+    pub adaptation_speed: f32,
+    pub channel_config: ChannelConfigOptions,
+}
+
+impl Default for AgcOptions {
+    fn default() -> Self {
+        Self {
+            target_level: -18.,
+            max_gain: 30.,
+            adaptation_speed: 6.,
+            channel_config: ChannelConfigOptions::default(),
+        }
+    }
+}
+
+/// `AgcNode` is an automatic gain control, leveling its input to a target level
+///
+/// The RMS level of the input (averaged over all channels) is followed with a time constant of
+/// 100ms, and the gain slides towards the difference between the target level and the input
+/// level, by at most `adaptation_speed` dB per second. The gain never exceeds `max_gain`, and is
+/// held while the input is quieter than -60 dBFS so the background noise is not raised in the
+/// pauses of a speech. The current gain can be read with [`gain`](Self::gain).
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::media_devices::{self, MediaStreamConstraints};
+/// use web_audio_api::node::{AgcNode, AgcOptions, AudioNode};
+///
+/// let context = AudioContext::default();
+///
+/// let mic = media_devices::get_user_media_sync(MediaStreamConstraints::Audio);
+/// let source = context.create_media_stream_source(&mic);
+///
+/// let agc = AgcNode::new(&context, AgcOptions::default());
+/// agc.target_level().set_value(-16.);
+/// source.connect(&agc);
+///
+/// let recording = context.create_media_stream_destination();
+/// agc.connect(&recording);
+/// ```
+pub struct AgcNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    target_level: AudioParam,
+    max_gain: AudioParam,
+    adaptation_speed: AudioParam,
+    gain: Arc<AtomicF32>,
+}
+
+impl AudioNode for AgcNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl AgcNode {
+    pub fn new<C: BaseAudioContext>(context: &C, options: AgcOptions) -> Self {
+        context.register(move |registration| {
+            let target_level_opts = AudioParamDescriptor {
+                min_value: -100.,
+                max_value: 0.,
+                default_value: -18.,
+                automation_rate: AutomationRate::K,
+            };
+            let (target_level, target_level_proc) =
+                context.create_audio_param(target_level_opts, &registration);
+            target_level.set_value(options.target_level);
+
+            let max_gain_opts = AudioParamDescriptor {
+                min_value: 0.,
+                max_value: 100.,
+                default_value: 30.,
+                automation_rate: AutomationRate::K,
+            };
+            let (max_gain, max_gain_proc) =
+                context.create_audio_param(max_gain_opts, &registration);
+            max_gain.set_value(options.max_gain);
+
+            let adaptation_speed_opts = AudioParamDescriptor {
+                min_value: 0.,
+                max_value: f32::MAX,
+                default_value: 6.,
+                automation_rate: AutomationRate::K,
+            };
+            let (adaptation_speed, adaptation_speed_proc) =
+                context.create_audio_param(adaptation_speed_opts, &registration);
+            adaptation_speed.set_value(options.adaptation_speed);
+
+            let gain = Arc::new(AtomicF32::new(0.));
+
+            let render = AgcRenderer {
+                target_level: target_level_proc,
+                max_gain: max_gain_proc,
+                adaptation_speed: adaptation_speed_proc,
+                mean_square: 0.,
+                gain_db: 0.,
+                gain: Arc::clone(&gain),
+            };
+
+            let node = AgcNode {
+                registration,
+                channel_config: options.channel_config.into(),
+                target_level,
+                max_gain,
+                adaptation_speed,
+                gain,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// Level of the output, in dBFS RMS
+    #[must_use]
+    pub fn target_level(&self) -> &AudioParam {
+        &self.target_level
+    }
+
+    /// Maximum gain applied to the input, in dB
+    #[must_use]
+    pub fn max_gain(&self) -> &AudioParam {
+        &self.max_gain
+    }
+
+    /// Maximum rate of change of the gain, in dB per second
+    #[must_use]
+    pub fn adaptation_speed(&self) -> &AudioParam {
+        &self.adaptation_speed
+    }
+
+    /// Gain currently applied to the input, in dB
+    #[must_use]
+    pub fn gain(&self) -> f32 {
+        self.gain.load(Ordering::Relaxed)
+    }
+}
+
+struct AgcRenderer {
+    target_level: AudioParamId,
+    max_gain: AudioParamId,
+    adaptation_speed: AudioParamId,
+    /// exponentially averaged mean square of the input
+    mean_square: f64,
+    /// gain at the end of the last render quantum, in dB
+    gain_db: f32,
+    gain: Arc<AtomicF32>,
+}
+
+impl AudioProcessor for AgcRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &RenderScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        let target_level = params.get(&self.target_level)[0];
+        let max_gain = params.get(&self.max_gain)[0];
+        let adaptation_speed = params.get(&self.adaptation_speed)[0];
+
+        let sample_rate = f64::from(scope.sample_rate);
+        let smoothing = (-1. / (LEVEL_TIME_CONSTANT * sample_rate)).exp();
+
+        if input.is_silent() {
+            output.make_silent();
+            self.mean_square *= smoothing.powi(RENDER_QUANTUM_SIZE as i32);
+            // the gain is held, no tail time
+            return false;
+        }
+
+        // follow the level of the input
+        let scale = 1. / input.number_of_channels() as f64;
+        for i in 0..RENDER_QUANTUM_SIZE {
+            let sum: f64 = input
+                .channels()
+                .iter()
+                .map(|channel| f64::from(channel[i]).powi(2))
+                .sum();
+            self.mean_square = smoothing * self.mean_square + (1. - smoothing) * sum * scale;
+        }
+        let level = (10. * self.mean_square.max(1e-20).log10()) as f32;
+
+        let previous_gain_db = self.gain_db;
+        if level > GATE_LEVEL {
+            let desired = (target_level - level).min(max_gain);
+            let max_step = adaptation_speed * RENDER_QUANTUM_SIZE as f32 / scope.sample_rate;
+            self.gain_db += (desired - self.gain_db).clamp(-max_step, max_step);
+        }
+        // max gain could have been lowered while the input was quiet
+        self.gain_db = self.gain_db.min(max_gain);
+        self.gain.store(self.gain_db, Ordering::Relaxed);
+
+        // ramp the gain over the render quantum to avoid zipper noise
+        let start = db_to_gain(previous_gain_db);
+        let end = db_to_gain(self.gain_db);
+        let step = (end - start) / RENDER_QUANTUM_SIZE as f32;
+
+        *output = input.clone();
+        output.channels_mut().iter_mut().for_each(|channel| {
+            channel.iter_mut().enumerate().for_each(|(i, o)| {
+                *o *= start + step * (i + 1) as f32;
+            });
+        });
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    fn level(offset: f32, options: AgcOptions) -> (f32, f32) {
+        let context = OfflineAudioContext::new(1, 48_000, 48_000.);
+        let agc = AgcNode::new(&context, options);
+        agc.connect(&context.destination());
+
+        let mut src = context.create_constant_source();
+        src.offset().set_value(offset);
+        src.connect(&agc);
+        src.start();
+
+        let output = context.start_rendering_sync();
+        (agc.gain(), *output.get_channel_data(0).last().unwrap())
+    }
+
+    #[test]
+    fn test_reach_target() {
+        let options = AgcOptions {
+            target_level: -10.,
+            adaptation_speed: 100.,
+            ..AgcOptions::default()
+        };
+
+        // -20 dBFS input is raised
+        let (gain, output) = level(0.1, options.clone());
+        assert_float_eq!(gain, 10., abs <= 0.1);
+        assert_float_eq!(output, db_to_gain(-10.), abs <= 1e-3);
+
+        // -6 dBFS input is lowered
+        let (gain, output) = level(0.5, options);
+        assert_float_eq!(gain, -3.98, abs <= 0.1);
+        assert_float_eq!(output, db_to_gain(-10.), abs <= 1e-3);
+    }
+
+    #[test]
+    fn test_max_gain_and_speed() {
+        let options = AgcOptions {
+            target_level: -10.,
+            max_gain: 12.,
+            adaptation_speed: 100.,
+            ..AgcOptions::default()
+        };
+        let (gain, _) = level(0.01, options);
+        assert_float_eq!(gain, 12., abs <= 0.);
+
+        // the gain moves by at most 6 dB in one second
+        let (gain, _) = level(0.01, AgcOptions::default());
+        assert_float_eq!(gain, 6., abs <= 0.01);
+    }
+
+    #[test]
+    fn test_gate() {
+        // the noise is not raised
+        let (gain, _) = level(1e-4, AgcOptions::default());
+        assert_float_eq!(gain, 0., abs <= 0.);
+    }
+}
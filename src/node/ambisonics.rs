@@ -0,0 +1,1371 @@
+//! Higher-order ambisonics (HOA) encoding, rotation, and binaural or speaker decoding
+//!
+//! Ambisonic signals use the ACN channel ordering and the SN3D normalization (i.e. the AmbiX
+//! format), up to the 3rd order. Directions are expressed in the ambisonic convention: the
+//! azimuth is measured counterclockwise from the front, the elevation upwards from the horizontal
+//! plane, both in degrees.
+use std::any::Any;
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use realfft::num_complex::Complex;
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::memory::{Allocation, MemoryKind};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::convolver::Fft;
+use super::{
+    AudioNode, ChannelConfig, ChannelConfigOptions, ChannelCountMode, ChannelInterpretation,
+};
+
+/// Highest supported ambisonic order
+pub const MAX_AMBISONIC_ORDER: usize = 3;
+
+/// Number of channels of a signal of the highest supported order
+const MAX_AMBISONIC_CHANNELS: usize = (MAX_AMBISONIC_ORDER + 1) * (MAX_AMBISONIC_ORDER + 1);
+
+/// Number of directions used to sample the spherical harmonics when computing rotations
+const NUM_SAMPLING_DIRECTIONS: usize = 64;
+
+/// Number of channels of an ambisonic signal of the given order, i.e. `(order + 1)^2`
+pub fn ambisonic_channel_count(order: usize) -> usize {
+    (order + 1) * (order + 1)
+}
+
+#[track_caller]
+#[inline(always)]
+fn assert_valid_order(order: usize) {
+    if order == 0 || order > MAX_AMBISONIC_ORDER {
+        panic!(
+            "NotSupportedError - ambisonic order {:?} is outside range [1, {:?}]",
+            order, MAX_AMBISONIC_ORDER
+        );
+    }
+}
+
+/// Unit vector (x front, y left, z up) of the given azimuth and elevation in degrees
+fn direction(azimuth: f32, elevation: f32) -> [f32; 3] {
+    let (az, el) = (azimuth.to_radians(), elevation.to_radians());
+    [az.cos() * el.cos(), az.sin() * el.cos(), el.sin()]
+}
+
+/// SN3D normalized real spherical harmonics of the given direction, in ACN order
+fn spherical_harmonics(order: usize, [x, y, z]: [f32; 3], out: &mut [f32]) {
+    out[0] = 1.;
+
+    if order >= 1 {
+        out[1] = y;
+        out[2] = z;
+        out[3] = x;
+    }
+
+    if order >= 2 {
+        let s3 = 3_f32.sqrt();
+        out[4] = s3 * x * y;
+        out[5] = s3 * y * z;
+        out[6] = 0.5 * (3. * z * z - 1.);
+        out[7] = s3 * x * z;
+        out[8] = 0.5 * s3 * (x * x - y * y);
+    }
+
+    if order >= 3 {
+        let a = (5_f32 / 8.).sqrt();
+        let b = 15_f32.sqrt();
+        let c = (3_f32 / 8.).sqrt();
+        out[9] = a * y * (3. * x * x - y * y);
+        out[10] = b * x * y * z;
+        out[11] = c * y * (5. * z * z - 1.);
+        out[12] = 0.5 * z * (5. * z * z - 3.);
+        out[13] = c * x * (5. * z * z - 1.);
+        out[14] = 0.5 * b * z * (x * x - y * y);
+        out[15] = a * x * (x * x - 3. * y * y);
+    }
+}
+
+/// Directions evenly spread on the unit sphere (Fibonacci lattice)
+fn sampling_directions(count: usize) -> Vec<[f32; 3]> {
+    let golden_angle = PI * (3. - 5_f32.sqrt());
+    (0..count)
+        .map(|i| {
+            let z = 1. - (2 * i + 1) as f32 / count as f32;
+            let radius = (1. - z * z).sqrt();
+            let phi = golden_angle * i as f32;
+            [radius * phi.cos(), radius * phi.sin(), z]
+        })
+        .collect()
+}
+
+/// Regularized pseudo inverse of the matrix of spherical harmonics sampled in the given
+/// directions (one row per direction).
+///
+/// Returns the weights `w` (one row per direction) such that `Σ w[n][k] Y(d_n) ≈ e_k`, i.e. that
+/// map an ambisonic signal onto the sampling directions.
+fn pseudo_inverse(harmonics: &[Vec<f32>], regularization: f64) -> Vec<Vec<f32>> {
+    let k = harmonics[0].len();
+
+    // Gram matrix, augmented with the identity for the Gauss-Jordan elimination
+    let mut gram = vec![vec![0_f64; 2 * k]; k];
+    for (i, row) in gram.iter_mut().enumerate() {
+        for j in 0..k {
+            row[j] = harmonics
+                .iter()
+                .map(|y| f64::from(y[i]) * f64::from(y[j]))
+                .sum();
+        }
+        row[i] += regularization;
+        row[k + i] = 1.;
+    }
+
+    for col in 0..k {
+        let pivot = (col..k)
+            .max_by(|&a, &b| gram[a][col].abs().total_cmp(&gram[b][col].abs()))
+            .unwrap();
+        gram.swap(col, pivot);
+        let scale = gram[col][col];
+        gram[col].iter_mut().for_each(|v| *v /= scale);
+        let pivot_row = gram[col].clone();
+        for (row, values) in gram.iter_mut().enumerate() {
+            if row != col {
+                let factor = values[col];
+                values
+                    .iter_mut()
+                    .zip(pivot_row.iter())
+                    .for_each(|(v, p)| *v -= factor * p);
+            }
+        }
+    }
+
+    harmonics
+        .iter()
+        .map(|y| {
+            (0..k)
+                .map(|j| {
+                    (0..k)
+                        .map(|i| f64::from(y[i]) * gram[i][k + j])
+                        .sum::<f64>() as f32
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Options for constructing an [`AmbisonicEncoderNode`]
+#[derive(Clone, Debug)]
+pub struct AmbisonicEncoderOptions {
+    pub order: usize,
+    pub azimuth: f32,
+    pub elevation: f32,
+}
+
+impl Default for AmbisonicEncoderOptions {
+    fn default() -> Self {
+        Self {
+            order: 1,
+            azimuth: 0.,
+            elevation: 0.,
+        }
+    }
+}
+
+/// `AmbisonicEncoderNode` positions a mono input in an ambisonic sound field
+///
+/// The output has `(order + 1)^2` channels, in ACN order with SN3D normalization.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{AmbisonicEncoderNode, AmbisonicEncoderOptions};
+/// use web_audio_api::node::{AmbisonicBinauralDecoderNode, AmbisonicBinauralDecoderOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let options = AmbisonicEncoderOptions { order: 3, azimuth: 90., ..Default::default() };
+/// let encoder = AmbisonicEncoderNode::new(&context, options);
+/// let options = AmbisonicBinauralDecoderOptions { order: 3 };
+/// let decoder = AmbisonicBinauralDecoderNode::new(&context, options);
+/// encoder.connect(&decoder);
+/// decoder.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&encoder);
+/// osc.start();
+/// ```
+pub struct AmbisonicEncoderNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    order: usize,
+    azimuth: AudioParam,
+    elevation: AudioParam,
+}
+
+impl AudioNode for AmbisonicEncoderNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn set_channel_count(&self, _v: usize) {
+        panic!("InvalidStateError: Cannot edit channel count of AmbisonicEncoderNode")
+    }
+
+    fn set_channel_count_mode(&self, _v: ChannelCountMode) {
+        panic!("InvalidStateError: Cannot edit channel count mode of AmbisonicEncoderNode")
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl AmbisonicEncoderNode {
+    /// Create a new `AmbisonicEncoderNode`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the order is outside the [1, 3] range
+    pub fn new<C: BaseAudioContext>(context: &C, options: AmbisonicEncoderOptions) -> Self {
+        let AmbisonicEncoderOptions {
+            order,
+            azimuth,
+            elevation,
+        } = options;
+        assert_valid_order(order);
+
+        context.register(move |registration| {
+            let (azimuth_param, azimuth_proc) = angle_param(context, &registration, azimuth);
+            let (elevation_param, elevation_proc) = angle_param(context, &registration, elevation);
+
+            let mut gains = [0.; MAX_AMBISONIC_CHANNELS];
+            spherical_harmonics(order, direction(azimuth, elevation), &mut gains);
+
+            let renderer = AmbisonicEncoderRenderer {
+                order,
+                azimuth: azimuth_proc,
+                elevation: elevation_proc,
+                gains,
+            };
+
+            let node = Self {
+                registration,
+                channel_config: ChannelConfigOptions {
+                    count: 1,
+                    count_mode: ChannelCountMode::Explicit,
+                    interpretation: ChannelInterpretation::Speakers,
+                }
+                .into(),
+                order,
+                azimuth: azimuth_param,
+                elevation: elevation_param,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// Ambisonic order of the output signal
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// K-rate [`AudioParam`] for the azimuth of the source, in degrees counterclockwise from
+    /// the front
+    pub fn azimuth(&self) -> &AudioParam {
+        &self.azimuth
+    }
+
+    /// K-rate [`AudioParam`] for the elevation of the source, in degrees
+    pub fn elevation(&self) -> &AudioParam {
+        &self.elevation
+    }
+}
+
+/// Create a k-rate param for an angle in degrees
+fn angle_param<C: BaseAudioContext>(
+    context: &C,
+    registration: &AudioContextRegistration,
+    default_value: f32,
+) -> (AudioParam, AudioParamId) {
+    let options = AudioParamDescriptor {
+        min_value: f32::MIN,
+        max_value: f32::MAX,
+        default_value,
+        automation_rate: AutomationRate::K,
+    };
+    let (mut param, proc) = context.create_audio_param(options, registration);
+    param.set_automation_rate_constrained(true);
+    (param, proc)
+}
+
+struct AmbisonicEncoderRenderer {
+    order: usize,
+    azimuth: AudioParamId,
+    elevation: AudioParamId,
+    gains: [f32; MAX_AMBISONIC_CHANNELS],
+}
+
+impl AudioProcessor for AmbisonicEncoderRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        _scope: &RenderScope,
+    ) -> bool {
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        let azimuth = params.get(&self.azimuth)[0];
+        let elevation = params.get(&self.elevation)[0];
+        let prev_gains = self.gains;
+        spherical_harmonics(self.order, direction(azimuth, elevation), &mut self.gains);
+
+        if input.is_silent() {
+            output.make_silent();
+            return false;
+        }
+
+        let channel_count = ambisonic_channel_count(self.order);
+        output.set_number_of_channels(channel_count);
+
+        let source = input.channel_data(0);
+        output
+            .channels_mut()
+            .iter_mut()
+            .zip(prev_gains.iter().zip(self.gains.iter()))
+            .for_each(|(channel, (&prev, &gain))| {
+                // interpolate the gains to avoid zipper noise on movements
+                let step = (gain - prev) / RENDER_QUANTUM_SIZE as f32;
+                channel
+                    .iter_mut()
+                    .zip(source.iter())
+                    .enumerate()
+                    .for_each(|(i, (o, s))| *o = s * step.mul_add((i + 1) as f32, prev));
+            });
+
+        false
+    }
+}
+
+/// Options for constructing an [`AmbisonicRotatorNode`]
+#[derive(Clone, Debug)]
+pub struct AmbisonicRotatorOptions {
+    pub order: usize,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub roll: f32,
+}
+
+impl Default for AmbisonicRotatorOptions {
+    fn default() -> Self {
+        Self {
+            order: 1,
+            yaw: 0.,
+            pitch: 0.,
+            roll: 0.,
+        }
+    }
+}
+
+/// `AmbisonicRotatorNode` rotates an ambisonic sound field
+///
+/// The rotation is applied around the up axis (yaw), then the left axis (pitch), then the front
+/// axis (roll), following the right-hand rule, e.g. a positive yaw moves the sources to the left.
+/// For head tracking, rotate the sound field by the inverse of the head orientation.
+///
+/// The input is expected to have `(order + 1)^2` channels, in ACN order with SN3D
+/// normalization.
+pub struct AmbisonicRotatorNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    order: usize,
+    yaw: AudioParam,
+    pitch: AudioParam,
+    roll: AudioParam,
+}
+
+impl AudioNode for AmbisonicRotatorNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn set_channel_count(&self, _v: usize) {
+        panic!("InvalidStateError: Cannot edit channel count of AmbisonicRotatorNode")
+    }
+
+    fn set_channel_count_mode(&self, _v: ChannelCountMode) {
+        panic!("InvalidStateError: Cannot edit channel count mode of AmbisonicRotatorNode")
+    }
+
+    fn set_channel_interpretation(&self, _v: ChannelInterpretation) {
+        panic!("InvalidStateError: Cannot edit channel interpretation of AmbisonicRotatorNode")
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl AmbisonicRotatorNode {
+    /// Create a new `AmbisonicRotatorNode`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the order is outside the [1, 3] range
+    pub fn new<C: BaseAudioContext>(context: &C, options: AmbisonicRotatorOptions) -> Self {
+        let AmbisonicRotatorOptions {
+            order,
+            yaw,
+            pitch,
+            roll,
+        } = options;
+        assert_valid_order(order);
+
+        context.register(move |registration| {
+            let (yaw_param, yaw_proc) = angle_param(context, &registration, yaw);
+            let (pitch_param, pitch_proc) = angle_param(context, &registration, pitch);
+            let (roll_param, roll_proc) = angle_param(context, &registration, roll);
+
+            let channel_count = ambisonic_channel_count(order);
+            let directions = sampling_directions(NUM_SAMPLING_DIRECTIONS);
+            let harmonics: Vec<Vec<f32>> = directions
+                .iter()
+                .map(|&d| {
+                    let mut y = vec![0.; channel_count];
+                    spherical_harmonics(order, d, &mut y);
+                    y
+                })
+                .collect();
+            let weights = pseudo_inverse(&harmonics, 0.);
+
+            let mut renderer = AmbisonicRotatorRenderer {
+                order,
+                yaw: yaw_proc,
+                pitch: pitch_proc,
+                roll: roll_proc,
+                directions,
+                weights,
+                angles: [yaw, pitch, roll],
+                matrix: [[0.; MAX_AMBISONIC_CHANNELS]; MAX_AMBISONIC_CHANNELS],
+                prev_matrix: [[0.; MAX_AMBISONIC_CHANNELS]; MAX_AMBISONIC_CHANNELS],
+            };
+            renderer.update_matrix();
+            renderer.prev_matrix = renderer.matrix;
+
+            let node = Self {
+                registration,
+                channel_config: ChannelConfigOptions {
+                    count: channel_count,
+                    count_mode: ChannelCountMode::Explicit,
+                    interpretation: ChannelInterpretation::Discrete,
+                }
+                .into(),
+                order,
+                yaw: yaw_param,
+                pitch: pitch_param,
+                roll: roll_param,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// Ambisonic order of the rotated signal
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// K-rate [`AudioParam`] for the rotation around the up axis, in degrees
+    pub fn yaw(&self) -> &AudioParam {
+        &self.yaw
+    }
+
+    /// K-rate [`AudioParam`] for the rotation around the left axis, in degrees
+    pub fn pitch(&self) -> &AudioParam {
+        &self.pitch
+    }
+
+    /// K-rate [`AudioParam`] for the rotation around the front axis, in degrees
+    pub fn roll(&self) -> &AudioParam {
+        &self.roll
+    }
+}
+
+struct AmbisonicRotatorRenderer {
+    order: usize,
+    yaw: AudioParamId,
+    pitch: AudioParamId,
+    roll: AudioParamId,
+    directions: Vec<[f32; 3]>,
+    /// pseudo inverse of the harmonics sampled in `directions`
+    weights: Vec<Vec<f32>>,
+    angles: [f32; 3],
+    matrix: [[f32; MAX_AMBISONIC_CHANNELS]; MAX_AMBISONIC_CHANNELS],
+    prev_matrix: [[f32; MAX_AMBISONIC_CHANNELS]; MAX_AMBISONIC_CHANNELS],
+}
+
+impl AmbisonicRotatorRenderer {
+    /// Compute the rotation matrix of the spherical harmonics from the current angles
+    ///
+    /// As the signal is a combination of the harmonics sampled in the sampling directions, its
+    /// rotation is the same combination of the harmonics sampled in the rotated directions.
+    fn update_matrix(&mut self) {
+        let [yaw, pitch, roll] = self.angles.map(f32::to_radians);
+        let (sy, cy) = yaw.sin_cos();
+        let (sp, cp) = pitch.sin_cos();
+        let (sr, cr) = roll.sin_cos();
+        // Rz(yaw) * Ry(pitch) * Rx(roll)
+        let rotation = [
+            [cy * cp, cy * sp * sr - sy * cr, cy * sp * cr + sy * sr],
+            [sy * cp, sy * sp * sr + cy * cr, sy * sp * cr - cy * sr],
+            [-sp, cp * sr, cp * cr],
+        ];
+
+        let channel_count = ambisonic_channel_count(self.order);
+        self.matrix = [[0.; MAX_AMBISONIC_CHANNELS]; MAX_AMBISONIC_CHANNELS];
+        let mut rotated = [0.; MAX_AMBISONIC_CHANNELS];
+
+        for (d, weights) in self.directions.iter().zip(self.weights.iter()) {
+            let d = rotation.map(|row| row[0] * d[0] + row[1] * d[1] + row[2] * d[2]);
+            spherical_harmonics(self.order, d, &mut rotated);
+
+            for (row, &y) in self.matrix.iter_mut().zip(&rotated[..channel_count]) {
+                row.iter_mut()
+                    .zip(weights.iter())
+                    .for_each(|(m, w)| *m += y * w);
+            }
+        }
+    }
+}
+
+impl AudioProcessor for AmbisonicRotatorRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        _scope: &RenderScope,
+    ) -> bool {
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        let angles = [
+            params.get(&self.yaw)[0],
+            params.get(&self.pitch)[0],
+            params.get(&self.roll)[0],
+        ];
+        self.prev_matrix = self.matrix;
+        if angles != self.angles {
+            self.angles = angles;
+            self.update_matrix();
+        }
+
+        if input.is_silent() {
+            output.make_silent();
+            return false;
+        }
+
+        let channel_count = ambisonic_channel_count(self.order);
+        output.set_number_of_channels(channel_count);
+        let interpolate = self.prev_matrix != self.matrix;
+
+        for (k, channel) in output.channels_mut().iter_mut().enumerate() {
+            let (row, prev_row) = (&self.matrix[k], &self.prev_matrix[k]);
+
+            channel.iter_mut().enumerate().for_each(|(i, o)| {
+                // interpolate the rotation to avoid zipper noise on movements
+                let t = (i + 1) as f32 / RENDER_QUANTUM_SIZE as f32;
+                *o = input
+                    .channels()
+                    .iter()
+                    .enumerate()
+                    .map(|(j, c)| {
+                        let gain = if interpolate {
+                            (row[j] - prev_row[j]).mul_add(t, prev_row[j])
+                        } else {
+                            row[j]
+                        };
+                        gain * c[i]
+                    })
+                    .sum();
+            });
+        }
+
+        false
+    }
+}
+
+/// Options for constructing an [`AmbisonicBinauralDecoderNode`]
+#[derive(Clone, Debug)]
+pub struct AmbisonicBinauralDecoderOptions {
+    pub order: usize,
+}
+
+impl Default for AmbisonicBinauralDecoderOptions {
+    fn default() -> Self {
+        Self { order: 1 }
+    }
+}
+
+/// Spectra of the filters mapping each ambisonic channel to each ear, split in partitions of
+/// one render quantum
+struct BinauralFilters {
+    num_partitions: usize,
+    /// indexed by channel, ear, partition
+    spectra: Vec<Complex<f32>>,
+}
+
+/// Number of complex bins of the spectrum of one partition
+const NUM_BINS: usize = RENDER_QUANTUM_SIZE + 1;
+
+/// Binaural filters by order and sample rate
+type BinauralFiltersCache = HashMap<(usize, u32), Arc<BinauralFilters>>;
+
+/// Compute the binaural decoding filters for the given order and sample rate
+///
+/// The ambisonic signal is decoded to virtual speakers located at the HRIR measurement points,
+/// whose HRIRs are summed into one filter per ambisonic channel and ear. The result is cached
+/// for each order and sample rate, as loading the HRIR (and resampling) is expensive.
+fn binaural_filters(order: usize, sample_rate: u32) -> Arc<BinauralFilters> {
+    static INSTANCE: OnceLock<Mutex<BinauralFiltersCache>> = OnceLock::new();
+    let cache = INSTANCE.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut guard = cache.lock().unwrap();
+    let filters = guard.entry((order, sample_rate)).or_insert_with(|| {
+        let hrir_sphere = super::default_hrir_sphere(sample_rate);
+        let points = hrir_sphere.points();
+
+        // the sphere uses x right, y up and z back
+        let channel_count = ambisonic_channel_count(order);
+        let harmonics: Vec<Vec<f32>> = points
+            .iter()
+            .map(|p| {
+                let mut y = vec![0.; channel_count];
+                spherical_harmonics(order, [-p.pos.z, -p.pos.x, p.pos.y], &mut y);
+                y
+            })
+            .collect();
+        // the measurement points do not cover the lowest elevations, regularize the decoder
+        let weights = pseudo_inverse(&harmonics, 0.01 * points.len() as f64);
+
+        let length = points[0].left_hrir().len();
+        let num_partitions = (length + RENDER_QUANTUM_SIZE - 1) / RENDER_QUANTUM_SIZE;
+        let mut fft = Fft::new(2 * RENDER_QUANTUM_SIZE);
+        let mut spectra = Vec::with_capacity(channel_count * 2 * num_partitions * NUM_BINS);
+        let mut filter = vec![0.; num_partitions * RENDER_QUANTUM_SIZE];
+
+        for k in 0..channel_count {
+            for ear in 0..2 {
+                filter.fill(0.);
+                points.iter().zip(weights.iter()).for_each(|(p, w)| {
+                    let hrir = if ear == 0 {
+                        p.left_hrir()
+                    } else {
+                        p.right_hrir()
+                    };
+                    filter
+                        .iter_mut()
+                        .zip(hrir)
+                        .for_each(|(f, h)| *f += w[k] * h);
+                });
+
+                for partition in filter.chunks(RENDER_QUANTUM_SIZE) {
+                    fft.real()[..RENDER_QUANTUM_SIZE].copy_from_slice(partition);
+                    fft.real()[RENDER_QUANTUM_SIZE..].fill(0.);
+                    spectra.extend_from_slice(fft.process());
+                }
+            }
+        }
+
+        Arc::new(BinauralFilters {
+            num_partitions,
+            spectra,
+        })
+    });
+
+    Arc::clone(filters)
+}
+
+/// `AmbisonicBinauralDecoderNode` renders an ambisonic sound field to headphones
+///
+/// The input is expected to have `(order + 1)^2` channels, in ACN order with SN3D
+/// normalization. The output is stereo.
+pub struct AmbisonicBinauralDecoderNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    order: usize,
+}
+
+impl AudioNode for AmbisonicBinauralDecoderNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn set_channel_count(&self, _v: usize) {
+        panic!("InvalidStateError: Cannot edit channel count of AmbisonicBinauralDecoderNode")
+    }
+
+    fn set_channel_count_mode(&self, _v: ChannelCountMode) {
+        panic!("InvalidStateError: Cannot edit channel count mode of AmbisonicBinauralDecoderNode")
+    }
+
+    fn set_channel_interpretation(&self, _v: ChannelInterpretation) {
+        panic!(
+            "InvalidStateError: Cannot edit channel interpretation of AmbisonicBinauralDecoderNode"
+        )
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl AmbisonicBinauralDecoderNode {
+    /// Create a new `AmbisonicBinauralDecoderNode`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the order is outside the [1, 3] range, when the HRTF panner quota of the
+    /// context is exceeded, or without the `embedded-hrir` feature
+    pub fn new<C: BaseAudioContext>(context: &C, options: AmbisonicBinauralDecoderOptions) -> Self {
+        let AmbisonicBinauralDecoderOptions { order } = options;
+        assert_valid_order(order);
+
+        context
+            .base()
+            .enforce_quota(|memory| memory.check_hrtf(None));
+        let filters = binaural_filters(order, context.sample_rate() as u32);
+        let bytes = filters.spectra.len() * std::mem::size_of::<Complex<f32>>();
+        let allocation = Allocation::shared(&filters, bytes);
+
+        let node = context.register(move |registration| {
+            let fdl_len = filters.num_partitions * NUM_BINS;
+            let renderer = AmbisonicBinauralDecoderRenderer {
+                order,
+                fft: Fft::new(2 * RENDER_QUANTUM_SIZE),
+                fdl: [
+                    vec![Complex::default(); fdl_len],
+                    vec![Complex::default(); fdl_len],
+                ],
+                out: [
+                    vec![0.; 2 * RENDER_QUANTUM_SIZE],
+                    vec![0.; 2 * RENDER_QUANTUM_SIZE],
+                ],
+                filters,
+                tail_count: 0,
+            };
+
+            let node = Self {
+                registration,
+                channel_config: ChannelConfigOptions {
+                    count: ambisonic_channel_count(order),
+                    count_mode: ChannelCountMode::Explicit,
+                    interpretation: ChannelInterpretation::Discrete,
+                }
+                .into(),
+                order,
+            };
+
+            (node, Box::new(renderer))
+        });
+
+        node.registration
+            .set_memory(MemoryKind::Hrir, vec![allocation]);
+
+        node
+    }
+
+    /// Ambisonic order of the decoded signal
+    pub fn order(&self) -> usize {
+        self.order
+    }
+}
+
+struct AmbisonicBinauralDecoderRenderer {
+    order: usize,
+    filters: Arc<BinauralFilters>,
+    fft: Fft,
+    /// frequency-domain delay line, per ear
+    fdl: [Vec<Complex<f32>>; 2],
+    /// overlap-add output buffer, per ear
+    out: [Vec<f32>; 2],
+    /// number of render quanta until the end of the tail
+    tail_count: usize,
+}
+
+impl AudioProcessor for AmbisonicBinauralDecoderRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &RenderScope,
+    ) -> bool {
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        if input.is_silent() {
+            if self.tail_count == 0 {
+                output.make_silent();
+                return false;
+            }
+            self.tail_count -= 1;
+        } else {
+            let num_partitions = self.filters.num_partitions;
+            self.tail_count = num_partitions;
+
+            let channel_count = ambisonic_channel_count(self.order);
+            for (k, channel) in input.channels().iter().take(channel_count).enumerate() {
+                self.fft.real()[..RENDER_QUANTUM_SIZE].copy_from_slice(channel);
+                self.fft.real()[RENDER_QUANTUM_SIZE..].fill(0.);
+                let spectrum = self.fft.process();
+
+                for (ear, fdl) in self.fdl.iter_mut().enumerate() {
+                    let offset = (k * 2 + ear) * num_partitions * NUM_BINS;
+                    let filter = &self.filters.spectra[offset..offset + num_partitions * NUM_BINS];
+                    fdl.chunks_mut(NUM_BINS)
+                        .zip(filter.chunks(NUM_BINS))
+                        .for_each(|(f, h)| {
+                            f.iter_mut()
+                                .zip(h)
+                                .zip(spectrum)
+                                .for_each(|((f, h), s)| *f += h * s)
+                        });
+                }
+            }
+        }
+
+        output.set_number_of_channels(2);
+        for ((fdl, out), channel) in self
+            .fdl
+            .iter_mut()
+            .zip(self.out.iter_mut())
+            .zip(output.channels_mut().iter_mut())
+        {
+            self.fft.complex().copy_from_slice(&fdl[..NUM_BINS]);
+            let inverse = self.fft.inverse();
+            out.iter_mut().zip(inverse).for_each(|(o, i)| {
+                *o += i / (2 * RENDER_QUANTUM_SIZE) as f32;
+            });
+            channel.copy_from_slice(&out[..RENDER_QUANTUM_SIZE]);
+
+            // advance the delay line and the output buffer by one render quantum
+            fdl.copy_within(NUM_BINS.., 0);
+            let len = fdl.len();
+            fdl[len - NUM_BINS..].fill(Complex::default());
+            out.copy_within(RENDER_QUANTUM_SIZE.., 0);
+            out[RENDER_QUANTUM_SIZE..].fill(0.);
+        }
+
+        true
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        log::warn!("AmbisonicBinauralDecoderRenderer: Dropping incoming message {msg:?}");
+    }
+}
+
+/// Options for constructing an [`AmbisonicSpeakerDecoderNode`]
+#[derive(Clone, Debug)]
+pub struct AmbisonicSpeakerDecoderOptions {
+    pub order: usize,
+    /// azimuth and elevation in degrees of each speaker, in the order of the output channels
+    pub speakers: Vec<[f32; 2]>,
+}
+
+impl Default for AmbisonicSpeakerDecoderOptions {
+    /// First order, to the quad layout (front left, front right, back left, back right)
+    fn default() -> Self {
+        Self {
+            order: 1,
+            speakers: vec![[45., 0.], [-45., 0.], [135., 0.], [-135., 0.]],
+        }
+    }
+}
+
+/// `AmbisonicSpeakerDecoderNode` renders an ambisonic sound field to a speaker layout
+///
+/// The input is expected to have `(order + 1)^2` channels, in ACN order with SN3D
+/// normalization. The output has one channel per speaker. The decoder matches the sound field
+/// reproduced by the speakers to the input, regularized for the layouts that do not cover the
+/// whole sphere, e.g. horizontal layouts. Regular layouts with at least `(order + 1)^2` speakers
+/// give the best results.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{AmbisonicEncoderNode, AmbisonicEncoderOptions};
+/// use web_audio_api::node::{AmbisonicSpeakerDecoderNode, AmbisonicSpeakerDecoderOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let options = AmbisonicEncoderOptions { azimuth: 90., ..Default::default() };
+/// let encoder = AmbisonicEncoderNode::new(&context, options);
+/// // 5.0 layout, in the channel order of 5.1 without the LFE channel
+/// let options = AmbisonicSpeakerDecoderOptions {
+///     order: 1,
+///     speakers: vec![[30., 0.], [-30., 0.], [0., 0.], [110., 0.], [-110., 0.]],
+/// };
+/// let decoder = AmbisonicSpeakerDecoderNode::new(&context, options);
+/// encoder.connect(&decoder);
+/// decoder.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&encoder);
+/// osc.start();
+/// ```
+pub struct AmbisonicSpeakerDecoderNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    order: usize,
+    speakers: Vec<[f32; 2]>,
+}
+
+impl AudioNode for AmbisonicSpeakerDecoderNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn set_channel_count(&self, _v: usize) {
+        panic!("InvalidStateError: Cannot edit channel count of AmbisonicSpeakerDecoderNode")
+    }
+
+    fn set_channel_count_mode(&self, _v: ChannelCountMode) {
+        panic!("InvalidStateError: Cannot edit channel count mode of AmbisonicSpeakerDecoderNode")
+    }
+
+    fn set_channel_interpretation(&self, _v: ChannelInterpretation) {
+        panic!(
+            "InvalidStateError: Cannot edit channel interpretation of AmbisonicSpeakerDecoderNode"
+        )
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl AmbisonicSpeakerDecoderNode {
+    /// Create a new `AmbisonicSpeakerDecoderNode`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the order is outside the [1, 3] range, if there are no speakers or more than
+    /// [`MAX_CHANNELS`](crate::MAX_CHANNELS), or if a direction is not finite
+    pub fn new<C: BaseAudioContext>(context: &C, options: AmbisonicSpeakerDecoderOptions) -> Self {
+        let AmbisonicSpeakerDecoderOptions { order, speakers } = options;
+        assert_valid_order(order);
+        assert!(
+            !speakers.is_empty() && speakers.len() <= crate::MAX_CHANNELS,
+            "NotSupportedError - number of speakers {:?} is outside range [1, {:?}]",
+            speakers.len(),
+            crate::MAX_CHANNELS
+        );
+        assert!(
+            speakers.iter().flatten().all(|angle| angle.is_finite()),
+            "RangeError - speaker directions must be finite, received {:?}",
+            speakers
+        );
+
+        let channel_count = ambisonic_channel_count(order);
+        let harmonics: Vec<Vec<f32>> = speakers
+            .iter()
+            .map(|&[azimuth, elevation]| {
+                let mut y = vec![0.; channel_count];
+                spherical_harmonics(order, direction(azimuth, elevation), &mut y);
+                y
+            })
+            .collect();
+        // the layout may not cover the whole sphere, regularize the decoder
+        let weights = pseudo_inverse(&harmonics, 0.01 * speakers.len() as f64);
+        let matrix = weights
+            .iter()
+            .map(|w| {
+                let mut row = [0.; MAX_AMBISONIC_CHANNELS];
+                row[..channel_count].copy_from_slice(w);
+                row
+            })
+            .collect();
+
+        context.register(move |registration| {
+            let renderer = AmbisonicSpeakerDecoderRenderer { order, matrix };
+
+            let node = Self {
+                registration,
+                channel_config: ChannelConfigOptions {
+                    count: channel_count,
+                    count_mode: ChannelCountMode::Explicit,
+                    interpretation: ChannelInterpretation::Discrete,
+                }
+                .into(),
+                order,
+                speakers,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// Ambisonic order of the decoded signal
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Azimuth and elevation in degrees of each speaker, in the order of the output channels
+    pub fn speakers(&self) -> &[[f32; 2]] {
+        &self.speakers
+    }
+}
+
+struct AmbisonicSpeakerDecoderRenderer {
+    order: usize,
+    /// gains of each ambisonic channel, per speaker
+    matrix: Vec<[f32; MAX_AMBISONIC_CHANNELS]>,
+}
+
+impl AudioProcessor for AmbisonicSpeakerDecoderRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &RenderScope,
+    ) -> bool {
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        if input.is_silent() {
+            output.make_silent();
+            return false;
+        }
+
+        let channel_count = ambisonic_channel_count(self.order);
+        let channels = input.channels();
+        output.set_number_of_channels(self.matrix.len());
+        output
+            .channels_mut()
+            .iter_mut()
+            .zip(self.matrix.iter())
+            .for_each(|(speaker, row)| {
+                speaker.iter_mut().enumerate().for_each(|(i, o)| {
+                    *o = channels
+                        .iter()
+                        .take(channel_count)
+                        .zip(row)
+                        .map(|(c, gain)| gain * c[i])
+                        .sum();
+                });
+            });
+
+        false
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        log::warn!("AmbisonicSpeakerDecoderRenderer: Dropping incoming message {msg:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    #[test]
+    fn test_spherical_harmonics() {
+        // front
+        let mut y = [0.; MAX_AMBISONIC_CHANNELS];
+        spherical_harmonics(3, direction(0., 0.), &mut y);
+        let s = 3_f32.sqrt();
+        let expected = [
+            1.,
+            0.,
+            0.,
+            1.,
+            0.,
+            0.,
+            -0.5,
+            0.,
+            s / 2.,
+            0.,
+            0.,
+            0.,
+            0.,
+            -(3_f32 / 8.).sqrt(),
+            0.,
+            (5_f32 / 8.).sqrt(),
+        ];
+        assert_float_eq!(y, expected, abs_all <= 1e-6);
+
+        // SN3D: the harmonics of order l have a squared mean of 1 / (2l + 1) on the sphere
+        let directions = sampling_directions(4096);
+        let mut mean = [0.; MAX_AMBISONIC_CHANNELS];
+        for d in directions.iter() {
+            spherical_harmonics(3, *d, &mut y);
+            mean.iter_mut()
+                .zip(y)
+                .for_each(|(m, y)| *m += y * y / 4096.);
+        }
+        for l in 0..=3 {
+            for m in mean[l * l..(l + 1) * (l + 1)].iter() {
+                assert_float_eq!(*m, 1. / (2 * l + 1) as f32, abs <= 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_order() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44100.);
+        let options = AmbisonicEncoderOptions {
+            order: 4,
+            ..Default::default()
+        };
+        let _ = AmbisonicEncoderNode::new(&context, options);
+    }
+
+    #[test]
+    fn test_encoder() {
+        let context = OfflineAudioContext::new(4, RENDER_QUANTUM_SIZE, 44100.);
+        let options = AmbisonicEncoderOptions {
+            order: 1,
+            azimuth: 90.,
+            elevation: 0.,
+        };
+        let encoder = AmbisonicEncoderNode::new(&context, options);
+        encoder.connect(&context.destination());
+        assert_eq!(encoder.order(), 1);
+
+        let mut src = context.create_constant_source();
+        src.connect(&encoder);
+        src.start();
+
+        let output = context.start_rendering_sync();
+        // a source on the left is only present in W and Y
+        let expected = [1., 1., 0., 0.];
+        for (k, value) in expected.iter().enumerate() {
+            assert_float_eq!(
+                output.get_channel_data(k),
+                &[*value; RENDER_QUANTUM_SIZE][..],
+                abs_all <= 1e-6
+            );
+        }
+    }
+
+    #[test]
+    fn test_rotator() {
+        let context = OfflineAudioContext::new(16, RENDER_QUANTUM_SIZE, 44100.);
+        let options = AmbisonicEncoderOptions {
+            order: 3,
+            azimuth: 30.,
+            elevation: 20.,
+        };
+        let encoder = AmbisonicEncoderNode::new(&context, options);
+        let options = AmbisonicRotatorOptions {
+            order: 3,
+            yaw: 60.,
+            ..Default::default()
+        };
+        let rotator = AmbisonicRotatorNode::new(&context, options);
+        encoder.connect(&rotator);
+        let dest = context.destination();
+        dest.set_channel_interpretation(ChannelInterpretation::Discrete);
+        rotator.connect(&dest);
+
+        let mut src = context.create_constant_source();
+        src.connect(&encoder);
+        src.start();
+
+        let output = context.start_rendering_sync();
+        // rotated to the left
+        let mut expected = [0.; MAX_AMBISONIC_CHANNELS];
+        spherical_harmonics(3, direction(90., 20.), &mut expected);
+        for (k, value) in expected.iter().enumerate() {
+            assert_float_eq!(
+                output.get_channel_data(k),
+                &[*value; RENDER_QUANTUM_SIZE][..],
+                abs_all <= 1e-4
+            );
+        }
+    }
+
+    #[test]
+    fn test_rotator_pitch() {
+        let context = OfflineAudioContext::new(16, RENDER_QUANTUM_SIZE, 44100.);
+        let encoder = AmbisonicEncoderNode::new(
+            &context,
+            AmbisonicEncoderOptions {
+                order: 3,
+                ..Default::default()
+            },
+        );
+        let options = AmbisonicRotatorOptions {
+            order: 3,
+            pitch: 90.,
+            ..Default::default()
+        };
+        let rotator = AmbisonicRotatorNode::new(&context, options);
+        assert_eq!(rotator.order(), 3);
+        encoder.connect(&rotator);
+        let dest = context.destination();
+        dest.set_channel_interpretation(ChannelInterpretation::Discrete);
+        rotator.connect(&dest);
+
+        let mut src = context.create_constant_source();
+        src.connect(&encoder);
+        src.start();
+
+        let output = context.start_rendering_sync();
+        // pitch around the left axis moves the front down
+        let mut expected = [0.; MAX_AMBISONIC_CHANNELS];
+        spherical_harmonics(3, direction(0., -90.), &mut expected);
+        for (k, value) in expected.iter().enumerate() {
+            assert_float_eq!(
+                output.get_channel_data(k),
+                &[*value; RENDER_QUANTUM_SIZE][..],
+                abs_all <= 1e-4
+            );
+        }
+    }
+
+    #[test]
+    fn test_binaural_decoder() {
+        let sample_rate = 44100.;
+
+        // energy of the left and right ear for a source at the given azimuth
+        let render = |azimuth: f32| {
+            let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE * 8, sample_rate);
+            let options = AmbisonicEncoderOptions {
+                order: 3,
+                azimuth,
+                elevation: 0.,
+            };
+            let encoder = AmbisonicEncoderNode::new(&context, options);
+            let decoder = AmbisonicBinauralDecoderNode::new(
+                &context,
+                AmbisonicBinauralDecoderOptions { order: 3 },
+            );
+            encoder.connect(&decoder);
+            decoder.connect(&context.destination());
+
+            let mut impulse = context.create_buffer(1, 1, sample_rate);
+            impulse.copy_to_channel(&[1.], 0);
+            let mut src = context.create_buffer_source();
+            src.set_buffer(impulse);
+            src.connect(&encoder);
+            src.start();
+
+            let output = context.start_rendering_sync();
+            let energy = |c: &[f32]| c.iter().map(|v| v * v).sum::<f32>();
+            (
+                energy(output.get_channel_data(0)),
+                energy(output.get_channel_data(1)),
+            )
+        };
+
+        let (left, right) = render(90.);
+        assert!(left > 2. * right);
+        assert!(right > 0.);
+
+        let (left, right) = render(-90.);
+        assert!(right > 2. * left);
+        assert!(left > 0.);
+    }
+
+    #[test]
+    fn test_speaker_decoder() {
+        // level of each speaker of the quad layout for a source at the given azimuth
+        let render = |azimuth: f32| {
+            let context = OfflineAudioContext::new(4, RENDER_QUANTUM_SIZE, 44100.);
+            let options = AmbisonicEncoderOptions {
+                order: 1,
+                azimuth,
+                elevation: 0.,
+            };
+            let encoder = AmbisonicEncoderNode::new(&context, options);
+            let decoder = AmbisonicSpeakerDecoderNode::new(
+                &context,
+                AmbisonicSpeakerDecoderOptions::default(),
+            );
+            assert_eq!(decoder.order(), 1);
+            assert_eq!(decoder.speakers().len(), 4);
+            encoder.connect(&decoder);
+            let dest = context.destination();
+            dest.set_channel_interpretation(ChannelInterpretation::Discrete);
+            decoder.connect(&dest);
+
+            let mut src = context.create_constant_source();
+            src.connect(&encoder);
+            src.start();
+
+            let output = context.start_rendering_sync();
+            (0..4)
+                .map(|k| output.get_channel_data(k)[0])
+                .collect::<Vec<_>>()
+        };
+
+        // a source at a speaker position is loudest in this speaker
+        for (speaker, azimuth) in [45., -45., 135., -135.].into_iter().enumerate() {
+            let levels = render(azimuth);
+            (0..4)
+                .filter(|&k| k != speaker)
+                .for_each(|k| assert!(levels[speaker] > levels[k], "{levels:?}"));
+        }
+
+        // a source in front is shared by the front speakers
+        let levels = render(0.);
+        assert_float_eq!(levels[0], levels[1], abs <= 1e-6);
+        assert!(levels[0] > levels[2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_speaker_decoder_without_speakers() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44100.);
+        let options = AmbisonicSpeakerDecoderOptions {
+            order: 1,
+            speakers: vec![],
+        };
+        let _ = AmbisonicSpeakerDecoderNode::new(&context, options);
+    }
+}
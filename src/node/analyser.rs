@@ -3,6 +3,7 @@ use crate::analysis::{
     DEFAULT_SMOOTHING_TIME_CONSTANT,
 };
 use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::gain_to_db;
 use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
 
 use super::{AudioNode, ChannelConfig, ChannelConfigOptions, ChannelInterpretation};
@@ -234,6 +235,38 @@ impl AnalyserNode {
         self.analyser.get_byte_time_domain_data(buffer);
     }
 
+    /// Peak level in dBFS of the current time domain data, i.e. of the last `fft_size` frames
+    ///
+    /// Returns `f32::NEG_INFINITY` for silence.
+    ///
+    /// # Panics
+    ///
+    /// This method may panic if the lock to the inner analyser is poisoned
+    pub fn get_peak_dbfs(&mut self) -> f32 {
+        let data = self.time_domain_data();
+        gain_to_db(data.iter().fold(0., |peak, v| v.abs().max(peak)))
+    }
+
+    /// Root mean square level in dBFS of the current time domain data, i.e. of the last
+    /// `fft_size` frames
+    ///
+    /// A full scale sine wave reads -3 dBFS. Returns `f32::NEG_INFINITY` for silence.
+    ///
+    /// # Panics
+    ///
+    /// This method may panic if the lock to the inner analyser is poisoned
+    pub fn get_rms_dbfs(&mut self) -> f32 {
+        let data = self.time_domain_data();
+        let mean_square = data.iter().map(|v| v * v).sum::<f32>() / data.len() as f32;
+        gain_to_db(mean_square.sqrt())
+    }
+
+    fn time_domain_data(&mut self) -> Vec<f32> {
+        let mut data = vec![0.; self.fft_size()];
+        self.get_float_time_domain_data(&mut data);
+        data
+    }
+
     /// Copy the current frequency data into the provided buffer
     ///
     /// # Panics
@@ -287,3 +320,36 @@ impl AudioProcessor for AnalyserRenderer {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+    use crate::context::OfflineAudioContext;
+    use crate::node::{AudioNode, AudioScheduledSourceNode};
+
+    #[test]
+    fn test_meter_dbfs() {
+        let context = OfflineAudioContext::new(1, 4096, 48000.);
+        let mut analyser = context.create_analyser();
+        analyser.connect(&context.destination());
+
+        assert_eq!(analyser.get_peak_dbfs(), f32::NEG_INFINITY);
+        assert_eq!(analyser.get_rms_dbfs(), f32::NEG_INFINITY);
+
+        // 375 Hz, i.e. an integer number of periods over the fft size
+        let mut osc = context.create_oscillator();
+        osc.frequency().set_value(375.);
+        let gain = context.create_gain();
+        gain.set_gain_db(-6.);
+        osc.connect(&gain);
+        gain.connect(&analyser);
+        osc.start();
+
+        let _ = context.start_rendering_sync();
+
+        assert_float_eq!(analyser.get_peak_dbfs(), -6., abs <= 0.01);
+        assert_float_eq!(analyser.get_rms_dbfs(), -6. - 3.0103, abs <= 0.01);
+    }
+}
@@ -0,0 +1,578 @@
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use arrayvec::ArrayVec;
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+use realfft::num_complex::Complex;
+use realfft::RealToComplex;
+
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::dsp::{plan_fft_forward, Window};
+use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
+
+use super::{AudioNode, ChannelConfig, ChannelConfigOptions};
+
+/// Maximum number of frequency bands of an [`AnalysisTapNode`]
+pub const MAX_TAP_BANDS: usize = 32;
+
+/// Duration of the average of the spectral flux the onset threshold is relative to, in seconds
+const THRESHOLD_AVERAGE: f64 = 0.5;
+
+/// Spectral flux (per frequency bin) below which no onset is detected, so silence and noise
+/// floors are ignored
+const MIN_ONSET_FLUX: f32 = 1e-4;
+
+/// Equivalent noise bandwidth of the Hann window, in frequency bins
+const HANN_BANDWIDTH: f32 = 1.5;
+
+/// Analysis results published by an [`AnalysisTapNode`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnalysisFrame {
+    /// End of the analysed interval, in terms of the associated AudioContext's currentTime
+    pub time: f64,
+    /// Highest absolute sample value of all channels over the interval, in dBFS
+    pub peak: f32,
+    /// RMS level of the interval, averaged over the channels, in dBFS
+    pub rms: f32,
+    /// Level of each frequency band over the last FFT frame, in dBFS, from low to high
+    ///
+    /// A full scale sine in a band reads 0 dB, a band without any FFT bin reads minus infinity.
+    pub bands: ArrayVec<f32, MAX_TAP_BANDS>,
+    /// Whether an onset was detected since the previous frame
+    pub onset: bool,
+    /// Spectral flux of the frame, i.e. the increase of the log magnitude spectrum per
+    /// frequency bin
+    pub onset_strength: f32,
+}
+
+/// Receiving end of the analysis frames of an [`AnalysisTapNode`]
+///
+/// The tap can be sent to, and polled from any thread. Clones share the same queue: each frame
+/// is received once, by one of them.
+#[derive(Clone, Debug)]
+pub struct AnalysisTap {
+    receiver: Receiver<AnalysisFrame>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl AnalysisTap {
+    /// Oldest frame of the queue, if any
+    pub fn try_recv(&self) -> Option<AnalysisFrame> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Oldest frame of the queue, waiting at most `timeout` for one to be published
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<AnalysisFrame> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+
+    /// Newest frame of the queue, if any, discarding the older ones
+    pub fn latest(&self) -> Option<AnalysisFrame> {
+        self.receiver.try_iter().last()
+    }
+
+    /// Number of frames waiting in the queue
+    pub fn len(&self) -> usize {
+        self.receiver.len()
+    }
+
+    /// Whether no frame is waiting in the queue
+    pub fn is_empty(&self) -> bool {
+        self.receiver.is_empty()
+    }
+
+    /// Number of frames which were not published because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Options for constructing an [`AnalysisTapNode`]
+#[derive(Clone, Debug)]
+pub struct AnalysisTapOptions {
+    /// number of frames published per second
+    pub report_rate: f64,
+    /// size of the FFT frames the bands and the onsets are computed from, in sample-frames
+    pub fft_size: usize,
+    /// number of frequency bands, logarithmically spaced from `min_frequency` to Nyquist
+    pub bands: usize,
+    /// lower edge of the first frequency band, in Hertz
+    pub min_frequency: f32,
+    /// ratio of the spectral flux over its recent average above which an onset is detected
+    pub onset_threshold: f32,
+    /// number of frames the queue holds before new frames are dropped
+    pub capacity: usize,
+    pub channel_config: ChannelConfigOptions,
+}
+
+impl Default for AnalysisTapOptions {
+    fn default() -> Self {
+        Self {
+            report_rate: 60.,
+            fft_size: 2048,
+            bands: 8,
+            min_frequency: 40.,
+            onset_threshold: 1.5,
+            capacity: 64,
+            channel_config: ChannelConfigOptions::default(),
+        }
+    }
+}
+
+/// `AnalysisTapNode` publishes analysis results of its input at a fixed rate, for non-audio
+/// real-time systems such as lighting or LED controllers
+///
+/// Each [`AnalysisFrame`] holds the peak and RMS levels of the interval since the previous
+/// frame, the levels of frequency bands and the onset detection of the last FFT frame, and is
+/// timestamped in context time. The frames are published sample accurately from the render
+/// thread, without locking nor allocating, into a bounded queue read through an
+/// [`AnalysisTap`]. When the consumer lags behind, the new frames are dropped until the queue
+/// has room again, see [`AnalysisTap::dropped`].
+///
+/// The input is passed through unchanged, so the node can be inserted anywhere in the graph.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AnalysisTapNode, AnalysisTapOptions, AudioNode};
+/// use web_audio_api::node::AudioScheduledSourceNode;
+///
+/// let context = AudioContext::default();
+///
+/// let tap_node = AnalysisTapNode::new(&context, AnalysisTapOptions::default());
+/// tap_node.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&tap_node);
+/// osc.start();
+///
+/// let tap = tap_node.tap();
+/// std::thread::spawn(move || loop {
+///     if let Some(frame) = tap.recv_timeout(Duration::from_secs(1)) {
+///         // drive the lights
+///         println!("{:.3}s {:.1} dBFS {:?}", frame.time, frame.rms, frame.bands);
+///     }
+/// });
+/// ```
+pub struct AnalysisTapNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    report_rate: f64,
+    band_edges: ArrayVec<f32, { MAX_TAP_BANDS + 1 }>,
+    tap: AnalysisTap,
+}
+
+impl AudioNode for AnalysisTapNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl AnalysisTapNode {
+    /// Create a new `AnalysisTapNode`
+    ///
+    /// # Panics
+    ///
+    /// Panics if:
+    /// - the report rate is not strictly positive or exceeds the sample rate
+    /// - the fft size is not a power of two in the range [256, 32768]
+    /// - the number of bands is not in the range [1, 32]
+    /// - the min frequency is not in the range ]0, Nyquist[
+    /// - the onset threshold is not strictly positive and finite
+    /// - the capacity is zero
+    pub fn new<C: BaseAudioContext>(context: &C, options: AnalysisTapOptions) -> Self {
+        let AnalysisTapOptions {
+            report_rate,
+            fft_size,
+            bands,
+            min_frequency,
+            onset_threshold,
+            capacity,
+            channel_config,
+        } = options;
+
+        let sample_rate = context.sample_rate();
+        let nyquist = sample_rate / 2.;
+
+        assert!(
+            report_rate > 0. && report_rate <= f64::from(sample_rate),
+            "RangeError - report rate ({:?}) should be in the range ]0, {:?}]",
+            report_rate,
+            sample_rate
+        );
+        assert!(
+            fft_size.is_power_of_two() && (256..=32768).contains(&fft_size),
+            "IndexSizeError - Invalid fft size: {:?} is outside range [256, 32768] or not a power of two",
+            fft_size
+        );
+        assert!(
+            (1..=MAX_TAP_BANDS).contains(&bands),
+            "IndexSizeError - number of bands ({:?}) should be in the range [1, {:?}]",
+            bands,
+            MAX_TAP_BANDS
+        );
+        assert!(
+            min_frequency > 0. && min_frequency < nyquist,
+            "RangeError - min frequency ({:?}) should be in the range ]0, {:?}[",
+            min_frequency,
+            nyquist
+        );
+        assert!(
+            onset_threshold > 0. && onset_threshold.is_finite(),
+            "RangeError - onset threshold ({:?}) should be strictly positive and finite",
+            onset_threshold
+        );
+        assert!(capacity > 0, "RangeError - capacity should not be zero");
+
+        let ratio = nyquist / min_frequency;
+        let band_edges: ArrayVec<f32, { MAX_TAP_BANDS + 1 }> = (0..=bands)
+            .map(|b| min_frequency * ratio.powf(b as f32 / bands as f32))
+            .collect();
+
+        context.register(move |registration| {
+            let (sender, receiver) = crossbeam_channel::bounded(capacity);
+            let dropped = Arc::new(AtomicU64::new(0));
+
+            let bin_width = sample_rate / fft_size as f32;
+            let number_of_bins = fft_size / 2 + 1;
+            let band_bins = band_edges
+                .windows(2)
+                .map(|edges| {
+                    let start = (edges[0] / bin_width).ceil() as usize;
+                    let end = ((edges[1] / bin_width).ceil() as usize).min(number_of_bins);
+                    // the last band includes the Nyquist bin
+                    let end = if edges[1] >= nyquist {
+                        number_of_bins
+                    } else {
+                        end
+                    };
+                    start.min(end)..end
+                })
+                .collect();
+
+            let r2c = plan_fft_forward(fft_size);
+            let window = Window::Hann.values(fft_size);
+            let window_sum: f32 = window.iter().sum();
+
+            let frames_per_report = f64::from(sample_rate) / report_rate;
+
+            let render = AnalysisTapRenderer {
+                sender,
+                dropped: Arc::clone(&dropped),
+                ring_buffer: vec![0.; fft_size],
+                write_index: 0,
+                frames_per_report,
+                frames_to_report: frames_per_report,
+                peak: 0.,
+                sum_of_squares: 0.,
+                frames: 0,
+                input: r2c.make_input_vec(),
+                spectrum: r2c.make_output_vec(),
+                scratch: r2c.make_scratch_vec(),
+                r2c,
+                window,
+                scale: 2. / window_sum,
+                band_bins,
+                magnitudes: vec![0.; number_of_bins],
+                onset_threshold,
+                flux_average: 0.,
+                flux_smoothing: (-1. / (THRESHOLD_AVERAGE * report_rate)).exp() as f32,
+            };
+
+            let node = AnalysisTapNode {
+                registration,
+                channel_config: channel_config.into(),
+                report_rate,
+                band_edges,
+                tap: AnalysisTap { receiver, dropped },
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// Number of frames published per second
+    pub fn report_rate(&self) -> f64 {
+        self.report_rate
+    }
+
+    /// Edges of the frequency bands, in Hertz, from the lower edge of the first band to the
+    /// upper edge of the last band
+    pub fn band_edges(&self) -> &[f32] {
+        &self.band_edges
+    }
+
+    /// Receiving end of the published frames
+    pub fn tap(&self) -> AnalysisTap {
+        self.tap.clone()
+    }
+}
+
+struct AnalysisTapRenderer {
+    sender: Sender<AnalysisFrame>,
+    dropped: Arc<AtomicU64>,
+    /// mono down mix of the last `fft_size` sample-frames
+    ring_buffer: Vec<f32>,
+    write_index: usize,
+    frames_per_report: f64,
+    /// sample-frames until the next report, fractional to keep the rate exact
+    frames_to_report: f64,
+    /// levels of the current interval
+    peak: f32,
+    sum_of_squares: f64,
+    frames: usize,
+    r2c: Arc<dyn RealToComplex<f32>>,
+    window: Vec<f32>,
+    /// scale of the FFT magnitudes to the amplitude of a sine
+    scale: f32,
+    input: Vec<f32>,
+    spectrum: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    band_bins: Vec<Range<usize>>,
+    /// log magnitudes of the previous FFT frame
+    magnitudes: Vec<f32>,
+    onset_threshold: f32,
+    flux_average: f32,
+    flux_smoothing: f32,
+}
+
+impl AnalysisTapRenderer {
+    /// Analyse the last FFT frame and publish the levels of the current interval
+    fn report(&mut self, time: f64) {
+        // unroll the ring buffer, oldest sample first
+        let fft_size = self.ring_buffer.len();
+        self.input.iter_mut().enumerate().for_each(|(i, v)| {
+            let sample = self.ring_buffer[(self.write_index + i) % fft_size];
+            *v = sample * self.window[i];
+        });
+        self.r2c
+            .process_with_scratch(&mut self.input, &mut self.spectrum, &mut self.scratch)
+            .unwrap();
+
+        let scale = self.scale;
+        let bands = self
+            .band_bins
+            .iter()
+            .map(|bins| {
+                let power: f32 = self.spectrum[bins.clone()]
+                    .iter()
+                    .map(|c| (c.norm() * scale).powi(2))
+                    .sum();
+                10. * (power / HANN_BANDWIDTH).log10()
+            })
+            .collect();
+
+        // sum of the increases of the log magnitudes
+        let flux = self
+            .magnitudes
+            .iter_mut()
+            .zip(&self.spectrum)
+            .map(|(previous, c)| {
+                let magnitude = c.norm().ln_1p();
+                let increase = (magnitude - *previous).max(0.);
+                *previous = magnitude;
+                increase
+            })
+            .sum::<f32>()
+            / self.magnitudes.len() as f32;
+        let onset = flux >= MIN_ONSET_FLUX && flux > self.onset_threshold * self.flux_average;
+        self.flux_average =
+            self.flux_smoothing * self.flux_average + (1. - self.flux_smoothing) * flux;
+
+        let frames = self.frames.max(1) as f64;
+        let frame = AnalysisFrame {
+            time,
+            peak: 20. * self.peak.log10(),
+            rms: (10. * (self.sum_of_squares / frames).log10()) as f32,
+            bands,
+            onset,
+            onset_strength: flux,
+        };
+        self.peak = 0.;
+        self.sum_of_squares = 0.;
+        self.frames = 0;
+
+        if let Err(TrySendError::Full(_)) = self.sender.try_send(frame) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl AudioProcessor for AnalysisTapRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        scope: &RenderScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        // pass through input
+        *output = input.clone();
+
+        let sample_rate = f64::from(scope.sample_rate);
+        let fft_size = self.ring_buffer.len();
+        let channels = input.channels();
+        let scale = 1. / channels.len() as f32;
+
+        for i in 0..channels[0].len() {
+            let mut sum = 0.;
+            let mut square = 0.;
+            for channel in channels {
+                let sample = channel[i];
+                self.peak = self.peak.max(sample.abs());
+                sum += sample;
+                square += sample * sample;
+            }
+            self.sum_of_squares += f64::from(square * scale);
+            self.frames += 1;
+
+            self.ring_buffer[self.write_index] = sum * scale;
+            self.write_index = (self.write_index + 1) % fft_size;
+
+            self.frames_to_report -= 1.;
+            if self.frames_to_report <= 0. {
+                self.frames_to_report += self.frames_per_report;
+                self.report(scope.current_time + (i + 1) as f64 / sample_rate);
+            }
+        }
+
+        // no tail-time
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    const SAMPLE_RATE: f32 = 48_000.;
+
+    fn render(
+        options: AnalysisTapOptions,
+        frequency: f32,
+        amplitude: f32,
+        start: f64,
+    ) -> (AnalysisTapNode, Vec<f32>) {
+        let context = OfflineAudioContext::new(1, SAMPLE_RATE as usize, SAMPLE_RATE);
+        let tap_node = AnalysisTapNode::new(&context, options);
+        tap_node.connect(&context.destination());
+
+        let gain = context.create_gain();
+        gain.gain().set_value(amplitude);
+        gain.connect(&tap_node);
+
+        let mut osc = context.create_oscillator();
+        osc.frequency().set_value(frequency);
+        osc.connect(&gain);
+        osc.start_at(start);
+
+        let output = context.start_rendering_sync();
+        (tap_node, output.get_channel_data(0).to_vec())
+    }
+
+    #[test]
+    fn test_levels_and_bands() {
+        let options = AnalysisTapOptions {
+            report_rate: 100.,
+            ..AnalysisTapOptions::default()
+        };
+        let (tap_node, output) = render(options, 1500., 0.5, 0.);
+
+        // input is passed through
+        assert_float_eq!(
+            output.iter().fold(0., |a, &b| f32::max(a, b)),
+            0.5,
+            abs <= 1e-3
+        );
+
+        let band = tap_node
+            .band_edges()
+            .windows(2)
+            .position(|edges| (edges[0]..edges[1]).contains(&1500.))
+            .unwrap();
+
+        let tap = tap_node.tap();
+        assert_eq!(tap.len(), 64);
+        assert_eq!(tap.dropped(), 36);
+
+        let frames: Vec<_> = std::iter::from_fn(|| tap.try_recv()).collect();
+        assert_eq!(frames.len(), 64);
+        for (i, frame) in frames.iter().enumerate() {
+            assert_float_eq!(frame.time, (i + 1) as f64 / 100., abs <= 1e-9);
+        }
+
+        // skip the first frames, the FFT frame is not filled yet
+        let frame = &frames[10];
+        assert_float_eq!(frame.peak, -6.02, abs <= 0.05);
+        assert_float_eq!(frame.rms, -9.03, abs <= 0.05);
+        assert_eq!(frame.bands.len(), 8);
+        assert_float_eq!(frame.bands[band], -6.02, abs <= 0.1);
+        frame
+            .bands
+            .iter()
+            .enumerate()
+            .filter(|&(b, _)| b != band)
+            .for_each(|(_, &level)| assert!(level < -40.));
+        assert!(!frame.onset);
+    }
+
+    #[test]
+    fn test_onset() {
+        let options = AnalysisTapOptions {
+            report_rate: 50.,
+            fft_size: 512,
+            ..AnalysisTapOptions::default()
+        };
+        let (tap_node, _) = render(options, 440., 1., 0.5);
+
+        let tap = tap_node.tap();
+        let onsets: Vec<_> = std::iter::from_fn(|| tap.try_recv())
+            .filter(|frame| frame.onset)
+            .collect();
+        assert_eq!(onsets.len(), 1);
+        assert_float_eq!(onsets[0].time, 0.52, abs <= 1e-9);
+    }
+
+    #[test]
+    fn test_latest() {
+        let options = AnalysisTapOptions {
+            report_rate: 10.,
+            ..AnalysisTapOptions::default()
+        };
+        let (tap_node, _) = render(options, 440., 1., 0.);
+
+        let tap = tap_node.tap();
+        assert_eq!(tap.dropped(), 0);
+        let frame = tap.latest().unwrap();
+        assert_float_eq!(frame.time, 1., abs <= 1e-9);
+        assert!(tap.is_empty());
+        assert!(tap.try_recv().is_none());
+    }
+}
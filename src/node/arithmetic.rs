@@ -0,0 +1,523 @@
+//! Signal math utility nodes
+//!
+//! These nodes implement simple arithmetic on audio signals, which would otherwise require
+//! combining `GainNode`s and `ConstantSourceNode`s in non obvious ways (e.g. connecting a signal
+//! into the gain param of a `GainNode` set to zero to multiply two signals).
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioRenderQuantumChannel, RenderScope,
+};
+
+use super::{AudioNode, ChannelConfig, ChannelConfigOptions};
+
+/// Channel of `quantum` used to compute the output channel `index`, a mono quantum is applied to
+/// all output channels
+fn channel_of(quantum: &AudioRenderQuantum, index: usize) -> Option<&AudioRenderQuantumChannel> {
+    if quantum.number_of_channels() == 1 {
+        Some(quantum.channel_data(0))
+    } else {
+        quantum.channels().get(index)
+    }
+}
+
+fn unbounded_param<C: BaseAudioContext>(
+    context: &C,
+    registration: &AudioContextRegistration,
+    default_value: f32,
+) -> (AudioParam, AudioParamId) {
+    let param_opts = AudioParamDescriptor {
+        min_value: f32::MIN,
+        max_value: f32::MAX,
+        default_value,
+        automation_rate: AutomationRate::A,
+    };
+    context.create_audio_param(param_opts, registration)
+}
+
+/// Options for constructing a [`MultiplyNode`]
+#[derive(Clone, Debug, Default)]
+pub struct MultiplyOptions {
+    pub channel_config: ChannelConfigOptions,
+}
+
+/// `MultiplyNode` outputs the product of its two inputs, sample by sample
+///
+/// Typical uses are ring modulation (multiplying two audio signals) and voltage controlled
+/// amplifiers (multiplying a signal by an envelope). A mono input is applied to all channels of
+/// the other input, otherwise the channels missing in one of the inputs are silent.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{MultiplyNode, MultiplyOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let ring_mod = MultiplyNode::new(&context, MultiplyOptions::default());
+/// ring_mod.connect(&context.destination());
+///
+/// let mut carrier = context.create_oscillator();
+/// carrier.connect_at(&ring_mod, 0, 0);
+/// carrier.start();
+///
+/// let mut modulator = context.create_oscillator();
+/// modulator.frequency().set_value(30.);
+/// modulator.connect_at(&ring_mod, 0, 1);
+/// modulator.start();
+/// ```
+pub struct MultiplyNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+}
+
+impl AudioNode for MultiplyNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        2
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl MultiplyNode {
+    pub fn new<C: BaseAudioContext>(context: &C, options: MultiplyOptions) -> Self {
+        context.register(move |registration| {
+            let node = MultiplyNode {
+                registration,
+                channel_config: options.channel_config.into(),
+            };
+
+            (node, Box::new(MultiplyRenderer {}))
+        })
+    }
+}
+
+struct MultiplyRenderer {}
+
+impl AudioProcessor for MultiplyRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &RenderScope,
+    ) -> bool {
+        let [left, right] = inputs else {
+            unreachable!()
+        };
+        let output = &mut outputs[0];
+
+        if left.is_silent() || right.is_silent() {
+            output.make_silent();
+            return false;
+        }
+
+        let number_of_channels = left.number_of_channels().max(right.number_of_channels());
+        output.set_number_of_channels(number_of_channels);
+
+        output
+            .channels_mut()
+            .iter_mut()
+            .enumerate()
+            .for_each(
+                |(index, o)| match (channel_of(left, index), channel_of(right, index)) {
+                    (Some(l), Some(r)) => o
+                        .iter_mut()
+                        .zip(l.iter().zip(r.iter()))
+                        .for_each(|(o, (l, r))| *o = l * r),
+                    _ => o.fill(0.),
+                },
+            );
+
+        false
+    }
+}
+
+/// Options for constructing an [`AddOffsetNode`]
+#[derive(Clone, Debug, Default)]
+pub struct AddOffsetOptions {
+    pub offset: f32,
+    pub channel_config: ChannelConfigOptions,
+}
+
+/// `AddOffsetNode` adds the value of its `offset` param to its input
+///
+/// When no input is connected, the node outputs the offset as a mono signal.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{AddOffsetNode, AddOffsetOptions};
+///
+/// let context = AudioContext::default();
+///
+/// // unipolar LFO in the [0, 1] range
+/// let mut lfo = context.create_oscillator();
+/// lfo.frequency().set_value(2.);
+/// let half = context.create_gain();
+/// half.gain().set_value(0.5);
+/// let offset = AddOffsetNode::new(&context, AddOffsetOptions { offset: 0.5, ..Default::default() });
+/// lfo.connect(&half);
+/// half.connect(&offset);
+/// lfo.start();
+///
+/// let gain = context.create_gain();
+/// gain.gain().set_value(0.);
+/// offset.connect(gain.gain());
+/// ```
+pub struct AddOffsetNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    offset: AudioParam,
+}
+
+impl AudioNode for AddOffsetNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl AddOffsetNode {
+    pub fn new<C: BaseAudioContext>(context: &C, options: AddOffsetOptions) -> Self {
+        context.register(move |registration| {
+            let (param, proc) = unbounded_param(context, &registration, 0.);
+            param.set_value(options.offset);
+
+            let node = AddOffsetNode {
+                registration,
+                channel_config: options.channel_config.into(),
+                offset: param,
+            };
+
+            (node, Box::new(AddOffsetRenderer { offset: proc }))
+        })
+    }
+
+    /// The value added to the input
+    pub fn offset(&self) -> &AudioParam {
+        &self.offset
+    }
+}
+
+struct AddOffsetRenderer {
+    offset: AudioParamId,
+}
+
+impl AudioProcessor for AddOffsetRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        _scope: &RenderScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        let offset = params.get(&self.offset);
+
+        if input.is_silent() && offset.len() == 1 && offset[0] == 0. {
+            output.make_silent();
+            return false;
+        }
+
+        *output = input.clone();
+
+        output.channels_mut().iter_mut().for_each(|channel| {
+            channel
+                .iter_mut()
+                .zip(offset.iter().cycle())
+                .for_each(|(o, offset)| *o += offset);
+        });
+
+        false
+    }
+}
+
+/// Options for constructing a [`ClampNode`]
+#[derive(Clone, Debug)]
+pub struct ClampOptions {
+    pub min: f32,
+    pub max: f32,
+    pub channel_config: ChannelConfigOptions,
+}
+
+impl Default for ClampOptions {
+    fn default() -> Self {
+        Self {
+            min: -1.,
+            max: 1.,
+            channel_config: ChannelConfigOptions::default(),
+        }
+    }
+}
+
+/// `ClampNode` limits its input to the range defined by its `min` and `max` params
+///
+/// Set one of the bounds to `f32::MIN` or `f32::MAX` to only compute the maximum or minimum of
+/// the input and the other bound. When `min` is greater than `max`, the output is `max`.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{ClampNode, ClampOptions};
+///
+/// let context = AudioContext::default();
+///
+/// // half-wave rectifier
+/// let options = ClampOptions { min: 0., max: f32::MAX, ..Default::default() };
+/// let rectifier = ClampNode::new(&context, options);
+/// rectifier.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&rectifier);
+/// osc.start();
+/// ```
+pub struct ClampNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    min: AudioParam,
+    max: AudioParam,
+}
+
+impl AudioNode for ClampNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl ClampNode {
+    pub fn new<C: BaseAudioContext>(context: &C, options: ClampOptions) -> Self {
+        context.register(move |registration| {
+            let (min_param, min_proc) = unbounded_param(context, &registration, -1.);
+            min_param.set_value(options.min);
+            let (max_param, max_proc) = unbounded_param(context, &registration, 1.);
+            max_param.set_value(options.max);
+
+            let node = ClampNode {
+                registration,
+                channel_config: options.channel_config.into(),
+                min: min_param,
+                max: max_param,
+            };
+
+            let render = ClampRenderer {
+                min: min_proc,
+                max: max_proc,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// The lower bound of the output
+    pub fn min(&self) -> &AudioParam {
+        &self.min
+    }
+
+    /// The upper bound of the output
+    pub fn max(&self) -> &AudioParam {
+        &self.max
+    }
+}
+
+struct ClampRenderer {
+    min: AudioParamId,
+    max: AudioParamId,
+}
+
+impl AudioProcessor for ClampRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        _scope: &RenderScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        let min = params.get(&self.min);
+        let max = params.get(&self.max);
+
+        // fast track, silence is within the bounds
+        if input.is_silent() && min.len() == 1 && max.len() == 1 && min[0] <= 0. && max[0] >= 0. {
+            output.make_silent();
+            return false;
+        }
+
+        *output = input.clone();
+
+        output.channels_mut().iter_mut().for_each(|channel| {
+            channel
+                .iter_mut()
+                .zip(min.iter().cycle().zip(max.iter().cycle()))
+                .for_each(|(o, (min, max))| *o = o.max(*min).min(*max));
+        });
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+    use crate::AudioBuffer;
+
+    use float_eq::assert_float_eq;
+
+    fn ramp_source(context: &OfflineAudioContext) -> crate::node::AudioBufferSourceNode {
+        let data = (0..128).map(|i| i as f32 / 32. - 2.).collect();
+        let mut src = context.create_buffer_source();
+        src.set_buffer(AudioBuffer::from(vec![data], context.sample_rate()));
+        src.start();
+        src
+    }
+
+    #[test]
+    fn test_multiply() {
+        let context = OfflineAudioContext::new(2, 128, 48000.);
+        let node = MultiplyNode::new(&context, MultiplyOptions::default());
+        node.connect(&context.destination());
+
+        // stereo signal
+        let merger = context.create_channel_merger(2);
+        let mut left = context.create_constant_source();
+        left.offset().set_value(2.);
+        left.connect_at(&merger, 0, 0);
+        left.start();
+        let mut right = context.create_constant_source();
+        right.offset().set_value(3.);
+        right.connect_at(&merger, 0, 1);
+        right.start();
+        merger.connect_at(&node, 0, 0);
+
+        // mono control
+        let src = ramp_source(&context);
+        src.connect_at(&node, 0, 1);
+
+        let output = context.start_rendering_sync();
+        let ramp: Vec<f32> = (0..128).map(|i| i as f32 / 32. - 2.).collect();
+        let expected: Vec<f32> = ramp.iter().map(|v| v * 2.).collect();
+        assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 0.);
+        let expected: Vec<f32> = ramp.iter().map(|v| v * 3.).collect();
+        assert_float_eq!(output.get_channel_data(1), &expected[..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_multiply_single_input() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let node = MultiplyNode::new(&context, MultiplyOptions::default());
+        node.connect(&context.destination());
+
+        let src = ramp_source(&context);
+        src.connect_at(&node, 0, 0);
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(output.get_channel_data(0), &[0.; 128][..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_add_offset() {
+        let context = OfflineAudioContext::new(1, 256, 48000.);
+        let options = AddOffsetOptions {
+            offset: 0.5,
+            ..Default::default()
+        };
+        let node = AddOffsetNode::new(&context, options);
+        node.connect(&context.destination());
+        assert_float_eq!(node.offset().value(), 0.5, abs <= 0.);
+
+        let src = ramp_source(&context);
+        src.connect(&node);
+
+        let output = context.start_rendering_sync();
+        let expected: Vec<f32> = (0..128).map(|i| i as f32 / 32. - 1.5).collect();
+        let channel = output.get_channel_data(0);
+        assert_float_eq!(&channel[..128], &expected[..], abs_all <= 0.);
+        // the offset alone once the source has ended
+        assert_float_eq!(&channel[128..], &[0.5; 128][..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_clamp() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let node = ClampNode::new(&context, ClampOptions::default());
+        node.connect(&context.destination());
+        node.min().set_value(-0.5);
+
+        let src = ramp_source(&context);
+        src.connect(&node);
+
+        let output = context.start_rendering_sync();
+        let expected: Vec<f32> = (0..128)
+            .map(|i| (i as f32 / 32. - 2.).clamp(-0.5, 1.))
+            .collect();
+        assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_clamp_inverted_bounds() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let options = ClampOptions {
+            min: 1.,
+            max: 0.5,
+            ..Default::default()
+        };
+        let node = ClampNode::new(&context, options);
+        node.connect(&context.destination());
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(output.get_channel_data(0), &[0.5; 128][..], abs_all <= 0.);
+    }
+}
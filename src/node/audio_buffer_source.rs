@@ -1,9 +1,11 @@
 use std::any::Any;
+use std::f32::consts::PI;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use crate::buffer::AudioBuffer;
 use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::memory::MemoryKind;
 use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
 use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
 use crate::{AtomicF64, RENDER_QUANTUM_SIZE};
@@ -53,11 +55,28 @@ struct PlaybackInfo {
     k: f32,
 }
 
+/// Jump of the playhead, scheduled with [`AudioBufferSourceNode::seek`]
+#[derive(Debug, Clone, Copy)]
+struct Seek {
+    when: f64,
+    buffer_time: f64,
+    crossfade: f64,
+}
+
+/// Previous playhead, faded out after a seek
+#[derive(Debug, Clone, Copy)]
+struct Crossfade {
+    buffer_time: f64,
+    elapsed: f64,
+    duration: f64,
+}
+
 #[derive(Debug, Clone)]
 struct LoopState {
     pub is_looping: bool,
     pub start: f64,
     pub end: f64,
+    pub crossfade: f64,
 }
 
 /// Instructions to start or stop processing
@@ -68,12 +87,21 @@ enum ControlMessage {
     Loop(bool),
     LoopStart(f64),
     LoopEnd(f64),
+    LoopCrossfade(f64),
+    Seek(Seek),
+    Pause { when: f64, paused: bool },
 }
 
+/// Duration in seconds of the fade applied when pausing and resuming the playback
+const PAUSE_FADE_DURATION: f64 = 0.005;
+
 /// `AudioBufferSourceNode` represents an audio source that consists of an
 /// in-memory audio source (i.e. an audio file completely loaded in memory),
 /// stored in an [`AudioBuffer`].
 ///
+/// Pausing and resuming the playback, with [`pause`](Self::pause) and [`resume`](Self::resume)
+/// and their scheduled variants, is an extension to the spec.
+///
 /// - MDN documentation: <https://developer.mozilla.org/en-US/docs/Web/API/AudioBufferSourceNode>
 /// - specification: <https://webaudio.github.io/web-audio-api/#AudioBufferSourceNode>
 /// - see also: [`BaseAudioContext::create_buffer_source`]
@@ -111,6 +139,7 @@ pub struct AudioBufferSourceNode {
     buffer: Option<AudioBuffer>,
     loop_state: LoopState,
     source_started: bool,
+    paused: bool,
 }
 
 impl AudioNode for AudioBufferSourceNode {
@@ -152,23 +181,24 @@ impl AudioScheduledSourceNode for AudioBufferSourceNode {
             "InvalidStateError cannot stop before start"
         );
 
-        self.registration.post_message(ControlMessage::Stop(when));
+        self.registration
+            .post_scheduled_message(ControlMessage::Stop(when), when);
     }
 }
 
 impl AudioBufferSourceNode {
     /// Create a new [`AudioBufferSourceNode`] instance
     pub fn new<C: BaseAudioContext>(context: &C, options: AudioBufferSourceOptions) -> Self {
-        context.register(move |registration| {
-            let AudioBufferSourceOptions {
-                buffer,
-                detune,
-                loop_,
-                loop_start,
-                loop_end,
-                playback_rate,
-            } = options;
-
+        let AudioBufferSourceOptions {
+            buffer,
+            detune,
+            loop_,
+            loop_start,
+            loop_end,
+            playback_rate,
+        } = options;
+
+        let mut node = context.register(move |registration| {
             // these parameters can't be changed to a-rate
             // @see - <https://webaudio.github.io/web-audio-api/#audioparam-automation-rate-constraints>
             let detune_param_options = AudioParamDescriptor {
@@ -197,6 +227,7 @@ impl AudioBufferSourceNode {
                 is_looping: loop_,
                 start: loop_start,
                 end: loop_end,
+                crossfade: 0.,
             };
 
             let renderer = AudioBufferSourceRenderer {
@@ -210,9 +241,14 @@ impl AudioBufferSourceNode {
                 loop_state: loop_state.clone(),
                 render_state: AudioBufferRendererState::default(),
                 ended_triggered: false,
+                seek: None,
+                crossfade: None,
+                paused: false,
+                pause_schedule: Vec::with_capacity(8),
+                pause_gain: 1.,
             };
 
-            let mut node = Self {
+            let node = Self {
                 registration,
                 channel_config: ChannelConfig::default(),
                 detune: d_param,
@@ -221,14 +257,18 @@ impl AudioBufferSourceNode {
                 buffer: None,
                 loop_state,
                 source_started: false,
+                paused: false,
             };
 
-            if let Some(buf) = buffer {
-                node.set_buffer(buf);
-            }
-
             (node, Box::new(renderer))
-        })
+        });
+
+        // the buffer is sent to the renderer once it is part of the graph
+        if let Some(buf) = buffer {
+            node.set_buffer(buf);
+        }
+
+        node
     }
 
     /// Start the playback at the given time and with a given offset
@@ -253,9 +293,91 @@ impl AudioBufferSourceNode {
         self.source_started = true;
 
         let control = ControlMessage::StartWithOffsetAndDuration(start, offset, duration);
+        self.registration.post_scheduled_message(control, start);
+    }
+
+    /// Move the playhead to the given position (in seconds within the [`AudioBuffer`]) at the
+    /// given time, without stopping the playback
+    ///
+    /// A new seek replaces a previous one that did not happen yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buffer_time` or `when` is negative
+    pub fn seek(&mut self, buffer_time: f64, when: f64) {
+        self.seek_with_crossfade(buffer_time, when, 0.);
+    }
+
+    /// Move the playhead to the given position at the given time, crossfading from the previous
+    /// position over `crossfade` seconds to avoid clicks
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buffer_time`, `when` or `crossfade` is negative
+    pub fn seek_with_crossfade(&mut self, buffer_time: f64, when: f64, crossfade: f64) {
+        assert!(
+            buffer_time >= 0. && when >= 0. && crossfade >= 0.,
+            "RangeError - seek values should not be negative"
+        );
+
+        let seek = Seek {
+            when,
+            buffer_time,
+            crossfade,
+        };
+        self.registration.post_message(ControlMessage::Seek(seek));
+    }
+
+    /// Freeze the playhead, after a short fade out
+    ///
+    /// Unlike `stop`, the source can be resumed and keeps its schedule: the stop time still
+    /// applies, and the duration given to `start` does not include the paused time.
+    pub fn pause(&mut self) {
+        let when = self.registration.context().current_time();
+        self.pause_at(when);
+    }
+
+    /// Freeze the playhead at the given time, after a short fade out
+    ///
+    /// # Panics
+    ///
+    /// Panics if `when` is negative
+    pub fn pause_at(&mut self, when: f64) {
+        assert!(when >= 0., "RangeError - pause time should not be negative");
+        self.paused = true;
+        let control = ControlMessage::Pause { when, paused: true };
         self.registration.post_message(control);
     }
 
+    /// Continue the playback from the paused playhead, with a short fade in
+    pub fn resume(&mut self) {
+        let when = self.registration.context().current_time();
+        self.resume_at(when);
+    }
+
+    /// Continue the playback from the paused playhead at the given time, with a short fade in
+    ///
+    /// # Panics
+    ///
+    /// Panics if `when` is negative
+    pub fn resume_at(&mut self, when: f64) {
+        assert!(
+            when >= 0.,
+            "RangeError - resume time should not be negative"
+        );
+        self.paused = false;
+        let control = ControlMessage::Pause {
+            when,
+            paused: false,
+        };
+        self.registration.post_message(control);
+    }
+
+    /// Check if the last call was to [`Self::pause`] rather than [`Self::resume`]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
     /// Current buffer value (nullable)
     pub fn buffer(&self) -> Option<&AudioBuffer> {
         self.buffer.as_ref()
@@ -266,13 +388,19 @@ impl AudioBufferSourceNode {
     /// # Panics
     ///
     /// Panics if a buffer has already been given to the source (though `new` or through
-    /// `set_buffer`)
+    /// `set_buffer`), or if the buffer exceeds the buffer memory quota of the context
     pub fn set_buffer(&mut self, audio_buffer: AudioBuffer) {
         let clone = audio_buffer.clone();
 
         if self.buffer.is_some() {
             panic!("InvalidStateError - cannot assign buffer twice");
         }
+        let allocations = audio_buffer.allocations();
+        let id = self.registration.id();
+        self.context()
+            .enforce_quota(|memory| memory.check_buffer_memory(Some(id), &allocations));
+        self.registration
+            .set_memory(MemoryKind::AudioBuffer, allocations);
         self.buffer = Some(audio_buffer);
 
         self.registration.post_message(clone);
@@ -290,7 +418,9 @@ impl AudioBufferSourceNode {
 
     /// Current playhead position in seconds within the [`AudioBuffer`].
     ///
-    /// This value is updated at the end of each render quantum.
+    /// This value is updated atomically at the end of each render quantum, it
+    /// follows the automations of the `playback_rate` and `detune` params, seeks
+    /// and stays within the loop boundaries when looping.
     ///
     /// Unofficial v2 API extension, not part of the spec yet.
     /// See also: <https://github.com/WebAudio/web-audio-api/issues/2397#issuecomment-709478405>
@@ -338,6 +468,43 @@ impl AudioBufferSourceNode {
         self.registration
             .post_message(ControlMessage::LoopEnd(value));
     }
+
+    /// Duration in seconds of the equal-power crossfade applied across the loop points
+    pub fn loop_crossfade_duration(&self) -> f64 {
+        self.loop_state.crossfade
+    }
+
+    /// Set the duration in seconds of the crossfade applied across the loop points
+    ///
+    /// The end of the loop is crossfaded with its beginning, so that loops that are
+    /// not cut exactly at zero-crossings do not click. After the first iteration the
+    /// playback thus continues after the crossfaded part, i.e. each iteration is
+    /// shortened by the crossfade duration. The duration is limited to half the
+    /// loop length, and no crossfade is applied when playing backwards. The crossfade is
+    /// disabled in [strict conformance
+    /// mode](crate::context::BaseAudioContext::set_strict_conformance).
+    ///
+    /// This is an extension to the spec.
+    ///
+    /// Defaults to 0, i.e. no crossfade.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the duration is negative
+    pub fn set_loop_crossfade_duration(&mut self, value: f64) {
+        assert!(
+            value >= 0.,
+            "RangeError - loop crossfade duration should not be negative"
+        );
+        self.loop_state.crossfade = value;
+        let value = if self.context().strict_conformance() {
+            0.
+        } else {
+            value
+        };
+        self.registration
+            .post_message(ControlMessage::LoopCrossfade(value));
+    }
 }
 
 struct AudioBufferRendererState {
@@ -371,6 +538,13 @@ struct AudioBufferSourceRenderer {
     loop_state: LoopState,
     render_state: AudioBufferRendererState,
     ended_triggered: bool,
+    seek: Option<Seek>,
+    crossfade: Option<Crossfade>,
+    paused: bool,
+    /// scheduled pauses and resumptions, sorted by time
+    pause_schedule: Vec<(f64, bool)>,
+    /// gain of the pause fade, the playhead is frozen when paused and faded out
+    pause_gain: f32,
 }
 
 impl AudioBufferSourceRenderer {
@@ -385,10 +559,62 @@ impl AudioBufferSourceRenderer {
             ControlMessage::Loop(is_looping) => self.loop_state.is_looping = *is_looping,
             ControlMessage::LoopStart(loop_start) => self.loop_state.start = *loop_start,
             ControlMessage::LoopEnd(loop_end) => self.loop_state.end = *loop_end,
+            ControlMessage::LoopCrossfade(crossfade) => self.loop_state.crossfade = *crossfade,
+            ControlMessage::Seek(seek) => self.seek = Some(*seek),
+            ControlMessage::Pause { when, paused } => {
+                let index = self.pause_schedule.partition_point(|(t, _)| t <= when);
+                self.pause_schedule.insert(index, (*when, *paused));
+            }
         }
     }
 }
 
+/// Fraction of a sample frame within which the start time of a source is considered reached
+const START_TIME_TOLERANCE: f64 = 1e-6;
+
+/// Position of the playhead in the buffer, `None` if outside the buffer
+fn compute_playback_info(
+    buffer_time: f64,
+    buffer_duration: f64,
+    sampling_ratio: f64,
+    sample_rate: f64,
+) -> Option<PlaybackInfo> {
+    if buffer_time >= 0. && buffer_time < buffer_duration {
+        let position = buffer_time * sampling_ratio;
+        let playhead = position * sample_rate;
+        let playhead_floored = playhead.floor();
+        let prev_frame_index = playhead_floored as usize; // can't be < 0.
+        let k = (playhead - playhead_floored) as f32;
+
+        Some(PlaybackInfo {
+            prev_frame_index,
+            k,
+        })
+    } else {
+        None
+    }
+}
+
+/// Linearly interpolated sample of the buffer at the playhead
+fn interpolate(buffer_channel: &[f32], playhead: &Option<PlaybackInfo>) -> f32 {
+    match playhead {
+        Some(PlaybackInfo {
+            prev_frame_index,
+            k,
+        }) => {
+            // `prev_frame_index` cannot be out of bounds
+            let prev_sample = buffer_channel[*prev_frame_index];
+            let next_sample = match buffer_channel.get(prev_frame_index + 1) {
+                Some(val) => *val,
+                None => 0.,
+            };
+
+            (1. - k).mul_add(prev_sample, k * next_sample)
+        }
+        None => 0.,
+    }
+}
+
 impl AudioProcessor for AudioBufferSourceRenderer {
     fn process(
         &mut self,
@@ -409,6 +635,7 @@ impl AudioProcessor for AudioBufferSourceRenderer {
             is_looping,
             start: loop_start,
             end: loop_end,
+            crossfade: loop_crossfade,
         } = self.loop_state.clone();
 
         // these will only be used if `loop_` is true, so no need for `Option`
@@ -488,6 +715,9 @@ impl AudioProcessor for AudioBufferSourceRenderer {
             }
         }
 
+        // keep the upcoming frames of a memory-mapped buffer resident
+        buffer.prefetch((buffer_time.max(0.) * buffer.sample_rate() as f64) as usize);
+
         output.set_number_of_channels(buffer.number_of_channels());
 
         // go through the algorithm described in the spec
@@ -528,6 +758,16 @@ impl AudioProcessor for AudioBufferSourceRenderer {
             self.render_state.is_aligned = false;
         }
 
+        // seeking and loop crossfades are handled with sample accuracy in the slow track
+        if self.seek.is_some() || self.crossfade.is_some() || (is_looping && loop_crossfade > 0.) {
+            self.render_state.is_aligned = false;
+        }
+
+        // pausing is handled in the slow track too
+        if self.paused || self.pause_gain < 1. || !self.pause_schedule.is_empty() {
+            self.render_state.is_aligned = false;
+        }
+
         // ---------------------------------------------------------------
         // Fast track
         // ---------------------------------------------------------------
@@ -634,13 +874,33 @@ impl AudioProcessor for AudioBufferSourceRenderer {
             self.render_state.entered_loop = false;
         }
 
+        // with a loop crossfade, the end of the loop is mixed with its beginning and
+        // the playhead then jumps right after the crossfaded beginning
+        let loop_fade = if computed_playback_rate > 0. {
+            loop_crossfade.min((actual_loop_end - actual_loop_start) / 2.)
+        } else {
+            0.
+        };
+        let loop_jump = actual_loop_end - actual_loop_start - loop_fade;
+
         // internal buffer used to store playback infos to compute the samples
         // according to the source buffer. (prev_sample_index, k)
         let mut playback_infos = [None; RENDER_QUANTUM_SIZE];
+        // playhead before a seek and gain of the new playhead, during a crossfade
+        let mut crossfade_infos = [None; RENDER_QUANTUM_SIZE];
+        // gain of the pause fade
+        let mut pause_gains = [1.; RENDER_QUANTUM_SIZE];
+        let pause_fade_step = (dt / PAUSE_FADE_DURATION) as f32;
 
         // compute position for each sample and store into `self.positions`
-        for playback_info in playback_infos.iter_mut() {
-            if current_time < self.start_time
+        for ((playback_info, crossfade_info), pause_gain) in playback_infos
+            .iter_mut()
+            .zip(crossfade_infos.iter_mut())
+            .zip(pause_gains.iter_mut())
+        {
+            // `current_time` accumulates rounding errors, tolerate them so that a source
+            // started on a sample frame starts exactly on that frame
+            if current_time + START_TIME_TOLERANCE * dt < self.start_time
                 || current_time >= self.stop_time
                 || self.render_state.buffer_time_elapsed >= self.duration
             {
@@ -651,8 +911,9 @@ impl AudioProcessor for AudioBufferSourceRenderer {
             }
 
             // we have now reached start time
+            let was_started = self.render_state.started;
             if !self.render_state.started {
-                self.offset += current_time - self.start_time;
+                self.offset += (current_time - self.start_time).max(0.);
 
                 if is_looping && computed_playback_rate >= 0. && self.offset >= actual_loop_end {
                     self.offset = actual_loop_end;
@@ -666,6 +927,25 @@ impl AudioProcessor for AudioBufferSourceRenderer {
                 self.render_state.started = true;
             }
 
+            if let Some(seek) = self.seek {
+                if current_time >= seek.when {
+                    if was_started && seek.crossfade > 0. {
+                        self.crossfade = Some(Crossfade {
+                            buffer_time,
+                            elapsed: 0.,
+                            duration: seek.crossfade,
+                        });
+                    }
+
+                    // playback restarts from the new position, loop is entered again
+                    // according to this new offset
+                    buffer_time = seek.buffer_time;
+                    self.offset = seek.buffer_time;
+                    self.render_state.entered_loop = false;
+                    self.seek = None;
+                }
+            }
+
             if is_looping {
                 if !self.render_state.entered_loop {
                     // playback began before or within loop, and playhead is now past loop start
@@ -683,7 +963,7 @@ impl AudioProcessor for AudioBufferSourceRenderer {
                 // check loop boundaries
                 if self.render_state.entered_loop {
                     while buffer_time >= actual_loop_end {
-                        buffer_time -= actual_loop_end - actual_loop_start;
+                        buffer_time -= loop_jump;
                     }
 
                     while buffer_time < actual_loop_start {
@@ -692,22 +972,63 @@ impl AudioProcessor for AudioBufferSourceRenderer {
                 }
             }
 
-            if buffer_time >= 0. && buffer_time < buffer_duration {
-                let position = buffer_time * sampling_ratio;
-                let playhead = position * sample_rate;
-                let playhead_floored = playhead.floor();
-                let prev_frame_index = playhead_floored as usize; // can't be < 0.
-                let k = (playhead - playhead_floored) as f32;
-
-                *playback_info = Some(PlaybackInfo {
-                    prev_frame_index,
-                    k,
-                });
+            // the playhead does not move once the pause fade out is complete
+            while let Some(&(when, paused)) = self.pause_schedule.first() {
+                if current_time + START_TIME_TOLERANCE * dt < when {
+                    break;
+                }
+                self.paused = paused;
+                self.pause_schedule.remove(0);
+            }
+            if self.paused {
+                self.pause_gain = (self.pause_gain - pause_fade_step).max(0.);
             } else {
+                self.pause_gain = (self.pause_gain + pause_fade_step).min(1.);
+            }
+            if self.paused && self.pause_gain == 0. {
                 *playback_info = None;
+                current_time += dt;
+
+                continue;
+            }
+            *pause_gain = self.pause_gain;
+
+            *playback_info =
+                compute_playback_info(buffer_time, buffer_duration, sampling_ratio, sample_rate);
+
+            let fade_start = actual_loop_end - loop_fade;
+            if is_looping
+                && self.render_state.entered_loop
+                && loop_fade > 0.
+                && buffer_time >= fade_start
+            {
+                // fade in the beginning of the loop while fading out its end
+                let head = buffer_time - loop_jump;
+                let phase = ((buffer_time - fade_start) / loop_fade) as f32;
+                *crossfade_info = Some((*playback_info, phase));
+                *playback_info =
+                    compute_playback_info(head, buffer_duration, sampling_ratio, sample_rate);
             }
 
             let time_incr = dt * computed_playback_rate;
+
+            if let Some(crossfade) = &mut self.crossfade {
+                let previous = compute_playback_info(
+                    crossfade.buffer_time,
+                    buffer_duration,
+                    sampling_ratio,
+                    sample_rate,
+                );
+                let phase = (crossfade.elapsed / crossfade.duration) as f32;
+                *crossfade_info = Some((previous, phase));
+
+                crossfade.buffer_time += time_incr;
+                crossfade.elapsed += dt;
+                if crossfade.elapsed >= crossfade.duration {
+                    self.crossfade = None;
+                }
+            }
+
             buffer_time += time_incr;
             self.render_state.buffer_time_elapsed += time_incr;
             current_time += dt;
@@ -723,27 +1044,34 @@ impl AudioProcessor for AudioBufferSourceRenderer {
 
                 playback_infos
                     .iter()
+                    .zip(crossfade_infos.iter())
+                    .zip(pause_gains.iter())
                     .zip(output_channel.iter_mut())
-                    .for_each(|(playhead, o)| {
-                        *o = match playhead {
-                            Some(PlaybackInfo {
-                                prev_frame_index,
-                                k,
-                            }) => {
-                                // `prev_frame_index` cannot be out of bounds
-                                let prev_sample = buffer_channel[*prev_frame_index];
-                                let next_sample = match buffer_channel.get(prev_frame_index + 1) {
-                                    Some(val) => *val,
-                                    None => 0.,
-                                };
-
-                                (1. - k).mul_add(prev_sample, k * next_sample)
+                    .for_each(|(((playhead, crossfade), pause_gain), o)| {
+                        *o = match crossfade {
+                            // equal power crossfade between both playheads
+                            Some((previous, phase)) => {
+                                let (gain_in, gain_out) = (phase * PI / 2.).sin_cos();
+                                interpolate(buffer_channel, playhead) * gain_in
+                                    + interpolate(buffer_channel, previous) * gain_out
                             }
-                            None => 0.,
-                        };
+                            None => interpolate(buffer_channel, playhead),
+                        } * pause_gain;
                     });
             });
 
+        // wrap the playhead within the loop now rather than on the next sample, so that
+        // the reported position never goes beyond the loop boundaries
+        if is_looping && self.render_state.entered_loop {
+            while buffer_time >= actual_loop_end {
+                buffer_time -= loop_jump;
+            }
+
+            while buffer_time < actual_loop_start {
+                buffer_time += actual_loop_end - actual_loop_start;
+            }
+        }
+
         // update render state
         self.render_state
             .buffer_time
@@ -780,7 +1108,6 @@ impl AudioProcessor for AudioBufferSourceRenderer {
 #[cfg(test)]
 mod tests {
     use float_eq::assert_float_eq;
-    use std::f32::consts::PI;
 
     use crate::context::{BaseAudioContext, OfflineAudioContext};
     use crate::RENDER_QUANTUM_SIZE;
@@ -1351,4 +1678,190 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_seek() {
+        let sample_rate = 48000.;
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE * 2, sample_rate);
+
+        // buffer contains its own frame index
+        let len = RENDER_QUANTUM_SIZE * 4;
+        let mut ramp = context.create_buffer(1, len, sample_rate);
+        let values: Vec<f32> = (0..len).map(|i| i as f32).collect();
+        ramp.copy_to_channel(&values, 0);
+
+        let mut src = context.create_buffer_source();
+        src.connect(&context.destination());
+        src.set_buffer(ramp);
+        src.start();
+        // jump to frame 300 at frame 100
+        src.seek(300. / sample_rate as f64, 100. / sample_rate as f64);
+
+        let result = context.start_rendering_sync();
+        let channel = result.get_channel_data(0);
+
+        let mut expected: Vec<f32> = (0..100).map(|i| i as f32).collect();
+        expected.extend((300..456).map(|i| i as f32));
+        assert_float_eq!(channel[..], expected[..], abs_all <= 1e-3);
+        assert_float_eq!(src.position(), 456. / sample_rate as f64, abs <= 1e-9);
+    }
+
+    #[test]
+    fn test_pause_resume() {
+        let sample_rate = 44100.;
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE * 16, sample_rate);
+
+        // buffer contains its own frame index
+        let len = RENDER_QUANTUM_SIZE * 32;
+        let mut ramp = context.create_buffer(1, len, sample_rate);
+        let values: Vec<f32> = (0..len).map(|i| i as f32).collect();
+        ramp.copy_to_channel(&values, 0);
+
+        let mut src = context.create_buffer_source();
+        src.connect(&context.destination());
+        src.set_buffer(ramp);
+        src.start();
+        assert!(!src.is_paused());
+        // pause at frame 100, the fade out lasts 220.5 frames
+        src.pause_at(100. / sample_rate as f64);
+        assert!(src.is_paused());
+        // resume at frame 1000, from the frozen playhead at frame 320
+        src.resume_at(1000. / sample_rate as f64);
+        assert!(!src.is_paused());
+
+        let result = context.start_rendering_sync();
+        let channel = result.get_channel_data(0);
+
+        let expected: Vec<f32> = (0..100).map(|i| i as f32).collect();
+        assert_float_eq!(channel[..100], expected[..], abs_all <= 0.);
+        // fade out is monotonic
+        channel[100..320]
+            .iter()
+            .zip(100..)
+            .map(|(v, i)| v / i as f32)
+            .collect::<Vec<_>>()
+            .windows(2)
+            .for_each(|w| assert!(w[1] < w[0]));
+        assert_float_eq!(channel[320..1000], [0.; 680][..], abs_all <= 0.);
+        // fade in is monotonic
+        channel[1000..1220]
+            .iter()
+            .zip(320..)
+            .map(|(v, i)| v / i as f32)
+            .collect::<Vec<_>>()
+            .windows(2)
+            .for_each(|w| assert!(w[1] > w[0]));
+        let expected: Vec<f32> = (540..1368).map(|i| i as f32).collect();
+        assert_float_eq!(channel[1220..], expected[..], abs_all <= 1e-3);
+        assert_float_eq!(src.position(), 1368. / sample_rate as f64, abs <= 1e-9);
+    }
+
+    #[test]
+    fn test_seek_with_crossfade() {
+        let sample_rate = 48000.;
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE * 2, sample_rate);
+
+        // ones in first half, zeros in second half
+        let len = RENDER_QUANTUM_SIZE * 4;
+        let mut buffer = context.create_buffer(1, len, sample_rate);
+        buffer.copy_to_channel(&[1.; RENDER_QUANTUM_SIZE * 2], 0);
+
+        let mut src = context.create_buffer_source();
+        src.connect(&context.destination());
+        src.set_buffer(buffer);
+        src.start();
+        src.seek_with_crossfade(
+            (RENDER_QUANTUM_SIZE * 3) as f64 / sample_rate as f64,
+            10. / sample_rate as f64,
+            100. / sample_rate as f64,
+        );
+
+        let result = context.start_rendering_sync();
+        let channel = result.get_channel_data(0);
+
+        assert_float_eq!(channel[..10], [1.; 10][..], abs_all <= 0.);
+        assert_float_eq!(channel[10], 1., abs <= 1e-6);
+        // half way, equal power crossfade
+        assert_float_eq!(channel[60], (PI / 4.).cos(), abs <= 1e-4);
+        // fade out is monotonic
+        channel[10..110]
+            .windows(2)
+            .for_each(|w| assert!(w[1] <= w[0]));
+        assert_float_eq!(channel[110..], [0.; 146][..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_position() {
+        let sample_rate = 48000.;
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE * 2, sample_rate);
+        let buffer = context.create_buffer(1, RENDER_QUANTUM_SIZE * 4, sample_rate);
+
+        let mut src = context.create_buffer_source();
+        src.connect(&context.destination());
+        src.set_buffer(buffer);
+        src.playback_rate().set_value(0.5);
+        src.playback_rate()
+            .set_value_at_time(2., RENDER_QUANTUM_SIZE as f64 / sample_rate as f64);
+        src.start();
+        assert_float_eq!(src.position(), 0., abs <= 0.);
+
+        let _ = context.start_rendering_sync();
+        // 64 frames at half speed, then 256 frames at double speed
+        assert_float_eq!(src.position(), 320. / sample_rate as f64, abs <= 1e-9);
+    }
+
+    #[test]
+    fn test_position_loop() {
+        let sample_rate = 32768.;
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE * 3, sample_rate);
+        let buffer = context.create_buffer(1, RENDER_QUANTUM_SIZE, sample_rate);
+
+        let mut src = context.create_buffer_source();
+        src.connect(&context.destination());
+        src.set_buffer(buffer);
+        src.set_loop(true);
+        src.playback_rate().set_value(2.);
+        src.start();
+
+        let _ = context.start_rendering_sync();
+        // 768 frames were played, i.e. exactly 6 times the buffer
+        assert_float_eq!(src.position(), 0., abs <= 1e-9);
+    }
+
+    #[test]
+    fn test_loop_crossfade() {
+        let sample_rate = 32768.;
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE * 2, sample_rate);
+
+        // 100 frames of ones followed by 100 frames of zeros
+        let mut buffer = context.create_buffer(1, 200, sample_rate);
+        buffer.copy_to_channel(&[1.; 100], 0);
+
+        let mut src = context.create_buffer_source();
+        src.connect(&context.destination());
+        src.set_buffer(buffer);
+        src.set_loop(true);
+        src.set_loop_crossfade_duration(50. / sample_rate as f64);
+        assert_float_eq!(
+            src.loop_crossfade_duration(),
+            50. / sample_rate as f64,
+            abs <= 0.
+        );
+        src.start();
+
+        let result = context.start_rendering_sync();
+        let channel = result.get_channel_data(0);
+
+        // loop end is crossfaded with loop start
+        assert_float_eq!(channel[..100], [1.; 100][..], abs_all <= 0.);
+        assert_float_eq!(channel[100..150], [0.; 50][..], abs_all <= 0.);
+        assert_float_eq!(channel[150], 0., abs <= 1e-6);
+        assert_float_eq!(channel[175], (PI / 4.).sin(), abs <= 1e-4);
+        channel[150..200]
+            .windows(2)
+            .for_each(|w| assert!(w[1] >= w[0]));
+        // playback continues after the crossfaded beginning, i.e. loop lasts 150 frames
+        assert_float_eq!(channel[200..250], [1.; 50][..], abs_all <= 0.);
+        assert_float_eq!(channel[250], 0., abs <= 0.);
+    }
 }
@@ -0,0 +1,588 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use realfft::num_complex::Complex;
+use realfft::RealToComplex;
+
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::dsp::{plan_fft_forward, Window};
+use crate::events::{EventHandler, EventPayload, EventType};
+use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
+use crate::{AtomicF32, Event, RENDER_QUANTUM_SIZE};
+
+use super::{AudioNode, ChannelConfig, ChannelConfigOptions, ChannelInterpretation};
+
+/// Duration of the spectral flux history used for the tempo estimate, in seconds
+const TEMPO_HISTORY: f32 = 6.;
+
+/// Interval between two tempo estimates, in seconds
+const TEMPO_INTERVAL: f32 = 0.5;
+
+/// Duration of the average of the spectral flux the onset threshold is relative to, in seconds
+const THRESHOLD_AVERAGE: f32 = 0.5;
+
+/// Minimum interval between two onsets, in seconds
+const MIN_ONSET_INTERVAL: f32 = 0.05;
+
+/// Spectral flux (per frequency bin) below which no onset is detected, so silence and noise
+/// floors are ignored
+const MIN_ONSET_FLUX: f32 = 1e-4;
+
+/// Ratio of the highest autocorrelation of the spectral flux above which the shortest period is
+/// picked as the beat period, so the tempo is not halved
+const BEAT_PERIOD_CUTOFF: f32 = 0.9;
+
+/// Event dispatched by a [`BeatDetectorNode`] when an onset is detected
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct OnsetEvent {
+    /// Identifier of the `BeatDetectorNode`
+    pub node_id: u64,
+    /// Time of the onset, in terms of the associated AudioContext's currentTime
+    pub time: f64,
+    /// Spectral flux of the onset, i.e. the increase of the log magnitude spectrum per
+    /// frequency bin
+    pub strength: f32,
+    /// Tempo estimate at the time of the onset, in beats per minute, 0 if unknown
+    pub tempo: f32,
+    /// Inherits from this base Event
+    pub event: Event,
+}
+
+/// Options for constructing a [`BeatDetectorNode`]
+#[derive(Clone, Debug)]
+pub struct BeatDetectorOptions {
+    /// size of the analysis frames, in sample-frames, the frames overlap by half
+    pub fft_size: usize,
+    /// ratio of the spectral flux over its recent average above which an onset is detected
+    pub threshold: f32,
+    /// lowest detected tempo, in beats per minute
+    pub min_tempo: f32,
+    /// highest detected tempo, in beats per minute
+    pub max_tempo: f32,
+    pub channel_config: ChannelConfigOptions,
+}
+
+impl Default for BeatDetectorOptions {
+    fn default() -> Self {
+        Self {
+            fft_size: 1024,
+            threshold: 1.5,
+            min_tempo: 60.,
+            max_tempo: 200.,
+            channel_config: ChannelConfigOptions::default(),
+        }
+    }
+}
+
+/// `BeatDetectorNode` detects the onsets and estimates the tempo of its input
+///
+/// The input is down mixed to mono and passed through unchanged. The onsets are the peaks of
+/// the spectral flux of the input, i.e. the sum of the increases of its log magnitude
+/// spectrum, which rise above `threshold` times its recent average. They are dispatched as
+/// [`OnsetEvent`]s, see [`set_ononset`](Self::set_ononset). The tempo is the period of the
+/// spectral flux over the last seconds, found by autocorrelation, see [`tempo`](Self::tempo).
+///
+/// The onsets are reported with a latency of about one analysis frame.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use std::fs::File;
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{BeatDetectorNode, BeatDetectorOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let file = File::open("samples/sample.wav").unwrap();
+/// let buffer = context.decode_audio_data_sync(file).unwrap();
+///
+/// let detector = BeatDetectorNode::new(&context, BeatDetectorOptions::default());
+/// detector.connect(&context.destination());
+/// detector.set_ononset(|event| println!("onset at {:.2}s, {} BPM", event.time, event.tempo));
+///
+/// let mut src = context.create_buffer_source();
+/// src.set_buffer(buffer);
+/// src.connect(&detector);
+/// src.start();
+/// ```
+pub struct BeatDetectorNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    fft_size: usize,
+    tempo: Arc<AtomicF32>,
+}
+
+impl AudioNode for BeatDetectorNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl BeatDetectorNode {
+    /// Create a new `BeatDetectorNode`
+    ///
+    /// # Panics
+    ///
+    /// Panics if:
+    /// - the fft size is not a power of two in the range [256, 32768]
+    /// - the threshold is not strictly positive and finite
+    /// - the tempo range is empty or not strictly positive
+    pub fn new<C: BaseAudioContext>(context: &C, options: BeatDetectorOptions) -> Self {
+        let BeatDetectorOptions {
+            fft_size,
+            threshold,
+            min_tempo,
+            max_tempo,
+            channel_config,
+        } = options;
+
+        assert!(
+            fft_size.is_power_of_two() && (256..=32768).contains(&fft_size),
+            "IndexSizeError - Invalid fft size: {:?} is outside range [256, 32768] or not a power of two",
+            fft_size
+        );
+        assert!(
+            threshold > 0. && threshold.is_finite(),
+            "RangeError - threshold ({:?}) should be strictly positive and finite",
+            threshold
+        );
+        assert!(
+            min_tempo > 0. && min_tempo < max_tempo && max_tempo.is_finite(),
+            "RangeError - invalid tempo range [{:?}, {:?}]",
+            min_tempo,
+            max_tempo
+        );
+
+        context.register(move |registration| {
+            let tempo = Arc::new(AtomicF32::new(0.));
+
+            let detector = OnsetDetector::new(
+                fft_size,
+                context.sample_rate(),
+                threshold,
+                (min_tempo, max_tempo),
+            );
+
+            let render = BeatDetectorRenderer {
+                ring_buffer: vec![0.; fft_size],
+                write_index: 0,
+                hop_frames: 0,
+                frame: vec![0.; fft_size],
+                detector,
+                tempo: Arc::clone(&tempo),
+            };
+
+            let node = BeatDetectorNode {
+                registration,
+                channel_config: channel_config.into(),
+                fft_size,
+                tempo,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// Size of the analysis frames, in sample-frames
+    pub fn fft_size(&self) -> usize {
+        self.fft_size
+    }
+
+    /// Tempo estimate, in beats per minute
+    ///
+    /// Returns 0 until a few seconds of input have been analysed, or if the input has no
+    /// regular beat in the tempo range.
+    pub fn tempo(&self) -> f32 {
+        self.tempo.load(Ordering::Relaxed)
+    }
+
+    /// Register callback to run when an onset is detected
+    ///
+    /// Only a single event handler is active at any time. Calling this method multiple times will
+    /// override the previous event handler.
+    pub fn set_ononset<F: FnMut(OnsetEvent) + Send + 'static>(&self, mut callback: F) {
+        let callback = move |v| match v {
+            EventPayload::Onset(v) => callback(v),
+            _ => unreachable!(),
+        };
+
+        self.context().set_event_handler(
+            EventType::Onset(self.registration().id()),
+            EventHandler::Multiple(Box::new(callback)),
+        );
+    }
+
+    /// Unset the callback to run when an onset is detected
+    pub fn clear_ononset(&self) {
+        self.context()
+            .clear_event_handler(EventType::Onset(self.registration().id()));
+    }
+}
+
+/// Spectral flux onset detector and tempo estimator, processing overlapping frames of a mono
+/// signal
+struct OnsetDetector {
+    r2c: Arc<dyn RealToComplex<f32>>,
+    window: Vec<f32>,
+    input: Vec<f32>,
+    spectrum: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    /// log magnitudes of the previous frame
+    magnitudes: Vec<f32>,
+    /// ring buffer of the spectral flux of the last frames
+    flux: Vec<f32>,
+    /// the spectral flux history in chronological order, for the tempo estimate
+    history: Vec<f32>,
+    /// number of processed frames
+    frames: usize,
+    last_onset: Option<usize>,
+    threshold: f32,
+    frames_per_second: f32,
+    average_frames: usize,
+    min_onset_interval: usize,
+    tempo_interval: usize,
+    /// range of the beat period, in frames
+    min_period: usize,
+    max_period: usize,
+    tempo: f32,
+}
+
+impl OnsetDetector {
+    fn new(fft_size: usize, sample_rate: f32, threshold: f32, tempo_range: (f32, f32)) -> Self {
+        let r2c = plan_fft_forward(fft_size);
+        let input = r2c.make_input_vec();
+        let spectrum = r2c.make_output_vec();
+        let scratch = r2c.make_scratch_vec();
+
+        let frames_per_second = sample_rate / (fft_size / 2) as f32;
+        let frames = |seconds: f32| ((seconds * frames_per_second).round() as usize).max(1);
+        let min_period = ((60. * frames_per_second / tempo_range.1).floor() as usize).max(1);
+        let max_period = (60. * frames_per_second / tempo_range.0).ceil() as usize;
+        // the history spans at least two beat periods
+        let history_frames = frames(TEMPO_HISTORY).max(2 * max_period + 1);
+
+        Self {
+            r2c,
+            window: Window::Hann.values(fft_size),
+            input,
+            magnitudes: vec![0.; spectrum.len()],
+            spectrum,
+            scratch,
+            flux: vec![0.; history_frames],
+            history: vec![0.; history_frames],
+            frames: 0,
+            last_onset: None,
+            threshold,
+            frames_per_second,
+            average_frames: frames(THRESHOLD_AVERAGE),
+            min_onset_interval: frames(MIN_ONSET_INTERVAL),
+            tempo_interval: frames(TEMPO_INTERVAL),
+            min_period,
+            max_period,
+            tempo: 0.,
+        }
+    }
+
+    /// Spectral flux of the frame `back` frames before the last one
+    fn flux_at(&self, back: usize) -> f32 {
+        let len = self.flux.len();
+        self.flux[(self.frames - 1 - back) % len]
+    }
+
+    /// Process the next frame, returns the strength of the onset of the previous frame if any
+    fn process(&mut self, frame: &[f32]) -> Option<f32> {
+        self.input
+            .iter_mut()
+            .zip(frame)
+            .zip(&self.window)
+            .for_each(|((i, s), w)| *i = s * w);
+        self.r2c
+            .process_with_scratch(&mut self.input, &mut self.spectrum, &mut self.scratch)
+            .unwrap();
+
+        // sum of the increases of the log magnitudes
+        let flux: f32 = self
+            .magnitudes
+            .iter_mut()
+            .zip(&self.spectrum)
+            .map(|(previous, c)| {
+                let magnitude = c.norm().ln_1p();
+                let increase = (magnitude - *previous).max(0.);
+                *previous = magnitude;
+                increase
+            })
+            .sum();
+
+        let len = self.flux.len();
+        self.flux[self.frames % len] = flux / self.magnitudes.len() as f32;
+        self.frames += 1;
+
+        if self.frames % self.tempo_interval == 0 {
+            self.estimate_tempo();
+        }
+
+        self.pick_onset()
+    }
+
+    /// An onset is a local maximum of the spectral flux above the threshold, it is picked one
+    /// frame later
+    fn pick_onset(&mut self) -> Option<f32> {
+        if self.frames < 3 {
+            return None;
+        }
+
+        let candidate = self.flux_at(1);
+        if candidate <= self.flux_at(2) || candidate < self.flux_at(0) {
+            return None;
+        }
+        if candidate < MIN_ONSET_FLUX {
+            return None;
+        }
+
+        let average_frames = self.average_frames.min(self.frames - 2);
+        let average = (2..2 + average_frames)
+            .map(|back| self.flux_at(back))
+            .sum::<f32>()
+            / average_frames as f32;
+        if candidate <= self.threshold * average {
+            return None;
+        }
+
+        let onset = self.frames - 2;
+        if matches!(self.last_onset, Some(last) if onset - last < self.min_onset_interval) {
+            return None;
+        }
+        self.last_onset = Some(onset);
+
+        Some(candidate)
+    }
+
+    /// Update the tempo estimate by autocorrelation of the spectral flux history
+    fn estimate_tempo(&mut self) {
+        let len = self.flux.len();
+        if self.frames < len {
+            return;
+        }
+
+        // unroll the ring buffer, oldest frame first, and remove the mean
+        let start = self.frames % len;
+        let (newest, oldest) = self.flux.split_at(start);
+        self.history[..oldest.len()].copy_from_slice(oldest);
+        self.history[oldest.len()..].copy_from_slice(newest);
+        let mean = self.history.iter().sum::<f32>() / len as f32;
+        self.history.iter_mut().for_each(|v| *v -= mean);
+
+        let history = &self.history;
+        let autocorrelation = |lag: usize| {
+            history
+                .iter()
+                .zip(&history[lag..])
+                .map(|(a, b)| a * b)
+                .sum::<f32>()
+                / (len - lag) as f32
+        };
+
+        let highest = (self.min_period..=self.max_period)
+            .map(autocorrelation)
+            .fold(0., f32::max);
+        if highest <= 0. {
+            self.tempo = 0.;
+            return;
+        }
+
+        // shortest period close enough to the highest autocorrelation, at one of its peaks
+        let period = (self.min_period..=self.max_period)
+            .find(|&lag| {
+                let value = autocorrelation(lag);
+                value >= BEAT_PERIOD_CUTOFF * highest
+                    && value >= autocorrelation(lag - 1).max(autocorrelation(lag + 1))
+            })
+            .unwrap_or(self.min_period);
+
+        // parabolic interpolation of the peak
+        let (a, b, c) = (
+            autocorrelation(period - 1),
+            autocorrelation(period),
+            autocorrelation(period + 1),
+        );
+        let curvature = a - 2. * b + c;
+        let delta = if curvature < 0. {
+            0.5 * (a - c) / curvature
+        } else {
+            0.
+        };
+
+        self.tempo = 60. * self.frames_per_second / (period as f32 + delta);
+    }
+}
+
+struct BeatDetectorRenderer {
+    ring_buffer: Vec<f32>,
+    write_index: usize,
+    /// number of sample-frames written since the last analysis frame
+    hop_frames: usize,
+    /// the analysis frame in chronological order
+    frame: Vec<f32>,
+    detector: OnsetDetector,
+    tempo: Arc<AtomicF32>,
+}
+
+impl AudioProcessor for BeatDetectorRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        scope: &RenderScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        // pass through input
+        *output = input.clone();
+
+        // down mix to mono
+        let mut mono = input.clone();
+        mono.mix(1, ChannelInterpretation::Speakers);
+
+        let fft_size = self.ring_buffer.len();
+        let data = mono.channel_data(0);
+        self.ring_buffer[self.write_index..self.write_index + RENDER_QUANTUM_SIZE]
+            .copy_from_slice(&data[..]);
+        self.write_index = (self.write_index + RENDER_QUANTUM_SIZE) % fft_size;
+        self.hop_frames += RENDER_QUANTUM_SIZE;
+
+        if self.hop_frames < fft_size / 2 {
+            return false;
+        }
+        self.hop_frames = 0;
+
+        let (newest, oldest) = self.ring_buffer.split_at(self.write_index);
+        let (frame_oldest, frame_newest) = self.frame.split_at_mut(oldest.len());
+        frame_oldest.copy_from_slice(oldest);
+        frame_newest.copy_from_slice(newest);
+
+        let strength = self.detector.process(&self.frame);
+        let tempo = self.detector.tempo;
+        self.tempo.store(tempo, Ordering::Relaxed);
+
+        if let Some(strength) = strength {
+            // the onset is in the newest half of the previous frame
+            let hop = (fft_size / 2) as f64 / scope.sample_rate as f64;
+            let end_time =
+                scope.current_time + RENDER_QUANTUM_SIZE as f64 / scope.sample_rate as f64;
+            let event = OnsetEvent {
+                node_id: scope.node_id.get().0,
+                time: end_time - 2. * hop,
+                strength,
+                tempo,
+                event: Event { type_: "onset" },
+            };
+            scope.send_onset_event(event);
+        }
+
+        // no tail-time
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+    use crate::AudioBuffer;
+
+    /// Clicks at the given tempo, starting half a period in
+    fn click_track(tempo: f32, duration: f32, sample_rate: f32) -> Vec<f32> {
+        let period = 60. / tempo * sample_rate;
+        let mut signal = vec![0.; (duration * sample_rate) as usize];
+        let mut position = period / 2.;
+        while (position as usize) < signal.len() {
+            let start = position as usize;
+            let end = (start + 64).min(signal.len());
+            signal[start..end].iter_mut().for_each(|s| *s = 0.8);
+            position += period;
+        }
+        signal
+    }
+
+    #[test]
+    fn test_onsets() {
+        let sample_rate = 48000.;
+        let fft_size = 1024;
+        let hop = fft_size / 2;
+        let signal = click_track(120., 4., sample_rate);
+
+        let mut detector = OnsetDetector::new(fft_size, sample_rate, 1.5, (60., 200.));
+        let onsets: Vec<_> = (0..signal.len() / hop - 1)
+            .filter_map(|i| {
+                let frame = &signal[i * hop..i * hop + fft_size];
+                // the onset is in the newest half of the previous frame
+                detector.process(frame).map(|_| i * hop)
+            })
+            .collect();
+
+        assert_eq!(onsets.len(), 8);
+        onsets.iter().enumerate().for_each(|(beat, &onset)| {
+            let click = 12000 + beat * 24000;
+            assert!(onset <= click + hop && click <= onset + hop);
+        });
+    }
+
+    #[test]
+    fn test_silence() {
+        let mut detector = OnsetDetector::new(1024, 48000., 1.5, (60., 200.));
+        let frame = vec![0.; 1024];
+        (0..1000).for_each(|_| assert!(detector.process(&frame).is_none()));
+        assert_float_eq!(detector.tempo, 0., abs <= 0.);
+    }
+
+    #[test]
+    fn test_tempo() {
+        let sample_rate = 48000.;
+        let signal = click_track(128., 10., sample_rate);
+
+        let context = OfflineAudioContext::new(1, signal.len(), sample_rate);
+        let detector = BeatDetectorNode::new(&context, BeatDetectorOptions::default());
+        detector.connect(&context.destination());
+
+        let mut src = context.create_buffer_source();
+        src.set_buffer(AudioBuffer::from(vec![signal], sample_rate));
+        src.connect(&detector);
+        src.start();
+
+        let _ = context.start_rendering_sync();
+        assert_float_eq!(detector.tempo(), 128., abs <= 2.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_fft_size() {
+        let context = OfflineAudioContext::new(1, 1024, 48000.);
+        let options = BeatDetectorOptions {
+            fft_size: 1000,
+            ..BeatDetectorOptions::default()
+        };
+        let _ = BeatDetectorNode::new(&context, options);
+    }
+}
@@ -9,6 +9,7 @@ use crate::param::{AudioParam, AudioParamDescriptor};
 use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
 use crate::{MAX_CHANNELS, RENDER_QUANTUM_SIZE};
 
+use super::dry_wet::{mix_dry_wet, mix_param_descriptor};
 use super::{AudioNode, ChannelConfig, ChannelConfigOptions};
 
 fn get_computed_freq(freq: f32, detune: f32) -> f32 {
@@ -248,6 +249,29 @@ impl Default for BiquadFilterOptions {
     }
 }
 
+impl BiquadFilterOptions {
+    /// Magnitude of the frequency response of a filter with these options, at the given
+    /// normalized frequency (0 is DC, 1 is the Nyquist frequency)
+    pub(crate) fn magnitude_response(&self, sample_rate: f32, frequency: f64) -> f64 {
+        let nyquist = sample_rate / 2.;
+        let computed_freq = get_computed_freq(self.frequency, self.detune).clamp(0., nyquist);
+        let Coefficients { b0, b1, b2, a1, a2 } = calculate_coefs(
+            self.type_,
+            f64::from(sample_rate),
+            f64::from(computed_freq),
+            f64::from(self.gain),
+            f64::from(self.q),
+        );
+
+        // H(z) evaluated at z = exp(j*pi*frequency)
+        let omega = -PI * frequency;
+        let z = Complex::new(omega.cos(), omega.sin());
+        let numerator = b0 + (b1 + b2 * z) * z;
+        let denominator = Complex::new(1., 0.) + (a1 + a2 * z) * z;
+        (numerator / denominator).norm()
+    }
+}
+
 /// BiquadFilterNode is an AudioNode processor implementing very common low-order
 /// IIR filters.
 ///
@@ -308,6 +332,8 @@ pub struct BiquadFilterNode {
     /// boost/attenuation (dB) - its impact on the frequency response of the
     /// filter, depends on the `BiquadFilterType`
     gain: AudioParam,
+    /// balance between the dry and the wet signal
+    mix: AudioParam,
     /// Current biquad filter type
     type_: BiquadFilterType,
 }
@@ -377,17 +403,25 @@ impl BiquadFilterNode {
             let (f_param, f_proc) = context.create_audio_param(freq_options, &registration);
             f_param.set_value(frequency);
 
+            // the gain is expressed in dB and applied as `10^(gain / 40)`, which overflows
+            // above `40 * log10(f32::MAX)`
             let gain_options = AudioParamDescriptor {
                 min_value: f32::MIN,
-                max_value: f32::MAX,
+                max_value: 40. * f32::MAX.log10(),
                 default_value: 0.,
                 automation_rate: crate::param::AutomationRate::A,
             };
             let (g_param, g_proc) = context.create_audio_param(gain_options, &registration);
             g_param.set_value(gain);
 
+            let (mix_param, mix_proc) = context.create_audio_param(
+                mix_param_descriptor(context.strict_conformance()),
+                &registration,
+            );
+
             let renderer = BiquadFilterRenderer {
                 gain: g_proc,
+                mix: mix_proc,
                 detune: d_proc,
                 frequency: f_proc,
                 q: q_proc,
@@ -406,6 +440,7 @@ impl BiquadFilterNode {
                 detune: d_param,
                 frequency: f_param,
                 gain: g_param,
+                mix: mix_param,
             };
 
             (node, Box::new(renderer))
@@ -436,6 +471,15 @@ impl BiquadFilterNode {
         &self.q
     }
 
+    /// Returns the mix audio parameter, the equal-power balance between the dry (0) and the
+    /// filtered (1) signal
+    ///
+    /// This is an extension to the spec, defaults to 1.
+    #[must_use]
+    pub fn mix(&self) -> &AudioParam {
+        &self.mix
+    }
+
     /// Returns the biquad filter type
     #[must_use]
     pub fn type_(&self) -> BiquadFilterType {
@@ -541,6 +585,8 @@ struct BiquadFilterRenderer {
     /// boost/attenuation (dB) - its impact on the frequency response of the filter
     /// depends on the `BiquadFilterType`
     gain: AudioParamId,
+    /// balance between the dry and the wet signal
+    mix: AudioParamId,
     /// `BiquadFilterType`
     type_: BiquadFilterType,
     // keep filter state for each channel
@@ -677,6 +723,8 @@ impl AudioProcessor for BiquadFilterRenderer {
             self.y2[channel_number] = y2;
         }
 
+        mix_dry_wet(input, output, &params.get(&self.mix));
+
         true
     }
 
@@ -753,6 +801,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_param_ranges() {
+        let context = OfflineAudioContext::new(2, 1, 44_100.);
+        let biquad = BiquadFilterNode::new(&context, BiquadFilterOptions::default());
+
+        assert_float_eq!(biquad.frequency().min_value(), 0., abs <= 0.);
+        assert_float_eq!(biquad.frequency().max_value(), 22_050., abs <= 0.);
+        assert_float_eq!(biquad.detune().max_value(), 153_600., abs <= 0.);
+        assert_float_eq!(biquad.q().max_value(), f32::MAX, abs <= 0.);
+        // 40 * log10(FLT_MAX)
+        assert_float_eq!(biquad.gain().max_value(), 1541.273, abs <= 1e-3);
+    }
+
     #[test]
     #[should_panic]
     fn test_frequency_response_arguments() {
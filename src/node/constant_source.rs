@@ -100,7 +100,8 @@ impl AudioScheduledSourceNode for ConstantSourceNode {
     }
 
     fn start_at(&mut self, when: f64) {
-        self.registration.post_message(Schedule::Start(when));
+        self.registration
+            .post_scheduled_message(Schedule::Start(when), when);
     }
 
     fn stop(&mut self) {
@@ -109,7 +110,8 @@ impl AudioScheduledSourceNode for ConstantSourceNode {
     }
 
     fn stop_at(&mut self, when: f64) {
-        self.registration.post_message(Schedule::Stop(when));
+        self.registration
+            .post_scheduled_message(Schedule::Stop(when), when);
     }
 }
 
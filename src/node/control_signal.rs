@@ -0,0 +1,443 @@
+//! Conditioning of control signals, modular synthesizer style
+//!
+//! These nodes shape signals meant to drive `AudioParam`s (LFOs, envelopes, sequences) rather
+//! than audible signals.
+use std::ops::Range;
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
+use crate::{MAX_CHANNELS, RENDER_QUANTUM_SIZE};
+
+use super::{AudioNode, ChannelConfig, ChannelConfigOptions};
+
+/// Options for constructing a [`SampleAndHoldNode`]
+#[derive(Clone, Debug, Default)]
+pub struct SampleAndHoldOptions {
+    pub channel_config: ChannelConfigOptions,
+}
+
+/// `SampleAndHoldNode` samples its signal input on each rising edge of its gate input, and
+/// outputs the sampled value until the next one
+///
+/// The signal is connected to the input 0 and the gate to the input 1. A rising edge is a change
+/// of the (first channel of the) gate from a value lower or equal to zero to a positive value.
+/// The output holds zero until the first rising edge.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode, OscillatorType};
+/// use web_audio_api::node::{SampleAndHoldNode, SampleAndHoldOptions};
+///
+/// let context = AudioContext::default();
+///
+/// // staircase of 8 steps per second following a slow sawtooth
+/// let mut lfo = context.create_oscillator();
+/// lfo.set_type(OscillatorType::Sawtooth);
+/// lfo.frequency().set_value(0.5);
+/// lfo.start();
+/// let mut clock = context.create_oscillator();
+/// clock.set_type(OscillatorType::Square);
+/// clock.frequency().set_value(8.);
+///
+/// let sample_and_hold = SampleAndHoldNode::new(&context, SampleAndHoldOptions::default());
+/// lfo.connect_at(&sample_and_hold, 0, 0);
+/// clock.connect_at(&sample_and_hold, 0, 1);
+/// clock.start();
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&context.destination());
+/// osc.start();
+/// let depth = context.create_gain();
+/// depth.gain().set_value(200.);
+/// sample_and_hold.connect(&depth);
+/// depth.connect(osc.frequency());
+/// ```
+pub struct SampleAndHoldNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+}
+
+impl AudioNode for SampleAndHoldNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        2
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl SampleAndHoldNode {
+    pub fn new<C: BaseAudioContext>(context: &C, options: SampleAndHoldOptions) -> Self {
+        context.register(move |registration| {
+            let node = SampleAndHoldNode {
+                registration,
+                channel_config: options.channel_config.into(),
+            };
+
+            let render = SampleAndHoldRenderer {
+                held: [0.; MAX_CHANNELS],
+                number_of_channels: 1,
+                last_gate: 0.,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+}
+
+/// Fill a range of frames of `output` with the held values
+fn fill_held(output: &mut AudioRenderQuantum, held: &[f32], range: Range<usize>) {
+    output
+        .channels_mut()
+        .iter_mut()
+        .zip(held)
+        .for_each(|(o, held)| o[range.clone()].fill(*held));
+}
+
+struct SampleAndHoldRenderer {
+    held: [f32; MAX_CHANNELS],
+    /// number of channels of the signal at the last rising edge
+    number_of_channels: usize,
+    /// last value of the gate, to detect rising edges across render quanta
+    last_gate: f32,
+}
+
+impl AudioProcessor for SampleAndHoldRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &RenderScope,
+    ) -> bool {
+        let [signal, gate] = inputs else {
+            unreachable!()
+        };
+        let output = &mut outputs[0];
+
+        // a silent signal is sampled as zeros with the previous number of channels
+        if !signal.is_silent() {
+            self.number_of_channels = signal.number_of_channels();
+        }
+        output.set_number_of_channels(self.number_of_channels);
+
+        // the output is filled segment by segment, between the rising edges
+        let mut segment_start = 0;
+
+        if gate.is_silent() {
+            self.last_gate = 0.;
+        } else {
+            for (i, &g) in gate.channel_data(0).iter().enumerate() {
+                if g > 0. && self.last_gate <= 0. {
+                    fill_held(output, &self.held, segment_start..i);
+                    segment_start = i;
+
+                    let held = &mut self.held[..self.number_of_channels];
+                    if signal.is_silent() {
+                        held.fill(0.);
+                    } else {
+                        held.iter_mut()
+                            .zip(signal.channels())
+                            .for_each(|(held, channel)| *held = channel[i]);
+                    }
+                }
+                self.last_gate = g;
+            }
+        }
+
+        fill_held(output, &self.held, segment_start..RENDER_QUANTUM_SIZE);
+
+        false
+    }
+}
+
+/// Options for constructing a [`SlewLimiterNode`]
+#[derive(Clone, Debug)]
+pub struct SlewLimiterOptions {
+    /// maximum rate of increase of the output, in units per second
+    pub rise_rate: f32,
+    /// maximum rate of decrease of the output, in units per second
+    pub fall_rate: f32,
+    pub channel_config: ChannelConfigOptions,
+}
+
+impl Default for SlewLimiterOptions {
+    fn default() -> Self {
+        Self {
+            rise_rate: 100.,
+            fall_rate: 100.,
+            channel_config: ChannelConfigOptions::default(),
+        }
+    }
+}
+
+/// `SlewLimiterNode` limits the rate of change of its input
+///
+/// The output follows the input, but rises by at most `rise_rate` units per second and falls by
+/// at most `fall_rate` units per second, e.g. to smooth the steps of a sequence (portamento) or to
+/// turn a gate into a linear attack / release envelope.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{SlewLimiterNode, SlewLimiterOptions};
+///
+/// let context = AudioContext::default();
+///
+/// // glide between the frequencies of the sequence, at 2000 Hz per second at most
+/// let mut sequence = context.create_constant_source();
+/// sequence.offset().set_value_at_time(220., 0.);
+/// sequence.offset().set_value_at_time(440., 0.5);
+/// sequence.start();
+///
+/// let options = SlewLimiterOptions { rise_rate: 2000., fall_rate: 2000., ..Default::default() };
+/// let glide = SlewLimiterNode::new(&context, options);
+/// sequence.connect(&glide);
+///
+/// let mut osc = context.create_oscillator();
+/// osc.frequency().set_value(0.);
+/// glide.connect(osc.frequency());
+/// osc.connect(&context.destination());
+/// osc.start();
+/// ```
+pub struct SlewLimiterNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    rise_rate: AudioParam,
+    fall_rate: AudioParam,
+}
+
+impl AudioNode for SlewLimiterNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+fn rate_param<C: BaseAudioContext>(
+    context: &C,
+    registration: &AudioContextRegistration,
+    value: f32,
+) -> (AudioParam, AudioParamId) {
+    let param_opts = AudioParamDescriptor {
+        min_value: 0.,
+        max_value: f32::MAX,
+        default_value: 100.,
+        automation_rate: AutomationRate::A,
+    };
+    let (param, proc) = context.create_audio_param(param_opts, registration);
+    param.set_value(value);
+    (param, proc)
+}
+
+impl SlewLimiterNode {
+    pub fn new<C: BaseAudioContext>(context: &C, options: SlewLimiterOptions) -> Self {
+        context.register(move |registration| {
+            let (rise_param, rise_proc) = rate_param(context, &registration, options.rise_rate);
+            let (fall_param, fall_proc) = rate_param(context, &registration, options.fall_rate);
+
+            let node = SlewLimiterNode {
+                registration,
+                channel_config: options.channel_config.into(),
+                rise_rate: rise_param,
+                fall_rate: fall_param,
+            };
+
+            let render = SlewLimiterRenderer {
+                rise_rate: rise_proc,
+                fall_rate: fall_proc,
+                last: [0.; MAX_CHANNELS],
+                number_of_channels: 1,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// Maximum rate of increase of the output, in units per second
+    pub fn rise_rate(&self) -> &AudioParam {
+        &self.rise_rate
+    }
+
+    /// Maximum rate of decrease of the output, in units per second
+    pub fn fall_rate(&self) -> &AudioParam {
+        &self.fall_rate
+    }
+}
+
+struct SlewLimiterRenderer {
+    rise_rate: AudioParamId,
+    fall_rate: AudioParamId,
+    /// last output value of each channel
+    last: [f32; MAX_CHANNELS],
+    number_of_channels: usize,
+}
+
+impl AudioProcessor for SlewLimiterRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &RenderScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        // the output falls back to zero when the input is silent
+        if input.is_silent() {
+            let last = &self.last[..self.number_of_channels];
+            if last.iter().all(|v| *v == 0.) {
+                output.make_silent();
+                return false;
+            }
+        } else {
+            // channels which are no longer present restart from zero
+            let number_of_channels = input.number_of_channels();
+            self.last[number_of_channels..self.number_of_channels.max(number_of_channels)].fill(0.);
+            self.number_of_channels = number_of_channels;
+        }
+
+        let dt = 1. / scope.sample_rate;
+        let rise_rate = params.get(&self.rise_rate);
+        let fall_rate = params.get(&self.fall_rate);
+
+        output.set_number_of_channels(self.number_of_channels);
+
+        output
+            .channels_mut()
+            .iter_mut()
+            .enumerate()
+            .zip(self.last.iter_mut())
+            .for_each(|((index, o), last)| {
+                let input = (!input.is_silent()).then(|| &input.channel_data(index)[..]);
+
+                o.iter_mut()
+                    .enumerate()
+                    .zip(rise_rate.iter().cycle().zip(fall_rate.iter().cycle()))
+                    .for_each(|((i, o), (rise, fall))| {
+                        let target = input.map_or(0., |input| input[i]);
+                        let delta = (target - *last).clamp(-fall * dt, rise * dt);
+                        *last += delta;
+                        *o = *last;
+                    });
+            });
+
+        // keep rendering until the output is back to zero
+        self.last[..self.number_of_channels]
+            .iter()
+            .any(|v| *v != 0.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+    use crate::AudioBuffer;
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_sample_and_hold() {
+        let context = OfflineAudioContext::new(1, 256, 48000.);
+        let node = SampleAndHoldNode::new(&context, SampleAndHoldOptions::default());
+        node.connect(&context.destination());
+
+        let ramp = (0..256).map(|i| i as f32).collect();
+        let mut signal = context.create_buffer_source();
+        signal.set_buffer(AudioBuffer::from(vec![ramp], 48000.));
+        signal.connect_at(&node, 0, 0);
+        signal.start();
+
+        // rising edges at frames 10, 100 and 200, the gate stays high from 200
+        let mut gate = vec![0.; 256];
+        gate[10..50].fill(1.);
+        gate[100] = 0.5;
+        gate[101..150].fill(-1.);
+        gate[200..].fill(1.);
+        let mut trigger = context.create_buffer_source();
+        trigger.set_buffer(AudioBuffer::from(vec![gate], 48000.));
+        trigger.connect_at(&node, 0, 1);
+        trigger.start();
+
+        let output = context.start_rendering_sync();
+        let mut expected = [0.; 256];
+        expected[10..100].fill(10.);
+        expected[100..200].fill(100.);
+        expected[200..].fill(200.);
+        assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_sample_and_hold_without_gate() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let node = SampleAndHoldNode::new(&context, SampleAndHoldOptions::default());
+        node.connect(&context.destination());
+
+        let mut signal = context.create_constant_source();
+        signal.connect_at(&node, 0, 0);
+        signal.start();
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(output.get_channel_data(0), &[0.; 128][..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_slew_limiter() {
+        let sample_rate = 1280.;
+        let context = OfflineAudioContext::new(1, 256, sample_rate);
+        let options = SlewLimiterOptions {
+            rise_rate: 10.,   // 1 per 128 frames
+            fall_rate: 1280., // 1 per frame
+            ..Default::default()
+        };
+        let node = SlewLimiterNode::new(&context, options);
+        node.connect(&context.destination());
+
+        // step from 0 to 0.5 during the first quantum, then back to zero
+        let mut src = context.create_constant_source();
+        src.offset().set_value(0.5);
+        src.connect(&node);
+        src.start();
+        src.stop_at(128. / sample_rate as f64);
+
+        let output = context.start_rendering_sync();
+        let channel = output.get_channel_data(0);
+        let expected: Vec<f32> = (1..=128).map(|i| i as f32 / 128.).collect();
+        assert_float_eq!(&channel[..64], &expected[..64], abs_all <= 1e-6);
+        assert_float_eq!(&channel[64..128], &[0.5; 64][..], abs_all <= 1e-6);
+        assert_float_eq!(&channel[128..], &[0.; 128][..], abs_all <= 1e-6);
+    }
+}
@@ -1,13 +1,17 @@
 use std::any::Any;
 use std::sync::Arc;
 
-use realfft::{num_complex::Complex, ComplexToReal, RealFftPlanner, RealToComplex};
+use realfft::{num_complex::Complex, ComplexToReal, RealToComplex};
 
 use crate::buffer::AudioBuffer;
-use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::dsp::{plan_fft_forward, plan_fft_inverse};
+use crate::memory::{Allocation, MemoryKind};
+use crate::param::AudioParam;
 use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
 use crate::RENDER_QUANTUM_SIZE;
 
+use super::dry_wet::{mix_dry_wet, mix_param_descriptor};
 use super::{AudioNode, ChannelConfig, ChannelConfigOptions, ChannelInterpretation};
 
 /// Scale buffer by an equal-power normalization
@@ -115,6 +119,8 @@ pub struct ConvolverNode {
     normalize: bool,
     /// The response buffer, nullable
     buffer: Option<AudioBuffer>,
+    /// Balance between the dry and the wet signal
+    mix: AudioParam,
 }
 
 impl AudioNode for ConvolverNode {
@@ -155,13 +161,21 @@ impl ConvolverNode {
         } = options;
 
         let mut node = context.base().register(move |registration| {
-            let renderer = ConvolverRenderer { inner: None };
+            let (mix, mix_proc) = context.create_audio_param(
+                mix_param_descriptor(context.strict_conformance()),
+                &registration,
+            );
+            let renderer = ConvolverRenderer {
+                inner: None,
+                mix: mix_proc,
+            };
 
             let node = Self {
                 registration,
                 channel_config: channel_config.into(),
                 normalize: !disable_normalization,
                 buffer: None,
+                mix,
             };
 
             (node, Box::new(renderer))
@@ -214,8 +228,11 @@ impl ConvolverNode {
 
         let padded_buffer = AudioBuffer::from(samples, sample_rate);
         let convolve = ConvolverRendererInner::new(padded_buffer);
+        let allocation = Allocation::owned(convolve.memory_size());
 
         self.registration.post_message(Some(convolve));
+        self.registration
+            .set_memory(MemoryKind::Convolver, vec![allocation]);
         self.buffer = Some(buffer);
     }
 
@@ -228,6 +245,14 @@ impl ConvolverNode {
     pub fn set_normalize(&mut self, value: bool) {
         self.normalize = value;
     }
+
+    /// Balance between the dry (0) and the convolved (1) signal, with an equal-power curve
+    ///
+    /// The input passes through unchanged while no buffer is set. This is an extension to the
+    /// spec, defaults to 1.
+    pub fn mix(&self) -> &AudioParam {
+        &self.mix
+    }
 }
 
 fn roll_zero<T: Default + Copy>(signal: &mut [T], n: usize) {
@@ -238,7 +263,8 @@ fn roll_zero<T: Default + Copy>(signal: &mut [T], n: usize) {
     signal[len - n..].fill(T::default());
 }
 
-struct Fft {
+/// Real FFT of a fixed length, with preallocated buffers
+pub(crate) struct Fft {
     fft_forward: Arc<dyn RealToComplex<f32>>,
     fft_inverse: Arc<dyn ComplexToReal<f32>>,
     fft_input: Vec<f32>,
@@ -247,11 +273,9 @@ struct Fft {
 }
 
 impl Fft {
-    fn new(length: usize) -> Self {
-        let mut fft_planner = RealFftPlanner::<f32>::new();
-
-        let fft_forward = fft_planner.plan_fft_forward(length);
-        let fft_inverse = fft_planner.plan_fft_inverse(length);
+    pub(crate) fn new(length: usize) -> Self {
+        let fft_forward = plan_fft_forward(length);
+        let fft_inverse = plan_fft_inverse(length);
 
         let fft_input = fft_forward.make_input_vec();
         let fft_scratch = fft_forward.make_scratch_vec();
@@ -266,15 +290,15 @@ impl Fft {
         }
     }
 
-    fn real(&mut self) -> &mut [f32] {
+    pub(crate) fn real(&mut self) -> &mut [f32] {
         &mut self.fft_input[..]
     }
 
-    fn complex(&mut self) -> &mut [Complex<f32>] {
+    pub(crate) fn complex(&mut self) -> &mut [Complex<f32>] {
         &mut self.fft_output[..]
     }
 
-    fn process(&mut self) -> &[Complex<f32>] {
+    pub(crate) fn process(&mut self) -> &[Complex<f32>] {
         self.fft_forward
             .process_with_scratch(
                 &mut self.fft_input,
@@ -285,7 +309,7 @@ impl Fft {
         &self.fft_output[..]
     }
 
-    fn inverse(&mut self) -> &[f32] {
+    pub(crate) fn inverse(&mut self) -> &[f32] {
         self.fft_inverse
             .process_with_scratch(
                 &mut self.fft_output,
@@ -338,6 +362,12 @@ impl ConvolverRendererInner {
         }
     }
 
+    /// Bytes of the partitioned impulse response and of the processing buffers
+    fn memory_size(&self) -> usize {
+        let complex = std::mem::size_of::<Complex<f32>>();
+        (self.h.len() + self.fdl.len()) * complex + self.out.len() * std::mem::size_of::<f32>()
+    }
+
     fn process(&mut self, input: &[f32], output: &mut [f32]) {
         self.fft2.real()[..RENDER_QUANTUM_SIZE].copy_from_slice(input);
         self.fft2.real()[RENDER_QUANTUM_SIZE..].fill(0.);
@@ -395,6 +425,7 @@ impl ConvolverRendererInner {
 
 struct ConvolverRenderer {
     inner: Option<ConvolverRendererInner>,
+    mix: AudioParamId,
 }
 
 impl AudioProcessor for ConvolverRenderer {
@@ -402,7 +433,7 @@ impl AudioProcessor for ConvolverRenderer {
         &mut self,
         inputs: &[AudioRenderQuantum],
         outputs: &mut [AudioRenderQuantum],
-        _params: AudioParamValues<'_>,
+        params: AudioParamValues<'_>,
         _scope: &RenderScope,
     ) -> bool {
         // single input/output node
@@ -421,15 +452,19 @@ impl AudioProcessor for ConvolverRenderer {
 
         // handle tail time
         if input.is_silent() {
-            return convolver.tail(output);
+            let tail = convolver.tail(output);
+            mix_dry_wet(input, output, &params.get(&self.mix));
+            return tail;
         }
 
         let mut mono = input.clone();
         mono.mix(1, ChannelInterpretation::Speakers);
-        let input = &mono.channel_data(0)[..];
-        let output = &mut output.channel_data_mut(0)[..];
+        convolver.process(
+            &mono.channel_data(0)[..],
+            &mut output.channel_data_mut(0)[..],
+        );
 
-        convolver.process(input, output);
+        mix_dry_wet(input, output, &params.get(&self.mix));
 
         true
     }
@@ -1,4 +1,5 @@
 use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::memory::{Allocation, MemoryKind};
 use crate::param::{AudioParam, AudioParamDescriptor};
 use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
 use crate::RENDER_QUANTUM_SIZE;
@@ -111,6 +112,10 @@ impl AudioNode for DelayNode {
         &self.writer_registration
     }
 
+    fn output_registration(&self) -> &AudioContextRegistration {
+        &self.reader_registration
+    }
+
     fn channel_config(&self) -> &ChannelConfig {
         &self.channel_config
     }
@@ -266,6 +271,10 @@ impl DelayNode {
         context.base().mark_cycle_breaker(&node.writer_registration);
         context.base().connect(writer_id, reader_id, 0, 0);
 
+        let bytes = num_quanta * RENDER_QUANTUM_SIZE * std::mem::size_of::<f32>();
+        node.writer_registration
+            .set_memory(MemoryKind::DelayLine, vec![Allocation::owned(bytes)]);
+
         node
     }
 
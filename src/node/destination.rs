@@ -1,3 +1,5 @@
+use std::any::Any;
+
 use crate::context::{AudioContextRegistration, BaseAudioContext};
 use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
 
@@ -86,7 +88,9 @@ impl AudioDestinationNode {
                 registration,
                 channel_config,
             };
-            let proc = DestinationRenderer {};
+            let proc = DestinationRenderer {
+                channel_map: Vec::new(),
+            };
 
             (node, Box::new(proc))
         })
@@ -111,9 +115,40 @@ impl AudioDestinationNode {
     pub fn max_channel_count(&self) -> usize {
         self.registration.context().base().max_channel_count()
     }
+
+    /// Route the channels of the graph to the channels of the device
+    ///
+    /// The channel `i` of the input of the destination is played on the device channel
+    /// `map[i]`, or dropped when it is `None`. Graph channels that are routed to the same device
+    /// channel are summed, and the device channels that receive no graph channel are silent. For
+    /// example, `&[None, None, Some(2), Some(3)]` sends the third and fourth channels of the
+    /// graph, e.g. a click track, to the outputs 3/4 of an audio interface only.
+    ///
+    /// An empty map restores the default routing, where each graph channel is played on the
+    /// device channel with the same index.
+    ///
+    /// This is an extension to the spec.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a device channel is not lower than [`Self::max_channel_count`]
+    pub fn set_channel_map(&self, map: &[Option<usize>]) {
+        let max_channel_count = self.max_channel_count();
+        map.iter().flatten().for_each(|&channel| {
+            assert!(
+                channel < max_channel_count,
+                "IndexSizeError - device channel {channel} is out of bounds, maxChannelCount is {max_channel_count}"
+            );
+        });
+
+        self.registration.post_message(map.to_vec());
+    }
 }
 
-struct DestinationRenderer {}
+struct DestinationRenderer {
+    /// Device channel of each graph channel, the channels are not routed when empty
+    channel_map: Vec<Option<usize>>,
+}
 
 impl AudioProcessor for DestinationRenderer {
     fn process(
@@ -127,9 +162,112 @@ impl AudioProcessor for DestinationRenderer {
         let input = &inputs[0];
         let output = &mut outputs[0];
 
-        // just move input to output
-        *output = input.clone();
+        if self.channel_map.is_empty() {
+            // just move input to output
+            *output = input.clone();
+            return true;
+        }
+
+        let number_of_channels = self
+            .channel_map
+            .iter()
+            .flatten()
+            .max()
+            .map_or(1, |&channel| channel + 1);
+        output.make_silent();
+        output.set_number_of_channels(number_of_channels);
+
+        self.channel_map
+            .iter()
+            .zip(input.channels())
+            .for_each(|(device_channel, channel)| {
+                if let Some(device_channel) = *device_channel {
+                    output.channel_data_mut(device_channel).add(channel);
+                }
+            });
 
         true
     }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if let Some(channel_map) = msg.downcast_mut::<Vec<Option<usize>>>() {
+            // Avoid deallocation in the render thread by swapping the channel map.
+            std::mem::swap(&mut self.channel_map, channel_map);
+            return;
+        }
+
+        log::warn!("DestinationRenderer: Dropping incoming message {msg:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::{AudioNode, AudioScheduledSourceNode};
+    use crate::RENDER_QUANTUM_SIZE;
+
+    #[test]
+    fn test_channel_map() {
+        let context = OfflineAudioContext::new(4, RENDER_QUANTUM_SIZE, 44_100.);
+        let merger = context.create_channel_merger(2);
+        for (input, value) in [1., 2.].into_iter().enumerate() {
+            let mut src = context.create_constant_source();
+            src.offset().set_value(value);
+            src.connect_at(&merger, 0, input);
+            src.start();
+        }
+        merger.connect(&context.destination());
+
+        // the second channel of the graph is sent to the outputs 3/4
+        context
+            .destination()
+            .set_channel_map(&[None, Some(2), Some(3)]);
+        let output = context.start_rendering_sync();
+
+        let expected = [0., 0., 2., 0.];
+        for (channel, value) in expected.into_iter().enumerate() {
+            assert_float_eq!(
+                output.get_channel_data(channel),
+                &[value; RENDER_QUANTUM_SIZE][..],
+                abs_all <= 0.
+            );
+        }
+    }
+
+    #[test]
+    fn test_channel_map_sum() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44_100.);
+        let merger = context.create_channel_merger(2);
+        for (input, value) in [1., 2.].into_iter().enumerate() {
+            let mut src = context.create_constant_source();
+            src.offset().set_value(value);
+            src.connect_at(&merger, 0, input);
+            src.start();
+        }
+        merger.connect(&context.destination());
+
+        let destination = context.destination();
+        destination.set_channel_map(&[Some(1), Some(1)]);
+        let output = context.start_rendering_sync();
+
+        assert_float_eq!(
+            output.get_channel_data(0),
+            &[0.; RENDER_QUANTUM_SIZE][..],
+            abs_all <= 0.
+        );
+        assert_float_eq!(
+            output.get_channel_data(1),
+            &[3.; RENDER_QUANTUM_SIZE][..],
+            abs_all <= 0.
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_channel_map() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44_100.);
+        context.destination().set_channel_map(&[Some(2)]);
+    }
 }
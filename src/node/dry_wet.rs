@@ -0,0 +1,197 @@
+//! Dry/wet mix of the effect nodes
+//!
+//! The effect nodes expose a `mix` param, the balance between their input (dry) and their output
+//! (wet). The mix is applied by the renderer of the effect, so the dry signal can be aligned with
+//! the latency of the effect and no parallel dry path needs to be connected. The `mix` param is
+//! an extension to the spec, it is fixed to fully wet in strict conformance mode.
+//!
+//! The `DelayNode` has no `mix` param: it can be part of a cycle, where its output is rendered
+//! before its input.
+use std::f32::consts::FRAC_PI_2;
+
+use crate::param::{AudioParamDescriptor, AutomationRate};
+use crate::render::AudioRenderQuantum;
+
+use super::ChannelInterpretation;
+
+/// Descriptor of the `mix` param of the effect nodes, the output is fully wet by default
+///
+/// In `strict` conformance mode the nominal range is reduced to 1, so the output stays fully wet.
+pub(crate) fn mix_param_descriptor(strict: bool) -> AudioParamDescriptor {
+    AudioParamDescriptor {
+        min_value: if strict { 1. } else { 0. },
+        max_value: 1.,
+        default_value: 1.,
+        automation_rate: AutomationRate::A,
+    }
+}
+
+/// Equal-power gains of the dry and the wet signal
+fn dry_wet_gains(mix: f32) -> (f32, f32) {
+    // cos(PI / 2) is not exactly zero, the dry signal would leak into a fully wet output
+    if mix == 1. {
+        return (0., 1.);
+    }
+    let angle = mix * FRAC_PI_2;
+    (angle.cos(), angle.sin())
+}
+
+/// Mix the dry signal into the output of the effect, following the values of the `mix` param
+///
+/// The dry signal must be aligned with the wet signal, i.e. delayed by the latency of the
+/// effect. The output has the channel count of the largest of both signals.
+pub(crate) fn mix_dry_wet(dry: &AudioRenderQuantum, wet: &mut AudioRenderQuantum, mix: &[f32]) {
+    // fast path for the default, fully wet, output
+    if mix.len() == 1 && mix[0] == 1. {
+        return;
+    }
+
+    if dry.is_silent() {
+        if wet.is_silent() {
+            return;
+        }
+        wet.channels_mut().iter_mut().for_each(|channel| {
+            channel
+                .iter_mut()
+                .zip(mix.iter().cycle())
+                .for_each(|(w, &m)| *w *= dry_wet_gains(m).1);
+        });
+        return;
+    }
+
+    let number_of_channels = dry.number_of_channels().max(wet.number_of_channels());
+    let mut dry = dry.clone();
+    dry.mix(number_of_channels, ChannelInterpretation::Speakers);
+    wet.mix(number_of_channels, ChannelInterpretation::Speakers);
+
+    if mix.len() == 1 {
+        let (dry_gain, wet_gain) = dry_wet_gains(mix[0]);
+        wet.channels_mut()
+            .iter_mut()
+            .zip(dry.channels())
+            .for_each(|(wet, dry)| {
+                wet.iter_mut()
+                    .zip(dry.iter())
+                    .for_each(|(w, d)| *w = *w * wet_gain + d * dry_gain);
+            });
+    } else {
+        wet.channels_mut()
+            .iter_mut()
+            .zip(dry.channels())
+            .for_each(|(wet, dry)| {
+                wet.iter_mut()
+                    .zip(dry.iter())
+                    .zip(mix)
+                    .for_each(|((w, d), &m)| {
+                        let (dry_gain, wet_gain) = dry_wet_gains(m);
+                        *w = *w * wet_gain + d * dry_gain;
+                    });
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::{AudioNode, AudioScheduledSourceNode};
+    use crate::{AudioBuffer, RENDER_QUANTUM_SIZE};
+
+    #[test]
+    fn test_dry_wet_gains() {
+        let (dry, wet) = super::dry_wet_gains(0.);
+        assert_float_eq!(dry, 1., abs <= 0.);
+        assert_float_eq!(wet, 0., abs <= 0.);
+        let (dry, wet) = super::dry_wet_gains(0.5);
+        assert_float_eq!(dry * dry + wet * wet, 1., abs <= 1e-6);
+        assert_float_eq!(dry, wet, abs <= 1e-6);
+    }
+
+    #[test]
+    fn test_biquad_mix() {
+        let render = |mix: f32| {
+            let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+            let mut src = context.create_constant_source();
+            src.start();
+            let biquad = context.create_biquad_filter();
+            biquad.frequency().set_value(100.);
+            biquad.mix().set_value(mix);
+            src.connect(&biquad);
+            biquad.connect(&context.destination());
+            context.start_rendering_sync()
+        };
+
+        // dry signal only
+        let output = render(0.);
+        assert_float_eq!(
+            output.get_channel_data(0),
+            &[1.; RENDER_QUANTUM_SIZE][..],
+            abs_all <= 0.
+        );
+
+        // equal-power sum of the dry and the filtered signals
+        let wet = render(1.);
+        let output = render(0.5);
+        output
+            .get_channel_data(0)
+            .iter()
+            .zip(wet.get_channel_data(0))
+            .for_each(|(o, w)| {
+                let expected = std::f32::consts::FRAC_1_SQRT_2 * (1. + w);
+                assert_float_eq!(*o, expected, abs <= 1e-6);
+            });
+    }
+
+    #[test]
+    fn test_compressor_mix() {
+        let sample_rate = 44_100.;
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE * 4, sample_rate);
+        let data = (0..RENDER_QUANTUM_SIZE).map(|i| i as f32 / 128.).collect();
+        let mut src = context.create_buffer_source();
+        src.set_buffer(AudioBuffer::from(vec![data], sample_rate));
+        src.start();
+        let compressor = context.create_dynamics_compressor();
+        compressor.mix().set_value(0.);
+        src.connect(&compressor);
+        compressor.connect(&context.destination());
+
+        // the dry signal is delayed by the lookahead of the compressor
+        let output = context.start_rendering_sync();
+        let channel = output.get_channel_data(0);
+        let delay = channel.iter().position(|v| *v != 0.).unwrap() - 1;
+        assert_eq!(delay % RENDER_QUANTUM_SIZE, 0);
+        assert!(delay > 0);
+        channel[delay..delay + RENDER_QUANTUM_SIZE]
+            .iter()
+            .enumerate()
+            .for_each(|(i, v)| assert_float_eq!(*v, i as f32 / 128., abs <= 0.));
+    }
+
+    #[test]
+    fn test_convolver_mix() {
+        let sample_rate = 44_100.;
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE * 2, sample_rate);
+        let mut impulse = vec![0.; RENDER_QUANTUM_SIZE];
+        impulse[0] = 1.;
+        let mut src = context.create_buffer_source();
+        src.set_buffer(AudioBuffer::from(vec![impulse], sample_rate));
+        src.start();
+
+        // the response repeats the impulse after 10 frames
+        let mut response = vec![0.; 20];
+        response[10] = 1.;
+        let mut convolver = context.create_convolver();
+        convolver.set_normalize(false);
+        convolver.set_buffer(AudioBuffer::from(vec![response], sample_rate));
+        convolver.mix().set_value(0.5);
+        src.connect(&convolver);
+        convolver.connect(&context.destination());
+
+        let output = context.start_rendering_sync();
+        let mut expected = vec![0.; RENDER_QUANTUM_SIZE * 2];
+        expected[0] = std::f32::consts::FRAC_1_SQRT_2;
+        expected[10] = std::f32::consts::FRAC_1_SQRT_2;
+        assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 1e-6);
+    }
+}
@@ -6,6 +6,7 @@ use crate::param::{AudioParam, AudioParamDescriptor};
 use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
 use crate::{AtomicF32, RENDER_QUANTUM_SIZE};
 
+use super::dry_wet::{mix_dry_wet, mix_param_descriptor};
 use super::{AudioNode, ChannelConfig, ChannelConfigOptions};
 
 // Converting a value 𝑣 in decibels to linear gain unit means returning 10𝑣/20.
@@ -104,6 +105,7 @@ pub struct DynamicsCompressorNode {
     ratio: AudioParam,
     release: AudioParam,
     threshold: AudioParam,
+    mix: AudioParam,
     reduction: Arc<AtomicF32>,
 }
 
@@ -185,6 +187,11 @@ impl DynamicsCompressorNode {
             threshold_param.set_automation_rate_constrained(true);
             threshold_param.set_value(options.threshold);
 
+            let (mix_param, mix_proc) = context.create_audio_param(
+                mix_param_descriptor(context.strict_conformance()),
+                &registration,
+            );
+
             let reduction = Arc::new(AtomicF32::new(0.));
 
             // define the number of buffers we need to have a delay line of ~6ms
@@ -199,6 +206,7 @@ impl DynamicsCompressorNode {
                 ratio: ratio_proc,
                 release: release_proc,
                 threshold: threshold_proc,
+                mix: mix_proc,
                 reduction: Arc::clone(&reduction),
                 ring_buffer,
                 ring_index: 0,
@@ -213,6 +221,7 @@ impl DynamicsCompressorNode {
                 ratio: ratio_param,
                 release: release_param,
                 threshold: threshold_param,
+                mix: mix_param,
                 reduction,
             };
 
@@ -240,6 +249,14 @@ impl DynamicsCompressorNode {
         &self.threshold
     }
 
+    /// Balance between the dry (0) and the compressed (1) signal, with an equal-power curve
+    ///
+    /// The dry signal is delayed by the lookahead of the compressor. This is an extension to the
+    /// spec, defaults to 1.
+    pub fn mix(&self) -> &AudioParam {
+        &self.mix
+    }
+
     pub fn reduction(&self) -> f32 {
         self.reduction.load(Ordering::Relaxed)
     }
@@ -251,6 +268,7 @@ struct DynamicsCompressorRenderer {
     ratio: AudioParamId,
     release: AudioParamId,
     threshold: AudioParamId,
+    mix: AudioParamId,
     reduction: Arc<AtomicF32>,
     ring_buffer: Vec<AudioRenderQuantum>,
     ring_index: usize,
@@ -413,6 +431,9 @@ impl AudioProcessor for DynamicsCompressorRenderer {
                 .for_each(|(o, g)| *o *= g);
         });
 
+        // the dry signal is the delayed input
+        mix_dry_wet(delayed, output, &params.get(&self.mix));
+
         true
     }
 }
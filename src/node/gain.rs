@@ -1,6 +1,7 @@
 use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
 use crate::param::{AudioParam, AudioParamDescriptor};
 use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
+use crate::{db_to_gain, gain_to_db};
 
 use super::{AudioNode, ChannelConfig, ChannelConfigOptions};
 
@@ -76,6 +77,19 @@ impl GainNode {
     pub fn gain(&self) -> &AudioParam {
         &self.gain
     }
+
+    /// Current gain in decibels, `f32::NEG_INFINITY` for a gain of zero
+    pub fn gain_db(&self) -> f32 {
+        gain_to_db(self.gain.value())
+    }
+
+    /// Set the gain in decibels, `f32::NEG_INFINITY` mutes the node
+    ///
+    /// This is a shorthand for `gain().set_value(db_to_gain(value_db))`, see
+    /// [`AudioParam::ramp_db`] to fade the gain linearly in decibels.
+    pub fn set_gain_db(&self, value_db: f32) {
+        self.gain.set_value(db_to_gain(value_db));
+    }
 }
 
 struct GainRenderer {
@@ -2,10 +2,12 @@
 use num_complex::Complex;
 use std::f64::consts::PI;
 
-use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::AudioParam;
 use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
 use crate::MAX_CHANNELS;
 
+use super::dry_wet::{mix_dry_wet, mix_param_descriptor};
 use super::{AudioNode, ChannelConfig, ChannelConfigOptions};
 
 /// Filter order is limited to 20
@@ -135,6 +137,8 @@ pub struct IIRFilterNode {
     feedforward: Vec<f64>,
     /// denomintor filter's coefficients
     feedback: Vec<f64>,
+    /// balance between the dry and the wet signal
+    mix: AudioParam,
 }
 
 impl AudioNode for IIRFilterNode {
@@ -181,19 +185,33 @@ impl IIRFilterNode {
             assert_valid_feedforward_coefs(&feedforward);
             assert_valid_feedback_coefs(&feedback);
 
-            let render = IirFilterRenderer::new(feedforward.clone(), feedback.clone());
+            let (mix, mix_proc) = context.create_audio_param(
+                mix_param_descriptor(context.strict_conformance()),
+                &registration,
+            );
+            let render = IirFilterRenderer::new(feedforward.clone(), feedback.clone(), mix_proc);
 
             let node = Self {
                 registration,
                 channel_config: channel_config.into(),
                 feedforward,
                 feedback,
+                mix,
             };
 
             (node, Box::new(render))
         })
     }
 
+    /// Returns the mix audio parameter, the equal-power balance between the dry (0) and the
+    /// filtered (1) signal
+    ///
+    /// This is an extension to the spec, defaults to 1.
+    #[must_use]
+    pub fn mix(&self) -> &AudioParam {
+        &self.mix
+    }
+
     /// Returns the frequency response for the specified frequencies
     ///
     /// # Arguments
@@ -248,6 +266,8 @@ struct IirFilterRenderer {
     norm_coeffs: Vec<(f64, f64)>,
     /// filter's states
     states: Vec<Vec<f64>>,
+    /// balance between the dry and the wet signal
+    mix: AudioParamId,
 }
 
 impl IirFilterRenderer {
@@ -255,8 +275,10 @@ impl IirFilterRenderer {
     ///
     /// # Arguments
     ///
-    /// * `config` - renderer config
-    fn new(mut feedforward: Vec<f64>, mut feedback: Vec<f64>) -> Self {
+    /// * `feedforward` - numerator filter's coefficients
+    /// * `feedback` - denominator filter's coefficients
+    /// * `mix` - balance between the dry and the wet signal
+    fn new(mut feedforward: Vec<f64>, mut feedback: Vec<f64>, mix: AudioParamId) -> Self {
         // make sure feedback and feedforward have same length, fill with 0. to match
         match (feedforward.len(), feedback.len()) {
             (feedforward_len, feedback_len) if feedforward_len > feedback_len => {
@@ -290,6 +312,7 @@ impl IirFilterRenderer {
         Self {
             norm_coeffs,
             states,
+            mix,
         }
     }
 }
@@ -299,7 +322,7 @@ impl AudioProcessor for IirFilterRenderer {
         &mut self,
         inputs: &[AudioRenderQuantum],
         outputs: &mut [AudioRenderQuantum],
-        _params: AudioParamValues<'_>,
+        params: AudioParamValues<'_>,
         _scope: &RenderScope,
     ) -> bool {
         // single input/output node
@@ -373,6 +396,8 @@ impl AudioProcessor for IirFilterRenderer {
             }
         }
 
+        mix_dry_wet(input, output, &params.get(&self.mix));
+
         true
     }
 }
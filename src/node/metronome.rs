@@ -0,0 +1,507 @@
+use std::any::Any;
+use std::f64::consts::PI;
+
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::{AudioNode, ChannelConfig, ChannelConfigOptions};
+
+/// Duration of the clicks, in seconds
+const CLICK_DURATION: f64 = 0.03;
+
+/// Sound of the clicks of a [`MetronomeNode`]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ClickSound {
+    /// Short sine beep, a fifth higher on accented beats
+    #[default]
+    Beep,
+    /// Damped resonances of a wood block, a fifth higher on accented beats
+    Woodblock,
+    /// Burst of white noise
+    Noise,
+}
+
+/// Samples of the clicks of a sound, the accented click first
+fn click_samples(sound: ClickSound, sample_rate: f32) -> [Vec<f32>; 2] {
+    let sample_rate = f64::from(sample_rate);
+    let length = (CLICK_DURATION * sample_rate).ceil() as usize;
+
+    // the clicks start at full amplitude, on the sample frame of the beat
+    let click = |pitch: f64, gain: f64| {
+        let mut seed = 0x2545_f491_u32;
+        (0..length)
+            .map(|i| {
+                let t = i as f64 / sample_rate;
+                let value = match sound {
+                    ClickSound::Beep => (2. * PI * 1000. * pitch * t).cos() * (-t / 0.008).exp(),
+                    ClickSound::Woodblock => {
+                        let body = (2. * PI * 800. * pitch * t).cos();
+                        let overtone = (2. * PI * 2200. * pitch * t).cos();
+                        (body + 0.5 * overtone) / 1.5 * (-t / 0.004).exp()
+                    }
+                    ClickSound::Noise => {
+                        // xorshift, the same burst for each click
+                        seed ^= seed << 13;
+                        seed ^= seed >> 17;
+                        seed ^= seed << 5;
+                        let noise = seed as f64 / u32::MAX as f64 * 2. - 1.;
+                        noise * (-t / 0.003).exp()
+                    }
+                };
+                (value * gain) as f32
+            })
+            .collect()
+    };
+
+    [click(1.5, 1.), click(1., 0.5)]
+}
+
+/// Options for constructing a [`MetronomeNode`]
+#[derive(Clone, Debug)]
+pub struct MetronomeOptions {
+    /// tempo, in beats per minute
+    pub tempo: f64,
+    /// time of the first beat, in seconds
+    pub origin: f64,
+    /// accented beats of the bar, the pattern repeats from the first beat
+    pub accents: Vec<bool>,
+    pub sound: ClickSound,
+}
+
+impl Default for MetronomeOptions {
+    fn default() -> Self {
+        Self {
+            tempo: 120.,
+            origin: 0.,
+            accents: vec![true, false, false, false],
+            sound: ClickSound::default(),
+        }
+    }
+}
+
+/// `MetronomeNode` emits a click on each beat
+///
+/// The clicks start exactly on the first sample frame at or after the time of the beat. Beat `n`
+/// falls at `origin + n * 60 / tempo` seconds, in the clock of the context or of the
+/// [`Transport`](crate::transport::Transport) the node is part of: the clicks stay on the beats
+/// of the transport when it is paused. This makes the node a reference for the sample accuracy
+/// of the scheduling, as well as a practice tool.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::AudioNode;
+/// use web_audio_api::node::{ClickSound, MetronomeNode, MetronomeOptions};
+/// use web_audio_api::transport::Transport;
+///
+/// let context = AudioContext::default();
+///
+/// // 3/4 at 90 BPM
+/// let options = MetronomeOptions {
+///     tempo: 90.,
+///     accents: vec![true, false, false],
+///     sound: ClickSound::Woodblock,
+///     ..Default::default()
+/// };
+/// let metronome = MetronomeNode::new(&context, options);
+/// metronome.connect(&context.destination());
+///
+/// let mut transport = Transport::new(&context);
+/// transport.add(&metronome);
+/// ```
+pub struct MetronomeNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    tempo: f64,
+    accents: Vec<bool>,
+    sound: ClickSound,
+}
+
+impl AudioNode for MetronomeNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        0
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+fn assert_valid_tempo(tempo: f64) {
+    assert!(
+        tempo > 0. && tempo.is_finite(),
+        "RangeError - invalid tempo {:?}",
+        tempo
+    );
+}
+
+fn assert_valid_accents(accents: &[bool]) {
+    assert!(
+        !accents.is_empty(),
+        "RangeError - the accent pattern should have at least one beat"
+    );
+}
+
+impl MetronomeNode {
+    /// Create a new `MetronomeNode`
+    ///
+    /// # Panics
+    ///
+    /// Panics if
+    /// - the tempo is not strictly positive and finite
+    /// - the origin is not finite
+    /// - the accent pattern is empty
+    pub fn new<C: BaseAudioContext>(context: &C, options: MetronomeOptions) -> Self {
+        let MetronomeOptions {
+            tempo,
+            origin,
+            accents,
+            sound,
+        } = options;
+
+        assert_valid_tempo(tempo);
+        assert!(
+            origin.is_finite(),
+            "RangeError - origin ({:?}) should be finite",
+            origin
+        );
+        assert_valid_accents(&accents);
+
+        context.register(move |registration| {
+            let sample_rate = context.sample_rate();
+            let clicks = [ClickSound::Beep, ClickSound::Woodblock, ClickSound::Noise]
+                .map(|sound| click_samples(sound, sample_rate));
+
+            let render = MetronomeRenderer {
+                sample_rate,
+                next_frame: 0,
+                beat_frames: beat_frames(tempo, sample_rate),
+                anchor_frame: origin * f64::from(sample_rate),
+                anchor_beat: 0,
+                accents: accents.clone(),
+                sound,
+                clicks,
+                playing: None,
+            };
+
+            let node = MetronomeNode {
+                registration,
+                channel_config: ChannelConfigOptions::default().into(),
+                tempo,
+                accents,
+                sound,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// Tempo, in beats per minute
+    #[must_use]
+    pub fn tempo(&self) -> f64 {
+        self.tempo
+    }
+
+    /// Change the tempo from the next beat on
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tempo is not strictly positive and finite
+    pub fn set_tempo(&mut self, tempo: f64) {
+        assert_valid_tempo(tempo);
+        self.tempo = tempo;
+        self.registration.post_message(tempo);
+    }
+
+    /// Accented beats of the bar
+    #[must_use]
+    pub fn accents(&self) -> &[bool] {
+        &self.accents
+    }
+
+    /// Change the accented beats of the bar
+    ///
+    /// The pattern is aligned to the first beat, e.g. the next beat is the first one of the bar
+    /// when the number of beats since the origin is a multiple of the pattern length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the accent pattern is empty
+    pub fn set_accents(&mut self, accents: Vec<bool>) {
+        assert_valid_accents(&accents);
+        self.accents.clone_from(&accents);
+        self.registration.post_message(accents);
+    }
+
+    /// Sound of the clicks
+    #[must_use]
+    pub fn sound(&self) -> ClickSound {
+        self.sound
+    }
+
+    pub fn set_sound(&mut self, sound: ClickSound) {
+        self.sound = sound;
+        self.registration.post_message(sound);
+    }
+}
+
+/// Duration of a beat, in sample frames
+fn beat_frames(tempo: f64, sample_rate: f32) -> f64 {
+    60. / tempo * f64::from(sample_rate)
+}
+
+struct MetronomeRenderer {
+    sample_rate: f32,
+    /// first sample frame of the next render quantum
+    next_frame: u64,
+    /// duration of a beat, in sample frames
+    beat_frames: f64,
+    /// position of the beat `anchor_beat`, in sample frames, the other beats are evenly spaced
+    /// from there
+    anchor_frame: f64,
+    anchor_beat: u64,
+    accents: Vec<bool>,
+    sound: ClickSound,
+    /// samples of the accented and normal clicks, for each sound
+    clicks: [[Vec<f32>; 2]; 3],
+    /// samples of the click being played, and the position in the click
+    playing: Option<(usize, usize)>,
+}
+
+impl MetronomeRenderer {
+    /// Position of the beat, in sample frames
+    fn beat_position(&self, beat: u64) -> f64 {
+        self.anchor_frame + (beat - self.anchor_beat) as f64 * self.beat_frames
+    }
+
+    /// First sample frame of the beat
+    fn beat_frame(&self, beat: u64) -> u64 {
+        // tolerate rounding errors of beats falling on a sample frame
+        (self.beat_position(beat) - 1e-6).max(0.).ceil() as u64
+    }
+
+    /// Index of the first beat starting at or after the given frame
+    fn next_beat(&self, frame: u64) -> u64 {
+        let beats = (frame as f64 - self.anchor_frame) / self.beat_frames;
+        let mut beat = self.anchor_beat + (beats - 1.).max(0.).floor() as u64;
+        while self.beat_frame(beat) < frame {
+            beat += 1;
+        }
+        beat
+    }
+
+    fn click(&self, index: usize) -> &[f32] {
+        &self.clicks[self.sound as usize][index]
+    }
+}
+
+impl AudioProcessor for MetronomeRenderer {
+    fn process(
+        &mut self,
+        _inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        scope: &RenderScope,
+    ) -> bool {
+        let output = &mut outputs[0];
+        let start = scope.current_frame;
+        let end = start + RENDER_QUANTUM_SIZE as u64;
+        self.next_frame = end;
+
+        let mut beat = self.next_beat(start);
+        if self.playing.is_none() && self.beat_frame(beat) >= end {
+            output.make_silent();
+            return false;
+        }
+
+        output.force_mono();
+        let channel = output.channel_data_mut(0);
+        channel.fill(0.);
+
+        let mut frame = start;
+        while frame < end {
+            // play the current click until the next beat
+            let beat_frame = self.beat_frame(beat).max(frame);
+            let until = beat_frame.min(end);
+            if let Some((index, position)) = self.playing {
+                let click = self.click(index);
+                let count = ((until - frame) as usize).min(click.len() - position);
+                let offset = (frame - start) as usize;
+                channel[offset..offset + count].copy_from_slice(&click[position..position + count]);
+                self.playing =
+                    (position + count < click.len()).then_some((index, position + count));
+            }
+            frame = until;
+
+            if beat_frame < end {
+                let accent = self.accents[(beat % self.accents.len() as u64) as usize];
+                self.playing = Some((if accent { 0 } else { 1 }, 0));
+                beat += 1;
+            }
+        }
+
+        false
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if let Some(&mut tempo) = msg.downcast_mut::<f64>() {
+            // the next beat keeps its time, the following ones are spaced at the new tempo
+            let beat = self.next_beat(self.next_frame);
+            self.anchor_frame = self.beat_position(beat);
+            self.anchor_beat = beat;
+            self.beat_frames = beat_frames(tempo, self.sample_rate);
+            return;
+        }
+
+        if let Some(accents) = msg.downcast_mut::<Vec<bool>>() {
+            // the previous pattern is dropped by the garbage collector
+            std::mem::swap(&mut self.accents, accents);
+            return;
+        }
+
+        if let Some(&mut sound) = msg.downcast_mut::<ClickSound>() {
+            self.sound = sound;
+            return;
+        }
+
+        log::warn!("MetronomeRenderer: Dropping incoming message {msg:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::OfflineAudioContext;
+    use crate::transport::Transport;
+
+    use float_eq::assert_float_eq;
+
+    /// First sample frames of the clicks, preceded by silence
+    fn onsets(output: &[f32]) -> Vec<usize> {
+        let mut silent = 0;
+        let mut onsets = vec![];
+        output.iter().enumerate().for_each(|(i, v)| {
+            if *v == 0. {
+                silent += 1;
+            } else {
+                if i == 0 || silent > 100 {
+                    onsets.push(i);
+                }
+                silent = 0;
+            }
+        });
+        onsets
+    }
+
+    fn render(options: MetronomeOptions, length: usize) -> Vec<f32> {
+        let context = OfflineAudioContext::new(1, length, 48_000.);
+        let metronome = MetronomeNode::new(&context, options);
+        metronome.connect(&context.destination());
+        context.start_rendering_sync().get_channel_data(0).to_vec()
+    }
+
+    #[test]
+    fn test_beats() {
+        for sound in [ClickSound::Beep, ClickSound::Woodblock, ClickSound::Noise] {
+            let options = MetronomeOptions {
+                sound,
+                ..MetronomeOptions::default()
+            };
+            let output = render(options, 48_000 * 2);
+            // 120 BPM
+            assert_eq!(onsets(&output), [0, 24_000, 48_000, 72_000]);
+        }
+
+        // beats between sample frames start on the next frame, without drift
+        let options = MetronomeOptions {
+            tempo: 100.,
+            origin: 0.01,
+            ..MetronomeOptions::default()
+        };
+        let output = render(options, 48_000 * 2);
+        assert_eq!(onsets(&output), [480, 29_280, 58_080, 86_880]);
+    }
+
+    #[test]
+    fn test_accents() {
+        let options = MetronomeOptions {
+            accents: vec![true, false],
+            ..MetronomeOptions::default()
+        };
+        let output = render(options, 48_000 * 2);
+        assert_float_eq!(output[0], 1., abs <= 0.);
+        assert_float_eq!(output[24_000], 0.5, abs <= 0.);
+        assert_float_eq!(output[48_000], 1., abs <= 0.);
+        assert_float_eq!(output[72_000], 0.5, abs <= 0.);
+    }
+
+    #[test]
+    fn test_set_tempo() {
+        let context = OfflineAudioContext::new(1, 48_000, 48_000.);
+        let mut metronome = MetronomeNode::new(&context, MetronomeOptions::default());
+        metronome.connect(&context.destination());
+        metronome.set_tempo(240.);
+        assert_float_eq!(metronome.tempo(), 240., abs <= 0.);
+        metronome.set_accents(vec![false]);
+        assert_eq!(metronome.accents(), [false]);
+        metronome.set_sound(ClickSound::Noise);
+        assert_eq!(metronome.sound(), ClickSound::Noise);
+
+        let output = context.start_rendering_sync().get_channel_data(0).to_vec();
+        assert_eq!(onsets(&output), [0, 12_000, 24_000, 36_000]);
+        let noise = click_samples(ClickSound::Noise, 48_000.);
+        assert_float_eq!(output[12_000..12_100], noise[1][..100], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_transport() {
+        let render_transport = |paused: bool| {
+            let context = OfflineAudioContext::new(1, 48_000, 48_000.);
+            let options = MetronomeOptions {
+                origin: 0.25,
+                ..MetronomeOptions::default()
+            };
+            let metronome = MetronomeNode::new(&context, options);
+            metronome.connect(&context.destination());
+
+            let mut transport = Transport::new(&context);
+            transport.add(&metronome);
+            if paused {
+                transport.pause();
+            }
+
+            context.start_rendering_sync().get_channel_data(0).to_vec()
+        };
+
+        // the beats are on the clock of the transport
+        let output = render_transport(false);
+        assert_eq!(onsets(&output), [12_000, 36_000]);
+
+        // and are postponed while it is paused
+        let output = render_transport(true);
+        assert!(output.iter().all(|v| *v == 0.));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_empty_accents() {
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+        let options = MetronomeOptions {
+            accents: vec![],
+            ..MetronomeOptions::default()
+        };
+        let _ = MetronomeNode::new(&context, options);
+    }
+}
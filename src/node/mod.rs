@@ -3,16 +3,30 @@ use std::f32::consts::PI;
 use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 use std::sync::{Arc, OnceLock};
 
-use crate::context::{AudioContextRegistration, ConcreteBaseAudioContext};
+use crate::buffer::AudioBuffer;
+use crate::context::{AudioContextRegistration, BaseAudioContext, ConcreteBaseAudioContext};
 use crate::events::{ErrorEvent, EventHandler, EventPayload, EventType};
+use crate::lookback::LookbackBuffer;
 use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
 use crate::AudioBufferIter;
 use crate::Event;
 
+#[cfg(feature = "ambisonics")]
+mod ambisonics;
+#[cfg(feature = "ambisonics")]
+pub use ambisonics::*;
+mod agc;
+pub use agc::*;
 mod analyser;
 pub use analyser::*;
+mod analysis_tap;
+pub use analysis_tap::*;
+mod arithmetic;
+pub use arithmetic::*;
 mod audio_buffer_source;
 pub use audio_buffer_source::*;
+mod beat_detector;
+pub use beat_detector::*;
 mod biquad_filter;
 pub use biquad_filter::*;
 mod channel_merger;
@@ -21,12 +35,15 @@ mod channel_splitter;
 pub use channel_splitter::*;
 mod constant_source;
 pub use constant_source::*;
+mod control_signal;
+pub use control_signal::*;
 mod convolver;
 pub use convolver::*;
 mod delay;
 pub use delay::*;
 mod destination;
 pub use destination::*;
+mod dry_wet;
 mod dynamics_compressor;
 pub use dynamics_compressor::*;
 mod gain;
@@ -41,14 +58,32 @@ mod media_stream_source;
 pub use media_stream_source::*;
 mod media_stream_track_source;
 pub use media_stream_track_source::*;
+mod metronome;
+pub use metronome::*;
+mod modulation;
+pub use modulation::*;
 mod oscillator;
 pub use oscillator::*;
 mod panner;
 pub use panner::*;
+mod param_expression;
+pub use param_expression::*;
+mod pitch_detector;
+pub use pitch_detector::*;
+mod rhythm;
+pub use rhythm::*;
 mod stereo_panner;
 pub use stereo_panner::*;
+mod sync_group;
+pub use sync_group::*;
+mod tremolo;
+pub use tremolo::*;
+mod vbap;
+pub use vbap::*;
 mod waveshaper;
 pub use waveshaper::*;
+mod weighting;
+pub use weighting::*;
 
 pub(crate) const TABLE_LENGTH_USIZE: usize = 8192;
 pub(crate) const TABLE_LENGTH_BY_4_USIZE: usize = TABLE_LENGTH_USIZE / 4;
@@ -234,6 +269,15 @@ pub trait AudioNode {
 
     fn channel_config(&self) -> &ChannelConfig;
 
+    /// Registration of the processor rendering the outputs of this node
+    ///
+    /// This only differs from [`Self::registration`] for nodes that are rendered by several
+    /// processors, e.g. the `DelayNode` which is split in a writer and a reader.
+    #[doc(hidden)]
+    fn output_registration(&self) -> &AudioContextRegistration {
+        self.registration()
+    }
+
     /// The [`BaseAudioContext`](crate::context::BaseAudioContext) concrete type which owns this
     /// AudioNode.
     fn context(&self) -> &ConcreteBaseAudioContext {
@@ -361,6 +405,43 @@ pub trait AudioNode {
         self.context()
             .clear_event_handler(EventType::ProcessorError(self.registration().id()));
     }
+
+    /// Continuously record the last `duration` seconds of the (first) output of this node, to
+    /// be retrieved with [`dump_lookback`](AudioNode::dump_lookback)
+    ///
+    /// `channel_count` channels are recorded. Calling this method again discards the current
+    /// recording.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic when
+    /// - the node has no outputs
+    /// - the duration is not strictly positive and finite
+    fn enable_lookback(&self, duration: f64) {
+        if self.number_of_outputs() == 0 {
+            panic!("InvalidStateError: cannot record the output of a node without outputs");
+        }
+
+        let buffer =
+            LookbackBuffer::new(duration, self.channel_count(), self.context().sample_rate());
+        self.context()
+            .set_lookback(self.registration().id(), Some(buffer));
+    }
+
+    /// Stop recording the output of this node and discard the recording
+    fn disable_lookback(&self) {
+        self.context().set_lookback(self.registration().id(), None);
+    }
+
+    /// Copy of the recorded output of this node, oldest frame first
+    ///
+    /// Returns `None` when the lookback is not enabled. The buffer is shorter than the lookback
+    /// duration until this duration has been rendered.
+    fn dump_lookback(&self) -> Option<AudioBuffer> {
+        self.context()
+            .lookback(self.registration().id())
+            .map(|buffer| buffer.dump())
+    }
 }
 
 /// Interface of source nodes, controlling start and stop times.
@@ -0,0 +1,685 @@
+//! Modulation effects: chorus, flanger and phaser
+//!
+//! The effects share the same controls: the `rate` and `depth` of their low frequency
+//! oscillator (LFO), the `feedback` of the wet signal into the effect, and the `mix` between the
+//! dry and the wet signal. The LFO of each channel is a quarter period ahead of the previous
+//! channel, which widens the stereo image.
+use std::f32::consts::PI;
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::{AudioNode, ChannelConfig, ChannelConfigOptions};
+
+/// Delay of the chorus at the bottom of the LFO sweep, in seconds
+const CHORUS_MIN_DELAY: f32 = 0.015;
+/// Range of the chorus delay sweep at full depth, in seconds
+const CHORUS_SWEEP: f32 = 0.01;
+/// Delay of the flanger at the bottom of the LFO sweep, in seconds
+const FLANGER_MIN_DELAY: f32 = 0.0005;
+/// Range of the flanger delay sweep at full depth, in seconds
+const FLANGER_SWEEP: f32 = 0.005;
+/// Break frequency of the phaser allpass filters at the center of the LFO sweep, in Hertz
+const PHASER_CENTER_FREQUENCY: f32 = 700.;
+/// Range of the phaser sweep at full depth, in octaves on both sides of the center
+const PHASER_SWEEP_OCTAVES: f32 = 2.;
+/// Maximum number of allpass stages of the phaser
+pub const MAX_PHASER_STAGES: usize = 12;
+
+/// Output amplitude under which the tail of the effect is considered finished
+const TAIL_THRESHOLD: f32 = 1e-6;
+
+/// Circular delay line, read with linear interpolation
+struct DelayLine {
+    buffer: Vec<f32>,
+    /// capacity - 1, the capacity is a power of two
+    mask: usize,
+    write_index: usize,
+}
+
+impl DelayLine {
+    fn new(max_delay_frames: usize) -> Self {
+        let capacity = (max_delay_frames + 2).next_power_of_two();
+        Self {
+            buffer: vec![0.; capacity],
+            mask: capacity - 1,
+            write_index: 0,
+        }
+    }
+
+    /// Sample written `delay` frames before the next write, `delay` should be at least 1
+    fn read(&self, delay: f32) -> f32 {
+        let whole = delay.floor();
+        let frac = delay - whole;
+        let index = (self.write_index + 1).wrapping_sub(whole as usize) & self.mask;
+        let previous = index.wrapping_sub(1) & self.mask;
+        self.buffer[index] * (1. - frac) + self.buffer[previous] * frac
+    }
+
+    fn write(&mut self, value: f32) {
+        self.write_index = (self.write_index + 1) & self.mask;
+        self.buffer[self.write_index] = value;
+    }
+}
+
+/// First order allpass filter
+#[derive(Clone, Copy, Default)]
+struct Allpass {
+    x1: f32,
+    y1: f32,
+}
+
+impl Allpass {
+    /// Coefficient of the filter with a 90 degrees phase shift at the given frequency
+    fn coefficient(frequency: f32, sample_rate: f32) -> f32 {
+        let t = (PI * frequency / sample_rate).tan();
+        (t - 1.) / (t + 1.)
+    }
+
+    fn process(&mut self, x: f32, a: f32) -> f32 {
+        let y = a * x + self.x1 - a * self.y1;
+        self.x1 = x;
+        self.y1 = y;
+        y
+    }
+}
+
+/// The effect applied by a [`ModulationRenderer`]
+#[derive(Clone, Copy)]
+enum Effect {
+    Chorus,
+    Flanger,
+    Phaser { stages: usize },
+}
+
+impl Effect {
+    /// Longest delay of the effect, in seconds
+    fn max_delay(self) -> f32 {
+        match self {
+            Self::Chorus => CHORUS_MIN_DELAY + CHORUS_SWEEP,
+            Self::Flanger => FLANGER_MIN_DELAY + FLANGER_SWEEP,
+            Self::Phaser { .. } => 0.,
+        }
+    }
+}
+
+/// Params shared by the modulation effects
+struct ModulationParams {
+    rate: AudioParam,
+    depth: AudioParam,
+    feedback: AudioParam,
+    mix: AudioParam,
+}
+
+fn create_params<C: BaseAudioContext>(
+    context: &C,
+    registration: &AudioContextRegistration,
+    [rate, depth, feedback, mix]: [f32; 4],
+) -> (ModulationParams, [AudioParamId; 4]) {
+    let create = |min_value, max_value, value| {
+        let descriptor = AudioParamDescriptor {
+            min_value,
+            max_value,
+            default_value: value,
+            automation_rate: AutomationRate::A,
+        };
+        let (param, proc) = context.create_audio_param(descriptor, registration);
+        param.set_value(value);
+        (param, proc)
+    };
+
+    let (rate, rate_id) = create(0., 100., rate);
+    let (depth, depth_id) = create(0., 1., depth);
+    let (feedback, feedback_id) = create(-0.99, 0.99, feedback);
+    let (mix, mix_id) = create(0., 1., mix);
+
+    let params = ModulationParams {
+        rate,
+        depth,
+        feedback,
+        mix,
+    };
+    (params, [rate_id, depth_id, feedback_id, mix_id])
+}
+
+fn register<C: BaseAudioContext, N: AudioNode>(
+    context: &C,
+    effect: Effect,
+    values: [f32; 4],
+    channel_config: ChannelConfigOptions,
+    node: impl FnOnce(AudioContextRegistration, ChannelConfig, ModulationParams) -> N,
+) -> N {
+    context.register(move |registration| {
+        let (params, [rate, depth, feedback, mix]) = create_params(context, &registration, values);
+
+        let max_delay_frames = (effect.max_delay() * context.sample_rate()).ceil() as usize;
+        let render = ModulationRenderer {
+            effect,
+            rate,
+            depth,
+            feedback,
+            mix,
+            phase: 0.,
+            max_delay_frames,
+            channels: vec![],
+            silent_frames: 0,
+        };
+
+        let node = node(registration, channel_config.into(), params);
+        (node, Box::new(render))
+    })
+}
+
+macro_rules! modulation_node {
+    ($node:ident) => {
+        impl AudioNode for $node {
+            fn registration(&self) -> &AudioContextRegistration {
+                &self.registration
+            }
+
+            fn channel_config(&self) -> &ChannelConfig {
+                &self.channel_config
+            }
+
+            fn number_of_inputs(&self) -> usize {
+                1
+            }
+
+            fn number_of_outputs(&self) -> usize {
+                1
+            }
+        }
+
+        impl $node {
+            /// Frequency of the LFO, in Hertz
+            pub fn rate(&self) -> &AudioParam {
+                &self.params.rate
+            }
+
+            /// Amount of modulation, between 0 and 1
+            pub fn depth(&self) -> &AudioParam {
+                &self.params.depth
+            }
+
+            /// Amount of the wet signal fed back into the effect, between -0.99 and 0.99
+            pub fn feedback(&self) -> &AudioParam {
+                &self.params.feedback
+            }
+
+            /// Balance between the dry (0) and the wet (1) signal
+            pub fn mix(&self) -> &AudioParam {
+                &self.params.mix
+            }
+        }
+    };
+}
+
+/// Options for constructing a [`ChorusNode`]
+#[derive(Clone, Debug)]
+pub struct ChorusOptions {
+    pub rate: f32,
+    pub depth: f32,
+    pub feedback: f32,
+    pub mix: f32,
+    pub channel_config: ChannelConfigOptions,
+}
+
+impl Default for ChorusOptions {
+    fn default() -> Self {
+        Self {
+            rate: 1.5,
+            depth: 0.5,
+            feedback: 0.,
+            mix: 0.5,
+            channel_config: ChannelConfigOptions::default(),
+        }
+    }
+}
+
+/// `ChorusNode` thickens its input by mixing it with a slowly modulated delayed copy
+///
+/// The delay sweeps between 15ms and 15ms + 10ms times the depth.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode, ChorusNode, ChorusOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let chorus = ChorusNode::new(&context, ChorusOptions::default());
+/// chorus.connect(&context.destination());
+/// chorus.depth().set_value(0.8);
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&chorus);
+/// osc.start();
+/// ```
+pub struct ChorusNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    params: ModulationParams,
+}
+
+modulation_node!(ChorusNode);
+
+impl ChorusNode {
+    pub fn new<C: BaseAudioContext>(context: &C, options: ChorusOptions) -> Self {
+        let values = [options.rate, options.depth, options.feedback, options.mix];
+        register(
+            context,
+            Effect::Chorus,
+            values,
+            options.channel_config,
+            |registration, channel_config, params| Self {
+                registration,
+                channel_config,
+                params,
+            },
+        )
+    }
+}
+
+/// Options for constructing a [`FlangerNode`]
+#[derive(Clone, Debug)]
+pub struct FlangerOptions {
+    pub rate: f32,
+    pub depth: f32,
+    pub feedback: f32,
+    pub mix: f32,
+    pub channel_config: ChannelConfigOptions,
+}
+
+impl Default for FlangerOptions {
+    fn default() -> Self {
+        Self {
+            rate: 0.25,
+            depth: 0.7,
+            feedback: 0.5,
+            mix: 0.5,
+            channel_config: ChannelConfigOptions::default(),
+        }
+    }
+}
+
+/// `FlangerNode` sweeps a comb filter over its input by mixing it with a short modulated
+/// delayed copy
+///
+/// The delay sweeps between 0.5ms and 0.5ms + 5ms times the depth, a negative feedback moves
+/// the peaks of the comb filter by half an octave.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode, FlangerNode, FlangerOptions};
+/// use web_audio_api::node::OscillatorType;
+///
+/// let context = AudioContext::default();
+///
+/// let flanger = FlangerNode::new(&context, FlangerOptions::default());
+/// flanger.connect(&context.destination());
+/// flanger.feedback().set_value(0.8);
+///
+/// let mut osc = context.create_oscillator();
+/// osc.set_type(OscillatorType::Sawtooth);
+/// osc.connect(&flanger);
+/// osc.start();
+/// ```
+pub struct FlangerNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    params: ModulationParams,
+}
+
+modulation_node!(FlangerNode);
+
+impl FlangerNode {
+    pub fn new<C: BaseAudioContext>(context: &C, options: FlangerOptions) -> Self {
+        let values = [options.rate, options.depth, options.feedback, options.mix];
+        register(
+            context,
+            Effect::Flanger,
+            values,
+            options.channel_config,
+            |registration, channel_config, params| Self {
+                registration,
+                channel_config,
+                params,
+            },
+        )
+    }
+}
+
+/// Options for constructing a [`PhaserNode`]
+#[derive(Clone, Debug)]
+pub struct PhaserOptions {
+    pub rate: f32,
+    pub depth: f32,
+    pub feedback: f32,
+    pub mix: f32,
+    /// number of first order allpass filters, each pair adds a notch
+    pub stages: usize,
+    pub channel_config: ChannelConfigOptions,
+}
+
+impl Default for PhaserOptions {
+    fn default() -> Self {
+        Self {
+            rate: 0.5,
+            depth: 0.7,
+            feedback: 0.3,
+            mix: 0.5,
+            stages: 4,
+            channel_config: ChannelConfigOptions::default(),
+        }
+    }
+}
+
+/// `PhaserNode` sweeps notches over its input by mixing it with a phase shifted copy
+///
+/// The copy goes through a chain of first order allpass filters, their break frequency sweeps
+/// around 700 Hz, up to 2 octaves on both sides at full depth.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode, PhaserNode, PhaserOptions};
+/// use web_audio_api::node::OscillatorType;
+///
+/// let context = AudioContext::default();
+///
+/// let options = PhaserOptions { stages: 8, ..PhaserOptions::default() };
+/// let phaser = PhaserNode::new(&context, options);
+/// phaser.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.set_type(OscillatorType::Square);
+/// osc.connect(&phaser);
+/// osc.start();
+/// ```
+pub struct PhaserNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    params: ModulationParams,
+    stages: usize,
+}
+
+modulation_node!(PhaserNode);
+
+impl PhaserNode {
+    /// Create a new `PhaserNode`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of stages is zero or larger than [`MAX_PHASER_STAGES`]
+    pub fn new<C: BaseAudioContext>(context: &C, options: PhaserOptions) -> Self {
+        let stages = options.stages;
+        assert!(
+            (1..=MAX_PHASER_STAGES).contains(&stages),
+            "RangeError - invalid number of phaser stages {:?}, should be in [1, {:?}]",
+            stages,
+            MAX_PHASER_STAGES
+        );
+
+        let values = [options.rate, options.depth, options.feedback, options.mix];
+        register(
+            context,
+            Effect::Phaser { stages },
+            values,
+            options.channel_config,
+            |registration, channel_config, params| Self {
+                registration,
+                channel_config,
+                params,
+                stages,
+            },
+        )
+    }
+
+    /// Number of allpass filters
+    pub fn stages(&self) -> usize {
+        self.stages
+    }
+}
+
+/// Processing state of a channel
+struct ChannelState {
+    delay_line: DelayLine,
+    allpass: [Allpass; MAX_PHASER_STAGES],
+    /// last wet sample, fed back into the phaser
+    last_wet: f32,
+}
+
+struct ModulationRenderer {
+    effect: Effect,
+    rate: AudioParamId,
+    depth: AudioParamId,
+    feedback: AudioParamId,
+    mix: AudioParamId,
+    /// phase of the LFO of the first channel, in periods
+    phase: f32,
+    max_delay_frames: usize,
+    channels: Vec<ChannelState>,
+    /// number of frames since the input is silent and the output decayed
+    silent_frames: usize,
+}
+
+impl AudioProcessor for ModulationRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &RenderScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        if input.is_silent() {
+            // the delay lines have been flushed and the feedback has decayed
+            if self.silent_frames > self.max_delay_frames || self.channels.is_empty() {
+                output.make_silent();
+                return false;
+            }
+            self.silent_frames += RENDER_QUANTUM_SIZE;
+        } else {
+            self.silent_frames = 0;
+            // if in tail time, we should continue with previous number of channels
+            let max_delay_frames = self.max_delay_frames;
+            self.channels
+                .resize_with(input.number_of_channels(), || ChannelState {
+                    delay_line: DelayLine::new(max_delay_frames),
+                    allpass: [Allpass::default(); MAX_PHASER_STAGES],
+                    last_wet: 0.,
+                });
+        }
+
+        let number_of_channels = self.channels.len();
+        *output = input.clone();
+        output.set_number_of_channels(number_of_channels);
+
+        let rate = params.get(&self.rate);
+        let depth = params.get(&self.depth);
+        let feedback = params.get(&self.feedback);
+        let mix = params.get(&self.mix);
+        let sample_rate = scope.sample_rate;
+        let effect = self.effect;
+
+        let mut peak: f32 = 0.;
+        let initial_phase = self.phase;
+
+        for (c, (channel, state)) in output
+            .channels_mut()
+            .iter_mut()
+            .zip(self.channels.iter_mut())
+            .enumerate()
+        {
+            let mut phase = initial_phase + c as f32 * 0.25;
+
+            let params = rate
+                .iter()
+                .cycle()
+                .zip(depth.iter().cycle())
+                .zip(feedback.iter().cycle().zip(mix.iter().cycle()));
+
+            for (o, ((&rate, &depth), (&feedback, &mix))) in channel.iter_mut().zip(params) {
+                let lfo = (2. * PI * phase).sin();
+                phase = (phase + rate / sample_rate).fract();
+
+                let dry = *o;
+                let wet = match effect {
+                    Effect::Chorus | Effect::Flanger => {
+                        let (min_delay, sweep) = match effect {
+                            Effect::Chorus => (CHORUS_MIN_DELAY, CHORUS_SWEEP),
+                            _ => (FLANGER_MIN_DELAY, FLANGER_SWEEP),
+                        };
+                        let delay = min_delay + depth * sweep * 0.5 * (1. + lfo);
+                        let delayed = state.delay_line.read((delay * sample_rate).max(1.));
+                        state.delay_line.write(dry + feedback * delayed);
+                        delayed
+                    }
+                    Effect::Phaser { stages } => {
+                        let octaves = depth * PHASER_SWEEP_OCTAVES * lfo;
+                        let frequency = PHASER_CENTER_FREQUENCY * octaves.exp2();
+                        let a = Allpass::coefficient(frequency, sample_rate);
+                        let x = dry + feedback * state.last_wet;
+                        let wet = state.allpass[..stages]
+                            .iter_mut()
+                            .fold(x, |x, allpass| allpass.process(x, a));
+                        state.last_wet = wet;
+                        wet
+                    }
+                };
+
+                *o = (1. - mix) * dry + mix * wet;
+                peak = peak.max(o.abs());
+            }
+
+            if c == 0 {
+                self.phase = phase;
+            }
+        }
+
+        // the feedback loop keeps ringing until the output decays
+        if peak > TAIL_THRESHOLD {
+            self.silent_frames = 0;
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+    use crate::AudioBuffer;
+
+    #[test]
+    fn test_chorus_dry() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let options = ChorusOptions {
+            mix: 0.,
+            ..ChorusOptions::default()
+        };
+        let chorus = ChorusNode::new(&context, options);
+        chorus.connect(&context.destination());
+
+        let mut src = context.create_constant_source();
+        src.connect(&chorus);
+        src.start();
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(
+            output.get_channel_data(0),
+            &[1.; RENDER_QUANTUM_SIZE][..],
+            abs_all <= 0.
+        );
+    }
+
+    #[test]
+    fn test_flanger_delay() {
+        let sample_rate = 48_000.;
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, sample_rate);
+        let options = FlangerOptions {
+            depth: 0.,
+            feedback: 0.5,
+            mix: 1.,
+            ..FlangerOptions::default()
+        };
+        let flanger = FlangerNode::new(&context, options);
+        flanger.connect(&context.destination());
+
+        let mut impulse = vec![0.; RENDER_QUANTUM_SIZE];
+        impulse[0] = 1.;
+        let mut src = context.create_buffer_source();
+        src.set_buffer(AudioBuffer::from(vec![impulse], sample_rate));
+        src.connect(&flanger);
+        src.start();
+
+        // 0.5ms delay at 48kHz, with the echoes of the feedback
+        let output = context.start_rendering_sync();
+        let mut expected = vec![0.; RENDER_QUANTUM_SIZE];
+        expected[24] = 1.;
+        expected[48] = 0.5;
+        expected[72] = 0.25;
+        expected[96] = 0.125;
+        expected[120] = 0.0625;
+        assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 1e-5);
+    }
+
+    #[test]
+    fn test_phaser_notch() {
+        let sample_rate = 48_000.;
+        let context = OfflineAudioContext::new(1, 48_000, sample_rate);
+        let options = PhaserOptions {
+            rate: 0.,
+            depth: 0.,
+            feedback: 0.,
+            mix: 0.5,
+            stages: 4,
+            ..PhaserOptions::default()
+        };
+        let phaser = PhaserNode::new(&context, options);
+        phaser.connect(&context.destination());
+
+        // each stage shifts the phase by 45 degrees at the notch
+        let t = (PI * PHASER_CENTER_FREQUENCY / sample_rate).tan() * (PI / 8.).tan();
+        let notch = t.atan() * sample_rate / PI;
+
+        let mut osc = context.create_oscillator();
+        osc.frequency().set_value(notch);
+        osc.connect(&phaser);
+        osc.start();
+
+        let output = context.start_rendering_sync();
+        let tail = &output.get_channel_data(0)[24_000..];
+        assert!(tail.iter().all(|v| v.abs() < 1e-3));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_stages() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let options = PhaserOptions {
+            stages: 0,
+            ..PhaserOptions::default()
+        };
+        let _ = PhaserNode::new(&context, options);
+    }
+}
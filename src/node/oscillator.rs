@@ -160,7 +160,8 @@ impl AudioScheduledSourceNode for OscillatorNode {
     }
 
     fn start_at(&mut self, when: f64) {
-        self.registration.post_message(Schedule::Start(when));
+        self.registration
+            .post_scheduled_message(Schedule::Start(when), when);
     }
 
     fn stop(&mut self) {
@@ -169,7 +170,8 @@ impl AudioScheduledSourceNode for OscillatorNode {
     }
 
     fn stop_at(&mut self, when: f64) {
-        self.registration.post_message(Schedule::Stop(when));
+        self.registration
+            .post_scheduled_message(Schedule::Stop(when), when);
     }
 }
 
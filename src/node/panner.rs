@@ -1,45 +1,186 @@
 use std::any::Any;
 use std::collections::HashMap;
+use std::error::Error;
 use std::f32::consts::PI;
-use std::sync::{Mutex, OnceLock};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
 
 use float_eq::float_eq;
-use hrtf::{HrirSphere, HrtfContext, HrtfProcessor, Vec3};
+use hrtf::{HrirSphere, HrtfContext, HrtfError, HrtfProcessor, Vec3};
 
 use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::memory::{Allocation, MemoryKind};
 use crate::param::{AudioParam, AudioParamDescriptor};
 use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
-use crate::RENDER_QUANTUM_SIZE;
+use crate::{CoordinateSystem, SofaHrtf, RENDER_QUANTUM_SIZE};
 
 use super::{
     AudioNode, ChannelConfig, ChannelConfigOptions, ChannelCountMode, ChannelInterpretation,
 };
 
-/// Load the HRTF processor for the given sample_rate
+/// Typical radius of a human head, in meters
+const DEFAULT_HEAD_RADIUS: f32 = 0.0875;
+/// Distance below which the HRTF panning model applies near-field compensation, in meters
+const DEFAULT_NEAR_FIELD_DISTANCE: f32 = 1.;
+/// Speed of sound in air at 20 degrees Celsius, in meters per second
+const DEFAULT_SPEED_OF_SOUND: f32 = 343.;
+/// Maximum propagation delay of the Doppler effect, in seconds
+const MAX_DOPPLER_DELAY: f32 = 1.;
+/// Coefficient of the air absorption, per meter
+const DEFAULT_AIR_ABSORPTION_COEFFICIENT: f32 = 0.0025;
+/// Cutoff frequency of the air absorption filter of a source at the listener position, in Hertz
+const AIR_ABSORPTION_MAX_CUTOFF: f32 = 20000.;
+/// Attenuation of the direct path of a fully obstructed source, in decibels
+const OBSTRUCTION_ATTENUATION_DB: f32 = 6.;
+/// Cutoff frequency of the low-pass filter of a fully obstructed source, in Hertz
+const OBSTRUCTION_MIN_CUTOFF: f32 = 2000.;
+/// Attenuation of the direct path of a fully occluded source, in decibels
+const OCCLUSION_ATTENUATION_DB: f32 = 18.;
+/// Cutoff frequency of the low-pass filter of a fully occluded source, in Hertz
+const OCCLUSION_MIN_CUTOFF: f32 = 500.;
+/// Number of HRTF interpolation steps per render quantum
+const DEFAULT_HRTF_INTERPOLATION_STEPS: usize = 1;
+/// Length of the impulse responses of the included HRIR sphere, at any sample rate
+const DEFAULT_HRIR_LENGTH: usize = 512;
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+/// HRTF processor, with the length of its impulse responses and the bytes of its HRTF data
+type LoadedHrtf = (HrtfProcessor, usize, usize);
+
+/// Load the included HRIR sphere for the given sample rate
 ///
-/// The included data contains the impulse responses at 44100 Hertz, so it needs to be resampled
-/// for other values (which can easily take 100s of milliseconds). Therefore cache the result (per
-/// sample rate) in a global variable and clone it every time a new panner is created.
-pub(crate) fn load_hrtf_processor(sample_rate: u32) -> (HrtfProcessor, usize) {
-    static INSTANCE: OnceLock<Mutex<HashMap<u32, (HrtfProcessor, usize)>>> = OnceLock::new();
+/// The sphere is embedded in the binary with the `embedded-hrir` feature, which is enabled by
+/// default.
+#[cfg(feature = "embedded-hrir")]
+pub(crate) fn default_hrir_sphere(sample_rate: u32) -> HrirSphere {
+    let resource = include_bytes!("../../resources/IRC_1003_C.bin");
+    HrirSphere::new(&resource[..], sample_rate).unwrap()
+}
+
+/// Load the included HRIR sphere for the given sample rate
+///
+/// The sphere is embedded in the binary with the `embedded-hrir` feature, which is enabled by
+/// default.
+#[cfg(not(feature = "embedded-hrir"))]
+pub(crate) fn default_hrir_sphere(_sample_rate: u32) -> HrirSphere {
+    assert_embedded_hrir();
+    unreachable!()
+}
+
+fn assert_embedded_hrir() {
+    if !cfg!(feature = "embedded-hrir") {
+        panic!("NotSupportedError - the included HRIR sphere requires the `embedded-hrir` feature, use another HRIR source");
+    }
+}
+
+/// Slot of the HRTF processor of the included sphere, for a sample rate and interpolation steps
+fn hrtf_processor_slot(sample_rate: u32, interpolation_steps: usize) -> Arc<OnceLock<LoadedHrtf>> {
+    type HrtfCache = HashMap<(u32, usize), Arc<OnceLock<LoadedHrtf>>>;
+    static INSTANCE: OnceLock<Mutex<HrtfCache>> = OnceLock::new();
     let cache = INSTANCE.get_or_init(|| Mutex::new(HashMap::new()));
     let mut guard = cache.lock().unwrap();
-    guard
-        .entry(sample_rate)
-        .or_insert_with(|| {
-            let resource = include_bytes!("../../resources/IRC_1003_C.bin");
-            let hrir_sphere = HrirSphere::new(&resource[..], sample_rate).unwrap();
-            let len = hrir_sphere.len();
-
-            let interpolation_steps = 1; // TODO?
-            let samples_per_step = RENDER_QUANTUM_SIZE / interpolation_steps;
-            let processor = HrtfProcessor::new(hrir_sphere, interpolation_steps, samples_per_step);
-
-            (processor, len)
+    Arc::clone(guard.entry((sample_rate, interpolation_steps)).or_default())
+}
+
+/// Load the HRTF processor for the given sample_rate and interpolation steps
+///
+/// The included data contains the impulse responses at 44100 Hertz, so it needs to be resampled
+/// for other values (which can easily take 100s of milliseconds). Therefore cache the result (per
+/// sample rate and interpolation steps) in a global variable and clone it every time a new panner
+/// is created. A concurrent load of the same processor is awaited.
+///
+/// # Panics
+///
+/// Panics when the included sphere is not embedded, or when the interpolation steps are not
+/// supported, see [`check_hrtf_interpolation_steps`]
+fn load_hrtf_processor(sample_rate: u32, interpolation_steps: usize) -> LoadedHrtf {
+    hrtf_processor_slot(sample_rate, interpolation_steps)
+        .get_or_init(|| {
+            let hrir_sphere = default_hrir_sphere(sample_rate);
+            hrtf_processor(hrir_sphere, interpolation_steps).unwrap_or_else(|e| panic!("{e}"))
         })
         .clone()
 }
 
+/// The HRTF processor of the included sphere, when it is already loaded
+fn cached_hrtf_processor(sample_rate: u32, interpolation_steps: usize) -> Option<LoadedHrtf> {
+    hrtf_processor_slot(sample_rate, interpolation_steps)
+        .get()
+        .cloned()
+}
+
+/// Load the HRTF processor of a custom HRIR sphere for the given sample rate
+///
+/// The included sphere is loaded from the cache of [`load_hrtf_processor`].
+fn load_hrir_source(
+    source: &HrirSource,
+    sample_rate: u32,
+    interpolation_steps: usize,
+) -> Result<LoadedHrtf> {
+    let bytes = match source {
+        HrirSource::Default => {
+            check_hrtf_interpolation_steps(DEFAULT_HRIR_LENGTH, interpolation_steps)?;
+            return Ok(load_hrtf_processor(sample_rate, interpolation_steps));
+        }
+        HrirSource::Path(path) => std::fs::read(path)?,
+        HrirSource::Bytes(bytes) => bytes.clone(),
+        HrirSource::Sofa(hrtf) => {
+            let hrir_sphere = hrtf.hrir_sphere(sample_rate);
+            return hrtf_processor(hrir_sphere, interpolation_steps);
+        }
+    };
+
+    if !bytes.starts_with(b"HRIR") {
+        return Err("InvalidData - HRIR sphere: missing HRIR header".into());
+    }
+    let hrir_sphere = HrirSphere::new(&bytes[..], sample_rate).map_err(|e| match e {
+        HrtfError::IoError(e) => format!("InvalidData - HRIR sphere: {e}"),
+        HrtfError::InvalidFileFormat => "InvalidData - HRIR sphere: invalid format".to_string(),
+        HrtfError::InvalidLength(_) => {
+            "InvalidData - HRIR sphere: empty impulse responses".to_string()
+        }
+    })?;
+
+    hrtf_processor(hrir_sphere, interpolation_steps)
+}
+
+/// Create the HRTF processor (and its impulse response length and size) for the given HRIR sphere
+///
+/// The render quantum is convolved in `interpolation_steps` blocks, the impulse responses are
+/// interpolated between the previous and the new direction of the source from block to block.
+///
+/// The interpolation steps are checked with [`check_hrtf_interpolation_steps`].
+fn hrtf_processor(hrir_sphere: HrirSphere, interpolation_steps: usize) -> Result<LoadedHrtf> {
+    let len = hrir_sphere.len();
+    check_hrtf_interpolation_steps(len, interpolation_steps)?;
+    let samples_per_step = RENDER_QUANTUM_SIZE / interpolation_steps;
+    // the processor stores the spectrum of both ears for each point, zero padded to the
+    // convolution length
+    let pad_len = samples_per_step + len - 1;
+    let bytes = hrir_sphere.points().len() * 2 * pad_len * std::mem::size_of::<[f32; 2]>();
+
+    let processor = HrtfProcessor::new(hrir_sphere, interpolation_steps, samples_per_step);
+
+    Ok((processor, len, bytes))
+}
+
+/// Check that impulse responses of the given length support the interpolation steps
+///
+/// The processor only provides scratch space of the convolution length to its FFT, which is
+/// not enough for some lengths. These interpolation steps are rejected.
+fn check_hrtf_interpolation_steps(len: usize, interpolation_steps: usize) -> Result<()> {
+    let pad_len = RENDER_QUANTUM_SIZE / interpolation_steps + len - 1;
+    let fft = rustfft::FftPlanner::<f32>::new().plan_fft_forward(pad_len);
+    if fft.get_inplace_scratch_len() > pad_len {
+        return Err(format!(
+            "NotSupportedError - {interpolation_steps} HRTF interpolation steps are not supported for impulse responses of {len} samples"
+        )
+        .into());
+    }
+    Ok(())
+}
+
 /// Spatialization algorithm used to position the audio in 3D space
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
 pub enum PanningModelType {
@@ -58,6 +199,25 @@ impl From<u8> for PanningModelType {
     }
 }
 
+/// Head-related impulse responses of the HRTF panning model of a [`PannerNode`]
+///
+/// This is an extension to the spec. Custom spheres use the binary format of the `hrtf` crate,
+/// like the included IRCAM dataset (`resources/IRC_1003_C.bin`), or are loaded from a SOFA file
+/// with [`SofaHrtf`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum HrirSource {
+    /// The included IRCAM dataset (subject 1003), embedded with the `embedded-hrir` feature
+    /// (enabled by default)
+    #[default]
+    Default,
+    /// Path of an HRIR sphere file
+    Path(PathBuf),
+    /// Contents of an HRIR sphere file
+    Bytes(Vec<u8>),
+    /// Impulse responses of a SOFA file
+    Sofa(SofaHrtf),
+}
+
 /// Algorithm to reduce the volume of an audio source as it moves away from the listener
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
 pub enum DistanceModelType {
@@ -67,6 +227,182 @@ pub enum DistanceModelType {
     Exponential,
 }
 
+/// Custom distance attenuation curve of a [`PannerNode`]
+///
+/// This is an extension to the spec, replacing the [`DistanceModelType`] of the panner when set
+/// with [`PannerNode::set_distance_curve`]. The curve maps the distance between the source and the
+/// listener to a gain. It is defined by a set of `(distance, gain)` points: below the first point
+/// the gain of the first point is used, beyond the last point the gain of the last point.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::DistanceCurve;
+///
+/// let context = AudioContext::default();
+/// let mut panner = context.create_panner();
+///
+/// // full volume up to 2 meters, then fade out until 40 meters
+/// let curve = DistanceCurve::monotonic_spline(&[(2., 1.), (10., 0.4), (40., 0.)]);
+/// panner.set_distance_curve(Some(curve));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceCurve {
+    distances: Vec<f32>,
+    gains: Vec<f32>,
+    /// slopes at the points for cubic interpolation, empty for linear interpolation
+    tangents: Vec<f32>,
+}
+
+impl DistanceCurve {
+    /// Create a curve that linearly interpolates between the given `(distance, gain)` points
+    ///
+    /// # Panics
+    ///
+    /// This function panics if:
+    /// - no points are given
+    /// - the distances are not strictly increasing
+    /// - a distance or gain is not finite
+    pub fn lookup_table(points: &[(f32, f32)]) -> Self {
+        assert!(
+            !points.is_empty(),
+            "InvalidStateError - distance curve requires at least one point"
+        );
+        assert!(
+            points
+                .iter()
+                .all(|(distance, gain)| distance.is_finite() && gain.is_finite()),
+            "TypeError - distance curve points must be finite"
+        );
+        assert!(
+            points.windows(2).all(|w| w[0].0 < w[1].0),
+            "InvalidStateError - distance curve distances must be strictly increasing"
+        );
+
+        Self {
+            distances: points.iter().map(|p| p.0).collect(),
+            gains: points.iter().map(|p| p.1).collect(),
+            tangents: vec![],
+        }
+    }
+
+    /// Create a curve that smoothly interpolates between the given `(distance, gain)` points
+    ///
+    /// The interpolation is a monotone cubic spline (Fritsch-Carlson), it does not overshoot:
+    /// the curve is monotonic between any two consecutive points.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if:
+    /// - no points are given
+    /// - the distances are not strictly increasing
+    /// - a distance or gain is not finite
+    pub fn monotonic_spline(points: &[(f32, f32)]) -> Self {
+        let mut curve = Self::lookup_table(points);
+        let (x, y) = (&curve.distances, &curve.gains);
+        let n = x.len();
+        if n < 2 {
+            return curve;
+        }
+
+        let secants: Vec<f32> = (0..n - 1)
+            .map(|k| (y[k + 1] - y[k]) / (x[k + 1] - x[k]))
+            .collect();
+
+        let mut tangents = vec![0.; n];
+        tangents[0] = secants[0];
+        tangents[n - 1] = secants[n - 2];
+        for k in 1..n - 1 {
+            tangents[k] = if secants[k - 1] * secants[k] <= 0. {
+                0. // local extremum
+            } else {
+                (secants[k - 1] + secants[k]) / 2.
+            };
+        }
+
+        // limit the tangents to preserve monotonicity
+        for k in 0..n - 1 {
+            if secants[k] == 0. {
+                tangents[k] = 0.;
+                tangents[k + 1] = 0.;
+                continue;
+            }
+            let a = tangents[k] / secants[k];
+            let b = tangents[k + 1] / secants[k];
+            let h = a.hypot(b);
+            if h > 3. {
+                tangents[k] = 3. / h * a * secants[k];
+                tangents[k + 1] = 3. / h * b * secants[k];
+            }
+        }
+
+        curve.tangents = tangents;
+        curve
+    }
+
+    /// Create a curve by sampling a function at `size` equally spaced distances between zero and
+    /// `max_distance`, with linear interpolation
+    ///
+    /// The function is evaluated once, on the calling thread.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `max_distance` is not a positive number, if `size` is less than
+    /// two or if the function returns a value that is not finite.
+    pub fn from_fn<F: FnMut(f32) -> f32>(mut f: F, max_distance: f32, size: usize) -> Self {
+        assert!(
+            max_distance.is_finite() && max_distance > 0.,
+            "RangeError - max distance must be a positive number, received {max_distance}"
+        );
+        assert!(
+            size >= 2,
+            "RangeError - distance curve size must be at least 2, received {size}"
+        );
+
+        let points: Vec<_> = (0..size)
+            .map(|i| {
+                let distance = max_distance * i as f32 / (size - 1) as f32;
+                (distance, f(distance))
+            })
+            .collect();
+        Self::lookup_table(&points)
+    }
+
+    /// Gain of the curve at the given distance
+    pub fn gain(&self, distance: f32) -> f32 {
+        let x = &self.distances;
+        let y = &self.gains;
+        let n = x.len();
+
+        // index of the first point beyond the distance
+        let k = x.partition_point(|&d| d <= distance);
+        if k == 0 {
+            return y[0];
+        }
+        if k == n {
+            return y[n - 1];
+        }
+
+        let (k0, k1) = (k - 1, k);
+        let h = x[k1] - x[k0];
+        let t = (distance - x[k0]) / h;
+
+        if self.tangents.is_empty() {
+            y[k0] + (y[k1] - y[k0]) * t
+        } else {
+            // cubic Hermite basis
+            let t2 = t * t;
+            let t3 = t2 * t;
+            let h00 = 2. * t3 - 3. * t2 + 1.;
+            let h10 = t3 - 2. * t2 + t;
+            let h01 = -2. * t3 + 3. * t2;
+            let h11 = t3 - t2;
+            h00 * y[k0] + h10 * h * self.tangents[k0] + h01 * y[k1] + h11 * h * self.tangents[k1]
+        }
+    }
+}
+
 impl From<u8> for DistanceModelType {
     fn from(i: u8) -> Self {
         match i {
@@ -111,6 +447,28 @@ pub struct PannerOptions {
     pub cone_inner_angle: f64,
     pub cone_outer_angle: f64,
     pub cone_outer_gain: f64,
+    /// impulse responses of the HRTF panning model, this is an extension to the spec
+    pub hrtf_source: HrirSource,
+    /// number of steps per render quantum of the interpolation of the HRTF panning model
+    /// between directions, this is an extension to the spec
+    pub hrtf_interpolation_steps: usize,
+    /// strength of the Doppler effect, 0 disables it, this is an extension to the spec
+    pub doppler_factor: f32,
+    /// speed of sound of the Doppler effect in meters per second, this is an extension to the
+    /// spec
+    pub speed_of_sound: f32,
+    /// low-pass filter the input depending on the distance, this is an extension to the spec
+    pub air_absorption: bool,
+    /// coefficient of the air absorption per meter, this is an extension to the spec
+    pub air_absorption_coefficient: f32,
+    /// amount of occlusion of the source, in the range `[0, 1]`, this is an extension to the spec
+    pub occlusion: f32,
+    /// amount of obstruction of the source, in the range `[0, 1]`, this is an extension to the
+    /// spec
+    pub obstruction: f32,
+    /// account for the elevation of the source in the EqualPower panning model, this is an
+    /// extension to the spec
+    pub equal_power_elevation: bool,
     pub channel_config: ChannelConfigOptions,
 }
 
@@ -131,6 +489,15 @@ impl Default for PannerOptions {
             cone_inner_angle: 360.,
             cone_outer_angle: 360.,
             cone_outer_gain: 0.,
+            hrtf_source: HrirSource::Default,
+            hrtf_interpolation_steps: DEFAULT_HRTF_INTERPOLATION_STEPS,
+            doppler_factor: 0.,
+            speed_of_sound: DEFAULT_SPEED_OF_SOUND,
+            air_absorption: false,
+            air_absorption_coefficient: DEFAULT_AIR_ABSORPTION_COEFFICIENT,
+            occlusion: 0.,
+            obstruction: 0.,
+            equal_power_elevation: false,
             channel_config: ChannelConfigOptions {
                 count: 2,
                 count_mode: ChannelCountMode::ClampedMax,
@@ -144,12 +511,32 @@ enum ControlMessage {
     DistanceModel(DistanceModelType),
     // Box this payload - one large variant can penalize the memory layout of this enum
     PanningModel(Box<Option<HrtfState>>),
+    DistanceCurve(Option<Box<DistanceCurve>>),
     RefDistance(f64),
     MaxDistance(f64),
     RollOffFactor(f64),
     ConeInnerAngle(f64),
     ConeOuterAngle(f64),
     ConeOuterGain(f64),
+    Doppler {
+        factor: f32,
+        speed_of_sound: f32,
+        // allocated when the Doppler effect is enabled
+        line: Option<Box<DopplerLine>>,
+    },
+    AirAbsorption {
+        enabled: bool,
+        coefficient: f32,
+    },
+    Occlusion {
+        occlusion: f32,
+        obstruction: f32,
+    },
+    NearField {
+        head_radius: f32,
+        reference_distance: f32,
+    },
+    EqualPowerElevation(bool),
 }
 
 /// Assert that the channel count is valid for the PannerNode
@@ -182,15 +569,292 @@ fn assert_valid_channel_count_mode(mode: ChannelCountMode) {
     }
 }
 
+/// Assert that the ref distance is valid for the PannerNode
+///
+/// # Panics
+///
+/// This function panics if given value is negative or not finite
+#[track_caller]
+fn assert_valid_ref_distance(value: f64) {
+    assert!(
+        value.is_finite() && value >= 0.,
+        "RangeError - ref distance must be a positive number, received {value}"
+    );
+}
+
+/// Assert that the max distance is valid for the PannerNode
+///
+/// # Panics
+///
+/// This function panics if given value is not strictly positive
+#[track_caller]
+fn assert_valid_max_distance(value: f64) {
+    assert!(
+        value > 0.,
+        "RangeError - max distance must be strictly positive, received {value}"
+    );
+}
+
+/// Assert that the rolloff factor is valid for the PannerNode
+///
+/// # Panics
+///
+/// This function panics if given value is negative or not finite
+#[track_caller]
+fn assert_valid_rolloff_factor(value: f64) {
+    assert!(
+        value.is_finite() && value >= 0.,
+        "RangeError - rolloff factor must be a positive number, received {value}"
+    );
+}
+
+fn assert_valid_hrtf_interpolation_steps(steps: usize) {
+    assert!(
+        steps > 0 && RENDER_QUANTUM_SIZE % steps == 0,
+        "RangeError - HRTF interpolation steps must divide the render quantum size ({RENDER_QUANTUM_SIZE}), received {steps}"
+    );
+}
+
+fn assert_valid_doppler_factor(value: f32) {
+    assert!(
+        value.is_finite() && value >= 0.,
+        "RangeError - Doppler factor must be a positive number, received {value}"
+    );
+}
+
+fn assert_valid_speed_of_sound(value: f32) {
+    assert!(
+        value.is_finite() && value > 0.,
+        "RangeError - speed of sound must be strictly positive, received {value}"
+    );
+}
+
+fn assert_valid_air_absorption_coefficient(value: f32) {
+    assert!(
+        value.is_finite() && value >= 0.,
+        "RangeError - air absorption coefficient must be a positive number, received {value}"
+    );
+}
+
+fn assert_valid_occlusion(name: &str, value: f32) {
+    assert!(
+        (0. ..=1.).contains(&value),
+        "RangeError - {name} must be in the range [0, 1], received {value}"
+    );
+}
+
+/// One-pole low-pass filter per channel of the air absorption
+#[derive(Default)]
+struct AirAbsorptionFilter {
+    state: [f32; 2],
+    /// coefficient of the filter at the end of the previous render quantum
+    prev_alpha: Option<f32>,
+}
+
+impl AirAbsorptionFilter {
+    /// Coefficient of the filter for a source at the given distance
+    ///
+    /// The cutoff frequency decreases exponentially with the distance, from 20 kHz at the
+    /// listener position.
+    fn alpha(distance: f32, coefficient: f32, sample_rate: f32) -> f32 {
+        let cutoff = AIR_ABSORPTION_MAX_CUTOFF * (-coefficient * distance).exp();
+        1. - (-2. * PI * cutoff / sample_rate).exp()
+    }
+
+    fn is_drained(&self) -> bool {
+        self.state == [0.; 2]
+    }
+
+    /// Filter the (mono or stereo) quantum, the coefficient is ramped from the previous one
+    fn process(&mut self, quantum: &mut AudioRenderQuantum, alpha: f32) {
+        let prev_alpha = self.prev_alpha.unwrap_or(alpha);
+        self.prev_alpha = Some(alpha);
+        // render the decay of the filter after the input turns silent
+        if quantum.is_silent() && self.is_drained() {
+            return;
+        }
+
+        quantum
+            .channels_mut()
+            .iter_mut()
+            .zip(&mut self.state)
+            .for_each(|(channel, state)| {
+                channel.iter_mut().enumerate().for_each(|(i, sample)| {
+                    let t = (i + 1) as f32 / RENDER_QUANTUM_SIZE as f32;
+                    let alpha = prev_alpha + (alpha - prev_alpha) * t;
+                    *state += alpha * (*sample - *state);
+                    *sample = *state;
+                });
+                if state.abs() < 1e-9 {
+                    *state = 0.;
+                }
+            });
+    }
+}
+
+/// Low-pass filter and attenuation of the direct path of an occluded or obstructed source
+#[derive(Default)]
+struct OcclusionFilter {
+    lowpass: AirAbsorptionFilter,
+    /// gain at the end of the previous render quantum
+    prev_gain: Option<f32>,
+}
+
+impl OcclusionFilter {
+    /// Coefficient of the low-pass filter and gain for the given amounts
+    ///
+    /// The cutoff frequency decreases exponentially with the amounts, from 20 kHz, and the
+    /// attenuations in decibels add up.
+    fn target(occlusion: f32, obstruction: f32, sample_rate: f32) -> (f32, f32) {
+        let cutoff = AIR_ABSORPTION_MAX_CUTOFF
+            * (OCCLUSION_MIN_CUTOFF / AIR_ABSORPTION_MAX_CUTOFF).powf(occlusion)
+            * (OBSTRUCTION_MIN_CUTOFF / AIR_ABSORPTION_MAX_CUTOFF).powf(obstruction);
+        let alpha = 1. - (-2. * PI * cutoff / sample_rate).exp();
+        let attenuation_db =
+            occlusion * OCCLUSION_ATTENUATION_DB + obstruction * OBSTRUCTION_ATTENUATION_DB;
+        (alpha, crate::db_to_gain(-attenuation_db))
+    }
+
+    /// Whether the ramps to the unblocked direct path are complete, the filter can be bypassed
+    fn is_transparent(&self) -> bool {
+        // the filter and the gain are ramped together
+        self.prev_gain == Some(1.)
+    }
+
+    /// Filter and attenuate the quantum, the filter and the gain are ramped from the previous
+    /// amounts
+    fn process(
+        &mut self,
+        quantum: &mut AudioRenderQuantum,
+        occlusion: f32,
+        obstruction: f32,
+        sample_rate: f32,
+    ) {
+        // a new filter is ramped from the unblocked direct path
+        if self.prev_gain.is_none() {
+            let (alpha, gain) = Self::target(0., 0., sample_rate);
+            self.lowpass.prev_alpha = Some(alpha);
+            self.prev_gain = Some(gain);
+        }
+
+        let (alpha, gain) = Self::target(occlusion, obstruction, sample_rate);
+        self.lowpass.process(quantum, alpha);
+
+        let prev_gain = self.prev_gain.unwrap();
+        self.prev_gain = Some(gain);
+        if quantum.is_silent() {
+            return;
+        }
+        quantum.channels_mut().iter_mut().for_each(|channel| {
+            channel.iter_mut().enumerate().for_each(|(i, sample)| {
+                let t = (i + 1) as f32 / RENDER_QUANTUM_SIZE as f32;
+                *sample *= prev_gain + (gain - prev_gain) * t;
+            });
+        });
+    }
+}
+
+/// Propagation delay of the Doppler effect, a delay line per channel read at fractional
+/// positions
+struct DopplerLine {
+    buffers: [Vec<f32>; 2],
+    write_index: usize,
+    /// delay at the end of the previous render quantum, in sample frames
+    prev_delay: Option<f32>,
+    /// number of frames since the input turned silent
+    silent_frames: usize,
+}
+
+impl DopplerLine {
+    fn length(sample_rate: f32) -> usize {
+        (MAX_DOPPLER_DELAY * sample_rate).ceil() as usize + RENDER_QUANTUM_SIZE + 2
+    }
+
+    fn new(sample_rate: f32) -> Self {
+        let length = Self::length(sample_rate);
+        Self {
+            buffers: [vec![0.; length], vec![0.; length]],
+            write_index: 0,
+            prev_delay: None,
+            silent_frames: 0,
+        }
+    }
+
+    fn bytes(sample_rate: f32) -> usize {
+        2 * Self::length(sample_rate) * std::mem::size_of::<f32>()
+    }
+
+    /// Whether the line only holds silence
+    fn is_drained(&self) -> bool {
+        self.silent_frames >= self.buffers[0].len()
+    }
+
+    /// Delay the quantum by the given delays in sample frames, at most `MAX_DOPPLER_DELAY`
+    fn process(&mut self, quantum: &mut AudioRenderQuantum, delays: &[f32; RENDER_QUANTUM_SIZE]) {
+        let length = self.buffers[0].len();
+        if quantum.is_silent() {
+            self.silent_frames += RENDER_QUANTUM_SIZE;
+        } else {
+            self.silent_frames = 0;
+        }
+
+        // the line of the right channel follows a mono input
+        let number_of_channels = quantum.number_of_channels();
+        for (channel, buffer) in self.buffers.iter_mut().enumerate() {
+            let input = quantum.channel_data(channel.min(number_of_channels - 1));
+            input.iter().enumerate().for_each(|(i, v)| {
+                buffer[(self.write_index + i) % length] = *v;
+            });
+        }
+
+        for (channel, buffer) in self.buffers.iter().enumerate().take(number_of_channels) {
+            quantum
+                .channel_data_mut(channel)
+                .iter_mut()
+                .zip(delays)
+                .enumerate()
+                .for_each(|(i, (o, delay))| {
+                    let whole = delay.floor();
+                    let frac = delay - whole;
+                    let index = (self.write_index + i + length - whole as usize) % length;
+                    let previous = (index + length - 1) % length;
+                    *o = buffer[index] * (1. - frac) + buffer[previous] * frac;
+                });
+        }
+
+        self.write_index = (self.write_index + RENDER_QUANTUM_SIZE) % length;
+    }
+}
+
+/// Convolution of one input channel with the impulse responses of both ears
+struct HrtfConvolution {
+    output_interleaved: Vec<(f32, f32)>,
+    prev_left_samples: Vec<f32>,
+    prev_right_samples: Vec<f32>,
+}
+
+impl HrtfConvolution {
+    fn new() -> Self {
+        Self {
+            output_interleaved: vec![(0., 0.); RENDER_QUANTUM_SIZE],
+            prev_left_samples: vec![],  // will resize accordingly
+            prev_right_samples: vec![], // will resize accordingly
+        }
+    }
+}
+
 /// Internal state of the HRTF renderer
 struct HrtfState {
     len: usize,
     processor: HrtfProcessor,
-    output_interleaved: Vec<(f32, f32)>,
     prev_sample_vector: Vec3,
-    prev_left_samples: Vec<f32>,
-    prev_right_samples: Vec<f32>,
     prev_distance_gain: f32,
+    /// convolution of the mono input, or of the left channel of a stereo input
+    first: HrtfConvolution,
+    /// convolution of the right channel of a stereo input
+    second: HrtfConvolution,
+    /// whether the previous input was stereo
+    stereo: bool,
 }
 
 impl HrtfState {
@@ -198,46 +862,80 @@ impl HrtfState {
         Self {
             len,
             processor,
-            output_interleaved: vec![(0., 0.); RENDER_QUANTUM_SIZE],
             prev_sample_vector: Vec3::new(0., 0., 1.),
-            prev_left_samples: vec![],  // will resize accordingly
-            prev_right_samples: vec![], // will resize accordingly
             prev_distance_gain: 0.,
+            first: HrtfConvolution::new(),
+            second: HrtfConvolution::new(),
+            stereo: false,
         }
     }
 
+    /// Spatialize a mono or stereo input
+    ///
+    /// Like the mono input, each channel of a stereo input is convolved with the impulse
+    /// responses of the source direction, the left ear hears the left channel and the right ear
+    /// the right channel.
     fn process(
         &mut self,
-        source: &[f32],
+        input: &AudioRenderQuantum,
         new_distance_gain: f32,
         projected_source: [f32; 3],
     ) -> &[(f32, f32)] {
-        // reset state of output buffer
-        self.output_interleaved.fill((0., 0.));
-
         let new_sample_vector = Vec3 {
             x: projected_source[0],
             z: projected_source[1],
             y: projected_source[2],
         };
 
-        let context = HrtfContext {
-            source,
-            output: &mut self.output_interleaved,
-            new_sample_vector,
-            prev_sample_vector: self.prev_sample_vector,
-            prev_left_samples: &mut self.prev_left_samples,
-            prev_right_samples: &mut self.prev_right_samples,
-            new_distance_gain,
-            prev_distance_gain: self.prev_distance_gain,
-        };
+        // keep rendering the tail of both channels when a stereo input turns silent
+        let stereo = input.number_of_channels() == 2 || (self.stereo && input.is_silent());
+        if stereo && !self.stereo {
+            // the history of both channels is the previous mono input
+            let Self { first, second, .. } = self;
+            second
+                .prev_left_samples
+                .clone_from(&first.prev_left_samples);
+            second
+                .prev_right_samples
+                .clone_from(&first.prev_right_samples);
+        }
+        self.stereo = stereo;
+
+        let channels = if stereo { 2 } else { 1 };
+        for (channel, convolution) in [&mut self.first, &mut self.second]
+            .into_iter()
+            .enumerate()
+            .take(channels)
+        {
+            // reset state of output buffer
+            convolution.output_interleaved.fill((0., 0.));
+
+            let context = HrtfContext {
+                source: &input.channel_data(channel.min(input.number_of_channels() - 1))[..],
+                output: &mut convolution.output_interleaved,
+                new_sample_vector,
+                prev_sample_vector: self.prev_sample_vector,
+                prev_left_samples: &mut convolution.prev_left_samples,
+                prev_right_samples: &mut convolution.prev_right_samples,
+                new_distance_gain,
+                prev_distance_gain: self.prev_distance_gain,
+            };
+
+            self.processor.process_samples(context);
+        }
 
-        self.processor.process_samples(context);
+        if stereo {
+            self.first
+                .output_interleaved
+                .iter_mut()
+                .zip(&self.second.output_interleaved)
+                .for_each(|(first, second)| first.1 = second.1);
+        }
 
         self.prev_sample_vector = new_sample_vector;
         self.prev_distance_gain = new_distance_gain;
 
-        &self.output_interleaved
+        &self.first.output_interleaved
     }
 
     fn tail_time_samples(&self) -> usize {
@@ -308,6 +1006,24 @@ pub struct PannerNode {
     max_distance: f64,
     rolloff_factor: f64,
     panning_model: PanningModelType,
+    head_radius: f32,
+    near_field_distance: f32,
+    distance_curve: Option<DistanceCurve>,
+    hrtf_source: HrirSource,
+    hrtf_interpolation_steps: usize,
+    /// processor of a custom HRIR sphere, the included sphere is loaded from a global cache
+    custom_hrtf: Option<LoadedHrtf>,
+    /// whether the included sphere, loaded on a background thread, is still awaited
+    pending_hrtf: Option<Arc<Mutex<bool>>>,
+    doppler_factor: f32,
+    /// whether the renderer holds a Doppler delay line, never in strict conformance mode
+    doppler_enabled: bool,
+    speed_of_sound: f32,
+    air_absorption: bool,
+    air_absorption_coefficient: f32,
+    occlusion: f32,
+    obstruction: f32,
+    equal_power_elevation: bool,
 }
 
 impl AudioNode for PannerNode {
@@ -340,6 +1056,15 @@ impl AudioNode for PannerNode {
     }
 }
 
+impl Drop for PannerNode {
+    fn drop(&mut self) {
+        // the node id must not receive the sphere that is loaded in the background
+        if let Some(pending) = self.pending_hrtf.take() {
+            *pending.lock().unwrap() = false;
+        }
+    }
+}
+
 impl PannerNode {
     /// returns a `PannerNode` instance
     ///
@@ -354,10 +1079,55 @@ impl PannerNode {
     ///
     /// * `options.channel_config.count` is greater than 2
     /// * `options.channel_config.mode` is `ChannelCountMode::Max`
-    ///
-    /// Can panic when loading HRIR-sphere
-    #[allow(clippy::missing_panics_doc)]
+    /// * `options.ref_distance` or `options.rolloff_factor` is negative
+    /// * `options.max_distance` is not strictly positive
+    /// * the HRIR sphere of `options.hrtf_source` cannot be loaded, see
+    ///   [`try_new`](Self::try_new)
     pub fn new<C: BaseAudioContext>(context: &C, options: PannerOptions) -> Self {
+        match Self::try_new(context, options) {
+            Ok(node) => node,
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    /// returns a `PannerNode` instance, or the error raised when loading a custom HRIR sphere
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the file of `options.hrtf_source` cannot be read, or does not
+    /// contain a valid HRIR sphere.
+    ///
+    /// # Panics
+    ///
+    /// Will panic on invalid options, see [`new`](Self::new)
+    pub fn try_new<C: BaseAudioContext>(context: &C, options: PannerOptions) -> Result<Self> {
+        assert_valid_ref_distance(options.ref_distance);
+        assert_valid_max_distance(options.max_distance);
+        assert_valid_rolloff_factor(options.rolloff_factor);
+        assert_valid_doppler_factor(options.doppler_factor);
+        assert_valid_speed_of_sound(options.speed_of_sound);
+        assert_valid_air_absorption_coefficient(options.air_absorption_coefficient);
+        assert_valid_occlusion("occlusion", options.occlusion);
+        assert_valid_occlusion("obstruction", options.obstruction);
+        assert_valid_hrtf_interpolation_steps(options.hrtf_interpolation_steps);
+
+        // load the custom sphere before the node is registered, so errors leave no trace
+        let custom_hrtf = match &options.hrtf_source {
+            HrirSource::Default => {
+                // the included sphere is cached, only check the interpolation steps
+                check_hrtf_interpolation_steps(
+                    DEFAULT_HRIR_LENGTH,
+                    options.hrtf_interpolation_steps,
+                )?;
+                None
+            }
+            source => Some(load_hrir_source(
+                source,
+                context.sample_rate() as u32,
+                options.hrtf_interpolation_steps,
+            )?),
+        };
+
         let mut node = context.register(|registration| {
             use crate::spatial::PARAM_OPTS;
 
@@ -375,6 +1145,15 @@ impl PannerNode {
                 cone_inner_angle,
                 cone_outer_angle,
                 cone_outer_gain,
+                hrtf_source,
+                hrtf_interpolation_steps,
+                doppler_factor: _,
+                speed_of_sound,
+                air_absorption,
+                air_absorption_coefficient,
+                occlusion,
+                obstruction,
+                equal_power_elevation,
                 channel_config,
                 panning_model,
             } = options;
@@ -400,6 +1179,14 @@ impl PannerNode {
             param_oy.set_value_at_time(orientation_y, 0.);
             param_oz.set_value_at_time(orientation_z, 0.);
 
+            // the extensions are disabled in strict conformance mode
+            let strict = context.strict_conformance();
+            let (render_occlusion, render_obstruction) = if strict {
+                (0., 0.)
+            } else {
+                (occlusion, obstruction)
+            };
+
             let render = PannerRenderer {
                 position_x: render_px,
                 position_y: render_py,
@@ -415,7 +1202,28 @@ impl PannerNode {
                 cone_outer_angle,
                 cone_outer_gain,
                 hrtf_state: None,
+                previous_hrtf_state: None,
+                rendered: false,
                 tail_time_counter: 0,
+                head_radius: DEFAULT_HEAD_RADIUS,
+                near_field_distance: if strict {
+                    0.
+                } else {
+                    DEFAULT_NEAR_FIELD_DISTANCE
+                },
+                prev_near_field_gains: (1., 1.),
+                prev_equal_power_gains: None,
+                distance_curve: None,
+                doppler: None,
+                doppler_factor: 0.,
+                speed_of_sound,
+                air_absorption: (air_absorption && !strict).then(AirAbsorptionFilter::default),
+                air_absorption_coefficient,
+                occlusion_filter: (render_occlusion > 0. || render_obstruction > 0.)
+                    .then(OcclusionFilter::default),
+                occlusion: render_occlusion,
+                obstruction: render_obstruction,
+                equal_power_elevation: equal_power_elevation && !strict,
             };
 
             let node = PannerNode {
@@ -435,6 +1243,21 @@ impl PannerNode {
                 cone_outer_angle,
                 cone_outer_gain,
                 panning_model,
+                head_radius: DEFAULT_HEAD_RADIUS,
+                near_field_distance: DEFAULT_NEAR_FIELD_DISTANCE,
+                distance_curve: None,
+                hrtf_source,
+                hrtf_interpolation_steps,
+                custom_hrtf,
+                pending_hrtf: None,
+                doppler_factor: 0.,
+                doppler_enabled: false,
+                speed_of_sound,
+                air_absorption,
+                air_absorption_coefficient,
+                occlusion,
+                obstruction,
+                equal_power_elevation,
             };
 
             // instruct to BaseContext to add the AudioListener if it has not already
@@ -450,8 +1273,11 @@ impl PannerNode {
 
         // load the HRTF sphere if requested
         node.set_panning_model(options.panning_model);
+        if options.doppler_factor > 0. {
+            node.set_doppler_factor(options.doppler_factor);
+        }
 
-        node
+        Ok(node)
     }
 
     pub fn position_x(&self) -> &AudioParam {
@@ -466,10 +1292,15 @@ impl PannerNode {
         &self.position_z
     }
 
+    /// Set the position of the source, equivalent to setting the `value` of the position params
+    ///
+    /// The three coordinates are guaranteed to be updated within the same render quantum.
     pub fn set_position(&self, x: f32, y: f32, z: f32) {
-        self.position_x.set_value(x);
-        self.position_y.set_value(y);
-        self.position_z.set_value(z);
+        crate::param::set_values_synchronized(&[
+            (&self.position_x, x),
+            (&self.position_y, y),
+            (&self.position_z, z),
+        ]);
     }
 
     pub fn orientation_x(&self) -> &AudioParam {
@@ -484,12 +1315,47 @@ impl PannerNode {
         &self.orientation_z
     }
 
+    /// Set the orientation of the source, equivalent to setting the `value` of the orientation
+    /// params
+    ///
+    /// The three coordinates are guaranteed to be updated within the same render quantum.
     pub fn set_orientation(&self, x: f32, y: f32, z: f32) {
-        self.orientation_x.set_value(x);
-        self.orientation_y.set_value(y);
-        self.orientation_z.set_value(z);
+        crate::param::set_values_synchronized(&[
+            (&self.orientation_x, x),
+            (&self.orientation_y, y),
+            (&self.orientation_z, z),
+        ]);
+    }
+
+    /// Set the position and orientation of the source from its world transform
+    ///
+    /// The transform is a 4x4 matrix in column-major order, e.g.
+    /// `glam::Mat4::to_cols_array_2d()` or a `mint::ColumnMatrix4<f32>`. The translation is the
+    /// position of the source, its orientation is the [forward](CoordinateSystem::forward) axis
+    /// of the given coordinate system, rotated by the transform.
+    ///
+    /// The six coordinates are guaranteed to be updated within the same render quantum.
+    pub fn set_transform(
+        &self,
+        transform: impl Into<[[f32; 4]; 4]>,
+        coordinate_system: CoordinateSystem,
+    ) {
+        let transform = transform.into();
+        let [x, y, z] = coordinate_system.transform_point(transform);
+        let [ox, oy, oz] =
+            coordinate_system.transform_direction(transform, coordinate_system.forward());
+
+        crate::param::set_values_synchronized(&[
+            (&self.position_x, x),
+            (&self.position_y, y),
+            (&self.position_z, z),
+            (&self.orientation_x, ox),
+            (&self.orientation_y, oy),
+            (&self.orientation_z, oz),
+        ]);
     }
 
+    /// Algorithm used to reduce the volume of the source as it moves away from the listener
     pub fn distance_model(&self) -> DistanceModelType {
         self.distance_model
     }
@@ -500,31 +1366,66 @@ impl PannerNode {
             .post_message(ControlMessage::DistanceModel(value));
     }
 
+    /// Custom distance attenuation curve, if any
+    pub fn distance_curve(&self) -> Option<&DistanceCurve> {
+        self.distance_curve.as_ref()
+    }
+
+    /// Set a custom distance attenuation curve, or `None` to use the distance model
+    ///
+    /// This is an extension to the spec. When a curve is set, the [distance
+    /// model](Self::distance_model), ref distance, max distance and rolloff factor are ignored.
+    /// The curve is ignored in [strict conformance
+    /// mode](crate::context::BaseAudioContext::set_strict_conformance).
+    pub fn set_distance_curve(&mut self, value: Option<DistanceCurve>) {
+        self.distance_curve = value.clone();
+        let value = value.filter(|_| !self.strict_conformance());
+        self.registration
+            .post_message(ControlMessage::DistanceCurve(value.map(Box::new)));
+    }
+
+    /// Distance below which the volume is not reduced, for all distance models
     pub fn ref_distance(&self) -> f64 {
         self.ref_distance
     }
 
+    /// # Panics
+    ///
+    /// Panics if the value is negative or not finite
     pub fn set_ref_distance(&mut self, value: f64) {
+        assert_valid_ref_distance(value);
         self.ref_distance = value;
         self.registration
             .post_message(ControlMessage::RefDistance(value));
     }
 
+    /// Distance beyond which the volume is not reduced any further, for the linear model only
     pub fn max_distance(&self) -> f64 {
         self.max_distance
     }
 
+    /// # Panics
+    ///
+    /// Panics if the value is not strictly positive
     pub fn set_max_distance(&mut self, value: f64) {
+        assert_valid_max_distance(value);
         self.max_distance = value;
         self.registration
             .post_message(ControlMessage::MaxDistance(value));
     }
 
+    /// How quickly the volume is reduced as the source moves away from the listener
+    ///
+    /// The linear model clamps the factor to the range [0, 1].
     pub fn rolloff_factor(&self) -> f64 {
         self.rolloff_factor
     }
 
+    /// # Panics
+    ///
+    /// Panics if the value is negative or not finite
     pub fn set_rolloff_factor(&mut self, value: f64) {
+        assert_valid_rolloff_factor(value);
         self.rolloff_factor = value;
         self.registration
             .post_message(ControlMessage::RollOffFactor(value));
@@ -564,21 +1465,507 @@ impl PannerNode {
         self.panning_model
     }
 
-    #[allow(clippy::missing_panics_doc)] // loading the provided HRTF will not panic
-    pub fn set_panning_model(&mut self, value: PanningModelType) {
-        let hrtf_option = match value {
-            PanningModelType::EqualPower => None,
+    /// Change the panning model
+    ///
+    /// The output of the previous and of the new panning model are crossfaded over one render
+    /// quantum, so the change does not cause a discontinuity.
+    ///
+    /// In an [`AudioContext`](crate::context::AudioContext), the included HRIR sphere is loaded
+    /// on a background thread when it is not cached yet. The previous panning model is rendered
+    /// until it is loaded.
+    ///
+    /// # Panics
+    ///
+    /// Panics when enabling HRTF panning exceeds the HRTF panner quota of the context, or when
+    /// the included HRIR sphere is used without the `embedded-hrir` feature
+    pub fn set_panning_model(&mut self, value: PanningModelType) {
+        // the sphere that is loaded in the background must not override this model
+        if let Some(pending) = self.pending_hrtf.take() {
+            *pending.lock().unwrap() = false;
+        }
+
+        let (hrtf_option, bytes) = match value {
+            PanningModelType::EqualPower => (None, 0),
             PanningModelType::HRTF => {
-                let sample_rate = self.context().sample_rate() as u32;
-                let (processor, len) = load_hrtf_processor(sample_rate);
-                Some(HrtfState::new(processor, len))
+                let id = self.registration.id();
+                self.context()
+                    .enforce_quota(|memory| memory.check_hrtf(Some(id)));
+                let loaded = match &self.custom_hrtf {
+                    Some(loaded) => Some(loaded.clone()),
+                    None => self.load_default_hrtf(),
+                };
+                let Some((processor, len, bytes)) = loaded else {
+                    self.panning_model = value;
+                    return;
+                };
+                (Some(HrtfState::new(processor, len)), bytes)
             }
         };
+        self.registration
+            .set_memory(MemoryKind::Hrir, vec![Allocation::owned(bytes)]);
 
         self.panning_model = value;
         self.registration
             .post_message(ControlMessage::PanningModel(Box::new(hrtf_option)));
     }
+
+    /// Load the HRTF processor of the included sphere
+    ///
+    /// When an online context has not cached it yet, the processor is loaded on a background
+    /// thread, which sends it to the renderer, and `None` is returned.
+    fn load_default_hrtf(&mut self) -> Option<LoadedHrtf> {
+        assert_embedded_hrir();
+        let sample_rate = self.context().sample_rate() as u32;
+        // the interpolation steps are checked when they are set
+        let steps = self.hrtf_interpolation_steps;
+        if let Some(loaded) = cached_hrtf_processor(sample_rate, steps) {
+            return Some(loaded);
+        }
+        if self.context().offline() {
+            return Some(load_hrtf_processor(sample_rate, steps));
+        }
+
+        let pending = Arc::new(Mutex::new(true));
+        self.pending_hrtf = Some(Arc::clone(&pending));
+        let context = self.context().clone();
+        let id = self.registration.id();
+        std::thread::Builder::new()
+            .name("web-audio-api-hrtf".into())
+            .spawn(move || {
+                let (processor, len, bytes) = load_hrtf_processor(sample_rate, steps);
+                // hold the lock while posting, so a later panning model is posted after it
+                let pending = pending.lock().unwrap();
+                if *pending {
+                    context.set_node_memory(id, MemoryKind::Hrir, vec![Allocation::owned(bytes)]);
+                    let hrtf_state = Some(HrtfState::new(processor, len));
+                    let message = ControlMessage::PanningModel(Box::new(hrtf_state));
+                    context.post_node_message(id, llq::Node::new(Box::new(message)));
+                }
+            })
+            .expect("failed to spawn the HRTF loading thread");
+
+        None
+    }
+
+    /// Radius of the listener's head in meters, used for the near-field compensation of the
+    /// HRTF panning model
+    ///
+    /// Defaults to 0.0875
+    pub fn head_radius(&self) -> f32 {
+        self.head_radius
+    }
+
+    /// Set the radius of the listener's head in meters
+    ///
+    /// In HRTF mode, sources closer than the [near-field
+    /// distance](Self::near_field_distance) get additional level differences between the ears,
+    /// which depend on the size of the head. A radius of zero disables the near-field
+    /// compensation.
+    ///
+    /// This is an extension to the spec and has no effect on the equal-power panning model.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is negative or not finite
+    pub fn set_head_radius(&mut self, value: f32) {
+        assert!(
+            value.is_finite() && value >= 0.,
+            "RangeError - head radius must be a positive number, received {value}"
+        );
+        self.head_radius = value;
+        self.post_near_field();
+    }
+
+    /// Distance in meters below which the near-field compensation of the HRTF panning model
+    /// applies
+    ///
+    /// Defaults to 1.0
+    pub fn near_field_distance(&self) -> f32 {
+        self.near_field_distance
+    }
+
+    /// Set the distance in meters below which the near-field compensation applies
+    ///
+    /// This should match the distance of the loudspeaker to the listener when the HRTF dataset
+    /// in use was measured, typically between 1 and 2 meters.
+    ///
+    /// This is an extension to the spec and has no effect on the equal-power panning model. The
+    /// near-field compensation is disabled in [strict conformance
+    /// mode](crate::context::BaseAudioContext::set_strict_conformance).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is negative or not finite
+    pub fn set_near_field_distance(&mut self, value: f32) {
+        assert!(
+            value.is_finite() && value >= 0.,
+            "RangeError - near-field distance must be a positive number, received {value}"
+        );
+        self.near_field_distance = value;
+        self.post_near_field();
+    }
+
+    fn post_near_field(&self) {
+        let reference_distance = if self.strict_conformance() {
+            0.
+        } else {
+            self.near_field_distance
+        };
+        self.registration.post_message(ControlMessage::NearField {
+            head_radius: self.head_radius,
+            reference_distance,
+        });
+    }
+
+    /// Strength of the Doppler effect, 0 when it is disabled
+    ///
+    /// This is an extension to the spec.
+    ///
+    /// Defaults to 0.0
+    pub fn doppler_factor(&self) -> f32 {
+        self.doppler_factor
+    }
+
+    /// Set the strength of the Doppler effect, 1 for a physical effect
+    ///
+    /// The input is delayed by the propagation time of the sound from the source to the
+    /// listener, i.e. the distance divided by the [speed of sound](Self::speed_of_sound), times
+    /// the Doppler factor. The delay follows the position of the source and of the
+    /// `AudioListener`, so a source moving towards the listener is pitched up and a source moving
+    /// away is pitched down. The delay is limited to 1 second. The effect is disabled in [strict
+    /// conformance mode](crate::context::BaseAudioContext::set_strict_conformance).
+    ///
+    /// This is an extension to the spec.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is negative or not finite
+    pub fn set_doppler_factor(&mut self, value: f32) {
+        assert_valid_doppler_factor(value);
+
+        // the delay line is only allocated while the effect is enabled
+        let sample_rate = self.context().sample_rate();
+        let enabled = value > 0. && !self.strict_conformance();
+        let line =
+            (enabled && !self.doppler_enabled).then(|| Box::new(DopplerLine::new(sample_rate)));
+        let bytes = if enabled {
+            DopplerLine::bytes(sample_rate)
+        } else {
+            0
+        };
+        self.registration
+            .set_memory(MemoryKind::DelayLine, vec![Allocation::owned(bytes)]);
+
+        self.doppler_factor = value;
+        self.doppler_enabled = enabled;
+        self.post_doppler(line);
+    }
+
+    /// Speed of sound of the Doppler effect, in meters per second
+    ///
+    /// This is an extension to the spec.
+    ///
+    /// Defaults to 343.0
+    pub fn speed_of_sound(&self) -> f32 {
+        self.speed_of_sound
+    }
+
+    /// Set the speed of sound of the Doppler effect, in the units of the positions per second
+    ///
+    /// This is an extension to the spec.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not strictly positive and finite
+    pub fn set_speed_of_sound(&mut self, value: f32) {
+        assert_valid_speed_of_sound(value);
+        self.speed_of_sound = value;
+        self.post_doppler(None);
+    }
+
+    fn post_doppler(&self, line: Option<Box<DopplerLine>>) {
+        let factor = if self.doppler_enabled {
+            self.doppler_factor
+        } else {
+            0.
+        };
+        self.registration.post_message(ControlMessage::Doppler {
+            factor,
+            speed_of_sound: self.speed_of_sound,
+            line,
+        });
+    }
+
+    /// Whether the input is low-pass filtered depending on the distance
+    ///
+    /// This is an extension to the spec.
+    ///
+    /// Defaults to false
+    pub fn air_absorption(&self) -> bool {
+        self.air_absorption
+    }
+
+    /// Enable or disable the air absorption
+    ///
+    /// High frequencies are absorbed by the air over long distances. When enabled, the input is
+    /// low-pass filtered with a cutoff frequency of `20000 * exp(-coefficient * distance)` Hertz,
+    /// where the distance is the distance between the source and the `AudioListener`, and the
+    /// coefficient is the [air absorption coefficient](Self::air_absorption_coefficient). This
+    /// comes on top of the distance gain. The filter is disabled in [strict conformance
+    /// mode](crate::context::BaseAudioContext::set_strict_conformance).
+    ///
+    /// This is an extension to the spec.
+    pub fn set_air_absorption(&mut self, value: bool) {
+        self.air_absorption = value;
+        self.post_air_absorption();
+    }
+
+    /// Coefficient of the air absorption, per meter
+    ///
+    /// This is an extension to the spec.
+    ///
+    /// Defaults to 0.0025, the cutoff frequency is about 1.6 kHz at 1 kilometer
+    pub fn air_absorption_coefficient(&self) -> f32 {
+        self.air_absorption_coefficient
+    }
+
+    /// Set the coefficient of the air absorption, per unit of the positions
+    ///
+    /// Larger values filter distant sources more strongly.
+    ///
+    /// This is an extension to the spec.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is negative or not finite
+    pub fn set_air_absorption_coefficient(&mut self, value: f32) {
+        assert_valid_air_absorption_coefficient(value);
+        self.air_absorption_coefficient = value;
+        self.post_air_absorption();
+    }
+
+    fn post_air_absorption(&self) {
+        self.registration
+            .post_message(ControlMessage::AirAbsorption {
+                enabled: self.air_absorption && !self.strict_conformance(),
+                coefficient: self.air_absorption_coefficient,
+            });
+    }
+
+    /// Amount of occlusion of the source, in the range `[0, 1]`
+    ///
+    /// This is an extension to the spec.
+    ///
+    /// Defaults to 0
+    pub fn occlusion(&self) -> f32 {
+        self.occlusion
+    }
+
+    /// Set the amount of occlusion of the source, e.g. a source in another room
+    ///
+    /// An occluded source is fully blocked from the listener, the sound only passes through the
+    /// walls. The direct path is low-pass filtered, down to a cutoff frequency of 500 Hz, and
+    /// attenuated, by up to 18 dB, as the amount increases. Changes are ramped over a render
+    /// quantum, so the amount can be updated by the occlusion checks of a game engine. The
+    /// occlusion and the obstruction are ignored in [strict conformance
+    /// mode](crate::context::BaseAudioContext::set_strict_conformance).
+    ///
+    /// This is an extension to the spec.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not in the range `[0, 1]`
+    pub fn set_occlusion(&mut self, value: f32) {
+        assert_valid_occlusion("occlusion", value);
+        self.occlusion = value;
+        self.post_occlusion();
+    }
+
+    /// Amount of obstruction of the source, in the range `[0, 1]`
+    ///
+    /// This is an extension to the spec.
+    ///
+    /// Defaults to 0
+    pub fn obstruction(&self) -> f32 {
+        self.obstruction
+    }
+
+    /// Set the amount of obstruction of the source, e.g. a source behind a pillar
+    ///
+    /// An obstructed source is in the same room as the listener, only its direct path is
+    /// blocked. The direct path is low-pass filtered, down to a cutoff frequency of 2 kHz, and
+    /// attenuated, by up to 6 dB, as the amount increases. The reverb sends of the source should
+    /// not be affected. Obstruction and occlusion add up.
+    ///
+    /// This is an extension to the spec.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not in the range `[0, 1]`
+    pub fn set_obstruction(&mut self, value: f32) {
+        assert_valid_occlusion("obstruction", value);
+        self.obstruction = value;
+        self.post_occlusion();
+    }
+
+    fn post_occlusion(&self) {
+        let (occlusion, obstruction) = if self.strict_conformance() {
+            (0., 0.)
+        } else {
+            (self.occlusion, self.obstruction)
+        };
+        self.registration.post_message(ControlMessage::Occlusion {
+            occlusion,
+            obstruction,
+        });
+    }
+
+    /// Whether the elevation of the source is accounted for in the EqualPower panning model
+    ///
+    /// This is an extension to the spec.
+    ///
+    /// Defaults to false
+    pub fn equal_power_elevation(&self) -> bool {
+        self.equal_power_elevation
+    }
+
+    /// Account for the elevation of the source in the EqualPower panning model
+    ///
+    /// The spec pans on the azimuth only, so a source slightly off the vertical axis of the
+    /// listener is panned hard to one ear. When enabled, the lateral component of the source
+    /// direction is scaled by the cosine of the elevation, so sources near the vertical axis
+    /// converge smoothly to the center. This is identical to the spec for sources in the
+    /// horizontal plane. The elevation is ignored in
+    /// [strict conformance mode](crate::context::BaseAudioContext::set_strict_conformance).
+    ///
+    /// This is an extension to the spec.
+    pub fn set_equal_power_elevation(&mut self, value: bool) {
+        self.equal_power_elevation = value;
+        let enabled = value && !self.strict_conformance();
+        self.registration
+            .post_message(ControlMessage::EqualPowerElevation(enabled));
+    }
+
+    /// Whether the extensions are ignored, see [`BaseAudioContext::set_strict_conformance`]
+    fn strict_conformance(&self) -> bool {
+        self.registration.context().strict_conformance()
+    }
+
+    /// Source of the head-related impulse responses of the HRTF panning model
+    ///
+    /// This is an extension to the spec.
+    pub fn hrtf_source(&self) -> &HrirSource {
+        &self.hrtf_source
+    }
+
+    /// Set the source of the head-related impulse responses of the HRTF panning model
+    ///
+    /// The sphere is loaded and resampled to the sample rate of the context right away, which
+    /// can take some time for large datasets. It is used as soon as the panning model is HRTF.
+    ///
+    /// This is an extension to the spec.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the file cannot be read, or does not contain a valid HRIR sphere.
+    /// The current impulse responses are kept in that case.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the panning model is HRTF and reloading it exceeds the HRTF panner quota of
+    /// the context
+    pub fn set_hrtf_source(&mut self, source: HrirSource) -> Result<()> {
+        self.custom_hrtf = match &source {
+            HrirSource::Default => {
+                check_hrtf_interpolation_steps(DEFAULT_HRIR_LENGTH, self.hrtf_interpolation_steps)?;
+                None
+            }
+            source => Some(load_hrir_source(
+                source,
+                self.context().sample_rate() as u32,
+                self.hrtf_interpolation_steps,
+            )?),
+        };
+        self.hrtf_source = source;
+
+        if self.panning_model == PanningModelType::HRTF {
+            self.set_panning_model(PanningModelType::HRTF);
+        }
+        Ok(())
+    }
+
+    /// Use the provided head-related impulse responses for HRTF panning
+    ///
+    /// This sets the panning model to [`PanningModelType::HRTF`] and the [HRIR
+    /// source](Self::hrtf_source) to [`HrirSource::Sofa`]. The impulse responses are resampled
+    /// to the sample rate of the context when needed, which can take some time for large
+    /// datasets.
+    ///
+    /// # Panics
+    ///
+    /// Panics when enabling HRTF panning exceeds the HRTF panner quota of the context, or when
+    /// the length of the impulse responses does not support the [HRTF interpolation
+    /// steps](Self::hrtf_interpolation_steps)
+    pub fn set_hrtf(&mut self, hrtf: &SofaHrtf) {
+        let sample_rate = self.context().sample_rate() as u32;
+        let loaded = hrtf_processor(hrtf.hrir_sphere(sample_rate), self.hrtf_interpolation_steps)
+            .unwrap_or_else(|e| panic!("{e}"));
+        self.custom_hrtf = Some(loaded);
+        self.hrtf_source = HrirSource::Sofa(hrtf.clone());
+        self.set_panning_model(PanningModelType::HRTF);
+    }
+
+    /// Number of steps per render quantum of the interpolation of the HRTF panning model
+    ///
+    /// This is an extension to the spec.
+    ///
+    /// Defaults to 1
+    pub fn hrtf_interpolation_steps(&self) -> usize {
+        self.hrtf_interpolation_steps
+    }
+
+    /// Set the number of steps per render quantum of the interpolation of the HRTF panning model
+    ///
+    /// The impulse responses follow the direction of the source from step to step, each step
+    /// convolves `128 / steps` samples. More steps give smoother transitions for fast-moving
+    /// sources, at the cost of CPU. The impulse responses are reloaded right away, which can take
+    /// some time when they need to be resampled.
+    ///
+    /// This is an extension to the spec and has no effect on the equal-power panning model.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the impulse responses of the [HRIR source](Self::hrtf_source)
+    /// cannot be reloaded, or when their length does not support this number of steps. The
+    /// current settings are kept in that case.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of steps does not divide the render quantum size (128)
+    pub fn set_hrtf_interpolation_steps(&mut self, steps: usize) -> Result<()> {
+        assert_valid_hrtf_interpolation_steps(steps);
+        if steps == self.hrtf_interpolation_steps {
+            return Ok(());
+        }
+
+        self.custom_hrtf = match &self.hrtf_source {
+            HrirSource::Default => {
+                check_hrtf_interpolation_steps(DEFAULT_HRIR_LENGTH, steps)?;
+                None
+            }
+            source => Some(load_hrir_source(
+                source,
+                self.context().sample_rate() as u32,
+                steps,
+            )?),
+        };
+        self.hrtf_interpolation_steps = steps;
+
+        if self.panning_model == PanningModelType::HRTF {
+            self.set_panning_model(PanningModelType::HRTF);
+        }
+        Ok(())
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -587,6 +1974,7 @@ struct SpatialParams {
     cone_gain: f32,
     azimuth: f32,
     elevation: f32,
+    distance: f32,
 }
 
 struct PannerRenderer {
@@ -604,7 +1992,30 @@ struct PannerRenderer {
     cone_outer_angle: f64,
     cone_outer_gain: f64,
     hrtf_state: Option<HrtfState>, // use EqualPower panning model if `None`
+    /// panning model to crossfade from, after a change of the panning model
+    previous_hrtf_state: Option<Option<HrtfState>>,
+    /// whether the input has been panned, the panning model is only crossfaded afterwards
+    rendered: bool,
     tail_time_counter: usize,
+    head_radius: f32,
+    near_field_distance: f32,
+    prev_near_field_gains: (f32, f32),
+    /// gains of the EqualPower panning at the end of the previous render quantum
+    prev_equal_power_gains: Option<[[f32; 2]; 2]>,
+    distance_curve: Option<Box<DistanceCurve>>,
+    /// propagation delay of the Doppler effect, `None` while it is disabled
+    doppler: Option<Box<DopplerLine>>,
+    doppler_factor: f32,
+    speed_of_sound: f32,
+    /// low-pass filter of the air absorption, `None` while it is disabled
+    air_absorption: Option<AirAbsorptionFilter>,
+    air_absorption_coefficient: f32,
+    /// filter of the occlusion and obstruction, `None` while the direct path is not blocked
+    occlusion_filter: Option<OcclusionFilter>,
+    occlusion: f32,
+    obstruction: f32,
+    /// whether the elevation is projected onto the azimuth of the EqualPower panning
+    equal_power_elevation: bool,
 }
 
 impl AudioProcessor for PannerRenderer {
@@ -613,7 +2024,7 @@ impl AudioProcessor for PannerRenderer {
         inputs: &[AudioRenderQuantum],
         outputs: &mut [AudioRenderQuantum],
         params: AudioParamValues<'_>,
-        _scope: &RenderScope,
+        scope: &RenderScope,
     ) -> bool {
         // single input/output node
         let input = &inputs[0];
@@ -622,8 +2033,39 @@ impl AudioProcessor for PannerRenderer {
         // pass through input
         *output = input.clone();
 
-        // only handle mono for now (todo issue #44)
-        output.mix(1, ChannelInterpretation::Speakers);
+        // The channel count is at most 2. The input of a mono source is upmixed to identical
+        // channels when the channel count is 2, and is spatialized as mono.
+        if output.all_channels_identical() {
+            output.force_mono();
+        }
+
+        // delay the input by the propagation time of the Doppler effect
+        if let Some(mut line) = self.doppler.take() {
+            if !(output.is_silent() && line.is_drained()) {
+                let delays = self.doppler_delays(&params, scope.sample_rate, &mut line);
+                line.process(output, &delays);
+            }
+            self.doppler = Some(line);
+        }
+
+        // low-pass filter the input depending on the distance
+        if let Some(mut filter) = self.air_absorption.take() {
+            let alpha = self.air_absorption_alpha(&params, scope.sample_rate);
+            filter.process(output, alpha);
+            self.air_absorption = Some(filter);
+        }
+
+        // low-pass filter and attenuate the blocked direct path
+        if let Some(mut filter) = self.occlusion_filter.take() {
+            filter.process(output, self.occlusion, self.obstruction, scope.sample_rate);
+            // bypass the filter once the direct path is no longer blocked
+            let blocked = self.occlusion > 0. || self.obstruction > 0.;
+            if blocked || !filter.is_transparent() {
+                self.occlusion_filter = Some(filter);
+            }
+        }
+        let input = output.clone();
+        let stereo = input.number_of_channels() == 2;
 
         // early exit for silence
         if input.is_silent() {
@@ -640,12 +2082,213 @@ impl AudioProcessor for PannerRenderer {
             self.tail_time_counter += RENDER_QUANTUM_SIZE;
         }
 
-        // convert mono to identical stereo
+        // render to stereo
         output.mix(2, ChannelInterpretation::Speakers);
 
+        // crossfade from the previous panning model over this render quantum
+        let previous_output = self.previous_hrtf_state.take().map(|mut previous_state| {
+            let mut previous_output = output.clone();
+            // the near-field gains are ramped by the current model only
+            let prev_near_field_gains = self.prev_near_field_gains;
+            self.pan(
+                previous_state.as_mut(),
+                &input,
+                &mut previous_output,
+                &params,
+                stereo,
+            );
+            self.prev_near_field_gains = prev_near_field_gains;
+            previous_output
+        });
+
         // for borrow reasons, take the hrtf_state out of self
         let mut hrtf_state = self.hrtf_state.take();
+        self.pan(hrtf_state.as_mut(), &input, output, &params, stereo);
+        // put the hrtf_state back into self (borrow reasons)
+        self.hrtf_state = hrtf_state;
+        self.rendered = true;
+
+        if let Some(previous_output) = previous_output {
+            output
+                .channels_mut()
+                .iter_mut()
+                .zip(previous_output.channels())
+                .for_each(|(channel, previous)| {
+                    channel
+                        .iter_mut()
+                        .zip(previous.iter())
+                        .enumerate()
+                        .for_each(|(i, (o, p))| {
+                            let t = (i + 1) as f32 / RENDER_QUANTUM_SIZE as f32;
+                            *o = p + (*o - p) * t;
+                        });
+                });
+        }
+
+        // tail time only for HRTF panning, and while the Doppler delay line or the air absorption
+        // and occlusion filters are not drained
+        self.hrtf_state.is_some()
+            || self.doppler.as_ref().is_some_and(|line| !line.is_drained())
+            || self
+                .air_absorption
+                .as_ref()
+                .is_some_and(|filter| !filter.is_drained())
+            || self
+                .occlusion_filter
+                .as_ref()
+                .is_some_and(|filter| !filter.lowpass.is_drained())
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if let Some(control) = msg.downcast_mut::<ControlMessage>() {
+            match control {
+                ControlMessage::DistanceModel(value) => self.distance_model = *value,
+                ControlMessage::RefDistance(value) => self.ref_distance = *value,
+                ControlMessage::MaxDistance(value) => self.max_distance = *value,
+                ControlMessage::RollOffFactor(value) => self.rolloff_factor = *value,
+                ControlMessage::ConeInnerAngle(value) => self.cone_inner_angle = *value,
+                ControlMessage::ConeOuterAngle(value) => self.cone_outer_angle = *value,
+                ControlMessage::ConeOuterGain(value) => self.cone_outer_gain = *value,
+                ControlMessage::PanningModel(value) => {
+                    let previous_state = std::mem::replace(&mut self.hrtf_state, value.take());
+                    // crossfade from the model that was last rendered
+                    if self.rendered && self.previous_hrtf_state.is_none() {
+                        self.previous_hrtf_state = Some(previous_state);
+                    }
+                }
+                ControlMessage::Doppler {
+                    factor,
+                    speed_of_sound,
+                    line,
+                } => {
+                    self.doppler_factor = *factor;
+                    self.speed_of_sound = *speed_of_sound;
+                    if *factor == 0. {
+                        // the delay line is dropped by the garbage collector
+                        std::mem::swap(&mut self.doppler, line);
+                    } else if self.doppler.is_none() {
+                        self.doppler = line.take();
+                    }
+                }
+                ControlMessage::AirAbsorption {
+                    enabled,
+                    coefficient,
+                } => {
+                    self.air_absorption_coefficient = *coefficient;
+                    if !*enabled {
+                        self.air_absorption = None;
+                    } else if self.air_absorption.is_none() {
+                        self.air_absorption = Some(AirAbsorptionFilter::default());
+                    }
+                }
+                ControlMessage::Occlusion {
+                    occlusion,
+                    obstruction,
+                } => {
+                    self.occlusion = *occlusion;
+                    self.obstruction = *obstruction;
+                    let blocked = *occlusion > 0. || *obstruction > 0.;
+                    if blocked && self.occlusion_filter.is_none() {
+                        self.occlusion_filter = Some(OcclusionFilter::default());
+                    }
+                }
+                ControlMessage::NearField {
+                    head_radius,
+                    reference_distance,
+                } => {
+                    self.head_radius = *head_radius;
+                    self.near_field_distance = *reference_distance;
+                }
+                ControlMessage::EqualPowerElevation(value) => self.equal_power_elevation = *value,
+                ControlMessage::DistanceCurve(value) => self.distance_curve = value.take(),
+            }
+
+            return;
+        }
+
+        log::warn!("PannerRenderer: Dropping incoming message {msg:?}");
+    }
+}
 
+impl PannerRenderer {
+    /// Propagation delays of the Doppler effect over the render quantum, in sample frames
+    ///
+    /// The delay is ramped from the previous render quantum when the positions are k-rate, so
+    /// the pitch does not jump at each render quantum.
+    fn doppler_delays(
+        &self,
+        params: &AudioParamValues<'_>,
+        sample_rate: f32,
+        line: &mut DopplerLine,
+    ) -> [f32; RENDER_QUANTUM_SIZE] {
+        let source = [
+            params.get(&self.position_x),
+            params.get(&self.position_y),
+            params.get(&self.position_z),
+        ];
+        let [listener_x, listener_y, listener_z, ..] = params.listener_params();
+        let listener = [listener_x, listener_y, listener_z];
+
+        let scale = self.doppler_factor / self.speed_of_sound * sample_rate;
+        let max_delay = MAX_DOPPLER_DELAY * sample_rate;
+        let delay = |i: usize| {
+            let at = |values: &[f32]| values[i % values.len()];
+            let source_position = [at(&source[0]), at(&source[1]), at(&source[2])];
+            let listener_position = [at(&listener[0]), at(&listener[1]), at(&listener[2])];
+            let distance = crate::spatial::distance(source_position, listener_position);
+            (distance * scale).min(max_delay)
+        };
+
+        let mut delays = [0.; RENDER_QUANTUM_SIZE];
+        if source.iter().all(|values| values.len() == 1)
+            && listener.iter().all(|values| values.len() == 1)
+        {
+            let target = delay(0);
+            let prev = line.prev_delay.unwrap_or(target);
+            delays.iter_mut().enumerate().for_each(|(i, d)| {
+                let t = (i + 1) as f32 / RENDER_QUANTUM_SIZE as f32;
+                *d = prev + (target - prev) * t;
+            });
+        } else {
+            delays
+                .iter_mut()
+                .enumerate()
+                .for_each(|(i, d)| *d = delay(i));
+        }
+        line.prev_delay = Some(delays[RENDER_QUANTUM_SIZE - 1]);
+
+        delays
+    }
+
+    /// Coefficient of the air absorption filter, for the distance at the end of the render
+    /// quantum
+    ///
+    /// The filter ramps the coefficient from the previous render quantum.
+    fn air_absorption_alpha(&self, params: &AudioParamValues<'_>, sample_rate: f32) -> f32 {
+        let last = |values: &[f32]| values[values.len() - 1];
+        let source = [
+            last(&params.get(&self.position_x)),
+            last(&params.get(&self.position_y)),
+            last(&params.get(&self.position_z)),
+        ];
+        let [listener_x, listener_y, listener_z, ..] = params.listener_params();
+        let listener = [last(&listener_x), last(&listener_y), last(&listener_z)];
+        let distance = crate::spatial::distance(source, listener);
+
+        AirAbsorptionFilter::alpha(distance, self.air_absorption_coefficient, sample_rate)
+    }
+
+    /// Spatialize the input with the given panning model, `None` is the equal-power model
+    ///
+    /// The output holds the stereo input.
+    fn pan(
+        &mut self,
+        hrtf_state: Option<&mut HrtfState>,
+        input: &AudioRenderQuantum,
+        output: &mut AudioRenderQuantum,
+        params: &AudioParamValues<'_>,
+        stereo: bool,
+    ) {
         // source parameters (Panner)
         let source_position_x = params.get(&self.position_x);
         let source_position_y = params.get(&self.position_y);
@@ -658,67 +2301,66 @@ impl AudioProcessor for PannerRenderer {
         let [listener_position_x, listener_position_y, listener_position_z, listener_forward_x, listener_forward_y, listener_forward_z, listener_up_x, listener_up_y, listener_up_z] =
             params.listener_params();
 
-        // build up the a-rate iterator for spatial variables
-        let mut a_rate_params = source_position_x
-            .iter()
-            .cycle()
-            .zip(source_position_y.iter().cycle())
-            .zip(source_position_z.iter().cycle())
-            .zip(source_orientation_x.iter().cycle())
-            .zip(source_orientation_y.iter().cycle())
-            .zip(source_orientation_z.iter().cycle())
-            .zip(listener_position_x.iter().cycle())
-            .zip(listener_position_y.iter().cycle())
-            .zip(listener_position_z.iter().cycle())
-            .zip(listener_forward_x.iter().cycle())
-            .zip(listener_forward_y.iter().cycle())
-            .zip(listener_forward_z.iter().cycle())
-            .zip(listener_up_x.iter().cycle())
-            .zip(listener_up_y.iter().cycle())
-            .zip(listener_up_z.iter().cycle())
-            .map(|tuple| {
-                // unpack giant tuple
-                let ((((((sp_so_lp, lfx), lfy), lfz), lux), luy), luz) = tuple;
-                let (((sp_so, lpx), lpy), lpz) = sp_so_lp;
-                let (((sp, sox), soy), soz) = sp_so;
-                let ((spx, spy), spz) = sp;
-
-                // define base vectors in 3D
-                let source_position = [*spx, *spy, *spz];
-                let source_orientation = [*sox, *soy, *soz];
-                let listener_position = [*lpx, *lpy, *lpz];
-                let listener_forward = [*lfx, *lfy, *lfz];
-                let listener_up = [*lux, *luy, *luz];
-
-                // determine distance and cone gain
-                let dist_gain = self.dist_gain(source_position, listener_position);
-                let cone_gain =
-                    self.cone_gain(source_position, source_orientation, listener_position);
-
-                // azimuth and elevation of the panner in frame of reference of the listener
-                let (azimuth, elevation) = crate::spatial::azimuth_and_elevation(
-                    source_position,
-                    listener_position,
-                    listener_forward,
-                    listener_up,
-                );
-
-                SpatialParams {
-                    dist_gain,
-                    cone_gain,
-                    azimuth,
-                    elevation,
-                }
-            });
+        // spatial variables at the given frame of the render quantum
+        let spatial_params_at = |i: usize| {
+            let at = |values: &[f32]| values[i % values.len()];
+
+            // define base vectors in 3D
+            let source_position = [
+                at(&source_position_x),
+                at(&source_position_y),
+                at(&source_position_z),
+            ];
+            let source_orientation = [
+                at(&source_orientation_x),
+                at(&source_orientation_y),
+                at(&source_orientation_z),
+            ];
+            let listener_position = [
+                at(&listener_position_x),
+                at(&listener_position_y),
+                at(&listener_position_z),
+            ];
+            let listener_forward = [
+                at(&listener_forward_x),
+                at(&listener_forward_y),
+                at(&listener_forward_z),
+            ];
+            let listener_up = [at(&listener_up_x), at(&listener_up_y), at(&listener_up_z)];
+
+            // determine distance and cone gain
+            let distance = crate::spatial::distance(source_position, listener_position);
+            let dist_gain = self.dist_gain(distance);
+            let cone_gain = self.cone_gain(source_position, source_orientation, listener_position);
+
+            // azimuth and elevation of the panner in frame of reference of the listener
+            let (azimuth, elevation) = crate::spatial::azimuth_and_elevation(
+                source_position,
+                listener_position,
+                listener_forward,
+                listener_up,
+            );
+
+            SpatialParams {
+                dist_gain,
+                cone_gain,
+                azimuth,
+                elevation,
+                distance,
+            }
+        };
 
-        if let Some(hrtf_state) = &mut hrtf_state {
-            // HRTF panning - always k-rate so take a single value from the a-rate iter
+        if let Some(hrtf_state) = hrtf_state {
+            // HRTF panning - always k-rate, the impulse responses and the gains are ramped from
+            // the previous render quantum towards the values of the last frame, so the a-rate
+            // movements of the source or the listener are followed without a step
             let SpatialParams {
                 dist_gain,
                 cone_gain,
                 azimuth,
                 elevation,
-            } = a_rate_params.next().unwrap();
+                distance,
+            } = spatial_params_at(RENDER_QUANTUM_SIZE - 1);
 
             let new_distance_gain = cone_gain * dist_gain;
 
@@ -734,54 +2376,51 @@ impl AudioProcessor for PannerRenderer {
                 projected_source = [0., 0., 1.];
             }
 
-            let output_interleaved =
-                hrtf_state.process(output.channel_data(0), new_distance_gain, projected_source);
+            let output_interleaved = hrtf_state.process(input, new_distance_gain, projected_source);
+
+            // near-field level differences, ramped over the render quantum to avoid clicks
+            let (prev_gain_l, prev_gain_r) = self.prev_near_field_gains;
+            let (gain_l, gain_r) = near_field_gains(
+                azimuth,
+                elevation,
+                distance,
+                self.head_radius,
+                self.near_field_distance,
+            );
+            self.prev_near_field_gains = (gain_l, gain_r);
 
             let [left, right] = output.stereo_mut();
             output_interleaved
                 .iter()
                 .zip(&mut left[..])
                 .zip(&mut right[..])
-                .for_each(|((p, l), r)| {
-                    *l = p.0;
-                    *r = p.1;
+                .enumerate()
+                .for_each(|(i, ((p, l), r))| {
+                    let t = (i + 1) as f32 / RENDER_QUANTUM_SIZE as f32;
+                    *l = p.0 * (prev_gain_l + (gain_l - prev_gain_l) * t);
+                    *r = p.1 * (prev_gain_r + (gain_r - prev_gain_r) * t);
                 });
         } else {
             // EqualPower panning
             let [left, right] = output.stereo_mut();
 
-            // Closure to apply gain per stereo channel
-            let apply_stereo_gain =
-                |((spatial_params, l), r): ((SpatialParams, &mut f32), &mut f32)| {
-                    let SpatialParams {
-                        dist_gain,
-                        cone_gain,
-                        azimuth,
-                        ..
-                    } = spatial_params;
-
-                    // Determine left/right ear gain. Clamp azimuth to range of [-180, 180].
-                    let mut azimuth = azimuth.clamp(-180., 180.);
-
-                    // Then wrap to range [-90, 90].
-                    if azimuth < -90. {
-                        azimuth = -180. - azimuth;
-                    } else if azimuth > 90. {
-                        azimuth = 180. - azimuth;
-                    }
-
-                    // x is the horizontal plane orientation of the sound
-                    let x = (azimuth + 90.) / 180.;
-                    let gain_l = (x * PI / 2.).cos();
-                    let gain_r = (x * PI / 2.).sin();
+            // Closure to apply the gains per stereo channel
+            let apply_stereo_gain = |((gains, l), r): (([[f32; 2]; 2], &mut f32), &mut f32)| {
+                let [[ll, rl], [lr, rr]] = gains;
+                let (in_l, in_r) = (*l, *r);
+                *l = in_l * ll + in_r * rl;
+                *r = in_l * lr + in_r * rr;
+            };
 
-                    // multiply signal with gain per ear
-                    *l *= gain_l * dist_gain * cone_gain;
-                    *r *= gain_r * dist_gain * cone_gain;
-                };
-
-            // Optimize for static Panner & Listener
-            let single_valued = listener_position_x.len() == 1
+            // Optimize for static Panner & Listener, any automated or connected param of
+            // either makes the gains a-rate
+            let single_valued = source_position_x.len() == 1
+                && source_position_y.len() == 1
+                && source_position_z.len() == 1
+                && source_orientation_x.len() == 1
+                && source_orientation_y.len() == 1
+                && source_orientation_z.len() == 1
+                && listener_position_x.len() == 1
                 && listener_position_y.len() == 1
                 && listener_position_z.len() == 1
                 && listener_forward_x.len() == 1
@@ -791,46 +2430,46 @@ impl AudioProcessor for PannerRenderer {
                 && listener_up_y.len() == 1
                 && listener_up_z.len() == 1;
             if single_valued {
-                std::iter::repeat(a_rate_params.next().unwrap())
+                // the gains are ramped from the previous render quantum to avoid clicks when the
+                // source or the listener jumps
+                let gains =
+                    equal_power_matrix(spatial_params_at(0), stereo, self.equal_power_elevation);
+                let prev_gains = self.prev_equal_power_gains.unwrap_or(gains);
+                self.prev_equal_power_gains = Some(gains);
+
+                (0..RENDER_QUANTUM_SIZE)
+                    .map(|i| {
+                        let t = (i + 1) as f32 / RENDER_QUANTUM_SIZE as f32;
+                        let lerp = |p: f32, g: f32| p + (g - p) * t;
+                        let [[pll, prl], [plr, prr]] = prev_gains;
+                        let [[ll, rl], [lr, rr]] = gains;
+                        [
+                            [lerp(pll, ll), lerp(prl, rl)],
+                            [lerp(plr, lr), lerp(prr, rr)],
+                        ]
+                    })
                     .zip(&mut left[..])
                     .zip(&mut right[..])
                     .for_each(apply_stereo_gain);
             } else {
-                a_rate_params
+                let mut gains = [[0.; 2]; 2];
+                (0..RENDER_QUANTUM_SIZE)
+                    .map(|i| {
+                        gains = equal_power_matrix(
+                            spatial_params_at(i),
+                            stereo,
+                            self.equal_power_elevation,
+                        );
+                        gains
+                    })
                     .zip(&mut left[..])
                     .zip(&mut right[..])
                     .for_each(apply_stereo_gain);
+                self.prev_equal_power_gains = Some(gains);
             }
         }
-
-        // put the hrtf_state back into self (borrow reasons)
-        self.hrtf_state = hrtf_state;
-
-        // tail time only for HRTF panning
-        self.hrtf_state.is_some()
     }
 
-    fn onmessage(&mut self, msg: &mut dyn Any) {
-        if let Some(control) = msg.downcast_mut::<ControlMessage>() {
-            match control {
-                ControlMessage::DistanceModel(value) => self.distance_model = *value,
-                ControlMessage::RefDistance(value) => self.ref_distance = *value,
-                ControlMessage::MaxDistance(value) => self.max_distance = *value,
-                ControlMessage::RollOffFactor(value) => self.rolloff_factor = *value,
-                ControlMessage::ConeInnerAngle(value) => self.cone_inner_angle = *value,
-                ControlMessage::ConeOuterAngle(value) => self.cone_outer_angle = *value,
-                ControlMessage::ConeOuterGain(value) => self.cone_outer_gain = *value,
-                ControlMessage::PanningModel(value) => self.hrtf_state = value.take(),
-            }
-
-            return;
-        }
-
-        log::warn!("PannerRenderer: Dropping incoming message {msg:?}");
-    }
-}
-
-impl PannerRenderer {
     fn cone_gain(
         &self,
         source_position: [f32; 3],
@@ -859,19 +2498,33 @@ impl PannerRenderer {
         }
     }
 
-    fn dist_gain(&self, source_position: [f32; 3], listener_position: [f32; 3]) -> f32 {
+    fn dist_gain(&self, distance: f32) -> f32 {
+        if let Some(curve) = &self.distance_curve {
+            return curve.gain(distance);
+        }
+
         let distance_model = self.distance_model;
         let ref_distance = self.ref_distance;
         let rolloff_factor = self.rolloff_factor;
-        let distance = crate::spatial::distance(source_position, listener_position) as f64;
+        let distance = distance as f64;
 
         let dist_gain = match distance_model {
             DistanceModelType::Linear => {
                 let max_distance = self.max_distance;
                 let d2ref = ref_distance.min(max_distance);
                 let d2max = ref_distance.max(max_distance);
-                let d_clamped = distance.clamp(d2ref, d2max);
-                1. - rolloff_factor * (d_clamped - d2ref) / (d2max - d2ref)
+                let rolloff_factor = rolloff_factor.clamp(0., 1.);
+                if d2ref == d2max {
+                    // the volume drops at once at the ref distance
+                    if distance < d2ref {
+                        1.
+                    } else {
+                        1. - rolloff_factor
+                    }
+                } else {
+                    let d_clamped = distance.clamp(d2ref, d2max);
+                    1. - rolloff_factor * (d_clamped - d2ref) / (d2max - d2ref)
+                }
             }
             DistanceModelType::Inverse => {
                 if distance > 0. {
@@ -883,13 +2536,146 @@ impl PannerRenderer {
                 }
             }
             DistanceModelType::Exponential => {
-                (distance.max(ref_distance) / ref_distance).powf(-rolloff_factor)
+                if ref_distance > 0. {
+                    (distance.max(ref_distance) / ref_distance).powf(-rolloff_factor)
+                } else if distance > 0. && rolloff_factor > 0. {
+                    // a source at any distance is infinitely far relative to the ref distance
+                    0.
+                } else {
+                    1.
+                }
             }
         };
         dist_gain as f32
     }
 }
 
+/// Left and right ear gain compensating for the proximity of the source in HRTF mode
+///
+/// HRTFs are measured at a fixed distance, so they lack the interaural level differences of
+/// sources close to the head. Below the measurement distance, the level at each ear is corrected
+/// with the ratio of the source distance to the center of the head and to the ear, relative to
+/// that ratio at the measurement distance. The ears are modelled as two points on the interaural
+/// axis, at `head_radius` from the center. Returns unity gains for distant sources or when the
+/// head radius is zero.
+fn near_field_gains(
+    azimuth: f32,
+    elevation: f32,
+    distance: f32,
+    head_radius: f32,
+    reference_distance: f32,
+) -> (f32, f32) {
+    if head_radius <= 0. || distance >= reference_distance {
+        return (1., 1.);
+    }
+
+    // do not let sources enter the head
+    let distance = distance.max(2. * head_radius);
+
+    // component of the source direction towards the right ear
+    let lateral = (azimuth * PI / 180.).sin() * (elevation * PI / 180.).cos();
+
+    let ear_gain = |distance: f32, side: f32| {
+        let ear_distance = (distance * distance + head_radius * head_radius
+            - 2. * distance * head_radius * side)
+            .sqrt();
+        distance / ear_distance
+    };
+
+    let gain_l = ear_gain(distance, -lateral) / ear_gain(reference_distance, -lateral);
+    let gain_r = ear_gain(distance, lateral) / ear_gain(reference_distance, lateral);
+
+    (gain_l, gain_r)
+}
+
+/// Left and right ear gain of the equal-power panning model
+///
+/// The azimuth is first wrapped to the frontal range of [-90, 90] degrees, as described by the
+/// spec. Then the lateral component of the source direction is scaled by the cosine of the
+/// elevation, so sources near the vertical axis of the listener converge smoothly to the center
+/// instead of panning hard to one ear. The spec ignores the elevation, which is the case for an
+/// elevation of 0.
+fn equal_power_gains(azimuth: f32, elevation: f32) -> (f32, f32) {
+    let azimuth = equal_power_azimuth(azimuth, elevation);
+
+    // x is the horizontal plane orientation of the sound
+    let x = (azimuth + 90.) / 180.;
+    let gain_l = (x * PI / 2.).cos();
+    let gain_r = (x * PI / 2.).sin();
+
+    (gain_l, gain_r)
+}
+
+/// Gains of the equal-power panning of a stereo input, `[[ll, rl], [lr, rr]]` where `rl` is the
+/// gain of the right input channel in the left output channel
+///
+/// As described by the spec, the channel on the side of the source is kept, and the other
+/// channel is panned between both outputs. The azimuth is adjusted for the elevation like for
+/// [mono inputs](equal_power_gains).
+fn equal_power_stereo_gains(azimuth: f32, elevation: f32) -> [[f32; 2]; 2] {
+    let azimuth = equal_power_azimuth(azimuth, elevation);
+
+    if azimuth <= 0. {
+        let x = (azimuth + 90.) / 90.;
+        let gain_l = (x * PI / 2.).cos();
+        let gain_r = (x * PI / 2.).sin();
+        [[1., gain_l], [0., gain_r]]
+    } else {
+        let x = azimuth / 90.;
+        let gain_l = (x * PI / 2.).cos();
+        let gain_r = (x * PI / 2.).sin();
+        [[gain_l, 0.], [gain_r, 1.]]
+    }
+}
+
+/// Gains of the EqualPower panning from the input to the output channels, including the
+/// distance and cone gains
+///
+/// The gains are indexed by output and input channel, the left channel of a mono input is used.
+/// The elevation of the source is ignored as described by the spec, unless `elevation` is set.
+fn equal_power_matrix(
+    spatial_params: SpatialParams,
+    stereo: bool,
+    elevation: bool,
+) -> [[f32; 2]; 2] {
+    let SpatialParams {
+        dist_gain,
+        cone_gain,
+        azimuth,
+        elevation: source_elevation,
+        ..
+    } = spatial_params;
+    let gain = dist_gain * cone_gain;
+    let elevation = if elevation { source_elevation } else { 0. };
+
+    let gains = if stereo {
+        equal_power_stereo_gains(azimuth, elevation)
+    } else {
+        let (gain_l, gain_r) = equal_power_gains(azimuth, elevation);
+        [[gain_l, 0.], [gain_r, 0.]]
+    };
+
+    gains.map(|row| row.map(|g| g * gain))
+}
+
+/// Azimuth of the equal-power panning, in the frontal range of [-90, 90] degrees
+fn equal_power_azimuth(azimuth: f32, elevation: f32) -> f32 {
+    // Clamp azimuth to range of [-180, 180].
+    let mut azimuth = azimuth.clamp(-180., 180.);
+
+    // Then wrap to range [-90, 90].
+    if azimuth < -90. {
+        azimuth = -180. - azimuth;
+    } else if azimuth > 90. {
+        azimuth = 180. - azimuth;
+    }
+
+    // Project onto the interaural axis to account for the elevation
+    let elevation = elevation.clamp(-90., 90.);
+    let lateral = (azimuth * PI / 180.).sin() * (elevation * PI / 180.).cos();
+    lateral.clamp(-1., 1.).asin() * 180. / PI
+}
+
 #[cfg(test)]
 mod tests {
     use float_eq::{assert_float_eq, assert_float_ne};
@@ -952,6 +2738,339 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_equal_power_a_rate() {
+        let sample_rate = 44100.;
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, sample_rate);
+
+        let mut src = context.create_constant_source();
+        src.start();
+
+        let options = PannerOptions {
+            panning_model: PanningModelType::EqualPower,
+            ..PannerOptions::default()
+        };
+        let panner = PannerNode::new(&context, options);
+        // sound moves from the left to the right within the render quantum
+        panner.position_z().set_value(1.);
+        panner.position_x().set_value_at_time(-1., 0.);
+        let end = RENDER_QUANTUM_SIZE as f64 / sample_rate as f64;
+        panner.position_x().linear_ramp_to_value_at_time(1., end);
+
+        src.connect(&panner);
+        panner.connect(&context.destination());
+
+        let output = context.start_rendering_sync();
+        let left = output.get_channel_data(0);
+        let right = output.get_channel_data(1);
+
+        // the gains follow the automation sample by sample
+        for i in 0..RENDER_QUANTUM_SIZE {
+            let x = -1. + 2. * i as f32 / RENDER_QUANTUM_SIZE as f32;
+            let source = [x, 0., 1.];
+            let (azimuth, elevation) = crate::spatial::azimuth_and_elevation(
+                source,
+                [0., 0., 0.],
+                [0., 0., -1.],
+                [0., 1., 0.],
+            );
+            let (gain_l, gain_r) = equal_power_gains(azimuth, elevation);
+            let distance = crate::spatial::distance(source, [0., 0., 0.]);
+            assert_float_eq!(left[i], gain_l / distance, abs <= 1E-5);
+            assert_float_eq!(right[i], gain_r / distance, abs <= 1E-5);
+        }
+        assert!(left[0] > right[0]);
+        assert!(left[127] < right[127]);
+    }
+
+    #[test]
+    fn test_equal_power_dezippering() {
+        let sample_rate = 44100.;
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE * 3, sample_rate);
+
+        let mut src = context.create_constant_source();
+        src.start();
+
+        let options = PannerOptions {
+            panning_model: PanningModelType::EqualPower,
+            ..PannerOptions::default()
+        };
+        let panner = PannerNode::new(&context, options);
+        // sound jumps from the right to the left at the second render quantum
+        panner.position_x().set_value(1.);
+        let quantum = RENDER_QUANTUM_SIZE as f64 / sample_rate as f64;
+        panner.position_x().set_value_at_time(-1., quantum);
+
+        src.connect(&panner);
+        panner.connect(&context.destination());
+
+        let output = context.start_rendering_sync();
+        let left = output.get_channel_data(0);
+        let right = output.get_channel_data(1);
+
+        // the gains are ramped over the second render quantum
+        assert_float_eq!(left[..128], [0.; 128][..], abs_all <= 1E-6);
+        assert_float_eq!(right[..128], [1.; 128][..], abs_all <= 1E-6);
+        for i in 0..RENDER_QUANTUM_SIZE {
+            let t = (i + 1) as f32 / RENDER_QUANTUM_SIZE as f32;
+            assert_float_eq!(left[128 + i], t, abs <= 1E-6);
+            assert_float_eq!(right[128 + i], 1. - t, abs <= 1E-6);
+        }
+        assert_float_eq!(left[256..], [1.; 128][..], abs_all <= 1E-6);
+        assert_float_eq!(right[256..], [0.; 128][..], abs_all <= 1E-6);
+    }
+
+    #[test]
+    fn test_equal_power_gains() {
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+
+        // horizontal plane: front, right, left, behind
+        let (l, r) = equal_power_gains(0., 0.);
+        assert_float_eq!([l, r], [half, half], abs_all <= 1E-6);
+        let (l, r) = equal_power_gains(90., 0.);
+        assert_float_eq!([l, r], [0., 1.], abs_all <= 1E-6);
+        let (l, r) = equal_power_gains(-90., 0.);
+        assert_float_eq!([l, r], [1., 0.], abs_all <= 1E-6);
+        let (l, r) = equal_power_gains(180., 0.);
+        assert_float_eq!([l, r], [half, half], abs_all <= 1E-6);
+
+        // front-back symmetry
+        let (l1, r1) = equal_power_gains(30., 0.);
+        let (l2, r2) = equal_power_gains(150., 0.);
+        assert_float_eq!([l1, r1], [l2, r2], abs_all <= 1E-6);
+
+        // directly above or below the listener is centered, whatever the azimuth
+        let (l, r) = equal_power_gains(90., 90.);
+        assert_float_eq!([l, r], [half, half], abs_all <= 1E-6);
+        let (l, r) = equal_power_gains(-90., -90.);
+        assert_float_eq!([l, r], [half, half], abs_all <= 1E-6);
+
+        // right at 60 degrees elevation: lateral component is sin(90) * cos(60) = 0.5,
+        // i.e. equal to a source at 30 degrees azimuth in the horizontal plane
+        let (l1, r1) = equal_power_gains(90., 60.);
+        let (l2, r2) = equal_power_gains(30., 0.);
+        assert_float_eq!([l1, r1], [l2, r2], abs_all <= 1E-6);
+
+        // power is preserved
+        let (l, r) = equal_power_gains(45., 20.);
+        assert_float_eq!(l * l + r * r, 1., abs <= 1E-6);
+    }
+
+    /// Render a source slightly to the right of the vertical axis of the listener with the
+    /// EqualPower panning model
+    fn render_equal_power_above_listener(elevation: bool, strict: bool) -> AudioBuffer {
+        let sample_rate = 44100.;
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, sample_rate);
+        context.set_strict_conformance(strict);
+
+        let mut src = context.create_constant_source();
+        src.start();
+
+        let options = PannerOptions {
+            panning_model: PanningModelType::EqualPower,
+            equal_power_elevation: elevation,
+            ..PannerOptions::default()
+        };
+        let panner = PannerNode::new(&context, options);
+        panner.position_x().set_value(0.001);
+        panner.position_y().set_value(1.);
+
+        src.connect(&panner);
+        panner.connect(&context.destination());
+
+        context.start_rendering_sync()
+    }
+
+    #[test]
+    fn test_equal_power_source_above_listener() {
+        // the spec ignores the elevation, the source is panned hard to the right ear
+        let output = render_equal_power_above_listener(false, false);
+        assert_float_eq!(output.get_channel_data(0)[0], 0., abs <= 1E-6);
+        assert_float_eq!(output.get_channel_data(1)[0], 1., abs <= 1E-6);
+
+        // nearly centered when the elevation is accounted for
+        let output = render_equal_power_above_listener(true, false);
+        let left = output.get_channel_data(0);
+        let right = output.get_channel_data(1);
+        assert_float_eq!(left[0], std::f32::consts::FRAC_1_SQRT_2, abs <= 1E-3);
+        assert_float_eq!(right[0], std::f32::consts::FRAC_1_SQRT_2, abs <= 1E-3);
+        assert!(right[0] > left[0]);
+
+        // the elevation is ignored in strict conformance mode
+        let output = render_equal_power_above_listener(true, true);
+        assert_float_eq!(output.get_channel_data(0)[0], 0., abs <= 1E-6);
+        assert_float_eq!(output.get_channel_data(1)[0], 1., abs <= 1E-6);
+    }
+
+    #[test]
+    fn test_set_equal_power_elevation() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+
+        let mut src = context.create_constant_source();
+        src.start();
+
+        let options = PannerOptions {
+            panning_model: PanningModelType::EqualPower,
+            ..PannerOptions::default()
+        };
+        let mut panner = PannerNode::new(&context, options);
+        assert!(!panner.equal_power_elevation());
+        panner.set_equal_power_elevation(true);
+        assert!(panner.equal_power_elevation());
+
+        panner.position_x().set_value(0.001);
+        panner.position_y().set_value(1.);
+        src.connect(&panner);
+        panner.connect(&context.destination());
+
+        let output = context.start_rendering_sync();
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        assert_float_eq!(output.get_channel_data(0)[0], half, abs <= 1E-3);
+        assert_float_eq!(output.get_channel_data(1)[0], half, abs <= 1E-3);
+    }
+
+    #[test]
+    fn test_equal_power_stereo_gains() {
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+
+        // front: both channels are kept on their side
+        let gains = equal_power_stereo_gains(0., 0.);
+        assert_float_eq!(gains[0], [1., 0.], abs_all <= 1E-6);
+        assert_float_eq!(gains[1], [0., 1.], abs_all <= 1E-6);
+
+        // hard left: the right channel is moved to the left
+        let gains = equal_power_stereo_gains(-90., 0.);
+        assert_float_eq!(gains[0], [1., 1.], abs_all <= 1E-6);
+        assert_float_eq!(gains[1], [0., 0.], abs_all <= 1E-6);
+
+        // hard right: the left channel is moved to the right
+        let gains = equal_power_stereo_gains(90., 0.);
+        assert_float_eq!(gains[0], [0., 0.], abs_all <= 1E-6);
+        assert_float_eq!(gains[1], [1., 1.], abs_all <= 1E-6);
+
+        // halfway to the right, the power of the left channel is split
+        let gains = equal_power_stereo_gains(45., 0.);
+        assert_float_eq!(gains[0], [half, 0.], abs_all <= 1E-6);
+        assert_float_eq!(gains[1], [half, 1.], abs_all <= 1E-6);
+
+        // elevation is accounted for like for mono inputs
+        let gains = equal_power_stereo_gains(-90., 90.);
+        assert_float_eq!(gains[0], [1., 0.], abs_all <= 1E-6);
+        assert_float_eq!(gains[1], [0., 1.], abs_all <= 1E-6);
+    }
+
+    /// Render the 2-channel input `[1., 0.5]` with the equal-power panning model
+    fn render_equal_power_stereo(position_x: f32) -> AudioBuffer {
+        let sample_rate = 44100.;
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, sample_rate);
+
+        let input = AudioBuffer::from(
+            vec![
+                vec![1.; RENDER_QUANTUM_SIZE],
+                vec![0.5; RENDER_QUANTUM_SIZE],
+            ],
+            sample_rate,
+        );
+        let mut src = AudioBufferSourceNode::new(&context, AudioBufferSourceOptions::default());
+        src.set_buffer(input);
+        src.start();
+
+        let options = PannerOptions {
+            panning_model: PanningModelType::EqualPower,
+            ..PannerOptions::default()
+        };
+        let panner = PannerNode::new(&context, options);
+        panner.position_x().set_value(position_x);
+        panner.position_z().set_value(-1.);
+
+        src.connect(&panner);
+        panner.connect(&context.destination());
+
+        context.start_rendering_sync()
+    }
+
+    #[test]
+    fn test_equal_power_stereo() {
+        // in front of the listener, the stereo image is preserved
+        let output = render_equal_power_stereo(0.);
+        assert_float_eq!(output.get_channel_data(0)[0], 1., abs <= 1E-6);
+        assert_float_eq!(output.get_channel_data(1)[0], 0.5, abs <= 1E-6);
+
+        // to the left, the right channel is moved to the left, at a distance of sqrt(2)
+        let gain = 1. / 2_f32.sqrt();
+        let output = render_equal_power_stereo(-1.);
+        assert_float_eq!(
+            output.get_channel_data(0)[0],
+            (1. + 0.5 * (PI / 4.).cos()) * gain,
+            abs <= 1E-6
+        );
+        assert_float_eq!(
+            output.get_channel_data(1)[0],
+            0.5 * (PI / 4.).sin() * gain,
+            abs <= 1E-6
+        );
+    }
+
+    #[test]
+    fn test_equal_power_identical_channels() {
+        let sample_rate = 44100.;
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, sample_rate);
+
+        // the mono source is upmixed to 2 channels, but panned as mono
+        let mut src = context.create_constant_source();
+        src.start();
+
+        let options = PannerOptions {
+            panning_model: PanningModelType::EqualPower,
+            ..PannerOptions::default()
+        };
+        let panner = PannerNode::new(&context, options);
+        assert_eq!(panner.channel_count(), 2);
+        panner.position_x().set_value(1.);
+
+        src.connect(&panner);
+        panner.connect(&context.destination());
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(output.get_channel_data(0)[0], 0., abs <= 1E-6);
+        assert_float_eq!(output.get_channel_data(1)[0], 1., abs <= 1E-6);
+    }
+
+    #[test]
+    fn test_set_position_orientation() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+
+        let mut src = context.create_constant_source();
+        src.start();
+
+        let panner = context.create_panner();
+        panner.set_position(-2., 0., 0.); // sound comes from the left
+        panner.set_orientation(0., 1., 0.);
+        assert_float_eq!(panner.position_x().value(), -2., abs <= 0.);
+        assert_float_eq!(panner.position_y().value(), 0., abs <= 0.);
+        assert_float_eq!(panner.position_z().value(), 0., abs <= 0.);
+        assert_float_eq!(panner.orientation_x().value(), 0., abs <= 0.);
+        assert_float_eq!(panner.orientation_y().value(), 1., abs <= 0.);
+        assert_float_eq!(panner.orientation_z().value(), 0., abs <= 0.);
+
+        src.connect(&panner);
+        panner.connect(&context.destination());
+
+        let output = context.start_rendering_sync();
+
+        // inverse distance model, ref_distance 1: gain 0.5, fully panned to the left
+        assert_float_eq!(
+            output.get_channel_data(0),
+            &[0.5; RENDER_QUANTUM_SIZE][..],
+            abs_all <= 1E-6
+        );
+        assert_float_eq!(
+            output.get_channel_data(1),
+            &[0.; RENDER_QUANTUM_SIZE][..],
+            abs_all <= 1E-6
+        );
+    }
+
     #[test]
     fn test_hrtf() {
         let sample_rate = 44100.;
@@ -997,4 +3116,835 @@ mod tests {
         let right = output.channel_data(1).as_slice();
         assert!(right[128..256].iter().any(|v| *v >= 1E-6));
     }
+
+    #[test]
+    fn test_hrtf_a_rate_listener() {
+        let sample_rate = 44100.;
+        let length = RENDER_QUANTUM_SIZE * 4;
+
+        // the listener moves away from the source at the last frame of the first render quantum
+        let render = |jump: bool| {
+            let context = OfflineAudioContext::new(2, length, sample_rate);
+            let mut src = context.create_constant_source();
+            src.start();
+
+            let options = PannerOptions {
+                panning_model: PanningModelType::HRTF,
+                ..PannerOptions::default()
+            };
+            let panner = PannerNode::new(&context, options);
+            panner.position_z().set_value(-1.);
+            src.connect(&panner);
+            panner.connect(&context.destination());
+
+            let listener = context.listener();
+            if jump {
+                let when = (RENDER_QUANTUM_SIZE - 1) as f64 / f64::from(sample_rate);
+                listener.position_z().set_value_at_time(9., when);
+            } else {
+                listener.position_z().set_value(9.);
+            }
+
+            context.start_rendering_sync()
+        };
+
+        // the gains are ramped towards the position at the end of the render quantum
+        let jump = render(true);
+        let reference = render(false);
+        for channel in 0..2 {
+            assert_float_eq!(
+                jump.get_channel_data(channel),
+                reference.get_channel_data(channel),
+                abs_all <= 1e-6
+            );
+        }
+    }
+
+    /// Render a 2-channel click from the right with the HRTF panning model
+    fn render_hrtf_stereo(left: f32, right: f32) -> AudioBuffer {
+        let sample_rate = 44100.;
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE * 2, sample_rate);
+
+        let input = AudioBuffer::from(
+            vec![
+                vec![left; RENDER_QUANTUM_SIZE],
+                vec![right; RENDER_QUANTUM_SIZE],
+            ],
+            sample_rate,
+        );
+        let mut src = AudioBufferSourceNode::new(&context, AudioBufferSourceOptions::default());
+        src.set_buffer(input);
+        src.start();
+
+        let options = PannerOptions {
+            panning_model: PanningModelType::HRTF,
+            ..PannerOptions::default()
+        };
+        let panner = PannerNode::new(&context, options);
+        panner.position_x().set_value(1.);
+
+        src.connect(&panner);
+        panner.connect(&context.destination());
+
+        context.start_rendering_sync()
+    }
+
+    #[test]
+    fn test_hrtf_stereo() {
+        let mono = render_hrtf_stereo(1., 1.);
+        let left_only = render_hrtf_stereo(1., 0.);
+        let right_only = render_hrtf_stereo(0., 1.);
+
+        // the left ear only hears the left channel, and the right ear the right channel
+        let left = left_only.get_channel_data(0);
+        assert!(left.iter().any(|v| v.abs() >= 1E-6));
+        assert_float_eq!(
+            left_only.get_channel_data(1),
+            &[0.; 256][..],
+            abs_all <= 1E-9
+        );
+        assert_float_eq!(
+            right_only.get_channel_data(0),
+            &[0.; 256][..],
+            abs_all <= 1E-9
+        );
+
+        // identical channels are spatialized as mono, the stereo channels add up to it
+        assert_float_eq!(mono.get_channel_data(0), left, abs_all <= 1E-6);
+        assert_float_eq!(
+            mono.get_channel_data(1),
+            right_only.get_channel_data(1),
+            abs_all <= 1E-6
+        );
+    }
+
+    #[test]
+    fn test_panning_model_crossfade() {
+        use crate::context::{AudioContext, AudioContextOptions};
+
+        // the panning model is changed while rendering, to a file sink
+        let path = std::env::temp_dir().join("web_audio_api_test_panning_model_crossfade.wav");
+        let options = AudioContextOptions {
+            sink_id: format!("file:{}?speed=4", path.display()),
+            sample_rate: Some(44100.),
+            ..AudioContextOptions::default()
+        };
+        let context = AudioContext::new(options);
+
+        let mut src = context.create_constant_source();
+        src.start();
+
+        let options = PannerOptions {
+            panning_model: PanningModelType::EqualPower,
+            ..PannerOptions::default()
+        };
+        let mut panner = PannerNode::new(&context, options);
+        panner.position_x().set_value(1.);
+        src.connect(&panner);
+        panner.connect(&context.destination());
+
+        while context.current_time() < 0.1 {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        panner.set_panning_model(PanningModelType::HRTF);
+        assert_eq!(panner.panning_model(), PanningModelType::HRTF);
+        while context.current_time() < 0.2 {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        context.close_sync();
+
+        let mut reader = hound::WavReader::open(&path).unwrap();
+        let samples: Vec<f32> = reader.samples().map(|s| s.unwrap()).collect();
+        std::fs::remove_file(path).unwrap();
+        let left: Vec<f32> = samples.iter().step_by(2).copied().collect();
+        let right: Vec<f32> = samples.iter().skip(1).step_by(2).copied().collect();
+
+        // hard right with equal-power panning, until the HRTF output is faded in (skip the
+        // first render quanta, until the listener is connected)
+        let start = 4410;
+        let switch = start + left[start..].iter().position(|v| v.abs() > 1E-6).unwrap();
+        assert_float_eq!(right[switch - 1], 1., abs <= 1E-6);
+        assert!(right[switch + 1000] < 0.99);
+
+        // without the discontinuity of a sudden switch, the steps are those of the HRTF output
+        let max_step = |channel: &[f32]| {
+            channel[switch - 1..switch + RENDER_QUANTUM_SIZE]
+                .windows(2)
+                .map(|w| (w[1] - w[0]).abs())
+                .fold(0., f32::max)
+        };
+        assert!(max_step(&left) < 0.2);
+        assert!(max_step(&right) < 0.2);
+    }
+
+    /// Render a click from the right with the HRTF panning model
+    fn render_hrtf(panner: &PannerNode, context: OfflineAudioContext) -> AudioBuffer {
+        let input = AudioBuffer::from(vec![vec![1.; RENDER_QUANTUM_SIZE]], context.sample_rate());
+        let mut src = AudioBufferSourceNode::new(&context, AudioBufferSourceOptions::default());
+        src.set_buffer(input);
+        src.start();
+
+        panner.position_x().set_value(1.);
+        src.connect(panner);
+        panner.connect(&context.destination());
+
+        context.start_rendering_sync()
+    }
+
+    #[test]
+    fn test_default_hrir_length() {
+        for sample_rate in [44100, 48000] {
+            let (_, len, _) = load_hrtf_processor(sample_rate, DEFAULT_HRTF_INTERPOLATION_STEPS);
+            assert_eq!(len, DEFAULT_HRIR_LENGTH);
+        }
+    }
+
+    #[test]
+    fn test_hrtf_interpolation_steps() {
+        let render = |steps: usize| {
+            let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE * 2, 44100.);
+            let mut panner = PannerNode::new(
+                &context,
+                PannerOptions {
+                    panning_model: PanningModelType::HRTF,
+                    ..PannerOptions::default()
+                },
+            );
+            panner.set_hrtf_interpolation_steps(steps).unwrap();
+            assert_eq!(panner.hrtf_interpolation_steps(), steps);
+            render_hrtf(&panner, context)
+        };
+        let coarse = render(1);
+        let smooth = render(4);
+
+        // the first render quantum moves from the front to the right of the listener, in 4 steps
+        let quantum = ..RENDER_QUANTUM_SIZE;
+        let difference = coarse.get_channel_data(1)[quantum]
+            .iter()
+            .zip(&smooth.get_channel_data(1)[quantum])
+            .map(|(c, s)| (c - s).abs())
+            .fold(0., f32::max);
+        assert!(difference > 1E-3);
+
+        // then the direction is the same
+        let quantum = RENDER_QUANTUM_SIZE..;
+        for channel in 0..2 {
+            assert_float_eq!(
+                coarse.get_channel_data(channel)[quantum.clone()],
+                smooth.get_channel_data(channel)[quantum.clone()],
+                abs_all <= 1E-5
+            );
+        }
+
+        // same as the option
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE * 2, 44100.);
+        let options = PannerOptions {
+            panning_model: PanningModelType::HRTF,
+            hrtf_interpolation_steps: 4,
+            ..PannerOptions::default()
+        };
+        let mut panner = PannerNode::new(&context, options);
+
+        // the FFT of the convolution of 16 samples needs more scratch space than provided
+        assert!(panner.set_hrtf_interpolation_steps(8).is_err());
+        assert_eq!(panner.hrtf_interpolation_steps(), 4);
+        let output = render_hrtf(&panner, context);
+        assert_float_eq!(
+            output.get_channel_data(1),
+            smooth.get_channel_data(1),
+            abs_all <= 0.
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_hrtf_interpolation_steps() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        let mut panner = context.create_panner();
+        let _ = panner.set_hrtf_interpolation_steps(3);
+    }
+
+    #[test]
+    fn test_hrtf_source() {
+        let length = RENDER_QUANTUM_SIZE * 2;
+        let context = OfflineAudioContext::new(2, length, 44100.);
+        let panner = PannerNode::new(
+            &context,
+            PannerOptions {
+                panning_model: PanningModelType::HRTF,
+                ..PannerOptions::default()
+            },
+        );
+        let expected = render_hrtf(&panner, context);
+
+        // the included sphere, loaded from bytes
+        let resource = include_bytes!("../../resources/IRC_1003_C.bin");
+        let source = HrirSource::Bytes(resource.to_vec());
+        let context = OfflineAudioContext::new(2, length, 44100.);
+        let options = PannerOptions {
+            panning_model: PanningModelType::HRTF,
+            hrtf_source: source.clone(),
+            ..PannerOptions::default()
+        };
+        let panner = PannerNode::try_new(&context, options).unwrap();
+        assert_eq!(panner.hrtf_source(), &source);
+        let output = render_hrtf(&panner, context);
+
+        assert_float_eq!(
+            output.get_channel_data(0),
+            expected.get_channel_data(0),
+            abs_all <= 0.
+        );
+        assert_float_eq!(
+            output.get_channel_data(1),
+            expected.get_channel_data(1),
+            abs_all <= 0.
+        );
+    }
+
+    #[test]
+    fn test_invalid_hrtf_source() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+
+        let options = PannerOptions {
+            hrtf_source: HrirSource::Bytes(b"HRIR".to_vec()),
+            ..PannerOptions::default()
+        };
+        assert!(PannerNode::try_new(&context, options).is_err());
+
+        let options = PannerOptions {
+            hrtf_source: HrirSource::Bytes(vec![0; 64]),
+            ..PannerOptions::default()
+        };
+        let error = PannerNode::try_new(&context, options).err().unwrap();
+        assert!(error.to_string().starts_with("InvalidData"));
+
+        let mut panner = context.create_panner();
+        let source = HrirSource::Path("does/not/exist.bin".into());
+        assert!(panner.set_hrtf_source(source).is_err());
+        assert_eq!(panner.hrtf_source(), &HrirSource::Default);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_hrtf_source_panics() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        let options = PannerOptions {
+            hrtf_source: HrirSource::Path("does/not/exist.bin".into()),
+            ..PannerOptions::default()
+        };
+        PannerNode::new(&context, options);
+    }
+
+    #[test]
+    fn test_sofa_hrtf() {
+        let sample_rate = 48000.;
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE * 2, sample_rate);
+
+        let impulse_buffer = |context: &OfflineAudioContext| {
+            let mut impulse = context.create_buffer(1, 1, sample_rate);
+            impulse.copy_to_channel(&[1.], 0);
+            impulse
+        };
+        let mut src = AudioBufferSourceNode::new(&context, AudioBufferSourceOptions::default());
+        src.set_buffer(impulse_buffer(&context));
+        src.start();
+
+        // the left ear responses are positive, the right ear responses negative
+        let sofa = crate::sofa::tests::test_sofa_file(true);
+        let hrtf = SofaHrtf::from_bytes(&sofa).unwrap();
+
+        let mut panner = context.create_panner();
+        panner.set_hrtf(&hrtf);
+        assert_eq!(panner.panning_model(), PanningModelType::HRTF);
+        assert_eq!(panner.hrtf_source(), &HrirSource::Sofa(hrtf.clone()));
+        panner.position_x().set_value(-1.); // sound comes from the left
+
+        // the impulse responses are kept when switching panning models
+        panner.set_panning_model(PanningModelType::EqualPower);
+        panner.set_panning_model(PanningModelType::HRTF);
+
+        src.connect(&panner);
+        panner.connect(&context.destination());
+
+        let output = context.start_rendering_sync();
+        let left: f32 = output.get_channel_data(0).iter().sum();
+        let right: f32 = output.get_channel_data(1).iter().sum();
+        assert!(left > 0.5);
+        assert!(right < -0.5);
+
+        // the same impulse responses, as an HRIR source of the options
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE * 2, sample_rate);
+        let options = PannerOptions {
+            panning_model: PanningModelType::HRTF,
+            hrtf_source: HrirSource::Sofa(hrtf),
+            ..PannerOptions::default()
+        };
+        let panner = PannerNode::try_new(&context, options).unwrap();
+        panner.position_x().set_value(-1.);
+
+        let mut src = AudioBufferSourceNode::new(&context, AudioBufferSourceOptions::default());
+        src.set_buffer(impulse_buffer(&context));
+        src.start();
+        src.connect(&panner);
+        panner.connect(&context.destination());
+
+        let expected = output;
+        let output = context.start_rendering_sync();
+        assert_float_eq!(
+            output.get_channel_data(0),
+            expected.get_channel_data(0),
+            abs_all <= 0.
+        );
+        assert_float_eq!(
+            output.get_channel_data(1),
+            expected.get_channel_data(1),
+            abs_all <= 0.
+        );
+    }
+
+    #[test]
+    fn test_near_field_gains() {
+        // distant sources and disabled compensation
+        assert_eq!(near_field_gains(90., 0., 1., 0.0875, 1.), (1., 1.));
+        assert_eq!(near_field_gains(90., 0., 5., 0.0875, 1.), (1., 1.));
+        assert_eq!(near_field_gains(90., 0., 0.2, 0., 1.), (1., 1.));
+
+        // continuous at the near-field distance
+        let (l, r) = near_field_gains(90., 0., 0.999, 0.0875, 1.);
+        assert_float_eq!(l, 1., abs <= 1e-3);
+        assert_float_eq!(r, 1., abs <= 1e-3);
+
+        // source close to the right ear
+        let (l, r) = near_field_gains(90., 0., 0.2, 0.0875, 1.);
+        assert!(r > 1.5);
+        assert!(l < 0.9);
+
+        // no level difference when in front or above
+        let (l, r) = near_field_gains(0., 0., 0.2, 0.0875, 1.);
+        assert_float_eq!(l, r, abs <= 1e-6);
+        let (l, r) = near_field_gains(30., 90., 0.2, 0.0875, 1.);
+        assert_float_eq!(l, r, abs <= 1e-6);
+
+        // the source is kept outside of the head
+        let (l, r) = near_field_gains(-90., 0., 0., 0.0875, 1.);
+        assert!(l.is_finite() && r.is_finite());
+        assert!(l > r);
+    }
+
+    #[test]
+    fn test_near_field_compensation() {
+        let render = |head_radius: f32| {
+            let sample_rate = 44100.;
+            let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE * 4, sample_rate);
+
+            let mut src = context.create_constant_source();
+            src.start();
+
+            let mut panner = context.create_panner();
+            panner.set_panning_model(PanningModelType::HRTF);
+            assert_eq!(panner.head_radius(), 0.0875);
+            assert_eq!(panner.near_field_distance(), 1.);
+            panner.set_head_radius(head_radius);
+            panner.position_x().set_value(0.25); // close to the right ear
+
+            src.connect(&panner);
+            panner.connect(&context.destination());
+
+            let output = context.start_rendering_sync();
+            let energy = |channel: &[f32]| channel[256..].iter().map(|v| v * v).sum::<f32>();
+            energy(output.get_channel_data(1)) / energy(output.get_channel_data(0))
+        };
+
+        // larger interaural level difference with compensation
+        assert!(render(0.0875) > 2. * render(0.));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_head_radius() {
+        let context = OfflineAudioContext::new(2, 128, 44100.);
+        let mut panner = context.create_panner();
+        panner.set_head_radius(-1.);
+    }
+
+    #[test]
+    fn test_set_transform() {
+        use crate::{Handedness, UpAxis};
+
+        let context = OfflineAudioContext::new(2, 128, 44100.);
+        let panner = context.create_panner();
+
+        // Unity: translation only, facing forward (+Z)
+        let unity = CoordinateSystem {
+            handedness: Handedness::Left,
+            up_axis: UpAxis::Y,
+        };
+        let mut transform = [[0.; 4]; 4];
+        (0..4).for_each(|i| transform[i][i] = 1.);
+        transform[3] = [1., 2., 3., 1.];
+        panner.set_transform(transform, unity);
+
+        assert_eq!(panner.position_x().value(), 1.);
+        assert_eq!(panner.position_y().value(), 2.);
+        assert_eq!(panner.position_z().value(), -3.);
+        assert_eq!(panner.orientation_x().value(), 0.);
+        assert_eq!(panner.orientation_y().value(), 0.);
+        assert_eq!(panner.orientation_z().value(), -1.);
+    }
+
+    /// Distance gain of a source in front of the listener, at the given distance
+    fn render_distance_gain(
+        distance_model: DistanceModelType,
+        ref_distance: f64,
+        max_distance: f64,
+        rolloff_factor: f64,
+        distance: f32,
+    ) -> f32 {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+
+        let mut src = context.create_constant_source();
+        src.start();
+
+        let mut panner = context.create_panner();
+        panner.set_distance_model(distance_model);
+        panner.set_ref_distance(ref_distance);
+        panner.set_max_distance(max_distance);
+        panner.set_rolloff_factor(rolloff_factor);
+        assert_eq!(panner.distance_model(), distance_model);
+        panner.position_z().set_value(-distance);
+
+        src.connect(&panner);
+        panner.connect(&context.destination());
+
+        let output = context.start_rendering_sync();
+        // equal-power panning in the center gives a gain of cos(pi / 4) per ear
+        output.get_channel_data(0)[0] / std::f32::consts::FRAC_1_SQRT_2
+    }
+
+    #[test]
+    fn test_distance_models() {
+        let linear = |r, m, f, d| render_distance_gain(DistanceModelType::Linear, r, m, f, d);
+        assert_float_eq!(linear(1., 11., 1., 0.5), 1., abs <= 1e-6);
+        assert_float_eq!(linear(1., 11., 1., 6.), 0.5, abs <= 1e-6);
+        assert_float_eq!(linear(1., 11., 0.5, 11.), 0.5, abs <= 1e-6);
+        assert_float_eq!(linear(1., 11., 1., 20.), 0., abs <= 1e-6);
+        // the rolloff factor is clamped to 1
+        assert_float_eq!(linear(1., 11., 4., 20.), 0., abs <= 1e-6);
+        // ref and max distance are swapped when inverted
+        assert_float_eq!(linear(11., 1., 1., 6.), 0.5, abs <= 1e-6);
+        // equal ref and max distance
+        assert_float_eq!(linear(2., 2., 0.75, 1.), 1., abs <= 1e-6);
+        assert_float_eq!(linear(2., 2., 0.75, 3.), 0.25, abs <= 1e-6);
+
+        let inverse = |r, f, d| render_distance_gain(DistanceModelType::Inverse, r, 100., f, d);
+        assert_float_eq!(inverse(1., 1., 0.5), 1., abs <= 1e-6);
+        assert_float_eq!(inverse(1., 1., 4.), 0.25, abs <= 1e-6);
+        assert_float_eq!(inverse(2., 0.5, 6.), 0.5, abs <= 1e-6);
+
+        let exponential =
+            |r, f, d| render_distance_gain(DistanceModelType::Exponential, r, 100., f, d);
+        assert_float_eq!(exponential(1., 2., 0.5), 1., abs <= 1e-6);
+        assert_float_eq!(exponential(1., 2., 4.), 1. / 16., abs <= 1e-6);
+        assert_float_eq!(exponential(2., 0.5, 8.), 0.5, abs <= 1e-6);
+        assert_float_eq!(exponential(0., 1., 8.), 0., abs <= 1e-6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_ref_distance() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        context.create_panner().set_ref_distance(-1.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_max_distance() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        let options = PannerOptions {
+            max_distance: 0.,
+            ..PannerOptions::default()
+        };
+        PannerNode::new(&context, options);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_rolloff_factor() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        context.create_panner().set_rolloff_factor(-1.);
+    }
+
+    #[test]
+    fn test_distance_curve_lookup_table() {
+        let curve = DistanceCurve::lookup_table(&[(1., 1.), (3., 0.5), (5., 0.)]);
+        assert_float_eq!(curve.gain(0.), 1., abs <= 0.);
+        assert_float_eq!(curve.gain(1.), 1., abs <= 0.);
+        assert_float_eq!(curve.gain(2.), 0.75, abs <= 1e-6);
+        assert_float_eq!(curve.gain(3.), 0.5, abs <= 1e-6);
+        assert_float_eq!(curve.gain(4.5), 0.125, abs <= 1e-6);
+        assert_float_eq!(curve.gain(100.), 0., abs <= 0.);
+
+        let single = DistanceCurve::lookup_table(&[(1., 0.3)]);
+        assert_float_eq!(single.gain(0.), 0.3, abs <= 0.);
+        assert_float_eq!(single.gain(10.), 0.3, abs <= 0.);
+
+        let sampled = DistanceCurve::from_fn(|d| 1. / (1. + d), 10., 101);
+        assert_float_eq!(sampled.gain(1.), 0.5, abs <= 1e-6);
+        assert_float_eq!(sampled.gain(20.), 1. / 11., abs <= 1e-6);
+    }
+
+    #[test]
+    fn test_distance_curve_monotonic_spline() {
+        let points = [(0., 1.), (1., 0.9), (2., 0.2), (10., 0.1), (20., 0.)];
+        let curve = DistanceCurve::monotonic_spline(&points);
+
+        // passes through the points
+        points.iter().for_each(|&(distance, gain)| {
+            assert_float_eq!(curve.gain(distance), gain, abs <= 1e-6);
+        });
+
+        // monotonic, without overshoot
+        let mut prev = curve.gain(0.);
+        for i in 1..=2000 {
+            let gain = curve.gain(i as f32 / 100.);
+            assert!(gain <= prev + 1e-6);
+            assert!((0. ..=1.).contains(&gain));
+            prev = gain;
+        }
+
+        // flat segments stay flat
+        let curve = DistanceCurve::monotonic_spline(&[(0., 1.), (2., 1.), (4., 0.)]);
+        assert_float_eq!(curve.gain(1.), 1., abs <= 1e-6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_distance_curve_invalid() {
+        DistanceCurve::lookup_table(&[(2., 1.), (1., 0.5)]);
+    }
+
+    #[test]
+    fn test_distance_curve_render() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+
+        let mut src = context.create_constant_source();
+        src.start();
+
+        let mut panner = context.create_panner();
+        let curve = DistanceCurve::lookup_table(&[(0., 1.), (10., 0.)]);
+        panner.set_distance_curve(Some(curve.clone()));
+        assert_eq!(panner.distance_curve(), Some(&curve));
+        panner.position_z().set_value(-5.); // in front of the listener, at half the curve
+
+        src.connect(&panner);
+        panner.connect(&context.destination());
+
+        let output = context.start_rendering_sync();
+        // equal-power panning in the center gives a gain of cos(pi / 4) per ear
+        let expected = 0.5 * std::f32::consts::FRAC_1_SQRT_2;
+        assert_float_eq!(output.get_channel_data(0)[0], expected, abs <= 1e-6);
+        assert_float_eq!(output.get_channel_data(1)[0], expected, abs <= 1e-6);
+    }
+
+    fn render_doppler(doppler_factor: f32, position_z: impl FnOnce(&AudioParam)) -> AudioBuffer {
+        let sample_rate = 44100.;
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE * 8, sample_rate);
+
+        // impulse at the first frame
+        let mut impulse = vec![0.; RENDER_QUANTUM_SIZE];
+        impulse[0] = 1.;
+        let mut src = AudioBufferSourceNode::new(&context, AudioBufferSourceOptions::default());
+        src.set_buffer(AudioBuffer::from(vec![impulse], sample_rate));
+        src.start();
+
+        let options = PannerOptions {
+            panning_model: PanningModelType::EqualPower,
+            doppler_factor,
+            ..PannerOptions::default()
+        };
+        let panner = PannerNode::new(&context, options);
+        position_z(panner.position_z());
+
+        src.connect(&panner);
+        panner.connect(&context.destination());
+        context.start_rendering_sync()
+    }
+
+    #[test]
+    fn test_doppler_delay() {
+        // 441 frames of propagation delay at 3.43 meters
+        let output = render_doppler(1., |z| {
+            z.set_value(-3.43);
+        });
+        let channel = output.get_channel_data(0);
+        let first = channel.iter().position(|v| *v != 0.).unwrap();
+        assert_eq!(first, 441);
+        assert_float_eq!(
+            channel[first],
+            std::f32::consts::FRAC_1_SQRT_2 / 3.43,
+            abs <= 1e-4
+        );
+
+        // half the delay with half the Doppler factor
+        let output = render_doppler(0.5, |z| {
+            z.set_value(-3.43);
+        });
+        let first = output.get_channel_data(0).iter().position(|v| *v != 0.);
+        assert_eq!(first, Some(220));
+    }
+
+    #[test]
+    fn test_doppler_disabled() {
+        let output = render_doppler(0., |z| {
+            z.set_value(-3.43);
+        });
+        let first = output.get_channel_data(0).iter().position(|v| *v != 0.);
+        assert_eq!(first, Some(0));
+
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        let mut panner = context.create_panner();
+        assert_eq!(panner.doppler_factor(), 0.);
+        assert_eq!(panner.speed_of_sound(), DEFAULT_SPEED_OF_SOUND);
+        panner.set_doppler_factor(1.);
+        panner.set_speed_of_sound(340.);
+        assert_eq!(panner.doppler_factor(), 1.);
+        assert_eq!(panner.speed_of_sound(), 340.);
+    }
+
+    #[test]
+    fn test_doppler_a_rate_position() {
+        // the source is at 3.43 meters when the impulse is played, and recedes until it is heard
+        let output = render_doppler(1., |z| {
+            z.set_value_at_time(-3.43, 0.);
+            z.linear_ramp_to_value_at_time(-6.86, 1.);
+        });
+        let first = output.get_channel_data(0).iter().position(|v| *v != 0.);
+        assert!(matches!(first, Some(442..=446)), "{first:?}");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_doppler_factor() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        context.create_panner().set_doppler_factor(-1.);
+    }
+
+    fn render_air_absorption(air_absorption: bool, distance: f32) -> f32 {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE * 8, 44100.);
+        let mut osc = context.create_oscillator();
+        osc.frequency().set_value(10000.);
+        osc.start();
+
+        let options = PannerOptions {
+            panning_model: PanningModelType::EqualPower,
+            position_z: -distance,
+            rolloff_factor: 0.,
+            air_absorption,
+            ..PannerOptions::default()
+        };
+        let panner = PannerNode::new(&context, options);
+        osc.connect(&panner);
+        panner.connect(&context.destination());
+
+        let output = context.start_rendering_sync();
+        let channel = &output.get_channel_data(0)[RENDER_QUANTUM_SIZE..];
+        (channel.iter().map(|v| v * v).sum::<f32>() / channel.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn test_air_absorption() {
+        // the high frequencies of distant sources are absorbed
+        let dry = render_air_absorption(false, 1000.);
+        let absorbed = render_air_absorption(true, 1000.);
+        assert!(absorbed < 0.3 * dry, "{absorbed} {dry}");
+
+        // but not the ones of close sources
+        let dry = render_air_absorption(false, 1.);
+        let absorbed = render_air_absorption(true, 1.);
+        assert!(absorbed > 0.9 * dry, "{absorbed} {dry}");
+
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        let mut panner = context.create_panner();
+        assert!(!panner.air_absorption());
+        assert_eq!(
+            panner.air_absorption_coefficient(),
+            DEFAULT_AIR_ABSORPTION_COEFFICIENT
+        );
+        panner.set_air_absorption(true);
+        panner.set_air_absorption_coefficient(0.01);
+        assert!(panner.air_absorption());
+        assert_eq!(panner.air_absorption_coefficient(), 0.01);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_air_absorption_coefficient() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        context.create_panner().set_air_absorption_coefficient(-1.);
+    }
+
+    fn render_occlusion(occlusion: f32, obstruction: f32, frequency: f32) -> f32 {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE * 8, 44100.);
+        let mut osc = context.create_oscillator();
+        osc.frequency().set_value(frequency);
+        osc.start();
+
+        let options = PannerOptions {
+            panning_model: PanningModelType::EqualPower,
+            occlusion,
+            obstruction,
+            ..PannerOptions::default()
+        };
+        let panner = PannerNode::new(&context, options);
+        osc.connect(&panner);
+        panner.connect(&context.destination());
+
+        let output = context.start_rendering_sync();
+        let channel = &output.get_channel_data(0)[RENDER_QUANTUM_SIZE..];
+        (channel.iter().map(|v| v * v).sum::<f32>() / channel.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn test_occlusion() {
+        // the high frequencies are filtered
+        let dry = render_occlusion(0., 0., 10000.);
+        let obstructed = render_occlusion(0., 1., 10000.);
+        let occluded = render_occlusion(1., 0., 10000.);
+        assert!(obstructed < 0.2 * dry, "{obstructed} {dry}");
+        assert!(occluded < 0.1 * obstructed, "{occluded} {obstructed}");
+
+        // the low frequencies are attenuated
+        let dry = render_occlusion(0., 0., 50.);
+        let occluded = render_occlusion(1., 0., 50.);
+        let expected = crate::db_to_gain(-OCCLUSION_ATTENUATION_DB) * dry;
+        assert_float_eq!(occluded, expected, rmax <= 0.05);
+
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        let mut panner = context.create_panner();
+        assert_eq!(panner.occlusion(), 0.);
+        assert_eq!(panner.obstruction(), 0.);
+        panner.set_occlusion(0.5);
+        panner.set_obstruction(1.);
+        assert_eq!(panner.occlusion(), 0.5);
+        assert_eq!(panner.obstruction(), 1.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_occlusion() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        context.create_panner().set_occlusion(1.5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_speed_of_sound() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        let options = PannerOptions {
+            speed_of_sound: 0.,
+            ..PannerOptions::default()
+        };
+        PannerNode::new(&context, options);
+    }
 }
@@ -0,0 +1,267 @@
+use std::any::Any;
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::AudioParam;
+use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
+
+use super::{AudioNode, ChannelConfig, ChannelConfigOptions};
+
+/// Function evaluated by a [`ParamExpressionNode`], from the values of the source params to the
+/// output value
+type Expression = Box<dyn FnMut(&[f32]) -> f32 + Send>;
+
+/// AudioNode computing a value from the values of other params, once per render quantum
+///
+/// The node outputs the result of a pure function of the current values of its source params,
+/// e.g. `1. / (1. + distance)`. Connect it to an `AudioParam` (see
+/// [`AudioParam::bind_expression`]) to compute the param on the render thread instead of using
+/// chains of helper nodes for simple math. Audio signals can be used in the expression by
+/// connecting them to a source param.
+///
+/// The expression is evaluated at the start of each render quantum, with the first value of each
+/// source param in this quantum (i.e. as a k-rate param). Non finite results are ignored, the
+/// node keeps its previous output instead.
+///
+/// The expression stops being evaluated once the node is dropped.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+///
+/// let context = AudioContext::default();
+///
+/// let mut distance = context.create_constant_source();
+/// distance.offset().set_value(3.);
+/// distance.start();
+///
+/// let gain = context.create_gain();
+/// gain.connect(&context.destination());
+///
+/// // gain = 1 / (1 + distance)
+/// let _binding = gain
+///     .gain()
+///     .bind_expression(&[distance.offset()], |values| 1. / (1. + values[0]));
+/// ```
+pub struct ParamExpressionNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    number_of_sources: usize,
+}
+
+impl AudioNode for ParamExpressionNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        0
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl ParamExpressionNode {
+    /// Create a node evaluating `expression` with the values of `sources`, in the same order
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any source param does not belong to the given context
+    pub fn new<C, F>(context: &C, sources: &[&AudioParam], expression: F) -> Self
+    where
+        C: BaseAudioContext,
+        F: FnMut(&[f32]) -> f32 + Send + 'static,
+    {
+        sources.iter().for_each(|source| {
+            if source.context() != context.base() {
+                panic!("InvalidAccessError: Attempting to use a param of a different context");
+            }
+        });
+
+        let node = context.register(move |registration| {
+            let render = ParamExpressionRenderer {
+                sources: sources.iter().map(|p| AudioParamId::of(p)).collect(),
+                values: vec![0.; sources.len()],
+                value: 0.,
+                expression: Box::new(expression),
+            };
+
+            let node = ParamExpressionNode {
+                registration,
+                channel_config: ChannelConfigOptions::default().into(),
+                number_of_sources: sources.len(),
+            };
+
+            (node, Box::new(render))
+        });
+
+        // connect the sources to the 'hidden' input port, so they are rendered first
+        sources.iter().for_each(|source| {
+            context.base().connect(
+                source.registration().id(),
+                node.registration().id(),
+                0,
+                usize::MAX,
+            );
+        });
+
+        node
+    }
+
+    /// Number of params the expression is computed from
+    pub fn number_of_sources(&self) -> usize {
+        self.number_of_sources
+    }
+}
+
+struct ParamExpressionRenderer {
+    sources: Vec<AudioParamId>,
+    /// values of the sources in the current render quantum, passed to the expression
+    values: Vec<f32>,
+    /// last finite result of the expression
+    value: f32,
+    expression: Expression,
+}
+
+impl AudioProcessor for ParamExpressionRenderer {
+    fn process(
+        &mut self,
+        _inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        _scope: &RenderScope,
+    ) -> bool {
+        // single output node
+        let output = &mut outputs[0];
+
+        self.values
+            .iter_mut()
+            .zip(self.sources.iter())
+            .for_each(|(value, source)| *value = params.get(source)[0]);
+
+        let value = (self.expression)(&self.values);
+        if value.is_finite() {
+            self.value = value;
+        }
+
+        output.force_mono();
+        output.channel_data_mut(0).fill(self.value);
+
+        false
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        log::warn!("ParamExpressionRenderer: Dropping incoming message {msg:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::{AudioNode, AudioScheduledSourceNode};
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_bind_expression() {
+        let context = OfflineAudioContext::new(1, 256, 48000.);
+
+        let mut distance = context.create_constant_source();
+        distance.offset().set_value(3.);
+        distance.start();
+
+        let mut src = context.create_constant_source();
+        src.start();
+        let gain = context.create_gain();
+        src.connect(&gain);
+        gain.connect(&context.destination());
+
+        let binding = gain
+            .gain()
+            .bind_expression(&[distance.offset()], |values| 1. / (1. + values[0]));
+        assert_eq!(binding.number_of_sources(), 1);
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(output.get_channel_data(0), &[0.25; 256][..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_multiple_sources() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+
+        let a = context.create_gain();
+        a.gain().set_value(2.);
+        let b = context.create_gain();
+        b.gain().set_value(5.);
+
+        let mut src = context.create_constant_source();
+        src.start();
+        src.connect(&context.destination());
+
+        let _binding = src
+            .offset()
+            .bind_expression(&[a.gain(), b.gain()], |values| values[0] * values[1]);
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(output.get_channel_data(0), &[10.; 128][..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_non_finite_result() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+
+        let mut src = context.create_constant_source();
+        src.start();
+        src.connect(&context.destination());
+
+        let _binding = src.offset().bind_expression(&[], |_| f32::NAN);
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(output.get_channel_data(0), &[0.; 128][..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_source_owner_dropped() {
+        let context = OfflineAudioContext::new(1, 128 * 4, 48000.);
+
+        let mut src = context.create_constant_source();
+        src.start();
+        src.connect(&context.destination());
+
+        let mut distance = context.create_constant_source();
+        distance.offset().set_value(1.);
+        distance.start();
+        distance.stop_at(128. / 48000.);
+
+        let _binding = src
+            .offset()
+            .bind_expression(&[distance.offset()], |values| values[0] + 1.);
+        // the owner of the source param is freed once stopped
+        drop(distance);
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(output.get_channel_data(0), &[2.; 512][..], abs_all <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_different_contexts() {
+        let context1 = OfflineAudioContext::new(1, 128, 48000.);
+        let context2 = OfflineAudioContext::new(1, 128, 48000.);
+        let gain1 = context1.create_gain();
+        let gain2 = context2.create_gain();
+
+        let _ = gain1
+            .gain()
+            .bind_expression(&[gain2.gain()], |values| values[0]);
+    }
+}
@@ -0,0 +1,459 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use realfft::num_complex::Complex;
+use realfft::{ComplexToReal, RealToComplex};
+
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::dsp::{plan_fft_forward, plan_fft_inverse};
+use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
+use crate::{AtomicF32, RENDER_QUANTUM_SIZE};
+
+use super::{AudioNode, ChannelConfig, ChannelConfigOptions, ChannelInterpretation};
+
+/// Ratio of the highest key maximum of the normalized square difference function above which
+/// the first key maximum is picked as the period (the `k` constant of the McLeod pitch method)
+const KEY_MAXIMUM_CUTOFF: f32 = 0.9;
+
+/// Mean square of the analysis window below which it is considered silent (-100 dBFS)
+const SILENCE_MEAN_SQUARE: f32 = 1e-10;
+
+/// Options for constructing a [`PitchDetectorNode`]
+#[derive(Clone, Debug)]
+pub struct PitchDetectorOptions {
+    /// size of the analysis window, in sample-frames
+    pub window_size: usize,
+    /// lowest detected frequency, in Hertz
+    pub min_frequency: f32,
+    /// highest detected frequency, in Hertz
+    pub max_frequency: f32,
+    pub channel_config: ChannelConfigOptions,
+}
+
+impl Default for PitchDetectorOptions {
+    fn default() -> Self {
+        Self {
+            window_size: 2048,
+            min_frequency: 50.,
+            max_frequency: 2000.,
+            channel_config: ChannelConfigOptions::default(),
+        }
+    }
+}
+
+/// `PitchDetectorNode` estimates the fundamental frequency of its input
+///
+/// The input is down mixed to mono and passed through unchanged. At each render quantum, the
+/// pitch of the last `window_size` sample-frames is estimated with the McLeod pitch method
+/// (MPM), along with its clarity: a value between 0 and 1 measuring how periodic the signal
+/// is. Both values can be read with [`frequency`](Self::frequency) and
+/// [`clarity`](Self::clarity) from the control thread.
+///
+/// The window should span at least two periods of the lowest detected frequency, periods
+/// longer than half the window are not detected.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::media_devices::{self, MediaStreamConstraints};
+/// use web_audio_api::node::{AudioNode, PitchDetectorNode, PitchDetectorOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let mic = media_devices::get_user_media_sync(MediaStreamConstraints::Audio);
+/// let source = context.create_media_stream_source(&mic);
+///
+/// let detector = PitchDetectorNode::new(&context, PitchDetectorOptions::default());
+/// source.connect(&detector);
+///
+/// loop {
+///     if detector.clarity() > 0.9 {
+///         println!("{:.1} Hz", detector.frequency());
+///     }
+///     std::thread::sleep(std::time::Duration::from_millis(100));
+/// }
+/// ```
+pub struct PitchDetectorNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    window_size: usize,
+    min_frequency: f32,
+    max_frequency: f32,
+    frequency: Arc<AtomicF32>,
+    clarity: Arc<AtomicF32>,
+}
+
+impl AudioNode for PitchDetectorNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl PitchDetectorNode {
+    /// Create a new `PitchDetectorNode`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the window size is smaller than two render quanta, or if the frequency range
+    /// is empty or not strictly positive
+    pub fn new<C: BaseAudioContext>(context: &C, options: PitchDetectorOptions) -> Self {
+        let PitchDetectorOptions {
+            window_size,
+            min_frequency,
+            max_frequency,
+            channel_config,
+        } = options;
+
+        assert!(
+            window_size >= 2 * RENDER_QUANTUM_SIZE,
+            "RangeError - window size ({:?}) should be at least {:?}",
+            window_size,
+            2 * RENDER_QUANTUM_SIZE
+        );
+        assert!(
+            min_frequency > 0. && min_frequency < max_frequency && max_frequency.is_finite(),
+            "RangeError - invalid frequency range [{:?}, {:?}]",
+            min_frequency,
+            max_frequency
+        );
+
+        context.register(move |registration| {
+            let sample_rate = context.sample_rate();
+            // the first lag is kept out of the range for the parabolic interpolation
+            let min_lag = ((sample_rate / max_frequency).floor() as usize).max(1);
+            let max_lag = ((sample_rate / min_frequency).ceil() as usize)
+                .min(window_size / 2)
+                .max(min_lag);
+
+            let frequency = Arc::new(AtomicF32::new(0.));
+            let clarity = Arc::new(AtomicF32::new(0.));
+
+            // zero padded to twice the window size, for a linear autocorrelation
+            let r2c = plan_fft_forward(2 * window_size);
+            let c2r = plan_fft_inverse(2 * window_size);
+            let fft_input = r2c.make_input_vec();
+            let spectrum = r2c.make_output_vec();
+            let scratch_len = r2c.get_scratch_len().max(c2r.get_scratch_len());
+            let scratch = vec![Complex::default(); scratch_len];
+
+            let render = PitchDetectorRenderer {
+                ring_buffer: vec![0.; window_size],
+                write_index: 0,
+                silent_frames: window_size,
+                min_lag,
+                max_lag,
+                r2c,
+                c2r,
+                fft_input,
+                spectrum,
+                scratch,
+                nsdf: vec![0.; max_lag + 2],
+                frequency: Arc::clone(&frequency),
+                clarity: Arc::clone(&clarity),
+            };
+
+            let node = PitchDetectorNode {
+                registration,
+                channel_config: channel_config.into(),
+                window_size,
+                min_frequency,
+                max_frequency,
+                frequency,
+                clarity,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// Size of the analysis window, in sample-frames
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+
+    /// Lowest detected frequency, in Hertz
+    pub fn min_frequency(&self) -> f32 {
+        self.min_frequency
+    }
+
+    /// Highest detected frequency, in Hertz
+    pub fn max_frequency(&self) -> f32 {
+        self.max_frequency
+    }
+
+    /// Fundamental frequency of the last analysis window, in Hertz
+    ///
+    /// Returns 0 if no pitch was detected, e.g. when the input is silent.
+    pub fn frequency(&self) -> f32 {
+        self.frequency.load(Ordering::Relaxed)
+    }
+
+    /// Clarity of the pitch of the last analysis window, from 0 (unpitched) to 1 (perfectly
+    /// periodic)
+    pub fn clarity(&self) -> f32 {
+        self.clarity.load(Ordering::Relaxed)
+    }
+}
+
+struct PitchDetectorRenderer {
+    ring_buffer: Vec<f32>,
+    write_index: usize,
+    /// number of silent frames in a row at the end of the ring buffer
+    silent_frames: usize,
+    min_lag: usize,
+    max_lag: usize,
+    r2c: Arc<dyn RealToComplex<f32>>,
+    c2r: Arc<dyn ComplexToReal<f32>>,
+    fft_input: Vec<f32>,
+    spectrum: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    /// normalized square difference function, for the lags up to `max_lag + 1`
+    nsdf: Vec<f32>,
+    frequency: Arc<AtomicF32>,
+    clarity: Arc<AtomicF32>,
+}
+
+impl PitchDetectorRenderer {
+    /// Compute the normalized square difference function of the analysis window, returns
+    /// `false` if the window is silent
+    fn compute_nsdf(&mut self) -> bool {
+        let window_size = self.ring_buffer.len();
+
+        // unroll the ring buffer, oldest sample first
+        let (newest, oldest) = self.ring_buffer.split_at(self.write_index);
+        self.fft_input[..oldest.len()].copy_from_slice(oldest);
+        self.fft_input[oldest.len()..window_size].copy_from_slice(newest);
+        self.fft_input[window_size..].fill(0.);
+
+        let energy: f32 = self.fft_input.iter().map(|x| x * x).sum();
+        if energy < SILENCE_MEAN_SQUARE * window_size as f32 {
+            return false;
+        }
+
+        // normalization terms, computed before the input is consumed by the FFT
+        let signal = &self.fft_input[..window_size];
+        let mut m = 2. * energy;
+        let squares = |i: usize| signal[i] * signal[i];
+        // m(tau) = sum_{j < W - tau} x_j^2 + x_{j + tau}^2
+        self.nsdf[0] = m;
+        for tau in 1..self.nsdf.len() {
+            m -= squares(tau - 1) + squares(window_size - tau);
+            self.nsdf[tau] = m;
+        }
+
+        // autocorrelation through the power spectrum
+        self.r2c
+            .process_with_scratch(&mut self.fft_input, &mut self.spectrum, &mut self.scratch)
+            .unwrap();
+        self.spectrum
+            .iter_mut()
+            .for_each(|c| *c = Complex::new(c.norm_sqr(), 0.));
+        self.c2r
+            .process_with_scratch(&mut self.spectrum, &mut self.fft_input, &mut self.scratch)
+            .unwrap();
+
+        let scale = 1. / self.fft_input.len() as f32;
+        self.nsdf
+            .iter_mut()
+            .zip(&self.fft_input)
+            .for_each(|(n, r)| {
+                *n = if *n > 0. { 2. * r * scale / *n } else { 0. };
+            });
+
+        true
+    }
+
+    /// Pick the period in the normalized square difference function, returns the interpolated
+    /// lag and the clarity
+    fn pick_period(&self) -> Option<(f32, f32)> {
+        let nsdf = &self.nsdf;
+        let key_maxima = || key_maxima(&nsdf[..=self.max_lag]).filter(|&tau| tau >= self.min_lag);
+
+        // the period is the first key maximum close enough to the highest one
+        let highest = key_maxima().map(|tau| nsdf[tau]).fold(0., f32::max);
+        let tau = key_maxima().find(|&tau| nsdf[tau] >= KEY_MAXIMUM_CUTOFF * highest)?;
+
+        // parabolic interpolation of the peak
+        let (a, b, c) = (nsdf[tau - 1], nsdf[tau], nsdf[tau + 1]);
+        let curvature = a - 2. * b + c;
+        if curvature >= 0. {
+            return Some((tau as f32, b));
+        }
+        let delta = 0.5 * (a - c) / curvature;
+        Some((tau as f32 + delta, b - 0.25 * (a - c) * delta))
+    }
+}
+
+/// Lags of the key maxima of a normalized square difference function: the highest value of
+/// each positive lobe, after the lobe of the lag zero
+fn key_maxima(nsdf: &[f32]) -> impl Iterator<Item = usize> + '_ {
+    let mut tau = nsdf.iter().position(|&n| n <= 0.).unwrap_or(nsdf.len());
+
+    std::iter::from_fn(move || {
+        let mut lobe_max: Option<usize> = None;
+        while tau < nsdf.len() {
+            if nsdf[tau] > 0. {
+                match lobe_max {
+                    Some(max) if nsdf[max] >= nsdf[tau] => (),
+                    _ => lobe_max = Some(tau),
+                }
+            } else if lobe_max.is_some() {
+                break;
+            }
+            tau += 1;
+        }
+        lobe_max
+    })
+}
+
+impl AudioProcessor for PitchDetectorRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        scope: &RenderScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        // pass through input
+        *output = input.clone();
+
+        let window_size = self.ring_buffer.len();
+
+        if input.is_silent() {
+            // skip the analysis once the window is silent
+            if self.silent_frames >= window_size {
+                return false;
+            }
+            self.silent_frames += RENDER_QUANTUM_SIZE;
+            for _ in 0..RENDER_QUANTUM_SIZE {
+                self.ring_buffer[self.write_index] = 0.;
+                self.write_index = (self.write_index + 1) % window_size;
+            }
+        } else {
+            self.silent_frames = 0;
+
+            // down mix to mono
+            let mut mono = input.clone();
+            mono.mix(1, ChannelInterpretation::Speakers);
+
+            for &sample in mono.channel_data(0).iter() {
+                self.ring_buffer[self.write_index] = sample;
+                self.write_index = (self.write_index + 1) % window_size;
+            }
+        }
+
+        let pitch = if self.compute_nsdf() {
+            self.pick_period()
+        } else {
+            None
+        };
+
+        let (frequency, clarity) = match pitch {
+            Some((lag, clarity)) => (scope.sample_rate / lag, clarity.clamp(0., 1.)),
+            None => (0., 0.),
+        };
+        self.frequency.store(frequency, Ordering::Relaxed);
+        self.clarity.store(clarity, Ordering::Relaxed);
+
+        // no tail-time
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+    use crate::context::OfflineAudioContext;
+    use crate::node::{AudioNode, AudioScheduledSourceNode, OscillatorType};
+
+    fn detect(
+        type_: OscillatorType,
+        frequency: f32,
+        options: PitchDetectorOptions,
+    ) -> (f32, f32, crate::AudioBuffer) {
+        let context = OfflineAudioContext::new(1, 8192, 48000.);
+        let detector = PitchDetectorNode::new(&context, options);
+        detector.connect(&context.destination());
+
+        let mut osc = context.create_oscillator();
+        osc.set_type(type_);
+        osc.frequency().set_value(frequency);
+        osc.connect(&detector);
+        osc.start();
+
+        let output = context.start_rendering_sync();
+        (detector.frequency(), detector.clarity(), output)
+    }
+
+    #[test]
+    fn test_sine() {
+        let (frequency, clarity, output) =
+            detect(OscillatorType::Sine, 440., PitchDetectorOptions::default());
+        assert_float_eq!(frequency, 440., abs <= 1.);
+        assert!(clarity > 0.95);
+
+        // the input is passed through
+        assert!(output.get_channel_data(0).iter().any(|&v| v > 0.5));
+    }
+
+    #[test]
+    fn test_fundamental_of_harmonics() {
+        let (frequency, clarity, _) = detect(
+            OscillatorType::Sawtooth,
+            110.,
+            PitchDetectorOptions::default(),
+        );
+        assert_float_eq!(frequency, 110., abs <= 1.);
+        assert!(clarity > 0.9);
+
+        // out of the frequency range
+        let options = PitchDetectorOptions {
+            min_frequency: 200.,
+            ..PitchDetectorOptions::default()
+        };
+        let (frequency, _, _) = detect(OscillatorType::Sine, 110., options);
+        assert!(frequency == 0. || frequency >= 200.);
+    }
+
+    #[test]
+    fn test_silence() {
+        let context = OfflineAudioContext::new(1, 1024, 48000.);
+        let detector = PitchDetectorNode::new(&context, PitchDetectorOptions::default());
+        let _ = context.start_rendering_sync();
+        assert_float_eq!(detector.frequency(), 0., abs <= 0.);
+        assert_float_eq!(detector.clarity(), 0., abs <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_frequency_range() {
+        let context = OfflineAudioContext::new(1, 1024, 48000.);
+        let options = PitchDetectorOptions {
+            min_frequency: 500.,
+            max_frequency: 100.,
+            ..PitchDetectorOptions::default()
+        };
+        let _ = PitchDetectorNode::new(&context, options);
+    }
+}
@@ -0,0 +1,797 @@
+//! Gate and clock utilities for rhythm patches, modular synthesizer style
+//!
+//! Gates are signals which are high (positive) while a note or step is active, and low (zero or
+//! negative) otherwise. Triggers are short gates marking an instant, and clocks are regular
+//! streams of gates. These nodes only read the first channel of their input and have a mono
+//! output, which is 1 when high and 0 when low.
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::{AudioNode, ChannelConfig, ChannelConfigOptions};
+
+/// Gate values of the first channel of `input`, `None` when the gate is low for the whole
+/// quantum
+fn gate_of(input: &AudioRenderQuantum) -> Option<&[f32]> {
+    (!input.is_silent()).then(|| &input.channel_data(0)[..])
+}
+
+/// Options for constructing a [`GateToTriggerNode`]
+#[derive(Clone, Debug)]
+pub struct GateToTriggerOptions {
+    /// duration of the trigger pulses, in seconds
+    pub pulse_duration: f64,
+}
+
+impl Default for GateToTriggerOptions {
+    fn default() -> Self {
+        Self {
+            pulse_duration: 0.001,
+        }
+    }
+}
+
+/// `GateToTriggerNode` outputs a short pulse at each rising edge of its input gate
+///
+/// The pulses last at least one sample frame.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode, OscillatorType};
+/// use web_audio_api::node::{GateToTriggerNode, GateToTriggerOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let mut clock = context.create_oscillator();
+/// clock.set_type(OscillatorType::Square);
+/// clock.frequency().set_value(2.);
+/// clock.start();
+///
+/// // 2 clicks per second
+/// let trigger = GateToTriggerNode::new(&context, GateToTriggerOptions::default());
+/// clock.connect(&trigger);
+/// trigger.connect(&context.destination());
+/// ```
+pub struct GateToTriggerNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    pulse_duration: f64,
+}
+
+impl AudioNode for GateToTriggerNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl GateToTriggerNode {
+    /// Create a new `GateToTriggerNode`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pulse duration is negative or not finite
+    pub fn new<C: BaseAudioContext>(context: &C, options: GateToTriggerOptions) -> Self {
+        let GateToTriggerOptions { pulse_duration } = options;
+        assert!(
+            pulse_duration >= 0. && pulse_duration.is_finite(),
+            "RangeError - pulse duration ({:?}) should be positive and finite",
+            pulse_duration
+        );
+
+        context.register(move |registration| {
+            let pulse_frames = (pulse_duration * context.sample_rate() as f64).round() as usize;
+
+            let node = GateToTriggerNode {
+                registration,
+                channel_config: ChannelConfigOptions::default().into(),
+                pulse_duration,
+            };
+
+            let render = GateToTriggerRenderer {
+                pulse_frames: pulse_frames.max(1),
+                remaining: 0,
+                last_gate: 0.,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// Duration of the trigger pulses, in seconds
+    pub fn pulse_duration(&self) -> f64 {
+        self.pulse_duration
+    }
+}
+
+struct GateToTriggerRenderer {
+    pulse_frames: usize,
+    /// remaining frames of the current pulse
+    remaining: usize,
+    last_gate: f32,
+}
+
+impl AudioProcessor for GateToTriggerRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &RenderScope,
+    ) -> bool {
+        let gate = gate_of(&inputs[0]);
+        let output = &mut outputs[0];
+
+        if gate.is_none() && self.remaining == 0 {
+            self.last_gate = 0.;
+            output.make_silent();
+            return false;
+        }
+
+        output.force_mono();
+        output
+            .channel_data_mut(0)
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, o)| {
+                let g = gate.map_or(0., |gate| gate[i]);
+                if g > 0. && self.last_gate <= 0. {
+                    self.remaining = self.pulse_frames;
+                }
+                self.last_gate = g;
+
+                if self.remaining > 0 {
+                    self.remaining -= 1;
+                    *o = 1.;
+                } else {
+                    *o = 0.;
+                }
+            });
+
+        self.remaining > 0
+    }
+}
+
+/// Options for constructing a [`ClockDividerNode`]
+#[derive(Clone, Debug)]
+pub struct ClockDividerOptions {
+    /// number of input clock cycles per output cycle
+    pub division: usize,
+}
+
+impl Default for ClockDividerOptions {
+    fn default() -> Self {
+        Self { division: 2 }
+    }
+}
+
+/// `ClockDividerNode` only lets through one clock cycle every `division` cycles of its input
+///
+/// The first cycle is let through, so the output is in phase with the input.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode, OscillatorType};
+/// use web_audio_api::node::{ClockDividerNode, ClockDividerOptions};
+///
+/// let context = AudioContext::default();
+///
+/// // 8th notes at 120 BPM
+/// let mut clock = context.create_oscillator();
+/// clock.set_type(OscillatorType::Square);
+/// clock.frequency().set_value(4.);
+/// clock.start();
+///
+/// // bars of 4/4
+/// let bars = ClockDividerNode::new(&context, ClockDividerOptions { division: 8 });
+/// clock.connect(&bars);
+/// ```
+pub struct ClockDividerNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    division: usize,
+}
+
+impl AudioNode for ClockDividerNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl ClockDividerNode {
+    /// Create a new `ClockDividerNode`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the division is zero
+    pub fn new<C: BaseAudioContext>(context: &C, options: ClockDividerOptions) -> Self {
+        let ClockDividerOptions { division } = options;
+        assert!(division > 0, "RangeError - division should not be zero");
+
+        context.register(move |registration| {
+            let node = ClockDividerNode {
+                registration,
+                channel_config: ChannelConfigOptions::default().into(),
+                division,
+            };
+
+            let render = ClockDividerRenderer {
+                division,
+                cycle: None,
+                last_gate: 0.,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// Number of input clock cycles per output cycle
+    pub fn division(&self) -> usize {
+        self.division
+    }
+}
+
+struct ClockDividerRenderer {
+    division: usize,
+    /// index of the current input cycle, `None` before the first rising edge
+    cycle: Option<usize>,
+    last_gate: f32,
+}
+
+impl AudioProcessor for ClockDividerRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &RenderScope,
+    ) -> bool {
+        let output = &mut outputs[0];
+        let Some(gate) = gate_of(&inputs[0]) else {
+            self.last_gate = 0.;
+            output.make_silent();
+            return false;
+        };
+
+        output.force_mono();
+        output
+            .channel_data_mut(0)
+            .iter_mut()
+            .zip(gate.iter())
+            .for_each(|(o, &g)| {
+                if g > 0. && self.last_gate <= 0. {
+                    self.cycle = Some(self.cycle.map_or(0, |c| (c + 1) % self.division));
+                }
+                self.last_gate = g;
+
+                *o = if g > 0. && self.cycle == Some(0) {
+                    1.
+                } else {
+                    0.
+                };
+            });
+
+        false
+    }
+}
+
+/// Options for constructing a [`ClockMultiplierNode`]
+#[derive(Clone, Debug)]
+pub struct ClockMultiplierOptions {
+    /// number of output clock cycles per input cycle
+    pub multiplication: usize,
+}
+
+impl Default for ClockMultiplierOptions {
+    fn default() -> Self {
+        Self { multiplication: 2 }
+    }
+}
+
+/// `ClockMultiplierNode` outputs `multiplication` clock cycles for each cycle of its input
+///
+/// The period of the input clock is measured between its last two rising edges, the output
+/// cycles are restarted at each rising edge of the input and have a duty cycle of 50%. Until the
+/// period is known (i.e. before the second rising edge), the input is let through.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode, OscillatorType};
+/// use web_audio_api::node::{ClockMultiplierNode, ClockMultiplierOptions};
+///
+/// let context = AudioContext::default();
+///
+/// // quarter notes at 120 BPM
+/// let mut clock = context.create_oscillator();
+/// clock.set_type(OscillatorType::Square);
+/// clock.frequency().set_value(2.);
+/// clock.start();
+///
+/// // 16th notes
+/// let sixteenths = ClockMultiplierNode::new(&context, ClockMultiplierOptions { multiplication: 4 });
+/// clock.connect(&sixteenths);
+/// ```
+pub struct ClockMultiplierNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    multiplication: usize,
+}
+
+impl AudioNode for ClockMultiplierNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl ClockMultiplierNode {
+    /// Create a new `ClockMultiplierNode`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the multiplication is zero
+    pub fn new<C: BaseAudioContext>(context: &C, options: ClockMultiplierOptions) -> Self {
+        let ClockMultiplierOptions { multiplication } = options;
+        assert!(
+            multiplication > 0,
+            "RangeError - multiplication should not be zero"
+        );
+
+        context.register(move |registration| {
+            let node = ClockMultiplierNode {
+                registration,
+                channel_config: ChannelConfigOptions::default().into(),
+                multiplication,
+            };
+
+            let render = ClockMultiplierRenderer {
+                multiplication,
+                period: None,
+                frames_since_edge: None,
+                last_gate: 0.,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// Number of output clock cycles per input cycle
+    pub fn multiplication(&self) -> usize {
+        self.multiplication
+    }
+}
+
+struct ClockMultiplierRenderer {
+    multiplication: usize,
+    /// number of frames between the last two rising edges of the input
+    period: Option<u64>,
+    /// number of frames since the last rising edge of the input
+    frames_since_edge: Option<u64>,
+    last_gate: f32,
+}
+
+impl AudioProcessor for ClockMultiplierRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &RenderScope,
+    ) -> bool {
+        let gate = gate_of(&inputs[0]);
+        let output = &mut outputs[0];
+
+        // without input clock, the output stops at the end of the last measured period
+        let running = match (self.period, self.frames_since_edge) {
+            (Some(period), Some(frames)) => frames < period,
+            _ => false,
+        };
+        if gate.is_none() && !running {
+            self.last_gate = 0.;
+            self.frames_since_edge = self
+                .frames_since_edge
+                .map(|f| f + RENDER_QUANTUM_SIZE as u64);
+            output.make_silent();
+            return false;
+        }
+
+        output.force_mono();
+        output
+            .channel_data_mut(0)
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, o)| {
+                let g = gate.map_or(0., |gate| gate[i]);
+                if g > 0. && self.last_gate <= 0. {
+                    if let Some(frames) = self.frames_since_edge {
+                        self.period = Some(frames);
+                    }
+                    self.frames_since_edge = Some(0);
+                }
+                self.last_gate = g;
+
+                *o = match (self.period, self.frames_since_edge) {
+                    (Some(period), Some(frames)) if frames < period => {
+                        let sub_period = period as f64 / self.multiplication as f64;
+                        let phase = (frames as f64 % sub_period) / sub_period;
+                        if phase < 0.5 {
+                            1.
+                        } else {
+                            0.
+                        }
+                    }
+                    _ => {
+                        if g > 0. {
+                            1.
+                        } else {
+                            0.
+                        }
+                    }
+                };
+
+                if let Some(frames) = self.frames_since_edge.as_mut() {
+                    *frames += 1;
+                }
+            });
+
+        false
+    }
+}
+
+/// Options for constructing an [`EuclideanRhythmNode`]
+#[derive(Clone, Debug)]
+pub struct EuclideanRhythmOptions {
+    /// number of steps of the pattern
+    pub steps: usize,
+    /// number of active steps, evenly distributed over the pattern
+    pub pulses: usize,
+    /// number of steps the pattern is rotated to the left
+    pub rotation: usize,
+    /// duration of a step, in seconds
+    pub step_duration: f64,
+    /// fraction of the active steps during which the gate is high, in the ]0, 1] range
+    pub gate_length: f64,
+}
+
+impl Default for EuclideanRhythmOptions {
+    fn default() -> Self {
+        Self {
+            steps: 8,
+            pulses: 3,
+            rotation: 0,
+            step_duration: 0.125,
+            gate_length: 0.5,
+        }
+    }
+}
+
+/// `EuclideanRhythmNode` generates the gates of an euclidean rhythm
+///
+/// The `pulses` active steps are distributed as evenly as possible over the `steps` of the
+/// pattern (e.g. 3 pulses over 8 steps is the tresillo `x..x..x.`). The steps are aligned to the
+/// time of the context: step `n` of the pattern starts at `n * step_duration` seconds (modulo the
+/// pattern length), so multiple rhythm nodes with commensurate step durations stay in sync.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::AudioNode;
+/// use web_audio_api::node::{EuclideanRhythmNode, EuclideanRhythmOptions};
+///
+/// let context = AudioContext::default();
+///
+/// // 5 hits over 16 steps of 16th notes at 120 BPM
+/// let options = EuclideanRhythmOptions {
+///     steps: 16,
+///     pulses: 5,
+///     step_duration: 0.125,
+///     ..Default::default()
+/// };
+/// let rhythm = EuclideanRhythmNode::new(&context, options);
+/// ```
+pub struct EuclideanRhythmNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    pattern: Vec<bool>,
+}
+
+impl AudioNode for EuclideanRhythmNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        0
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+/// Active steps of the euclidean rhythm, rotated to the left by `rotation` steps
+fn euclidean_pattern(steps: usize, pulses: usize, rotation: usize) -> Vec<bool> {
+    (0..steps)
+        .map(|i| ((i + rotation) % steps * pulses) % steps < pulses)
+        .collect()
+}
+
+impl EuclideanRhythmNode {
+    /// Create a new `EuclideanRhythmNode`
+    ///
+    /// # Panics
+    ///
+    /// Panics if
+    /// - the number of steps is zero or lower than the number of pulses
+    /// - the step duration is not strictly positive and finite
+    /// - the gate length is outside the ]0, 1] range
+    pub fn new<C: BaseAudioContext>(context: &C, options: EuclideanRhythmOptions) -> Self {
+        let EuclideanRhythmOptions {
+            steps,
+            pulses,
+            rotation,
+            step_duration,
+            gate_length,
+        } = options;
+
+        assert!(
+            steps > 0 && pulses <= steps,
+            "RangeError - invalid euclidean rhythm of {} pulses over {} steps",
+            pulses,
+            steps
+        );
+        assert!(
+            step_duration > 0. && step_duration.is_finite(),
+            "RangeError - step duration ({:?}) should be strictly positive and finite",
+            step_duration
+        );
+        assert!(
+            gate_length > 0. && gate_length <= 1.,
+            "RangeError - gate length ({:?}) should be in the ]0, 1] range",
+            gate_length
+        );
+
+        let pattern = euclidean_pattern(steps, pulses, rotation);
+
+        context.register(move |registration| {
+            let render = EuclideanRhythmRenderer {
+                pattern: pattern.clone(),
+                step_frames: step_duration * context.sample_rate() as f64,
+                gate_length,
+            };
+
+            let node = EuclideanRhythmNode {
+                registration,
+                channel_config: ChannelConfigOptions::default().into(),
+                pattern,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// Active steps of the pattern
+    pub fn pattern(&self) -> &[bool] {
+        &self.pattern
+    }
+}
+
+struct EuclideanRhythmRenderer {
+    pattern: Vec<bool>,
+    /// duration of a step, in sample frames
+    step_frames: f64,
+    gate_length: f64,
+}
+
+impl AudioProcessor for EuclideanRhythmRenderer {
+    fn process(
+        &mut self,
+        _inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        scope: &RenderScope,
+    ) -> bool {
+        let output = &mut outputs[0];
+
+        if !self.pattern.contains(&true) {
+            output.make_silent();
+            return false;
+        }
+
+        output.force_mono();
+        output
+            .channel_data_mut(0)
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, o)| {
+                let position = (scope.current_frame + i as u64) as f64 / self.step_frames;
+                let step = position.floor() as u64 % self.pattern.len() as u64;
+                let active = self.pattern[step as usize] && position.fract() < self.gate_length;
+
+                *o = if active { 1. } else { 0. };
+            });
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+    use crate::AudioBuffer;
+
+    use float_eq::assert_float_eq;
+
+    fn gate_source(
+        context: &OfflineAudioContext,
+        gate: Vec<f32>,
+    ) -> crate::node::AudioBufferSourceNode {
+        let mut src = context.create_buffer_source();
+        src.set_buffer(AudioBuffer::from(vec![gate], context.sample_rate()));
+        src.start();
+        src
+    }
+
+    /// gate of `length` frames, high during the first half of each cycle of `period` frames
+    fn clock(length: usize, period: usize) -> Vec<f32> {
+        (0..length)
+            .map(|i| if i % period < period / 2 { 1. } else { 0. })
+            .collect()
+    }
+
+    #[test]
+    fn test_euclidean_pattern() {
+        let tresillo = [true, false, false, true, false, false, true, false];
+        assert_eq!(euclidean_pattern(8, 3, 0), tresillo);
+
+        let rotated = [false, false, true, false, false, true, false, true];
+        assert_eq!(euclidean_pattern(8, 3, 1), rotated);
+
+        assert_eq!(euclidean_pattern(4, 4, 0), [true; 4]);
+        assert_eq!(euclidean_pattern(4, 0, 0), [false; 4]);
+    }
+
+    #[test]
+    fn test_gate_to_trigger() {
+        let context = OfflineAudioContext::new(1, 128, 2000.);
+        let options = GateToTriggerOptions {
+            pulse_duration: 0.002,
+        };
+        let node = GateToTriggerNode::new(&context, options);
+        node.connect(&context.destination());
+
+        let src = gate_source(&context, clock(128, 40));
+        src.connect(&node);
+
+        let output = context.start_rendering_sync();
+        let expected: Vec<f32> = (0..128).map(|i| if i % 40 < 4 { 1. } else { 0. }).collect();
+        assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_clock_divider() {
+        let context = OfflineAudioContext::new(1, 256, 48000.);
+        let node = ClockDividerNode::new(&context, ClockDividerOptions { division: 3 });
+        node.connect(&context.destination());
+        assert_eq!(node.division(), 3);
+
+        let src = gate_source(&context, clock(256, 20));
+        src.connect(&node);
+
+        let output = context.start_rendering_sync();
+        let expected: Vec<f32> = (0..256)
+            .map(|i| if i % 60 < 10 { 1. } else { 0. })
+            .collect();
+        assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_clock_multiplier() {
+        let context = OfflineAudioContext::new(1, 256, 48000.);
+        let options = ClockMultiplierOptions { multiplication: 4 };
+        let node = ClockMultiplierNode::new(&context, options);
+        node.connect(&context.destination());
+
+        let src = gate_source(&context, clock(256, 80));
+        src.connect(&node);
+
+        let output = context.start_rendering_sync();
+        let channel = output.get_channel_data(0);
+        // first cycle passes through
+        let expected: Vec<f32> = (0..80).map(|i| if i < 40 { 1. } else { 0. }).collect();
+        assert_float_eq!(&channel[..80], &expected[..], abs_all <= 0.);
+        // then 4 cycles per input cycle
+        let expected: Vec<f32> = (80..240)
+            .map(|i| if i % 20 < 10 { 1. } else { 0. })
+            .collect();
+        assert_float_eq!(&channel[80..240], &expected[..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_euclidean_rhythm() {
+        let context = OfflineAudioContext::new(1, 256, 2000.);
+        let options = EuclideanRhythmOptions {
+            steps: 4,
+            pulses: 2,
+            rotation: 1,
+            step_duration: 0.005,
+            gate_length: 0.5,
+        };
+        let node = EuclideanRhythmNode::new(&context, options);
+        node.connect(&context.destination());
+        assert_eq!(node.pattern(), [false, true, false, true]);
+
+        let output = context.start_rendering_sync();
+        let expected: Vec<f32> = (0..256)
+            .map(|i| if i % 20 >= 10 && i % 10 < 5 { 1. } else { 0. })
+            .collect();
+        assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_euclidean_rhythm() {
+        let context = OfflineAudioContext::new(1, 128, 2000.);
+        let options = EuclideanRhythmOptions {
+            steps: 4,
+            pulses: 5,
+            ..Default::default()
+        };
+        let _ = EuclideanRhythmNode::new(&context, options);
+    }
+}
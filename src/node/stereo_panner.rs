@@ -69,7 +69,7 @@ fn assert_valid_channel_count_mode(mode: ChannelCountMode) {
 /// - `gain_left = (x * PI / 2.).cos()`
 /// - `gain_right = (x * PI / 2.).sin()`
 #[inline(always)]
-fn get_stereo_gains(sine_table: &[f32], x: f32) -> [f32; 2] {
+pub(super) fn get_stereo_gains(sine_table: &[f32], x: f32) -> [f32; 2] {
     let idx = (x * TABLE_LENGTH_BY_4_F32) as usize;
 
     let gain_left = sine_table[idx + TABLE_LENGTH_BY_4_USIZE];
@@ -0,0 +1,195 @@
+use crate::context::ConcreteBaseAudioContext;
+
+use super::{AudioNode, AudioScheduledSourceNode};
+
+/// Object safe subset of [`AudioScheduledSourceNode`]
+trait SyncSource {
+    fn context(&self) -> &ConcreteBaseAudioContext;
+    fn start_at(&mut self, when: f64);
+    fn stop_at(&mut self, when: f64);
+}
+
+impl<S: AudioScheduledSourceNode> SyncSource for S {
+    fn context(&self) -> &ConcreteBaseAudioContext {
+        AudioNode::context(self)
+    }
+
+    fn start_at(&mut self, when: f64) {
+        AudioScheduledSourceNode::start_at(self, when)
+    }
+
+    fn stop_at(&mut self, when: f64) {
+        AudioScheduledSourceNode::stop_at(self, when)
+    }
+}
+
+/// Group of source nodes that start and stop on the exact same sample frame
+///
+/// Sources started separately with the same `when` only share their start frame if their start
+/// messages reach the render thread within the same render quantum, which is not the case when
+/// `when` is already in the past or when the messages are split over multiple render quanta. The
+/// start and stop messages of a `SyncGroup` are sent in a single control message instead, so the
+/// sources stay phase coherent in any case (e.g. for multi-stem music playback).
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, SyncGroup};
+///
+/// let context = AudioContext::default();
+///
+/// let mut drums = context.create_buffer_source();
+/// let mut bass = context.create_buffer_source();
+/// drums.connect(&context.destination());
+/// bass.connect(&context.destination());
+///
+/// let mut group = SyncGroup::new();
+/// group.add(&mut drums);
+/// group.add(&mut bass);
+/// group.start_all(context.current_time() + 0.1);
+/// ```
+#[derive(Default)]
+pub struct SyncGroup<'a> {
+    sources: Vec<&'a mut dyn SyncSource>,
+}
+
+impl std::fmt::Debug for SyncGroup<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SyncGroup")
+            .field("sources", &self.sources.len())
+            .finish()
+    }
+}
+
+impl<'a> SyncGroup<'a> {
+    /// Create an empty group
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a source node to the group
+    ///
+    /// # Panics
+    ///
+    /// Panics if the source does not belong to the same context as the other sources of the
+    /// group
+    pub fn add<S: AudioScheduledSourceNode>(&mut self, source: &'a mut S) {
+        if let Some(first) = self.sources.first() {
+            if first.context() != AudioNode::context(source) {
+                panic!("InvalidAccessError: Attempting to group sources from different contexts");
+            }
+        }
+        self.sources.push(source);
+    }
+
+    /// Number of sources in the group
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// Check if the group has no sources
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Run `f` on all sources, sending the resulting messages at once
+    fn for_each(&mut self, f: impl Fn(&mut dyn SyncSource)) {
+        let Some(context) = self.sources.first().map(|s| s.context().clone()) else {
+            return;
+        };
+        context.batch_node_messages(|| self.sources.iter_mut().for_each(|s| f(&mut **s)));
+    }
+
+    /// Schedule the playback start of all sources on the same sample frame
+    ///
+    /// # Panics
+    ///
+    /// Panics if any source was already started
+    pub fn start_all(&mut self, when: f64) {
+        self.for_each(|source| source.start_at(when));
+    }
+
+    /// Schedule the playback stop of all sources on the same sample frame
+    ///
+    /// # Panics
+    ///
+    /// Panics if any source was not started or already stopped
+    pub fn stop_all(&mut self, when: f64) {
+        self.for_each(|source| source.stop_at(when));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::AudioBuffer;
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_start_all() {
+        let context = OfflineAudioContext::new(2, 512, 32_768.);
+        let merger = context.create_channel_merger(2);
+        merger.connect(&context.destination());
+
+        let mut constant = context.create_constant_source();
+        constant.connect_at(&merger, 0, 0);
+
+        let mut src = context.create_buffer_source();
+        src.set_buffer(AudioBuffer::from(vec![vec![1.; 512]], 32_768.));
+        src.connect_at(&merger, 0, 1);
+
+        let mut group = SyncGroup::new();
+        group.add(&mut constant);
+        group.add(&mut src);
+        assert_eq!(group.len(), 2);
+
+        group.start_all(200. / 32_768.);
+        group.stop_all(300. / 32_768.);
+
+        let output = context.start_rendering_sync();
+        let mut expected = [0.; 512];
+        expected[200..300].fill(1.);
+        assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 0.);
+        assert_float_eq!(output.get_channel_data(1), &expected[..], abs_all <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_different_contexts() {
+        let context1 = OfflineAudioContext::new(1, 128, 32_768.);
+        let context2 = OfflineAudioContext::new(1, 128, 32_768.);
+        let mut constant1 = context1.create_constant_source();
+        let mut constant2 = context2.create_constant_source();
+
+        let mut group = SyncGroup::new();
+        group.add(&mut constant1);
+        group.add(&mut constant2);
+    }
+
+    #[test]
+    fn test_batch_flushed_on_panic() {
+        let context = OfflineAudioContext::new(1, 256, 32_768.);
+        let mut constant = context.create_constant_source();
+        constant.connect(&context.destination());
+        constant.start();
+        let mut src = context.create_buffer_source();
+        src.start();
+
+        let mut group = SyncGroup::new();
+        group.add(&mut src);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            group.start_all(0.); // already started
+        }));
+        assert!(result.is_err());
+
+        // messages are no longer held back
+        constant.offset().set_value(2.);
+        let output = context.start_rendering_sync();
+        assert_float_eq!(output.get_channel_data(0), &[2.; 256][..], abs_all <= 0.);
+    }
+}
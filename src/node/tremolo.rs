@@ -0,0 +1,815 @@
+//! Tremolo and auto-pan, amplitude and position modulated by a low frequency oscillator
+//!
+//! The low frequency oscillator (LFO) runs either freely at the `rate` of the node, in Hertz, or
+//! synced to a tempo with a [`TempoSync`]. A synced LFO derives its phase from the clock of the
+//! node, its periods start on the beats of the context time, or of the
+//! [`Transport`](crate::transport::Transport) the node has been added to.
+use std::any::Any;
+use std::f64::consts::PI;
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::stereo_panner::get_stereo_gains;
+use super::{
+    precomputed_sine_table, AudioNode, ChannelConfig, ChannelConfigOptions, ChannelCountMode,
+    ChannelInterpretation,
+};
+
+/// Waveform of the low frequency oscillator of a [`TremoloNode`] or an [`AutoPanNode`]
+///
+/// All shapes start at zero, rising, except the square which starts high.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum LfoShape {
+    #[default]
+    Sine,
+    Triangle,
+    Square,
+    Sawtooth,
+}
+
+impl LfoShape {
+    /// Value of the waveform, between -1 and 1, at the given phase in periods
+    fn value(self, phase: f64) -> f32 {
+        let value = match self {
+            Self::Sine => (2. * PI * phase).sin(),
+            Self::Triangle => 1. - 4. * ((phase + 0.25).fract() - 0.5).abs(),
+            Self::Square => {
+                if phase < 0.5 {
+                    1.
+                } else {
+                    -1.
+                }
+            }
+            Self::Sawtooth => 2. * (phase + 0.5).fract() - 1.,
+        };
+        value as f32
+    }
+}
+
+/// Duration of a note, relative to a quarter note beat
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum NoteValue {
+    Whole,
+    DottedHalf,
+    Half,
+    HalfTriplet,
+    DottedQuarter,
+    #[default]
+    Quarter,
+    QuarterTriplet,
+    DottedEighth,
+    Eighth,
+    EighthTriplet,
+    Sixteenth,
+    ThirtySecond,
+}
+
+impl NoteValue {
+    /// Duration of the note, in quarter note beats
+    #[must_use]
+    pub fn beats(self) -> f64 {
+        match self {
+            Self::Whole => 4.,
+            Self::DottedHalf => 3.,
+            Self::Half => 2.,
+            Self::HalfTriplet => 4. / 3.,
+            Self::DottedQuarter => 1.5,
+            Self::Quarter => 1.,
+            Self::QuarterTriplet => 2. / 3.,
+            Self::DottedEighth => 0.75,
+            Self::Eighth => 0.5,
+            Self::EighthTriplet => 1. / 3.,
+            Self::Sixteenth => 0.25,
+            Self::ThirtySecond => 0.125,
+        }
+    }
+}
+
+/// Period of a low frequency oscillator, given as a note value at a tempo
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TempoSync {
+    /// tempo, in quarter note beats per minute
+    pub tempo: f64,
+    /// duration of one period of the oscillator
+    pub note: NoteValue,
+}
+
+impl TempoSync {
+    /// Duration of one period of the oscillator, in seconds
+    #[must_use]
+    pub fn period(&self) -> f64 {
+        self.note.beats() * 60. / self.tempo
+    }
+}
+
+/// # Panics
+///
+/// Panics if the tempo is not strictly positive
+#[track_caller]
+fn assert_valid_sync(sync: Option<TempoSync>) {
+    if let Some(sync) = sync {
+        assert!(
+            sync.tempo > 0. && sync.tempo.is_finite(),
+            "RangeError - invalid tempo {:?}",
+            sync.tempo
+        );
+    }
+}
+
+/// Low frequency oscillator of the renderers
+struct Lfo {
+    shape: LfoShape,
+    sync: Option<TempoSync>,
+    /// phase of the free running oscillator, in periods
+    phase: f64,
+}
+
+impl Lfo {
+    /// Value of the oscillator at the given time of the clock of the node, then advance the free
+    /// running phase by one sample frame
+    fn tick(&mut self, time: f64, rate: f32, sample_rate: f64) -> f32 {
+        let phase = match self.sync {
+            Some(sync) => (time / sync.period()).fract(),
+            None => self.phase,
+        };
+        self.phase = (self.phase + f64::from(rate) / sample_rate).fract();
+        self.shape.value(phase)
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) -> bool {
+        if let Some(&shape) = msg.downcast_ref::<LfoShape>() {
+            self.shape = shape;
+            return true;
+        }
+        if let Some(&sync) = msg.downcast_ref::<Option<TempoSync>>() {
+            self.sync = sync;
+            return true;
+        }
+        false
+    }
+}
+
+fn create_params<C: BaseAudioContext>(
+    context: &C,
+    registration: &AudioContextRegistration,
+    rate: f32,
+    depth: f32,
+) -> ([AudioParam; 2], [AudioParamId; 2]) {
+    let rate_opts = AudioParamDescriptor {
+        min_value: 0.,
+        max_value: 100.,
+        default_value: 5.,
+        automation_rate: AutomationRate::A,
+    };
+    let (rate_param, rate_proc) = context.create_audio_param(rate_opts, registration);
+    rate_param.set_value(rate);
+
+    let depth_opts = AudioParamDescriptor {
+        min_value: 0.,
+        max_value: 1.,
+        default_value: 0.5,
+        automation_rate: AutomationRate::A,
+    };
+    let (depth_param, depth_proc) = context.create_audio_param(depth_opts, registration);
+    depth_param.set_value(depth);
+
+    ([rate_param, depth_param], [rate_proc, depth_proc])
+}
+
+/// Options for constructing a [`TremoloNode`]
+#[derive(Clone, Debug)]
+pub struct TremoloOptions {
+    /// frequency of the LFO, in Hertz, ignored while synced
+    pub rate: f32,
+    /// amount of the gain reduction at the bottom of the LFO, between 0 and 1
+    pub depth: f32,
+    pub shape: LfoShape,
+    pub sync: Option<TempoSync>,
+    pub channel_config: ChannelConfigOptions,
+}
+
+impl Default for TremoloOptions {
+    fn default() -> Self {
+        Self {
+            rate: 5.,
+            depth: 0.5,
+            shape: LfoShape::default(),
+            sync: None,
+            channel_config: ChannelConfigOptions::default(),
+        }
+    }
+}
+
+/// `TremoloNode` modulates the gain of its input with a low frequency oscillator
+///
+/// The gain is 1 at the top of the LFO and `1 - depth` at its bottom.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode, LfoShape, NoteValue};
+/// use web_audio_api::node::{TempoSync, TremoloNode, TremoloOptions};
+///
+/// let context = AudioContext::default();
+///
+/// // square tremolo on the eighth notes at 120 BPM
+/// let options = TremoloOptions {
+///     depth: 1.,
+///     shape: LfoShape::Square,
+///     sync: Some(TempoSync { tempo: 120., note: NoteValue::Eighth }),
+///     ..TremoloOptions::default()
+/// };
+/// let tremolo = TremoloNode::new(&context, options);
+/// tremolo.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&tremolo);
+/// osc.start();
+/// ```
+pub struct TremoloNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    rate: AudioParam,
+    depth: AudioParam,
+    shape: LfoShape,
+    sync: Option<TempoSync>,
+}
+
+impl AudioNode for TremoloNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl TremoloNode {
+    /// # Panics
+    ///
+    /// Panics if the tempo of `options.sync` is not strictly positive
+    pub fn new<C: BaseAudioContext>(context: &C, options: TremoloOptions) -> Self {
+        assert_valid_sync(options.sync);
+
+        context.register(move |registration| {
+            let ([rate, depth], [rate_proc, depth_proc]) =
+                create_params(context, &registration, options.rate, options.depth);
+
+            let render = TremoloRenderer {
+                rate: rate_proc,
+                depth: depth_proc,
+                lfo: Lfo {
+                    shape: options.shape,
+                    sync: options.sync,
+                    phase: 0.,
+                },
+            };
+
+            let node = TremoloNode {
+                registration,
+                channel_config: options.channel_config.into(),
+                rate,
+                depth,
+                shape: options.shape,
+                sync: options.sync,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// Frequency of the LFO, in Hertz, ignored while synced
+    #[must_use]
+    pub fn rate(&self) -> &AudioParam {
+        &self.rate
+    }
+
+    /// Amount of the gain reduction at the bottom of the LFO, between 0 and 1
+    #[must_use]
+    pub fn depth(&self) -> &AudioParam {
+        &self.depth
+    }
+
+    /// Waveform of the LFO
+    #[must_use]
+    pub fn shape(&self) -> LfoShape {
+        self.shape
+    }
+
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.shape = shape;
+        self.registration.post_message(shape);
+    }
+
+    /// Tempo the LFO is synced to, `None` while it runs at [`rate`](Self::rate)
+    #[must_use]
+    pub fn sync(&self) -> Option<TempoSync> {
+        self.sync
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the tempo is not strictly positive
+    pub fn set_sync(&mut self, sync: Option<TempoSync>) {
+        assert_valid_sync(sync);
+        self.sync = sync;
+        self.registration.post_message(sync);
+    }
+}
+
+struct TremoloRenderer {
+    rate: AudioParamId,
+    depth: AudioParamId,
+    lfo: Lfo,
+}
+
+impl AudioProcessor for TremoloRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &RenderScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        let rate = params.get(&self.rate);
+        let depth = params.get(&self.depth);
+        let sample_rate = f64::from(scope.sample_rate);
+
+        if input.is_silent() {
+            output.make_silent();
+            // keep the free running LFO going
+            let phase =
+                self.lfo.phase + f64::from(rate[0]) * RENDER_QUANTUM_SIZE as f64 / sample_rate;
+            self.lfo.phase = phase.fract();
+            return false;
+        }
+
+        let mut gains = [0.; RENDER_QUANTUM_SIZE];
+        gains
+            .iter_mut()
+            .zip(rate.iter().cycle())
+            .zip(depth.iter().cycle())
+            .enumerate()
+            .for_each(|(i, ((g, &rate), &depth))| {
+                let time = (scope.current_frame + i as u64) as f64 / sample_rate;
+                let lfo = self.lfo.tick(time, rate, sample_rate);
+                *g = 1. - depth * (1. - lfo) * 0.5;
+            });
+
+        *output = input.clone();
+        output.channels_mut().iter_mut().for_each(|channel| {
+            channel.iter_mut().zip(gains).for_each(|(o, g)| *o *= g);
+        });
+
+        false
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if !self.lfo.onmessage(msg) {
+            log::warn!("TremoloRenderer: Dropping incoming message {msg:?}");
+        }
+    }
+}
+
+/// Options for constructing an [`AutoPanNode`]
+#[derive(Clone, Debug)]
+pub struct AutoPanOptions {
+    /// frequency of the LFO, in Hertz, ignored while synced
+    pub rate: f32,
+    /// width of the sweep, between 0 (centered) and 1 (from hard left to hard right)
+    pub depth: f32,
+    pub shape: LfoShape,
+    pub sync: Option<TempoSync>,
+    pub channel_config: ChannelConfigOptions,
+}
+
+impl Default for AutoPanOptions {
+    fn default() -> Self {
+        Self {
+            rate: 0.5,
+            depth: 1.,
+            shape: LfoShape::default(),
+            sync: None,
+            channel_config: ChannelConfigOptions {
+                count: 2,
+                count_mode: ChannelCountMode::ClampedMax,
+                interpretation: ChannelInterpretation::Speakers,
+            },
+        }
+    }
+}
+
+/// # Panics
+///
+/// Panics if the count is greater than 2
+#[track_caller]
+fn assert_valid_channel_count(count: usize) {
+    assert!(
+        count <= 2,
+        "NotSupportedError - AutoPanNode channel count cannot be greater than two"
+    );
+}
+
+/// # Panics
+///
+/// Panics if the mode is [`ChannelCountMode::Max`]
+#[track_caller]
+fn assert_valid_channel_count_mode(mode: ChannelCountMode) {
+    assert_ne!(
+        mode,
+        ChannelCountMode::Max,
+        "NotSupportedError - AutoPanNode channel count mode cannot be set to max"
+    );
+}
+
+/// `AutoPanNode` moves its input across the stereo image with a low frequency oscillator
+///
+/// The pan position is `depth` times the LFO, it is applied with the equal power algorithm of
+/// the [`StereoPannerNode`](super::StereoPannerNode): the output is always stereo, and the
+/// channel count constraints are the same.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode, AutoPanNode, AutoPanOptions};
+/// use web_audio_api::node::LfoShape;
+///
+/// let context = AudioContext::default();
+///
+/// let options = AutoPanOptions { shape: LfoShape::Triangle, ..AutoPanOptions::default() };
+/// let autopan = AutoPanNode::new(&context, options);
+/// autopan.connect(&context.destination());
+/// autopan.rate().set_value(0.25);
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&autopan);
+/// osc.start();
+/// ```
+pub struct AutoPanNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    rate: AudioParam,
+    depth: AudioParam,
+    shape: LfoShape,
+    sync: Option<TempoSync>,
+}
+
+impl AudioNode for AutoPanNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+
+    fn set_channel_count_mode(&self, mode: ChannelCountMode) {
+        assert_valid_channel_count_mode(mode);
+        self.channel_config.set_count_mode(mode);
+    }
+
+    fn set_channel_count(&self, count: usize) {
+        assert_valid_channel_count(count);
+        self.channel_config.set_count(count);
+    }
+}
+
+impl AutoPanNode {
+    /// # Panics
+    ///
+    /// Will panic if:
+    ///
+    /// * `options.channel_config.count` is greater than 2
+    /// * `options.channel_config.mode` is `ChannelCountMode::Max`
+    /// * the tempo of `options.sync` is not strictly positive
+    pub fn new<C: BaseAudioContext>(context: &C, options: AutoPanOptions) -> Self {
+        assert_valid_channel_count_mode(options.channel_config.count_mode);
+        assert_valid_channel_count(options.channel_config.count);
+        assert_valid_sync(options.sync);
+
+        context.register(move |registration| {
+            let ([rate, depth], [rate_proc, depth_proc]) =
+                create_params(context, &registration, options.rate, options.depth);
+
+            let render = AutoPanRenderer {
+                rate: rate_proc,
+                depth: depth_proc,
+                lfo: Lfo {
+                    shape: options.shape,
+                    sync: options.sync,
+                    phase: 0.,
+                },
+                sine_table: precomputed_sine_table(),
+            };
+
+            let node = AutoPanNode {
+                registration,
+                channel_config: options.channel_config.into(),
+                rate,
+                depth,
+                shape: options.shape,
+                sync: options.sync,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// Frequency of the LFO, in Hertz, ignored while synced
+    #[must_use]
+    pub fn rate(&self) -> &AudioParam {
+        &self.rate
+    }
+
+    /// Width of the sweep, between 0 (centered) and 1 (from hard left to hard right)
+    #[must_use]
+    pub fn depth(&self) -> &AudioParam {
+        &self.depth
+    }
+
+    /// Waveform of the LFO
+    #[must_use]
+    pub fn shape(&self) -> LfoShape {
+        self.shape
+    }
+
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.shape = shape;
+        self.registration.post_message(shape);
+    }
+
+    /// Tempo the LFO is synced to, `None` while it runs at [`rate`](Self::rate)
+    #[must_use]
+    pub fn sync(&self) -> Option<TempoSync> {
+        self.sync
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the tempo is not strictly positive
+    pub fn set_sync(&mut self, sync: Option<TempoSync>) {
+        assert_valid_sync(sync);
+        self.sync = sync;
+        self.registration.post_message(sync);
+    }
+}
+
+struct AutoPanRenderer {
+    rate: AudioParamId,
+    depth: AudioParamId,
+    lfo: Lfo,
+    sine_table: &'static [f32],
+}
+
+impl AudioProcessor for AutoPanRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &RenderScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        let rate = params.get(&self.rate);
+        let depth = params.get(&self.depth);
+        let sample_rate = f64::from(scope.sample_rate);
+
+        if input.is_silent() {
+            output.make_silent();
+            // keep the free running LFO going
+            let phase =
+                self.lfo.phase + f64::from(rate[0]) * RENDER_QUANTUM_SIZE as f64 / sample_rate;
+            self.lfo.phase = phase.fract();
+            return false;
+        }
+
+        let mut pans = [0.; RENDER_QUANTUM_SIZE];
+        pans.iter_mut()
+            .zip(rate.iter().cycle())
+            .zip(depth.iter().cycle())
+            .enumerate()
+            .for_each(|(i, ((p, &rate), &depth))| {
+                let time = (scope.current_frame + i as u64) as f64 / sample_rate;
+                *p = depth * self.lfo.tick(time, rate, sample_rate);
+            });
+
+        let input = input.clone();
+        output.set_number_of_channels(2);
+        let [left, right] = output.stereo_mut();
+
+        match input.number_of_channels() {
+            1 => {
+                let samples = input.channel_data(0);
+                for (i, &pan) in pans.iter().enumerate() {
+                    let x = (pan + 1.) * 0.5;
+                    let [gain_left, gain_right] = get_stereo_gains(self.sine_table, x);
+                    left[i] = samples[i] * gain_left;
+                    right[i] = samples[i] * gain_right;
+                }
+            }
+            2 => {
+                let input_left = input.channel_data(0);
+                let input_right = input.channel_data(1);
+                for (i, &pan) in pans.iter().enumerate() {
+                    if pan <= 0. {
+                        let [gain_left, gain_right] = get_stereo_gains(self.sine_table, pan + 1.);
+                        left[i] = input_right[i].mul_add(gain_left, input_left[i]);
+                        right[i] = input_right[i] * gain_right;
+                    } else {
+                        let [gain_left, gain_right] = get_stereo_gains(self.sine_table, pan);
+                        left[i] = input_left[i] * gain_left;
+                        right[i] = input_left[i].mul_add(gain_right, input_right[i]);
+                    }
+                }
+            }
+            _ => panic!("AutoPanNode should not have more than 2 channels to process"),
+        }
+
+        false
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if !self.lfo.onmessage(msg) {
+            log::warn!("AutoPanRenderer: Dropping incoming message {msg:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    const SAMPLE_RATE: f32 = 32_768.;
+
+    fn render_tremolo(options: TremoloOptions) -> Vec<f32> {
+        let context = OfflineAudioContext::new(1, SAMPLE_RATE as usize, SAMPLE_RATE);
+        let tremolo = TremoloNode::new(&context, options);
+        tremolo.connect(&context.destination());
+
+        let mut src = context.create_constant_source();
+        src.connect(&tremolo);
+        src.start();
+
+        context.start_rendering_sync().get_channel_data(0).to_vec()
+    }
+
+    #[test]
+    fn test_lfo_shapes() {
+        for shape in [
+            LfoShape::Sine,
+            LfoShape::Triangle,
+            LfoShape::Square,
+            LfoShape::Sawtooth,
+        ] {
+            let values: Vec<f32> = [0., 0.25, 0.75].iter().map(|&p| shape.value(p)).collect();
+            match shape {
+                LfoShape::Square => assert_float_eq!(values[..], [1., 1., -1.][..], abs_all <= 0.),
+                LfoShape::Sawtooth => {
+                    assert_float_eq!(values[..], [0., 0.5, -0.5][..], abs_all <= 1e-6)
+                }
+                _ => assert_float_eq!(values[..], [0., 1., -1.][..], abs_all <= 1e-6),
+            }
+        }
+    }
+
+    #[test]
+    fn test_tremolo() {
+        let output = render_tremolo(TremoloOptions {
+            rate: 2.,
+            depth: 0.5,
+            shape: LfoShape::Square,
+            ..TremoloOptions::default()
+        });
+
+        // 2 periods, high then low
+        assert_float_eq!(output[0], 1., abs <= 0.);
+        assert_float_eq!(output[8191], 1., abs <= 0.);
+        assert_float_eq!(output[8192], 0.5, abs <= 0.);
+        assert_float_eq!(output[16383], 0.5, abs <= 0.);
+        assert_float_eq!(output[16384], 1., abs <= 0.);
+    }
+
+    #[test]
+    fn test_tempo_sync() {
+        let sync = TempoSync {
+            tempo: 120.,
+            note: NoteValue::Eighth,
+        };
+        assert_float_eq!(sync.period(), 0.25, abs <= 0.);
+
+        // the rate is ignored
+        let output = render_tremolo(TremoloOptions {
+            rate: 1.,
+            depth: 1.,
+            shape: LfoShape::Square,
+            sync: Some(sync),
+            ..TremoloOptions::default()
+        });
+
+        assert_float_eq!(output[4095], 1., abs <= 0.);
+        assert_float_eq!(output[4096], 0., abs <= 0.);
+        assert_float_eq!(output[8191], 0., abs <= 0.);
+        assert_float_eq!(output[8192], 1., abs <= 0.);
+        assert_float_eq!(output[28672], 0., abs <= 0.);
+    }
+
+    fn mono() -> ChannelConfigOptions {
+        ChannelConfigOptions {
+            count: 1,
+            ..AutoPanOptions::default().channel_config
+        }
+    }
+
+    #[test]
+    fn test_auto_pan() {
+        let context = OfflineAudioContext::new(2, SAMPLE_RATE as usize, SAMPLE_RATE);
+        let options = AutoPanOptions {
+            rate: 1.,
+            shape: LfoShape::Square,
+            channel_config: mono(),
+            ..AutoPanOptions::default()
+        };
+        let autopan = AutoPanNode::new(&context, options);
+        autopan.connect(&context.destination());
+
+        let mut src = context.create_constant_source();
+        src.connect(&autopan);
+        src.start();
+
+        let output = context.start_rendering_sync();
+        let left = output.get_channel_data(0);
+        let right = output.get_channel_data(1);
+
+        // hard right, then hard left
+        assert_float_eq!(left[0], 0., abs <= 1e-6);
+        assert_float_eq!(right[0], 1., abs <= 1e-6);
+        assert_float_eq!(left[16384], 1., abs <= 1e-6);
+        assert_float_eq!(right[16384], 0., abs <= 1e-6);
+    }
+
+    #[test]
+    fn test_auto_pan_no_depth() {
+        let context = OfflineAudioContext::new(2, 128, SAMPLE_RATE);
+        let options = AutoPanOptions {
+            depth: 0.,
+            channel_config: mono(),
+            ..AutoPanOptions::default()
+        };
+        let autopan = AutoPanNode::new(&context, options);
+        autopan.connect(&context.destination());
+
+        let mut src = context.create_constant_source();
+        src.connect(&autopan);
+        src.start();
+
+        let output = context.start_rendering_sync();
+        let center = std::f32::consts::FRAC_1_SQRT_2;
+        assert_float_eq!(
+            output.get_channel_data(0)[..],
+            [center; 128],
+            abs_all <= 1e-6
+        );
+        assert_float_eq!(
+            output.get_channel_data(1)[..],
+            [center; 128],
+            abs_all <= 1e-6
+        );
+    }
+}
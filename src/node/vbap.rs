@@ -0,0 +1,394 @@
+//! Vector base amplitude panning (VBAP) to arbitrary horizontal speaker layouts
+//!
+//! Directions are expressed as azimuths in degrees, measured counterclockwise from the front.
+use std::any::Any;
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::{
+    AudioNode, ChannelConfig, ChannelConfigOptions, ChannelCountMode, ChannelInterpretation,
+};
+
+/// Widest gap between two adjacent speakers that is panned with vectors, the wider gaps (e.g.
+/// behind a front-only layout) are crossed with an equal-power law
+const MAX_VECTOR_BASE_WIDTH: f32 = PI - 1e-3;
+
+/// Options for constructing a [`VbapPannerNode`]
+#[derive(Clone, Debug)]
+pub struct VbapPannerOptions {
+    /// azimuth in degrees of each speaker, in the order of the output channels
+    pub speakers: Vec<f32>,
+    /// initial azimuth of the source in degrees
+    pub azimuth: f32,
+}
+
+impl Default for VbapPannerOptions {
+    fn default() -> Self {
+        Self {
+            speakers: vec![45., -45., 135., -135.],
+            azimuth: 0.,
+        }
+    }
+}
+
+/// Two speakers adjacent on the circle, with the arc between them
+#[derive(Clone, Copy, Debug)]
+struct SpeakerPair {
+    channels: [usize; 2],
+    /// azimuth of the first speaker, in radians
+    start: f32,
+    /// counterclockwise angle to the second speaker, in radians
+    width: f32,
+}
+
+impl SpeakerPair {
+    /// Normalized gains of both speakers for the given angle from the first speaker
+    fn gains(&self, offset: f32) -> [f32; 2] {
+        if self.width == 0. {
+            // speakers at the same azimuth
+            [1., 0.]
+        } else if self.width <= MAX_VECTOR_BASE_WIDTH {
+            // p = g1 * l1 + g2 * l2, solved for the unit vectors of the speakers
+            let g1 = (self.width - offset).sin();
+            let g2 = offset.sin();
+            let norm = g1.hypot(g2);
+            [g1 / norm, g2 / norm]
+        } else {
+            let x = offset / self.width * FRAC_PI_2;
+            [x.cos(), x.sin()]
+        }
+    }
+}
+
+/// Speaker pairs covering the whole circle
+#[derive(Clone, Debug)]
+struct VbapLayout {
+    pairs: Vec<SpeakerPair>,
+}
+
+impl VbapLayout {
+    fn new(speakers: &[f32]) -> Self {
+        let mut sorted: Vec<(usize, f32)> = speakers
+            .iter()
+            .map(|azimuth| azimuth.to_radians().rem_euclid(TAU))
+            .enumerate()
+            .collect();
+        sorted.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        let pairs = (0..sorted.len())
+            .map(|k| {
+                let (first, start) = sorted[k];
+                let (second, end) = sorted[(k + 1) % sorted.len()];
+                let width = if k + 1 == sorted.len() {
+                    end + TAU - start
+                } else {
+                    end - start
+                };
+                SpeakerPair {
+                    channels: [first, second],
+                    start,
+                    width,
+                }
+            })
+            .collect();
+
+        Self { pairs }
+    }
+
+    /// Gain of each speaker for a source at the given azimuth in degrees
+    fn gains(&self, azimuth: f32, gains: &mut [f32]) {
+        gains.fill(0.);
+        if self.pairs.len() == 1 {
+            gains[0] = 1.;
+            return;
+        }
+
+        let azimuth = azimuth.to_radians();
+        let (pair, offset) = self
+            .pairs
+            .iter()
+            .map(|pair| (pair, (azimuth - pair.start).rem_euclid(TAU)))
+            .find(|(pair, offset)| *offset <= pair.width)
+            .unwrap_or((&self.pairs[0], 0.));
+
+        let [g1, g2] = pair.gains(offset.min(pair.width));
+        gains[pair.channels[0]] += g1;
+        gains[pair.channels[1]] += g2;
+    }
+}
+
+/// `VbapPannerNode` pans a mono source across a user-defined horizontal speaker layout
+///
+/// The source is played by the two speakers surrounding its azimuth, with vector base amplitude
+/// panning (VBAP) and a constant power. The output has one channel per speaker, which can be
+/// laid out arbitrarily, e.g. for surround or installation setups.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::node::{VbapPannerNode, VbapPannerOptions};
+///
+/// let context = AudioContext::default();
+///
+/// // hexagonal layout, the first speaker in front
+/// let options = VbapPannerOptions {
+///     speakers: vec![0., -60., -120., 180., 120., 60.],
+///     azimuth: 0.,
+/// };
+/// let panner = VbapPannerNode::new(&context, options);
+/// panner.connect(&context.destination());
+///
+/// // the source turns around the listener
+/// panner.azimuth().linear_ramp_to_value_at_time(360., 10.);
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&panner);
+/// osc.start();
+/// ```
+pub struct VbapPannerNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    speakers: Vec<f32>,
+    azimuth: AudioParam,
+}
+
+impl AudioNode for VbapPannerNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn set_channel_count(&self, _v: usize) {
+        panic!("InvalidStateError: Cannot edit channel count of VbapPannerNode")
+    }
+
+    fn set_channel_count_mode(&self, _v: ChannelCountMode) {
+        panic!("InvalidStateError: Cannot edit channel count mode of VbapPannerNode")
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl VbapPannerNode {
+    /// Create a new `VbapPannerNode`
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no speakers or more than [`MAX_CHANNELS`](crate::MAX_CHANNELS), or if
+    /// an azimuth is not finite
+    pub fn new<C: BaseAudioContext>(context: &C, options: VbapPannerOptions) -> Self {
+        let VbapPannerOptions { speakers, azimuth } = options;
+        assert!(
+            !speakers.is_empty() && speakers.len() <= crate::MAX_CHANNELS,
+            "NotSupportedError - number of speakers {:?} is outside range [1, {:?}]",
+            speakers.len(),
+            crate::MAX_CHANNELS
+        );
+        assert!(
+            speakers.iter().all(|angle| angle.is_finite()),
+            "RangeError - speaker azimuths must be finite, received {:?}",
+            speakers
+        );
+
+        context.register(move |registration| {
+            let descriptor = AudioParamDescriptor {
+                min_value: f32::MIN,
+                max_value: f32::MAX,
+                default_value: azimuth,
+                automation_rate: AutomationRate::K,
+            };
+            let (mut azimuth_param, azimuth_proc) =
+                context.create_audio_param(descriptor, &registration);
+            azimuth_param.set_automation_rate_constrained(true);
+
+            let layout = VbapLayout::new(&speakers);
+            let mut gains = vec![0.; speakers.len()];
+            layout.gains(azimuth, &mut gains);
+
+            let renderer = VbapPannerRenderer {
+                azimuth: azimuth_proc,
+                layout,
+                prev_gains: gains.clone(),
+                gains,
+            };
+
+            let node = Self {
+                registration,
+                channel_config: ChannelConfigOptions {
+                    count: 1,
+                    count_mode: ChannelCountMode::Explicit,
+                    interpretation: ChannelInterpretation::Speakers,
+                }
+                .into(),
+                speakers,
+                azimuth: azimuth_param,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// Azimuth in degrees of each speaker, in the order of the output channels
+    pub fn speakers(&self) -> &[f32] {
+        &self.speakers
+    }
+
+    /// K-rate [`AudioParam`] for the azimuth of the source, in degrees counterclockwise from
+    /// the front
+    pub fn azimuth(&self) -> &AudioParam {
+        &self.azimuth
+    }
+}
+
+struct VbapPannerRenderer {
+    azimuth: AudioParamId,
+    layout: VbapLayout,
+    prev_gains: Vec<f32>,
+    gains: Vec<f32>,
+}
+
+impl AudioProcessor for VbapPannerRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        _scope: &RenderScope,
+    ) -> bool {
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        let azimuth = params.get(&self.azimuth)[0];
+        std::mem::swap(&mut self.prev_gains, &mut self.gains);
+        self.layout.gains(azimuth, &mut self.gains);
+
+        if input.is_silent() {
+            output.make_silent();
+            return false;
+        }
+
+        output.set_number_of_channels(self.gains.len());
+
+        let source = input.channel_data(0);
+        output
+            .channels_mut()
+            .iter_mut()
+            .zip(self.prev_gains.iter().zip(self.gains.iter()))
+            .for_each(|(channel, (&prev, &gain))| {
+                // interpolate the gains to avoid zipper noise on movements
+                let step = (gain - prev) / RENDER_QUANTUM_SIZE as f32;
+                channel
+                    .iter_mut()
+                    .zip(source.iter())
+                    .enumerate()
+                    .for_each(|(i, (o, s))| *o = s * step.mul_add((i + 1) as f32, prev));
+            });
+
+        false
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        log::warn!("VbapPannerRenderer: Dropping incoming message {msg:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+    use std::f32::consts::FRAC_1_SQRT_2;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    /// Level of each speaker for a constant source at the given azimuth
+    fn render(speakers: &[f32], azimuth: f32) -> Vec<f32> {
+        let context = OfflineAudioContext::new(speakers.len(), RENDER_QUANTUM_SIZE, 44100.);
+        let options = VbapPannerOptions {
+            speakers: speakers.to_vec(),
+            azimuth,
+        };
+        let panner = VbapPannerNode::new(&context, options);
+        assert_eq!(panner.speakers(), speakers);
+        let dest = context.destination();
+        dest.set_channel_interpretation(ChannelInterpretation::Discrete);
+        panner.connect(&dest);
+
+        let mut src = context.create_constant_source();
+        src.connect(&panner);
+        src.start();
+
+        let output = context.start_rendering_sync();
+        (0..speakers.len())
+            .map(|k| output.get_channel_data(k)[0])
+            .collect()
+    }
+
+    #[test]
+    fn test_vbap_quad() {
+        let speakers = [45., -45., 135., -135.];
+
+        // a source at a speaker position only plays in this speaker
+        let levels = render(&speakers, -45.);
+        assert_float_eq!(levels[..], [0., 1., 0., 0.][..], abs_all <= 1e-6);
+        let levels = render(&speakers, 225.);
+        assert_float_eq!(levels[..], [0., 0., 0., 1.][..], abs_all <= 1e-6);
+
+        // a source between two speakers is shared with a constant power
+        let levels = render(&speakers, 0.);
+        let expected = [FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0., 0.];
+        assert_float_eq!(levels[..], expected[..], abs_all <= 1e-6);
+        let levels = render(&speakers, 90.);
+        let expected = [FRAC_1_SQRT_2, 0., FRAC_1_SQRT_2, 0.];
+        assert_float_eq!(levels[..], expected[..], abs_all <= 1e-6);
+
+        let levels = render(&speakers, 20.);
+        assert!(levels[0] > levels[1] && levels[1] > 0.);
+        let power: f32 = levels.iter().map(|l| l * l).sum();
+        assert_float_eq!(power, 1., abs <= 1e-6);
+    }
+
+    #[test]
+    fn test_vbap_front_layout() {
+        // the source crosses the gap behind a front-only layout with a constant power
+        let speakers = [30., -30.];
+        let levels = render(&speakers, 180.);
+        assert_float_eq!(levels[0], levels[1], abs <= 1e-6);
+        let levels = render(&speakers, 90.);
+        assert!(levels[0] > levels[1]);
+        let power: f32 = levels.iter().map(|l| l * l).sum();
+        assert_float_eq!(power, 1., abs <= 1e-6);
+
+        let levels = render(&[0.], 90.);
+        assert_float_eq!(levels[0], 1., abs <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_vbap_without_speakers() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44100.);
+        let options = VbapPannerOptions {
+            speakers: vec![],
+            azimuth: 0.,
+        };
+        let _ = VbapPannerNode::new(&context, options);
+    }
+}
@@ -3,11 +3,13 @@ use std::any::Any;
 use rubato::{FftFixedInOut, Resampler as _};
 
 use crate::{
-    context::{AudioContextRegistration, BaseAudioContext},
+    context::{AudioContextRegistration, AudioParamId, BaseAudioContext},
+    param::AudioParam,
     render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope},
     RENDER_QUANTUM_SIZE,
 };
 
+use super::dry_wet::{mix_dry_wet, mix_param_descriptor};
 use super::{AudioNode, ChannelConfig, ChannelConfigOptions};
 
 /// enumerates the oversampling rate available for `WaveShaperNode`
@@ -127,6 +129,8 @@ pub struct WaveShaperNode {
     curve: Option<Vec<f32>>,
     /// oversample type
     oversample: OverSampleType,
+    /// balance between the dry and the wet signal
+    mix: AudioParam,
 }
 
 impl AudioNode for WaveShaperNode {
@@ -163,10 +167,15 @@ impl WaveShaperNode {
 
         let mut node = context.register(move |registration| {
             let sample_rate = context.sample_rate() as usize;
+            let (mix, mix_proc) = context.create_audio_param(
+                mix_param_descriptor(context.strict_conformance()),
+                &registration,
+            );
 
             let renderer = WaveShaperRenderer::new(RendererConfig {
                 oversample,
                 sample_rate,
+                mix: mix_proc,
             });
 
             let node = Self {
@@ -174,6 +183,7 @@ impl WaveShaperNode {
                 channel_config: channel_config.into(),
                 curve: None,
                 oversample,
+                mix,
             };
 
             (node, Box::new(renderer))
@@ -187,6 +197,16 @@ impl WaveShaperNode {
         node
     }
 
+    /// Returns the mix audio parameter, the equal-power balance between the dry (0) and the
+    /// distorted (1) signal
+    ///
+    /// The input passes through unchanged while no curve is set. This is an extension to the
+    /// spec, defaults to 1.
+    #[must_use]
+    pub fn mix(&self) -> &AudioParam {
+        &self.mix
+    }
+
     /// Returns the distortion curve
     #[must_use]
     pub fn curve(&self) -> Option<&[f32]> {
@@ -355,6 +375,8 @@ struct RendererConfig {
     oversample: OverSampleType,
     /// Sample rate (equals to audio context sample rate)
     sample_rate: usize,
+    /// balance between the dry and the wet signal
+    mix: AudioParamId,
 }
 
 /// `WaveShaperRenderer` represents the rendering part of `WaveShaperNode`
@@ -365,6 +387,8 @@ struct WaveShaperRenderer {
     curve: Option<Vec<f32>>,
     /// Sample rate (equals to audio context sample rate)
     sample_rate: usize,
+    /// balance between the dry and the wet signal
+    mix: AudioParamId,
     /// Number of channels used to build the up/down sampler X2
     channels_x2: usize,
     /// Number of channels used to build the up/down sampler X4
@@ -384,7 +408,7 @@ impl AudioProcessor for WaveShaperRenderer {
         &mut self,
         inputs: &[AudioRenderQuantum],
         outputs: &mut [AudioRenderQuantum],
-        _params: AudioParamValues<'_>,
+        params: AudioParamValues<'_>,
         _scope: &RenderScope,
     ) -> bool {
         // single input/output node
@@ -479,6 +503,8 @@ impl AudioProcessor for WaveShaperRenderer {
                     }
                 }
             }
+
+            mix_dry_wet(input, output, &params.get(&self.mix));
         }
 
         // @tbc - rubato::FftFixedInOut doesn't seem to introduce any latency
@@ -507,6 +533,7 @@ impl WaveShaperRenderer {
         let RendererConfig {
             sample_rate,
             oversample,
+            mix,
         } = config;
 
         let channels_x2 = 1;
@@ -526,6 +553,7 @@ impl WaveShaperRenderer {
             oversample,
             curve: None,
             sample_rate,
+            mix,
             channels_x2,
             channels_x4,
             upsampler_x2,
@@ -0,0 +1,455 @@
+use std::f64::consts::PI;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use arrayvec::ArrayVec;
+
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
+use crate::{AtomicF32, RENDER_QUANTUM_SIZE};
+
+use super::{AudioNode, ChannelConfig, ChannelConfigOptions};
+
+/// Pole frequencies of the weighting curves in Hertz, as given by IEC 61672-1
+const POLE_1: f64 = 20.598_997;
+const POLE_2: f64 = 107.652_65;
+const POLE_3: f64 = 737.862_23;
+const POLE_4: f64 = 12_194.217;
+
+/// Frequency at which the weighting curves are normalized to 0 dB, in Hertz
+const REFERENCE_FREQUENCY: f64 = 1000.;
+
+/// Maximum number of first order sections of a weighting filter
+const MAX_SECTIONS: usize = 6;
+
+/// Mean square of the signal below which the level is reported as silence (-200 dBFS)
+const SILENCE_MEAN_SQUARE: f64 = 1e-20;
+
+/// Frequency weighting curve of an [`AWeightingNode`], as defined by IEC 61672-1
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FrequencyWeighting {
+    /// A-weighting, approximating the loudness perceived at low levels
+    #[default]
+    A,
+    /// C-weighting, flatter, for peak and high level measurements
+    C,
+}
+
+/// Options for constructing an [`AWeightingNode`]
+#[derive(Clone, Debug)]
+pub struct AWeightingOptions {
+    pub weighting: FrequencyWeighting,
+    /// time constant of the exponential averaging of the level, in seconds, defaults to the
+    /// "fast" time weighting (0.125s)
+    pub time_constant: f64,
+    /// sound pressure level in dB of a full scale signal (0 dBFS RMS), see
+    /// [`AWeightingNode::set_calibration`]
+    pub calibration: f32,
+    pub channel_config: ChannelConfigOptions,
+}
+
+impl Default for AWeightingOptions {
+    fn default() -> Self {
+        Self {
+            weighting: FrequencyWeighting::A,
+            time_constant: 0.125,
+            calibration: 0.,
+            channel_config: ChannelConfigOptions::default(),
+        }
+    }
+}
+
+/// `AWeightingNode` applies an A or C frequency weighting to its input and measures the
+/// weighted level, as a sound level meter does
+///
+/// The weighting filters follow the analog prototypes of IEC 61672-1, digitized with the
+/// bilinear transform and normalized to 0 dB at 1 kHz. The curves match within 0.1 dB up to
+/// 4 kHz, and roll off slightly faster towards the Nyquist frequency.
+///
+/// The output is the weighted input. The level of the output is averaged over all channels with
+/// an exponential time weighting, and can be read from the control thread in dBFS with
+/// [`level`](Self::level), or in dB SPL with [`spl`](Self::spl) once the node has been
+/// calibrated. The calibration is the offset between both: the sound pressure level of a full
+/// scale signal through the whole input chain (microphone, preamp, converter).
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::media_devices::{self, MediaStreamConstraints};
+/// use web_audio_api::node::{AudioNode, AWeightingNode, AWeightingOptions};
+///
+/// let context = AudioContext::default();
+///
+/// let mic = media_devices::get_user_media_sync(MediaStreamConstraints::Audio);
+/// let source = context.create_media_stream_source(&mic);
+///
+/// let mut meter = AWeightingNode::new(&context, AWeightingOptions::default());
+/// source.connect(&meter);
+///
+/// // with a 94 dB SPL acoustic calibrator on the microphone
+/// std::thread::sleep(std::time::Duration::from_secs(2));
+/// meter.calibrate(94.);
+///
+/// loop {
+///     println!("{:.1} dB(A)", meter.spl());
+///     std::thread::sleep(std::time::Duration::from_millis(125));
+/// }
+/// ```
+pub struct AWeightingNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+    weighting: FrequencyWeighting,
+    time_constant: f64,
+    calibration: f32,
+    level: Arc<AtomicF32>,
+}
+
+impl AudioNode for AWeightingNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl AWeightingNode {
+    /// Create a new `AWeightingNode`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the time constant is not strictly positive and finite, or if the calibration is
+    /// not finite
+    pub fn new<C: BaseAudioContext>(context: &C, options: AWeightingOptions) -> Self {
+        let AWeightingOptions {
+            weighting,
+            time_constant,
+            calibration,
+            channel_config,
+        } = options;
+
+        assert!(
+            time_constant.is_finite() && time_constant > 0.,
+            "RangeError - invalid time constant {:?}",
+            time_constant
+        );
+        assert_valid_calibration(calibration);
+
+        context.register(move |registration| {
+            let sample_rate = f64::from(context.sample_rate());
+            let level = Arc::new(AtomicF32::new(f32::NEG_INFINITY));
+
+            let render = AWeightingRenderer {
+                sections: weighting_sections(weighting, sample_rate),
+                states: vec![],
+                mean_square: 0.,
+                smoothing: (-1. / (time_constant * sample_rate)).exp(),
+                level: Arc::clone(&level),
+            };
+
+            let node = AWeightingNode {
+                registration,
+                channel_config: channel_config.into(),
+                weighting,
+                time_constant,
+                calibration,
+                level,
+            };
+
+            (node, Box::new(render))
+        })
+    }
+
+    /// Frequency weighting curve of the filter
+    pub fn weighting(&self) -> FrequencyWeighting {
+        self.weighting
+    }
+
+    /// Time constant of the level averaging, in seconds
+    pub fn time_constant(&self) -> f64 {
+        self.time_constant
+    }
+
+    /// Weighted level of the output, in dBFS
+    ///
+    /// Negative infinity when the output is silent.
+    pub fn level(&self) -> f32 {
+        self.level.load(Ordering::Relaxed)
+    }
+
+    /// Weighted sound pressure level, in dB SPL
+    ///
+    /// This is the level offset by the calibration.
+    pub fn spl(&self) -> f32 {
+        self.level() + self.calibration
+    }
+
+    /// Sound pressure level in dB of a full scale signal
+    pub fn calibration(&self) -> f32 {
+        self.calibration
+    }
+
+    /// Set the sound pressure level in dB of a full scale signal, e.g. from the sensitivity of
+    /// the microphone and the gain of the input chain
+    ///
+    /// # Panics
+    ///
+    /// Panics if the calibration is not finite
+    pub fn set_calibration(&mut self, calibration: f32) {
+        assert_valid_calibration(calibration);
+        self.calibration = calibration;
+    }
+
+    /// Set the calibration so that the current level reads as the given sound pressure level,
+    /// e.g. while a reference calibrator is playing into the microphone
+    ///
+    /// # Panics
+    ///
+    /// Panics if the output is silent or the reference level is not finite
+    pub fn calibrate(&mut self, reference_spl: f32) {
+        let level = self.level();
+        assert!(
+            level.is_finite(),
+            "InvalidStateError - Unable to calibrate on a silent signal"
+        );
+        self.set_calibration(reference_spl - level);
+    }
+}
+
+fn assert_valid_calibration(calibration: f32) {
+    assert!(
+        calibration.is_finite(),
+        "RangeError - invalid calibration {:?}",
+        calibration
+    );
+}
+
+/// First order filter section `y[n] = b0 * x[n] + b1 * x[n - 1] - a1 * y[n - 1]`
+#[derive(Clone, Copy, Debug)]
+struct Section {
+    b0: f64,
+    b1: f64,
+    a1: f64,
+}
+
+impl Section {
+    /// Bilinear transform of `s / (s + w)` or `w / (s + w)`
+    fn new(pole: f64, high_pass: bool, sample_rate: f64) -> Self {
+        let k = 2. * sample_rate;
+        let w = 2. * PI * pole;
+        let norm = 1. / (k + w);
+        let (b0, b1) = if high_pass {
+            (k * norm, -k * norm)
+        } else {
+            (w * norm, w * norm)
+        };
+        Self {
+            b0,
+            b1,
+            a1: (w - k) * norm,
+        }
+    }
+
+    /// Magnitude of the frequency response at the given normalized angular frequency
+    fn magnitude(&self, omega: f64) -> f64 {
+        let (sin, cos) = omega.sin_cos();
+        // H(z) = (b0 + b1 z^-1) / (1 + a1 z^-1), with z^-1 = cos - j sin
+        let num = (self.b0 + self.b1 * cos).hypot(self.b1 * sin);
+        let den = (1. + self.a1 * cos).hypot(self.a1 * sin);
+        num / den
+    }
+}
+
+/// First order sections of the weighting filter, with the gain normalized at the reference
+/// frequency applied to the first section
+fn weighting_sections(
+    weighting: FrequencyWeighting,
+    sample_rate: f64,
+) -> ArrayVec<Section, MAX_SECTIONS> {
+    let mut sections = ArrayVec::new();
+
+    let high_poles: &[f64] = match weighting {
+        FrequencyWeighting::A => &[POLE_1, POLE_1, POLE_2, POLE_3],
+        FrequencyWeighting::C => &[POLE_1, POLE_1],
+    };
+    high_poles
+        .iter()
+        .for_each(|&pole| sections.push(Section::new(pole, true, sample_rate)));
+
+    (0..2).for_each(|_| sections.push(Section::new(POLE_4, false, sample_rate)));
+
+    let omega = 2. * PI * REFERENCE_FREQUENCY / sample_rate;
+    let gain: f64 = sections.iter().map(|s| s.magnitude(omega)).product();
+    sections[0].b0 /= gain;
+    sections[0].b1 /= gain;
+
+    sections
+}
+
+struct AWeightingRenderer {
+    sections: ArrayVec<Section, MAX_SECTIONS>,
+    /// per channel, the last input followed by the last output of each section
+    states: Vec<[f64; MAX_SECTIONS + 1]>,
+    /// exponentially averaged mean square of the output
+    mean_square: f64,
+    /// smoothing factor of the mean square, per sample
+    smoothing: f64,
+    level: Arc<AtomicF32>,
+}
+
+impl AudioProcessor for AWeightingRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &RenderScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        let ended = input.is_silent()
+            && self
+                .states
+                .iter()
+                .all(|state| state.iter().all(|v| !v.is_normal()));
+
+        if ended {
+            output.make_silent();
+            self.mean_square *= self.smoothing.powi(RENDER_QUANTUM_SIZE as i32);
+        } else {
+            // if in tail time, we should continue with previous number of channels
+            if !input.is_silent() {
+                self.states
+                    .resize(input.number_of_channels(), [0.; MAX_SECTIONS + 1]);
+            }
+            let number_of_channels = self.states.len();
+            *output = input.clone();
+            output.set_number_of_channels(number_of_channels);
+
+            let mut sum_squares = [0.; RENDER_QUANTUM_SIZE];
+            output
+                .channels_mut()
+                .iter_mut()
+                .zip(self.states.iter_mut())
+                .for_each(|(channel, state)| {
+                    channel
+                        .iter_mut()
+                        .zip(sum_squares.iter_mut())
+                        .for_each(|(o, sum)| {
+                            let mut x = f64::from(*o);
+                            for (i, section) in self.sections.iter().enumerate() {
+                                let y = section.b0 * x + section.b1 * state[i]
+                                    - section.a1 * state[i + 1];
+                                state[i] = x;
+                                x = y;
+                            }
+                            state[self.sections.len()] = x;
+                            *o = x as f32;
+                            *sum += x * x;
+                        });
+                });
+
+            let scale = 1. / number_of_channels.max(1) as f64;
+            sum_squares.iter().for_each(|sum| {
+                self.mean_square =
+                    self.smoothing * self.mean_square + (1. - self.smoothing) * sum * scale;
+            });
+        }
+
+        let level = if self.mean_square > SILENCE_MEAN_SQUARE {
+            10. * self.mean_square.log10()
+        } else {
+            f64::NEG_INFINITY
+        };
+        self.level.store(level as f32, Ordering::Relaxed);
+
+        !ended
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    fn response(weighting: FrequencyWeighting, frequency: f64) -> f64 {
+        let sample_rate = 48_000.;
+        let omega = 2. * PI * frequency / sample_rate;
+        let gain: f64 = weighting_sections(weighting, sample_rate)
+            .iter()
+            .map(|s| s.magnitude(omega))
+            .product();
+        20. * gain.log10()
+    }
+
+    #[test]
+    fn test_weighting_curves() {
+        // exact values of the analog curves of IEC 61672-1
+        let a = [
+            (31.5, -39.53),
+            (100., -19.15),
+            (250., -8.67),
+            (1000., 0.),
+            (2000., 1.20),
+            (4000., 0.96),
+        ];
+        a.iter().for_each(|&(f, db)| {
+            assert_float_eq!(response(FrequencyWeighting::A, f), db, abs <= 0.1);
+        });
+
+        let c = [(31.5, -2.97), (100., -0.30), (1000., 0.), (2000., -0.17)];
+        c.iter().for_each(|&(f, db)| {
+            assert_float_eq!(response(FrequencyWeighting::C, f), db, abs <= 0.1);
+        });
+    }
+
+    #[test]
+    fn test_level() {
+        let context = OfflineAudioContext::new(1, 48_000, 48_000.);
+        let options = AWeightingOptions {
+            calibration: 100.,
+            ..AWeightingOptions::default()
+        };
+        let mut meter = AWeightingNode::new(&context, options);
+        meter.connect(&context.destination());
+
+        let mut osc = context.create_oscillator();
+        osc.frequency().set_value(1000.);
+        osc.connect(&meter);
+        osc.start();
+
+        let _ = context.start_rendering_sync();
+        // full scale sine at 1 kHz
+        assert_float_eq!(meter.level(), -3.01, abs <= 0.05);
+        assert_float_eq!(meter.spl(), 96.99, abs <= 0.05);
+
+        meter.calibrate(94.);
+        assert_float_eq!(meter.calibration(), 97.01, abs <= 0.05);
+        assert_float_eq!(meter.spl(), 94., abs <= 1e-4);
+    }
+
+    #[test]
+    fn test_silence() {
+        let context = OfflineAudioContext::new(1, 1024, 48_000.);
+        let meter = AWeightingNode::new(&context, AWeightingOptions::default());
+        let _ = context.start_rendering_sync();
+        assert_eq!(meter.level(), f32::NEG_INFINITY);
+    }
+}
@@ -0,0 +1,685 @@
+//! Remote control of a running audio graph over OSC (Open Sound Control)
+//!
+//! An [`OscServer`] listens for OSC messages on a UDP socket and dispatches them to the handlers
+//! registered for their address, e.g. to set the value of an [`AudioParam`] or to suspend the
+//! context from a live-coding environment or a show-control application.
+//!
+//! Incoming addresses may contain the OSC 1.0 pattern syntax (`?`, `*`, `[a-z]`, `[!abc]` and
+//! `{foo,bar}`), a single message then reaches all the matching handlers. Bundles are
+//! dispatched as soon as they are received, their time tag is ignored.
+
+use std::error::Error;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::context::{AudioContext, BaseAudioContext};
+use crate::node::AudioNode;
+use crate::param::AudioParam;
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Maximum size of an incoming packet
+const MAX_PACKET_SIZE: usize = 65536;
+
+/// Interval at which the server thread checks if it should shut down
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Argument of an [`OscMessage`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum OscArgument {
+    Int(i32),
+    Float(f32),
+    String(String),
+    Blob(Vec<u8>),
+    Long(i64),
+    Double(f64),
+    True,
+    False,
+    Nil,
+}
+
+impl OscArgument {
+    /// Numeric value of the argument, if any
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Int(v) => Some(f64::from(*v)),
+            Self::Float(v) => Some(f64::from(*v)),
+            Self::Long(v) => Some(*v as f64),
+            Self::Double(v) => Some(*v),
+            Self::True => Some(1.),
+            Self::False => Some(0.),
+            _ => None,
+        }
+    }
+
+    fn type_tag(&self) -> u8 {
+        match self {
+            Self::Int(_) => b'i',
+            Self::Float(_) => b'f',
+            Self::String(_) => b's',
+            Self::Blob(_) => b'b',
+            Self::Long(_) => b'h',
+            Self::Double(_) => b'd',
+            Self::True => b'T',
+            Self::False => b'F',
+            Self::Nil => b'N',
+        }
+    }
+}
+
+/// OSC message, i.e. an address and a list of arguments
+#[derive(Clone, Debug, PartialEq)]
+pub struct OscMessage {
+    pub address: String,
+    pub arguments: Vec<OscArgument>,
+}
+
+/// Append a string, nul terminated and padded to a multiple of 4 bytes
+fn write_padded(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(bytes);
+    out.resize((out.len() + 4) & !3, 0);
+}
+
+/// Reads the big endian and 4 bytes aligned fields of a packet
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or("InvalidData - unexpected end of OSC packet")?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let rest = &self.data[self.pos.min(self.data.len())..];
+        let len = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or("InvalidData - unterminated OSC string")?;
+        let value = std::str::from_utf8(&rest[..len])?.to_owned();
+        self.pos += (len + 4) & !3;
+        Ok(value)
+    }
+
+    fn blob(&mut self) -> Result<Vec<u8>> {
+        let len = u32::from_be_bytes(self.array()?) as usize;
+        let value = self.take(len)?.to_vec();
+        self.pos += (4 - len % 4) % 4;
+        Ok(value)
+    }
+}
+
+impl OscMessage {
+    /// Create a new message
+    pub fn new(address: impl Into<String>, arguments: Vec<OscArgument>) -> Self {
+        Self {
+            address: address.into(),
+            arguments,
+        }
+    }
+
+    /// Encode the message as an OSC packet
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = vec![];
+        write_padded(&mut out, self.address.as_bytes());
+
+        let mut tags = vec![b','];
+        tags.extend(self.arguments.iter().map(OscArgument::type_tag));
+        write_padded(&mut out, &tags);
+
+        for argument in &self.arguments {
+            match argument {
+                OscArgument::Int(v) => out.extend_from_slice(&v.to_be_bytes()),
+                OscArgument::Float(v) => out.extend_from_slice(&v.to_be_bytes()),
+                OscArgument::String(v) => write_padded(&mut out, v.as_bytes()),
+                OscArgument::Blob(v) => {
+                    out.extend_from_slice(&(v.len() as u32).to_be_bytes());
+                    out.extend_from_slice(v);
+                    out.resize((out.len() + 3) & !3, 0);
+                }
+                OscArgument::Long(v) => out.extend_from_slice(&v.to_be_bytes()),
+                OscArgument::Double(v) => out.extend_from_slice(&v.to_be_bytes()),
+                OscArgument::True | OscArgument::False | OscArgument::Nil => (),
+            }
+        }
+
+        out
+    }
+
+    /// Decode the messages of an OSC packet, bundles are flattened
+    pub fn decode(packet: &[u8]) -> Result<Vec<Self>> {
+        let mut messages = vec![];
+        decode_packet(packet, &mut messages)?;
+        Ok(messages)
+    }
+}
+
+fn decode_packet(packet: &[u8], messages: &mut Vec<OscMessage>) -> Result<()> {
+    let mut reader = Reader {
+        data: packet,
+        pos: 0,
+    };
+
+    if packet.starts_with(b"#bundle\0") {
+        reader.pos = 16; // skip the time tag
+        while reader.pos < packet.len() {
+            let len = u32::from_be_bytes(reader.array()?) as usize;
+            decode_packet(reader.take(len)?, messages)?;
+        }
+        return Ok(());
+    }
+
+    let address = reader.string()?;
+    if !address.starts_with('/') {
+        return Err("InvalidData - OSC address should start with '/'".into());
+    }
+
+    // the type tag string may be missing in messages without arguments
+    let tags = if reader.pos < packet.len() {
+        reader.string()?
+    } else {
+        String::from(",")
+    };
+    let tags = tags
+        .strip_prefix(',')
+        .ok_or("InvalidData - invalid OSC type tag string")?;
+
+    let arguments = tags
+        .bytes()
+        .map(|tag| {
+            let argument = match tag {
+                b'i' => OscArgument::Int(i32::from_be_bytes(reader.array()?)),
+                b'f' => OscArgument::Float(f32::from_be_bytes(reader.array()?)),
+                b's' | b'S' => OscArgument::String(reader.string()?),
+                b'b' => OscArgument::Blob(reader.blob()?),
+                b'h' => OscArgument::Long(i64::from_be_bytes(reader.array()?)),
+                b'd' => OscArgument::Double(f64::from_be_bytes(reader.array()?)),
+                b'T' => OscArgument::True,
+                b'F' => OscArgument::False,
+                b'N' => OscArgument::Nil,
+                _ => {
+                    let tag = char::from(tag);
+                    return Err(format!("NotSupportedError - OSC type tag {tag:?}").into());
+                }
+            };
+            Ok(argument)
+        })
+        .collect::<Result<_>>()?;
+
+    messages.push(OscMessage { address, arguments });
+    Ok(())
+}
+
+/// Maximum length of an OSC address pattern, longer patterns do not match any address
+const MAX_PATTERN_LENGTH: usize = 1024;
+
+/// Check if an OSC address pattern matches an address
+fn pattern_matches(pattern: &[u8], address: &[u8]) -> bool {
+    if pattern.len() > MAX_PATTERN_LENGTH {
+        return false;
+    }
+
+    // The patterns are received from the network: the results are memoized by position in the
+    // pattern and in the address, so wildcards cannot cost exponential time.
+    let mut memo = vec![None; (pattern.len() + 1) * (address.len() + 1)];
+    matches_from(pattern, address, 0, 0, &mut memo)
+}
+
+/// Check if the pattern from position `p` matches the address from position `a`
+fn matches_from(
+    pattern: &[u8],
+    address: &[u8],
+    p: usize,
+    a: usize,
+    memo: &mut [Option<bool>],
+) -> bool {
+    let key = p * (address.len() + 1) + a;
+    if let Some(matches) = memo[key] {
+        return matches;
+    }
+
+    let next = address.get(a).copied();
+    let matches = match pattern.get(p) {
+        None => a == address.len(),
+        Some(b'*') => {
+            // match any sequence of characters within the current part of the address
+            (a..=address.len())
+                .take_while(|&i| i == a || address[i - 1] != b'/')
+                .any(|i| matches_from(pattern, address, p + 1, i, memo))
+        }
+        Some(b'?') => match next {
+            Some(c) if c != b'/' => matches_from(pattern, address, p + 1, a + 1, memo),
+            _ => false,
+        },
+        Some(b'[') => {
+            let end = match pattern[p..].iter().position(|&c| c == b']') {
+                Some(end) => p + end,
+                None => return false,
+            };
+            let c = match next {
+                Some(c) if c != b'/' => c,
+                _ => return false,
+            };
+
+            let (negate, set) = match &pattern[p + 1..end] {
+                [b'!', set @ ..] => (true, set),
+                set => (false, set),
+            };
+            let mut found = false;
+            let mut i = 0;
+            while i < set.len() {
+                if i + 2 < set.len() && set[i + 1] == b'-' {
+                    found |= (set[i]..=set[i + 2]).contains(&c);
+                    i += 3;
+                } else {
+                    found |= set[i] == c;
+                    i += 1;
+                }
+            }
+
+            found != negate && matches_from(pattern, address, end + 1, a + 1, memo)
+        }
+        Some(b'{') => {
+            let end = match pattern[p..].iter().position(|&c| c == b'}') {
+                Some(end) => p + end,
+                None => return false,
+            };
+            pattern[p + 1..end].split(|&c| c == b',').any(|alt| {
+                address[a..].starts_with(alt)
+                    && matches_from(pattern, address, end + 1, a + alt.len(), memo)
+            })
+        }
+        Some(&c) => next == Some(c) && matches_from(pattern, address, p + 1, a + 1, memo),
+    };
+
+    memo[key] = Some(matches);
+    matches
+}
+
+type Handler = Box<dyn Fn(&[OscArgument]) + Send + 'static>;
+
+/// UDP server dispatching incoming OSC messages to handlers
+///
+/// The messages are received and handled on a dedicated thread, which is stopped when the
+/// server is dropped.
+///
+/// # Usage
+///
+/// ```no_run
+/// use std::sync::Arc;
+///
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::osc::OscServer;
+///
+/// let context = Arc::new(AudioContext::default());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&context.destination());
+/// osc.start();
+///
+/// let server = OscServer::bind("0.0.0.0:9000").unwrap();
+/// // e.g. `/synth/frequency 440.0 0.5` ramps to 440 Hz in half a second
+/// server.map_param("/synth/frequency", osc.frequency());
+/// server.map_param("/synth/detune", osc.detune());
+/// // `/transport/suspend` and `/transport/resume`
+/// server.map_transport("/transport", Arc::clone(&context));
+///
+/// std::thread::park();
+/// ```
+pub struct OscServer {
+    local_addr: SocketAddr,
+    routes: Arc<Mutex<Vec<(String, Handler)>>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for OscServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let routes = self.routes.lock().unwrap_or_else(PoisonError::into_inner);
+        f.debug_struct("OscServer")
+            .field("local_addr", &self.local_addr)
+            .field(
+                "routes",
+                &routes
+                    .iter()
+                    .map(|(address, _)| address)
+                    .collect::<Vec<_>>(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl OscServer {
+    /// Listen for OSC messages on the given UDP address
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket cannot be bound
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_read_timeout(Some(POLL_INTERVAL))?;
+        let local_addr = socket.local_addr()?;
+
+        let routes: Arc<Mutex<Vec<(String, Handler)>>> = Arc::default();
+        let running = Arc::new(AtomicBool::new(true));
+
+        let thread = {
+            let routes = Arc::clone(&routes);
+            let running = Arc::clone(&running);
+            std::thread::Builder::new()
+                .name("web-audio-api-osc".into())
+                .spawn(move || {
+                    let mut buffer = vec![0; MAX_PACKET_SIZE];
+                    while running.load(Ordering::Relaxed) {
+                        let len = match socket.recv_from(&mut buffer) {
+                            Ok((len, _)) => len,
+                            Err(e)
+                                if matches!(
+                                    e.kind(),
+                                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                                ) =>
+                            {
+                                continue
+                            }
+                            Err(e) => {
+                                log::warn!("OscServer: receive failed: {e}");
+                                continue;
+                            }
+                        };
+
+                        match OscMessage::decode(&buffer[..len]) {
+                            Ok(messages) => {
+                                let routes = routes.lock().unwrap_or_else(PoisonError::into_inner);
+                                messages.iter().for_each(|message| {
+                                    routes
+                                        .iter()
+                                        .filter(|(address, _)| {
+                                            pattern_matches(
+                                                message.address.as_bytes(),
+                                                address.as_bytes(),
+                                            )
+                                        })
+                                        .for_each(|(address, handler)| {
+                                            // keep serving the other routes
+                                            let call = || handler(&message.arguments);
+                                            if panic::catch_unwind(AssertUnwindSafe(call)).is_err()
+                                            {
+                                                log::error!(
+                                                    "OscServer: handler of {address} panicked"
+                                                );
+                                            }
+                                        });
+                                });
+                            }
+                            Err(e) => log::warn!("OscServer: dropping invalid packet: {e}"),
+                        }
+                    }
+                })?
+        };
+
+        Ok(Self {
+            local_addr,
+            routes,
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    /// Address the server is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Call the handler with the arguments of the messages sent to the given address
+    ///
+    /// Several handlers can be registered for the same address. The handlers are called on the
+    /// server thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the address does not start with `/`
+    pub fn map<F: Fn(&[OscArgument]) + Send + 'static>(&self, address: &str, handler: F) {
+        assert!(
+            address.starts_with('/'),
+            "SyntaxError - OSC address should start with '/'"
+        );
+        self.routes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((address.to_owned(), Box::new(handler)));
+    }
+
+    /// Set the value of the param with the messages sent to the given address
+    ///
+    /// The first argument of the message is the new value. When a second argument is given, the
+    /// param linearly ramps to the new value over this duration in seconds. Messages with
+    /// non-finite values or durations are dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the address does not start with `/`
+    pub fn map_param(&self, address: &str, param: &AudioParam) {
+        let param = param.clone();
+        self.map(address, move |arguments| {
+            let value = match arguments.first().and_then(OscArgument::as_f64) {
+                Some(value) if (value as f32).is_finite() => value as f32,
+                _ => {
+                    log::warn!("OscServer: expected a finite numeric value, got {arguments:?}");
+                    return;
+                }
+            };
+            let duration = arguments.get(1).and_then(OscArgument::as_f64);
+            if duration.is_some_and(|duration| !duration.is_finite()) {
+                log::warn!("OscServer: expected a finite duration, got {arguments:?}");
+                return;
+            }
+
+            match duration {
+                Some(duration) if duration > 0. => {
+                    let now = param.context().current_time();
+                    param
+                        .cancel_and_hold_at_time(now)
+                        .linear_ramp_to_value_at_time(value, now + duration);
+                }
+                _ => {
+                    param.set_value(value);
+                }
+            }
+        });
+    }
+
+    /// Control the state of the context with the `{prefix}/suspend`, `{prefix}/resume` and
+    /// `{prefix}/close` addresses
+    ///
+    /// # Panics
+    ///
+    /// Panics if the prefix does not start with `/`
+    pub fn map_transport(&self, prefix: &str, context: Arc<AudioContext>) {
+        let prefix = prefix.trim_end_matches('/');
+
+        let ctx = Arc::clone(&context);
+        self.map(&format!("{prefix}/suspend"), move |_| ctx.suspend_sync());
+        let ctx = Arc::clone(&context);
+        self.map(&format!("{prefix}/resume"), move |_| ctx.resume_sync());
+        self.map(&format!("{prefix}/close"), move |_| context.close_sync());
+    }
+
+    /// Remove the handlers of the given address, returns `false` if there were none
+    pub fn unmap(&self, address: &str) -> bool {
+        let mut routes = self.routes.lock().unwrap_or_else(PoisonError::into_inner);
+        let len = routes.len();
+        routes.retain(|(a, _)| a != address);
+        routes.len() != len
+    }
+}
+
+impl Drop for OscServer {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::time::Instant;
+
+    use crate::context::OfflineAudioContext;
+
+    use super::*;
+
+    fn matches(pattern: &str, address: &str) -> bool {
+        pattern_matches(pattern.as_bytes(), address.as_bytes())
+    }
+
+    #[test]
+    fn test_pattern_matching() {
+        assert!(matches("/synth/freq", "/synth/freq"));
+        assert!(!matches("/synth/freq", "/synth/frequency"));
+        assert!(matches("/synth/*", "/synth/freq"));
+        assert!(!matches("/*", "/synth/freq"));
+        assert!(matches("/*/freq", "/synth/freq"));
+        assert!(matches("/synth/fr?q", "/synth/freq"));
+        assert!(!matches("/synth?freq", "/synth/freq"));
+        assert!(matches("/voice[1-3]/gain", "/voice2/gain"));
+        assert!(!matches("/voice[1-3]/gain", "/voice4/gain"));
+        assert!(matches("/voice[!1-3]/gain", "/voice4/gain"));
+        assert!(matches("/voice[135]/gain", "/voice5/gain"));
+        assert!(matches("/{drums,bass}/gain", "/bass/gain"));
+        assert!(!matches("/{drums,bass}/gain", "/lead/gain"));
+        assert!(matches("/*/{gain,pan}", "/lead/pan"));
+    }
+
+    #[test]
+    fn test_pathological_pattern() {
+        // each star could match any split of the address: exponential without memoization
+        let pattern = format!("/{}x", "*".repeat(200));
+        let address = format!("/{}", "a".repeat(100));
+        let start = std::time::Instant::now();
+        assert!(!matches(&pattern, &address));
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        let pattern = format!("/{}", "*".repeat(MAX_PATTERN_LENGTH));
+        assert!(!matches(&pattern, "/synth"));
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let message = OscMessage::new(
+            "/synth/freq",
+            vec![
+                OscArgument::Float(440.),
+                OscArgument::Int(-3),
+                OscArgument::String("hello".into()),
+                OscArgument::Blob(vec![1, 2, 3, 4, 5]),
+                OscArgument::True,
+                OscArgument::Double(0.5),
+                OscArgument::Long(1 << 40),
+            ],
+        );
+        let packet = message.encode();
+        assert_eq!(packet.len() % 4, 0);
+        assert_eq!(OscMessage::decode(&packet).unwrap(), vec![message.clone()]);
+
+        // bundle containing the message twice
+        let mut bundle = b"#bundle\0".to_vec();
+        bundle.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+        for _ in 0..2 {
+            bundle.extend_from_slice(&(packet.len() as u32).to_be_bytes());
+            bundle.extend_from_slice(&packet);
+        }
+        assert_eq!(
+            OscMessage::decode(&bundle).unwrap(),
+            vec![message.clone(), message]
+        );
+
+        assert!(OscMessage::decode(b"").is_err());
+        assert!(OscMessage::decode(b"synth\0\0\0").is_err());
+        assert!(OscMessage::decode(b"/a\0\0,f\0\0").is_err());
+    }
+
+    #[test]
+    fn test_server() {
+        let server = OscServer::bind("127.0.0.1:0").unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        server.map("/voice1/gain", move |args| {
+            sender.send(args.to_vec()).unwrap();
+        });
+
+        let context = OfflineAudioContext::new(1, 128, 44100.);
+        let gain = context.create_gain();
+        server.map_param("/voice2/gain", gain.gain());
+
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let message = OscMessage::new("/voice[1-2]/gain", vec![OscArgument::Float(0.25)]);
+        client
+            .send_to(&message.encode(), server.local_addr())
+            .unwrap();
+
+        let args = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(args, vec![OscArgument::Float(0.25)]);
+
+        let start = Instant::now();
+        while gain.gain().value() != 0.25 {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        assert!(server.unmap("/voice1/gain"));
+        assert!(!server.unmap("/voice1/gain"));
+    }
+
+    #[test]
+    fn test_server_invalid_messages() {
+        let server = OscServer::bind("127.0.0.1:0").unwrap();
+        server.map("/panic", |_| panic!("handler failure"));
+
+        let context = OfflineAudioContext::new(1, 128, 44100.);
+        let gain = context.create_gain();
+        server.map_param("/gain", gain.gain());
+
+        let (sender, receiver) = mpsc::channel();
+        server.map("/done", move |_| sender.send(()).unwrap());
+
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let messages = [
+            OscMessage::new("/panic", vec![]),
+            OscMessage::new("/gain", vec![OscArgument::Float(f32::NAN)]),
+            OscMessage::new("/gain", vec![OscArgument::Double(1e300)]),
+            OscMessage::new(
+                "/gain",
+                vec![OscArgument::Float(0.5), OscArgument::Double(f64::INFINITY)],
+            ),
+            OscMessage::new("/done", vec![]),
+        ];
+        messages.iter().for_each(|message| {
+            client
+                .send_to(&message.encode(), server.local_addr())
+                .unwrap();
+        });
+
+        // the server thread survived the panicking handler
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(gain.gain().value(), 1.);
+    }
+}
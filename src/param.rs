@@ -1,23 +1,33 @@
 //! AudioParam interface
 use std::any::Any;
 use std::slice::{Iter, IterMut};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, OnceLock};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, OnceLock, Weak};
 
 use arrayvec::ArrayVec;
 
 use crate::context::AudioContextRegistration;
+use crate::music::Quantization;
 use crate::node::{
     AudioNode, ChannelConfig, ChannelConfigOptions, ChannelCountMode, ChannelInterpretation,
+    ParamExpressionNode,
 };
 use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
-use crate::{AtomicF32, RENDER_QUANTUM_SIZE};
+use crate::sync::atomic::AtomicBool;
+use crate::transport::Transport;
+use crate::{db_to_gain, AtomicF32, RENDER_QUANTUM_SIZE};
 
 /// For SetTargetAtTime event, that theoretically cannot end, if the diff between
 /// the current value and the target is below this threshold, the value is set
 /// to target value and the event is considered ended.
 const SNAP_TO_TARGET: f32 = 1e-10;
 
+/// Number of points an easing function is sampled to
+const EASING_CURVE_LENGTH: usize = 256;
+
+/// Level in decibels a decibel ramp to silence fades to, before the value is set to zero
+const SILENCE_DB: f32 = -120.;
+
 // arguments sanity check functions for automation methods
 #[track_caller]
 fn assert_non_negative(value: f64) {
@@ -60,16 +70,22 @@ fn assert_sequence_length(values: &[f32]) {
 }
 
 // 𝑣(𝑡) = 𝑉0 + (𝑉1−𝑉0) * ((𝑡−𝑇0) / (𝑇1−𝑇0))
+// for eased ramps, the phase is first mapped through the sampled easing curve
 #[inline(always)]
 fn compute_linear_ramp_sample(
     start_time: f64,
     duration: f64,
     start_value: f32,
     diff: f32, // end_value - start_value
+    easing: Option<&[f32]>,
     time: f64,
 ) -> f32 {
     let phase = (time - start_time) / duration;
-    diff.mul_add(phase as f32, start_value)
+    let phase = match easing {
+        Some(curve) => compute_set_value_curve_sample(0., 1., curve, phase.max(0.)),
+        None => phase as f32,
+    };
+    diff.mul_add(phase, start_value)
 }
 
 // v(t) = v1 * (v2/v1)^((t-t1) / (t2-t1))
@@ -137,14 +153,80 @@ impl AutomationRate {
 }
 
 /// Options for constructing an [`AudioParam`]
+/// Shape of the transition of [`AudioParam::ramp_to_value_at_time_with_easing`]
+///
+/// The easing function maps the progress of the ramp (from 0 to 1) to the progress of the
+/// value, so it should start at 0 and end at 1.
+#[derive(Clone, Copy, Debug)]
+pub enum Easing {
+    /// Constant rate of change, same as [`AudioParam::linear_ramp_to_value_at_time`]
+    Linear,
+    /// Starts slow and accelerates, `t^3`
+    CubicIn,
+    /// Starts fast and decelerates, `1 - (1 - t)^3`
+    CubicOut,
+    /// Slow at both ends, cubic in the first half and cubic out in the second half
+    CubicInOut,
+    /// Slow at both ends, following half a period of a cosine
+    SCurve,
+    /// User defined easing function, sampled to a value curve
+    Custom(fn(f32) -> f32),
+}
+
+impl Easing {
+    fn apply(&self, t: f32) -> f32 {
+        match self {
+            Self::Linear => t,
+            Self::CubicIn => t * t * t,
+            Self::CubicOut => 1. - (1. - t).powi(3),
+            Self::CubicInOut => {
+                if t < 0.5 {
+                    4. * t * t * t
+                } else {
+                    1. - (2. - 2. * t).powi(3) / 2.
+                }
+            }
+            Self::SCurve => 0.5 - 0.5 * (std::f32::consts::PI * t).cos(),
+            Self::Custom(f) => f(t),
+        }
+    }
+}
+
+/// Metadata of an [`AudioParam`]: the nominal range and the initial value
 #[derive(Clone, Debug)]
 pub struct AudioParamDescriptor {
     pub automation_rate: AutomationRate,
+    /// Initial value, within `[min_value, max_value]`
     pub default_value: f32,
+    /// Lower bound of the nominal range, the computed value is clamped to it
     pub min_value: f32,
+    /// Upper bound of the nominal range, the computed value is clamped to it
     pub max_value: f32,
 }
 
+impl AudioParamDescriptor {
+    /// # Panics
+    ///
+    /// Will panic if `min_value > max_value` or if `default_value` is not within
+    /// `[min_value, max_value]`
+    #[track_caller]
+    fn assert_valid(&self) {
+        assert!(
+            self.min_value <= self.max_value,
+            "NotSupportedError - min value ({:?}) should not be greater than max value ({:?})",
+            self.min_value,
+            self.max_value
+        );
+        assert!(
+            self.default_value >= self.min_value && self.default_value <= self.max_value,
+            "NotSupportedError - default value ({:?}) should be within [{:?}, {:?}]",
+            self.default_value,
+            self.min_value,
+            self.max_value
+        );
+    }
+}
+
 #[derive(PartialEq, Eq, Debug, Copy, Clone)]
 enum AudioParamEventType {
     SetValue,
@@ -165,7 +247,7 @@ pub(crate) struct AudioParamEvent {
     time_constant: Option<f64>, // populated by `SetTargetAtTime` events
     cancel_time: Option<f64>,   // populated by `CancelAndHoldAtTime` events
     duration: Option<f64>,      // populated by `SetValueCurveAtTime` events
-    values: Option<Box<[f32]>>, // populated by `SetValueCurveAtTime` events
+    values: Option<Box<[f32]>>, // populated by `SetValueCurveAtTime` and eased ramp events
 }
 
 // Event queue that contains `AudioParamEvent`s, most of the time, events must be
@@ -335,14 +417,23 @@ impl AudioParam {
         self.raw_parts.automation_rate_constrained = value;
     }
 
+    /// Initial value of the `AudioParam`
     pub fn default_value(&self) -> f32 {
         self.raw_parts.default_value
     }
 
+    /// Lower bound of the nominal range
+    ///
+    /// Scheduled values below it are allowed, but the computed value of the `AudioParam`
+    /// (including its modulation by connected inputs) is clamped to this bound.
     pub fn min_value(&self) -> f32 {
         self.raw_parts.min_value
     }
 
+    /// Upper bound of the nominal range
+    ///
+    /// Scheduled values above it are allowed, but the computed value of the `AudioParam`
+    /// (including its modulation by connected inputs) is clamped to this bound.
     pub fn max_value(&self) -> f32 {
         self.raw_parts.max_value
     }
@@ -381,6 +472,7 @@ impl AudioParam {
         // current_value should always be clamped
         let clamped = value.clamp(self.raw_parts.min_value, self.raw_parts.max_value);
         self.raw_parts.shared_parts.store_current_value(clamped);
+        self.registration.context().record_automation(self, clamped);
 
         // this event is meant to update param intrinsic value before any calculation
         // is done, will behave as SetValueAtTime with `time == block_timestamp`
@@ -442,6 +534,92 @@ impl AudioParam {
         }
     }
 
+    /// Schedules a parameter value change at the next beat or bar of the musical
+    /// grid of the transport, and returns the time of the change
+    ///
+    /// The time is resolved from the current time of the transport, in its clock.
+    /// The node of this param should be part of the transport, the time is in the
+    /// clock of the context otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the transport does not belong to the context of this param
+    pub fn set_value_at_next(
+        &self,
+        value: f32,
+        quantization: Quantization,
+        transport: &Transport,
+    ) -> f64 {
+        let time = self.next_boundary(quantization, transport);
+        self.set_value_at_time(value, time);
+        time
+    }
+
+    /// Schedules a linear continuous change in parameter value from the
+    /// previous scheduled parameter value to the given value, ending at the next
+    /// beat or bar of the musical grid of the transport, and returns the end time
+    ///
+    /// See [`Self::set_value_at_next`] for the resolution of the time.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the transport does not belong to the context of this param
+    pub fn linear_ramp_to_value_at_next(
+        &self,
+        value: f32,
+        quantization: Quantization,
+        transport: &Transport,
+    ) -> f64 {
+        let time = self.next_boundary(quantization, transport);
+        self.linear_ramp_to_value_at_time(value, time);
+        time
+    }
+
+    fn next_boundary(&self, quantization: Quantization, transport: &Transport) -> f64 {
+        if self.registration().context() != transport.context() {
+            panic!(
+                "InvalidAccessError: Attempting to quantize to a transport of a different context"
+            );
+        }
+        transport.next_boundary(transport.current_time(), quantization)
+    }
+
+    /// Schedules a continuous change in parameter value from the previous
+    /// scheduled parameter value to the given value, following the given easing
+    ///
+    /// The easing function is sampled to a curve on the control thread, so
+    /// custom functions never run on the render thread.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `end_time` is negative
+    pub fn ramp_to_value_at_time_with_easing(
+        &self,
+        value: f32,
+        end_time: f64,
+        easing: Easing,
+    ) -> &Self {
+        self.send_event(self.ramp_to_value_at_time_with_easing_raw(value, end_time, easing))
+    }
+
+    fn ramp_to_value_at_time_with_easing_raw(
+        &self,
+        value: f32,
+        end_time: f64,
+        easing: Easing,
+    ) -> AudioParamEvent {
+        let mut event = self.linear_ramp_to_value_at_time_raw(value, end_time);
+
+        if !matches!(easing, Easing::Linear) {
+            let curve: Box<[f32]> = (0..EASING_CURVE_LENGTH)
+                .map(|i| easing.apply(i as f32 / (EASING_CURVE_LENGTH - 1) as f32))
+                .collect();
+            event.values = Some(curve);
+        }
+
+        event
+    }
+
     /// Schedules an exponential continuous change in parameter value from the
     /// previous scheduled parameter value to the given value.
     ///
@@ -454,6 +632,35 @@ impl AudioParam {
         self.send_event(self.exponential_ramp_to_value_at_time_raw(value, end_time))
     }
 
+    /// Schedules a continuous change in parameter value, linear in decibels, from the
+    /// previous scheduled parameter value to the given level in decibels.
+    ///
+    /// This is an exponential ramp to the linear value of the level. Exponential ramps cannot
+    /// reach zero, so a ramp to `f32::NEG_INFINITY` fades to -120 dB and the value is set to zero
+    /// at `end_time`. Likewise, a previous scheduled value of zero is held until `end_time`, use
+    /// [`Self::set_value_at_time`] with a tiny value to start a fade in from silence.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if:
+    /// - `value_db` is NaN or positive infinity
+    /// - `end_time` is negative
+    pub fn ramp_db(&self, value_db: f32, end_time: f64) -> &Self {
+        assert!(
+            !value_db.is_nan() && value_db != f32::INFINITY,
+            "RangeError - Invalid level in decibels: {:?}",
+            value_db
+        );
+
+        let value = db_to_gain(value_db);
+        if value == 0. {
+            self.exponential_ramp_to_value_at_time(db_to_gain(SILENCE_DB), end_time);
+            self.set_value_at_time(0., end_time)
+        } else {
+            self.exponential_ramp_to_value_at_time(value, end_time)
+        }
+    }
+
     fn exponential_ramp_to_value_at_time_raw(&self, value: f32, end_time: f64) -> AudioParamEvent {
         assert_not_zero(value);
         assert_non_negative(end_time);
@@ -602,6 +809,33 @@ impl AudioParam {
         }
     }
 
+    /// Bind the value of this param to a function of the values of other params
+    ///
+    /// The expression is evaluated on the render thread at the start of each render quantum,
+    /// see [`ParamExpressionNode`]. The intrinsic value of this param is set to zero, so its
+    /// computed value is the result of the expression (clamped to the nominal range, and still
+    /// modulated by other connected inputs).
+    ///
+    /// The binding is active as long as the returned node is alive.
+    ///
+    /// This is an extension to the spec.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if any source param does not belong to the same context, or when the context
+    /// runs in strict conformance mode
+    #[must_use = "the binding is removed when the returned node is dropped"]
+    pub fn bind_expression<F>(&self, sources: &[&AudioParam], expression: F) -> ParamExpressionNode
+    where
+        F: FnMut(&[f32]) -> f32 + Send + 'static,
+    {
+        self.context().assert_extension_allowed("bind_expression");
+        let node = ParamExpressionNode::new(self.context(), sources, expression);
+        self.set_value(0.);
+        node.connect(self);
+        node
+    }
+
     // helper function to detach from context (for borrow reasons)
     pub(crate) fn into_raw_parts(self) -> AudioParamRaw {
         let Self {
@@ -623,11 +857,74 @@ impl AudioParam {
     }
 
     fn send_event(&self, event: AudioParamEvent) -> &Self {
-        self.registration().post_message(event);
+        // the lookahead of the ramps is not meaningful, their time is the end of the ramp
+        match event.event_type {
+            AudioParamEventType::SetValue
+            | AudioParamEventType::LinearRampToValueAtTime
+            | AudioParamEventType::ExponentialRampToValueAtTime => {
+                self.registration().post_message(event)
+            }
+            _ => {
+                let when = event.time;
+                self.registration().post_scheduled_message(event, when);
+            }
+        }
         self
     }
 }
 
+/// Set the value of multiple `AudioParam`s at once
+///
+/// The updates are sent to the render thread in a single message, so they are guaranteed to take
+/// effect in the same render quantum. All params must belong to the same context.
+pub(crate) fn set_values_synchronized(params: &[(&AudioParam, f32)]) {
+    let Some((first, _)) = params.first() else {
+        return;
+    };
+
+    let messages = params
+        .iter()
+        .map(|(param, value)| {
+            let event: Box<dyn Any + Send> = Box::new(param.set_value_raw(*value));
+            (param.registration().id(), llq::Node::new(event))
+        })
+        .collect();
+
+    let message = crate::message::ControlMessage::NodeMessages { messages };
+    let _ = first.registration().context().send_control_msg(message);
+}
+
+/// Handle to an `AudioParam` that does not keep it (nor its context) alive
+#[derive(Clone)]
+pub(crate) struct WeakAudioParam {
+    registration: Weak<AudioContextRegistration>,
+    raw_parts: AudioParamRaw,
+}
+
+impl WeakAudioParam {
+    pub fn new(param: &AudioParam) -> Self {
+        Self {
+            registration: Arc::downgrade(&param.registration),
+            raw_parts: param.raw_parts.clone(),
+        }
+    }
+
+    /// Get the param back, as long as it is alive
+    ///
+    /// While the param is alive, its renderer is part of the audio graph.
+    pub fn upgrade(&self) -> Option<AudioParam> {
+        self.registration.upgrade().map(|registration| AudioParam {
+            registration,
+            raw_parts: self.raw_parts.clone(),
+        })
+    }
+
+    /// Check if this handle refers to the given param
+    pub fn is(&self, param: &AudioParam) -> bool {
+        std::ptr::eq(self.registration.as_ptr(), Arc::as_ptr(&param.registration))
+    }
+}
+
 // Atomic fields of `AudioParam` that could be safely shared between threads
 // when wrapped into an `Arc`.
 //
@@ -1104,6 +1401,7 @@ impl AudioParamProcessor {
         let start_value = last_event.value;
         let end_value = event.value;
         let diff = end_value - start_value;
+        let easing = event.values.as_deref();
 
         if infos.is_a_rate {
             let start_index = self.buffer.len();
@@ -1118,8 +1416,14 @@ impl AudioParamProcessor {
                 let mut time = (start_index as f64).mul_add(infos.dt, infos.block_time);
 
                 for _ in start_index..end_index_clipped {
-                    let value =
-                        compute_linear_ramp_sample(start_time, duration, start_value, diff, time);
+                    let value = compute_linear_ramp_sample(
+                        start_time,
+                        duration,
+                        start_value,
+                        diff,
+                        easing,
+                        time,
+                    );
 
                     self.buffer.push(value);
 
@@ -1139,6 +1443,7 @@ impl AudioParamProcessor {
                 duration,
                 start_value,
                 diff,
+                easing,
                 infos.next_block_time,
             );
             self.intrinsic_value = value;
@@ -1148,8 +1453,14 @@ impl AudioParamProcessor {
 
         // Event cancelled during this block
         if event.cancel_time.is_some() {
-            let value =
-                compute_linear_ramp_sample(start_time, duration, start_value, diff, end_time);
+            let value = compute_linear_ramp_sample(
+                start_time,
+                duration,
+                start_value,
+                diff,
+                easing,
+                end_time,
+            );
 
             self.intrinsic_value = value;
 
@@ -1466,9 +1777,16 @@ impl AudioParamProcessor {
             // compute value for `next_block_time` so that `param.value()`
             // stays coherent (see. comment in `AudioParam`)
             // allows to properly fill k-rate within next block too
-            let value =
-                compute_set_value_curve_sample(start_time, duration, values, infos.next_block_time);
-            self.intrinsic_value = value;
+            // (the intrinsic value is left untouched if the curve did not start yet)
+            if infos.next_block_time >= start_time {
+                let value = compute_set_value_curve_sample(
+                    start_time,
+                    duration,
+                    values,
+                    infos.next_block_time,
+                );
+                self.intrinsic_value = value;
+            }
 
             return true;
         }
@@ -1599,6 +1917,8 @@ pub(crate) fn audio_param_pair(
     descriptor: AudioParamDescriptor,
     registration: AudioContextRegistration,
 ) -> (AudioParam, AudioParamProcessor) {
+    descriptor.assert_valid();
+
     let AudioParamDescriptor {
         automation_rate,
         default_value,
@@ -1639,10 +1959,59 @@ mod tests {
     use float_eq::assert_float_eq;
 
     use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::{AudioNode, AudioScheduledSourceNode};
     use crate::render::Alloc;
 
     use super::*;
 
+    #[test]
+    fn test_ramp_db() {
+        let sample_rate = 8000.;
+        let context = OfflineAudioContext::new(1, 3 * 8000, sample_rate);
+        let mut src = context.create_constant_source();
+        let gain = context.create_gain();
+        src.connect(&gain);
+        gain.connect(&context.destination());
+        src.start();
+
+        gain.gain()
+            .set_value_at_time(1., 0.)
+            .ramp_db(-20., 1.)
+            .ramp_db(f32::NEG_INFINITY, 2.);
+
+        let output = context.start_rendering_sync();
+        let data = output.get_channel_data(0);
+
+        // linear in decibels
+        assert_float_eq!(crate::gain_to_db(data[4000]), -10., abs <= 0.01);
+        assert_float_eq!(data[8000], 0.1, abs <= 1e-5);
+        assert_float_eq!(crate::gain_to_db(data[12000]), -70., abs <= 0.01);
+        // silence after the ramp to -inf
+        assert!(data[16000..].iter().all(|&v| v == 0.));
+    }
+
+    #[test]
+    fn test_set_gain_db() {
+        let context = OfflineAudioContext::new(1, 0, 48000.);
+        let gain = context.create_gain();
+
+        gain.set_gain_db(-20.);
+        assert_float_eq!(gain.gain().value(), 0.1, abs <= 1e-6);
+        assert_float_eq!(gain.gain_db(), -20., abs <= 1e-4);
+
+        gain.set_gain_db(f32::NEG_INFINITY);
+        assert_eq!(gain.gain().value(), 0.);
+        assert_eq!(gain.gain_db(), f32::NEG_INFINITY);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_ramp_db_nan() {
+        let context = OfflineAudioContext::new(1, 0, 48000.);
+        let gain = context.create_gain();
+        gain.gain().ramp_db(f32::NAN, 1.);
+    }
+
     #[test]
     #[should_panic]
     fn test_assert_non_negative_fail() {
@@ -1707,6 +2076,34 @@ mod tests {
         assert_float_eq!(param.value(), 0., abs_all <= 0.);
     }
 
+    #[test]
+    #[should_panic]
+    fn test_default_out_of_range() {
+        let context = OfflineAudioContext::new(1, 0, 48000.);
+
+        let opts = AudioParamDescriptor {
+            automation_rate: AutomationRate::A,
+            default_value: 20.,
+            min_value: -10.,
+            max_value: 10.,
+        };
+        let _ = audio_param_pair(opts, context.mock_registration());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_min_greater_than_max() {
+        let context = OfflineAudioContext::new(1, 0, 48000.);
+
+        let opts = AudioParamDescriptor {
+            automation_rate: AutomationRate::A,
+            default_value: 0.,
+            min_value: 10.,
+            max_value: -10.,
+        };
+        let _ = audio_param_pair(opts, context.mock_registration());
+    }
+
     #[test]
     fn test_set_value() {
         {
@@ -1865,6 +2262,76 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_eased_ramp_arate() {
+        let context = OfflineAudioContext::new(1, 0, 48000.);
+
+        let opts = AudioParamDescriptor {
+            automation_rate: AutomationRate::A,
+            default_value: 0.,
+            min_value: -10.,
+            max_value: 10.,
+        };
+        let (param, mut render) = audio_param_pair(opts, context.mock_registration());
+
+        // ease to 8 from t = 0 to t = 4
+        render.handle_incoming_event(param.ramp_to_value_at_time_with_easing_raw(
+            8.,
+            4.,
+            Easing::CubicIn,
+        ));
+        // s-curve to 0 from t = 4 to t = 8
+        render.handle_incoming_event(param.ramp_to_value_at_time_with_easing_raw(
+            0.,
+            8.,
+            Easing::SCurve,
+        ));
+        // custom easing, back to 8 from t = 8 to t = 10
+        render.handle_incoming_event(param.ramp_to_value_at_time_with_easing_raw(
+            8.,
+            10.,
+            Easing::Custom(f32::sqrt),
+        ));
+
+        let vs = render.compute_intrinsic_values(0., 1., 10);
+        let sqrt_half = 0.5_f32.sqrt() * 8.;
+        assert_float_eq!(
+            vs,
+            &[0., 0.125, 1., 3.375, 8., 6.828427, 4., 1.171573, 0., sqrt_half][..],
+            abs_all <= 1e-3
+        );
+
+        let vs = render.compute_intrinsic_values(10., 1., 10);
+        assert_float_eq!(vs, &[8.; 10][..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_eased_ramp_cancel_and_hold() {
+        let context = OfflineAudioContext::new(1, 0, 48000.);
+
+        let opts = AudioParamDescriptor {
+            automation_rate: AutomationRate::A,
+            default_value: 0.,
+            min_value: -10.,
+            max_value: 10.,
+        };
+        let (param, mut render) = audio_param_pair(opts, context.mock_registration());
+
+        render.handle_incoming_event(param.ramp_to_value_at_time_with_easing_raw(
+            8.,
+            4.,
+            Easing::CubicOut,
+        ));
+        render.handle_incoming_event(param.cancel_and_hold_at_time_raw(2.));
+
+        let vs = render.compute_intrinsic_values(0., 1., 10);
+        assert_float_eq!(
+            vs,
+            &[0., 4.625, 7., 7., 7., 7., 7., 7., 7., 7.][..],
+            abs_all <= 1e-3
+        );
+    }
+
     #[test]
     fn test_linear_ramp_arate_end_of_block() {
         let context = OfflineAudioContext::new(1, 0, 48000.);
@@ -3169,6 +3636,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_set_value_curve_starting_after_next_block() {
+        let context = OfflineAudioContext::new(1, 0, 48000.);
+
+        let opts = AudioParamDescriptor {
+            automation_rate: AutomationRate::A,
+            default_value: 0.,
+            min_value: 0.,
+            max_value: 10.,
+        };
+        let (param, mut render) = audio_param_pair(opts, context.mock_registration());
+
+        // the curve is computed in the first block, after the set value event
+        let curve = [0., 0.5, 1., 0.5, 0.];
+        render.handle_incoming_event(param.set_value_at_time_raw(1., 0.));
+        render.handle_incoming_event(param.set_value_curve_at_time_raw(&curve[..], 25., 10.));
+
+        // the value is kept until the curve starts, across blocks
+        let vs = render.compute_intrinsic_values(0., 1., 10);
+        assert_float_eq!(vs, &[1.; 10][..], abs_all <= 0.);
+        let vs = render.compute_intrinsic_values(10., 1., 10);
+        assert_float_eq!(vs, &[1.; 1][..], abs_all <= 0.);
+
+        let vs = render.compute_intrinsic_values(20., 1., 10);
+        assert_float_eq!(
+            vs,
+            &[1., 1., 1., 1., 1., 0., 0.2, 0.4, 0.6, 0.8][..],
+            abs_all <= 1e-7
+        );
+    }
+
     #[test]
     fn test_update_automation_rate_to_k() {
         let context = OfflineAudioContext::new(1, 0, 48000.);
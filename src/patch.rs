@@ -0,0 +1,758 @@
+//! Text description of audio graphs, that can be reloaded while the graph is running
+//!
+//! A patch declares named nodes and their connections, one statement per line:
+//!
+//! ```text
+//! # comments start with a hash sign
+//! lfo = oscillator frequency=2
+//! depth = gain gain=20
+//! osc = oscillator type=sawtooth frequency=110
+//! filter = biquad type=lowpass frequency=800 q=4
+//! amp = gain gain=0.2
+//!
+//! lfo -> depth -> osc.detune
+//! osc -> filter -> amp -> out
+//! ```
+//!
+//! The supported node types are `oscillator`, `constant`, `gain`, `biquad`, `delay` and
+//! `stereo_panner`, their properties are named after the accessors of the corresponding nodes.
+//! The destination of the context is named `out`, and a connection to `node.param` targets the
+//! param of a node.
+//!
+//! When a [`LivePatch`] is reloaded, it is diffed against the running graph: the nodes that are
+//! still declared with the same type keep running (sources keep playing) and only the changed
+//! properties and connections are applied.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::context::{BaseAudioContext, ConcreteBaseAudioContext};
+use crate::node::*;
+use crate::param::AudioParam;
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Name of the destination of the context in a patch
+const DESTINATION: &str = "out";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum NodeKind {
+    Oscillator,
+    ConstantSource,
+    Gain,
+    BiquadFilter,
+    Delay,
+    StereoPanner,
+}
+
+impl NodeKind {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "oscillator" => Some(Self::Oscillator),
+            "constant" => Some(Self::ConstantSource),
+            "gain" => Some(Self::Gain),
+            "biquad" => Some(Self::BiquadFilter),
+            "delay" => Some(Self::Delay),
+            "stereo_panner" => Some(Self::StereoPanner),
+            _ => None,
+        }
+    }
+
+    /// Names of the audio params of the node
+    fn params(self) -> &'static [&'static str] {
+        match self {
+            Self::Oscillator => &["frequency", "detune"],
+            Self::ConstantSource => &["offset"],
+            Self::Gain => &["gain"],
+            Self::BiquadFilter => &["frequency", "detune", "q", "gain"],
+            Self::Delay => &["delay_time"],
+            Self::StereoPanner => &["pan"],
+        }
+    }
+}
+
+/// Type of an oscillator or a biquad filter
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum NodeType {
+    Oscillator(OscillatorType),
+    BiquadFilter(BiquadFilterType),
+}
+
+impl NodeType {
+    fn parse(kind: NodeKind, name: &str) -> Option<Self> {
+        let type_ = match (kind, name) {
+            (NodeKind::Oscillator, "sine") => Self::Oscillator(OscillatorType::Sine),
+            (NodeKind::Oscillator, "square") => Self::Oscillator(OscillatorType::Square),
+            (NodeKind::Oscillator, "sawtooth") => Self::Oscillator(OscillatorType::Sawtooth),
+            (NodeKind::Oscillator, "triangle") => Self::Oscillator(OscillatorType::Triangle),
+            (NodeKind::BiquadFilter, "lowpass") => Self::BiquadFilter(BiquadFilterType::Lowpass),
+            (NodeKind::BiquadFilter, "highpass") => Self::BiquadFilter(BiquadFilterType::Highpass),
+            (NodeKind::BiquadFilter, "bandpass") => Self::BiquadFilter(BiquadFilterType::Bandpass),
+            (NodeKind::BiquadFilter, "notch") => Self::BiquadFilter(BiquadFilterType::Notch),
+            (NodeKind::BiquadFilter, "allpass") => Self::BiquadFilter(BiquadFilterType::Allpass),
+            (NodeKind::BiquadFilter, "peaking") => Self::BiquadFilter(BiquadFilterType::Peaking),
+            (NodeKind::BiquadFilter, "lowshelf") => Self::BiquadFilter(BiquadFilterType::Lowshelf),
+            (NodeKind::BiquadFilter, "highshelf") => {
+                Self::BiquadFilter(BiquadFilterType::Highshelf)
+            }
+            _ => return None,
+        };
+        Some(type_)
+    }
+}
+
+/// Declaration of a node in a patch
+#[derive(Clone, Debug, PartialEq)]
+struct NodeDecl {
+    kind: NodeKind,
+    type_: Option<NodeType>,
+    /// only for delay nodes, cannot be changed without recreating the node
+    max_delay_time: Option<f64>,
+    params: BTreeMap<String, f32>,
+}
+
+impl NodeDecl {
+    /// Check if the running node can be updated to this declaration
+    fn is_compatible(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.max_delay_time == other.max_delay_time
+    }
+}
+
+/// Connection between two nodes of a patch, or between a node and a param
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Connection {
+    from: String,
+    to: String,
+    param: Option<String>,
+}
+
+impl fmt::Display for Connection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.param {
+            Some(param) => write!(f, "{} -> {}.{}", self.from, self.to, param),
+            None => write!(f, "{} -> {}", self.from, self.to),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Patch {
+    nodes: BTreeMap<String, NodeDecl>,
+    connections: BTreeSet<Connection>,
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_node(line: &str) -> std::result::Result<(String, NodeDecl), String> {
+    let (name, definition) = line.split_once('=').unwrap();
+    let name = name.trim();
+    if !is_identifier(name) || name == DESTINATION {
+        return Err(format!("invalid node name {name:?}"));
+    }
+
+    let mut words = definition.split_whitespace();
+    let kind_name = words.next().ok_or("missing node type")?;
+    let kind = NodeKind::parse(kind_name).ok_or(format!("unknown node type {kind_name:?}"))?;
+
+    let mut decl = NodeDecl {
+        kind,
+        type_: None,
+        max_delay_time: None,
+        params: BTreeMap::new(),
+    };
+
+    for property in words {
+        let (key, value) = property
+            .split_once('=')
+            .ok_or(format!("expected key=value, got {property:?}"))?;
+
+        if key == "type" {
+            let type_ = NodeType::parse(kind, value)
+                .ok_or(format!("invalid {kind_name} type {value:?}"))?;
+            decl.type_ = Some(type_);
+            continue;
+        }
+
+        let number: f64 = value
+            .parse()
+            .map_err(|_| format!("invalid value {value:?} for {key:?}"))?;
+        if kind == NodeKind::Delay && key == "max_delay_time" {
+            decl.max_delay_time = Some(number);
+        } else if kind.params().contains(&key) {
+            decl.params.insert(key.to_owned(), number as f32);
+        } else {
+            return Err(format!("unknown property {key:?} for {kind_name}"));
+        }
+    }
+
+    Ok((name.to_owned(), decl))
+}
+
+fn parse(source: &str) -> Result<Patch> {
+    let mut patch = Patch::default();
+    let mut connections = vec![];
+
+    for (index, line) in source.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        let error = |message: String| format!("SyntaxError - line {}: {}", index + 1, message);
+
+        if line.is_empty() {
+            continue;
+        } else if line.contains("->") {
+            let endpoints: Vec<&str> = line.split("->").map(str::trim).collect();
+            for pair in endpoints.windows(2) {
+                let (to, param) = match pair[1].split_once('.') {
+                    Some((to, param)) => (to, Some(param.to_owned())),
+                    None => (pair[1], None),
+                };
+                if pair[0].contains('.') {
+                    return Err(error(format!("cannot connect from a param {:?}", pair[0])).into());
+                }
+                let connection = Connection {
+                    from: pair[0].to_owned(),
+                    to: to.to_owned(),
+                    param,
+                };
+                connections.push((index, connection));
+            }
+        } else if line.contains('=') {
+            let (name, decl) = parse_node(line).map_err(error)?;
+            if patch.nodes.insert(name.clone(), decl).is_some() {
+                return Err(error(format!("node {name:?} is declared twice")).into());
+            }
+        } else {
+            return Err(error(format!("expected a node or a connection, got {line:?}")).into());
+        }
+    }
+
+    // check the connections once all the nodes are declared
+    for (index, connection) in connections {
+        let error = |message: String| format!("SyntaxError - line {}: {}", index + 1, message);
+
+        if !patch.nodes.contains_key(&connection.from) {
+            return Err(error(format!("unknown node {:?}", connection.from)).into());
+        }
+        match (patch.nodes.get(&connection.to), &connection.param) {
+            (None, None) if connection.to == DESTINATION => (),
+            (None, _) => return Err(error(format!("unknown node {:?}", connection.to)).into()),
+            (Some(decl), Some(param)) if !decl.kind.params().contains(&param.as_str()) => {
+                return Err(error(format!("unknown param {param:?} of {:?}", connection.to)).into())
+            }
+            (Some(decl), None)
+                if matches!(decl.kind, NodeKind::Oscillator | NodeKind::ConstantSource) =>
+            {
+                return Err(error(format!("node {:?} has no input", connection.to)).into())
+            }
+            _ => (),
+        }
+        patch.connections.insert(connection);
+    }
+
+    Ok(patch)
+}
+
+/// Running node of a [`LivePatch`]
+enum LiveNode {
+    Oscillator(OscillatorNode),
+    ConstantSource(ConstantSourceNode),
+    Gain(GainNode),
+    BiquadFilter(BiquadFilterNode),
+    Delay(DelayNode),
+    StereoPanner(StereoPannerNode),
+}
+
+impl LiveNode {
+    fn new(context: &ConcreteBaseAudioContext, decl: &NodeDecl) -> Self {
+        let mut node = match decl.kind {
+            NodeKind::Oscillator => {
+                let mut node = OscillatorNode::new(context, OscillatorOptions::default());
+                node.start();
+                Self::Oscillator(node)
+            }
+            NodeKind::ConstantSource => {
+                let mut node = ConstantSourceNode::new(context, ConstantSourceOptions::default());
+                node.start();
+                Self::ConstantSource(node)
+            }
+            NodeKind::Gain => Self::Gain(GainNode::new(context, GainOptions::default())),
+            NodeKind::BiquadFilter => Self::BiquadFilter(BiquadFilterNode::new(
+                context,
+                BiquadFilterOptions::default(),
+            )),
+            NodeKind::Delay => {
+                let mut options = DelayOptions::default();
+                if let Some(max_delay_time) = decl.max_delay_time {
+                    options.max_delay_time = max_delay_time;
+                }
+                Self::Delay(DelayNode::new(context, options))
+            }
+            NodeKind::StereoPanner => Self::StereoPanner(StereoPannerNode::new(
+                context,
+                StereoPannerOptions::default(),
+            )),
+        };
+
+        if let Some(type_) = decl.type_ {
+            node.set_type(type_);
+        }
+        decl.params.iter().for_each(|(name, &value)| {
+            node.param(name).unwrap().set_value(value);
+        });
+
+        node
+    }
+
+    fn node(&self) -> &dyn AudioNode {
+        match self {
+            Self::Oscillator(node) => node,
+            Self::ConstantSource(node) => node,
+            Self::Gain(node) => node,
+            Self::BiquadFilter(node) => node,
+            Self::Delay(node) => node,
+            Self::StereoPanner(node) => node,
+        }
+    }
+
+    fn param(&self, name: &str) -> Option<&AudioParam> {
+        let param = match (self, name) {
+            (Self::Oscillator(node), "frequency") => node.frequency(),
+            (Self::Oscillator(node), "detune") => node.detune(),
+            (Self::ConstantSource(node), "offset") => node.offset(),
+            (Self::Gain(node), "gain") => node.gain(),
+            (Self::BiquadFilter(node), "frequency") => node.frequency(),
+            (Self::BiquadFilter(node), "detune") => node.detune(),
+            (Self::BiquadFilter(node), "q") => node.q(),
+            (Self::BiquadFilter(node), "gain") => node.gain(),
+            (Self::Delay(node), "delay_time") => node.delay_time(),
+            (Self::StereoPanner(node), "pan") => node.pan(),
+            _ => return None,
+        };
+        Some(param)
+    }
+
+    fn set_type(&mut self, type_: NodeType) {
+        match (self, type_) {
+            (Self::Oscillator(node), NodeType::Oscillator(type_)) => node.set_type(type_),
+            (Self::BiquadFilter(node), NodeType::BiquadFilter(type_)) => node.set_type(type_),
+            _ => unreachable!(),
+        }
+    }
+
+    fn stop(&mut self) {
+        match self {
+            Self::Oscillator(node) => node.stop(),
+            Self::ConstantSource(node) => node.stop(),
+            _ => (),
+        }
+    }
+
+    /// Apply the changes between two declarations of a compatible node
+    fn update(&mut self, old: &NodeDecl, new: &NodeDecl) {
+        if old.type_ != new.type_ {
+            let type_ = new.type_.unwrap_or(match new.kind {
+                NodeKind::Oscillator => NodeType::Oscillator(OscillatorType::default()),
+                _ => NodeType::BiquadFilter(BiquadFilterType::default()),
+            });
+            self.set_type(type_);
+        }
+
+        // only touch the changed params, so values set by other means are preserved
+        for name in new.kind.params() {
+            match (old.params.get(*name), new.params.get(*name)) {
+                (old, Some(&value)) if old != Some(&value) => {
+                    self.param(name).unwrap().set_value(value);
+                }
+                (Some(_), None) => {
+                    let param = self.param(name).unwrap();
+                    param.set_value(param.default_value());
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Changes applied to the running graph when loading a patch
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PatchDiff {
+    /// Names of the nodes that were created
+    pub created: Vec<String>,
+    /// Names of the nodes whose properties were updated in place
+    pub updated: Vec<String>,
+    /// Names of the nodes that were recreated, because their type or a construction-only
+    /// property changed
+    pub replaced: Vec<String>,
+    /// Names of the nodes that were removed
+    pub removed: Vec<String>,
+    /// Connections that were added, formatted as `from -> to` or `from -> to.param`
+    pub connected: Vec<String>,
+    /// Connections that were removed
+    pub disconnected: Vec<String>,
+}
+
+impl PatchDiff {
+    /// Check if loading the patch left the graph unchanged
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Audio graph built from a patch description, that can be reloaded at runtime
+///
+/// # Usage
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use web_audio_api::context::AudioContext;
+/// use web_audio_api::patch::LivePatch;
+///
+/// let context = AudioContext::default();
+/// let mut patch = LivePatch::new(&context);
+///
+/// // edit the file while the graph is playing to hear the changes
+/// patch.load_file("patch.txt").unwrap();
+/// loop {
+///     std::thread::sleep(Duration::from_millis(200));
+///     match patch.reload_if_modified("patch.txt") {
+///         Ok(Some(diff)) => println!("{diff:?}"),
+///         Ok(None) => (),
+///         Err(e) => eprintln!("{e}"), // the running graph is left untouched
+///     }
+/// }
+/// ```
+pub struct LivePatch {
+    context: ConcreteBaseAudioContext,
+    nodes: BTreeMap<String, (NodeDecl, LiveNode)>,
+    connections: BTreeSet<Connection>,
+    modified: Option<SystemTime>,
+}
+
+impl fmt::Debug for LivePatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LivePatch")
+            .field("nodes", &self.nodes.keys().collect::<Vec<_>>())
+            .field(
+                "connections",
+                &self
+                    .connections
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl LivePatch {
+    /// Create an empty patch
+    pub fn new<C: BaseAudioContext>(context: &C) -> Self {
+        Self {
+            context: context.base().clone(),
+            nodes: BTreeMap::new(),
+            connections: BTreeSet::new(),
+            modified: None,
+        }
+    }
+
+    /// Get a node of the patch by name
+    pub fn node(&self, name: &str) -> Option<&dyn AudioNode> {
+        self.nodes.get(name).map(|(_, node)| node.node())
+    }
+
+    /// Get a param of a node of the patch, e.g. to automate it
+    pub fn param(&self, node: &str, param: &str) -> Option<&AudioParam> {
+        self.nodes.get(node).and_then(|(_, node)| node.param(param))
+    }
+
+    /// Names of the nodes of the patch
+    pub fn node_names(&self) -> impl Iterator<Item = &str> {
+        self.nodes.keys().map(String::as_str)
+    }
+
+    fn connect(&self, connection: &Connection, connect: bool) {
+        let from = self.nodes[&connection.from].1.node();
+        let destination;
+        let to: &dyn AudioNode = match &connection.param {
+            Some(param) => self.nodes[&connection.to].1.param(param).unwrap(),
+            None if connection.to == DESTINATION => {
+                destination = self.context.destination();
+                &destination
+            }
+            None => self.nodes[&connection.to].1.node(),
+        };
+
+        if connect {
+            from.connect(to);
+        } else {
+            from.disconnect_from(to);
+        }
+    }
+
+    /// Load a patch description, replacing the current graph
+    ///
+    /// The running graph is diffed against the new description, so that unchanged nodes keep
+    /// running.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the description is invalid, in which case the running graph is left
+    /// untouched
+    pub fn load(&mut self, source: &str) -> Result<PatchDiff> {
+        let patch = parse(source)?;
+        let mut diff = PatchDiff::default();
+
+        let replaced: BTreeSet<String> = self
+            .nodes
+            .iter()
+            .filter(|(name, (old, _))| {
+                patch
+                    .nodes
+                    .get(*name)
+                    .map_or(true, |new| !new.is_compatible(old))
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        // drop the connections that are gone, or that involve a node that is recreated
+        let stale: Vec<Connection> = self
+            .connections
+            .iter()
+            .filter(|c| {
+                !patch.connections.contains(c)
+                    || replaced.contains(&c.from)
+                    || replaced.contains(&c.to)
+            })
+            .cloned()
+            .collect();
+        for connection in &stale {
+            self.connect(connection, false);
+            self.connections.remove(connection);
+            if !patch.connections.contains(connection) {
+                diff.disconnected.push(connection.to_string());
+            }
+        }
+
+        for (name, (decl, mut node)) in std::mem::take(&mut self.nodes) {
+            if !replaced.contains(&name) {
+                self.nodes.insert(name, (decl, node));
+                continue;
+            }
+
+            node.stop();
+            node.node().disconnect();
+            if patch.nodes.contains_key(&name) {
+                diff.replaced.push(name);
+            } else {
+                diff.removed.push(name);
+            }
+        }
+
+        for (name, decl) in patch.nodes {
+            match self.nodes.get_mut(&name) {
+                Some((old, node)) => {
+                    if *old != decl {
+                        node.update(old, &decl);
+                        *old = decl;
+                        diff.updated.push(name);
+                    }
+                }
+                None => {
+                    let node = LiveNode::new(&self.context, &decl);
+                    if !replaced.contains(&name) {
+                        diff.created.push(name.clone());
+                    }
+                    self.nodes.insert(name, (decl, node));
+                }
+            }
+        }
+
+        for connection in patch.connections {
+            if !self.connections.contains(&connection) {
+                self.connect(&connection, true);
+                if !stale.contains(&connection) {
+                    diff.connected.push(connection.to_string());
+                }
+                self.connections.insert(connection);
+            }
+        }
+
+        Ok(diff)
+    }
+
+    /// Load a patch description from a file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or if the description is invalid
+    pub fn load_file<P: AsRef<Path>>(&mut self, path: P) -> Result<PatchDiff> {
+        let path = path.as_ref();
+        let modified = std::fs::metadata(path)?.modified().ok();
+        let diff = self.load(&std::fs::read_to_string(path)?)?;
+        self.modified = modified;
+        Ok(diff)
+    }
+
+    /// Load a patch description from a file, if it was modified since it was last loaded
+    ///
+    /// Returns `None` if the file is unchanged. Call this periodically to hot-reload a patch
+    /// while it is being edited.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or if the description is invalid
+    pub fn reload_if_modified<P: AsRef<Path>>(&mut self, path: P) -> Result<Option<PatchDiff>> {
+        let modified = std::fs::metadata(path.as_ref())?.modified().ok();
+        if modified.is_some() && modified == self.modified {
+            return Ok(None);
+        }
+        self.load_file(path).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let patch = parse(
+            "
+            # a simple synth
+            osc = oscillator type=square frequency=220 # inline comment
+            amp = gain gain=0.5
+            osc -> amp -> out
+            osc -> amp.gain
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(patch.nodes.len(), 2);
+        let osc = &patch.nodes["osc"];
+        assert_eq!(osc.kind, NodeKind::Oscillator);
+        assert_eq!(
+            osc.type_,
+            Some(NodeType::Oscillator(OscillatorType::Square))
+        );
+        assert_eq!(osc.params["frequency"], 220.);
+
+        let connections: Vec<String> = patch.connections.iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            connections,
+            vec!["amp -> out", "osc -> amp", "osc -> amp.gain"]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let cases = [
+            ("osc = synth", "line 1: unknown node type"),
+            ("osc = oscillator type=noise", "invalid oscillator type"),
+            ("amp = gain frequency=2", "unknown property"),
+            ("amp = gain gain=loud", "invalid value"),
+            (
+                "amp = gain\namp = gain",
+                "line 2: node \"amp\" is declared twice",
+            ),
+            ("amp = gain\namp -> filter", "unknown node \"filter\""),
+            ("amp = gain\namp -> amp.pan", "unknown param"),
+            ("osc = oscillator\nosc -> osc", "has no input"),
+            ("out = gain", "invalid node name"),
+            ("hello", "expected a node or a connection"),
+        ];
+
+        for (source, message) in cases {
+            let error = parse(source).unwrap_err().to_string();
+            assert!(error.contains(message), "{error:?} for {source:?}");
+        }
+    }
+
+    #[test]
+    fn test_reload() {
+        let context = OfflineAudioContext::new(1, 128, 44100.);
+        let mut patch = LivePatch::new(&context);
+
+        let diff = patch
+            .load(
+                "
+                src = constant offset=1
+                amp = gain gain=0.5
+                src -> amp -> out
+                ",
+            )
+            .unwrap();
+        assert_eq!(diff.created, vec!["amp", "src"]);
+        assert_eq!(diff.connected, vec!["amp -> out", "src -> amp"]);
+
+        let source = "
+            src = constant offset=1
+            amp = gain gain=0.25
+            echo = delay delay_time=0.1
+            src -> amp -> out
+            src -> echo -> out
+            ";
+        let diff = patch.load(source).unwrap();
+        assert_eq!(diff.created, vec!["echo"]);
+        assert_eq!(diff.updated, vec!["amp"]);
+        assert_eq!(diff.connected, vec!["echo -> out", "src -> echo"]);
+        assert!(diff.disconnected.is_empty());
+        assert_float_eq!(patch.param("amp", "gain").unwrap().value(), 0.25, abs <= 0.);
+        // reloading the same patch is a no-op
+        assert!(patch.load(source).unwrap().is_empty());
+
+        // changing a construction-only property recreates the node
+        let diff = patch
+            .load(
+                "
+                src = constant offset=1
+                echo = delay delay_time=0.1 max_delay_time=2
+                src -> echo -> out
+                ",
+            )
+            .unwrap();
+        assert_eq!(diff.removed, vec!["amp"]);
+        assert_eq!(diff.replaced, vec!["echo"]);
+        assert_eq!(diff.disconnected, vec!["amp -> out", "src -> amp"]);
+        assert!(diff.connected.is_empty());
+        assert_eq!(patch.node_names().collect::<Vec<_>>(), vec!["echo", "src"]);
+
+        // invalid patches leave the graph untouched
+        assert!(patch.load("src = constant\nsrc -> nowhere").is_err());
+        assert_eq!(patch.node_names().count(), 2);
+    }
+
+    #[test]
+    fn test_render() {
+        let context = OfflineAudioContext::new(1, 128, 44100.);
+        let mut patch = LivePatch::new(&context);
+        patch
+            .load(
+                "
+                src = constant offset=0.5
+                mod = constant offset=0.25
+                amp = gain gain=1
+                src -> amp -> out
+                mod -> amp.gain
+                ",
+            )
+            .unwrap();
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(
+            output.get_channel_data(0),
+            &[0.625; 128][..],
+            abs_all <= 1e-6
+        );
+    }
+}
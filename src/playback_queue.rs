@@ -0,0 +1,286 @@
+//! Gapless playback of successive audio buffers, as needed by music players
+//!
+//! A [`PlaybackQueue`] schedules each buffer to start on the sample frame following the end of
+//! the previous one, as soon as it is enqueued, so the render thread plays the buffers back to
+//! back without depending on the timing of the control thread. Decode the files with
+//! [`decode_gapless`] to remove the silence added by the encoders at both ends of the tracks.
+
+use std::collections::VecDeque;
+use std::error::Error;
+
+use crate::buffer::AudioBuffer;
+use crate::context::{BaseAudioContext, ConcreteBaseAudioContext};
+use crate::decoding::MediaDecoder;
+use crate::node::{
+    AudioBufferSourceNode, AudioBufferSourceOptions, AudioNode, AudioScheduledSourceNode, GainNode,
+};
+
+/// Decode an audio file without the encoder delay and padding
+///
+/// Lossy encoders add silence at the start (delay) and at the end (padding) of their
+/// output. It is removed according to the LAME/Xing header of MP3 files, and the `iTunSMPB`
+/// tag of files encoded by iTunes, so successive tracks of an album play without gaps.
+/// Otherwise this is [`BaseAudioContext::decode_audio_data_sync`].
+///
+/// This is an extension to the spec.
+///
+/// # Errors
+///
+/// This method returns an Error in various cases (IO, mime sniffing, decoding), or a
+/// [`QuotaExceededError`](crate::QuotaExceededError) when the decoded buffer exceeds the buffer
+/// memory quota.
+pub fn decode_gapless<C: BaseAudioContext, R: std::io::Read + Send + Sync + 'static>(
+    context: &C,
+    input: R,
+) -> Result<AudioBuffer, Box<dyn Error + Send + Sync>> {
+    let mut buffer = MediaDecoder::decode_gapless_to_buffer(input, context.sample_rate())?;
+
+    // resample to desired rate (no-op if already matching)
+    buffer.resample(context.sample_rate());
+
+    context
+        .base()
+        .check_quota(|memory| memory.check_buffer_memory(None, &buffer.allocations()))?;
+
+    Ok(buffer)
+}
+
+/// Buffer of a [`PlaybackQueue`], scheduled on the render thread
+struct ScheduledItem {
+    source: AudioBufferSourceNode,
+    /// first and last (excluded) sample frames of the playback, in context frames
+    start_frame: u64,
+    end_frame: u64,
+}
+
+/// Plays audio buffers one after the other, without gaps
+///
+/// Each enqueued buffer is scheduled right away to start at the end of the previous one, so
+/// the next track is always preloaded on the render thread. A buffer enqueued after the queue
+/// has drained starts immediately. The playback is sample accurate for buffers at the sample
+/// rate of the context, e.g. decoded with [`decode_gapless`].
+///
+/// All buffers are played into the output bus, which is connected to the destination of the
+/// context.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use std::fs::File;
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::playback_queue::{decode_gapless, PlaybackQueue};
+///
+/// let context = AudioContext::default();
+/// let mut queue = PlaybackQueue::new(&context);
+///
+/// for path in ["01.mp3", "02.mp3", "03.mp3"] {
+///     let track = decode_gapless(&context, File::open(path).unwrap()).unwrap();
+///     queue.enqueue(track);
+///     // decode the next track while this one plays
+///     while queue.remaining_time() > 10. {
+///         std::thread::sleep(std::time::Duration::from_millis(100));
+///     }
+/// }
+/// ```
+pub struct PlaybackQueue {
+    context: ConcreteBaseAudioContext,
+    output: GainNode,
+    items: VecDeque<ScheduledItem>,
+}
+
+impl std::fmt::Debug for PlaybackQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PlaybackQueue")
+            .field("len", &self.items.len())
+            .field("remaining_time", &self.remaining_time())
+            .finish_non_exhaustive()
+    }
+}
+
+impl PlaybackQueue {
+    /// Create a new queue, with its output bus connected to the destination of the context
+    pub fn new<C: BaseAudioContext>(context: &C) -> Self {
+        let output = context.create_gain();
+        output.connect(&context.destination());
+
+        Self {
+            context: context.base().clone(),
+            output,
+            items: VecDeque::new(),
+        }
+    }
+
+    /// The output bus, all buffers are played into this node
+    pub fn output(&self) -> &GainNode {
+        &self.output
+    }
+
+    /// Current sample frame of the context
+    fn current_frame(&self) -> u64 {
+        (self.context.current_time() * self.context.sample_rate() as f64).round() as u64
+    }
+
+    /// Forget the items whose playback has ended
+    fn remove_ended(&mut self) {
+        let current_frame = self.current_frame();
+        while matches!(self.items.front(), Some(item) if item.end_frame <= current_frame) {
+            self.items.pop_front();
+        }
+    }
+
+    /// Schedule a buffer to play after the previous ones
+    pub fn enqueue(&mut self, buffer: AudioBuffer) {
+        self.remove_ended();
+
+        let sample_rate = self.context.sample_rate() as f64;
+        let start_frame = self
+            .items
+            .back()
+            .map_or(0, |item| item.end_frame)
+            .max(self.current_frame());
+        let length = (buffer.duration() * sample_rate).round() as u64;
+        self.schedule(buffer, start_frame, start_frame + length);
+    }
+
+    fn schedule(&mut self, buffer: AudioBuffer, start_frame: u64, end_frame: u64) {
+        let options = AudioBufferSourceOptions {
+            buffer: Some(buffer),
+            ..AudioBufferSourceOptions::default()
+        };
+        let when = start_frame as f64 / self.context.sample_rate() as f64;
+        let source = self.context.batch_node_messages(|| {
+            let mut source = AudioBufferSourceNode::new(&self.context, options);
+            source.connect(&self.output);
+            source.start_at(when);
+            source
+        });
+
+        self.items.push_back(ScheduledItem {
+            source,
+            start_frame,
+            end_frame,
+        });
+    }
+
+    /// Number of buffers playing or waiting to be played
+    pub fn len(&mut self) -> usize {
+        self.remove_ended();
+        self.items.len()
+    }
+
+    /// Check if all the buffers have been played
+    pub fn is_empty(&mut self) -> bool {
+        self.len() == 0
+    }
+
+    /// Time in seconds until the queue has played all its buffers
+    pub fn remaining_time(&self) -> f64 {
+        let current_frame = self.current_frame();
+        let end_frame = self.items.back().map_or(0, |item| item.end_frame);
+        end_frame.saturating_sub(current_frame) as f64 / self.context.sample_rate() as f64
+    }
+
+    /// Stop the buffer currently playing, and play the next ones right away
+    pub fn skip(&mut self) {
+        self.remove_ended();
+        let current_frame = self.current_frame();
+        let Some(mut current) = self.items.pop_front() else {
+            return;
+        };
+
+        let context = self.context.clone();
+        context.batch_node_messages(|| {
+            let now = current_frame as f64 / self.context.sample_rate() as f64;
+            current.source.stop_at(now);
+
+            // move the next items earlier, the sources cannot be started again
+            let next: Vec<_> = self.items.drain(..).collect();
+            let mut start_frame = current_frame.max(current.start_frame);
+            for mut item in next {
+                item.source.stop_at(now);
+                let length = item.end_frame - item.start_frame;
+                if let Some(buffer) = item.source.buffer().cloned() {
+                    self.schedule(buffer, start_frame, start_frame + length);
+                    start_frame += length;
+                }
+            }
+        });
+    }
+
+    /// Stop the playback and remove all the buffers
+    pub fn clear(&mut self) {
+        let now = self.context.current_time();
+        let context = self.context.clone();
+        context.batch_node_messages(|| {
+            self.items
+                .drain(..)
+                .for_each(|mut item| item.source.stop_at(now));
+        });
+    }
+}
+
+impl Drop for PlaybackQueue {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::OfflineAudioContext;
+
+    use float_eq::assert_float_eq;
+
+    fn ramp(length: usize, offset: f32) -> AudioBuffer {
+        let data = (0..length).map(|i| offset + i as f32).collect();
+        AudioBuffer::from(vec![data], 44_100.)
+    }
+
+    #[test]
+    fn test_gapless() {
+        let context = OfflineAudioContext::new(1, 3000, 44_100.);
+        let mut queue = PlaybackQueue::new(&context);
+        queue.enqueue(ramp(1001, 0.));
+        queue.enqueue(ramp(777, 1001.));
+        queue.enqueue(ramp(999, 1778.));
+        assert_eq!(queue.len(), 3);
+        assert_float_eq!(queue.remaining_time(), 2777. / 44_100., abs <= 1e-12);
+
+        let output = context.start_rendering_sync();
+        let mut expected: Vec<f32> = (0..2777).map(|i| i as f32).collect();
+        expected.resize(3000, 0.);
+        assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_skip() {
+        let context = OfflineAudioContext::new(1, 512, 44_100.);
+        let mut queue = PlaybackQueue::new(&context);
+        queue.enqueue(ramp(1000, 0.));
+        queue.enqueue(ramp(100, 1000.));
+        queue.skip();
+        assert_eq!(queue.len(), 1);
+        assert_float_eq!(queue.remaining_time(), 100. / 44_100., abs <= 1e-12);
+
+        let output = context.start_rendering_sync();
+        let mut expected: Vec<f32> = (1000..1100).map(|i| i as f32).collect();
+        expected.resize(512, 0.);
+        assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_clear() {
+        let context = OfflineAudioContext::new(1, 128, 44_100.);
+        let mut queue = PlaybackQueue::new(&context);
+        queue.enqueue(ramp(100, 1.));
+        queue.clear();
+        assert!(queue.is_empty());
+        assert_float_eq!(queue.remaining_time(), 0., abs <= 0.);
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(output.get_channel_data(0), &[0.; 128][..], abs_all <= 0.);
+    }
+}
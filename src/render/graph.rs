@@ -1,14 +1,25 @@
 //! The audio graph topology and render algorithm
 use std::any::Any;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::BinaryHeap;
 use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Instant;
 
+use crate::capacity::{SchedulingCounters, MAX_REPORTED_NODES};
 use crate::context::AudioNodeId;
+use crate::lookback::LookbackBuffer;
+use crate::message::ScheduledMessage;
+use arrayvec::ArrayVec;
 use smallvec::{smallvec, SmallVec};
 
 use super::{Alloc, AudioParamValues, AudioProcessor, AudioRenderQuantum, NodeCollection};
 use crate::node::ChannelConfig;
 use crate::render::RenderScope;
+use crate::watchdog::{WatchdogConfig, WatchdogEvent, WatchdogReason};
+use crate::{AtomicF64, Event, RENDER_QUANTUM_SIZE};
 
 /// Connection between two audio nodes
 struct OutgoingEdge {
@@ -18,6 +29,135 @@ struct OutgoingEdge {
     other_id: AudioNodeId,
     /// index of the other Nodes input port
     other_index: usize,
+    /// gain ramp applied while the connection is faded in or out
+    fade: Option<EdgeFade>,
+}
+
+impl OutgoingEdge {
+    fn is_fading_out(&self) -> bool {
+        self.fade.is_some_and(|fade| fade.step < 0.)
+    }
+}
+
+/// Gain ramp of a connection, so structural changes of the graph do not produce clicks
+#[derive(Clone, Copy, Debug)]
+struct EdgeFade {
+    /// gain at the start of the next render quantum
+    gain: f32,
+    /// gain increment per frame, negative when fading out
+    step: f32,
+}
+
+impl EdgeFade {
+    fn fade_in(gain: f32, frames: usize) -> Self {
+        Self {
+            gain,
+            step: 1. / frames as f32,
+        }
+    }
+
+    fn fade_out(gain: f32, frames: usize) -> Self {
+        Self {
+            gain,
+            step: -1. / frames as f32,
+        }
+    }
+
+    /// Apply the ramp to the signal and advance it by a render quantum
+    fn apply(&mut self, signal: &mut AudioRenderQuantum) {
+        let Self { gain, step } = *self;
+        if !signal.is_silent() {
+            signal.channels_mut().iter_mut().for_each(|channel| {
+                channel
+                    .iter_mut()
+                    .enumerate()
+                    .for_each(|(i, s)| *s *= (gain + step * i as f32).clamp(0., 1.));
+            });
+        }
+        self.gain = (gain + step * RENDER_QUANTUM_SIZE as f32).clamp(0., 1.);
+    }
+
+    fn is_complete(&self) -> bool {
+        if self.step < 0. {
+            self.gain == 0.
+        } else {
+            self.gain == 1.
+        }
+    }
+}
+
+/// Change of the topology of the graph, applied at a scheduled time
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum GraphMutation {
+    Connect {
+        from: AudioNodeId,
+        to: AudioNodeId,
+        output: usize,
+        input: usize,
+    },
+    Disconnect {
+        from: AudioNodeId,
+        to: AudioNodeId,
+    },
+    DisconnectAll {
+        from: AudioNodeId,
+    },
+}
+
+impl GraphMutation {
+    fn nodes(&self) -> [Option<AudioNodeId>; 2] {
+        match *self {
+            Self::Connect { from, to, .. } | Self::Disconnect { from, to } => {
+                [Some(from), Some(to)]
+            }
+            Self::DisconnectAll { from } => [Some(from), None],
+        }
+    }
+}
+
+/// Maximum number of mutations of the topology waiting for their scheduled time
+///
+/// The queue is preallocated with this capacity, the control thread refuses to schedule more.
+pub(crate) const MAX_SCHEDULED_MUTATIONS: usize = 1024;
+
+/// Mutation waiting for its scheduled time, mutations scheduled at the same time are applied in
+/// the order of arrival
+struct ScheduledMutation {
+    when: f64,
+    seq: u64,
+    mutation: GraphMutation,
+}
+
+impl PartialEq for ScheduledMutation {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == CmpOrdering::Equal
+    }
+}
+
+impl Eq for ScheduledMutation {}
+
+impl PartialOrd for ScheduledMutation {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScheduledMutation {
+    // reversed, so that the max-heap pops the earliest mutation first
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        other
+            .when
+            .total_cmp(&self.when)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+/// Clock of a group of nodes that stops advancing while the group is paused
+struct TransportState {
+    paused: bool,
+    current_frame: u64,
+    /// shared with the control thread
+    current_time: Arc<AtomicF64>,
 }
 
 /// Renderer Node in the Audio Graph
@@ -40,6 +180,21 @@ pub struct Node {
     has_inputs_connected: bool,
     /// Indicates if the node can act as a cycle breaker (only DelayNode for now)
     cycle_breaker: bool,
+    /// Accumulated render time in seconds, only measured when profiling is enabled
+    render_time: f64,
+    /// Indicates if the watchdog has muted the output of this node
+    muted: bool,
+    /// Number of consecutive clipping frames, only counted at the watchdog watch points
+    clipping_frames: u64,
+    /// Recording of the first output, when enabled
+    lookback: Option<LookbackBuffer>,
+    /// Number of scheduled mutations involving this node, it is kept alive until they are applied
+    pending_mutations: usize,
+    /// Transport providing the time of this node, instead of the context
+    transport: Option<usize>,
+    /// Indicates if an allocation of this node has been reported
+    #[cfg(feature = "alloc-guard")]
+    allocated: bool,
 }
 
 impl Node {
@@ -57,6 +212,11 @@ impl Node {
             return false;
         }
 
+        // A scheduled connection may still revive this node
+        if self.pending_mutations > 0 {
+            return false;
+        }
+
         // Drop, when the node does not have any inputs connected,
         // and if the processor reports it won't yield output.
         if !self.has_inputs_connected && !tail_time {
@@ -68,6 +228,43 @@ impl Node {
         false
     }
 
+    /// Check if any output contains NaN or infinite sample values
+    fn has_non_finite_output(&self) -> bool {
+        self.outputs
+            .iter()
+            .flat_map(AudioRenderQuantum::channels)
+            .any(|channel| channel.iter().any(|v| !v.is_finite()))
+    }
+
+    /// Check if any output exceeds the clipping threshold in the current render quantum
+    fn has_clipping_output(&self, threshold: f32) -> bool {
+        self.outputs
+            .iter()
+            .flat_map(AudioRenderQuantum::channels)
+            .any(|channel| channel.iter().any(|v| v.abs() > threshold))
+    }
+
+    /// Inspect the current output at a watch point of the watchdog
+    fn inspect(&mut self, watchdog: &WatchdogConfig) -> Option<WatchdogReason> {
+        if self.has_non_finite_output() {
+            self.clipping_frames = 0;
+            return Some(WatchdogReason::NonFinite);
+        }
+
+        if !self.has_clipping_output(watchdog.clipping_threshold) {
+            self.clipping_frames = 0;
+            return None;
+        }
+
+        self.clipping_frames += RENDER_QUANTUM_SIZE as u64;
+        if self.clipping_frames > watchdog.clipping_frames {
+            self.clipping_frames = 0;
+            return Some(WatchdogReason::Clipping);
+        }
+
+        None
+    }
+
     /// Get the current buffer for AudioParam values
     pub fn get_buffer(&self) -> &AudioRenderQuantum {
         self.outputs.get(0).unwrap()
@@ -92,6 +289,20 @@ pub(crate) struct Graph {
     in_cycle: Vec<AudioNodeId>,
     /// Topological sorting helper
     cycle_breakers: Vec<AudioNodeId>,
+    /// Measure the render time of each node
+    profiling: bool,
+    /// Watchdog settings, `None` when disabled
+    watchdog: Option<WatchdogConfig>,
+    /// Mutations of the topology waiting for their scheduled time
+    scheduled: BinaryHeap<ScheduledMutation>,
+    /// Order of arrival of the next scheduled mutation
+    scheduled_seq: u64,
+    /// Clocks of the groups of nodes that can be paused, indexed by transport id
+    transports: Vec<Option<TransportState>>,
+    /// Lookahead of the scheduled node messages, and number of the pending scheduled mutations
+    scheduling_counters: Option<Arc<SchedulingCounters>>,
+    /// Duration in frames of the fade applied on connect and disconnect, 0 when disabled
+    edge_fade_frames: usize,
 }
 
 impl Graph {
@@ -105,6 +316,13 @@ impl Graph {
             marked_temp: vec![],
             in_cycle: vec![],
             cycle_breakers: vec![],
+            profiling: false,
+            watchdog: None,
+            scheduled: BinaryHeap::with_capacity(MAX_SCHEDULED_MUTATIONS),
+            scheduled_seq: 0,
+            transports: vec![],
+            scheduling_counters: None,
+            edge_fade_frames: 0,
         }
     }
 
@@ -142,44 +360,206 @@ impl Graph {
                 free_when_finished: false,
                 has_inputs_connected: false,
                 cycle_breaker: false,
+                render_time: 0.,
+                muted: false,
+                clipping_frames: 0,
+                lookback: None,
+                pending_mutations: 0,
+                transport: None,
+                #[cfg(feature = "alloc-guard")]
+                allocated: false,
             }),
         );
     }
 
     pub fn add_edge(&mut self, source: (AudioNodeId, usize), dest: (AudioNodeId, usize)) {
-        self.nodes[source.0]
-            .get_mut()
-            .outgoing_edges
-            .push(OutgoingEdge {
-                self_index: source.1,
-                other_id: dest.0,
-                other_index: dest.1,
-            });
+        let frames = self.edge_fade_frames;
+        let outgoing_edges = &mut self.nodes[source.0].get_mut().outgoing_edges;
+
+        // a connection that is still fading out is faded back in from its current gain
+        let fading_out = outgoing_edges.iter_mut().find(|edge| {
+            edge.self_index == source.1
+                && edge.other_id == dest.0
+                && edge.other_index == dest.1
+                && edge.is_fading_out()
+        });
+        if let Some(edge) = fading_out {
+            let gain = edge.fade.unwrap().gain;
+            edge.fade = (frames > 0).then(|| EdgeFade::fade_in(gain, frames));
+            return;
+        }
+
+        // audio params are connected to the 'hidden' usize::MAX input, they are not faded
+        let fade = (frames > 0 && dest.1 != usize::MAX).then(|| EdgeFade::fade_in(0., frames));
+        outgoing_edges.push(OutgoingEdge {
+            self_index: source.1,
+            other_id: dest.0,
+            other_index: dest.1,
+            fade,
+        });
 
         self.ordered.clear(); // void current ordering
     }
 
     pub fn remove_edge(&mut self, source: AudioNodeId, dest: AudioNodeId) {
+        let frames = self.edge_fade_frames;
         self.nodes[source]
             .get_mut()
             .outgoing_edges
-            .retain(|edge| edge.other_id != dest);
+            .retain(|edge| edge.other_id != dest || Self::fade_out_edge(edge, frames));
 
         self.ordered.clear(); // void current ordering
     }
 
     pub fn remove_edges_from(&mut self, source: AudioNodeId) {
-        self.nodes[source].get_mut().outgoing_edges.clear();
+        let frames = self.edge_fade_frames;
+        self.nodes[source]
+            .get_mut()
+            .outgoing_edges
+            .retain(|edge| Self::fade_out_edge(edge, frames));
 
         self.nodes.values_mut().for_each(|node| {
             node.get_mut()
                 .outgoing_edges
-                .retain(|edge| edge.other_id != source);
+                .retain(|edge| edge.other_id != source || Self::fade_out_edge(edge, frames));
         });
 
         self.ordered.clear(); // void current ordering
     }
 
+    /// Start fading out a removed connection, returns false if it must be removed immediately
+    ///
+    /// The edge is removed by the render loop when the fade out is complete.
+    fn fade_out_edge(edge: &mut OutgoingEdge, frames: usize) -> bool {
+        if frames == 0 || edge.other_index == usize::MAX {
+            return false;
+        }
+        if !edge.is_fading_out() {
+            let gain = edge.fade.map_or(1., |fade| fade.gain);
+            edge.fade = Some(EdgeFade::fade_out(gain, frames));
+        }
+        true
+    }
+
+    /// Fade the connections in and out over the given number of frames when the topology changes
+    pub fn set_connection_fade(&mut self, frames: usize) {
+        self.edge_fade_frames = frames;
+    }
+
+    /// Apply a mutation of the topology once the render quantum containing `when` is reached
+    ///
+    /// The mutation is dropped if the queue is full, which the control thread prevents.
+    pub fn schedule_mutation(&mut self, when: f64, mutation: GraphMutation) {
+        if self.scheduled.len() >= MAX_SCHEDULED_MUTATIONS {
+            self.release_mutation();
+            return;
+        }
+
+        mutation.nodes().into_iter().flatten().for_each(|id| {
+            if let Some(node) = self.nodes.get_mut(id) {
+                node.get_mut().pending_mutations += 1;
+            }
+        });
+
+        self.scheduled.push(ScheduledMutation {
+            when,
+            seq: self.scheduled_seq,
+            mutation,
+        });
+        self.scheduled_seq += 1;
+    }
+
+    /// Give the room of an applied or dropped mutation back to the control thread
+    fn release_mutation(&self) {
+        if let Some(counters) = &self.scheduling_counters {
+            counters.release_mutation();
+        }
+    }
+
+    /// Apply the scheduled mutations of the topology with a time before `until`
+    pub fn apply_scheduled_mutations(&mut self, until: f64) {
+        while self.scheduled.peek().is_some_and(|next| next.when < until) {
+            let mutation = self.scheduled.pop().unwrap().mutation;
+            self.release_mutation();
+
+            // the nodes are kept alive, unless their processor has panicked
+            let mut exists = true;
+            mutation
+                .nodes()
+                .into_iter()
+                .flatten()
+                .for_each(|id| match self.nodes.get_mut(id) {
+                    Some(node) => node.get_mut().pending_mutations -= 1,
+                    None => exists = false,
+                });
+            if !exists {
+                continue;
+            }
+
+            match mutation {
+                GraphMutation::Connect {
+                    from,
+                    to,
+                    output,
+                    input,
+                } => self.add_edge((from, output), (to, input)),
+                GraphMutation::Disconnect { from, to } => self.remove_edge(from, to),
+                GraphMutation::DisconnectAll { from } => self.remove_edges_from(from),
+            }
+        }
+    }
+
+    /// Add a transport, its time starts at the given frame
+    pub fn add_transport(&mut self, id: usize, current_frame: u64, current_time: Arc<AtomicF64>) {
+        if self.transports.len() <= id {
+            self.transports.resize_with(id + 1, || None);
+        }
+        self.transports[id] = Some(TransportState {
+            paused: false,
+            current_frame,
+            current_time,
+        });
+    }
+
+    pub fn set_transport_paused(&mut self, id: usize, paused: bool) {
+        if let Some(Some(transport)) = self.transports.get_mut(id) {
+            transport.paused = paused;
+        }
+    }
+
+    /// Let a node and its AudioParams follow the time of a transport
+    ///
+    /// With `upstream`, all nodes feeding into the node, directly or not, are added too. The
+    /// destination and the AudioListener are never part of a transport.
+    pub fn add_to_transport(&mut self, id: usize, index: AudioNodeId, upstream: bool) {
+        if index.0 < 2 {
+            return;
+        }
+
+        // borrow the topological sorting helper as work list, it is cleared before sorting
+        self.marked.clear();
+        if let Some(node) = self.nodes.get_mut(index) {
+            node.get_mut().transport = Some(id);
+            self.marked.push(index);
+        }
+
+        while let Some(target) = self.marked.pop() {
+            let feeds_target = |node: &Node| {
+                node.outgoing_edges.iter().any(|edge| {
+                    edge.other_id == target && (upstream || edge.other_index == usize::MAX)
+                })
+            };
+
+            for (source, node) in self.nodes.iter_mut() {
+                let node = node.get_mut();
+                if source.0 >= 2 && node.transport != Some(id) && feeds_target(node) {
+                    node.transport = Some(id);
+                    self.marked.push(source);
+                }
+            }
+        }
+    }
+
     pub fn mark_free_when_finished(&mut self, index: AudioNodeId) {
         // Issue #92, a race condition can occur for AudioParams. They may have already been
         // removed from the audio graph if the node they feed into was dropped.
@@ -193,10 +573,46 @@ impl Graph {
         self.nodes[index].get_mut().cycle_breaker = true;
     }
 
+    /// Append the rendered values of the AudioParam node to `values`
+    ///
+    /// When the node is no longer part of the graph, the last recorded value is repeated.
+    pub fn append_param_values(&self, index: AudioNodeId, values: &mut Vec<f32>) {
+        match self.nodes.get(index) {
+            Some(node) => {
+                let node = node.borrow();
+                let buffer = node.get_buffer();
+                let channel = buffer.channel_data(0);
+                if buffer.single_valued() {
+                    values.extend(std::iter::repeat(channel[0]).take(channel.len()));
+                } else {
+                    values.extend_from_slice(&channel[..]);
+                }
+            }
+            None => {
+                let last = values.last().copied().unwrap_or_default();
+                values.extend(std::iter::repeat(last).take(crate::RENDER_QUANTUM_SIZE));
+            }
+        }
+    }
+
     pub fn route_message(&mut self, index: AudioNodeId, msg: &mut dyn Any) {
         self.nodes[index].get_mut().processor.onmessage(msg);
     }
 
+    /// Route a message from the control thread, unwrapping the [`ScheduledMessage`]s and
+    /// recording their lookahead relative to `current_time`
+    pub fn route_node_message(&mut self, index: AudioNodeId, msg: &mut dyn Any, current_time: f64) {
+        match msg.downcast_mut::<ScheduledMessage>() {
+            Some(scheduled) => {
+                if let Some(counters) = &self.scheduling_counters {
+                    counters.record(scheduled.when - current_time);
+                }
+                self.route_message(index, scheduled.msg.as_mut());
+            }
+            None => self.route_message(index, msg),
+        }
+    }
+
     /// Helper function for `order_nodes` - traverse node and outgoing edges
     ///
     /// The return value indicates `cycle_breaker_applied`:
@@ -352,6 +768,73 @@ impl Graph {
         self.cycle_breakers = cycle_breakers;
     }
 
+    /// Enable or disable the measurement of the render time of each node
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profiling = enabled;
+        if !enabled {
+            self.nodes
+                .values_mut()
+                .for_each(|node| node.get_mut().render_time = 0.);
+        }
+    }
+
+    /// Collect the lookahead of the scheduled node messages in the given counters
+    pub fn set_scheduling_counters(&mut self, counters: Arc<SchedulingCounters>) {
+        self.scheduling_counters = Some(counters);
+    }
+
+    /// Enable or disable the watchdog, this unmutes all nodes muted by the watchdog
+    pub fn set_watchdog(&mut self, watchdog: Option<WatchdogConfig>) {
+        self.watchdog = watchdog;
+        self.nodes.values_mut().for_each(|node| {
+            let node = node.get_mut();
+            node.muted = false;
+            node.clipping_frames = 0;
+        });
+    }
+
+    /// Start or stop recording the output of a node, returns the previous recording
+    pub fn set_lookback(
+        &mut self,
+        index: AudioNodeId,
+        lookback: Option<LookbackBuffer>,
+    ) -> Option<LookbackBuffer> {
+        match self.nodes.get_mut(index) {
+            Some(node) => std::mem::replace(&mut node.get_mut().lookback, lookback),
+            // the node has already been dropped
+            None => lookback,
+        }
+    }
+
+    /// Slowest nodes since the last call, by decreasing render time, and reset the measurements
+    pub fn take_slowest_nodes(&mut self) -> ArrayVec<(AudioNodeId, f64), MAX_REPORTED_NODES> {
+        let mut slowest = ArrayVec::new();
+        if !self.profiling {
+            return slowest;
+        }
+
+        for &id in &self.ordered {
+            let node = match self.nodes.get_mut(id) {
+                Some(node) => node.get_mut(),
+                None => continue,
+            };
+            let render_time = std::mem::take(&mut node.render_time);
+
+            let position = slowest
+                .iter()
+                .position(|&(_, time)| time < render_time)
+                .unwrap_or(slowest.len());
+            if position < MAX_REPORTED_NODES {
+                if slowest.is_full() {
+                    slowest.pop();
+                }
+                slowest.insert(position, (id, render_time));
+            }
+        }
+
+        slowest
+    }
+
     /// Render a single audio quantum by traversing the node list
     pub fn render(&mut self, scope: &RenderScope) -> &AudioRenderQuantum {
         // if the audio graph was changed, determine the new ordering
@@ -361,9 +844,13 @@ impl Graph {
 
         // keep track of end-of-lifecyle nodes
         let mut nodes_dropped = false;
+        // keep track of the connections that have been faded out
+        let mut edges_removed = false;
 
         // for borrow-checker reasons, move mutable borrow of nodes out of self
         let nodes = &mut self.nodes;
+        let profiling = self.profiling;
+        let transports = &self.transports;
 
         // process every node, in topological sorted order
         self.ordered.iter().for_each(|index| {
@@ -378,16 +865,51 @@ impl Graph {
                 .iter_mut()
                 .for_each(|i| i.mix(count, interpretation));
 
+            // the nodes of a transport follow its clock, and are frozen while it is paused
+            let transport = node.transport.and_then(|id| transports.get(id)?.as_ref());
+            let transport_scope;
+            let scope = match transport {
+                Some(transport) => {
+                    transport_scope = RenderScope {
+                        current_frame: transport.current_frame,
+                        current_time: transport.current_frame as f64 / scope.sample_rate as f64,
+                        sample_rate: scope.sample_rate,
+                        node_id: Cell::new(*index),
+                        event_sender: scope.event_sender.clone(),
+                    };
+                    &transport_scope
+                }
+                None => scope,
+            };
+            let paused = transport.is_some_and(|transport| transport.paused);
+
             // let the current node process (catch any panics that may occur)
             let params = AudioParamValues::from(nodes);
             scope.node_id.set(*index);
-            let (success, tail_time) = {
+            let (success, tail_time) = if paused {
+                node.outputs
+                    .iter_mut()
+                    .for_each(AudioRenderQuantum::make_silent);
+                (true, true)
+            } else {
                 // We are abusing AssertUnwindSafe here, we cannot guarantee it upholds.
                 // This may lead to logic bugs later on, but it is the best that we can do.
                 // The alternative is to crash and reboot the render thread.
                 let catch_me = AssertUnwindSafe(|| node.process(params, scope));
 
-                match panic::catch_unwind(catch_me) {
+                let process_start = profiling.then(Instant::now);
+                #[cfg(feature = "alloc-guard")]
+                let alloc_guard = crate::alloc_guard::Guard::start();
+                let result = panic::catch_unwind(catch_me);
+                #[cfg(feature = "alloc-guard")]
+                if result.is_ok() {
+                    node.allocated |= alloc_guard.finish(*index, node.allocated);
+                }
+                if let Some(process_start) = process_start {
+                    node.render_time += process_start.elapsed().as_secs_f64();
+                }
+
+                match result {
                     Ok(tail_time) => (true, tail_time),
                     Err(e) => {
                         node.outgoing_edges.clear();
@@ -397,19 +919,90 @@ impl Graph {
                 }
             };
 
+            if node.muted {
+                node.outputs
+                    .iter_mut()
+                    .for_each(AudioRenderQuantum::make_silent);
+            }
+
+            // inspect the output at the watch points of the watchdog
+            let watchdog = self
+                .watchdog
+                .as_ref()
+                .filter(|watchdog| watchdog.watch_points.contains(index));
+            if let Some(watchdog) = watchdog {
+                if let Some(reason) = node.inspect(watchdog) {
+                    let is_faulty = |node: &Node| match reason {
+                        WatchdogReason::NonFinite => node.has_non_finite_output(),
+                        WatchdogReason::Clipping => {
+                            node.has_clipping_output(watchdog.clipping_threshold)
+                        }
+                    };
+                    let origin = find_origin(nodes, &self.ordered, *index, is_faulty);
+
+                    if origin == *index {
+                        node.muted = true;
+                    } else if let Some(origin) = nodes.get(origin) {
+                        origin.borrow_mut().muted = true;
+                    }
+                    node.outputs
+                        .iter_mut()
+                        .for_each(AudioRenderQuantum::make_silent);
+
+                    scope.send_watchdog_event(WatchdogEvent {
+                        node_id: origin.0,
+                        watch_point: index.0,
+                        reason,
+                        timestamp: scope.current_time,
+                        event: Event {
+                            type_: "WatchdogEvent",
+                        },
+                    });
+                }
+            }
+
+            if let Some(lookback) = node.lookback.as_ref() {
+                lookback.write(&node.outputs[0]);
+            }
+
             // iterate all outgoing edges, lookup these nodes and add to their input
-            node.outgoing_edges
-                .iter()
+            let Node {
+                outgoing_edges,
+                outputs,
+                ..
+            } = &mut *node;
+            outgoing_edges
+                .iter_mut()
                 // audio params are connected to the 'hidden' usize::MAX output, ignore them here
                 .filter(|edge| edge.other_index != usize::MAX)
                 .for_each(|edge| {
                     let mut output_node = nodes[edge.other_id].borrow_mut();
                     output_node.has_inputs_connected = true;
-                    let signal = &node.outputs[edge.self_index];
+                    let signal = &outputs[edge.self_index];
                     let channel_config = &output_node.channel_config.clone();
 
-                    output_node.inputs[edge.other_index].add(signal, channel_config);
+                    match edge.fade.as_mut() {
+                        None => output_node.inputs[edge.other_index].add(signal, channel_config),
+                        Some(fade) => {
+                            let mut signal = signal.clone();
+                            fade.apply(&mut signal);
+                            output_node.inputs[edge.other_index].add(&signal, channel_config);
+                        }
+                    }
+                });
+
+            // forget the completed fades, and the connections that have been faded out
+            if outgoing_edges.iter().any(|edge| edge.fade.is_some()) {
+                let prev_len = outgoing_edges.len();
+                outgoing_edges.retain(|edge| match edge.fade {
+                    Some(fade) if fade.is_complete() => {
+                        edge.fade = None;
+                        fade.step > 0.
+                    }
+                    _ => true,
                 });
+                edges_removed |= outgoing_edges.len() != prev_len;
+            }
 
             let can_free = !success || node.can_free(tail_time);
 
@@ -455,8 +1048,12 @@ impl Graph {
                     // Retain when
                     // - special node (destination = id 0, listener = id 1), or
                     // - not connected to this dropped node, or
-                    // - if the control thread still has a handle to it.
-                    let retain = id.0 < 2 || !was_connected || !node.free_when_finished;
+                    // - if the control thread still has a handle to it, or
+                    // - if it still feeds into other nodes (e.g. a param read by an expression)
+                    let retain = id.0 < 2
+                        || !was_connected
+                        || !node.free_when_finished
+                        || !node.outgoing_edges.is_empty();
 
                     if !retain {
                         self.reclaim_id_channel
@@ -479,11 +1076,65 @@ impl Graph {
             }
         }
 
+        // The removed connections void the current ordering
+        if edges_removed {
+            self.ordered.clear();
+        }
+
+        // advance the clocks of the running transports
+        let sample_rate = scope.sample_rate as f64;
+        self.transports
+            .iter_mut()
+            .flatten()
+            .filter(|transport| !transport.paused)
+            .for_each(|transport| {
+                transport.current_frame += RENDER_QUANTUM_SIZE as u64;
+                let current_time = transport.current_frame as f64 / sample_rate;
+                transport
+                    .current_time
+                    .store(current_time, Ordering::Relaxed);
+            });
+
         // Return the output buffer of destination node
         &self.nodes[AudioNodeId(0)].get_mut().outputs[0]
     }
 }
 
+/// Find the most upstream node producing the faulty signal detected at the watch point
+///
+/// The nodes upstream of the watch point have already been rendered in the current render
+/// quantum, so their outputs can be inspected to trace the faulty signal back to its origin.
+fn find_origin(
+    nodes: &NodeCollection,
+    ordered: &[AudioNodeId],
+    watch_point: AudioNodeId,
+    is_faulty: impl Fn(&Node) -> bool,
+) -> AudioNodeId {
+    let mut origin = watch_point;
+
+    // bound the number of steps, the faulty signal may run through a cycle
+    for _ in 0..ordered.len() {
+        let upstream = ordered
+            .iter()
+            .copied()
+            // the watch point is currently borrowed
+            .filter(|&id| id != watch_point && id != origin)
+            .find(|&id| {
+                nodes.get(id).is_some_and(|node| {
+                    let node = node.borrow();
+                    node.outgoing_edges.iter().any(|e| e.other_id == origin) && is_faulty(&node)
+                })
+            });
+
+        match upstream {
+            Some(id) => origin = id,
+            None => break,
+        }
+    }
+
+    origin
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -579,6 +1230,101 @@ mod tests {
         assert!(pos2 < pos1); // node 1 depends on node 2
     }
 
+    #[test]
+    fn test_scheduled_mutations() {
+        let mut graph = Graph::new(llq::Queue::new().split().0);
+
+        let node = Box::new(TestNode { tail_time: false });
+        add_node(&mut graph, 0, node.clone());
+        add_node(&mut graph, 1, node.clone());
+        add_node(&mut graph, 2, node);
+        add_edge(&mut graph, 1, 0);
+
+        let connect = |from, to| GraphMutation::Connect {
+            from: AudioNodeId(from),
+            to: AudioNodeId(to),
+            output: 0,
+            input: 0,
+        };
+        // applied by time, then by insertion order
+        graph.schedule_mutation(2., connect(1, 0));
+        graph.schedule_mutation(
+            1.,
+            GraphMutation::DisconnectAll {
+                from: AudioNodeId(1),
+            },
+        );
+        graph.schedule_mutation(1., connect(1, 2));
+        assert_eq!(graph.nodes[AudioNodeId(1)].borrow().pending_mutations, 3);
+
+        // not yet due
+        graph.apply_scheduled_mutations(1.);
+        assert_eq!(graph.nodes[AudioNodeId(1)].borrow().outgoing_edges.len(), 1);
+
+        graph.apply_scheduled_mutations(1.5);
+        {
+            let edges = &graph.nodes[AudioNodeId(1)].borrow().outgoing_edges;
+            assert_eq!(edges.len(), 1);
+            assert_eq!(edges[0].other_id, AudioNodeId(2));
+        }
+
+        graph.apply_scheduled_mutations(3.);
+        assert_eq!(graph.nodes[AudioNodeId(1)].borrow().outgoing_edges.len(), 2);
+        assert_eq!(graph.nodes[AudioNodeId(1)].borrow().pending_mutations, 0);
+        assert!(graph.scheduled.is_empty());
+    }
+
+    #[test]
+    fn test_scheduled_mutations_no_alloc() {
+        let mut graph = Graph::new(llq::Queue::new().split().0);
+        let counters = Arc::new(SchedulingCounters::default());
+        graph.set_scheduling_counters(Arc::clone(&counters));
+
+        let node = Box::new(TestNode { tail_time: false });
+        add_node(&mut graph, 0, node.clone());
+        add_node(&mut graph, 1, node);
+
+        // one more than the control thread would send
+        assert!(counters.reserve_mutations(MAX_SCHEDULED_MUTATIONS + 1, usize::MAX));
+        assert!(!counters.reserve_mutations(1, MAX_SCHEDULED_MUTATIONS));
+
+        alloc_counter::deny_alloc(|| {
+            // scheduled in decreasing order of time, the last one is connected
+            (0..MAX_SCHEDULED_MUTATIONS).rev().for_each(|i| {
+                let mutation = if i == MAX_SCHEDULED_MUTATIONS - 1 {
+                    GraphMutation::Connect {
+                        from: AudioNodeId(1),
+                        to: AudioNodeId(0),
+                        output: 0,
+                        input: 0,
+                    }
+                } else {
+                    GraphMutation::Disconnect {
+                        from: AudioNodeId(1),
+                        to: AudioNodeId(0),
+                    }
+                };
+                graph.schedule_mutation(i as f64, mutation);
+            });
+
+            // the queue is full, this one is dropped
+            graph.schedule_mutation(
+                0.,
+                GraphMutation::DisconnectAll {
+                    from: AudioNodeId(1),
+                },
+            );
+
+            graph.apply_scheduled_mutations(MAX_SCHEDULED_MUTATIONS as f64);
+        });
+
+        assert!(graph.scheduled.is_empty());
+        assert_eq!(graph.nodes[AudioNodeId(1)].borrow().outgoing_edges.len(), 1);
+        assert_eq!(graph.nodes[AudioNodeId(1)].borrow().pending_mutations, 0);
+        // all the room is given back to the control thread
+        assert!(counters.reserve_mutations(MAX_SCHEDULED_MUTATIONS, MAX_SCHEDULED_MUTATIONS));
+    }
+
     #[test]
     fn test_remove_all() {
         let mut graph = Graph::new(llq::Queue::new().split().0);
@@ -743,4 +1489,83 @@ mod tests {
         // No other dropped nodes
         assert!(node_id_consumer.pop().is_none());
     }
+
+    #[derive(Debug, Clone)]
+    struct ClockNode {
+        current_frame: Arc<std::sync::atomic::AtomicU64>,
+    }
+
+    impl AudioProcessor for ClockNode {
+        fn process(
+            &mut self,
+            _inputs: &[AudioRenderQuantum],
+            _outputs: &mut [AudioRenderQuantum],
+            _params: AudioParamValues<'_>,
+            scope: &RenderScope,
+        ) -> bool {
+            self.current_frame
+                .store(scope.current_frame, Ordering::Relaxed);
+            true
+        }
+    }
+
+    #[test]
+    fn test_transport() {
+        let mut graph = Graph::new(llq::Queue::new().split().0);
+
+        let node = Box::new(TestNode { tail_time: true });
+        add_node(&mut graph, 0, node.clone());
+        add_node(&mut graph, 1, node.clone());
+        let current_frame = Arc::new(std::sync::atomic::AtomicU64::new(u64::MAX));
+        let clock = Box::new(ClockNode {
+            current_frame: Arc::clone(&current_frame),
+        });
+        add_node(&mut graph, 2, clock);
+        add_node(&mut graph, 3, node.clone());
+        add_node(&mut graph, 4, node);
+        add_edge(&mut graph, 2, 0);
+        add_audioparam(&mut graph, 3, 2);
+        add_edge(&mut graph, 4, 2);
+
+        let transport_time = Arc::new(AtomicF64::new(0.));
+        graph.add_transport(0, 256, Arc::clone(&transport_time));
+        graph.add_to_transport(0, AudioNodeId(2), false);
+        // the AudioParam follows the node, not the other nodes upstream
+        assert_eq!(graph.nodes[AudioNodeId(3)].get_mut().transport, Some(0));
+        assert_eq!(graph.nodes[AudioNodeId(4)].get_mut().transport, None);
+        graph.add_to_transport(0, AudioNodeId(2), true);
+        assert_eq!(graph.nodes[AudioNodeId(4)].get_mut().transport, Some(0));
+        // never part of a transport
+        graph.add_to_transport(0, AudioNodeId(0), true);
+        assert_eq!(graph.nodes[AudioNodeId(0)].get_mut().transport, None);
+
+        let render = |graph: &mut Graph, current_frame: u64| {
+            let scope = RenderScope {
+                current_frame,
+                current_time: current_frame as f64 / 48000.,
+                sample_rate: 48000.,
+                node_id: std::cell::Cell::new(AudioNodeId(0)),
+                event_sender: None,
+            };
+            graph.render(&scope);
+        };
+
+        render(&mut graph, 1024);
+        assert_eq!(current_frame.load(Ordering::Relaxed), 256);
+        float_eq::assert_float_eq!(
+            transport_time.load(Ordering::Relaxed),
+            384. / 48000.,
+            abs <= 1e-12
+        );
+
+        // the node is not rendered while paused
+        graph.set_transport_paused(0, true);
+        current_frame.store(u64::MAX, Ordering::Relaxed);
+        render(&mut graph, 1152);
+        assert_eq!(current_frame.load(Ordering::Relaxed), u64::MAX);
+
+        graph.set_transport_paused(0, false);
+        render(&mut graph, 1280);
+        assert_eq!(current_frame.load(Ordering::Relaxed), 384);
+    }
 }
@@ -11,6 +11,8 @@ pub(crate) use thread::*;
 mod processor;
 pub use processor::*;
 mod quantum;
+mod spectral;
+pub use spectral::*;
 
 mod node_collection;
 pub(crate) use node_collection::NodeCollection;
@@ -54,6 +54,14 @@ impl NodeCollection {
         self.nodes.iter_mut().filter_map(Option::as_mut)
     }
 
+    #[inline(always)]
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (AudioNodeId, &mut RefCell<Node>)> {
+        self.nodes
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, v)| v.as_mut().map(|v| (AudioNodeId(i as u64), v)))
+    }
+
     #[inline(always)]
     pub fn get(&self, index: AudioNodeId) -> Option<&RefCell<Node>> {
         self.nodes[index.0 as usize].as_ref()
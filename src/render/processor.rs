@@ -1,6 +1,8 @@
 //! Audio processing code that runs on the audio rendering thread
 use crate::context::{AudioNodeId, AudioParamId};
 use crate::events::{ErrorEvent, EventDispatch};
+use crate::node::OnsetEvent;
+use crate::watchdog::WatchdogEvent;
 use crate::{Event, RENDER_QUANTUM_SIZE};
 
 use super::{graph::Node, AudioRenderQuantum, NodeCollection};
@@ -34,6 +36,18 @@ impl RenderScope {
         }
     }
 
+    pub(crate) fn send_watchdog_event(&self, event: WatchdogEvent) {
+        if let Some(sender) = self.event_sender.as_ref() {
+            let _ = sender.try_send(EventDispatch::watchdog(event));
+        }
+    }
+
+    pub(crate) fn send_onset_event(&self, event: OnsetEvent) {
+        if let Some(sender) = self.event_sender.as_ref() {
+            let _ = sender.try_send(EventDispatch::onset(self.node_id.get(), event));
+        }
+    }
+
     pub(crate) fn report_error(&self, error: Box<dyn Any + Send>) {
         pub fn type_name_of_val<T: ?Sized>(_val: &T) -> &'static str {
             std::any::type_name::<T>()
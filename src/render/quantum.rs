@@ -233,8 +233,8 @@ impl AudioRenderQuantum {
     ///
     /// # Panics
     ///
-    /// This function will panic if the given number of channels is outside the [1, 32] range, 32
-    /// being defined by the MAX_CHANNELS constant.
+    /// This function will panic if the given number of channels is outside the
+    /// [1, MAX_CHANNELS] range.
     pub fn set_number_of_channels(&mut self, n: usize) {
         assert_valid_number_of_channels(n);
         for _ in self.number_of_channels()..n {
@@ -286,8 +286,8 @@ impl AudioRenderQuantum {
     ///
     /// # Panics
     ///
-    /// This function will panic if the given number of channels is outside the [1, 32] range, 32
-    /// being defined by the MAX_CHANNELS constant.
+    /// This function will panic if the given number of channels is outside the
+    /// [1, MAX_CHANNELS] range.
     #[inline(always)]
     pub(crate) fn mix(
         &mut self,
@@ -656,7 +656,7 @@ impl AudioRenderQuantum {
     ///
     /// This is often the case for upmixed buffers. When all channels are identical, modifications
     /// only need to be applied once.
-    fn all_channels_identical(&self) -> bool {
+    pub(crate) fn all_channels_identical(&self) -> bool {
         let mut channels = self.channels.iter();
         let first = channels.next().unwrap();
         for c in channels {
@@ -0,0 +1,715 @@
+//! Frequency-domain audio processing, see [`SpectralProcessor`]
+use std::any::Any;
+use std::sync::Arc;
+
+pub use realfft::num_complex::Complex;
+use realfft::{ComplexToReal, RealToComplex};
+
+use crate::dsp::{plan_fft_forward, plan_fft_inverse, Window};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
+
+const MIN_FFT_SIZE: usize = 32;
+const MAX_FFT_SIZE: usize = 32768;
+
+/// Options for the short-time Fourier transform of a [`StftProcessor`]
+#[derive(Clone, Copy, Debug)]
+pub struct SpectralOptions {
+    /// Size of the frames, a power of two in the range [32, 32768]
+    pub fft_size: usize,
+    /// Number of sample-frames between the start of two frames, must divide `fft_size`
+    ///
+    /// Frames must overlap by at least half of their size, unless the window is rectangular.
+    pub hop_size: usize,
+    /// Window applied to each frame before the forward and after the inverse transform
+    pub window: Window,
+}
+
+impl Default for SpectralOptions {
+    fn default() -> Self {
+        Self {
+            fft_size: 1024,
+            hop_size: 256,
+            window: Window::Hann,
+        }
+    }
+}
+
+fn assert_valid_spectral_options(options: &SpectralOptions) {
+    let SpectralOptions {
+        fft_size,
+        hop_size,
+        window,
+    } = *options;
+
+    if !fft_size.is_power_of_two() || !(MIN_FFT_SIZE..=MAX_FFT_SIZE).contains(&fft_size) {
+        panic!(
+            "IndexSizeError - Invalid fft size: {:?} is not a power of two in range [{:?}, {:?}]",
+            fft_size, MIN_FFT_SIZE, MAX_FFT_SIZE
+        );
+    }
+
+    if hop_size == 0 || fft_size % hop_size != 0 {
+        panic!(
+            "IndexSizeError - Invalid hop size: {:?} does not divide the fft size {:?}",
+            hop_size, fft_size
+        );
+    }
+
+    if window != Window::Rectangular && hop_size > fft_size / 2 {
+        panic!(
+            "IndexSizeError - Invalid hop size: {:?} is larger than half the fft size {:?}",
+            hop_size, fft_size
+        );
+    }
+}
+
+/// Spectra of the channels of a signal, for a single frame
+///
+/// Each channel holds the `fft_size / 2 + 1` bins of the transform, from 0 Hz to the Nyquist
+/// frequency.
+#[derive(Debug)]
+pub struct SpectralFrame {
+    channels: Vec<Vec<Complex<f32>>>,
+    number_of_channels: usize,
+    fft_size: usize,
+    sample_rate: f32,
+}
+
+impl SpectralFrame {
+    fn new(fft_size: usize) -> Self {
+        Self {
+            channels: vec![vec![Complex::default(); fft_size / 2 + 1]],
+            number_of_channels: 1,
+            fft_size,
+            sample_rate: 0.,
+        }
+    }
+
+    fn set_number_of_channels(&mut self, number_of_channels: usize) {
+        let number_of_bins = self.number_of_bins();
+        if self.channels.len() < number_of_channels {
+            self.channels
+                .resize(number_of_channels, vec![Complex::default(); number_of_bins]);
+        }
+        self.number_of_channels = number_of_channels;
+    }
+
+    /// Number of channels of the frame
+    pub fn number_of_channels(&self) -> usize {
+        self.number_of_channels
+    }
+
+    /// Size of the transform
+    pub fn fft_size(&self) -> usize {
+        self.fft_size
+    }
+
+    /// Number of bins of each channel, `fft_size / 2 + 1`
+    pub fn number_of_bins(&self) -> usize {
+        self.fft_size / 2 + 1
+    }
+
+    /// Center frequency of the bin, in Hz
+    pub fn bin_frequency(&self, bin: usize) -> f32 {
+        bin as f32 * self.sample_rate / self.fft_size as f32
+    }
+
+    /// Bins of a single channel
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is greater than the available number of channels
+    pub fn channel(&self, index: usize) -> &[Complex<f32>] {
+        assert!(index < self.number_of_channels);
+        &self.channels[index]
+    }
+
+    /// Mutable bins of a single channel
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is greater than the available number of channels
+    pub fn channel_mut(&mut self, index: usize) -> &mut [Complex<f32>] {
+        assert!(index < self.number_of_channels);
+        &mut self.channels[index]
+    }
+
+    /// Bins of all channels
+    pub fn channels(&self) -> impl Iterator<Item = &[Complex<f32>]> {
+        self.channels[..self.number_of_channels]
+            .iter()
+            .map(Vec::as_slice)
+    }
+
+    /// Mutable bins of all channels
+    pub fn channels_mut(&mut self) -> impl Iterator<Item = &mut [Complex<f32>]> {
+        self.channels[..self.number_of_channels]
+            .iter_mut()
+            .map(Vec::as_mut_slice)
+    }
+}
+
+/// Interface for frequency-domain processing code that runs on the audio rendering thread
+///
+/// The processor is run by a [`StftProcessor`], which slices the inputs in overlapping windowed
+/// frames, transforms them to the frequency domain, and overlap-adds the inverse transform of the
+/// output frames. The processor only edits spectra, e.g. to write a robotization or a spectral
+/// morphing effect.
+pub trait SpectralProcessor: Send {
+    /// Spectral processing function, called once per hop
+    ///
+    /// # Arguments
+    ///
+    /// - inputs: spectra of the current frame of each input
+    /// - output: spectrum of the output frame, initialized with the spectrum of the first input
+    fn process_frame(&mut self, inputs: &[SpectralFrame], output: &mut SpectralFrame);
+
+    /// Handle incoming messages from the linked AudioNode
+    ///
+    /// See [`AudioProcessor::onmessage`]
+    #[allow(unused_variables)]
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        log::warn!("Ignoring incoming message");
+    }
+}
+
+/// Last `fft_size` sample-frames of the channels of an input
+struct InputHistory {
+    channels: Vec<Vec<f32>>,
+    number_of_channels: usize,
+}
+
+/// [`AudioProcessor`] running a [`SpectralProcessor`] on the short-time Fourier transform of its
+/// inputs
+///
+/// The output has the channel count of the first input, and is delayed by `fft_size`
+/// sample-frames. The window is applied twice, the overlap-added frames are normalized so an
+/// unmodified spectrum reconstructs the input.
+///
+/// This is an extension to the spec.
+///
+/// Check the `examples/spectral_worklet.rs` file for example usage.
+pub struct StftProcessor<P> {
+    processor: P,
+    fft_size: usize,
+    hop_size: usize,
+    forward: Arc<dyn RealToComplex<f32>>,
+    inverse: Arc<dyn ComplexToReal<f32>>,
+    window: Vec<f32>,
+    /// Gain of the overlap-added frames, by position within the hop
+    normalization: Vec<f32>,
+    histories: Vec<InputHistory>,
+    input_spectra: Vec<SpectralFrame>,
+    output_spectrum: SpectralFrame,
+    /// Overlap-added output of each channel
+    overlap: Vec<Vec<f32>>,
+    frame: Vec<f32>,
+    scratch: Vec<Complex<f32>>,
+    /// Position of the next sample-frame in the circular buffers
+    position: usize,
+    /// Number of sample-frames since the last frame
+    elapsed: usize,
+    /// Number of sample-frames still to output after the inputs became silent
+    tail: usize,
+}
+
+impl<P: SpectralProcessor> StftProcessor<P> {
+    /// Run the spectral processor with the given transform options
+    ///
+    /// # Panics
+    ///
+    /// Will panic if:
+    ///
+    /// - the fft size is not a power of two in the range [32, 32768]
+    /// - the hop size does not divide the fft size
+    /// - the hop size is larger than half the fft size, with a window other than rectangular
+    pub fn new(processor: P, options: SpectralOptions) -> Self {
+        assert_valid_spectral_options(&options);
+        let SpectralOptions {
+            fft_size,
+            hop_size,
+            window,
+        } = options;
+
+        let forward = plan_fft_forward(fft_size);
+        let inverse = plan_fft_inverse(fft_size);
+        let scratch_len = forward.get_scratch_len().max(inverse.get_scratch_len());
+
+        let window = window.values(fft_size);
+        // the inverse transform is not scaled, fold 1 / fft_size into the normalization
+        let normalization = (0..hop_size)
+            .map(|i| {
+                let sum: f32 = window[i..].iter().step_by(hop_size).map(|w| w * w).sum();
+                if sum > 1e-6 {
+                    1. / (sum * fft_size as f32)
+                } else {
+                    0.
+                }
+            })
+            .collect();
+
+        Self {
+            processor,
+            fft_size,
+            hop_size,
+            forward,
+            inverse,
+            window,
+            normalization,
+            histories: vec![],
+            input_spectra: vec![],
+            output_spectrum: SpectralFrame::new(fft_size),
+            overlap: vec![vec![0.; fft_size]],
+            frame: vec![0.; fft_size],
+            scratch: vec![Complex::default(); scratch_len],
+            position: 0,
+            elapsed: 0,
+            tail: 0,
+        }
+    }
+
+    /// The wrapped spectral processor
+    pub fn processor(&self) -> &P {
+        &self.processor
+    }
+
+    /// The wrapped spectral processor, mutably
+    pub fn processor_mut(&mut self) -> &mut P {
+        &mut self.processor
+    }
+
+    /// Delay of the output, in sample-frames
+    pub fn latency(&self) -> usize {
+        self.fft_size
+    }
+
+    /// Transform the last `fft_size` sample-frames, run the spectral processor and overlap-add
+    /// its output
+    fn process_frame(&mut self, sample_rate: f32) {
+        let fft_size = self.fft_size;
+        let position = self.position;
+
+        for (history, spectrum) in self.histories.iter().zip(&mut self.input_spectra) {
+            spectrum.sample_rate = sample_rate;
+            spectrum.set_number_of_channels(history.number_of_channels);
+            history
+                .channels
+                .iter()
+                .zip(spectrum.channels_mut())
+                .for_each(|(samples, bins)| {
+                    // the oldest sample-frame is at the current position
+                    let (newer, older) = samples.split_at(position);
+                    self.frame
+                        .iter_mut()
+                        .zip(older.iter().chain(newer))
+                        .zip(&self.window)
+                        .for_each(|((f, s), w)| *f = s * w);
+                    // the input values are valid, the transform cannot fail
+                    let _ =
+                        self.forward
+                            .process_with_scratch(&mut self.frame, bins, &mut self.scratch);
+                });
+        }
+
+        let output = &mut self.output_spectrum;
+        let first = &self.input_spectra[0];
+        output.sample_rate = sample_rate;
+        output.set_number_of_channels(first.number_of_channels());
+        output
+            .channels_mut()
+            .zip(first.channels())
+            .for_each(|(o, i)| o.copy_from_slice(i));
+
+        self.processor.process_frame(&self.input_spectra, output);
+
+        let number_of_channels = output.number_of_channels();
+        if self.overlap.len() < number_of_channels {
+            self.overlap.resize(number_of_channels, vec![0.; fft_size]);
+        }
+
+        output
+            .channels_mut()
+            .zip(&mut self.overlap)
+            .for_each(|(bins, overlap)| {
+                // the imaginary parts of the DC and Nyquist bins are ignored by the inverse
+                bins[0].im = 0.;
+                bins[fft_size / 2].im = 0.;
+                let _ = self
+                    .inverse
+                    .process_with_scratch(bins, &mut self.frame, &mut self.scratch);
+
+                let (newer, older) = overlap.split_at_mut(position);
+                older
+                    .iter_mut()
+                    .chain(newer)
+                    .zip(&self.frame)
+                    .zip(&self.window)
+                    .zip(self.normalization.iter().cycle())
+                    .for_each(|(((o, f), w), n)| *o += f * w * n);
+            });
+    }
+}
+
+impl<P: SpectralProcessor> AudioProcessor for StftProcessor<P> {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        scope: &RenderScope,
+    ) -> bool {
+        let output = &mut outputs[0];
+
+        if inputs.iter().all(AudioRenderQuantum::is_silent) {
+            // the last frames are flushed, the processor can be removed
+            if self.tail == 0 {
+                output.make_silent();
+                return false;
+            }
+            self.tail = self.tail.saturating_sub(RENDER_QUANTUM_SIZE);
+        } else {
+            self.tail = self.fft_size;
+        }
+
+        if self.histories.len() < inputs.len() {
+            let fft_size = self.fft_size;
+            self.histories.resize_with(inputs.len(), || InputHistory {
+                channels: vec![vec![0.; fft_size]],
+                number_of_channels: 1,
+            });
+            self.input_spectra
+                .resize_with(inputs.len(), || SpectralFrame::new(fft_size));
+        }
+
+        // silent inputs keep their channel count, to flush the history of all channels
+        for (history, input) in self.histories.iter_mut().zip(inputs) {
+            if !input.is_silent() {
+                let number_of_channels = input.number_of_channels();
+                if history.channels.len() < number_of_channels {
+                    history
+                        .channels
+                        .resize(number_of_channels, vec![0.; self.fft_size]);
+                }
+                history.number_of_channels = number_of_channels;
+            }
+        }
+
+        let number_of_channels = self.histories[0].number_of_channels;
+        output.set_number_of_channels(number_of_channels);
+
+        let mut offset = 0;
+        while offset < RENDER_QUANTUM_SIZE {
+            let len = (self.hop_size - self.elapsed).min(RENDER_QUANTUM_SIZE - offset);
+            let range = self.position..self.position + len;
+
+            for (history, input) in self.histories.iter_mut().zip(inputs) {
+                let last_channel = input.number_of_channels() - 1;
+                history
+                    .channels
+                    .iter_mut()
+                    .take(history.number_of_channels)
+                    .enumerate()
+                    .for_each(|(i, samples)| {
+                        let data = input.channel_data(i.min(last_channel));
+                        samples[range.clone()].copy_from_slice(&data[offset..offset + len]);
+                    });
+            }
+
+            for (i, overlap) in self.overlap.iter_mut().enumerate() {
+                if i < number_of_channels {
+                    output.channel_data_mut(i)[offset..offset + len]
+                        .copy_from_slice(&overlap[range.clone()]);
+                }
+                overlap[range.clone()].fill(0.);
+            }
+
+            self.position = (self.position + len) % self.fft_size;
+            self.elapsed += len;
+            offset += len;
+
+            if self.elapsed == self.hop_size {
+                self.elapsed = 0;
+                self.process_frame(scope.sample_rate);
+            }
+        }
+
+        true
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        self.processor.onmessage(msg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::{AudioContextRegistration, BaseAudioContext, OfflineAudioContext};
+    use crate::node::{AudioNode, AudioScheduledSourceNode, ChannelConfig};
+    use crate::AudioBuffer;
+    use std::f32::consts::PI;
+
+    use super::*;
+
+    struct SpectralNode {
+        registration: AudioContextRegistration,
+        channel_config: ChannelConfig,
+        number_of_inputs: usize,
+    }
+
+    impl AudioNode for SpectralNode {
+        fn registration(&self) -> &AudioContextRegistration {
+            &self.registration
+        }
+
+        fn channel_config(&self) -> &ChannelConfig {
+            &self.channel_config
+        }
+
+        fn number_of_inputs(&self) -> usize {
+            self.number_of_inputs
+        }
+
+        fn number_of_outputs(&self) -> usize {
+            1
+        }
+    }
+
+    fn spectral_node<P: SpectralProcessor + 'static>(
+        context: &OfflineAudioContext,
+        processor: P,
+        options: SpectralOptions,
+        number_of_inputs: usize,
+    ) -> SpectralNode {
+        context.register(|registration| {
+            let node = SpectralNode {
+                registration,
+                channel_config: ChannelConfig::default(),
+                number_of_inputs,
+            };
+            (node, Box::new(StftProcessor::new(processor, options)))
+        })
+    }
+
+    struct Identity;
+
+    impl SpectralProcessor for Identity {
+        fn process_frame(&mut self, _inputs: &[SpectralFrame], _output: &mut SpectralFrame) {}
+    }
+
+    /// Output the spectrum of the last input
+    struct LastInput;
+
+    impl SpectralProcessor for LastInput {
+        fn process_frame(&mut self, inputs: &[SpectralFrame], output: &mut SpectralFrame) {
+            let last = inputs.last().unwrap();
+            output
+                .channels_mut()
+                .zip(last.channels())
+                .for_each(|(o, i)| o.copy_from_slice(i));
+        }
+    }
+
+    /// Remove the bins above the cutoff frequency
+    struct BrickWall(f32);
+
+    impl SpectralProcessor for BrickWall {
+        fn process_frame(&mut self, _inputs: &[SpectralFrame], output: &mut SpectralFrame) {
+            let cutoff = (0..output.number_of_bins())
+                .position(|bin| output.bin_frequency(bin) > self.0)
+                .unwrap_or(output.number_of_bins());
+            output
+                .channels_mut()
+                .for_each(|channel| channel[cutoff..].fill(Complex::default()));
+        }
+    }
+
+    fn noise(length: usize, seed: u32) -> Vec<f32> {
+        let mut state = seed;
+        (0..length)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (state >> 8) as f32 / (1 << 23) as f32 - 1.
+            })
+            .collect()
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_hop_size() {
+        let options = SpectralOptions {
+            fft_size: 512,
+            hop_size: 384,
+            window: Window::Hann,
+        };
+        let _ = StftProcessor::new(Identity, options);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_overlap() {
+        let options = SpectralOptions {
+            fft_size: 512,
+            hop_size: 512,
+            window: Window::Hann,
+        };
+        let _ = StftProcessor::new(Identity, options);
+    }
+
+    #[test]
+    fn test_identity_reconstruction() {
+        let sample_rate = 44_100.;
+        let fft_size = 512;
+
+        for (window, hop_size) in [
+            (Window::Hann, 128),
+            (Window::Hann, 256),
+            (Window::Blackman, 64),
+            (Window::Rectangular, 512),
+        ] {
+            let length = 2048;
+            let context = OfflineAudioContext::new(2, length + fft_size, sample_rate);
+            let left = noise(length, 1);
+            let right = noise(length, 2);
+            let mut src = context.create_buffer_source();
+            src.set_buffer(AudioBuffer::from(
+                vec![left.clone(), right.clone()],
+                sample_rate,
+            ));
+            src.start();
+
+            let options = SpectralOptions {
+                fft_size,
+                hop_size,
+                window,
+            };
+            let node = spectral_node(&context, Identity, options, 1);
+            src.connect(&node);
+            node.connect(&context.destination());
+
+            // the input is reconstructed, delayed by the fft size
+            let output = context.start_rendering_sync();
+            assert_float_eq!(
+                output.get_channel_data(0)[..fft_size],
+                vec![0.; fft_size][..],
+                abs_all <= 1e-5
+            );
+            assert_float_eq!(
+                output.get_channel_data(0)[fft_size..],
+                left[..],
+                abs_all <= 1e-4
+            );
+            assert_float_eq!(
+                output.get_channel_data(1)[fft_size..],
+                right[..],
+                abs_all <= 1e-4
+            );
+        }
+    }
+
+    #[test]
+    fn test_multiple_inputs() {
+        let sample_rate = 44_100.;
+        let fft_size = 256;
+        let length = 1024;
+        let context = OfflineAudioContext::new(1, length + fft_size, sample_rate);
+
+        let options = SpectralOptions {
+            fft_size,
+            hop_size: 64,
+            window: Window::Hann,
+        };
+        let node = spectral_node(&context, LastInput, options, 2);
+
+        let first = noise(length, 1);
+        let second = noise(length, 2);
+        for (input, data) in [first, second.clone()].into_iter().enumerate() {
+            let mut src = context.create_buffer_source();
+            src.set_buffer(AudioBuffer::from(vec![data], sample_rate));
+            src.connect_at(&node, 0, input);
+            src.start();
+        }
+        node.connect(&context.destination());
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(
+            output.get_channel_data(0)[fft_size..],
+            second[..],
+            abs_all <= 1e-4
+        );
+    }
+
+    #[test]
+    fn test_spectral_filter() {
+        let sample_rate = 48_000.;
+        let fft_size = 1024;
+        let length = 4096;
+        let context = OfflineAudioContext::new(1, length + fft_size, sample_rate);
+
+        // two sines, at the center frequency of bins 16 and 256
+        let low = 16. * sample_rate / fft_size as f32;
+        let high = 256. * sample_rate / fft_size as f32;
+        let data = (0..length)
+            .map(|i| {
+                let t = i as f32 / sample_rate;
+                (2. * PI * low * t).sin() + (2. * PI * high * t).sin()
+            })
+            .collect();
+        let mut src = context.create_buffer_source();
+        src.set_buffer(AudioBuffer::from(vec![data], sample_rate));
+        src.start();
+
+        let options = SpectralOptions {
+            fft_size,
+            hop_size: 256,
+            window: Window::Hann,
+        };
+        let node = spectral_node(&context, BrickWall(1000.), options, 1);
+        src.connect(&node);
+        node.connect(&context.destination());
+
+        // once the frames are fully overlapped, only the low sine remains
+        let output = context.start_rendering_sync();
+        output.get_channel_data(0)[2 * fft_size..length]
+            .iter()
+            .enumerate()
+            .for_each(|(i, v)| {
+                let t = (i + fft_size) as f32 / sample_rate;
+                assert_float_eq!(*v, (2. * PI * low * t).sin(), abs <= 1e-3);
+            });
+    }
+
+    #[test]
+    fn test_tail() {
+        let sample_rate = 44_100.;
+        let fft_size = 512;
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE * 8, sample_rate);
+        let mut impulse = vec![0.; RENDER_QUANTUM_SIZE];
+        impulse[0] = 1.;
+        let mut src = context.create_buffer_source();
+        src.set_buffer(AudioBuffer::from(vec![impulse], sample_rate));
+        src.start();
+
+        let options = SpectralOptions {
+            fft_size,
+            hop_size: 128,
+            window: Window::Hann,
+        };
+        let node = spectral_node(&context, Identity, options, 1);
+        src.connect(&node);
+        node.connect(&context.destination());
+
+        // the impulse is output after the source has ended
+        let output = context.start_rendering_sync();
+        let channel = output.get_channel_data(0);
+        assert_float_eq!(channel[fft_size], 1., abs <= 1e-5);
+        channel
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != fft_size)
+            .for_each(|(_, v)| assert_float_eq!(*v, 0., abs <= 1e-5));
+    }
+}
@@ -2,20 +2,25 @@
 
 use std::any::Any;
 use std::cell::Cell;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::collections::VecDeque;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::Sender;
 use dasp_sample::FromSample;
+use rubato::{FftFixedInOut, Resampler};
 
 use super::AudioRenderQuantum;
 use crate::buffer::{AudioBuffer, AudioBufferOptions};
+use crate::capacity::UnderrunReport;
 use crate::context::AudioNodeId;
 use crate::events::EventDispatch;
 use crate::message::ControlMessage;
 use crate::node::ChannelInterpretation;
 use crate::render::RenderScope;
+use crate::sync::atomic::AtomicU64;
+use crate::sync::channel::Receiver;
 use crate::{AudioRenderCapacityLoad, RENDER_QUANTUM_SIZE};
 
 use super::graph::Graph;
@@ -33,6 +38,10 @@ pub(crate) struct RenderThread {
     load_value_sender: Option<Sender<AudioRenderCapacityLoad>>,
     event_sender: Option<Sender<EventDispatch>>,
     garbage_collector: Option<llq::Producer<Box<dyn Any + Send>>>,
+    /// measure the render time of each node, for the underrun diagnostics
+    node_profiling: bool,
+    /// conversion to the device sample rate, if it differs from the context sample rate
+    output_resampler: Option<OutputResampler>,
 }
 
 // SAFETY:
@@ -63,9 +72,32 @@ impl RenderThread {
             load_value_sender: None,
             event_sender: None,
             garbage_collector: None,
+            node_profiling: false,
+            output_resampler: None,
         }
     }
 
+    /// Convert the rendered audio to the sample rate of the audio device
+    ///
+    /// The render graph keeps running at the context sample rate, a sample rate conversion stage
+    /// is inserted before the output buffer when both rates differ.
+    pub(crate) fn set_device_sample_rate(&mut self, device_sample_rate: f32) {
+        self.output_resampler = if device_sample_rate == self.sample_rate {
+            None
+        } else {
+            log::info!(
+                "Resampling output from {} Hz (context) to {} Hz (device)",
+                self.sample_rate,
+                device_sample_rate
+            );
+            Some(OutputResampler::new(
+                self.sample_rate,
+                device_sample_rate,
+                self.number_of_channels,
+            ))
+        };
+    }
+
     pub(crate) fn set_event_channels(
         &mut self,
         load_value_sender: Sender<AudioRenderCapacityLoad>,
@@ -75,6 +107,30 @@ impl RenderThread {
         self.event_sender = Some(event_sender);
     }
 
+    /// Callback to report the under/overruns signalled by the audio backend, which may run outside
+    /// of the render thread
+    #[cfg(feature = "cpal")]
+    pub(crate) fn backend_underrun_reporter(&self) -> impl Fn(String) + Send + 'static {
+        let event_sender = self.event_sender.clone();
+        let frames_played = Arc::clone(&self.frames_played);
+        let sample_rate = self.sample_rate as f64;
+
+        move |message| {
+            if let Some(event_sender) = &event_sender {
+                let current_frame = frames_played.load(Ordering::SeqCst);
+                let report = UnderrunReport {
+                    timestamp: current_frame as f64 / sample_rate,
+                    render_quantum: current_frame / RENDER_QUANTUM_SIZE as u64,
+                    render_time: 0.,
+                    deadline: 0.,
+                    slowest_nodes: Default::default(),
+                    backend_message: Some(message),
+                };
+                let _ = event_sender.try_send(EventDispatch::underrun(report));
+            }
+        }
+    }
+
     pub(crate) fn spawn_garbage_collector_thread(&mut self) {
         if self.garbage_collector.is_none() {
             let (gc_producer, gc_consumer) = llq::Queue::new().split();
@@ -89,6 +145,10 @@ impl RenderThread {
             Some(receiver) => receiver,
         };
 
+        // time of the next render quantum, compared with the time of the scheduled messages
+        let current_frame = self.frames_played.load(Ordering::SeqCst);
+        let current_time = current_frame as f64 / self.sample_rate as f64;
+
         for msg in receiver.try_iter() {
             use ControlMessage::*;
 
@@ -138,16 +198,83 @@ impl RenderThread {
                     self.receiver = None;
                     return; // no further handling of ctrl msgs
                 }
-                Startup { graph } => {
+                Startup { mut graph } => {
                     debug_assert!(self.graph.is_none());
+                    graph.set_profiling(self.node_profiling);
                     self.graph = Some(graph);
                 }
+                SetWatchdog { config } => {
+                    self.graph.as_mut().unwrap().set_watchdog(config);
+                }
+                SetConnectionFade { frames } => {
+                    self.graph.as_mut().unwrap().set_connection_fade(frames);
+                }
+                ScheduleMutations { when, mutations } => {
+                    let graph = self.graph.as_mut().unwrap();
+                    mutations
+                        .into_iter()
+                        .for_each(|mutation| graph.schedule_mutation(when, mutation));
+                }
+                SetLookback { id, buffer } => {
+                    let previous = self.graph.as_mut().unwrap().set_lookback(id, buffer);
+                    // the recorded frames are deallocated off the render thread
+                    if let (Some(previous), Some(gc)) = (previous, self.garbage_collector.as_mut())
+                    {
+                        gc.push(llq::Node::new(Box::new(previous)));
+                    }
+                }
+                AddTransport { id, current_time } => {
+                    let current_frame = self.frames_played.load(Ordering::SeqCst);
+                    self.graph
+                        .as_mut()
+                        .unwrap()
+                        .add_transport(id, current_frame, current_time);
+                }
+                AddToTransport { id, node, upstream } => {
+                    self.graph
+                        .as_mut()
+                        .unwrap()
+                        .add_to_transport(id, node, upstream);
+                }
+                SetTransportPaused { id, paused } => {
+                    self.graph
+                        .as_mut()
+                        .unwrap()
+                        .set_transport_paused(id, paused);
+                }
+                SetNodeProfiling { enabled } => {
+                    self.node_profiling = enabled;
+                    if let Some(graph) = self.graph.as_mut() {
+                        graph.set_profiling(enabled);
+                    }
+                }
+                SetSchedulingCounters { counters } => {
+                    self.graph
+                        .as_mut()
+                        .unwrap()
+                        .set_scheduling_counters(counters);
+                }
                 NodeMessage { id, mut msg } => {
-                    self.graph.as_mut().unwrap().route_message(id, msg.as_mut());
+                    self.graph
+                        .as_mut()
+                        .unwrap()
+                        .route_node_message(id, msg.as_mut(), current_time);
                     if let Some(gc) = self.garbage_collector.as_mut() {
                         gc.push(msg)
                     }
                 }
+                NodeMessages { messages } => {
+                    for (id, mut msg) in messages {
+                        self.graph.as_mut().unwrap().route_node_message(
+                            id,
+                            msg.as_mut(),
+                            current_time,
+                        );
+                        if let Some(gc) = self.garbage_collector.as_mut() {
+                            gc.push(msg)
+                        }
+                    }
+                }
             }
         }
     }
@@ -157,7 +284,15 @@ impl RenderThread {
     // don't launch a thread.
     //
     // cf. https://webaudio.github.io/web-audio-api/#dom-offlineaudiocontext-startrendering
-    pub fn render_audiobuffer_sync(mut self, length: usize) -> AudioBuffer {
+    /// Render the full graph into an `AudioBuffer` of the given length
+    ///
+    /// The rendered values of the AudioParam nodes in `param_captures` are recorded into the
+    /// accompanying buffers, one value per sample-frame.
+    pub fn render_audiobuffer_sync(
+        mut self,
+        length: usize,
+        param_captures: Vec<(AudioNodeId, Arc<Mutex<Vec<f32>>>)>,
+    ) -> AudioBuffer {
         let options = AudioBufferOptions {
             number_of_channels: self.number_of_channels,
             length,
@@ -167,6 +302,13 @@ impl RenderThread {
         let mut buffer = AudioBuffer::new(options);
         let num_frames = (length + RENDER_QUANTUM_SIZE - 1) / RENDER_QUANTUM_SIZE;
 
+        let mut captured_values: Vec<Vec<f32>> = param_captures
+            .iter()
+            .map(|_| Vec::with_capacity(num_frames * RENDER_QUANTUM_SIZE))
+            .collect();
+
+        let quantum_duration = RENDER_QUANTUM_SIZE as f64 / self.sample_rate as f64;
+
         for _ in 0..num_frames {
             // Handle addition/removal of nodes/edges
             self.handle_control_messages();
@@ -187,6 +329,7 @@ impl RenderThread {
 
             // Render audio graph
             let graph = self.graph.as_mut().unwrap();
+            graph.apply_scheduled_mutations(current_time + quantum_duration);
 
             // For x64 and aarch, process with denormal floats disabled (for performance, #194)
             #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
@@ -203,22 +346,42 @@ impl RenderThread {
                     );
                 },
             );
+
+            param_captures
+                .iter()
+                .zip(captured_values.iter_mut())
+                .for_each(|((id, _), values)| graph.append_param_values(*id, values));
         }
 
+        param_captures
+            .into_iter()
+            .zip(captured_values)
+            .for_each(|((_, shared), mut values)| {
+                values.truncate(length);
+                *shared.lock().unwrap() = values;
+            });
+
         buffer
     }
 
     pub fn render<S: FromSample<f32> + Clone>(&mut self, output_buffer: &mut [S]) {
         // Collect timing information
         let render_start = Instant::now();
+        let start_frame = self.frames_played.load(Ordering::SeqCst);
+        let output_sample_rate = self
+            .output_resampler
+            .as_ref()
+            .map_or(self.sample_rate, |resampler| resampler.sample_rate);
+        let deadline =
+            (output_buffer.len() / self.number_of_channels) as f64 / output_sample_rate as f64;
 
         // Perform actual rendering
 
         // For x64 and aarch, process with denormal floats disabled (for performance, #194)
         #[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
-        no_denormals::no_denormals(|| self.render_inner(output_buffer));
+        no_denormals::no_denormals(|| self.render_output(output_buffer));
         #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
-        self.render_inner(output_buffer);
+        self.render_output(output_buffer);
 
         // calculate load value and ship to control thread
         if let Some(load_value_sender) = &self.load_value_sender {
@@ -233,6 +396,38 @@ impl RenderThread {
             };
             let _ = load_value_sender.try_send(load_value_data);
         }
+
+        // report missed deadlines, along with the slowest nodes when profiling is enabled
+        if let Some(event_sender) = &self.event_sender {
+            let render_time = render_start.elapsed().as_secs_f64();
+            let slowest_nodes = self
+                .graph
+                .as_mut()
+                .map(Graph::take_slowest_nodes)
+                .unwrap_or_default();
+
+            if render_time > deadline {
+                let report = UnderrunReport {
+                    timestamp: start_frame as f64 / self.sample_rate as f64,
+                    render_quantum: start_frame / RENDER_QUANTUM_SIZE as u64,
+                    render_time,
+                    deadline,
+                    slowest_nodes,
+                    backend_message: None,
+                };
+                let _ = event_sender.try_send(EventDispatch::underrun(report));
+            }
+        }
+    }
+
+    fn render_output<S: FromSample<f32> + Clone>(&mut self, output_buffer: &mut [S]) {
+        match self.output_resampler.take() {
+            Some(mut resampler) => {
+                resampler.render(self, output_buffer);
+                self.output_resampler = Some(resampler);
+            }
+            None => self.render_inner(output_buffer),
+        }
     }
 
     fn render_inner<S: FromSample<f32> + Clone>(&mut self, mut output_buffer: &mut [S]) {
@@ -278,6 +473,7 @@ impl RenderThread {
         // The audio graph is rendered in chunks of RENDER_QUANTUM_SIZE frames.  But some audio backends
         // may not be able to emit chunks of this size.
         let chunk_size = RENDER_QUANTUM_SIZE * self.number_of_channels;
+        let quantum_duration = RENDER_QUANTUM_SIZE as f64 / self.sample_rate as f64;
 
         for data in output_buffer.chunks_mut(chunk_size) {
             // update time
@@ -295,7 +491,9 @@ impl RenderThread {
             };
 
             // render audio graph, clone it in case we need to mutate/store the value later
-            let mut destination_buffer = self.graph.as_mut().unwrap().render(&scope).clone();
+            let graph = self.graph.as_mut().unwrap();
+            graph.apply_scheduled_mutations(current_time + quantum_duration);
+            let mut destination_buffer = graph.render(&scope).clone();
 
             // online AudioContext allows channel count to be less than the number
             // of channels of the backend stream, i.e. number of channels of the
@@ -327,6 +525,80 @@ impl RenderThread {
     }
 }
 
+/// Sample rate conversion stage between the render graph and the audio device
+struct OutputResampler {
+    processor: FftFixedInOut<f32>,
+    /// sample rate of the audio device
+    sample_rate: f32,
+    /// interleaved input chunk, rendered at the context sample rate
+    rendered: Vec<f32>,
+    samples_in: Vec<Vec<f32>>,
+    samples_out: Vec<Vec<f32>>,
+    /// interleaved frames at the device sample rate, not yet shipped to the device
+    converted: VecDeque<f32>,
+}
+
+impl OutputResampler {
+    fn new(sample_rate_in: f32, sample_rate_out: f32, number_of_channels: usize) -> Self {
+        let processor = FftFixedInOut::new(
+            sample_rate_in.round() as usize,
+            sample_rate_out.round() as usize,
+            RENDER_QUANTUM_SIZE,
+            number_of_channels,
+        )
+        .expect("invalid output resampler configuration");
+
+        let rendered = vec![0.; processor.input_frames_max() * number_of_channels];
+        let samples_in = processor.input_buffer_allocate(true);
+        let samples_out = processor.output_buffer_allocate(true);
+        // enough room for large device buffers, to avoid allocations in the render thread
+        let converted =
+            VecDeque::with_capacity((processor.output_frames_max() + 8192) * number_of_channels);
+
+        Self {
+            processor,
+            sample_rate: sample_rate_out,
+            rendered,
+            samples_in,
+            samples_out,
+            converted,
+        }
+    }
+
+    fn render<S: FromSample<f32> + Clone>(
+        &mut self,
+        render_thread: &mut RenderThread,
+        output_buffer: &mut [S],
+    ) {
+        let number_of_channels = self.samples_in.len();
+
+        while self.converted.len() < output_buffer.len() {
+            render_thread.render_inner(&mut self.rendered[..]);
+
+            for (i, channel) in self.samples_in.iter_mut().enumerate() {
+                let input = self.rendered.iter().skip(i).step_by(number_of_channels);
+                channel.iter_mut().zip(input).for_each(|(o, i)| *o = *i);
+            }
+
+            let (_, frames) = self
+                .processor
+                .process_into_buffer(&self.samples_in, &mut self.samples_out, None)
+                .unwrap();
+
+            for i in 0..frames {
+                self.converted
+                    .extend(self.samples_out.iter().map(|channel| channel[i]));
+            }
+        }
+
+        let len = output_buffer.len();
+        output_buffer
+            .iter_mut()
+            .zip(self.converted.drain(..len))
+            .for_each(|(o, i)| *o = S::from_sample_(i));
+    }
+}
+
 impl Drop for RenderThread {
     fn drop(&mut self) {
         if let Some(gc) = self.garbage_collector.as_mut() {
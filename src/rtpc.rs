@@ -0,0 +1,267 @@
+//! Real-time parameter controls (RTPC), to drive many params from a single game value
+//!
+//! An RTPC is a named value of the context, e.g. the health of the player. Params are bound to it
+//! with [`BaseAudioContext::bind_rtpc`], each through its own [`RtpcCurve`] mapping the RTPC
+//! value to the param value. Setting the RTPC with [`BaseAudioContext::set_rtpc`] updates all
+//! the bound params within the same render quantum.
+//!
+//! [`BaseAudioContext::bind_rtpc`]: crate::context::BaseAudioContext::bind_rtpc
+//! [`BaseAudioContext::set_rtpc`]: crate::context::BaseAudioContext::set_rtpc
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::param::{set_values_synchronized, AudioParam, WeakAudioParam};
+
+/// Mapping from the value of an RTPC to the value of a bound param
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::rtpc::RtpcCurve;
+///
+/// let context = AudioContext::default();
+/// let heartbeat = context.create_gain();
+/// let music = context.create_biquad_filter();
+///
+/// // the heartbeat gets louder and the music gets muffled when the health is low
+/// context.bind_rtpc("health", heartbeat.gain(), RtpcCurve::lookup_table(&[(0.2, 1.), (0.5, 0.)]));
+/// let cutoff = RtpcCurve::from_fn(|health| 500. * 40_f32.powf(health));
+/// context.bind_rtpc("health", music.frequency(), cutoff);
+///
+/// context.set_rtpc("health", 0.3);
+/// ```
+#[derive(Clone)]
+pub struct RtpcCurve {
+    kind: CurveKind,
+}
+
+#[derive(Clone)]
+enum CurveKind {
+    /// `(rtpc, param)` points with strictly increasing RTPC values
+    LookupTable(Vec<(f32, f32)>),
+    Function(Arc<dyn Fn(f32) -> f32 + Send + Sync>),
+}
+
+impl fmt::Debug for RtpcCurve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            CurveKind::LookupTable(points) => f
+                .debug_tuple("RtpcCurve::LookupTable")
+                .field(points)
+                .finish(),
+            CurveKind::Function(_) => f.debug_tuple("RtpcCurve::Function").finish(),
+        }
+    }
+}
+
+impl RtpcCurve {
+    /// The param value equals the RTPC value
+    pub fn identity() -> Self {
+        Self::from_fn(|value| value)
+    }
+
+    /// Linearly interpolate between the given `(rtpc, param)` points
+    ///
+    /// The curve is constant before the first point and after the last one.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if:
+    /// - no points are given
+    /// - the RTPC values are not strictly increasing
+    /// - a value is not finite
+    pub fn lookup_table(points: &[(f32, f32)]) -> Self {
+        assert!(
+            !points.is_empty(),
+            "InvalidStateError - RTPC curve requires at least one point"
+        );
+        assert!(
+            points.iter().all(|(x, y)| x.is_finite() && y.is_finite()),
+            "TypeError - RTPC curve points must be finite"
+        );
+        assert!(
+            points.windows(2).all(|w| w[0].0 < w[1].0),
+            "InvalidStateError - RTPC curve values must be strictly increasing"
+        );
+
+        Self {
+            kind: CurveKind::LookupTable(points.to_vec()),
+        }
+    }
+
+    /// Map the RTPC value with an arbitrary function
+    pub fn from_fn<F: Fn(f32) -> f32 + Send + Sync + 'static>(f: F) -> Self {
+        Self {
+            kind: CurveKind::Function(Arc::new(f)),
+        }
+    }
+
+    /// Param value for the given RTPC value
+    pub fn value_at(&self, value: f32) -> f32 {
+        match &self.kind {
+            CurveKind::Function(f) => f(value),
+            CurveKind::LookupTable(points) => {
+                let index = points.partition_point(|(x, _)| *x <= value);
+                if index == 0 {
+                    return points[0].1;
+                }
+                if index == points.len() {
+                    return points[index - 1].1;
+                }
+                let (x0, y0) = points[index - 1];
+                let (x1, y1) = points[index];
+                y0 + (y1 - y0) * (value - x0) / (x1 - x0)
+            }
+        }
+    }
+}
+
+struct RtpcBinding {
+    param: WeakAudioParam,
+    curve: RtpcCurve,
+}
+
+#[derive(Default)]
+struct Rtpc {
+    value: Option<f32>,
+    bindings: Vec<RtpcBinding>,
+}
+
+/// RTPCs of a context, with the params bound to them
+#[derive(Default)]
+pub(crate) struct RtpcRegistry {
+    rtpcs: HashMap<String, Rtpc>,
+}
+
+impl RtpcRegistry {
+    /// Current value of an RTPC, `None` if it has never been set
+    pub fn value(&self, name: &str) -> Option<f32> {
+        self.rtpcs.get(name).and_then(|rtpc| rtpc.value)
+    }
+
+    /// Bind a param to an RTPC, the param is updated right away if the RTPC has been set
+    ///
+    /// A param bound twice to the same RTPC only keeps the latest curve.
+    pub fn bind(&mut self, name: &str, param: &AudioParam, curve: RtpcCurve) {
+        let rtpc = self.rtpcs.entry(name.to_owned()).or_default();
+        rtpc.bindings.retain(|binding| !binding.param.is(param));
+        if let Some(value) = rtpc.value {
+            param.set_value(curve.value_at(value));
+        }
+        rtpc.bindings.push(RtpcBinding {
+            param: WeakAudioParam::new(param),
+            curve,
+        });
+    }
+
+    pub fn unbind(&mut self, name: &str, param: &AudioParam) {
+        if let Some(rtpc) = self.rtpcs.get_mut(name) {
+            rtpc.bindings.retain(|binding| !binding.param.is(param));
+        }
+    }
+
+    /// Set the value of an RTPC and update the params bound to it in a single message
+    pub fn set(&mut self, name: &str, value: f32) {
+        let rtpc = self.rtpcs.entry(name.to_owned()).or_default();
+        rtpc.value = Some(value);
+
+        // forget the params that have been dropped
+        let mut params = Vec::with_capacity(rtpc.bindings.len());
+        rtpc.bindings
+            .retain(|binding| match binding.param.upgrade() {
+                Some(param) => {
+                    params.push((param, binding.curve.value_at(value)));
+                    true
+                }
+                None => false,
+            });
+
+        let params: Vec<_> = params
+            .iter()
+            .map(|(param, value)| (param, *value))
+            .collect();
+        set_values_synchronized(&params);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::{AudioNode, AudioScheduledSourceNode};
+    use crate::RENDER_QUANTUM_SIZE;
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_lookup_table() {
+        let curve = RtpcCurve::lookup_table(&[(0., 1.), (1., 3.), (2., 0.)]);
+        assert_float_eq!(curve.value_at(-1.), 1., abs <= 0.);
+        assert_float_eq!(curve.value_at(0.5), 2., abs <= 0.);
+        assert_float_eq!(curve.value_at(1.), 3., abs <= 0.);
+        assert_float_eq!(curve.value_at(1.5), 1.5, abs <= 0.);
+        assert_float_eq!(curve.value_at(3.), 0., abs <= 0.);
+
+        let curve = RtpcCurve::from_fn(|x| x * 2.);
+        assert_float_eq!(curve.value_at(3.), 6., abs <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_lookup_table_not_increasing() {
+        let _ = RtpcCurve::lookup_table(&[(1., 1.), (1., 3.)]);
+    }
+
+    #[test]
+    fn test_set_rtpc() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        assert_eq!(context.rtpc("health"), None);
+
+        let mut a = context.create_constant_source();
+        a.connect(&context.destination());
+        a.start();
+        let mut b = context.create_constant_source();
+        b.connect(&context.destination());
+        b.start();
+
+        context.bind_rtpc("health", a.offset(), RtpcCurve::identity());
+        context.bind_rtpc("health", b.offset(), RtpcCurve::from_fn(|x| 10. * x));
+        context.set_rtpc("health", 0.5);
+        assert_eq!(context.rtpc("health"), Some(0.5));
+        assert_float_eq!(a.offset().value(), 0.5, abs <= 0.);
+        assert_float_eq!(b.offset().value(), 5., abs <= 0.);
+
+        // bound after the RTPC was set, rebinding replaces the curve
+        let c = context.create_gain();
+        context.bind_rtpc("health", c.gain(), RtpcCurve::identity());
+        assert_float_eq!(c.gain().value(), 0.5, abs <= 0.);
+        context.bind_rtpc("health", c.gain(), RtpcCurve::from_fn(|x| x + 1.));
+        assert_float_eq!(c.gain().value(), 1.5, abs <= 0.);
+        context.unbind_rtpc("health", c.gain());
+        context.set_rtpc("health", 0.25);
+        assert_float_eq!(c.gain().value(), 1.5, abs <= 0.);
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(
+            output.get_channel_data(0),
+            &[2.75; RENDER_QUANTUM_SIZE][..],
+            abs_all <= 0.
+        );
+    }
+
+    #[test]
+    fn test_dropped_param() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let gain = context.create_gain();
+        context.bind_rtpc("volume", gain.gain(), RtpcCurve::identity());
+        drop(gain);
+
+        context.set_rtpc("volume", 0.5);
+        assert!(context.base().rtpcs().rtpcs["volume"].bindings.is_empty());
+    }
+}
@@ -0,0 +1,1744 @@
+//! Minimal reader for the HDF5 file format, the container format of netCDF-4 and SOFA files
+//!
+//! Only the subset of the format that is required to read the numeric datasets (and their string
+//! attributes) from the root group of a file is implemented:
+//!
+//! - superblock versions 0 to 3
+//! - object header versions 1 and 2, including continuation blocks
+//! - old style groups (symbol tables) and new style groups (compact and dense link storage)
+//! - compact, contiguous and chunked data layouts (chunk indexing by version 1 B-tree or a
+//!   single chunk)
+//! - the deflate, shuffle and fletcher32 filters
+//!
+//! See <https://docs.hdfgroup.org/hdf5/develop/_f_m_t3.html> for the format specification.
+
+use std::error::Error;
+
+use super::inflate::zlib_decompress;
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+const SIGNATURE: &[u8; 8] = b"\x89HDF\r\n\x1a\n";
+
+// header message types
+const MSG_DATASPACE: u16 = 0x0001;
+const MSG_LINK_INFO: u16 = 0x0002;
+const MSG_DATATYPE: u16 = 0x0003;
+const MSG_LINK: u16 = 0x0006;
+const MSG_LAYOUT: u16 = 0x0008;
+const MSG_FILTER_PIPELINE: u16 = 0x000B;
+const MSG_ATTRIBUTE: u16 = 0x000C;
+const MSG_CONTINUATION: u16 = 0x0010;
+const MSG_SYMBOL_TABLE: u16 = 0x0011;
+
+// filter identifiers
+const FILTER_DEFLATE: u16 = 1;
+const FILTER_SHUFFLE: u16 = 2;
+const FILTER_FLETCHER32: u16 = 3;
+
+/// Maximum compression ratio of the deflate format
+const MAX_DEFLATE_RATIO: u64 = 1032;
+
+fn invalid<T>(msg: &str) -> Result<T> {
+    Err(format!("InvalidData - HDF5: {msg}").into())
+}
+
+fn unsupported<T>(msg: &str) -> Result<T> {
+    Err(format!("NotSupportedError - HDF5: {msg}").into())
+}
+
+/// Number of bytes needed to encode the given value (as used by B-tree and fractal heap fields)
+fn bytes_needed(value: u64) -> usize {
+    if value == 0 {
+        1
+    } else {
+        (64 - value.leading_zeros() as usize + 7) / 8
+    }
+}
+
+/// Size in bytes of the elements of the given shape, `None` on overflow
+fn data_size(shape: &[u64], element_size: usize) -> Option<u64> {
+    shape
+        .iter()
+        .try_fold(element_size as u64, |size, &d| size.checked_mul(d))
+}
+
+/// Little endian reader over a slice of the file
+#[derive(Clone)]
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    offset_size: usize,
+    length_size: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&end| end <= self.data.len());
+        match end {
+            Some(end) => {
+                let slice = &self.data[self.pos..end];
+                self.pos = end;
+                Ok(slice)
+            }
+            None => invalid("unexpected end of data"),
+        }
+    }
+
+    fn skip(&mut self, n: usize) -> Result<()> {
+        self.bytes(n).map(|_| ())
+    }
+
+    fn uint(&mut self, n: usize) -> Result<u64> {
+        let bytes = self.bytes(n)?;
+        if n > 8 {
+            return invalid("integer field too wide");
+        }
+        Ok(bytes
+            .iter()
+            .rev()
+            .fold(0u64, |acc, &b| (acc << 8) | u64::from(b)))
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        self.uint(2).map(|v| v as u16)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        self.uint(4).map(|v| v as u32)
+    }
+
+    /// Read an address, `None` denotes the undefined address
+    fn offset(&mut self) -> Result<Option<u64>> {
+        let n = self.offset_size;
+        let value = self.uint(n)?;
+        let undefined = if n == 8 { u64::MAX } else { (1 << (8 * n)) - 1 };
+        Ok((value != undefined).then_some(value))
+    }
+
+    fn length(&mut self) -> Result<u64> {
+        let n = self.length_size;
+        self.uint(n)
+    }
+
+    fn signature(&mut self, expected: &[u8; 4]) -> Result<()> {
+        if self.bytes(4)? != expected {
+            return invalid(&format!(
+                "expected {} signature",
+                String::from_utf8_lossy(expected)
+            ));
+        }
+        Ok(())
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+}
+
+/// Header message of an object
+struct Message<'a> {
+    kind: u16,
+    flags: u8,
+    data: &'a [u8],
+}
+
+/// Element type of a dataset or attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Datatype {
+    Integer {
+        size: usize,
+        signed: bool,
+        big_endian: bool,
+    },
+    Float {
+        size: usize,
+        big_endian: bool,
+    },
+    String {
+        size: usize,
+    },
+}
+
+impl Datatype {
+    fn size(&self) -> usize {
+        match *self {
+            Self::Integer { size, .. } | Self::Float { size, .. } | Self::String { size } => size,
+        }
+    }
+
+    fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < 8 {
+            return invalid("datatype message too short");
+        }
+        let class = data[0] & 0x0f;
+        let bits = data[1];
+        let size = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+
+        match class {
+            0 => Ok(Self::Integer {
+                size,
+                signed: bits & 0x08 != 0,
+                big_endian: bits & 0x01 != 0,
+            }),
+            1 => Ok(Self::Float {
+                size,
+                big_endian: bits & 0x01 != 0,
+            }),
+            3 => Ok(Self::String { size }),
+            _ => unsupported(&format!("datatype class {class}")),
+        }
+    }
+
+    fn to_f64(self, raw: &[u8]) -> Result<Vec<f64>> {
+        let size = self.size();
+        if size == 0 || raw.len() % size != 0 {
+            return invalid("data size is not a multiple of the element size");
+        }
+
+        let read = |chunk: &[u8], big_endian: bool| {
+            let mut bytes = [0u8; 8];
+            if big_endian {
+                chunk
+                    .iter()
+                    .rev()
+                    .zip(&mut bytes)
+                    .for_each(|(s, d)| *d = *s);
+            } else {
+                chunk.iter().zip(&mut bytes).for_each(|(s, d)| *d = *s);
+            }
+            u64::from_le_bytes(bytes)
+        };
+
+        let values = match self {
+            Self::Float {
+                size: 4,
+                big_endian,
+            } => raw
+                .chunks_exact(4)
+                .map(|c| f64::from(f32::from_bits(read(c, big_endian) as u32)))
+                .collect(),
+            Self::Float {
+                size: 8,
+                big_endian,
+            } => raw
+                .chunks_exact(8)
+                .map(|c| f64::from_bits(read(c, big_endian)))
+                .collect(),
+            Self::Integer {
+                size: size @ 1..=8,
+                signed,
+                big_endian,
+            } => raw
+                .chunks_exact(size)
+                .map(|c| {
+                    let value = read(c, big_endian);
+                    if signed {
+                        // sign extend
+                        let shift = 64 - 8 * size as u32;
+                        ((value << shift) as i64 >> shift) as f64
+                    } else {
+                        value as f64
+                    }
+                })
+                .collect(),
+            _ => return unsupported(&format!("numeric conversion of {self:?}")),
+        };
+
+        Ok(values)
+    }
+}
+
+/// Shape of a dataset or attribute, an empty shape denotes a scalar
+fn parse_dataspace(data: &[u8], length_size: usize) -> Result<Vec<u64>> {
+    let mut r = Reader {
+        data,
+        pos: 0,
+        offset_size: 8,
+        length_size,
+    };
+    let version = r.u8()?;
+    let rank = r.u8()? as usize;
+    let _flags = r.u8()?;
+    match version {
+        1 => r.skip(5)?,
+        2 => {
+            if r.u8()? == 2 {
+                // null dataspace
+                return Ok(vec![0]);
+            }
+        }
+        _ => return unsupported(&format!("dataspace version {version}")),
+    }
+
+    (0..rank).map(|_| r.length()).collect()
+}
+
+/// Filter of the filter pipeline
+struct Filter {
+    id: u16,
+    client_data: Vec<u32>,
+}
+
+fn parse_filter_pipeline(data: &[u8]) -> Result<Vec<Filter>> {
+    let mut r = Reader {
+        data,
+        pos: 0,
+        offset_size: 8,
+        length_size: 8,
+    };
+    let version = r.u8()?;
+    let count = r.u8()? as usize;
+    if version == 1 {
+        r.skip(6)?;
+    } else if version != 2 {
+        return unsupported(&format!("filter pipeline version {version}"));
+    }
+
+    let mut filters = Vec::with_capacity(count);
+    for _ in 0..count {
+        let id = r.u16()?;
+        let name_length = if version == 1 || id >= 256 {
+            r.u16()? as usize
+        } else {
+            0
+        };
+        let _flags = r.u16()?;
+        let client_count = r.u16()? as usize;
+        if version == 1 {
+            // the name is padded to a multiple of eight bytes
+            r.skip((name_length + 7) / 8 * 8)?;
+        } else {
+            r.skip(name_length)?;
+        }
+        let client_data = (0..client_count)
+            .map(|_| r.u32())
+            .collect::<Result<Vec<_>>>()?;
+        if version == 1 && client_count % 2 == 1 {
+            r.skip(4)?;
+        }
+        filters.push(Filter { id, client_data });
+    }
+
+    Ok(filters)
+}
+
+/// Storage of the raw data of a dataset
+enum Layout<'a> {
+    Compact(&'a [u8]),
+    Contiguous {
+        address: Option<u64>,
+        size: u64,
+    },
+    /// chunks indexed by a version 1 B-tree
+    Chunked {
+        btree: Option<u64>,
+        chunk_shape: Vec<u64>,
+    },
+    /// dataset consisting of a single (possibly filtered) chunk
+    SingleChunk {
+        address: Option<u64>,
+        filtered_size: Option<u64>,
+        filter_mask: u32,
+    },
+}
+
+/// Numeric dataset, converted to `f64`
+pub(crate) struct Dataset {
+    pub shape: Vec<usize>,
+    pub values: Vec<f64>,
+}
+
+/// A HDF5 file, fully loaded in memory
+pub(crate) struct File<'a> {
+    data: &'a [u8],
+    base: u64,
+    offset_size: usize,
+    length_size: usize,
+    root: u64,
+}
+
+impl<'a> File<'a> {
+    /// Parse the superblock of the file
+    pub fn open(data: &'a [u8]) -> Result<Self> {
+        // the superblock may be located at 0, 512, 1024, 2048, ...
+        let mut start = 0;
+        loop {
+            if data.len() < start + 8 {
+                return invalid("file signature not found");
+            }
+            if &data[start..start + 8] == SIGNATURE {
+                break;
+            }
+            start = if start == 0 { 512 } else { start * 2 };
+        }
+
+        let mut r = Reader {
+            data,
+            pos: start + 8,
+            offset_size: 8,
+            length_size: 8,
+        };
+        let version = r.u8()?;
+
+        let (offset_size, length_size, root) = match version {
+            0 | 1 => {
+                r.skip(4)?; // free-space, root group, reserved, shared header versions
+                let offset_size = r.u8()? as usize;
+                let length_size = r.u8()? as usize;
+                r.skip(1)?;
+                r.skip(4 + 4)?; // group K values, file consistency flags
+                if version == 1 {
+                    r.skip(4)?; // indexed storage K, reserved
+                }
+                r.offset_size = offset_size;
+                r.length_size = length_size;
+                let _base = r.offset()?;
+                let _free_space = r.offset()?;
+                let _eof = r.offset()?;
+                let _driver = r.offset()?;
+                // root group symbol table entry
+                let _name = r.offset()?;
+                let root = r.offset()?;
+                (offset_size, length_size, root)
+            }
+            2 | 3 => {
+                let offset_size = r.u8()? as usize;
+                let length_size = r.u8()? as usize;
+                r.skip(1)?; // file consistency flags
+                r.offset_size = offset_size;
+                r.length_size = length_size;
+                let _base = r.offset()?;
+                let _extension = r.offset()?;
+                let _eof = r.offset()?;
+                let root = r.offset()?;
+                (offset_size, length_size, root)
+            }
+            _ => return unsupported(&format!("superblock version {version}")),
+        };
+
+        if !(1..=8).contains(&offset_size) || !(1..=8).contains(&length_size) {
+            return invalid("invalid size of offsets or lengths");
+        }
+        let root = match root {
+            Some(root) => root,
+            None => return invalid("missing root group"),
+        };
+
+        Ok(Self {
+            data,
+            base: start as u64,
+            offset_size,
+            length_size,
+            root,
+        })
+    }
+
+    fn reader_at(&self, address: u64) -> Result<Reader<'a>> {
+        let pos = address
+            .checked_add(self.base)
+            .filter(|&pos| pos < self.data.len() as u64);
+        match pos {
+            Some(pos) => Ok(Reader {
+                data: self.data,
+                pos: pos as usize,
+                offset_size: self.offset_size,
+                length_size: self.length_size,
+            }),
+            None => invalid("address out of bounds"),
+        }
+    }
+
+    fn reader_for(&self, data: &'a [u8]) -> Reader<'a> {
+        Reader {
+            data,
+            pos: 0,
+            offset_size: self.offset_size,
+            length_size: self.length_size,
+        }
+    }
+
+    fn slice(&self, address: u64, size: u64) -> Result<&'a [u8]> {
+        let mut r = self.reader_at(address)?;
+        r.bytes(size as usize)
+    }
+
+    /// Collect all header messages of the object at the given address
+    fn messages(&self, address: u64) -> Result<Vec<Message<'a>>> {
+        let mut messages = vec![];
+        let mut r = self.reader_at(address)?;
+
+        if self.data.get(r.pos..r.pos + 4) == Some(b"OHDR") {
+            // version 2 object header
+            r.skip(4)?;
+            let version = r.u8()?;
+            if version != 2 {
+                return unsupported(&format!("object header version {version}"));
+            }
+            let flags = r.u8()?;
+            if flags & 0x20 != 0 {
+                r.skip(16)?; // times
+            }
+            if flags & 0x10 != 0 {
+                r.skip(4)?; // attribute phase change values
+            }
+            let chunk_size = r.uint(1 << (flags & 0x03))? as usize;
+            let chunk = r.bytes(chunk_size)?;
+            let mut blocks = vec![chunk];
+
+            while let Some(block) = blocks.pop() {
+                let mut r = self.reader_for(block);
+                // a message header is at least 4 bytes
+                let header_size = if flags & 0x04 != 0 { 6 } else { 4 };
+                while r.remaining() >= header_size {
+                    let kind = u16::from(r.u8()?);
+                    let size = r.u16()? as usize;
+                    let msg_flags = r.u8()?;
+                    if flags & 0x04 != 0 {
+                        r.skip(2)?;
+                    }
+                    let data = r.bytes(size)?;
+                    if kind == MSG_CONTINUATION {
+                        let mut c = self.reader_for(data);
+                        let offset = c.offset()?.ok_or("InvalidData - HDF5: bad continuation")?;
+                        let length = c.length()?;
+                        let mut b = self.reader_at(offset)?;
+                        b.signature(b"OCHK")?;
+                        // skip the checksum at the end of the block
+                        let body = b.bytes((length as usize).saturating_sub(8))?;
+                        blocks.push(body);
+                    } else {
+                        messages.push(Message {
+                            kind,
+                            flags: msg_flags,
+                            data,
+                        });
+                    }
+                }
+            }
+        } else {
+            // version 1 object header
+            let version = r.u8()?;
+            if version != 1 {
+                return unsupported(&format!("object header version {version}"));
+            }
+            r.skip(1)?;
+            let mut remaining_messages = r.u16()? as usize;
+            r.skip(4)?; // reference count
+            let header_size = r.u32()? as usize;
+            r.skip(4)?; // padding to align on 8 bytes
+            let mut blocks = vec![r.bytes(header_size)?];
+
+            while let Some(block) = blocks.pop() {
+                let mut r = self.reader_for(block);
+                while remaining_messages > 0 && r.remaining() >= 8 {
+                    let kind = r.u16()?;
+                    let size = r.u16()? as usize;
+                    let msg_flags = r.u8()?;
+                    r.skip(3)?;
+                    let data = r.bytes(size)?;
+                    remaining_messages -= 1;
+
+                    if kind == MSG_CONTINUATION {
+                        let mut c = self.reader_for(data);
+                        let offset = c.offset()?.ok_or("InvalidData - HDF5: bad continuation")?;
+                        let length = c.length()?;
+                        blocks.push(self.slice(offset, length)?);
+                    } else {
+                        messages.push(Message {
+                            kind,
+                            flags: msg_flags,
+                            data,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(messages)
+    }
+
+    /// Names and object header addresses of the members of the root group
+    pub fn root_links(&self) -> Result<Vec<(String, u64)>> {
+        self.links(self.root)
+    }
+
+    fn links(&self, address: u64) -> Result<Vec<(String, u64)>> {
+        let mut links = vec![];
+
+        for message in self.messages(address)? {
+            match message.kind {
+                MSG_SYMBOL_TABLE => {
+                    let mut r = self.reader_for(message.data);
+                    let btree = r.offset()?;
+                    let heap = r.offset()?;
+                    if let (Some(btree), Some(heap)) = (btree, heap) {
+                        self.symbol_table_links(btree, heap, &mut links)?;
+                    }
+                }
+                MSG_LINK => {
+                    if let Some(link) = self.parse_link(message.data)? {
+                        links.push(link);
+                    }
+                }
+                MSG_LINK_INFO => {
+                    let mut r = self.reader_for(message.data);
+                    let _version = r.u8()?;
+                    let flags = r.u8()?;
+                    if flags & 0x01 != 0 {
+                        r.skip(8)?;
+                    }
+                    let heap = r.offset()?;
+                    let name_index = r.offset()?;
+                    if let (Some(heap), Some(name_index)) = (heap, name_index) {
+                        self.dense_links(heap, name_index, &mut links)?;
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        Ok(links)
+    }
+
+    /// Read the links of an old style group
+    fn symbol_table_links(
+        &self,
+        btree: u64,
+        heap: u64,
+        links: &mut Vec<(String, u64)>,
+    ) -> Result<()> {
+        let mut r = self.reader_at(heap)?;
+        r.signature(b"HEAP")?;
+        r.skip(4)?;
+        let heap_size = r.length()?;
+        let _free_list = r.length()?;
+        let heap_data = r
+            .offset()?
+            .ok_or("InvalidData - HDF5: missing local heap")?;
+        let heap_data = self.slice(heap_data, heap_size)?;
+
+        let mut nodes = vec![btree];
+        while let Some(node) = nodes.pop() {
+            let mut r = self.reader_at(node)?;
+            r.signature(b"TREE")?;
+            let node_type = r.u8()?;
+            if node_type != 0 {
+                return invalid("expected group B-tree node");
+            }
+            let level = r.u8()?;
+            let entries = r.u16()? as usize;
+            r.offset()?;
+            r.offset()?;
+
+            for _ in 0..entries {
+                r.length()?; // key
+                let child = r
+                    .offset()?
+                    .ok_or("InvalidData - HDF5: missing B-tree child")?;
+                if level > 0 {
+                    nodes.push(child);
+                    continue;
+                }
+
+                let mut s = self.reader_at(child)?;
+                s.signature(b"SNOD")?;
+                s.skip(2)?;
+                let symbols = s.u16()? as usize;
+                for _ in 0..symbols {
+                    let name_offset = s.offset()?.unwrap_or(0) as usize;
+                    let object = s.offset()?;
+                    s.skip(4 + 4 + 16)?;
+
+                    let name = heap_data
+                        .get(name_offset..)
+                        .and_then(|n| n.split(|&b| b == 0).next())
+                        .ok_or("InvalidData - HDF5: bad link name")?;
+                    if let Some(object) = object {
+                        links.push((String::from_utf8_lossy(name).into_owned(), object));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parse a link message, returns `None` for soft and external links
+    fn parse_link(&self, data: &[u8]) -> Result<Option<(String, u64)>> {
+        let mut r = self.reader_for(data);
+        let version = r.u8()?;
+        if version != 1 {
+            return unsupported(&format!("link message version {version}"));
+        }
+        let flags = r.u8()?;
+        let link_type = if flags & 0x08 != 0 { r.u8()? } else { 0 };
+        if flags & 0x04 != 0 {
+            r.skip(8)?;
+        }
+        if flags & 0x10 != 0 {
+            r.skip(1)?;
+        }
+        let name_length = r.uint(1 << (flags & 0x03))? as usize;
+        let name = String::from_utf8_lossy(r.bytes(name_length)?).into_owned();
+
+        if link_type != 0 {
+            return Ok(None);
+        }
+        Ok(r.offset()?.map(|address| (name, address)))
+    }
+
+    /// Read the links of a new style group with dense link storage
+    fn dense_links(
+        &self,
+        heap: u64,
+        name_index: u64,
+        links: &mut Vec<(String, u64)>,
+    ) -> Result<()> {
+        let heap = FractalHeap::parse(self, heap)?;
+
+        let mut r = self.reader_at(name_index)?;
+        r.signature(b"BTHD")?;
+        r.skip(1)?;
+        let record_type = r.u8()?;
+        let node_size = r.u32()? as usize;
+        let record_size = r.u16()? as usize;
+        let depth = r.u16()? as usize;
+        r.skip(2)?;
+        let root = r.offset()?;
+        let root_records = r.u16()? as usize;
+
+        let id_offset = match record_type {
+            5 => 4, // link name: hash followed by heap ID
+            6 => 8, // creation order followed by heap ID
+            _ => return unsupported(&format!("B-tree record type {record_type} for links")),
+        };
+
+        let root = match root {
+            Some(root) => root,
+            None => return Ok(()), // empty group
+        };
+
+        // number of bytes used to store the number of records in child nodes, per depth
+        let mut max_records = vec![(node_size.saturating_sub(10) / record_size.max(1)) as u64];
+        let mut cumulative_records = vec![max_records[0]];
+        let mut pointer_sizes = vec![0usize];
+        for d in 1..=depth {
+            let pointer_size = self.offset_size
+                + bytes_needed(max_records[d - 1])
+                + if d > 1 {
+                    bytes_needed(cumulative_records[d - 1])
+                } else {
+                    0
+                };
+            let max =
+                (node_size.saturating_sub(10 + pointer_size) / (record_size + pointer_size)) as u64;
+            cumulative_records.push((max + 1) * cumulative_records[d - 1] + max);
+            max_records.push(max);
+            pointer_sizes.push(pointer_size);
+        }
+
+        let mut nodes = vec![(root, root_records, depth)];
+        while let Some((address, records, depth)) = nodes.pop() {
+            let mut r = self.reader_at(address)?;
+            r.signature(if depth == 0 { b"BTLF" } else { b"BTIN" })?;
+            r.skip(2)?;
+
+            for _ in 0..records {
+                let record = r.bytes(record_size)?;
+                let id = record
+                    .get(id_offset..)
+                    .ok_or("InvalidData - HDF5: bad B-tree record")?;
+                let object = heap.object(self, id)?;
+                if let Some(link) = self.parse_link(&object)? {
+                    links.push(link);
+                }
+            }
+
+            if depth > 0 {
+                for _ in 0..=records {
+                    let child = r
+                        .offset()?
+                        .ok_or("InvalidData - HDF5: missing B-tree child")?;
+                    let child_records = r.uint(bytes_needed(max_records[depth - 1]))? as usize;
+                    if depth > 1 {
+                        r.uint(bytes_needed(cumulative_records[depth - 1]))?;
+                    }
+                    nodes.push((child, child_records, depth - 1));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn datatype(messages: &[Message<'_>]) -> Result<Datatype> {
+        let message = messages
+            .iter()
+            .find(|m| m.kind == MSG_DATATYPE)
+            .ok_or("InvalidData - HDF5: missing datatype")?;
+        if message.flags & 0x02 != 0 {
+            return unsupported("shared datatypes");
+        }
+        Datatype::parse(message.data)
+    }
+
+    fn dataspace(&self, messages: &[Message<'_>]) -> Result<Vec<u64>> {
+        let message = messages
+            .iter()
+            .find(|m| m.kind == MSG_DATASPACE)
+            .ok_or("InvalidData - HDF5: missing dataspace")?;
+        parse_dataspace(message.data, self.length_size)
+    }
+
+    fn layout(&self, messages: &[Message<'a>]) -> Result<Layout<'a>> {
+        let message = messages
+            .iter()
+            .find(|m| m.kind == MSG_LAYOUT)
+            .ok_or("InvalidData - HDF5: missing data layout")?;
+        let mut r = self.reader_for(message.data);
+        let version = r.u8()?;
+        if !(3..=4).contains(&version) {
+            return unsupported(&format!("data layout version {version}"));
+        }
+
+        let layout = match r.u8()? {
+            0 => {
+                let size = r.u16()? as usize;
+                Layout::Compact(r.bytes(size)?)
+            }
+            1 => Layout::Contiguous {
+                address: r.offset()?,
+                size: r.length()?,
+            },
+            2 if version == 3 => {
+                let rank = r.u8()? as usize;
+                let btree = r.offset()?;
+                let mut chunk_shape = (0..rank)
+                    .map(|_| r.u32().map(u64::from))
+                    .collect::<Result<Vec<_>>>()?;
+                chunk_shape.pop(); // the last dimension is the element size
+                Layout::Chunked { btree, chunk_shape }
+            }
+            2 => {
+                let flags = r.u8()?;
+                let rank = r.u8()? as usize;
+                let encoded_size = r.u8()? as usize;
+                r.skip(rank * encoded_size)?; // chunk dimensions
+                match r.u8()? {
+                    1 => {
+                        let (filtered_size, filter_mask) = if flags & 0x02 != 0 {
+                            (Some(r.length()?), r.u32()?)
+                        } else {
+                            (None, 0)
+                        };
+                        Layout::SingleChunk {
+                            address: r.offset()?,
+                            filtered_size,
+                            filter_mask,
+                        }
+                    }
+                    index => return unsupported(&format!("chunk index type {index}")),
+                }
+            }
+            class => return unsupported(&format!("data layout class {class}")),
+        };
+
+        Ok(layout)
+    }
+
+    /// Read the full raw data of a dataset, with the filters reverted
+    fn raw_data(
+        &self,
+        layout: &Layout<'a>,
+        filters: &[Filter],
+        shape: &[u64],
+        element_size: usize,
+    ) -> Result<Vec<u8>> {
+        // the data is stored in the file, possibly compressed: do not trust the dataspace to
+        // allocate more than the file can hold
+        let file_size = self.data.len() as u64;
+        let max_size = if filters.iter().any(|f| f.id == FILTER_DEFLATE) {
+            file_size.saturating_mul(MAX_DEFLATE_RATIO)
+        } else {
+            file_size
+        };
+        let total_size = match data_size(shape, element_size) {
+            Some(size) if size <= max_size => size as usize,
+            _ => return invalid("dataspace is larger than the file"),
+        };
+
+        match layout {
+            Layout::Compact(data) => {
+                if total_size > data.len() {
+                    return invalid("dataspace is larger than the compact data");
+                }
+                Ok(data.to_vec())
+            }
+            Layout::Contiguous { address, size } => match address {
+                Some(_) if total_size as u64 > *size => {
+                    invalid("dataspace is larger than the contiguous data")
+                }
+                Some(address) => Ok(self.slice(*address, *size)?.to_vec()),
+                // never written, use the default fill value
+                None => Ok(vec![0; total_size]),
+            },
+            Layout::SingleChunk {
+                address,
+                filtered_size,
+                filter_mask,
+                ..
+            } => match address {
+                Some(address) => {
+                    let size = filtered_size.unwrap_or(total_size as u64);
+                    let chunk = self.slice(*address, size)?;
+                    if filtered_size.is_some() {
+                        apply_filters(chunk, filters, *filter_mask)
+                    } else {
+                        Ok(chunk.to_vec())
+                    }
+                }
+                None => Ok(vec![0; total_size]),
+            },
+            Layout::Chunked { btree, chunk_shape } => {
+                let mut output = vec![0; total_size];
+                if let Some(btree) = btree {
+                    self.read_chunks(
+                        *btree,
+                        filters,
+                        shape,
+                        chunk_shape,
+                        element_size,
+                        &mut output,
+                    )?;
+                }
+                Ok(output)
+            }
+        }
+    }
+
+    fn read_chunks(
+        &self,
+        btree: u64,
+        filters: &[Filter],
+        shape: &[u64],
+        chunk_shape: &[u64],
+        element_size: usize,
+        output: &mut [u8],
+    ) -> Result<()> {
+        let rank = chunk_shape.len();
+        if rank != shape.len() {
+            return invalid("chunk rank does not match the dataspace");
+        }
+
+        let mut nodes = vec![btree];
+        while let Some(node) = nodes.pop() {
+            let mut r = self.reader_at(node)?;
+            r.signature(b"TREE")?;
+            if r.u8()? != 1 {
+                return invalid("expected chunk B-tree node");
+            }
+            let level = r.u8()?;
+            let entries = r.u16()? as usize;
+            r.offset()?;
+            r.offset()?;
+
+            for _ in 0..entries {
+                let chunk_size = r.u32()?;
+                let filter_mask = r.u32()?;
+                let offsets = (0..=rank).map(|_| r.uint(8)).collect::<Result<Vec<_>>>()?;
+                let child = r
+                    .offset()?
+                    .ok_or("InvalidData - HDF5: missing chunk address")?;
+
+                if level > 0 {
+                    nodes.push(child);
+                    continue;
+                }
+
+                let chunk = self.slice(child, u64::from(chunk_size))?;
+                let chunk = apply_filters(chunk, filters, filter_mask)?;
+                copy_chunk(
+                    &chunk,
+                    &offsets[..rank],
+                    chunk_shape,
+                    shape,
+                    element_size,
+                    output,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read a numeric dataset, converted to `f64`
+    pub fn read_dataset(&self, address: u64) -> Result<Dataset> {
+        let messages = self.messages(address)?;
+        let datatype = Self::datatype(&messages)?;
+        let shape = self.dataspace(&messages)?;
+        let layout = self.layout(&messages)?;
+        let filters = match messages.iter().find(|m| m.kind == MSG_FILTER_PIPELINE) {
+            Some(message) => parse_filter_pipeline(message.data)?,
+            None => vec![],
+        };
+
+        let raw = self.raw_data(&layout, &filters, &shape, datatype.size())?;
+        let element_count = data_size(&shape, 1)
+            .and_then(|count| usize::try_from(count).ok())
+            .ok_or("InvalidData - HDF5: dataspace is too large")?;
+        let mut values = datatype.to_f64(&raw)?;
+        if values.len() < element_count {
+            return invalid("dataset is smaller than its dataspace");
+        }
+        values.truncate(element_count);
+
+        Ok(Dataset {
+            shape: shape.iter().map(|&d| d as usize).collect(),
+            values,
+        })
+    }
+
+    /// Read a string attribute of an object, only compact attribute storage is supported
+    pub fn string_attribute(&self, address: u64, name: &str) -> Result<Option<String>> {
+        for message in self.messages(address)? {
+            if message.kind != MSG_ATTRIBUTE {
+                continue;
+            }
+
+            let mut r = self.reader_for(message.data);
+            let version = r.u8()?;
+            r.skip(1)?;
+            let name_size = r.u16()? as usize;
+            let datatype_size = r.u16()? as usize;
+            let dataspace_size = r.u16()? as usize;
+            if version == 3 {
+                r.skip(1)?; // character set
+            }
+            let pad = |n: usize| if version == 1 { (n + 7) / 8 * 8 } else { n };
+
+            let attr_name = r.bytes(pad(name_size))?;
+            let attr_name = attr_name.split(|&b| b == 0).next().unwrap_or_default();
+            if attr_name != name.as_bytes() {
+                continue;
+            }
+
+            let datatype = Datatype::parse(r.bytes(pad(datatype_size))?)?;
+            let shape = parse_dataspace(r.bytes(pad(dataspace_size))?, self.length_size)?;
+            let count = shape.iter().product::<u64>() as usize;
+
+            return match datatype {
+                Datatype::String { size } => {
+                    let value = r.bytes((size * count).min(r.remaining()))?;
+                    let value = value.split(|&b| b == 0).next().unwrap_or_default();
+                    Ok(Some(String::from_utf8_lossy(value).trim_end().to_string()))
+                }
+                _ => Ok(None),
+            };
+        }
+
+        Ok(None)
+    }
+}
+
+/// Fractal heap, used for dense link storage
+struct FractalHeap {
+    heap_id_length: usize,
+    table_width: usize,
+    starting_block_size: u64,
+    max_direct_block_size: u64,
+    max_heap_size_bits: u16,
+    root_block: Option<u64>,
+    root_rows: usize,
+    has_checksum: bool,
+    filtered: bool,
+}
+
+impl FractalHeap {
+    fn parse(file: &File<'_>, address: u64) -> Result<Self> {
+        let mut r = file.reader_at(address)?;
+        r.signature(b"FRHP")?;
+        r.skip(1)?;
+        let heap_id_length = r.u16()? as usize;
+        let filter_length = r.u16()?;
+        let flags = r.u8()?;
+        r.skip(4)?; // max size of managed objects
+        r.length()?; // next huge object id
+        r.offset()?; // huge objects B-tree
+        r.length()?; // free space
+        r.offset()?; // free space manager
+        r.length()?; // managed space
+        r.length()?; // allocated managed space
+        r.length()?; // direct block allocation iterator
+        r.length()?; // number of managed objects
+        r.length()?; // size of huge objects
+        r.length()?; // number of huge objects
+        r.length()?; // size of tiny objects
+        r.length()?; // number of tiny objects
+        let table_width = r.u16()? as usize;
+        let starting_block_size = r.length()?;
+        let max_direct_block_size = r.length()?;
+        let max_heap_size_bits = r.u16()?;
+        r.u16()?; // starting number of rows in root indirect block
+        let root_block = r.offset()?;
+        let root_rows = r.u16()? as usize;
+
+        if starting_block_size == 0 || table_width == 0 {
+            return invalid("invalid fractal heap doubling table");
+        }
+
+        Ok(Self {
+            heap_id_length,
+            table_width,
+            starting_block_size,
+            max_direct_block_size,
+            max_heap_size_bits,
+            root_block,
+            root_rows,
+            has_checksum: flags & 0x02 != 0,
+            filtered: filter_length > 0,
+        })
+    }
+
+    fn offset_bytes(&self) -> usize {
+        (usize::from(self.max_heap_size_bits) + 7) / 8
+    }
+
+    fn row_block_size(&self, row: usize) -> u64 {
+        if row == 0 {
+            self.starting_block_size
+        } else {
+            self.starting_block_size << (row - 1)
+        }
+    }
+
+    fn max_direct_rows(&self) -> usize {
+        let ratio = self.max_direct_block_size / self.starting_block_size;
+        (63 - ratio.max(1).leading_zeros() as usize) + 2
+    }
+
+    /// Retrieve the object with the given heap ID
+    fn object(&self, file: &File<'_>, id: &[u8]) -> Result<Vec<u8>> {
+        let id = &id[..self.heap_id_length.min(id.len())];
+        let kind = (id.first().copied().unwrap_or(0) >> 4) & 0x03;
+
+        match kind {
+            0 => {
+                let mut r = file.reader_for(&id[1..]);
+                let offset_bytes = self.offset_bytes();
+                let offset = r.uint(offset_bytes)?;
+                let length_bytes = (id.len() - 1).saturating_sub(offset_bytes).min(8);
+                let length = r.uint(length_bytes)?;
+                self.managed_object(file, offset, length)
+            }
+            2 => {
+                let length = (id[0] & 0x0f) as usize + 1;
+                id.get(1..1 + length)
+                    .map(<[u8]>::to_vec)
+                    .ok_or_else(|| "InvalidData - HDF5: bad tiny object".into())
+            }
+            _ => unsupported("huge fractal heap objects"),
+        }
+    }
+
+    fn managed_object(&self, file: &File<'_>, offset: u64, length: u64) -> Result<Vec<u8>> {
+        if self.filtered {
+            return unsupported("filtered fractal heaps");
+        }
+        let root = self
+            .root_block
+            .ok_or("InvalidData - HDF5: empty fractal heap")?;
+
+        // find the direct block containing the object
+        let (block, block_offset) = if self.root_rows == 0 {
+            (root, 0)
+        } else {
+            self.find_direct_block(file, root, 0, self.root_rows, offset)?
+        };
+
+        let mut r = file.reader_at(block)?;
+        r.signature(b"FHDB")?;
+        let _ = r.u8()?;
+        let _ = r.offset()?;
+        let stored_offset = r.uint(self.offset_bytes())?;
+        debug_assert_eq!(stored_offset, block_offset);
+        let _ = self.has_checksum;
+
+        let start = offset
+            .checked_sub(block_offset)
+            .ok_or("InvalidData - HDF5: bad fractal heap offset")?;
+        Ok(file.slice(block + start, length)?.to_vec())
+    }
+
+    fn find_direct_block(
+        &self,
+        file: &File<'_>,
+        address: u64,
+        base: u64,
+        rows: usize,
+        offset: u64,
+    ) -> Result<(u64, u64)> {
+        let mut r = file.reader_at(address)?;
+        r.signature(b"FHIB")?;
+        r.skip(1)?;
+        r.offset()?;
+        r.uint(self.offset_bytes())?;
+
+        let mut block_offset = base;
+        for row in 0..rows {
+            let size = self.row_block_size(row);
+            let is_direct = row < self.max_direct_rows();
+            for _ in 0..self.table_width {
+                let child = r.offset()?;
+                if offset < block_offset + size {
+                    let child = child.ok_or("InvalidData - HDF5: missing fractal heap block")?;
+                    if is_direct {
+                        return Ok((child, block_offset));
+                    }
+                    let child_rows = (63 - size.leading_zeros() as usize)
+                        - (63
+                            - (self.starting_block_size * self.table_width as u64).leading_zeros()
+                                as usize)
+                        + 1;
+                    return self.find_direct_block(file, child, block_offset, child_rows, offset);
+                }
+                block_offset += size;
+            }
+        }
+
+        invalid("fractal heap offset out of range")
+    }
+}
+
+/// Revert the filter pipeline on a chunk of data
+fn apply_filters(data: &[u8], filters: &[Filter], mask: u32) -> Result<Vec<u8>> {
+    let mut data = data.to_vec();
+
+    for (i, filter) in filters.iter().enumerate().rev() {
+        if mask & (1 << i) != 0 {
+            continue; // filter was not applied to this chunk
+        }
+
+        data = match filter.id {
+            FILTER_DEFLATE => zlib_decompress(&data)?,
+            FILTER_SHUFFLE => {
+                let size = filter.client_data.first().copied().unwrap_or(1) as usize;
+                unshuffle(&data, size)
+            }
+            FILTER_FLETCHER32 => {
+                let len = data.len().saturating_sub(4);
+                data.truncate(len);
+                data
+            }
+            id => return unsupported(&format!("filter {id}")),
+        };
+    }
+
+    Ok(data)
+}
+
+/// Revert the byte shuffle filter
+fn unshuffle(data: &[u8], element_size: usize) -> Vec<u8> {
+    if element_size <= 1 {
+        return data.to_vec();
+    }
+
+    let count = data.len() / element_size;
+    let mut output = vec![0; data.len()];
+    for byte in 0..element_size {
+        for i in 0..count {
+            output[i * element_size + byte] = data[byte * count + i];
+        }
+    }
+    // leftover bytes are not shuffled
+    let tail = count * element_size;
+    output[tail..].copy_from_slice(&data[tail..]);
+
+    output
+}
+
+/// Copy a chunk into the dataset buffer, clipping at the dataset edges
+fn copy_chunk(
+    chunk: &[u8],
+    chunk_offset: &[u64],
+    chunk_shape: &[u64],
+    shape: &[u64],
+    element_size: usize,
+    output: &mut [u8],
+) -> Result<()> {
+    let rank = shape.len();
+    match data_size(chunk_shape, element_size) {
+        Some(size) if size <= chunk.len() as u64 => (),
+        _ => return invalid("chunk is smaller than the chunk shape"),
+    }
+    let chunk_elements = chunk_shape.iter().product::<u64>() as usize;
+    if rank == 0 {
+        let n = element_size.min(output.len());
+        output[..n].copy_from_slice(&chunk[..n]);
+        return Ok(());
+    }
+
+    // copy contiguous runs along the last dimension
+    let last = rank - 1;
+    let run = chunk_shape[last].min(shape[last].saturating_sub(chunk_offset[last])) as usize;
+    let rows = chunk_elements / chunk_shape[last].max(1) as usize;
+
+    let mut index = vec![0u64; last];
+    for row in 0..rows {
+        // position of this row in the chunk, in number of elements
+        let chunk_pos = row * chunk_shape[last] as usize;
+
+        // position of this row in the dataset
+        let inside = index
+            .iter()
+            .enumerate()
+            .all(|(d, &i)| chunk_offset[d] + i < shape[d]);
+        if inside && run > 0 {
+            let mut pos = 0u64;
+            for d in 0..last {
+                pos = pos * shape[d] + chunk_offset[d] + index[d];
+            }
+            pos = pos * shape[last] + chunk_offset[last];
+
+            let dst = pos as usize * element_size;
+            let src = chunk_pos * element_size;
+            let len = run * element_size;
+            output
+                .get_mut(dst..dst + len)
+                .ok_or("InvalidData - HDF5: chunk out of bounds")?
+                .copy_from_slice(&chunk[src..src + len]);
+        }
+
+        // increment the row index
+        for d in (0..last).rev() {
+            index[d] += 1;
+            if index[d] < chunk_shape[d] {
+                break;
+            }
+            index[d] = 0;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Numeric dataset used to build test files
+    pub(crate) struct TestDataset {
+        pub name: &'static str,
+        pub shape: Vec<u64>,
+        pub values: Vec<f64>,
+        /// store in two chunks along the first dimension, with the shuffle and deflate filters
+        pub chunked: bool,
+        pub attributes: Vec<(&'static str, &'static str)>,
+    }
+
+    const UNDEFINED: u64 = u64::MAX;
+
+    fn put(buf: &mut Vec<u8>, value: u64, size: usize) {
+        buf.extend_from_slice(&value.to_le_bytes()[..size]);
+    }
+
+    fn pad8(mut data: Vec<u8>) -> Vec<u8> {
+        data.resize((data.len() + 7) / 8 * 8, 0);
+        data
+    }
+
+    fn zlib_stored(data: &[u8]) -> Vec<u8> {
+        let mut out = vec![0x78, 0x01];
+        let chunks: Vec<_> = data.chunks(65535).collect();
+        for (i, chunk) in chunks.iter().enumerate() {
+            out.push(u8::from(i == chunks.len() - 1));
+            put(&mut out, chunk.len() as u64, 2);
+            put(&mut out, !(chunk.len() as u16) as u64, 2);
+            out.extend_from_slice(chunk);
+        }
+        let (mut a, mut b) = (1u32, 0u32);
+        data.iter().for_each(|&x| {
+            a = (a + u32::from(x)) % 65521;
+            b = (b + a) % 65521;
+        });
+        out.extend_from_slice(&((b << 16) | a).to_be_bytes());
+        out
+    }
+
+    fn shuffle(data: &[u8], size: usize) -> Vec<u8> {
+        let count = data.len() / size;
+        let mut out = vec![0; data.len()];
+        for i in 0..count {
+            for byte in 0..size {
+                out[byte * count + i] = data[i * size + byte];
+            }
+        }
+        out
+    }
+
+    fn dataspace(shape: &[u64]) -> Vec<u8> {
+        let mut m = vec![1, shape.len() as u8, 0, 0, 0, 0, 0, 0];
+        shape.iter().for_each(|&d| put(&mut m, d, 8));
+        m
+    }
+
+    fn string_attribute(name: &str, value: &str) -> Vec<u8> {
+        let mut name = name.as_bytes().to_vec();
+        name.push(0);
+        let mut datatype = vec![0x13, 0, 0, 0];
+        put(&mut datatype, value.len() as u64, 4);
+        let space = vec![1, 0, 0, 0, 0, 0, 0, 0]; // scalar
+
+        let mut m = vec![1, 0];
+        put(&mut m, name.len() as u64, 2);
+        put(&mut m, datatype.len() as u64, 2);
+        put(&mut m, space.len() as u64, 2);
+        m.extend(pad8(name));
+        m.extend(pad8(datatype));
+        m.extend(pad8(space));
+        m.extend_from_slice(value.as_bytes());
+        m
+    }
+
+    /// Header messages (type and data) of a dataset, the raw data is appended to `file`
+    fn dataset_messages(file: &mut Vec<u8>, dataset: &TestDataset) -> Vec<(u16, Vec<u8>)> {
+        let mut messages = vec![];
+        messages.push((MSG_DATASPACE, dataspace(&dataset.shape)));
+
+        // IEEE 754 double, little endian
+        let mut datatype = vec![0x11, 0x20, 0x3f, 0x00];
+        put(&mut datatype, 8, 4);
+        datatype.extend_from_slice(&[0, 0, 64, 0, 52, 11, 0, 52]);
+        put(&mut datatype, 1023, 4);
+        messages.push((MSG_DATATYPE, datatype));
+
+        let raw: Vec<u8> = dataset
+            .values
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+
+        if !dataset.chunked {
+            let address = file.len() as u64;
+            file.extend_from_slice(&raw);
+            let mut layout = vec![3, 1];
+            put(&mut layout, address, 8);
+            put(&mut layout, raw.len() as u64, 8);
+            messages.push((MSG_LAYOUT, layout));
+            return messages;
+        }
+
+        let rank = dataset.shape.len();
+        let row_size = dataset.shape[1..].iter().product::<u64>() as usize * 8;
+        let rows = dataset.shape[0] as usize;
+        let chunk_rows = (rows + 1) / 2;
+
+        let mut chunks = vec![];
+        for (i, start) in [0, chunk_rows].into_iter().enumerate() {
+            // edge chunks are padded to the full chunk size
+            let mut chunk =
+                raw[start * row_size..((start + chunk_rows) * row_size).min(raw.len())].to_vec();
+            chunk.resize(chunk_rows * row_size, 0);
+            let chunk = zlib_stored(&shuffle(&chunk, 8));
+            chunks.push((
+                file.len() as u64,
+                chunk.len() as u64,
+                (i * chunk_rows) as u64,
+            ));
+            file.extend(chunk);
+        }
+
+        let btree = file.len() as u64;
+        file.extend_from_slice(b"TREE");
+        file.extend_from_slice(&[1, 0]);
+        put(file, chunks.len() as u64, 2);
+        put(file, UNDEFINED, 8);
+        put(file, UNDEFINED, 8);
+        let key = |file: &mut Vec<u8>, size: u64, row: u64| {
+            put(file, size, 4);
+            put(file, 0, 4);
+            put(file, row, 8);
+            (0..rank).for_each(|_| put(file, 0, 8));
+        };
+        for &(address, size, row) in &chunks {
+            key(file, size, row);
+            put(file, address, 8);
+        }
+        key(file, 0, dataset.shape[0]);
+
+        let mut layout = vec![3, 2, rank as u8 + 1];
+        put(&mut layout, btree, 8);
+        put(&mut layout, chunk_rows as u64, 4);
+        dataset.shape[1..]
+            .iter()
+            .for_each(|&d| put(&mut layout, d, 4));
+        put(&mut layout, 8, 4);
+        messages.push((MSG_LAYOUT, layout));
+
+        let mut pipeline = vec![1, 2, 0, 0, 0, 0, 0, 0];
+        for (id, client_data) in [(FILTER_SHUFFLE, 8), (FILTER_DEFLATE, 6)] {
+            put(&mut pipeline, u64::from(id), 2);
+            put(&mut pipeline, 0, 2); // name length
+            put(&mut pipeline, 0, 2); // flags
+            put(&mut pipeline, 1, 2); // number of client values
+            put(&mut pipeline, client_data, 4);
+            put(&mut pipeline, 0, 4); // padding
+        }
+        messages.push((MSG_FILTER_PIPELINE, pipeline));
+
+        messages
+    }
+
+    fn object_header_v1(file: &mut Vec<u8>, messages: Vec<(u16, Vec<u8>)>) -> u64 {
+        let mut body = vec![];
+        for (kind, data) in &messages {
+            let data = pad8(data.clone());
+            put(&mut body, u64::from(*kind), 2);
+            put(&mut body, data.len() as u64, 2);
+            body.extend_from_slice(&[0; 4]);
+            body.extend(data);
+        }
+
+        let address = file.len() as u64;
+        file.extend_from_slice(&[1, 0]);
+        put(file, messages.len() as u64, 2);
+        put(file, 1, 4);
+        put(file, body.len() as u64, 4);
+        put(file, 0, 4);
+        file.extend(body);
+        address
+    }
+
+    fn object_header_v2(file: &mut Vec<u8>, messages: Vec<(u16, Vec<u8>)>) -> u64 {
+        let mut body = vec![];
+        for (kind, data) in &messages {
+            body.push(*kind as u8);
+            put(&mut body, data.len() as u64, 2);
+            body.push(0);
+            body.extend_from_slice(data);
+        }
+
+        let address = file.len() as u64;
+        file.extend_from_slice(b"OHDR");
+        file.extend_from_slice(&[2, 0x02]);
+        put(file, body.len() as u64, 4);
+        file.extend(body);
+        put(file, 0, 4); // checksum
+        address
+    }
+
+    /// Write a HDF5 file with the given datasets in the root group
+    ///
+    /// Version 0 files use an old style root group (symbol table), version 2 files store the
+    /// links in the root object header.
+    pub(crate) fn write_file(datasets: &[TestDataset], version: u8) -> Vec<u8> {
+        let superblock_size = if version == 0 { 96 } else { 48 };
+        let mut file = vec![0; superblock_size];
+
+        let objects: Vec<u64> = datasets
+            .iter()
+            .map(|dataset| {
+                let mut messages = dataset_messages(&mut file, dataset);
+                dataset
+                    .attributes
+                    .iter()
+                    .for_each(|(k, v)| messages.push((MSG_ATTRIBUTE, string_attribute(k, v))));
+                if version == 0 {
+                    object_header_v1(&mut file, messages)
+                } else {
+                    object_header_v2(&mut file, messages)
+                }
+            })
+            .collect();
+
+        let root = if version == 0 {
+            // local heap with the link names
+            let mut names = vec![0; 8];
+            let offsets: Vec<u64> = datasets
+                .iter()
+                .map(|dataset| {
+                    let offset = names.len() as u64;
+                    names.extend_from_slice(dataset.name.as_bytes());
+                    names.push(0);
+                    names = pad8(std::mem::take(&mut names));
+                    offset
+                })
+                .collect();
+            let heap_data = file.len() as u64;
+            file.extend_from_slice(&names);
+            let heap = file.len() as u64;
+            file.extend_from_slice(b"HEAP");
+            file.extend_from_slice(&[0; 4]);
+            put(&mut file, names.len() as u64, 8);
+            put(&mut file, UNDEFINED, 8);
+            put(&mut file, heap_data, 8);
+
+            let snod = file.len() as u64;
+            file.extend_from_slice(b"SNOD");
+            file.extend_from_slice(&[1, 0]);
+            put(&mut file, datasets.len() as u64, 2);
+            for (&offset, &object) in offsets.iter().zip(&objects) {
+                put(&mut file, offset, 8);
+                put(&mut file, object, 8);
+                file.extend_from_slice(&[0; 24]);
+            }
+
+            let btree = file.len() as u64;
+            file.extend_from_slice(b"TREE");
+            file.extend_from_slice(&[0, 0]);
+            put(&mut file, 1, 2);
+            put(&mut file, UNDEFINED, 8);
+            put(&mut file, UNDEFINED, 8);
+            put(&mut file, 0, 8);
+            put(&mut file, snod, 8);
+            put(&mut file, offsets.last().copied().unwrap_or(0), 8);
+
+            let mut symbol_table = vec![];
+            put(&mut symbol_table, btree, 8);
+            put(&mut symbol_table, heap, 8);
+            object_header_v1(&mut file, vec![(MSG_SYMBOL_TABLE, symbol_table)])
+        } else {
+            let links = datasets
+                .iter()
+                .zip(&objects)
+                .map(|(dataset, &object)| {
+                    let mut link = vec![1, 0, dataset.name.len() as u8];
+                    link.extend_from_slice(dataset.name.as_bytes());
+                    put(&mut link, object, 8);
+                    (MSG_LINK, link)
+                })
+                .collect();
+            object_header_v2(&mut file, links)
+        };
+
+        let eof = file.len() as u64;
+        let mut superblock = SIGNATURE.to_vec();
+        if version == 0 {
+            superblock.extend_from_slice(&[0, 0, 0, 0, 0, 8, 8, 0]);
+            put(&mut superblock, 4, 2);
+            put(&mut superblock, 16, 2);
+            put(&mut superblock, 0, 4);
+            put(&mut superblock, 0, 8);
+            put(&mut superblock, UNDEFINED, 8);
+            put(&mut superblock, eof, 8);
+            put(&mut superblock, UNDEFINED, 8);
+            put(&mut superblock, 0, 8);
+            put(&mut superblock, root, 8);
+            superblock.extend_from_slice(&[0; 24]);
+        } else {
+            superblock.extend_from_slice(&[2, 8, 8, 0]);
+            put(&mut superblock, 0, 8);
+            put(&mut superblock, UNDEFINED, 8);
+            put(&mut superblock, eof, 8);
+            put(&mut superblock, root, 8);
+            put(&mut superblock, 0, 4);
+        }
+        assert_eq!(superblock.len(), superblock_size);
+        file[..superblock_size].copy_from_slice(&superblock);
+
+        file
+    }
+
+    fn test_datasets() -> Vec<TestDataset> {
+        vec![
+            TestDataset {
+                name: "Scalar",
+                shape: vec![1],
+                values: vec![48000.],
+                chunked: false,
+                attributes: vec![("Units", "hertz")],
+            },
+            TestDataset {
+                name: "Matrix",
+                shape: vec![5, 2, 3],
+                values: (0..30).map(|i| i as f64 * 0.5 - 3.).collect(),
+                chunked: true,
+                attributes: vec![],
+            },
+        ]
+    }
+
+    #[test]
+    fn test_read_datasets() {
+        for version in [0, 2] {
+            let data = write_file(&test_datasets(), version);
+            let file = File::open(&data).unwrap();
+
+            let links = file.root_links().unwrap();
+            let names: Vec<_> = links.iter().map(|(name, _)| name.as_str()).collect();
+            assert_eq!(names, ["Scalar", "Matrix"]);
+
+            let scalar = file.read_dataset(links[0].1).unwrap();
+            assert_eq!(scalar.shape, [1]);
+            assert_eq!(scalar.values, [48000.]);
+            let units = file.string_attribute(links[0].1, "Units").unwrap();
+            assert_eq!(units.as_deref(), Some("hertz"));
+            assert!(file.string_attribute(links[0].1, "Type").unwrap().is_none());
+
+            let matrix = file.read_dataset(links[1].1).unwrap();
+            assert_eq!(matrix.shape, [5, 2, 3]);
+            assert_eq!(matrix.values, test_datasets()[1].values);
+        }
+    }
+
+    #[test]
+    fn test_invalid_file() {
+        assert!(File::open(b"not a HDF5 file").is_err());
+
+        let mut data = write_file(&test_datasets(), 0);
+        data.truncate(data.len() - 100);
+        let result = File::open(&data).and_then(|file| {
+            file.root_links()?
+                .iter()
+                .try_for_each(|(_, address)| file.read_dataset(*address).map(|_| ()))
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_corrupted_dimension() {
+        for chunked in [false, true] {
+            for dimension in [1 << 40, u64::MAX / 2] {
+                let mut datasets = test_datasets();
+                datasets[1].chunked = chunked;
+                let mut data = write_file(&datasets, 0);
+
+                // overwrite the first dimension of the dataspace of the matrix
+                let space = dataspace(&[5, 2, 3]);
+                let pos = data.windows(space.len()).position(|w| w == space).unwrap();
+                data[pos + 8..pos + 16].copy_from_slice(&dimension.to_le_bytes());
+
+                let file = File::open(&data).unwrap();
+                let links = file.root_links().unwrap();
+                assert!(file.read_dataset(links[1].1).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_unshuffle() {
+        let data: Vec<u8> = (0..13).collect();
+        assert_eq!(unshuffle(&shuffle(&data[..12], 4), 4), &data[..12]);
+        let mut shuffled = shuffle(&data[..12], 4);
+        shuffled.push(12);
+        assert_eq!(unshuffle(&shuffled, 4), data);
+    }
+
+    #[test]
+    fn test_copy_chunk_clips_edges() {
+        // 3x3 dataset of u8, chunk of 2x2 at offset (2, 2)
+        let mut output = vec![0; 9];
+        copy_chunk(&[1, 2, 3, 4], &[2, 2], &[2, 2], &[3, 3], 1, &mut output).unwrap();
+        assert_eq!(output, [0, 0, 0, 0, 0, 0, 0, 0, 1]);
+
+        copy_chunk(&[1, 2, 3, 4], &[0, 1], &[2, 2], &[3, 3], 1, &mut output).unwrap();
+        assert_eq!(output, [0, 1, 2, 0, 3, 4, 0, 0, 1]);
+    }
+}
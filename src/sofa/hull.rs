@@ -0,0 +1,197 @@
+//! Triangulation of a set of measurement directions on the unit sphere
+//!
+//! The HRTF renderer looks up the triangle that is hit by the source direction, so the
+//! measurement points need to form a closed triangle mesh around the listener. Since all points
+//! are located on the unit sphere, this mesh is simply their convex hull.
+
+use std::collections::HashSet;
+
+type Vector = [f64; 3];
+
+fn sub(a: Vector, b: Vector) -> Vector {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: Vector, b: Vector) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: Vector, b: Vector) -> Vector {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+/// Face of the hull, with its (outward pointing) normal
+struct Face {
+    vertices: [usize; 3],
+    normal: Vector,
+    offset: f64,
+}
+
+impl Face {
+    fn new(points: &[Vector], vertices: [usize; 3]) -> Self {
+        let [a, b, c] = vertices.map(|i| points[i]);
+        let normal = cross(sub(b, a), sub(c, a));
+        Self {
+            vertices,
+            normal,
+            offset: dot(normal, a),
+        }
+    }
+
+    /// Signed distance (scaled by the area of the face) of a point to the plane of the face
+    fn height(&self, point: Vector) -> f64 {
+        dot(self.normal, point) - self.offset
+    }
+}
+
+/// Compute the triangles of the convex hull of the given unit vectors
+///
+/// Returns `None` when the points do not surround the origin, i.e. when there are directions
+/// that are not covered by any triangle.
+pub(crate) fn triangulate(directions: &[[f32; 3]]) -> Option<Vec<[usize; 3]>> {
+    if directions.len() < 4 {
+        return None;
+    }
+
+    // Measurements are often taken on rings of equal elevation, which makes many points exactly
+    // coplanar. Slightly perturb the radius of each point so the hull is always a triangulation.
+    let points: Vec<Vector> = directions
+        .iter()
+        .enumerate()
+        .map(|(i, d)| {
+            let jitter = 1. + 1e-7 * ((i as u64 * 2_654_435_761) % 1000) as f64 / 1000.;
+            [d[0], d[1], d[2]].map(|v| f64::from(v) * jitter)
+        })
+        .collect();
+
+    let eps = 1e-12;
+
+    // initial tetrahedron
+    let p0 = 0;
+    let p1 = (0..points.len()).max_by(|&i, &j| {
+        let di = dot(sub(points[i], points[p0]), sub(points[i], points[p0]));
+        let dj = dot(sub(points[j], points[p0]), sub(points[j], points[p0]));
+        di.total_cmp(&dj)
+    })?;
+    let line = sub(points[p1], points[p0]);
+    let p2 = (0..points.len()).max_by(|&i, &j| {
+        let ci = cross(line, sub(points[i], points[p0]));
+        let cj = cross(line, sub(points[j], points[p0]));
+        dot(ci, ci).total_cmp(&dot(cj, cj))
+    })?;
+    let plane = Face::new(&points, [p0, p1, p2]);
+    let p3 = (0..points.len()).max_by(|&i, &j| {
+        plane
+            .height(points[i])
+            .abs()
+            .total_cmp(&plane.height(points[j]).abs())
+    })?;
+    if plane.height(points[p3]).abs() < 1e-6 {
+        return None; // all points are coplanar
+    }
+
+    let mut faces = if plane.height(points[p3]) < 0. {
+        vec![[p0, p1, p2], [p0, p3, p1], [p1, p3, p2], [p2, p3, p0]]
+    } else {
+        vec![[p0, p2, p1], [p0, p1, p3], [p1, p2, p3], [p2, p0, p3]]
+    }
+    .into_iter()
+    .map(|v| Face::new(&points, v))
+    .collect::<Vec<_>>();
+
+    for (index, &point) in points.iter().enumerate() {
+        if [p0, p1, p2, p3].contains(&index) {
+            continue;
+        }
+
+        let (visible, hidden): (Vec<_>, Vec<_>) =
+            faces.into_iter().partition(|f| f.height(point) > eps);
+        faces = hidden;
+        if visible.is_empty() {
+            continue; // point is inside the hull
+        }
+
+        // the horizon consists of the edges of visible faces that border a hidden face
+        let edges: HashSet<(usize, usize)> = visible
+            .iter()
+            .flat_map(|f| {
+                let [a, b, c] = f.vertices;
+                [(a, b), (b, c), (c, a)]
+            })
+            .collect();
+        edges
+            .iter()
+            .filter(|&&(a, b)| !edges.contains(&(b, a)))
+            .for_each(|&(a, b)| faces.push(Face::new(&points, [a, b, index])));
+    }
+
+    // every face should face away from the origin
+    let surrounds_origin = faces.iter().all(|f| f.offset > 1e-6);
+    surrounds_origin.then(|| faces.into_iter().map(|f| f.vertices).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_octahedron() {
+        let points = [
+            [1., 0., 0.],
+            [-1., 0., 0.],
+            [0., 1., 0.],
+            [0., -1., 0.],
+            [0., 0., 1.],
+            [0., 0., -1.],
+        ];
+        let faces = triangulate(&points).unwrap();
+        assert_eq!(faces.len(), 8);
+
+        // each edge is shared by exactly two faces
+        let mut edges = HashSet::new();
+        faces.iter().for_each(|&[a, b, c]| {
+            assert!(edges.insert((a, b)));
+            assert!(edges.insert((b, c)));
+            assert!(edges.insert((c, a)));
+        });
+        edges
+            .iter()
+            .for_each(|&(a, b)| assert!(edges.contains(&(b, a))));
+    }
+
+    #[test]
+    fn test_rings() {
+        // rings of equal elevation, like most HRTF measurement grids
+        let mut points = vec![];
+        for elevation in [-40_f32, 0., 40.] {
+            for azimuth in (0..360).step_by(30) {
+                let (az, el) = ((azimuth as f32).to_radians(), elevation.to_radians());
+                points.push([az.cos() * el.cos(), az.sin() * el.cos(), el.sin()]);
+            }
+        }
+        points.push([0., 0., 1.]);
+
+        let faces = triangulate(&points).unwrap();
+        // Euler: a closed triangle mesh with V vertices has 2V - 4 faces
+        assert_eq!(faces.len(), 2 * points.len() - 4);
+    }
+
+    #[test]
+    fn test_not_surrounding() {
+        // upper hemisphere only, with points just above the horizon
+        let mut points = vec![[0., 0., 1.]];
+        for azimuth in (0..360).step_by(45) {
+            let az = (azimuth as f32).to_radians();
+            points.push([az.cos() * 0.99, az.sin() * 0.99, 0.141]);
+        }
+        assert!(triangulate(&points).is_none());
+
+        // coplanar points
+        let points = [[1., 0., 0.], [0., 1., 0.], [-1., 0., 0.], [0., -1., 0.]];
+        assert!(triangulate(&points).is_none());
+    }
+}
@@ -0,0 +1,335 @@
+//! Decompression of zlib streams (RFC 1950) and raw DEFLATE data (RFC 1951)
+//!
+//! Only decoding is supported, which is all that is needed for the `deflate` filter of HDF5.
+
+use std::error::Error;
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Maximum number of bits of a Huffman code
+const MAX_BITS: usize = 15;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// Order in which the code length code lengths are stored
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Reads the input LSB-first, as required by DEFLATE
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit_buf: u32,
+    bit_count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            bit_buf: 0,
+            bit_count: 0,
+        }
+    }
+
+    fn bits(&mut self, n: u32) -> Result<u32> {
+        while self.bit_count < n {
+            let byte = *self
+                .data
+                .get(self.pos)
+                .ok_or("InvalidData - unexpected end of deflate stream")?;
+            self.pos += 1;
+            self.bit_buf |= u32::from(byte) << self.bit_count;
+            self.bit_count += 8;
+        }
+
+        let value = self.bit_buf & ((1u64 << n) - 1) as u32;
+        self.bit_buf >>= n;
+        self.bit_count -= n;
+        Ok(value)
+    }
+
+    /// Discard the remaining bits of the current byte
+    fn align(&mut self) {
+        self.bit_buf = 0;
+        self.bit_count = 0;
+    }
+}
+
+/// Canonical Huffman decoding table
+struct Huffman {
+    /// number of codes per code length
+    counts: [u16; MAX_BITS + 1],
+    /// symbols ordered by code
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        lengths.iter().for_each(|&l| counts[l as usize] += 1);
+        counts[0] = 0;
+
+        // check for an over-subscribed set of lengths
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left <<= 1;
+            left -= i32::from(count);
+            if left < 0 {
+                return Err("InvalidData - over-subscribed Huffman code".into());
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 1];
+        for len in 1..MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+
+        let mut symbols = vec![0; lengths.len()];
+        lengths.iter().enumerate().for_each(|(symbol, &len)| {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        });
+
+        Ok(Self { counts, symbols })
+    }
+
+    fn decode(&self, reader: &mut BitReader<'_>) -> Result<u16> {
+        let mut code = 0i32; // bits read so far
+        let mut first = 0i32; // first code of the current length
+        let mut index = 0i32; // index of the first code of the current length in `symbols`
+
+        for len in 1..=MAX_BITS {
+            code |= reader.bits(1)? as i32;
+            let count = i32::from(self.counts[len]);
+            if code - count < first {
+                return Ok(self.symbols[(index + (code - first)) as usize]);
+            }
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+
+        Err("InvalidData - invalid Huffman code".into())
+    }
+}
+
+fn fixed_tables() -> Result<(Huffman, Huffman)> {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5; 30])?))
+}
+
+fn dynamic_tables(reader: &mut BitReader<'_>) -> Result<(Huffman, Huffman)> {
+    let hlit = reader.bits(5)? as usize + 257;
+    let hdist = reader.bits(5)? as usize + 1;
+    let hclen = reader.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for &index in &CODE_LENGTH_ORDER[..hclen] {
+        code_lengths[index] = reader.bits(3)? as u8;
+    }
+    let code_length_table = Huffman::new(&code_lengths)?;
+
+    let mut lengths = vec![0u8; hlit + hdist];
+    let mut i = 0;
+    while i < hlit + hdist {
+        let symbol = code_length_table.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths[..i]
+                    .last()
+                    .ok_or("InvalidData - repeat without previous code length")?;
+                (previous, 3 + reader.bits(2)? as usize)
+            }
+            17 => (0, 3 + reader.bits(3)? as usize),
+            _ => (0, 11 + reader.bits(7)? as usize),
+        };
+
+        if i + repeat > hlit + hdist {
+            return Err("InvalidData - too many code lengths".into());
+        }
+        lengths[i..i + repeat].fill(value);
+        i += repeat;
+    }
+
+    Ok((
+        Huffman::new(&lengths[..hlit])?,
+        Huffman::new(&lengths[hlit..])?,
+    ))
+}
+
+fn inflate_block(
+    reader: &mut BitReader<'_>,
+    output: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> Result<()> {
+    loop {
+        let symbol = literals.decode(reader)? as usize;
+        match symbol {
+            0..=255 => output.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let index = symbol - 257;
+                if index >= LENGTH_BASE.len() {
+                    return Err("InvalidData - invalid length symbol".into());
+                }
+                let length = LENGTH_BASE[index] as usize
+                    + reader.bits(u32::from(LENGTH_EXTRA[index]))? as usize;
+
+                let index = distances.decode(reader)? as usize;
+                if index >= DIST_BASE.len() {
+                    return Err("InvalidData - invalid distance symbol".into());
+                }
+                let distance =
+                    DIST_BASE[index] as usize + reader.bits(u32::from(DIST_EXTRA[index]))? as usize;
+
+                if distance > output.len() {
+                    return Err("InvalidData - distance too far back".into());
+                }
+                let start = output.len() - distance;
+                // the source and destination may overlap, so copy byte by byte
+                for i in 0..length {
+                    output.push(output[start + i]);
+                }
+            }
+        }
+    }
+}
+
+/// Decompress raw DEFLATE data
+pub(crate) fn inflate(data: &[u8]) -> Result<Vec<u8>> {
+    let mut reader = BitReader::new(data);
+    let mut output = Vec::with_capacity(data.len() * 4);
+
+    loop {
+        let is_final = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                // stored block
+                reader.align();
+                let pos = reader.pos;
+                let header = data
+                    .get(pos..pos + 4)
+                    .ok_or("InvalidData - unexpected end of deflate stream")?;
+                let len = u16::from_le_bytes([header[0], header[1]]);
+                let nlen = u16::from_le_bytes([header[2], header[3]]);
+                if len != !nlen {
+                    return Err("InvalidData - corrupt stored block length".into());
+                }
+                let start = pos + 4;
+                let block = data
+                    .get(start..start + len as usize)
+                    .ok_or("InvalidData - unexpected end of deflate stream")?;
+                output.extend_from_slice(block);
+                reader.pos = start + len as usize;
+            }
+            1 => {
+                let (literals, distances) = fixed_tables()?;
+                inflate_block(&mut reader, &mut output, &literals, &distances)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_tables(&mut reader)?;
+                inflate_block(&mut reader, &mut output, &literals, &distances)?;
+            }
+            _ => return Err("InvalidData - invalid deflate block type".into()),
+        }
+
+        if is_final {
+            return Ok(output);
+        }
+    }
+}
+
+/// Decompress a zlib stream, the checksum is not verified
+pub(crate) fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < 2 {
+        return Err("InvalidData - zlib stream too short".into());
+    }
+
+    let (cmf, flg) = (data[0], data[1]);
+    if cmf & 0x0f != 8 || (u16::from(cmf) << 8 | u16::from(flg)) % 31 != 0 {
+        return Err("InvalidData - invalid zlib header".into());
+    }
+    if flg & 0x20 != 0 {
+        return Err("NotSupportedError - zlib preset dictionaries are not supported".into());
+    }
+
+    inflate(&data[2..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stored_block() {
+        // zlib.compress(b"hello", level=0)
+        let data = [
+            0x78, 0x01, 0x01, 0x05, 0x00, 0xfa, 0xff, 0x68, 0x65, 0x6c, 0x6c, 0x6f, 0x06, 0x2c,
+            0x02, 0x15,
+        ];
+        assert_eq!(zlib_decompress(&data).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn test_fixed_huffman() {
+        // zlib.compress(b"hello hello hello hello")
+        let data = [
+            0x78, 0x9c, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x27, 0x01, 0x68, 0x03,
+            0x08, 0xb1,
+        ];
+        assert_eq!(zlib_decompress(&data).unwrap(), b"hello hello hello hello");
+    }
+
+    #[test]
+    fn test_dynamic_huffman() {
+        // bytes of (i * i + 3 * i) % 7 letters, repeated 1 + i % 5 times, for i in 0..400
+        let mut expected = vec![];
+        for i in 0..400 {
+            let letter = b'a' + ((i * i + 3 * i) % 7) as u8;
+            expected.extend(std::iter::repeat(letter).take(1 + i % 5));
+        }
+
+        // zlib.compress(expected, 9)
+        let data = [
+            0x78, 0xda, 0xed, 0x8d, 0xc9, 0x09, 0x00, 0x40, 0x0c, 0x02, 0x6b, 0x0d, 0xa8, 0xfd,
+            0x97, 0xb0, 0x89, 0x7b, 0x34, 0xb0, 0x5f, 0x7d, 0x04, 0x94, 0x21, 0x53, 0x24, 0x00,
+            0x76, 0x6a, 0x22, 0xa9, 0xef, 0x54, 0x4c, 0xe8, 0x45, 0x7b, 0xed, 0xdd, 0xe4, 0xe1,
+            0x3c, 0x1e, 0x90, 0x7e, 0xf0, 0x40, 0x57, 0x98, 0xd4, 0x4d, 0x45, 0x14, 0x51, 0x44,
+            0x11, 0xfd, 0x89, 0x16, 0x9e, 0x78, 0xd4, 0xbf,
+        ];
+        assert_eq!(zlib_decompress(&data).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(zlib_decompress(&[]).is_err());
+        assert!(zlib_decompress(&[0x78, 0x9c]).is_err());
+        assert!(zlib_decompress(&[0x78, 0x9c, 0xff, 0xff, 0xff]).is_err());
+    }
+}
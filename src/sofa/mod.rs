@@ -0,0 +1,345 @@
+//! Loading of head-related impulse responses from SOFA files
+
+use std::error::Error;
+use std::path::Path;
+
+use hrtf::HrirSphere;
+
+mod hdf5;
+mod hull;
+mod inflate;
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Head-related impulse responses, loaded from a SOFA (AES69) file
+///
+/// Most HRTF datasets (e.g. SADIE, ARI, CIPIC, LISTEN) are distributed as SOFA files. These can
+/// be used for the HRTF panning model of a [`PannerNode`](crate::node::PannerNode) via
+/// [`PannerNode::set_hrtf`](crate::node::PannerNode::set_hrtf), instead of the included IRCAM
+/// dataset.
+///
+/// Only files following the `SimpleFreeFieldHRIR` convention are supported, with source positions
+/// in spherical or cartesian coordinates. The measurement distance is ignored, only the directions
+/// of the measurements are used. The measurements should surround the listener, directions that
+/// are not covered by the dataset (e.g. below the lowest measured elevation) are interpolated
+/// along the surface of the convex hull of the measurement directions.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::SofaHrtf;
+///
+/// let hrtf = SofaHrtf::from_file("samples/subject.sofa").unwrap();
+///
+/// let context = AudioContext::default();
+/// let mut panner = context.create_panner();
+/// panner.set_hrtf(&hrtf);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SofaHrtf {
+    sample_rate: f32,
+    length: usize,
+    /// unit vectors of the measurement directions, SOFA coordinate system (x front, y left, z up)
+    directions: Vec<[f32; 3]>,
+    left: Vec<Vec<f32>>,
+    right: Vec<Vec<f32>>,
+    /// triangulation of the measurement directions
+    faces: Vec<[usize; 3]>,
+}
+
+impl SofaHrtf {
+    /// Load the impulse responses from a SOFA file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the file cannot be read or does not contain a valid
+    /// `SimpleFreeFieldHRIR` dataset.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Load the impulse responses from the contents of a SOFA file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the data is not a valid `SimpleFreeFieldHRIR` dataset.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let file = hdf5::File::open(bytes)?;
+        let links = file.root_links()?;
+        let find = |name: &str| links.iter().find(|(n, _)| n == name).map(|&(_, a)| a);
+
+        let ir_address = find("Data.IR").ok_or("InvalidData - SOFA: missing Data.IR")?;
+        let ir = file.read_dataset(ir_address)?;
+        let (m, n) = match ir.shape[..] {
+            [m, 2, n] if m > 0 && n > 0 => (m, n),
+            _ => {
+                return Err(
+                    "NotSupportedError - SOFA: Data.IR must have the shape (M, 2, N)".into(),
+                )
+            }
+        };
+
+        let sample_rate = find("Data.SamplingRate")
+            .map(|address| file.read_dataset(address))
+            .transpose()?
+            .and_then(|ds| ds.values.first().copied())
+            .ok_or("InvalidData - SOFA: missing Data.SamplingRate")?;
+        if sample_rate.is_nan() || sample_rate <= 0. {
+            return Err("InvalidData - SOFA: invalid Data.SamplingRate".into());
+        }
+
+        let positions_address =
+            find("SourcePosition").ok_or("InvalidData - SOFA: missing SourcePosition")?;
+        let positions = file.read_dataset(positions_address)?;
+        let positions_type = file
+            .string_attribute(positions_address, "Type")?
+            .unwrap_or_else(|| "spherical".to_string());
+        let spherical = match positions_type.as_str() {
+            "spherical" => true,
+            "cartesian" => false,
+            other => {
+                let msg = format!("NotSupportedError - SOFA: SourcePosition type {other}");
+                return Err(msg.into());
+            }
+        };
+        let position = |i: usize| -> Result<[f64; 3]> {
+            match positions.shape[..] {
+                [rows, 3] if rows == m => Ok([0, 1, 2].map(|c| positions.values[i * 3 + c])),
+                [1, 3] => Ok([0, 1, 2].map(|c| positions.values[c])),
+                _ => Err("InvalidData - SOFA: SourcePosition must have the shape (M, 3)".into()),
+            }
+        };
+
+        // optional broadband delay per measurement and ear, in samples
+        let delay = match find("Data.Delay") {
+            Some(address) => {
+                let delay = file.read_dataset(address)?;
+                match delay.shape[..] {
+                    [rows, 2] if rows == m || rows == 1 => delay.values,
+                    _ => {
+                        return Err(
+                            "InvalidData - SOFA: Data.Delay must have the shape (M, 2)".into()
+                        )
+                    }
+                }
+            }
+            None => vec![0.; 2],
+        };
+        let delay_of = |i: usize, ear: usize| {
+            let index = if delay.len() == 2 { ear } else { i * 2 + ear };
+            delay[index].max(0.).round() as usize
+        };
+        let max_delay = (0..m)
+            .flat_map(|i| [delay_of(i, 0), delay_of(i, 1)])
+            .max()
+            .unwrap_or(0);
+        let length = n + max_delay;
+
+        let mut directions: Vec<[f32; 3]> = Vec::with_capacity(m);
+        let mut left = Vec::with_capacity(m);
+        let mut right = Vec::with_capacity(m);
+
+        for i in 0..m {
+            let [a, b, c] = position(i)?;
+            let direction = if spherical {
+                let (azimuth, elevation) = (a.to_radians(), b.to_radians());
+                [
+                    azimuth.cos() * elevation.cos(),
+                    azimuth.sin() * elevation.cos(),
+                    elevation.sin(),
+                ]
+            } else {
+                [a, b, c]
+            };
+            let norm = direction.iter().map(|v| v * v).sum::<f64>().sqrt();
+            if !norm.is_normal() {
+                return Err("InvalidData - SOFA: invalid source position".into());
+            }
+            let direction = direction.map(|v| (v / norm) as f32);
+
+            // skip duplicate directions, e.g. multiple azimuths at an elevation of 90 degrees
+            let duplicate = directions.iter().any(|d| {
+                let dot: f32 = d.iter().zip(&direction).map(|(a, b)| a * b).sum();
+                dot > 1. - 1e-6
+            });
+            if duplicate {
+                continue;
+            }
+
+            let ir_of = |ear: usize| {
+                let start = (i * 2 + ear) * n;
+                let mut response = vec![0.; delay_of(i, ear)];
+                response.extend(ir.values[start..start + n].iter().map(|&v| v as f32));
+                response.resize(length, 0.);
+                response
+            };
+            directions.push(direction);
+            left.push(ir_of(0));
+            right.push(ir_of(1));
+        }
+
+        let faces = hull::triangulate(&directions).ok_or(
+            "NotSupportedError - SOFA: the measurement directions do not surround the listener",
+        )?;
+
+        Ok(Self {
+            sample_rate: sample_rate as f32,
+            length,
+            directions,
+            left,
+            right,
+            faces,
+        })
+    }
+
+    /// Sample rate of the impulse responses
+    #[must_use]
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Length in samples of the impulse responses
+    #[must_use]
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Number of (distinct) measurement directions
+    #[must_use]
+    pub fn number_of_measurements(&self) -> usize {
+        self.directions.len()
+    }
+
+    /// Convert to the HRIR sphere used by the HRTF renderer, resampled to the given sample rate
+    pub(crate) fn hrir_sphere(&self, sample_rate: u32) -> HrirSphere {
+        // serialize to the IRCAM sphere format, which is the only way to construct a sphere
+        let mut bytes = Vec::new();
+        let mut put = |value: u32| bytes.extend_from_slice(&value.to_le_bytes());
+        put(u32::from_le_bytes(*b"HRIR"));
+        put(self.sample_rate.round() as u32);
+        put(self.length as u32);
+        put(self.directions.len() as u32);
+        put(self.faces.len() as u32 * 3);
+        self.faces.iter().flatten().for_each(|&i| put(i as u32));
+
+        for ((direction, left), right) in self.directions.iter().zip(&self.left).zip(&self.right) {
+            // The renderer expects x to the right, y to the front and z up, whereas SOFA uses x
+            // to the front, y to the left and z up
+            let [x, y, z] = *direction;
+            [-y, x, z]
+                .iter()
+                .chain(left)
+                .chain(right)
+                .for_each(|v| put(v.to_bits()));
+        }
+
+        HrirSphere::new(&bytes[..], sample_rate).expect("serialized HRIR sphere is valid")
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::hdf5::tests::{write_file, TestDataset};
+    use super::*;
+
+    /// Build a SOFA file with measurements on rings of equal elevation
+    ///
+    /// The left ear response is an impulse at sample `1 + measurement index`, the right ear
+    /// response is the negated left ear response.
+    pub(crate) fn test_sofa_file(chunked: bool) -> Vec<u8> {
+        let mut positions = vec![];
+        for elevation in [-45., 0., 45.] {
+            for azimuth in (0..360).step_by(45) {
+                positions.extend([azimuth as f64, elevation, 1.2]);
+            }
+        }
+        // multiple azimuths at the pole map to the same direction
+        positions.extend([0., 90., 1.2, 180., 90., 1.2]);
+
+        let m = positions.len() / 3;
+        let n = 64;
+        let mut ir = vec![0.; m * 2 * n];
+        for i in 0..m {
+            ir[i * 2 * n + 1 + i] = 1.;
+            ir[(i * 2 + 1) * n + 1 + i] = -1.;
+        }
+
+        let datasets = [
+            TestDataset {
+                name: "Data.IR",
+                shape: vec![m as u64, 2, n as u64],
+                values: ir,
+                chunked,
+                attributes: vec![],
+            },
+            TestDataset {
+                name: "Data.SamplingRate",
+                shape: vec![1],
+                values: vec![48000.],
+                chunked: false,
+                attributes: vec![("Units", "hertz")],
+            },
+            TestDataset {
+                name: "SourcePosition",
+                shape: vec![m as u64, 3],
+                values: positions,
+                chunked: false,
+                attributes: vec![("Type", "spherical"), ("Units", "degree, degree, metre")],
+            },
+        ];
+
+        write_file(&datasets, if chunked { 0 } else { 2 })
+    }
+
+    #[test]
+    fn test_load() {
+        for chunked in [false, true] {
+            let hrtf = SofaHrtf::from_bytes(&test_sofa_file(chunked)).unwrap();
+            assert_eq!(hrtf.sample_rate(), 48000.);
+            assert_eq!(hrtf.length(), 64);
+            assert_eq!(hrtf.number_of_measurements(), 25);
+
+            // source to the left (azimuth 90, elevation 0) is the 11th measurement
+            let left = &hrtf.directions[10];
+            assert!(left[1] > 0.999);
+            assert_eq!(hrtf.left[10][11], 1.);
+            assert_eq!(hrtf.right[10][11], -1.);
+        }
+    }
+
+    #[test]
+    fn test_hrir_sphere() {
+        let hrtf = SofaHrtf::from_bytes(&test_sofa_file(false)).unwrap();
+        let sphere = hrtf.hrir_sphere(48000);
+        assert_eq!(sphere.len(), 64);
+        assert_eq!(sphere.points().len(), 25);
+
+        // a source to the left is located at negative x for the renderer
+        let point = &sphere.points()[10];
+        assert!(point.pos.x < -0.999);
+        assert_eq!(point.left_hrir()[11], 1.);
+
+        // resampled
+        let sphere = hrtf.hrir_sphere(24000);
+        assert_eq!(sphere.points().len(), 25);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(SofaHrtf::from_bytes(&[]).is_err());
+        assert!(SofaHrtf::from_file("does/not/exist.sofa").is_err());
+
+        // missing source positions
+        let datasets = [TestDataset {
+            name: "Data.IR",
+            shape: vec![1, 2, 4],
+            values: vec![0.; 8],
+            chunked: false,
+            attributes: vec![],
+        }];
+        let result = SofaHrtf::from_bytes(&write_file(&datasets, 0));
+        assert!(result.unwrap_err().to_string().contains("SamplingRate"));
+    }
+}
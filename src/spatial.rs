@@ -67,6 +67,184 @@ impl AudioListener {
     pub fn up_z(&self) -> &AudioParam {
         &self.up_z
     }
+
+    /// Set the position of the listener, equivalent to setting the `value` of the position params
+    ///
+    /// The three coordinates are guaranteed to be updated within the same render quantum.
+    pub fn set_position(&self, x: f32, y: f32, z: f32) {
+        crate::param::set_values_synchronized(&[
+            (&self.position_x, x),
+            (&self.position_y, y),
+            (&self.position_z, z),
+        ]);
+    }
+
+    /// Set the orientation of the listener, equivalent to setting the `value` of the forward and
+    /// up params
+    ///
+    /// All six coordinates are guaranteed to be updated within the same render quantum.
+    pub fn set_orientation(&self, forward: [f32; 3], up: [f32; 3]) {
+        let [fx, fy, fz] = forward;
+        let [ux, uy, uz] = up;
+        crate::param::set_values_synchronized(&[
+            (&self.forward_x, fx),
+            (&self.forward_y, fy),
+            (&self.forward_z, fz),
+            (&self.up_x, ux),
+            (&self.up_y, uy),
+            (&self.up_z, uz),
+        ]);
+    }
+
+    /// Set the position and orientation of the listener from its world transform
+    ///
+    /// The transform is a 4x4 matrix in column-major order, as used by most game engines and
+    /// math libraries (e.g. `glam::Mat4::to_cols_array_2d()` or a `mint::ColumnMatrix4<f32>`).
+    /// The translation is the position of the listener, the rotation is applied to the
+    /// [forward](CoordinateSystem::forward) and [up](CoordinateSystem::up) axes of the given
+    /// coordinate system. Scaling factors are ignored.
+    ///
+    /// All nine params are guaranteed to be updated within the same render quantum.
+    pub fn set_transform(
+        &self,
+        transform: impl Into<[[f32; 4]; 4]>,
+        coordinate_system: CoordinateSystem,
+    ) {
+        let transform = transform.into();
+        let [x, y, z] = coordinate_system.transform_point(transform);
+        let [fx, fy, fz] =
+            coordinate_system.transform_direction(transform, coordinate_system.forward());
+        let [ux, uy, uz] = coordinate_system.transform_direction(transform, coordinate_system.up());
+
+        crate::param::set_values_synchronized(&[
+            (&self.position_x, x),
+            (&self.position_y, y),
+            (&self.position_z, z),
+            (&self.forward_x, fx),
+            (&self.forward_y, fy),
+            (&self.forward_z, fz),
+            (&self.up_x, ux),
+            (&self.up_y, uy),
+            (&self.up_z, uz),
+        ]);
+    }
+}
+
+/// Handedness of a 3D coordinate system
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Handedness {
+    /// Right-handed, as used by Web Audio, OpenGL, Blender and Godot
+    #[default]
+    Right,
+    /// Left-handed, as used by Unity, Unreal Engine and DirectX
+    Left,
+}
+
+/// Axis pointing upwards in a 3D coordinate system
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum UpAxis {
+    /// Y-up, as used by Web Audio, Unity and Godot
+    #[default]
+    Y,
+    /// Z-up, as used by Blender and Unreal Engine
+    Z,
+}
+
+/// Convention of a 3D coordinate system, to convert positions and orientations from a game engine
+/// or math library into the coordinate system of Web Audio
+///
+/// Web Audio uses a right-handed coordinate system with the Y-axis pointing up and the listener
+/// facing towards -Z by default, which is the [`Default`] value of this struct.
+///
+/// Vectors are accepted as anything that converts into `[f32; 3]`, such as `glam::Vec3` or
+/// `mint::Vector3<f32>`.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::{CoordinateSystem, Handedness, UpAxis};
+///
+/// // Z-up, left-handed coordinates
+/// let coordinates = CoordinateSystem {
+///     handedness: Handedness::Left,
+///     up_axis: UpAxis::Z,
+/// };
+///
+/// let context = AudioContext::default();
+/// let panner = context.create_panner();
+/// let [x, y, z] = coordinates.to_web_audio([1., 2., 3.]);
+/// panner.set_position(x, y, z);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct CoordinateSystem {
+    pub handedness: Handedness,
+    pub up_axis: UpAxis,
+}
+
+impl CoordinateSystem {
+    /// Convert a position or direction to the Web Audio coordinate system
+    ///
+    /// The axes are mapped as follows, which matches common engine conventions:
+    ///
+    /// - right-handed Y-up: unchanged (forward is -Z)
+    /// - left-handed Y-up: Z is mirrored (forward is +Z, e.g. Unity)
+    /// - right-handed Z-up: forward is +Y, right is +X (e.g. Blender)
+    /// - left-handed Z-up: forward is +X, right is +Y (e.g. Unreal Engine)
+    pub fn to_web_audio(&self, vector: impl Into<[f32; 3]>) -> [f32; 3] {
+        let [x, y, z] = vector.into();
+        match (self.handedness, self.up_axis) {
+            (Handedness::Right, UpAxis::Y) => [x, y, z],
+            (Handedness::Left, UpAxis::Y) => [x, y, -z],
+            (Handedness::Right, UpAxis::Z) => [x, z, -y],
+            (Handedness::Left, UpAxis::Z) => [y, z, -x],
+        }
+    }
+
+    /// Convert a position or direction from the Web Audio coordinate system
+    pub fn from_web_audio(&self, vector: impl Into<[f32; 3]>) -> [f32; 3] {
+        let [x, y, z] = vector.into();
+        match (self.handedness, self.up_axis) {
+            (Handedness::Right, UpAxis::Y) => [x, y, z],
+            (Handedness::Left, UpAxis::Y) => [x, y, -z],
+            (Handedness::Right, UpAxis::Z) => [x, -z, y],
+            (Handedness::Left, UpAxis::Z) => [-z, x, y],
+        }
+    }
+
+    /// Forward direction of an untransformed object, mapping to -Z in Web Audio
+    #[must_use]
+    pub fn forward(&self) -> [f32; 3] {
+        self.from_web_audio([0., 0., -1.])
+    }
+
+    /// Up direction of an untransformed object, mapping to +Y in Web Audio
+    #[must_use]
+    pub fn up(&self) -> [f32; 3] {
+        self.from_web_audio([0., 1., 0.])
+    }
+
+    /// Translation of a column-major transform, in Web Audio coordinates
+    pub(crate) fn transform_point(&self, transform: [[f32; 4]; 4]) -> [f32; 3] {
+        let [x, y, z, _] = transform[3];
+        self.to_web_audio([x, y, z])
+    }
+
+    /// Rotate a direction with a column-major transform, the result is in Web Audio coordinates
+    pub(crate) fn transform_direction(
+        &self,
+        transform: [[f32; 4]; 4],
+        direction: [f32; 3],
+    ) -> [f32; 3] {
+        let mut result = [0.; 3];
+        transform[..3]
+            .iter()
+            .zip(direction)
+            .for_each(|(column, d)| {
+                result.iter_mut().zip(column).for_each(|(r, c)| *r += c * d);
+            });
+        self.to_web_audio(result)
+    }
 }
 
 /// Wrapper for the [`AudioListener`] so it can be placed in the audio graph.
@@ -298,6 +476,10 @@ pub fn angle(
 mod tests {
     use float_eq::assert_float_eq;
 
+    use crate::context::{AudioContextRegistration, BaseAudioContext};
+    use crate::node::{AudioNode, ChannelConfig};
+    use crate::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
+
     use super::*;
 
     // listener coordinates/directions
@@ -387,4 +569,205 @@ mod tests {
 
         assert_float_eq!(angle, 90., abs <= 0.);
     }
+
+    #[test]
+    fn test_coordinate_system_round_trip() {
+        for handedness in [Handedness::Right, Handedness::Left] {
+            for up_axis in [UpAxis::Y, UpAxis::Z] {
+                let system = CoordinateSystem {
+                    handedness,
+                    up_axis,
+                };
+                let v = [1., 2., 3.];
+                assert_eq!(system.from_web_audio(system.to_web_audio(v)), v);
+                assert_eq!(system.to_web_audio(system.forward()), [0., 0., -1.]);
+                assert_eq!(system.to_web_audio(system.up()), [0., 1., 0.]);
+
+                // mirrored axes for left-handed systems
+                let right = vec3_cross(system.forward(), system.up());
+                let right = system.to_web_audio(right);
+                let expected = match handedness {
+                    Handedness::Right => [1., 0., 0.],
+                    Handedness::Left => [-1., 0., 0.],
+                };
+                assert_eq!(right, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_engine_conventions() {
+        let unity = CoordinateSystem {
+            handedness: Handedness::Left,
+            up_axis: UpAxis::Y,
+        };
+        assert_eq!(unity.forward(), [0., 0., 1.]);
+
+        let blender = CoordinateSystem {
+            handedness: Handedness::Right,
+            up_axis: UpAxis::Z,
+        };
+        assert_eq!(blender.forward(), [0., 1., 0.]);
+        assert_eq!(blender.up(), [0., 0., 1.]);
+
+        let unreal = CoordinateSystem {
+            handedness: Handedness::Left,
+            up_axis: UpAxis::Z,
+        };
+        assert_eq!(unreal.forward(), [1., 0., 0.]);
+        // right of the listener
+        assert_eq!(unreal.to_web_audio([0., 1., 0.]), [1., 0., 0.]);
+    }
+
+    /// Node pausing the render thread at the given frame until the control thread resumes it
+    struct PauseNode {
+        registration: AudioContextRegistration,
+        channel_config: ChannelConfig,
+    }
+
+    impl AudioNode for PauseNode {
+        fn registration(&self) -> &AudioContextRegistration {
+            &self.registration
+        }
+
+        fn channel_config(&self) -> &ChannelConfig {
+            &self.channel_config
+        }
+
+        fn number_of_inputs(&self) -> usize {
+            0
+        }
+
+        fn number_of_outputs(&self) -> usize {
+            1
+        }
+    }
+
+    struct PauseProcessor {
+        frame: u64,
+        paused: std::sync::mpsc::Sender<()>,
+        resume: std::sync::mpsc::Receiver<()>,
+    }
+
+    impl AudioProcessor for PauseProcessor {
+        fn process(
+            &mut self,
+            _inputs: &[AudioRenderQuantum],
+            _outputs: &mut [AudioRenderQuantum],
+            _params: AudioParamValues<'_>,
+            scope: &RenderScope,
+        ) -> bool {
+            if scope.current_frame == self.frame {
+                self.paused.send(()).unwrap();
+                self.resume.recv().unwrap();
+            }
+            true
+        }
+    }
+
+    #[test]
+    fn test_listener_set_position_orientation() {
+        use std::sync::mpsc;
+
+        use crate::context::OfflineAudioContext;
+        use crate::RENDER_QUANTUM_SIZE;
+
+        let mut context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE * 4, 44100.);
+        // the listener is rendered along with the panners
+        let panner = context.create_panner();
+        panner.connect(&context.destination());
+
+        let listener = context.listener();
+        let params = [
+            listener.position_x(),
+            listener.position_y(),
+            listener.position_z(),
+            listener.forward_x(),
+            listener.forward_y(),
+            listener.forward_z(),
+            listener.up_x(),
+            listener.up_y(),
+            listener.up_z(),
+        ];
+        let defaults = params.map(AudioParam::default_value);
+        let captures = params.map(|param| context.capture_param(param));
+
+        // pause the rendering in the second render quantum
+        let (paused_send, paused_recv) = mpsc::channel();
+        let (resume_send, resume_recv) = mpsc::channel();
+        let pause = context.register(|registration| {
+            let node = PauseNode {
+                registration,
+                channel_config: ChannelConfig::default(),
+            };
+            let render = PauseProcessor {
+                frame: RENDER_QUANTUM_SIZE as u64,
+                paused: paused_send,
+                resume: resume_recv,
+            };
+            (node, Box::new(render))
+        });
+        pause.connect(&context.destination());
+
+        // move the listener while the rendering is paused
+        let control = std::thread::spawn(move || {
+            paused_recv.recv().unwrap();
+            listener.set_position(1., 2., 3.);
+            listener.set_orientation([4., 5., 6.], [7., 8., 9.]);
+            resume_send.send(()).unwrap();
+        });
+        let _ = context.start_rendering_sync();
+        control.join().unwrap();
+
+        // all coordinates are updated from the start of the next render quantum
+        let frame = RENDER_QUANTUM_SIZE * 2;
+        captures
+            .iter()
+            .zip(defaults)
+            .enumerate()
+            .for_each(|(i, (capture, default))| {
+                let values = capture.values();
+                assert!(values[..frame].iter().all(|&v| v == default));
+                assert!(values[frame..].iter().all(|&v| v == (i + 1) as f32));
+            });
+    }
+
+    #[test]
+    fn test_listener_set_transform() {
+        use crate::context::{BaseAudioContext, OfflineAudioContext};
+
+        let context = OfflineAudioContext::new(1, 128, 44100.);
+        let listener = context.listener();
+
+        // rotate 90 degrees around the up axis (Z) and translate, in Blender coordinates
+        let transform = [
+            [0., 1., 0., 0.],
+            [-1., 0., 0., 0.],
+            [0., 0., 1., 0.],
+            [1., 2., 3., 1.],
+        ];
+        let blender = CoordinateSystem {
+            handedness: Handedness::Right,
+            up_axis: UpAxis::Z,
+        };
+        listener.set_transform(transform, blender);
+
+        let values = |params: [&AudioParam; 3]| params.map(AudioParam::value);
+        let position = values([
+            listener.position_x(),
+            listener.position_y(),
+            listener.position_z(),
+        ]);
+        assert_eq!(position, [1., 3., -2.]);
+
+        // forward +Y is rotated to -X
+        let forward = values([
+            listener.forward_x(),
+            listener.forward_y(),
+            listener.forward_z(),
+        ]);
+        assert_float_eq!(forward, [-1., 0., 0.], abs_all <= 1e-6);
+        let up = values([listener.up_x(), listener.up_y(), listener.up_z()]);
+        assert_float_eq!(up, [0., 1., 0.], abs_all <= 1e-6);
+    }
 }
@@ -0,0 +1,414 @@
+//! Synchronized playback of the stems of a song, as commonly used for music practice and remix
+//! apps
+//!
+//! A [`StemPlayer`] plays a set of audio buffers (stems) as a single track with a shared
+//! transport (play, pause, seek). The stems are started, stopped and moved in the same render
+//! quantum (as in a [`SyncGroup`](crate::node::SyncGroup)) so they never drift apart. Each stem
+//! has its own volume and can be muted or soloed.
+
+use crate::buffer::AudioBuffer;
+use crate::context::{BaseAudioContext, ConcreteBaseAudioContext};
+use crate::node::{
+    AudioBufferSourceNode, AudioBufferSourceOptions, AudioNode, AudioScheduledSourceNode, GainNode,
+};
+use crate::param::AudioParam;
+
+/// Time constant in seconds of the mute and solo transitions, to avoid clicks
+const GATE_TIME_CONSTANT: f64 = 0.005;
+
+/// Single track of a [`StemPlayer`]
+pub struct Stem {
+    name: String,
+    buffer: AudioBuffer,
+    volume: GainNode,
+    /// applies the mute and solo states
+    gate: GainNode,
+    muted: bool,
+    soloed: bool,
+    source: Option<AudioBufferSourceNode>,
+}
+
+impl std::fmt::Debug for Stem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Stem")
+            .field("name", &self.name)
+            .field("duration", &self.buffer.duration())
+            .field("muted", &self.muted)
+            .field("soloed", &self.soloed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Stem {
+    /// Name of the stem
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Audio data of the stem
+    pub fn buffer(&self) -> &AudioBuffer {
+        &self.buffer
+    }
+
+    /// Volume of the stem, as linear gain
+    pub fn volume(&self) -> &AudioParam {
+        self.volume.gain()
+    }
+
+    /// Check if the stem is muted
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    /// Check if the stem is soloed
+    pub fn is_soloed(&self) -> bool {
+        self.soloed
+    }
+}
+
+/// Plays multiple stems in sync with a shared transport
+///
+/// All stems are mixed into the output bus, which is connected to the destination of the
+/// context. The position of the transport is expressed in seconds from the start of the stems.
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::stems::StemPlayer;
+///
+/// let context = AudioContext::default();
+/// let drums = context.decode_audio_data_sync(std::fs::File::open("drums.wav").unwrap()).unwrap();
+/// let bass = context.decode_audio_data_sync(std::fs::File::open("bass.wav").unwrap()).unwrap();
+///
+/// let mut player = StemPlayer::new(&context);
+/// player.add_stem("drums", drums);
+/// let bass = player.add_stem("bass", bass);
+///
+/// player.set_soloed(bass, true);
+/// player.seek(30.);
+/// player.play();
+/// ```
+pub struct StemPlayer {
+    context: ConcreteBaseAudioContext,
+    output: GainNode,
+    stems: Vec<Stem>,
+    /// position of the transport when playback was last started, paused or seeked
+    offset: f64,
+    /// context time of the last start or seek, `None` when paused
+    start_time: Option<f64>,
+}
+
+impl std::fmt::Debug for StemPlayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StemPlayer")
+            .field("stems", &self.stems)
+            .field("position", &self.position())
+            .field("playing", &self.is_playing())
+            .finish_non_exhaustive()
+    }
+}
+
+impl StemPlayer {
+    /// Create a new player, with its output bus connected to the destination of the context
+    pub fn new<C: BaseAudioContext>(context: &C) -> Self {
+        let output = context.create_gain();
+        output.connect(&context.destination());
+
+        Self {
+            context: context.base().clone(),
+            output,
+            stems: vec![],
+            offset: 0.,
+            start_time: None,
+        }
+    }
+
+    /// The output bus, all stems are mixed into this node
+    pub fn output(&self) -> &GainNode {
+        &self.output
+    }
+
+    /// Add a stem and return its index
+    ///
+    /// When the player is playing, all stems are restarted in sync at the current position.
+    pub fn add_stem(&mut self, name: &str, buffer: AudioBuffer) -> usize {
+        let volume = self.context.create_gain();
+        let gate = self.context.create_gain();
+        volume.connect(&gate);
+        gate.connect(&self.output);
+
+        let playing = self.is_playing();
+        if playing {
+            self.pause();
+        }
+
+        self.stems.push(Stem {
+            name: name.to_string(),
+            buffer,
+            volume,
+            gate,
+            muted: false,
+            soloed: false,
+            source: None,
+        });
+        self.update_gates();
+
+        if playing {
+            self.play();
+        }
+
+        self.stems.len() - 1
+    }
+
+    /// The stem at the given index
+    pub fn stem(&self, index: usize) -> Option<&Stem> {
+        self.stems.get(index)
+    }
+
+    /// Index of the first stem with the given name
+    pub fn stem_index(&self, name: &str) -> Option<usize> {
+        self.stems.iter().position(|stem| stem.name == name)
+    }
+
+    /// All stems, by index
+    pub fn stems(&self) -> impl Iterator<Item = &Stem> {
+        self.stems.iter()
+    }
+
+    /// Number of stems
+    pub fn len(&self) -> usize {
+        self.stems.len()
+    }
+
+    /// Check if the player has no stems
+    pub fn is_empty(&self) -> bool {
+        self.stems.is_empty()
+    }
+
+    /// Duration in seconds of the longest stem
+    pub fn duration(&self) -> f64 {
+        self.stems
+            .iter()
+            .map(|stem| stem.buffer.duration())
+            .fold(0., f64::max)
+    }
+
+    /// Mute or unmute a stem
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds
+    pub fn set_muted(&mut self, index: usize, muted: bool) {
+        self.stem_mut(index).muted = muted;
+        self.update_gates();
+    }
+
+    /// Solo or unsolo a stem, only the soloed stems are heard when any stem is soloed
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds
+    pub fn set_soloed(&mut self, index: usize, soloed: bool) {
+        self.stem_mut(index).soloed = soloed;
+        self.update_gates();
+    }
+
+    #[track_caller]
+    fn stem_mut(&mut self, index: usize) -> &mut Stem {
+        let len = self.stems.len();
+        self.stems
+            .get_mut(index)
+            .unwrap_or_else(|| panic!("IndexSizeError: stem {} is out of bounds ({})", index, len))
+    }
+
+    fn update_gates(&self) {
+        let any_soloed = self.stems.iter().any(|stem| stem.soloed);
+        let now = self.context.current_time();
+
+        self.stems.iter().for_each(|stem| {
+            let audible = !stem.muted && (stem.soloed || !any_soloed);
+            let value = if audible { 1. } else { 0. };
+            stem.gate
+                .gain()
+                .set_target_at_time(value, now, GATE_TIME_CONSTANT);
+        });
+    }
+
+    /// Check if the transport is playing
+    pub fn is_playing(&self) -> bool {
+        self.start_time.is_some()
+    }
+
+    /// Current position of the transport in seconds, clamped to the duration of the stems
+    ///
+    /// While playing, the position is estimated from the current time of the context.
+    pub fn position(&self) -> f64 {
+        let elapsed = match self.start_time {
+            Some(start_time) => (self.context.current_time() - start_time).max(0.),
+            None => 0.,
+        };
+        (self.offset + elapsed).min(self.duration())
+    }
+
+    /// Start playing all stems from the current position, does nothing when already playing
+    pub fn play(&mut self) {
+        if self.is_playing() {
+            return;
+        }
+
+        let now = self.context.current_time();
+        let offset = self.position();
+        let context = self.context.clone();
+
+        context.batch_node_messages(|| {
+            self.stems.iter_mut().for_each(|stem| {
+                let options = AudioBufferSourceOptions {
+                    buffer: Some(stem.buffer.clone()),
+                    ..AudioBufferSourceOptions::default()
+                };
+                let mut source = AudioBufferSourceNode::new(&context, options);
+                source.connect(&stem.volume);
+                source.start_at_with_offset(now, offset);
+                stem.source = Some(source);
+            });
+        });
+
+        self.offset = offset;
+        self.start_time = Some(now);
+    }
+
+    /// Pause all stems at the current position, does nothing when already paused
+    pub fn pause(&mut self) {
+        if !self.is_playing() {
+            return;
+        }
+
+        let now = self.context.current_time();
+        self.offset = self.position();
+        self.start_time = None;
+
+        let context = self.context.clone();
+        context.batch_node_messages(|| {
+            self.stems
+                .iter_mut()
+                .filter_map(|stem| stem.source.take())
+                .for_each(|mut source| source.stop_at(now));
+        });
+    }
+
+    /// Move the transport to the given position in seconds, without interrupting the playback
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position is negative
+    pub fn seek(&mut self, position: f64) {
+        assert!(
+            position >= 0.,
+            "RangeError - seek position ({:?}) should not be negative",
+            position
+        );
+
+        if !self.is_playing() {
+            self.offset = position;
+            return;
+        }
+
+        let now = self.context.current_time();
+        self.offset = position;
+        self.start_time = Some(now);
+
+        let context = self.context.clone();
+        context.batch_node_messages(|| {
+            self.stems
+                .iter_mut()
+                .filter_map(|stem| stem.source.as_mut())
+                .for_each(|source| source.seek(position, now));
+        });
+    }
+}
+
+impl Drop for StemPlayer {
+    fn drop(&mut self) {
+        self.pause();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::OfflineAudioContext;
+
+    use float_eq::assert_float_eq;
+
+    fn ramp(length: usize, scale: f32) -> AudioBuffer {
+        let data = (0..length).map(|i| i as f32 * scale).collect();
+        AudioBuffer::from(vec![data], 32_768.)
+    }
+
+    #[test]
+    fn test_play_from_offset() {
+        let context = OfflineAudioContext::new(1, 256, 32_768.);
+        let mut player = StemPlayer::new(&context);
+        player.add_stem("a", ramp(1024, 1.));
+        player.add_stem("b", ramp(1024, 1000.));
+        assert_eq!(player.len(), 2);
+        assert_eq!(player.stem_index("b"), Some(1));
+        assert_float_eq!(player.duration(), 1024. / 32_768., abs <= 0.);
+
+        player.seek(512. / 32_768.);
+        assert!(!player.is_playing());
+        player.play();
+        assert!(player.is_playing());
+
+        let output = context.start_rendering_sync();
+        let expected: Vec<f32> = (512..768).map(|i| i as f32 * 1001.).collect();
+        assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_mute_solo() {
+        let context = OfflineAudioContext::new(1, 2048, 32_768.);
+        let mut player = StemPlayer::new(&context);
+        let a = player.add_stem("a", AudioBuffer::from(vec![vec![1.; 2048]], 32_768.));
+        let b = player.add_stem("b", AudioBuffer::from(vec![vec![2.; 2048]], 32_768.));
+        let c = player.add_stem("c", AudioBuffer::from(vec![vec![4.; 2048]], 32_768.));
+
+        player.set_soloed(a, true);
+        player.set_soloed(b, true);
+        player.set_muted(b, true);
+        assert!(player.stem(b).unwrap().is_muted());
+        assert!(!player.stem(c).unwrap().is_soloed());
+        player.stem(a).unwrap().volume().set_value(0.5);
+        player.play();
+
+        let output = context.start_rendering_sync();
+        // only `a` is audible, after the gate transitions
+        assert_float_eq!(output.get_channel_data(0)[2047], 0.5, abs <= 1e-3);
+    }
+
+    #[test]
+    fn test_pause_keeps_position() {
+        let context = OfflineAudioContext::new(1, 128, 32_768.);
+        let mut player = StemPlayer::new(&context);
+        player.add_stem("a", ramp(1024, 1.));
+        player.seek(0.01);
+        player.play();
+        player.pause();
+        assert!(!player.is_playing());
+        assert_float_eq!(player.position(), 0.01, abs <= 0.);
+
+        player.seek(1.);
+        assert_float_eq!(player.position(), player.duration(), abs <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_index() {
+        let context = OfflineAudioContext::new(1, 128, 32_768.);
+        let mut player = StemPlayer::new(&context);
+        player.set_muted(0, true);
+    }
+}
@@ -0,0 +1,192 @@
+//! Synchronization primitives shared by the control thread and the render thread
+//!
+//! The state that crosses the threads (the atomic values of the params and of the clock, and
+//! the queue of the control messages) is built on these primitives instead of `std::sync` and
+//! `crossbeam_channel`. When compiled with `--cfg loom`, they are replaced by the model checked
+//! versions of the [`loom`](https://docs.rs/loom) crate, which explores the interleavings of the
+//! threads in the tests of this module:
+//!
+//! ```sh
+//! RUSTFLAGS="--cfg loom" cargo test --no-default-features --release --lib sync::
+//! ```
+//!
+//! Everything built on these primitives must then be created inside `loom::model`, so the other
+//! tests of the crate do not run with `--cfg loom`.
+
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic;
+#[cfg(not(loom))]
+pub(crate) use std::sync::atomic;
+
+/// Multi-producer multi-consumer queue of the control messages
+#[cfg(not(loom))]
+pub(crate) mod channel {
+    pub(crate) use crossbeam_channel::{bounded, unbounded, Receiver, Sender};
+}
+
+/// Multi-producer multi-consumer queue of the control messages
+///
+/// The queue is a `VecDeque` behind a loom `Mutex`. It is never full nor disconnected, only the
+/// non-blocking operations used by the render thread are provided.
+#[cfg(loom)]
+pub(crate) mod channel {
+    use std::collections::VecDeque;
+
+    use crossbeam_channel::SendError;
+    use loom::sync::{Arc, Mutex};
+
+    pub(crate) struct Sender<T>(Arc<Mutex<VecDeque<T>>>);
+
+    pub(crate) struct Receiver<T>(Arc<Mutex<VecDeque<T>>>);
+
+    impl<T> Clone for Sender<T> {
+        fn clone(&self) -> Self {
+            Self(Arc::clone(&self.0))
+        }
+    }
+
+    impl<T> Clone for Receiver<T> {
+        fn clone(&self) -> Self {
+            Self(Arc::clone(&self.0))
+        }
+    }
+
+    pub(crate) fn unbounded<T>() -> (Sender<T>, Receiver<T>) {
+        let queue = Arc::new(Mutex::new(VecDeque::new()));
+        (Sender(Arc::clone(&queue)), Receiver(queue))
+    }
+
+    pub(crate) fn bounded<T>(_capacity: usize) -> (Sender<T>, Receiver<T>) {
+        unbounded()
+    }
+
+    impl<T> Sender<T> {
+        pub fn send(&self, msg: T) -> Result<(), SendError<T>> {
+            self.0.lock().unwrap().push_back(msg);
+            Ok(())
+        }
+    }
+
+    impl<T> Receiver<T> {
+        pub fn try_iter(&self) -> TryIter<'_, T> {
+            TryIter(self)
+        }
+    }
+
+    pub(crate) struct TryIter<'a, T>(&'a Receiver<T>);
+
+    impl<T> Iterator for TryIter<'_, T> {
+        type Item = T;
+
+        fn next(&mut self) -> Option<T> {
+            self.0 .0.lock().unwrap().pop_front()
+        }
+    }
+
+    impl<T> std::fmt::Debug for Sender<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.pad("Sender { .. }")
+        }
+    }
+
+    impl<T> std::fmt::Debug for Receiver<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.pad("Receiver { .. }")
+        }
+    }
+}
+
+#[cfg(all(test, loom))]
+mod tests {
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::{AudioNode, AudioScheduledSourceNode};
+    use crate::param::{AudioParamShared, AutomationRate};
+
+    use loom::sync::Arc;
+    use loom::thread;
+
+    #[test]
+    fn test_param_shared_handover() {
+        loom::model(|| {
+            let shared = Arc::new(AudioParamShared::new(0., AutomationRate::A));
+
+            let render = {
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || {
+                    shared.store_automation_rate(AutomationRate::K);
+                    shared.store_current_value(1.);
+                })
+            };
+
+            // the value is released after the automation rate
+            if shared.load_current_value() == 1. {
+                assert_eq!(shared.load_automation_rate(), AutomationRate::K);
+            }
+
+            render.join().unwrap();
+            assert_eq!(shared.load_current_value(), 1.);
+        });
+    }
+
+    #[test]
+    fn test_frames_played_handover() {
+        loom::model(|| {
+            let context = OfflineAudioContext::new(1, 256, 48_000.);
+            let quantum_duration = 128. / 48_000.;
+            let gain = context.create_gain();
+
+            // the control thread reads the clock advanced by the render thread
+            let control = thread::spawn(move || {
+                let first = gain.context().current_time();
+                let second = gain.context().current_time();
+                (gain, first, second)
+            });
+
+            let _ = context.start_rendering_sync();
+            let (gain, first, second) = control.join().unwrap();
+
+            // the clock only moves forward, by whole render quanta
+            assert!(first <= second, "{first} {second}");
+            for time in [first, second] {
+                assert!([0., 1., 2.].contains(&(time / quantum_duration)), "{time}");
+            }
+
+            // all the frames are accounted for once the rendering is done
+            assert_eq!(gain.context().current_time(), 2. * quantum_duration);
+        });
+    }
+
+    #[test]
+    fn test_graph_mutation_while_rendering() {
+        loom::model(|| {
+            let context = OfflineAudioContext::new(1, 128, 48_000.);
+            let mut src = context.create_constant_source();
+            src.start();
+            let gain = context.create_gain();
+            src.connect(&gain);
+
+            // the control thread mutates the graph while the render thread handles the
+            // control messages received so far
+            let control = thread::spawn(move || {
+                gain.gain().set_value(0.5);
+                gain.connect(&gain.context().destination());
+                (src, gain)
+            });
+
+            let output = context.start_rendering_sync();
+            let (_src, gain) = control.join().unwrap();
+
+            // the messages are handled in order: the connection is either missing, or made
+            // after the gain is set
+            let sample = output.get_channel_data(0)[0];
+            assert!(sample == 0. || sample == 0.5, "{sample}");
+
+            // the value is the one computed by the render thread, unless it is set afterwards
+            let value = gain.gain().value();
+            assert!(value == 0.5 || value == 1., "{value}");
+            if sample == 0.5 {
+                assert_eq!(value, 0.5);
+            }
+        });
+    }
+}
@@ -0,0 +1,423 @@
+//! Transports pause and resume a part of the audio graph
+//!
+//! The nodes of a [`Transport`] follow its clock instead of the clock of the context. While the
+//! transport is paused their clock does not advance: the sources stay at their playhead, the
+//! scheduled start and stop times and the automations of the parameters are postponed by the
+//! paused duration, and effects keep their tails for when the playback resumes. The rest of the
+//! graph keeps running, e.g. to pause the sounds of a game world while the music of its menu
+//! continues.
+
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::context::{BaseAudioContext, ConcreteBaseAudioContext};
+use crate::message::ControlMessage;
+use crate::music::{self, Quantization};
+use crate::node::AudioNode;
+use crate::AtomicF64;
+
+/// Clock of a group of nodes, which can be paused while the rest of the graph keeps running
+///
+/// A transport starts running at the current time of the context. Nodes are added one by one
+/// with [`add`](Self::add), or with the nodes feeding into them with
+/// [`add_subgraph`](Self::add_subgraph). The parameters of the nodes are always part of the
+/// transport. Nodes connected afterwards need to be added too: they follow the clock of the
+/// context otherwise.
+///
+/// Once the transport has been paused, its clock lags behind the clock of the context. The start
+/// and stop times of its sources, and the times of the automations of their parameters, are
+/// given in [`Transport::current_time`].
+///
+/// The nodes output silence while paused, ramp the gain of the bus down beforehand to avoid a
+/// click. Dropping the transport resumes its nodes.
+///
+/// The transport carries a musical grid of beats and bars, 4 beats per bar at 120 beats per
+/// minute by default. Changes of parameters can be quantized to the grid with
+/// [`AudioParam::set_value_at_next`](crate::AudioParam::set_value_at_next).
+///
+/// This is an extension to the spec.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::transport::Transport;
+///
+/// let context = AudioContext::default();
+///
+/// // all the sounds of the game world are mixed into a bus
+/// let world = context.create_gain();
+/// world.connect(&context.destination());
+/// let mut ambience = context.create_oscillator();
+/// ambience.connect(&world);
+/// ambience.start();
+///
+/// let mut transport = Transport::new(&context);
+/// transport.add_subgraph(&world);
+///
+/// // open the menu, the music of the menu keeps playing
+/// transport.pause();
+/// // close the menu
+/// transport.resume();
+/// ```
+pub struct Transport {
+    context: ConcreteBaseAudioContext,
+    id: usize,
+    paused: bool,
+    current_time: Arc<AtomicF64>,
+    tempo: f64,
+    beats_per_bar: usize,
+    origin: f64,
+}
+
+impl std::fmt::Debug for Transport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Transport")
+            .field("id", &self.id)
+            .field("paused", &self.paused)
+            .field("current_time", &self.current_time())
+            .field("tempo", &self.tempo)
+            .field("beats_per_bar", &self.beats_per_bar)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Transport {
+    /// Create a new transport, running from the current time of the context
+    pub fn new<C: BaseAudioContext>(context: &C) -> Self {
+        let context = context.base().clone();
+        let id = context.next_transport_id();
+        let current_time = Arc::new(AtomicF64::new(context.current_time()));
+
+        let message = ControlMessage::AddTransport {
+            id,
+            current_time: Arc::clone(&current_time),
+        };
+        let _ = context.send_control_msg(message);
+
+        Self {
+            context,
+            id,
+            paused: false,
+            current_time,
+            tempo: 120.,
+            beats_per_bar: 4,
+            origin: 0.,
+        }
+    }
+
+    fn add_node<N: AudioNode + ?Sized>(&mut self, node: &N, upstream: bool) {
+        if node.context() != &self.context {
+            panic!("InvalidAccessError: Attempting to add a node from a different context");
+        }
+        let message = ControlMessage::AddToTransport {
+            id: self.id,
+            node: node.registration().id(),
+            upstream,
+        };
+        let _ = self.context.send_control_msg(message);
+    }
+
+    /// Let a node and its parameters follow the clock of this transport
+    ///
+    /// The node leaves the transport it was part of, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node does not belong to the context of the transport
+    pub fn add<N: AudioNode + ?Sized>(&mut self, node: &N) {
+        self.add_node(node, false);
+    }
+
+    /// Let a node and all the nodes currently feeding into it, directly or not, follow the clock
+    /// of this transport
+    ///
+    /// The traversal stops at the nodes already part of this transport. The `AudioListener` is
+    /// never part of a transport.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node does not belong to the context of the transport
+    pub fn add_subgraph<N: AudioNode + ?Sized>(&mut self, node: &N) {
+        self.add_node(node, true);
+    }
+
+    /// Freeze the clock and the nodes of this transport
+    pub fn pause(&mut self) {
+        self.set_paused(true);
+    }
+
+    /// Let the clock and the nodes of this transport run again
+    pub fn resume(&mut self) {
+        self.set_paused(false);
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        let message = ControlMessage::SetTransportPaused {
+            id: self.id,
+            paused,
+        };
+        let _ = self.context.send_control_msg(message);
+    }
+
+    /// Check if the transport has been paused
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Time in seconds of the clock of this transport
+    ///
+    /// It equals the current time of the context minus the total paused duration, it is updated
+    /// by the render thread at the end of each render quantum.
+    pub fn current_time(&self) -> f64 {
+        self.current_time.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn context(&self) -> &ConcreteBaseAudioContext {
+        &self.context
+    }
+
+    /// Tempo of the musical grid in beats per minute
+    ///
+    /// Defaults to 120.0
+    pub fn tempo(&self) -> f64 {
+        self.tempo
+    }
+
+    /// Set the tempo of the musical grid in beats per minute
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tempo is not strictly positive and finite
+    pub fn set_tempo(&mut self, tempo: f64) {
+        music::assert_valid_tempo(tempo);
+        self.tempo = tempo;
+    }
+
+    /// Number of beats per bar of the musical grid
+    ///
+    /// Defaults to 4
+    pub fn beats_per_bar(&self) -> usize {
+        self.beats_per_bar
+    }
+
+    /// Set the number of beats per bar of the musical grid
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no beats per bar
+    pub fn set_beats_per_bar(&mut self, beats_per_bar: usize) {
+        assert!(
+            beats_per_bar > 0,
+            "RangeError - A bar should have at least one beat"
+        );
+        self.beats_per_bar = beats_per_bar;
+    }
+
+    /// Time of the first beat of the musical grid, in the clock of this transport
+    ///
+    /// Defaults to 0.0
+    pub fn origin(&self) -> f64 {
+        self.origin
+    }
+
+    /// Set the time of the first beat of the musical grid, in the clock of this transport
+    ///
+    /// # Panics
+    ///
+    /// Panics if the time is negative or not finite
+    pub fn set_origin(&mut self, origin: f64) {
+        assert!(
+            origin.is_finite() && origin >= 0.,
+            "RangeError - Invalid origin {:?}, should be positive",
+            origin
+        );
+        self.origin = origin;
+    }
+
+    /// First boundary of the musical grid at or after the given time, in the clock of this
+    /// transport
+    pub fn next_boundary(&self, time: f64, quantization: Quantization) -> f64 {
+        music::next_boundary(
+            time,
+            quantization,
+            self.tempo,
+            self.beats_per_bar,
+            self.origin,
+        )
+    }
+}
+
+impl Drop for Transport {
+    fn drop(&mut self) {
+        if self.paused {
+            self.resume();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::OfflineAudioContext;
+    use crate::node::{AudioBufferSourceOptions, AudioScheduledSourceNode};
+    use crate::{AudioBuffer, RENDER_QUANTUM_SIZE};
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_paused_subgraph() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+
+        let bus = context.create_gain();
+        bus.connect(&context.destination());
+        let mut paused = context.create_constant_source();
+        paused.connect(&bus);
+        paused.start();
+
+        let mut running = context.create_constant_source();
+        running.offset().set_value(2.);
+        running.connect(&context.destination());
+        running.start();
+
+        let mut transport = Transport::new(&context);
+        transport.add_subgraph(&bus);
+        transport.pause();
+        assert!(transport.is_paused());
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(
+            output.get_channel_data(0),
+            &[2.; RENDER_QUANTUM_SIZE][..],
+            abs_all <= 0.
+        );
+        assert_float_eq!(transport.current_time(), 0., abs <= 0.);
+    }
+
+    #[test]
+    fn test_running_subgraph() {
+        let length = RENDER_QUANTUM_SIZE * 2;
+        let context = OfflineAudioContext::new(1, length, 44_100.);
+
+        let data = (0..length).map(|i| i as f32).collect();
+        let options = AudioBufferSourceOptions {
+            buffer: Some(AudioBuffer::from(vec![data], 44_100.)),
+            ..AudioBufferSourceOptions::default()
+        };
+        let mut src = crate::node::AudioBufferSourceNode::new(&context, options);
+        src.connect(&context.destination());
+        src.start_at(100. / 44_100.);
+
+        let mut transport = Transport::new(&context);
+        transport.add(&src);
+
+        let output = context.start_rendering_sync();
+        let mut expected = vec![0.; 100];
+        expected.extend((0..length - 100).map(|i| i as f32));
+        assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 1e-9);
+        assert_float_eq!(
+            transport.current_time(),
+            length as f64 / 44_100.,
+            abs <= 1e-12
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_other_context() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let other = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let mut transport = Transport::new(&context);
+        transport.add(&other.create_gain());
+    }
+
+    #[test]
+    fn test_next_boundary() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let mut transport = Transport::new(&context);
+        assert_float_eq!(
+            transport.next_boundary(0.2, Quantization::Beat),
+            0.5,
+            abs <= 0.
+        );
+        assert_float_eq!(
+            transport.next_boundary(0.2, Quantization::Bar),
+            2.,
+            abs <= 0.
+        );
+
+        transport.set_tempo(60.);
+        transport.set_beats_per_bar(3);
+        transport.set_origin(1.);
+        assert_float_eq!(
+            transport.next_boundary(0.2, Quantization::Bar),
+            1.,
+            abs <= 0.
+        );
+        assert_float_eq!(
+            transport.next_boundary(1.2, Quantization::Beat),
+            2.,
+            abs <= 0.
+        );
+        assert_float_eq!(
+            transport.next_boundary(1.2, Quantization::Bar),
+            4.,
+            abs <= 0.
+        );
+        assert_float_eq!(
+            transport.next_boundary(1.2, Quantization::Immediate),
+            1.2,
+            abs <= 0.
+        );
+    }
+
+    #[test]
+    fn test_quantized_param_change() {
+        let sample_rate = 44_100.;
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE * 4, sample_rate);
+
+        let mut src = context.create_constant_source();
+        src.connect(&context.destination());
+        src.start();
+
+        let mut transport = Transport::new(&context);
+        transport.add(&src);
+        transport.set_origin(200. / sample_rate as f64);
+
+        // the grid starts at the origin
+        let time = src
+            .offset()
+            .set_value_at_next(2., Quantization::Bar, &transport);
+        assert_float_eq!(time, 200. / sample_rate as f64, abs <= 0.);
+
+        let output = context.start_rendering_sync();
+        let channel = output.get_channel_data(0);
+        assert_float_eq!(channel[..200], [1.; 200][..], abs_all <= 0.);
+        assert_float_eq!(
+            channel[200..],
+            [2.; RENDER_QUANTUM_SIZE * 4 - 200][..],
+            abs_all <= 0.
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_tempo() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        Transport::new(&context).set_tempo(0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_quantize_other_context() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let other = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let transport = Transport::new(&context);
+        other
+            .create_gain()
+            .gain()
+            .set_value_at_next(0., Quantization::Beat, &transport);
+    }
+}
@@ -0,0 +1,93 @@
+//! Render thread watchdog for runaway feedback and non-finite sample values
+use crate::context::AudioNodeId;
+use crate::Event;
+
+/// Options for the render thread watchdog, see
+/// [`BaseAudioContext::enable_watchdog`](crate::context::BaseAudioContext::enable_watchdog)
+#[derive(Clone, Debug)]
+pub struct WatchdogOptions {
+    /// Nodes whose output is inspected, identified by
+    /// [`AudioContextRegistration::node_id`](crate::context::AudioContextRegistration::node_id)
+    ///
+    /// The destination node is inspected when empty.
+    pub watch_points: Vec<u64>,
+    /// Absolute sample value above which the signal is considered clipping
+    pub clipping_threshold: f32,
+    /// Duration in seconds of the clipping before the offending path is muted
+    pub clipping_duration: f64,
+}
+
+impl Default for WatchdogOptions {
+    fn default() -> Self {
+        Self {
+            watch_points: vec![],
+            clipping_threshold: 1.,
+            clipping_duration: 1.,
+        }
+    }
+}
+
+/// Reason for the watchdog to mute a node
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WatchdogReason {
+    /// The signal contains NaN or infinite sample values
+    NonFinite,
+    /// The signal has been clipping for longer than the configured duration
+    Clipping,
+}
+
+/// Event dispatched when the watchdog has muted a node
+#[derive(Clone, Debug)]
+pub struct WatchdogEvent {
+    /// Identifier of the muted node, i.e. the most upstream node producing the faulty signal
+    pub node_id: u64,
+    /// Identifier of the watch point where the faulty signal was detected
+    pub watch_point: u64,
+    /// Reason for muting the node
+    pub reason: WatchdogReason,
+    /// Time of detection, in terms of the associated AudioContext's currentTime
+    pub timestamp: f64,
+    /// Inherits from this base Event
+    pub event: Event,
+}
+
+/// Watchdog settings of the render thread
+#[derive(Debug)]
+pub(crate) struct WatchdogConfig {
+    pub watch_points: Vec<AudioNodeId>,
+    pub clipping_threshold: f32,
+    /// Number of consecutive clipping frames before muting
+    pub clipping_frames: u64,
+}
+
+impl WatchdogConfig {
+    /// # Panics
+    ///
+    /// Will panic if the clipping threshold is not strictly positive or if the clipping duration
+    /// is negative
+    pub fn new(options: WatchdogOptions, sample_rate: f32) -> Self {
+        assert!(
+            options.clipping_threshold > 0.,
+            "RangeError - clipping threshold must be strictly positive, got {:?}",
+            options.clipping_threshold
+        );
+        assert!(
+            options.clipping_duration >= 0.,
+            "RangeError - clipping duration must be positive, got {:?}",
+            options.clipping_duration
+        );
+
+        let watch_points = if options.watch_points.is_empty() {
+            vec![AudioNodeId(0)]
+        } else {
+            options.watch_points.into_iter().map(AudioNodeId).collect()
+        };
+
+        Self {
+            watch_points,
+            clipping_threshold: options.clipping_threshold,
+            clipping_frames: (options.clipping_duration * sample_rate as f64).ceil() as u64,
+        }
+    }
+}
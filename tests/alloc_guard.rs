@@ -0,0 +1,106 @@
+#![cfg(feature = "alloc-guard")]
+
+use std::alloc::System;
+use std::panic;
+
+use web_audio_api::alloc_guard::{self, AllocGuard};
+use web_audio_api::context::{AudioContextRegistration, BaseAudioContext, OfflineAudioContext};
+use web_audio_api::node::{AudioNode, AudioScheduledSourceNode, ChannelConfig};
+use web_audio_api::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
+
+#[global_allocator]
+static ALLOCATOR: AllocGuard<System> = AllocGuard::new(System);
+
+struct AllocatingNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+}
+
+impl AudioNode for AllocatingNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl AllocatingNode {
+    fn new<C: BaseAudioContext>(context: &C) -> Self {
+        context.register(move |registration| {
+            let render = AllocatingProcessor { history: vec![] };
+
+            let node = AllocatingNode {
+                registration,
+                channel_config: ChannelConfig::default(),
+            };
+
+            (node, Box::new(render))
+        })
+    }
+}
+
+struct AllocatingProcessor {
+    history: Vec<Vec<f32>>,
+}
+
+impl AudioProcessor for AllocatingProcessor {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &RenderScope,
+    ) -> bool {
+        self.history.push(inputs[0].channel_data(0).to_vec());
+        outputs[0] = inputs[0].clone();
+        false
+    }
+}
+
+fn render(allocating: bool) {
+    let context = OfflineAudioContext::new(1, 128 * 8, 48_000.);
+
+    let mut osc = context.create_oscillator();
+    let gain = context.create_gain();
+    gain.gain().set_value_at_time(0.5, 0.01);
+    osc.connect(&gain);
+    osc.start();
+
+    if allocating {
+        let node = AllocatingNode::new(&context);
+        gain.connect(&node);
+        node.connect(&context.destination());
+    } else {
+        gain.connect(&context.destination());
+    }
+
+    context.start_rendering_sync();
+}
+
+// single test, the settings of the allocation detection are global
+#[test]
+fn test_alloc_guard() {
+    // the built-in nodes do not allocate
+    alloc_guard::set_panic_on_allocation(true);
+    render(false);
+    assert_eq!(alloc_guard::allocation_count(), 0);
+
+    // each render quantum with allocations is counted
+    alloc_guard::set_panic_on_allocation(false);
+    render(true);
+    assert_eq!(alloc_guard::allocation_count(), 8);
+
+    alloc_guard::set_panic_on_allocation(true);
+    let result = panic::catch_unwind(|| render(true));
+    assert!(result.is_err());
+}
@@ -0,0 +1,466 @@
+//! Spec conformance tests, ported from the Web Platform Tests
+//!
+//! See <https://github.com/web-platform-tests/wpt/tree/master/webaudio>
+//!
+//! The expected values are computed with the formulas of the specification, the renders run in
+//! strict conformance mode. The extensions of this library that change the rendering of the spec
+//! are checked to fall back to the spec in this mode.
+
+use float_eq::assert_float_eq;
+
+use web_audio_api::context::{BaseAudioContext, OfflineAudioContext};
+use web_audio_api::node::{
+    AudioNode, AudioScheduledSourceNode, ChannelInterpretation, DistanceCurve, PannerNode,
+    PanningModelType,
+};
+use web_audio_api::AudioBuffer;
+
+// frame times are exactly representable with a power of two sample rate
+const SAMPLE_RATE: f32 = 32_768.;
+const LENGTH: usize = 1024;
+
+fn strict_context(number_of_channels: usize) -> OfflineAudioContext {
+    let context = OfflineAudioContext::new(number_of_channels, LENGTH, SAMPLE_RATE);
+    context.set_strict_conformance(true);
+    assert!(context.strict_conformance());
+    context
+}
+
+fn frame_time(frame: usize) -> f64 {
+    frame as f64 / SAMPLE_RATE as f64
+}
+
+/// Render the `offset` param of a `ConstantSourceNode` with the given automation
+fn render_automation<F: FnOnce(&web_audio_api::AudioParam)>(automation: F) -> Vec<f32> {
+    let context = strict_context(1);
+
+    let mut src = context.create_constant_source();
+    automation(src.offset());
+    src.connect(&context.destination());
+    src.start();
+
+    context.start_rendering_sync().get_channel_data(0).to_vec()
+}
+
+// audioparam-setValueAtTime.html
+#[test]
+fn test_set_value_at_time() {
+    let steps = [(0, 1.), (100, 0.25), (300, -0.5), (700, 0.75)];
+
+    let output = render_automation(|param| {
+        steps.iter().for_each(|&(frame, value)| {
+            param.set_value_at_time(value, frame_time(frame));
+        });
+    });
+
+    let mut expected = vec![0.; LENGTH];
+    steps.iter().enumerate().for_each(|(i, &(frame, value))| {
+        let end = steps.get(i + 1).map(|s| s.0).unwrap_or(LENGTH);
+        expected[frame..end].fill(value);
+    });
+
+    output
+        .iter()
+        .zip(expected.iter())
+        .enumerate()
+        .for_each(|(frame, (&value, &expected))| {
+            assert_float_eq!(value, expected, abs <= 0., "at frame {}", frame);
+        });
+}
+
+// audioparam-linearRampToValueAtTime.html
+#[test]
+fn test_linear_ramp_to_value_at_time() {
+    let (v0, t0) = (0.1, 64);
+    let (v1, t1) = (0.9, 832);
+
+    let output = render_automation(|param| {
+        param
+            .set_value_at_time(v0, frame_time(t0))
+            .linear_ramp_to_value_at_time(v1, frame_time(t1));
+    });
+
+    output.iter().enumerate().for_each(|(frame, &value)| {
+        let expected = if frame < t0 {
+            1. // default value of the offset param
+        } else if frame < t1 {
+            let f = (frame - t0) as f32 / (t1 - t0) as f32;
+            v0 + (v1 - v0) * f
+        } else {
+            v1
+        };
+        assert_float_eq!(value, expected, abs <= 1e-6, "at frame {}", frame);
+    });
+}
+
+// audioparam-exponentialRampToValueAtTime.html
+#[test]
+fn test_exponential_ramp_to_value_at_time() {
+    let (v0, t0) = (0.1_f32, 64);
+    let (v1, t1) = (0.8_f32, 832);
+
+    let output = render_automation(|param| {
+        param
+            .set_value_at_time(v0, frame_time(t0))
+            .exponential_ramp_to_value_at_time(v1, frame_time(t1));
+    });
+
+    output.iter().enumerate().for_each(|(frame, &value)| {
+        let expected = if frame < t0 {
+            1.
+        } else if frame < t1 {
+            let f = (frame - t0) as f64 / (t1 - t0) as f64;
+            (v0 as f64 * (v1 as f64 / v0 as f64).powf(f)) as f32
+        } else {
+            v1
+        };
+        assert_float_eq!(value, expected, rmax <= 1e-5, "at frame {}", frame);
+    });
+}
+
+// audioparam-setTargetAtTime.html
+#[test]
+fn test_set_target_at_time() {
+    let (v0, t0) = (1., 128);
+    let v1 = 0.;
+    let time_constant = 0.001;
+
+    let output = render_automation(|param| {
+        param
+            .set_value_at_time(v0, 0.)
+            .set_target_at_time(v1, frame_time(t0), time_constant);
+    });
+
+    output.iter().enumerate().for_each(|(frame, &value)| {
+        let expected = if frame < t0 {
+            v0
+        } else {
+            let t = frame_time(frame) - frame_time(t0);
+            (v1 as f64 + (v0 - v1) as f64 * (-t / time_constant).exp()) as f32
+        };
+        assert_float_eq!(value, expected, abs <= 1e-6, "at frame {}", frame);
+    });
+}
+
+// audioparam-setValueCurveAtTime.html
+#[test]
+fn test_set_value_curve_at_time() {
+    let curve = [0., 1., -1., 0.5, 0.25];
+    let (start, duration) = (256, 512);
+
+    let output = render_automation(|param| {
+        param.set_value_curve_at_time(&curve, frame_time(start), frame_time(duration));
+    });
+
+    output.iter().enumerate().for_each(|(frame, &value)| {
+        let expected = if frame < start {
+            1.
+        } else if frame < start + duration {
+            let n = curve.len() - 1;
+            let pos = (frame - start) as f64 / duration as f64 * n as f64;
+            let k = pos.floor() as usize;
+            let f = (pos - k as f64) as f32;
+            curve[k] + (curve[k + 1] - curve[k]) * f
+        } else {
+            curve[curve.len() - 1]
+        };
+        assert_float_eq!(value, expected, abs <= 1e-6, "at frame {}", frame);
+    });
+}
+
+// constant-source-basic.html
+#[test]
+fn test_constant_source_default_output() {
+    let output = render_automation(|_| {});
+    assert_float_eq!(output[..], [1.; LENGTH][..], abs_all <= 0.);
+}
+
+// gain.html
+#[test]
+fn test_gain_node() {
+    let context = strict_context(1);
+
+    let mut src = context.create_constant_source();
+    src.offset().set_value(0.5);
+    let gain = context.create_gain();
+    gain.gain().set_value_at_time(4., 0.);
+    gain.gain().set_value_at_time(-2., frame_time(512));
+
+    src.connect(&gain);
+    gain.connect(&context.destination());
+    src.start();
+
+    let output = context.start_rendering_sync();
+    let output = output.get_channel_data(0);
+
+    assert_float_eq!(output[..512], [2.; 512][..], abs_all <= 0.);
+    assert_float_eq!(output[512..], [-1.; 512][..], abs_all <= 0.);
+}
+
+// delaynode.html
+#[test]
+fn test_delay_node() {
+    let delay_frames = 300;
+    let context = strict_context(1);
+
+    let mut impulse = context.create_buffer(1, 1, SAMPLE_RATE);
+    impulse.copy_to_channel(&[1.], 0);
+
+    let mut src = context.create_buffer_source();
+    src.set_buffer(impulse);
+
+    let delay = context.create_delay(1.);
+    delay
+        .delay_time()
+        .set_value(frame_time(delay_frames) as f32);
+
+    src.connect(&delay);
+    delay.connect(&context.destination());
+    src.start();
+
+    let output = context.start_rendering_sync();
+
+    let mut expected = [0.; LENGTH];
+    expected[delay_frames] = 1.;
+    assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 1e-5);
+}
+
+// audiobuffersource-start.html
+#[test]
+fn test_audio_buffer_source_start_is_sample_accurate() {
+    let start = 333;
+    let context = strict_context(1);
+
+    let buffer = AudioBuffer::from(vec![vec![1.; 10]], SAMPLE_RATE);
+    let mut src = context.create_buffer_source();
+    src.set_buffer(buffer);
+    src.connect(&context.destination());
+    src.start_at(frame_time(start));
+
+    let output = context.start_rendering_sync();
+
+    let mut expected = [0.; LENGTH];
+    expected[start..start + 10].fill(1.);
+    assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 0.);
+}
+
+// audionode-channel-rules.html
+#[test]
+fn test_speakers_down_mix_stereo_to_mono() {
+    let context = strict_context(1);
+
+    let buffer = AudioBuffer::from(vec![vec![1.; LENGTH], vec![0.5; LENGTH]], SAMPLE_RATE);
+    let mut src = context.create_buffer_source();
+    src.set_buffer(buffer);
+    src.connect(&context.destination());
+    src.start();
+
+    let output = context.start_rendering_sync();
+
+    // output = 0.5 * (input.L + input.R)
+    assert_float_eq!(
+        output.get_channel_data(0),
+        &[0.75; LENGTH][..],
+        abs_all <= 0.
+    );
+}
+
+// audionode-channel-rules.html
+#[test]
+fn test_discrete_down_mix_stereo_to_mono() {
+    let context = strict_context(1);
+    context
+        .destination()
+        .set_channel_interpretation(ChannelInterpretation::Discrete);
+
+    let buffer = AudioBuffer::from(vec![vec![1.; LENGTH], vec![0.5; LENGTH]], SAMPLE_RATE);
+    let mut src = context.create_buffer_source();
+    src.set_buffer(buffer);
+    src.connect(&context.destination());
+    src.start();
+
+    let output = context.start_rendering_sync();
+
+    // only the first channel is kept
+    assert_float_eq!(output.get_channel_data(0), &[1.; LENGTH][..], abs_all <= 0.);
+}
+
+// The extensions of this library fall back to the behavior of the spec in strict mode
+
+/// Render the graph built by `build`, with or without the extension it enables
+fn render_extension<F>(strict: bool, extension: bool, build: &F) -> AudioBuffer
+where
+    F: Fn(&OfflineAudioContext, bool),
+{
+    let context = OfflineAudioContext::new(2, LENGTH, SAMPLE_RATE);
+    context.set_strict_conformance(strict);
+    build(&context, extension);
+    context.start_rendering_sync()
+}
+
+/// Check that the extension enabled by `build` changes the render, except in strict mode
+fn assert_extension_disabled<F: Fn(&OfflineAudioContext, bool)>(build: F) {
+    let spec = render_extension(false, false, &build);
+    let extension = render_extension(false, true, &build);
+    let strict = render_extension(true, true, &build);
+
+    (0..2).for_each(|channel| {
+        assert_float_eq!(
+            strict.get_channel_data(channel),
+            spec.get_channel_data(channel),
+            abs_all <= 0.
+        );
+    });
+    assert!(
+        (0..2).any(|channel| extension.get_channel_data(channel) != spec.get_channel_data(channel))
+    );
+}
+
+/// Panner with the given panning model, fed by a constant source
+fn connect_panner(context: &OfflineAudioContext, model: PanningModelType) -> PannerNode {
+    let mut src = context.create_constant_source();
+    src.start();
+
+    let mut panner = context.create_panner();
+    panner.set_panning_model(model);
+    src.connect(&panner);
+    panner.connect(&context.destination());
+    panner
+}
+
+#[test]
+fn test_strict_equal_power_elevation() {
+    assert_extension_disabled(|context, extension| {
+        let mut panner = connect_panner(context, PanningModelType::EqualPower);
+        panner.set_position(0.1, 1., 0.);
+        panner.set_equal_power_elevation(extension);
+    });
+}
+
+#[test]
+fn test_strict_near_field_compensation() {
+    assert_extension_disabled(|context, extension| {
+        let mut panner = connect_panner(context, PanningModelType::HRTF);
+        panner.set_position(0.25, 0., 0.);
+        // the compensation is enabled by default
+        if !extension {
+            panner.set_near_field_distance(0.);
+        }
+    });
+}
+
+#[test]
+fn test_strict_distance_curve() {
+    assert_extension_disabled(|context, extension| {
+        let mut panner = connect_panner(context, PanningModelType::EqualPower);
+        panner.set_position(0., 0., -10.);
+        if extension {
+            let curve = DistanceCurve::lookup_table(&[(0., 1.), (100., 1.)]);
+            panner.set_distance_curve(Some(curve));
+        }
+    });
+}
+
+#[test]
+fn test_strict_doppler() {
+    assert_extension_disabled(|context, extension| {
+        let mut panner = connect_panner(context, PanningModelType::EqualPower);
+        panner.set_position(0., 0., -3.43);
+        if extension {
+            panner.set_doppler_factor(1.);
+        }
+    });
+}
+
+#[test]
+fn test_strict_air_absorption() {
+    assert_extension_disabled(|context, extension| {
+        let mut osc = context.create_oscillator();
+        osc.frequency().set_value(5000.);
+        osc.start();
+
+        let mut panner = context.create_panner();
+        panner.set_position(0., 0., -100.);
+        panner.set_air_absorption_coefficient(0.05);
+        panner.set_air_absorption(extension);
+        osc.connect(&panner);
+        panner.connect(&context.destination());
+    });
+}
+
+#[test]
+fn test_strict_occlusion() {
+    assert_extension_disabled(|context, extension| {
+        let mut panner = connect_panner(context, PanningModelType::EqualPower);
+        if extension {
+            panner.set_occlusion(1.);
+            panner.set_obstruction(1.);
+        }
+    });
+}
+
+#[test]
+fn test_strict_effect_mix() {
+    assert_extension_disabled(|context, extension| {
+        let mut osc = context.create_oscillator();
+        osc.frequency().set_value(5000.);
+        osc.start();
+
+        let filter = context.create_biquad_filter();
+        filter.frequency().set_value(200.);
+        if extension {
+            filter.mix().set_value(0.);
+        }
+        osc.connect(&filter);
+        filter.connect(&context.destination());
+    });
+}
+
+#[test]
+fn test_strict_loop_crossfade() {
+    assert_extension_disabled(|context, extension| {
+        let ramp = (0..100).map(|i| i as f32 / 100.).collect();
+        let buffer = AudioBuffer::from(vec![ramp], SAMPLE_RATE);
+
+        let mut src = context.create_buffer_source();
+        src.set_buffer(buffer);
+        src.set_loop(true);
+        if extension {
+            src.set_loop_crossfade_duration(frame_time(20));
+        }
+        src.connect(&context.destination());
+        src.start();
+    });
+}
+
+#[test]
+fn test_strict_connection_fade() {
+    assert_extension_disabled(|context, extension| {
+        if extension {
+            context.set_connection_fade(frame_time(256));
+        }
+
+        let mut src = context.create_constant_source();
+        src.connect(&context.destination());
+        src.start();
+    });
+}
+
+#[test]
+#[should_panic(expected = "NotSupportedError")]
+fn test_strict_param_expression() {
+    let context = strict_context(1);
+    let src = context.create_constant_source();
+    let gain = context.create_gain();
+    let _binding = gain
+        .gain()
+        .bind_expression(&[src.offset()], |values| values[0] * 2.);
+}
+
+#[test]
+#[should_panic(expected = "NotSupportedError")]
+fn test_strict_capture_param() {
+    let mut context = strict_context(1);
+    let gain = context.create_gain();
+    let _capture = context.capture_param(gain.gain());
+}
@@ -104,3 +104,36 @@ fn test_stereo_to_discrete_mono() {
     assert_eq!(output.number_of_channels(), 1);
     assert_float_eq!(output.get_channel_data(0), ONES, abs_all <= 0.);
 }
+
+#[test]
+fn test_high_channel_count() {
+    // e.g. 7th order ambisonics
+    let number_of_channels = 64;
+    let context = setup_with_destination_channel_config(number_of_channels, Discrete);
+
+    let samples: Vec<Vec<f32>> = (0..number_of_channels)
+        .map(|i| vec![i as f32; 128])
+        .collect();
+    let buffer = AudioBuffer::from(samples, 44_100.);
+
+    let mut src = context.create_buffer_source();
+    src.set_buffer(buffer);
+    src.start();
+
+    let gain = context.create_gain();
+    gain.set_channel_count(number_of_channels);
+    gain.set_channel_count_mode(Explicit);
+    gain.set_channel_interpretation(Discrete);
+    src.connect(&gain);
+    gain.connect(&context.destination());
+
+    let output = context.start_rendering_sync();
+    assert_eq!(output.number_of_channels(), number_of_channels);
+    (0..number_of_channels).for_each(|i| {
+        assert_float_eq!(
+            output.get_channel_data(i),
+            &[i as f32; 128][..],
+            abs_all <= 0.
+        );
+    });
+}
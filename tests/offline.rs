@@ -288,3 +288,175 @@ fn test_spatial() {
         abs_all <= 0.001
     );
 }
+
+#[test]
+fn test_lookback() {
+    const LENGTH: usize = 70 * RENDER_QUANTUM_SIZE;
+
+    let context = OfflineAudioContext::new(1, LENGTH, 44_100.);
+
+    let mut constant = context.create_constant_source();
+    constant.offset().set_value(0.);
+    constant
+        .offset()
+        .linear_ramp_to_value_at_time(1., LENGTH as f64 / 44_100.);
+    constant.start();
+
+    let gain = context.create_gain();
+    gain.set_channel_count(1);
+    constant.connect(&gain);
+    gain.connect(&context.destination());
+
+    assert!(gain.dump_lookback().is_none());
+    gain.enable_lookback(0.1);
+    constant.enable_lookback(0.1);
+    constant.disable_lookback();
+
+    let output = context.start_rendering_sync();
+
+    assert!(constant.dump_lookback().is_none());
+    let lookback = gain.dump_lookback().unwrap();
+    assert_eq!(lookback.number_of_channels(), 1);
+    assert_eq!(lookback.length(), 4410);
+    assert_float_eq!(
+        lookback.get_channel_data(0),
+        &output.get_channel_data(0)[LENGTH - 4410..],
+        abs_all <= 0.
+    );
+}
+
+#[test]
+fn test_scheduled_mutations() {
+    const LENGTH: usize = 8 * RENDER_QUANTUM_SIZE;
+    let sample_rate = 32_768.;
+
+    let context = OfflineAudioContext::new(1, LENGTH, sample_rate);
+
+    let mut constant = context.create_constant_source();
+    constant.start();
+
+    // connected in the render quantum containing frame 300, until the one containing frame 700
+    context.schedule(300. / sample_rate as f64, |graph| {
+        graph.connect(&constant, &context.destination());
+    });
+    context.schedule(700. / sample_rate as f64, |graph| {
+        graph.disconnect(&constant);
+    });
+
+    // added to the graph at frame 512, the handle is already dropped
+    {
+        let mut other = context.create_constant_source();
+        other.offset().set_value(2.);
+        other.start();
+        context.schedule(512. / sample_rate as f64, |graph| {
+            graph.connect(&other, &context.destination());
+        });
+    }
+
+    let output = context.start_rendering_sync();
+    let mut expected = [0.; LENGTH];
+    expected[256..640].fill(1.);
+    expected[512..].iter_mut().for_each(|v| *v += 2.);
+    assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 0.);
+}
+
+#[test]
+#[should_panic(expected = "InvalidStateError")]
+fn test_scheduled_mutations_limit() {
+    let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+    let constant = context.create_constant_source();
+
+    // the render thread does not apply the mutations before rendering
+    context.schedule(0., |graph| {
+        for _ in 0..1024 {
+            graph.disconnect(&constant);
+        }
+    });
+    context.schedule(0., |graph| {
+        graph.disconnect(&constant);
+    });
+}
+
+#[test]
+fn test_scheduled_delay_mutations() {
+    const LENGTH: usize = 4 * RENDER_QUANTUM_SIZE;
+    let sample_rate = 32_768.;
+
+    let context = OfflineAudioContext::new(1, LENGTH, sample_rate);
+
+    let mut constant = context.create_constant_source();
+    constant.start();
+    let delay = context.create_delay(1.);
+    delay.delay_time().set_value(64. / sample_rate);
+    constant.connect(&delay);
+
+    // the output of the delay is rendered by its reader end
+    context.schedule(256. / sample_rate as f64, |graph| {
+        graph.connect(&delay, &context.destination());
+    });
+
+    let output = context.start_rendering_sync();
+    let mut expected = [0.; LENGTH];
+    expected[256..].fill(1.);
+    assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 0.);
+}
+
+#[test]
+fn test_connection_fade() {
+    const LENGTH: usize = 10 * RENDER_QUANTUM_SIZE;
+    let sample_rate = 32_768.;
+
+    let render = |reconnect: bool| {
+        let context = OfflineAudioContext::new(1, LENGTH, sample_rate);
+        context.set_connection_fade(256. / sample_rate as f64);
+
+        let mut constant = context.create_constant_source();
+        constant.start();
+        constant.connect(&context.destination());
+
+        context.schedule(768. / sample_rate as f64, |graph| {
+            graph.disconnect(&constant);
+        });
+        if reconnect {
+            context.schedule(896. / sample_rate as f64, |graph| {
+                graph.connect(&constant, &context.destination());
+            });
+        }
+
+        context.start_rendering_sync()
+    };
+
+    // faded in, then faded out
+    let output = render(false);
+    let mut expected = [0.; LENGTH];
+    expected[..256]
+        .iter_mut()
+        .enumerate()
+        .for_each(|(i, v)| *v = i as f32 / 256.);
+    expected[256..768].fill(1.);
+    expected[768..1024]
+        .iter_mut()
+        .enumerate()
+        .for_each(|(i, v)| *v = 1. - i as f32 / 256.);
+    assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 1e-6);
+
+    // reconnected while fading out, faded back in from the current gain
+    let output = render(true);
+    expected[768..896]
+        .iter_mut()
+        .enumerate()
+        .for_each(|(i, v)| *v = 1. - i as f32 / 256.);
+    expected[896..1024]
+        .iter_mut()
+        .enumerate()
+        .for_each(|(i, v)| *v = 0.5 + i as f32 / 256.);
+    expected[1024..].fill(1.);
+    assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 1e-6);
+}
+
+#[test]
+#[should_panic]
+fn test_invalid_connection_fade() {
+    let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+    context.set_connection_fade(-1.);
+}
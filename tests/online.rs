@@ -4,12 +4,14 @@
 //! using the 'none' audio backend.
 
 use web_audio_api::context::{
-    AudioContext, AudioContextOptions, AudioContextState, BaseAudioContext,
+    AudioContext, AudioContextLatencyCategory, AudioContextOptions, AudioContextState,
+    AudioSessionEvent, BaseAudioContext, DeviceMixer,
 };
-use web_audio_api::node::AudioNode;
+use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
 
-use std::sync::atomic::{AtomicBool, Ordering};
-use web_audio_api::MAX_CHANNELS;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use web_audio_api::media_streams::{MediaStream, MediaStreamTrack};
+use web_audio_api::{AudioBuffer, ContextEvent, EventStream, MAX_CHANNELS};
 
 fn require_send_sync_static<T: Send + Sync + 'static>(_: T) {}
 
@@ -76,6 +78,249 @@ fn ensure_audio_scheduled_source_node_object_safe() {
 }
 */
 
+#[test]
+fn test_none_sink_clock() {
+    let options = AudioContextOptions {
+        sink_id: "none".into(),
+        latency_hint: AudioContextLatencyCategory::Playback,
+        sample_rate: Some(48000.),
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+    assert_eq!(context.output_latency(), 0.);
+
+    // the rendered frames follow the wall clock
+    let start = std::time::Instant::now();
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    let current_time = context.current_time();
+    let elapsed = start.elapsed().as_secs_f64();
+    assert!(current_time > 0.1 && current_time <= elapsed + 0.05);
+
+    context.close_sync();
+}
+
+#[test]
+fn test_file_sink() {
+    let path = std::env::temp_dir().join("web_audio_api_test_file_sink.wav");
+    let options = AudioContextOptions {
+        sink_id: format!("file:{}?speed=8&channels=1", path.display()),
+        sample_rate: Some(48000.),
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+    assert_eq!(context.destination().max_channel_count(), 1);
+
+    let mut src = context.create_constant_source();
+    src.offset().set_value(0.5);
+    src.connect(&context.destination());
+    src.start();
+
+    // rendered faster than real time
+    let start = std::time::Instant::now();
+    while context.current_time() < 4. {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    assert!(start.elapsed().as_secs_f64() < 4.);
+
+    // the file is finalized when the context is closed
+    context.close_sync();
+    let mut reader = hound::WavReader::open(&path).unwrap();
+    let spec = reader.spec();
+    assert_eq!(spec.channels, 1);
+    assert_eq!(spec.sample_rate, 48000);
+    assert!(reader.duration() >= 4 * 48000);
+
+    let samples: Vec<f32> = reader.samples().map(Result::unwrap).collect();
+    // silence is rendered until the source is connected
+    assert!(samples.iter().all(|&v| v == 0. || v == 0.5));
+    assert!(samples.iter().filter(|&&v| v == 0.5).count() > 48000);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_file_sink_resampled() {
+    let path = std::env::temp_dir().join("web_audio_api_test_file_sink_resampled.wav");
+    let options = AudioContextOptions {
+        sink_id: format!("file:{}?speed=8&channels=1&rate=44100", path.display()),
+        sample_rate: Some(48000.),
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+    assert_eq!(context.sample_rate(), 48000.);
+    assert_eq!(context.device_sample_rate(), 44100.);
+
+    let mut osc = context.create_oscillator();
+    osc.frequency().set_value(1000.);
+    osc.connect(&context.destination());
+    osc.start();
+
+    while context.current_time() < 2. {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+
+    context.close_sync();
+    let mut reader = hound::WavReader::open(&path).unwrap();
+    assert_eq!(reader.spec().sample_rate, 44100);
+    let samples: Vec<f32> = reader.samples().map(Result::unwrap).collect();
+    assert!(samples.len() >= 2 * 44100);
+
+    // the pitch is preserved: 1000 periods per second of the resampled signal
+    let second = &samples[samples.len() - 44100..];
+    let crossings = second
+        .windows(2)
+        .filter(|w| w[0] < 0. && w[1] >= 0.)
+        .count();
+    assert!((999..=1001).contains(&crossings), "{crossings}");
+    assert!(second.iter().all(|v| v.abs() < 1.01));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_file_sink_id() {
+    let context = AudioContext::new(AudioContextOptions {
+        sink_id: "none".into(),
+        ..AudioContextOptions::default()
+    });
+
+    assert!(context
+        .set_sink_id_sync("file:out.wav?speed=0".into())
+        .is_err());
+    assert!(context
+        .set_sink_id_sync("file:/non/existing/dir/out.wav".into())
+        .is_err());
+    assert_eq!(context.sink_id(), "none");
+
+    let path = std::env::temp_dir().join("web_audio_api_test_file_sink_id.wav");
+    let sink_id = format!("file:{}", path.display());
+    context.set_sink_id_sync(sink_id.clone()).unwrap();
+    assert_eq!(context.sink_id(), sink_id);
+
+    context.close_sync();
+    assert!(hound::WavReader::open(&path).is_ok());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_device_mixer() {
+    let path = std::env::temp_dir().join("web_audio_api_test_device_mixer.wav");
+    let mixer = DeviceMixer::new(
+        "test_device_mixer",
+        AudioContextOptions {
+            sink_id: format!("file:{}?speed=8&channels=1", path.display()),
+            sample_rate: Some(48000.),
+            ..AudioContextOptions::default()
+        },
+    );
+
+    // the contexts render at the sample rate of the mixer
+    let options = AudioContextOptions {
+        sink_id: mixer.sink_id(),
+        sample_rate: Some(44100.),
+        ..AudioContextOptions::default()
+    };
+    let first = AudioContext::new(options.clone());
+    assert_eq!(first.sink_id(), "mixer:test_device_mixer");
+    assert_eq!(first.sample_rate(), 48000.);
+    assert_eq!(first.destination().max_channel_count(), 1);
+
+    // a context can join the mixer after its creation
+    let second = AudioContext::new(AudioContextOptions {
+        sink_id: "none".into(),
+        ..AudioContextOptions::default()
+    });
+    second.set_sink_id_sync(mixer.sink_id()).unwrap();
+    mixer.gain(&second).set_value(0.5);
+
+    for (context, offset) in [(&first, 0.25), (&second, 0.5)] {
+        let mut src = context.create_constant_source();
+        src.offset().set_value(offset);
+        src.connect(&context.destination());
+        src.start();
+    }
+
+    while first.current_time() < 2. {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    assert!(second.current_time() > 1.5);
+
+    // a suspended context no longer progresses, the other one does
+    first.suspend_sync();
+    let suspended_time = first.current_time();
+    let time = second.current_time();
+    while second.current_time() < time + 1. {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    assert_eq!(first.current_time(), suspended_time);
+
+    mixer.close_sync();
+    let mut reader = hound::WavReader::open(&path).unwrap();
+    let samples: Vec<f32> = reader.samples().map(Result::unwrap).collect();
+    assert!(samples.len() >= 3 * 48000);
+
+    // sum of both contexts, then the second one alone
+    let mixed = [0., 0.25, 0.5];
+    assert!(samples.iter().all(|v| mixed.contains(v)));
+    assert!(samples.iter().filter(|&&v| v == 0.5).count() > 48000);
+    assert!(samples[samples.len() - 1000..].iter().all(|&v| v == 0.25));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_round_trip_latency() {
+    let context = AudioContext::new(AudioContextOptions {
+        sink_id: "none".into(),
+        ..AudioContextOptions::default()
+    });
+
+    // a stream which is not backed by an input device adds no latency
+    let buffers = vec![Ok(AudioBuffer::from(vec![vec![0.; 128]], 48000.))];
+    let stream = MediaStream::from_tracks(vec![MediaStreamTrack::from_iter(buffers)]);
+    assert_eq!(stream.get_tracks()[0].latency(), 0.);
+    assert_eq!(
+        context.round_trip_latency(&stream),
+        context.output_latency()
+    );
+}
+
+#[test]
+fn test_session_interruption() {
+    let context = AudioContext::new(AudioContextOptions {
+        sink_id: "none".into(),
+        ..AudioContextOptions::default()
+    });
+
+    context.handle_session_event(AudioSessionEvent::InterruptionBegan);
+    assert_eq!(context.state(), AudioContextState::Suspended);
+    context.handle_session_event(AudioSessionEvent::InterruptionEnded {
+        should_resume: false,
+    });
+    assert_eq!(context.state(), AudioContextState::Suspended);
+
+    context.resume_sync();
+    context.handle_session_event(AudioSessionEvent::InterruptionBegan);
+    context.handle_session_event(AudioSessionEvent::InterruptionEnded {
+        should_resume: true,
+    });
+    assert_eq!(context.state(), AudioContextState::Running);
+
+    // a context suspended by the user is not resumed
+    context.suspend_sync();
+    context.handle_session_event(AudioSessionEvent::InterruptionBegan);
+    context.handle_session_event(AudioSessionEvent::InterruptionEnded {
+        should_resume: true,
+    });
+    assert_eq!(context.state(), AudioContextState::Suspended);
+
+    // the route change only applies to the default device
+    context.handle_session_event(AudioSessionEvent::RouteChanged);
+    assert_eq!(context.sink_id(), "none");
+
+    context.close_sync();
+}
+
 #[test]
 fn test_none_sink_id() {
     let options = AudioContextOptions {
@@ -175,3 +420,94 @@ fn test_audioparam_outlives_audionode() {
     std::thread::sleep(std::time::Duration::from_millis(200));
     assert!(context.current_time() >= time + 0.15);
 }
+
+/// Minimal executor, to show the event stream does not depend on an async runtime
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    struct ThreadWaker(std::thread::Thread);
+    impl std::task::Wake for ThreadWaker {
+        fn wake(self: std::sync::Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = std::sync::Arc::new(ThreadWaker(std::thread::current())).into();
+    let mut cx = std::task::Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            std::task::Poll::Ready(output) => return output,
+            std::task::Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+fn is_underrun(event: ContextEvent) -> bool {
+    matches!(event, ContextEvent::Underrun(_))
+}
+
+/// Wait for the next event, ignoring the audio glitches of a busy test runner
+fn next_event(events: &mut EventStream) -> Option<ContextEvent> {
+    loop {
+        match block_on(events.next()) {
+            Some(ContextEvent::Underrun(_)) => continue,
+            event => break event,
+        }
+    }
+}
+
+#[test]
+fn test_event_stream() {
+    let options = AudioContextOptions {
+        sink_id: "none".into(),
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+    let mut events = context.events();
+
+    let state_changes = &*Box::leak(Box::new(AtomicUsize::new(0)));
+    context.set_onstatechange(move |_| {
+        state_changes.fetch_add(1, Ordering::SeqCst);
+    });
+
+    let mut src = context.create_constant_source();
+    src.connect(&context.destination());
+    src.start();
+    src.stop_at(0.01);
+    let node_id = src.registration().node_id();
+
+    // the initial state change to running may be dispatched first
+    let event = loop {
+        match next_event(&mut events) {
+            Some(ContextEvent::StateChange(AudioContextState::Running)) => continue,
+            event => break event,
+        }
+    };
+    assert!(matches!(event, Some(ContextEvent::Ended { node_id: id }) if id == node_id));
+
+    context.suspend_sync();
+    let event = next_event(&mut events);
+    assert!(matches!(
+        event,
+        Some(ContextEvent::StateChange(AudioContextState::Suspended))
+    ));
+    context.resume_sync();
+    let event = next_event(&mut events);
+    assert!(matches!(
+        event,
+        Some(ContextEvent::StateChange(AudioContextState::Running))
+    ));
+    assert!(std::iter::from_fn(|| events.try_next()).all(is_underrun));
+
+    // the callbacks are still called
+    assert!(state_changes.load(Ordering::SeqCst) >= 2);
+
+    // the stream ends when the context is closed
+    context.close_sync();
+    let event = next_event(&mut events);
+    assert!(matches!(
+        event,
+        Some(ContextEvent::StateChange(AudioContextState::Closed))
+    ));
+    assert!(next_event(&mut events).is_none());
+    assert!(context.events().try_next().is_none());
+}
@@ -0,0 +1,96 @@
+use std::sync::mpsc;
+use std::time::Duration;
+
+use web_audio_api::context::{
+    AudioContext, AudioContextOptions, AudioContextRegistration, BaseAudioContext,
+};
+use web_audio_api::node::{AudioNode, ChannelConfig};
+use web_audio_api::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
+
+struct SlowNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+}
+
+impl AudioNode for SlowNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        0
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl SlowNode {
+    fn new<C: BaseAudioContext>(context: &C) -> Self {
+        context.register(move |registration| {
+            let node = SlowNode {
+                registration,
+                channel_config: ChannelConfig::default(),
+            };
+
+            (node, Box::new(SlowProcessor {}))
+        })
+    }
+}
+
+struct SlowProcessor {}
+
+impl AudioProcessor for SlowProcessor {
+    fn process(
+        &mut self,
+        _inputs: &[AudioRenderQuantum],
+        _outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &RenderScope,
+    ) -> bool {
+        // a render quantum lasts less than 3 ms at 48kHz
+        std::thread::sleep(Duration::from_millis(10));
+        true
+    }
+}
+
+#[test]
+fn test_underrun_event() {
+    let options = AudioContextOptions {
+        sink_id: "none".into(),
+        sample_rate: Some(48000.),
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+
+    let (sender, receiver) = mpsc::channel();
+    context.render_capacity().set_onunderrun(move |event| {
+        let _ = sender.send(event);
+    });
+
+    let slow = SlowNode::new(&context);
+    slow.connect(&context.destination());
+    let node_id = slow.registration().node_id();
+
+    // the first underrun may occur before profiling is enabled
+    let event = receiver
+        .iter()
+        .find(|event| !event.slowest_nodes.is_empty())
+        .unwrap();
+
+    assert!(event.render_time > event.deadline);
+    assert!(event.backend_message.is_none());
+    assert_eq!(event.slowest_nodes[0].node_id, node_id);
+    assert!(event.slowest_nodes[0].render_time >= 0.01);
+    assert!(event
+        .slowest_nodes
+        .windows(2)
+        .all(|w| w[0].render_time >= w[1].render_time));
+
+    context.close_sync();
+}
@@ -0,0 +1,176 @@
+use std::sync::mpsc;
+
+use float_eq::assert_float_eq;
+use web_audio_api::context::{
+    AudioContext, AudioContextOptions, AudioContextRegistration, BaseAudioContext,
+    OfflineAudioContext,
+};
+use web_audio_api::node::{AudioNode, AudioScheduledSourceNode, ChannelConfig};
+use web_audio_api::render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, RenderScope};
+use web_audio_api::{WatchdogOptions, WatchdogReason};
+
+struct NanNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+}
+
+impl AudioNode for NanNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        0
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl NanNode {
+    fn new<C: BaseAudioContext>(context: &C) -> Self {
+        context.register(move |registration| {
+            let node = NanNode {
+                registration,
+                channel_config: ChannelConfig::default(),
+            };
+
+            (node, Box::new(NanProcessor {}))
+        })
+    }
+}
+
+struct NanProcessor {}
+
+impl AudioProcessor for NanProcessor {
+    fn process(
+        &mut self,
+        _inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &RenderScope,
+    ) -> bool {
+        outputs[0].channel_data_mut(0).fill(f32::NAN);
+        true
+    }
+}
+
+#[test]
+fn test_watchdog_disabled() {
+    let context = OfflineAudioContext::new(1, 128, 48000.);
+
+    let nan = NanNode::new(&context);
+    nan.connect(&context.destination());
+
+    let output = context.start_rendering_sync();
+    assert!(output.get_channel_data(0).iter().all(|v| v.is_nan()));
+}
+
+#[test]
+fn test_watchdog_non_finite() {
+    let context = OfflineAudioContext::new(1, 256, 48000.);
+    context.enable_watchdog(WatchdogOptions::default());
+
+    // the faulty node is muted, the other path keeps playing
+    let nan = NanNode::new(&context);
+    let gain = context.create_gain();
+    nan.connect(&gain);
+    gain.connect(&context.destination());
+
+    let mut src = context.create_constant_source();
+    src.offset().set_value(0.5);
+    src.connect(&context.destination());
+    src.start();
+
+    let output = context.start_rendering_sync();
+    let output = output.get_channel_data(0);
+    assert_float_eq!(output[..128], [0.; 128][..], abs_all <= 0.);
+    assert_float_eq!(output[128..], [0.5; 128][..], abs_all <= 0.);
+}
+
+#[test]
+fn test_watchdog_clipping() {
+    let context = OfflineAudioContext::new(1, 128 * 4, 48000.);
+    context.enable_watchdog(WatchdogOptions {
+        clipping_duration: 256. / 48000.,
+        ..WatchdogOptions::default()
+    });
+
+    let mut src = context.create_constant_source();
+    src.offset().set_value(2.);
+    src.connect(&context.destination());
+    src.start();
+
+    let output = context.start_rendering_sync();
+    let output = output.get_channel_data(0);
+    assert_float_eq!(output[..256], [2.; 256][..], abs_all <= 0.);
+    assert_float_eq!(output[256..], [0.; 256][..], abs_all <= 0.);
+}
+
+#[test]
+fn test_watchdog_watch_points() {
+    let context = OfflineAudioContext::new(1, 128, 48000.);
+
+    let mut src = context.create_constant_source();
+    src.offset().set_value(2.);
+    let gain = context.create_gain();
+    gain.gain().set_value(0.25);
+    src.connect(&gain);
+    gain.connect(&context.destination());
+    src.start();
+
+    // the source clips, but only the output of the gain is inspected
+    context.enable_watchdog(WatchdogOptions {
+        watch_points: vec![gain.registration().node_id()],
+        clipping_threshold: 1.,
+        clipping_duration: 0.,
+    });
+
+    let output = context.start_rendering_sync();
+    assert_float_eq!(output.get_channel_data(0), &[0.5; 128][..], abs_all <= 0.);
+}
+
+#[test]
+#[should_panic]
+fn test_watchdog_invalid_threshold() {
+    let context = OfflineAudioContext::new(1, 128, 48000.);
+    context.enable_watchdog(WatchdogOptions {
+        clipping_threshold: 0.,
+        ..WatchdogOptions::default()
+    });
+}
+
+#[test]
+fn test_watchdog_event() {
+    let options = AudioContextOptions {
+        sink_id: "none".into(),
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+
+    let (sender, receiver) = mpsc::channel();
+    context.set_onwatchdog(move |event| {
+        let _ = sender.send(event);
+    });
+    context.enable_watchdog(WatchdogOptions::default());
+
+    let nan = NanNode::new(&context);
+    let gain = context.create_gain();
+    nan.connect(&gain);
+    gain.connect(&context.destination());
+
+    let event = receiver.recv().unwrap();
+    assert_eq!(event.node_id, nan.registration().node_id());
+    assert_eq!(
+        event.watch_point,
+        context.destination().registration().node_id()
+    );
+    assert_eq!(event.reason, WatchdogReason::NonFinite);
+
+    context.close_sync();
+}